@@ -28,8 +28,11 @@ use diem_management::{
 use diem_secure_storage::{CryptoStorage, KVStorage, OnDiskStorage, Storage};
 use diem_types::{
     chain_id::ChainId,
-    network_address::encrypted::{
-        Key as NetworkAddressEncryptionKey, KeyVersion as NetworkAddressEncryptionKeyVersion,
+    network_address::{
+        encrypted::{
+            Key as NetworkAddressEncryptionKey, KeyVersion as NetworkAddressEncryptionKeyVersion,
+        },
+        NetworkAddress, Protocol,
     },
     on_chain_config::VMPublishingOption,
     transaction::{authenticator::AuthenticationKey, Transaction},
@@ -165,6 +168,7 @@ pub struct ValidatorBuilder {
     move_modules: Vec<Vec<u8>>,
     num_validators: NonZeroUsize,
     randomize_first_validator_ports: bool,
+    simulated_validator_network: bool,
     publishing_option: Option<VMPublishingOption>,
     template: NodeConfig,
 }
@@ -176,6 +180,7 @@ impl ValidatorBuilder {
             move_modules,
             num_validators: NonZeroUsize::new(1).unwrap(),
             randomize_first_validator_ports: true,
+            simulated_validator_network: false,
             publishing_option: None,
             template: NodeConfig::default_for_validator(),
         }
@@ -186,6 +191,13 @@ impl ValidatorBuilder {
         self
     }
 
+    /// Makes the validator at `index` listen on '/memory/<index + 1>' for the validator network,
+    /// so that the validators can be connected through a `NetworkSimulator`.
+    pub fn simulated_validator_network(mut self, value: bool) -> Self {
+        self.simulated_validator_network = value;
+        self
+    }
+
     pub fn num_validators(mut self, num_validators: NonZeroUsize) -> Self {
         self.num_validators = num_validators;
         self
@@ -235,6 +247,7 @@ impl ValidatorBuilder {
             &validators,
             self.publishing_option,
             self.move_modules,
+            self.simulated_validator_network,
         )?;
 
         // Insert Genesis and Waypoint into each validator
@@ -354,6 +367,11 @@ impl ValidatorBuilder {
         if index > 0 || self.randomize_first_validator_ports {
             config.randomize_ports();
         }
+        if self.simulated_validator_network {
+            let port = u16::try_from(index + 1)?;
+            config.validator_network.as_mut().unwrap().listen_address =
+                NetworkAddress::from(Protocol::Memory(port));
+        }
 
         Ok(validator)
     }
@@ -414,6 +432,7 @@ impl ValidatorBuilder {
         validators: &[ValidatorConfig],
         publishing_option: Option<VMPublishingOption>,
         move_modules: Vec<Vec<u8>>,
+        disable_address_validation: bool,
     ) -> Result<(Transaction, Waypoint)> {
         let mut genesis_builder = GenesisBuilder::new(genesis_storage);
 
@@ -456,8 +475,8 @@ impl ValidatorBuilder {
                     .as_ref()
                     .map(|a| a.listen_address.clone())
                     .unwrap(),
-                false, // This isn't a reconfiguration
-                false, // Don't disable address validation
+                false,                      // This isn't a reconfiguration
+                disable_address_validation, // Memory addresses don't pass the validation
             )?;
             genesis_builder.set_validator_config(&validator.operator(), &validator_config)?;
         }
@@ -40,7 +40,7 @@ impl CreateWaypoint {
 
 pub fn create_genesis_waypoint(genesis: &Transaction) -> Result<Waypoint, Error> {
     let path = TempPath::new();
-    let diemdb = DiemDB::open(&path, false, None, RocksdbConfig::default())
+    let diemdb = DiemDB::open(&path, false, None, None, RocksdbConfig::default())
         .map_err(|e| Error::UnexpectedError(e.to_string()))?;
     let db_rw = DbReaderWriter::new(diemdb);
 
@@ -29,6 +29,20 @@ pub struct ConsensusConfig {
     pub decoupled_execution: bool,
     pub channel_size: usize,
     pub back_pressure_limit: u64,
+    // global switch for the quorum store: when enabled, the validator broadcasts batches of
+    // transactions ahead of time and its proposals only carry the proofs of store of the batches
+    pub quorum_store_enabled: bool,
+    // How often the quorum store pulls a new batch from mempool (in milliseconds)
+    pub quorum_store_batch_interval_ms: u64,
+    // Max number of transactions in a quorum store batch
+    pub quorum_store_max_batch_size: u64,
+    // Batches that don't gather a proof of store in time are dropped (in milliseconds)
+    pub quorum_store_proof_timeout_ms: u64,
+    // Max number of uncommitted batches stored per author, the others are rejected
+    pub quorum_store_max_batches_per_author: usize,
+    // Uncommitted batches are dropped after this long (in milliseconds), and their proofs of
+    // store are no longer proposed after half of it
+    pub quorum_store_batch_expiry_ms: u64,
 }
 
 impl Default for ConsensusConfig {
@@ -50,6 +64,12 @@ impl Default for ConsensusConfig {
             decoupled_execution: false, // by default, we turn of the decoupling execution feature
             channel_size: 30,           // hard-coded
             back_pressure_limit: 1,
+            quorum_store_enabled: false,
+            quorum_store_batch_interval_ms: 100,
+            quorum_store_max_batch_size: 250,
+            quorum_store_proof_timeout_ms: 5000,
+            quorum_store_max_batches_per_author: 100,
+            quorum_store_batch_expiry_ms: 60_000,
         }
     }
 }
@@ -69,6 +89,9 @@ pub enum ConsensusProposerType {
     RotatingProposer,
     // Committed history based proposer election
     LeaderReputation(LeaderReputationConfig),
+    // Committed history based proposer election weighted by voting power, penalizing the
+    // leaders of failed rounds
+    StakeWeightedReputation(StakeWeightedReputationConfig),
     // Pre-specified proposers for each round,
     // or default proposer if round proposer not
     // specified
@@ -81,3 +104,13 @@ pub struct LeaderReputationConfig {
     pub active_weights: u64,
     pub inactive_weights: u64,
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StakeWeightedReputationConfig {
+    pub active_weights: u64,
+    pub inactive_weights: u64,
+    // Weight of the validators that were the leader of a failed round in the window,
+    // it takes precedence over the active weight
+    pub failed_weights: u64,
+}
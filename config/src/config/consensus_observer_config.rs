@@ -0,0 +1,43 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusObserverConfig {
+    // Publish the committed blocks to the subscribed fullnodes (validators and validator fullnodes)
+    pub publisher_enabled: bool,
+    // Follow consensus through the blocks published by the upstream peers instead of polling
+    // state sync (fullnodes), state sync is only used to catch up on gaps
+    pub observer_enabled: bool,
+    // Maximum number of fullnodes a publisher serves
+    pub max_subscribers: usize,
+    // Maximum number of ordered blocks an observer buffers while waiting for their commit
+    pub max_pending_blocks: usize,
+    // Size of the channel of observer network events
+    pub network_channel_size: usize,
+    // Interval between two checks of the subscription
+    pub progress_check_interval_ms: u64,
+    // The observer subscribes to another upstream peer if its subscription delivered no message
+    // for this long
+    pub subscription_timeout_ms: u64,
+    // State sync polls the upstream peers again, as on regular fullnodes, once the observer
+    // committed nothing for this long
+    pub state_sync_fallback_ms: u64,
+}
+
+impl Default for ConsensusObserverConfig {
+    fn default() -> Self {
+        Self {
+            publisher_enabled: false,
+            observer_enabled: false,
+            max_subscribers: 20,
+            max_pending_blocks: 100,
+            network_channel_size: 1_024,
+            progress_check_interval_ms: 1_000,
+            subscription_timeout_ms: 5_000,
+            state_sync_fallback_ms: 15_000,
+        }
+    }
+}
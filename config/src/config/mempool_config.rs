@@ -10,8 +10,15 @@ pub struct MempoolConfig {
     pub capacity_per_user: usize,
     // number of failovers to broadcast to when the primary network is alive
     pub default_failovers: usize,
+    // when mempool is full, evict the lowest-ranked ready txns to make room for higher-ranked ones
+    pub evict_lowest_ranked_txns: bool,
     pub max_broadcasts_per_peer: usize,
     pub mempool_snapshot_interval_secs: u64,
+    // persist accepted txns to disk, so that they are replayed into mempool after a restart
+    pub persist_txns: bool,
+    // minimum gas price increase (in percent) for a txn to replace one with the same sender and
+    // sequence number that is already in mempool
+    pub replace_by_fee_min_bump_pct: u64,
     pub shared_mempool_ack_timeout_ms: u64,
     pub shared_mempool_backoff_interval_ms: u64,
     pub shared_mempool_batch_size: usize,
@@ -31,9 +38,12 @@ impl Default for MempoolConfig {
             shared_mempool_max_concurrent_inbound_syncs: 2,
             max_broadcasts_per_peer: 1,
             mempool_snapshot_interval_secs: 180,
+            persist_txns: false,
             capacity: 1_000_000,
             capacity_per_user: 100,
             default_failovers: 3,
+            evict_lowest_ranked_txns: true,
+            replace_by_fee_min_bump_pct: 10,
             system_transaction_timeout_secs: 600,
             system_transaction_gc_interval_ms: 60_000,
         }
@@ -16,6 +16,8 @@ use thiserror::Error;
 
 mod consensus_config;
 pub use consensus_config::*;
+mod consensus_observer_config;
+pub use consensus_observer_config::*;
 mod debug_interface_config;
 pub use debug_interface_config::*;
 mod error;
@@ -64,6 +66,8 @@ pub struct NodeConfig {
     #[serde(default)]
     pub consensus: ConsensusConfig,
     #[serde(default)]
+    pub consensus_observer: ConsensusObserverConfig,
+    #[serde(default)]
     pub debug_interface: DebugInterfaceConfig,
     #[serde(default)]
     pub execution: ExecutionConfig,
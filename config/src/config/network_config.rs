@@ -42,9 +42,17 @@ pub const MAX_CONNECTION_DELAY_MS: u64 = 60_000; /* 1 minute */
 pub const MAX_FULLNODE_OUTBOUND_CONNECTIONS: usize = 3;
 pub const MAX_INBOUND_CONNECTIONS: usize = 100;
 pub const MAX_FRAME_SIZE: usize = 8 * 1024 * 1024; /* 8 MiB */
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024; /* 64 MiB */
 pub const CONNECTION_BACKOFF_BASE: u64 = 2;
 pub const IP_BYTE_BUCKET_RATE: usize = 102400 /* 100 KiB */;
 pub const IP_BYTE_BUCKET_SIZE: usize = IP_BYTE_BUCKET_RATE;
+pub const PEER_BAN_THRESHOLD: i64 = -100;
+pub const PEER_BAN_DURATION_MS: u64 = 10 * 60 * 1000; /* 10 minutes */
+pub const PEER_SCORE_RECOVERY_PER_SEC: i64 = 1;
+pub const PEER_EXCHANGE_INTERVAL_MS: u64 = 60_000; /* 1 minute */
+pub const PEER_EXCHANGE_MAX_PEERS_PER_LIST: usize = 32;
+pub const PEER_EXCHANGE_MAX_DISCOVERED_PEERS: usize = 256;
+pub const PEER_EXCHANGE_MAX_CHANGES_PER_INTERVAL: usize = 16;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -82,8 +90,13 @@ pub struct NetworkConfig {
     pub seeds: PeerSet,
     // The maximum size of an inbound or outbound request frame
     pub max_frame_size: usize,
+    // The maximum size of an inbound or outbound rpc message, larger messages than a frame are
+    // streamed over multiple frames
+    pub max_message_size: usize,
     // Enables proxy protocol on incoming connections to get original source addresses
     pub enable_proxy_protocol: bool,
+    // Compresses the messages of the protocols with large messages, if the peer enabled it too
+    pub enable_compression: bool,
     // Interval to send healthcheck pings to peers
     pub ping_interval_ms: u64,
     // Timeout until a healthcheck ping is rejected
@@ -98,6 +111,8 @@ pub struct NetworkConfig {
     pub inbound_rate_limit_config: Option<RateLimitConfig>,
     // Outbound rate limiting configuration, if not specified, no rate limiting
     pub outbound_rate_limit_config: Option<RateLimitConfig>,
+    // Scoring of misbehaving peers, peers with a low score are temporarily banned
+    pub peer_reputation_config: PeerReputationConfig,
 }
 
 impl Default for NetworkConfig {
@@ -119,7 +134,9 @@ impl NetworkConfig {
             seed_addrs: HashMap::new(),
             seeds: PeerSet::default(),
             max_frame_size: MAX_FRAME_SIZE,
+            max_message_size: MAX_MESSAGE_SIZE,
             enable_proxy_protocol: false,
+            enable_compression: true,
             max_connection_delay_ms: MAX_CONNECTION_DELAY_MS,
             connectivity_check_interval_ms: CONNECTIVITY_CHECK_INTERVAL_MS,
             network_channel_size: NETWORK_CHANNEL_SIZE,
@@ -132,6 +149,7 @@ impl NetworkConfig {
             max_inbound_connections: MAX_INBOUND_CONNECTIONS,
             inbound_rate_limit_config: None,
             outbound_rate_limit_config: None,
+            peer_reputation_config: PeerReputationConfig::default(),
         };
         config.prepare_identity();
         config
@@ -304,6 +322,7 @@ impl NetworkConfig {
 pub enum DiscoveryMethod {
     Onchain,
     File(PathBuf, Duration),
+    PeerExchange(PeerExchangeConfig),
     None,
 }
 
@@ -371,6 +390,65 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// Scoring and temporary banning of misbehaving peers. Only untrusted peers (with the `Unknown`
+/// role) can be banned, so this has no effect on mutually authenticated networks.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerReputationConfig {
+    /// Score at or below which a peer or an IP address is banned, scores start at zero
+    pub ban_threshold: i64,
+    /// Duration of a ban
+    pub ban_duration_ms: u64,
+    /// Score recovered every second, up to zero
+    pub recovery_per_sec: i64,
+    /// Allow for disabling the bans, scores are still tracked
+    pub enabled: bool,
+}
+
+impl Default for PeerReputationConfig {
+    fn default() -> Self {
+        Self {
+            ban_threshold: PEER_BAN_THRESHOLD,
+            ban_duration_ms: PEER_BAN_DURATION_MS,
+            recovery_per_sec: PEER_SCORE_RECOVERY_PER_SEC,
+            enabled: true,
+        }
+    }
+}
+
+/// Discovery of public peers through the lists shared by the connected peers.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerExchangeConfig {
+    /// Interval between two lists sent to every connected peer
+    pub interval_ms: u64,
+    /// Addresses this node is reachable at, advertised in its own lists. The noise and handshake
+    /// protocols are appended to them. Nodes without advertised addresses are only shared by the
+    /// peers that dialed them.
+    pub advertised_addresses: Vec<NetworkAddress>,
+    /// Maximum number of peers in a list, longer lists are rejected
+    pub max_peers_per_list: usize,
+    /// Maximum number of peers discovered through peer exchange
+    pub max_discovered_peers: usize,
+    /// Maximum number of peers added or with updated addresses per interval
+    pub max_changes_per_interval: usize,
+    /// Discovered peers are forgotten when no list shared them for this long
+    pub expiry_ms: u64,
+}
+
+impl Default for PeerExchangeConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: PEER_EXCHANGE_INTERVAL_MS,
+            advertised_addresses: Vec::new(),
+            max_peers_per_list: PEER_EXCHANGE_MAX_PEERS_PER_LIST,
+            max_discovered_peers: PEER_EXCHANGE_MAX_DISCOVERED_PEERS,
+            max_changes_per_interval: PEER_EXCHANGE_MAX_CHANGES_PER_INTERVAL,
+            expiry_ms: 10 * PEER_EXCHANGE_INTERVAL_MS,
+        }
+    }
+}
+
 pub type PeerSet = HashMap<PeerId, Peer>;
 
 // TODO: Combine with RoleType?
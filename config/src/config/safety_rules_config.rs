@@ -5,7 +5,7 @@ use crate::{
     config::{LoggerConfig, SecureBackend},
     keys::ConfigKey,
 };
-use diem_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+use diem_crypto::{ed25519::Ed25519PrivateKey, x25519, Uniform};
 use diem_types::{network_address::NetworkAddress, waypoint::Waypoint, PeerId};
 use rand::rngs::StdRng;
 use serde::{Deserialize, Serialize};
@@ -73,6 +73,30 @@ pub enum SafetyRulesService {
 #[serde(deny_unknown_fields)]
 pub struct RemoteService {
     pub server_address: NetworkAddress,
+    // Authenticates and encrypts the connection between consensus and the safety rules service
+    // with a Noise IK handshake, the connection is plaintext if unset
+    #[serde(default)]
+    pub noise: Option<NoiseKeysConfig>,
+}
+
+/// The static keys of one end of a Noise IK connection.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseKeysConfig {
+    // The private key of this end: consensus in the node config, the safety rules service in its
+    // own config
+    pub private_key: ConfigKey<x25519::PrivateKey>,
+    // The public key of the other end, any other peer is rejected
+    pub remote_public_key: x25519::PublicKey,
+}
+
+impl NoiseKeysConfig {
+    pub fn new(private_key: x25519::PrivateKey, remote_public_key: x25519::PublicKey) -> Self {
+        Self {
+            private_key: ConfigKey::new(private_key),
+            remote_public_key,
+        }
+    }
 }
 
 impl RemoteService {
@@ -3,13 +3,37 @@
 
 use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrappingMode {
+    // Execute every transaction after the local synced version (state sync v1)
+    ExecuteTransactionsFromGenesis,
+    // Download a verified account state snapshot at the latest epoch ending ledger info and only
+    // sync the transactions after it (state sync v2)
+    DownloadLatestAccountStates,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinuousSyncingMode {
+    // Execute every synced transaction and check the results against the transaction infos
+    ExecuteTransactions,
+    // Apply the write sets of the synced transaction outputs without executing the transactions,
+    // checking them against the transaction infos
+    ApplyTransactionOutputs,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StateSyncConfig {
+    // How the node catches up with the network when it starts
+    pub bootstrapping_mode: BootstrappingMode,
     // Size of chunk to request for state synchronization
     pub chunk_limit: u64,
     // The timeout of the state sync client to process a commit notification (in milliseconds)
     pub client_commit_timeout_ms: u64,
+    // How the node keeps up with the network once bootstrapped
+    pub continuous_syncing_mode: ContinuousSyncingMode,
     // default timeout used for long polling to remote peer
     pub long_poll_timeout_ms: u64,
     // valid maximum chunk limit for sanity check
@@ -22,6 +46,13 @@ pub struct StateSyncConfig {
     // if no progress is made by sending chunk requests to a number of networks,
     // the next sync request will be multicasted, i.e. sent to more networks
     pub multicast_timeout_ms: u64,
+    // Number of accounts to request in a single state snapshot chunk
+    pub state_snapshot_chunk_size: u64,
+    // The timeout of a state snapshot request to a remote peer (in milliseconds)
+    pub state_snapshot_request_timeout_ms: u64,
+    // The timeout of the whole state snapshot sync, after which the node falls back to syncing
+    // transactions (in milliseconds)
+    pub state_snapshot_sync_timeout_ms: u64,
     // The timeout for ensuring sync requests are making progress (i.e., the maximum time between
     // commits when processing a sync request).
     pub sync_request_timeout_ms: u64,
@@ -32,13 +63,18 @@ pub struct StateSyncConfig {
 impl Default for StateSyncConfig {
     fn default() -> Self {
         Self {
+            bootstrapping_mode: BootstrappingMode::ExecuteTransactionsFromGenesis,
             chunk_limit: 1000,
             client_commit_timeout_ms: 5_000,
+            continuous_syncing_mode: ContinuousSyncingMode::ExecuteTransactions,
             long_poll_timeout_ms: 10_000,
             max_chunk_limit: 1000,
             max_timeout_ms: 120_000,
             mempool_commit_timeout_ms: 5_000,
             multicast_timeout_ms: 30_000,
+            state_snapshot_chunk_size: 1000,
+            state_snapshot_request_timeout_ms: 10_000,
+            state_snapshot_sync_timeout_ms: 1_800_000,
             sync_request_timeout_ms: 60_000,
             tick_interval_ms: 100,
         }
@@ -15,7 +15,7 @@ use diem_types::{
     block_metadata::BlockMetadata,
     epoch_state::EpochState,
     ledger_info::LedgerInfo,
-    transaction::{Transaction, Version},
+    transaction::{SignedTransaction, Transaction, Version},
     validator_signer::ValidatorSigner,
     validator_verifier::ValidatorVerifier,
 };
@@ -229,6 +229,9 @@ impl Block {
                     .as_ref()
                     .ok_or_else(|| format_err!("Missing signature in Proposal"))?;
                 validator.verify(*author, &self.block_data, signature)?;
+                if let Some(payload) = self.payload() {
+                    payload.verify(validator, self.epoch())?;
+                }
                 self.quorum_cert().verify(validator)
             }
         }
@@ -288,15 +291,31 @@ impl Block {
         Ok(())
     }
 
-    pub fn transactions_to_execute(&self) -> Vec<Transaction> {
+    /// Verifies that `payload_txns` are the user transactions the payload of the block resolves
+    /// to, so that an executor can't be made to sign the execution of other transactions.
+    pub fn verify_payload_txns(&self, payload_txns: &[SignedTransaction]) -> anyhow::Result<()> {
+        match self.payload() {
+            Some(payload) => payload.verify_txns(payload_txns),
+            None => {
+                ensure!(
+                    payload_txns.is_empty(),
+                    "Block {} has no payload but {} transactions",
+                    self.id(),
+                    payload_txns.len()
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// The transactions executed by the block: the block prologue followed by the user
+    /// transactions the payload resolves to.
+    pub fn transactions_to_execute(
+        &self,
+        payload_txns: Vec<SignedTransaction>,
+    ) -> Vec<Transaction> {
         std::iter::once(Transaction::BlockMetadata(self.into()))
-            .chain(
-                self.payload()
-                    .unwrap_or(&Vec::new())
-                    .iter()
-                    .cloned()
-                    .map(Transaction::UserTransaction),
-            )
+            .chain(payload_txns.into_iter().map(Transaction::UserTransaction))
             .collect()
     }
 }
@@ -6,6 +6,8 @@ use crate::{
         block_test_utils::{certificate_for_genesis, *},
         Block,
     },
+    common::Payload,
+    proof_of_store::{Batch, ProofOfStore},
     quorum_cert::QuorumCert,
 };
 use diem_crypto::hash::HashValue;
@@ -43,7 +45,7 @@ fn test_nil_block() {
     assert!(nil_block.verify_well_formed().is_ok());
 
     let signer = ValidatorSigner::random(None);
-    let payload = vec![];
+    let payload = Payload::empty(false);
     let parent_block_info = nil_block.quorum_cert().certified_block();
     let nil_block_qc = gen_test_certificate(
         vec![&signer],
@@ -78,7 +80,7 @@ fn test_block_relation() {
     // Test genesis and the next block
     let genesis_block = Block::make_genesis_block();
     let quorum_cert = certificate_for_genesis();
-    let payload = vec![];
+    let payload = Payload::empty(false);
     let next_block = Block::new_proposal(
         payload.clone(),
         1,
@@ -105,7 +107,7 @@ fn test_same_qc_different_authors() {
     let signer = ValidatorSigner::random(None);
     let genesis_qc = certificate_for_genesis();
     let round = 1;
-    let payload = vec![];
+    let payload = Payload::empty(false);
     let current_timestamp = diem_infallible::duration_since_epoch().as_micros() as u64;
     let block_round_1 = Block::new_proposal(
         payload.clone(),
@@ -134,3 +136,46 @@ fn test_same_qc_different_authors() {
     assert!(block_round_1.id() != block_round_1_altered.id());
     assert_eq!(block_round_1.id(), block_round_1_same.id());
 }
+
+#[test]
+fn test_verify_payload_txns() {
+    let signer = ValidatorSigner::random(None);
+    let txns = match random_payload(4) {
+        Payload::DirectMempool(txns) => txns,
+        Payload::InQuorumStore(_) => unreachable!(),
+    };
+    let current_timestamp = diem_infallible::duration_since_epoch().as_micros() as u64;
+    let new_block = |payload| {
+        Block::new_proposal(
+            payload,
+            1,
+            current_timestamp,
+            certificate_for_genesis(),
+            &signer,
+        )
+    };
+
+    let direct = new_block(Payload::DirectMempool(txns.clone()));
+    direct.verify_payload_txns(&txns).unwrap();
+    direct.verify_payload_txns(&txns[1..]).unwrap_err();
+
+    let batch_1 = Batch::new(signer.author(), 1, 0, txns[..1].to_vec());
+    let batch_2 = Batch::new(signer.author(), 1, 1, txns[1..3].to_vec());
+    let in_quorum_store = new_block(Payload::InQuorumStore(
+        [&batch_1, &batch_2]
+            .iter()
+            .map(|batch| ProofOfStore::new(batch.info().clone(), BTreeMap::new()))
+            .collect(),
+    ));
+    in_quorum_store.verify_payload_txns(&txns[..3]).unwrap();
+    // Missing, extra or substituted transactions are rejected
+    in_quorum_store.verify_payload_txns(&txns[..2]).unwrap_err();
+    in_quorum_store.verify_payload_txns(&txns).unwrap_err();
+    in_quorum_store
+        .verify_payload_txns(&[txns[0].clone(), txns[1].clone(), txns[3].clone()])
+        .unwrap_err();
+
+    let nil_block = Block::new_nil(1, certificate_for_genesis());
+    nil_block.verify_payload_txns(&[]).unwrap();
+    nil_block.verify_payload_txns(&txns).unwrap_err();
+}
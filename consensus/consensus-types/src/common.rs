@@ -1,7 +1,14 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use crate::proof_of_store::{BatchPayload, ProofOfStore};
+use anyhow::ensure;
+use diem_crypto::hash::CryptoHash;
+use diem_types::{
+    account_address::AccountAddress, transaction::SignedTransaction,
+    validator_verifier::ValidatorVerifier,
+};
+use serde::{Deserialize, Serialize};
 
 /// The round of a block is a consensus-internal counter, which starts with 0 and increases
 /// monotonically. It is used for the protocol safety and liveness (please see the detailed
@@ -11,4 +18,94 @@ pub type Round = u64;
 pub type Author = AccountAddress;
 
 /// The payload in block.
-pub type Payload = Vec<SignedTransaction>;
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum Payload {
+    /// The transactions pulled directly from mempool by the proposer.
+    DirectMempool(Vec<SignedTransaction>),
+    /// The proofs of store of batches disseminated by the quorum store ahead of the proposal,
+    /// the transactions are resolved from the batches before execution.
+    InQuorumStore(Vec<ProofOfStore>),
+}
+
+impl Payload {
+    /// Returns an empty payload of the kind used when the quorum store is enabled or not.
+    pub fn empty(quorum_store_enabled: bool) -> Self {
+        if quorum_store_enabled {
+            Payload::InQuorumStore(vec![])
+        } else {
+            Payload::DirectMempool(vec![])
+        }
+    }
+
+    /// Returns the number of transactions ordered by the payload.
+    pub fn len(&self) -> usize {
+        match self {
+            Payload::DirectMempool(txns) => txns.len(),
+            Payload::InQuorumStore(proofs) => proofs
+                .iter()
+                .map(|proof| proof.info().num_txns() as usize)
+                .sum(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Payload::DirectMempool(txns) => txns.is_empty(),
+            Payload::InQuorumStore(proofs) => proofs.is_empty(),
+        }
+    }
+
+    /// Verifies that `txns` are the transactions the payload resolves to: the transactions
+    /// themselves for a direct payload, or the concatenation of the batches whose digests the
+    /// proofs of store certify.
+    pub fn verify_txns(&self, txns: &[SignedTransaction]) -> anyhow::Result<()> {
+        match self {
+            Payload::DirectMempool(payload_txns) => ensure!(
+                payload_txns.as_slice() == txns,
+                "Transactions don't match the direct payload"
+            ),
+            Payload::InQuorumStore(proofs) => {
+                let mut remaining = txns;
+                for proof in proofs {
+                    let num_txns = proof.info().num_txns() as usize;
+                    ensure!(
+                        remaining.len() >= num_txns,
+                        "Missing transactions of batch {}",
+                        proof.digest()
+                    );
+                    let (batch_txns, rest) = remaining.split_at(num_txns);
+                    ensure!(
+                        BatchPayload::new(batch_txns.to_vec()).hash() == proof.digest(),
+                        "Transactions don't match the digest of batch {}",
+                        proof.digest()
+                    );
+                    remaining = rest;
+                }
+                ensure!(
+                    remaining.is_empty(),
+                    "{} transactions are not part of any batch of the payload",
+                    remaining.len()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies the proofs of store carried by the payload were signed by a quorum of the
+    /// validators of the given epoch.
+    pub fn verify(&self, validator: &ValidatorVerifier, epoch: u64) -> anyhow::Result<()> {
+        if let Payload::InQuorumStore(proofs) = self {
+            for proof in proofs {
+                ensure!(
+                    proof.epoch() == epoch,
+                    "Proof of store for batch {} is from epoch {}, expected {}",
+                    proof.digest(),
+                    proof.epoch(),
+                    epoch
+                );
+                proof.verify(validator)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -11,7 +11,7 @@ use diem_crypto::hash::HashValue;
 use diem_types::{
     block_info::BlockInfo,
     contract_event::ContractEvent,
-    transaction::{Transaction, TransactionStatus},
+    transaction::{SignedTransaction, Transaction, TransactionStatus},
 };
 use executor_types::StateComputeResult;
 use std::fmt::{Debug, Display, Formatter};
@@ -104,13 +104,14 @@ impl ExecutedBlock {
         }
     }
 
-    pub fn transactions_to_commit(&self) -> Vec<Transaction> {
+    /// `payload_txns` are the user transactions the payload of the block resolves to.
+    pub fn transactions_to_commit(&self, payload_txns: Vec<SignedTransaction>) -> Vec<Transaction> {
         // reconfiguration suffix don't execute
         if self.block.block_data().is_reconfiguration_suffix() {
             return vec![];
         }
         itertools::zip_eq(
-            self.block.transactions_to_execute(),
+            self.block.transactions_to_execute(payload_txns),
             self.state_compute_result.compute_status(),
         )
         .filter_map(|(txn, status)| match status {
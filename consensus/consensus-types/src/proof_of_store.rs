@@ -0,0 +1,275 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Types used by the quorum store to disseminate batches of transactions ahead of the proposals
+//! that order them: validators broadcast batches, the receivers sign the info of the batches they
+//! stored, and a quorum of such signatures forms a proof of store that proposals refer to instead
+//! of carrying the transactions.
+
+use crate::common::Author;
+use anyhow::{ensure, Context};
+use diem_crypto::{ed25519::Ed25519Signature, hash::CryptoHash, HashValue};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_types::{transaction::SignedTransaction, validator_verifier::ValidatorVerifier};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+/// BatchInfo identifies a batch and is what the validators sign once they stored the batch.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, CryptoHasher, BCSCryptoHash)]
+pub struct BatchInfo {
+    /// The validator that created the batch.
+    author: Author,
+    /// The epoch the batch was created in, proofs are only valid within it.
+    epoch: u64,
+    /// Author-local counter, only used to tell apart the batches of an author.
+    batch_id: u64,
+    /// The hash of the transactions of the batch.
+    digest: HashValue,
+    num_txns: u64,
+}
+
+impl Display for BatchInfo {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "BatchInfo: [author: {}, epoch: {}, batch_id: {}, digest: {}, num_txns: {}]",
+            self.author, self.epoch, self.batch_id, self.digest, self.num_txns,
+        )
+    }
+}
+
+impl BatchInfo {
+    pub fn new(
+        author: Author,
+        epoch: u64,
+        batch_id: u64,
+        digest: HashValue,
+        num_txns: u64,
+    ) -> Self {
+        Self {
+            author,
+            epoch,
+            batch_id,
+            digest,
+            num_txns,
+        }
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn batch_id(&self) -> u64 {
+        self.batch_id
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.num_txns
+    }
+}
+
+/// The transactions of a batch, their hash is the digest of the batch.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, CryptoHasher, BCSCryptoHash)]
+pub struct BatchPayload {
+    txns: Vec<SignedTransaction>,
+}
+
+impl BatchPayload {
+    pub fn new(txns: Vec<SignedTransaction>) -> Self {
+        Self { txns }
+    }
+}
+
+/// A batch of transactions broadcast by its author to all the validators.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Batch {
+    info: BatchInfo,
+    payload: BatchPayload,
+}
+
+impl Batch {
+    pub fn new(author: Author, epoch: u64, batch_id: u64, txns: Vec<SignedTransaction>) -> Self {
+        let payload = BatchPayload::new(txns);
+        let info = BatchInfo::new(
+            author,
+            epoch,
+            batch_id,
+            payload.hash(),
+            payload.txns.len() as u64,
+        );
+        Self { info, payload }
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.info.digest()
+    }
+
+    pub fn author(&self) -> Author {
+        self.info.author()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.info.epoch()
+    }
+
+    pub fn txns(&self) -> &[SignedTransaction] {
+        &self.payload.txns
+    }
+
+    pub fn into_transactions(self) -> Vec<SignedTransaction> {
+        self.payload.txns
+    }
+
+    /// Verifies that the info of the batch matches its transactions.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.payload.hash() == self.info.digest(),
+            "Batch digest {} doesn't match its transactions",
+            self.info.digest()
+        );
+        ensure!(
+            self.payload.txns.len() as u64 == self.info.num_txns(),
+            "Batch {} has {} transactions, expected {}",
+            self.info.digest(),
+            self.payload.txns.len(),
+            self.info.num_txns()
+        );
+        Ok(())
+    }
+}
+
+/// The signature of a validator over the info of a batch it stored.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedBatchInfo {
+    info: BatchInfo,
+    signer: Author,
+    signature: Ed25519Signature,
+}
+
+impl SignedBatchInfo {
+    pub fn new(info: BatchInfo, signer: Author, signature: Ed25519Signature) -> Self {
+        Self {
+            info,
+            signer,
+            signature,
+        }
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn signer(&self) -> Author {
+        self.signer
+    }
+
+    pub fn signature(&self) -> &Ed25519Signature {
+        &self.signature
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.info.epoch()
+    }
+
+    /// Verifies that the signature was produced by the signer over the batch info.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        validator
+            .verify(self.signer, &self.info, &self.signature)
+            .context("Failed to verify SignedBatchInfo")
+    }
+}
+
+/// ProofOfStore certifies that a quorum of validators stored the batch, so that it can be
+/// retrieved by any validator that needs to execute it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProofOfStore {
+    info: BatchInfo,
+    signatures: BTreeMap<Author, Ed25519Signature>,
+}
+
+impl Display for ProofOfStore {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "ProofOfStore: [{}, signers: {}]",
+            self.info,
+            self.signatures.len()
+        )
+    }
+}
+
+impl ProofOfStore {
+    pub fn new(info: BatchInfo, signatures: BTreeMap<Author, Ed25519Signature>) -> Self {
+        Self { info, signatures }
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.info.digest()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.info.epoch()
+    }
+
+    /// The validators that signed the batch info, i.e., the ones the batch can be fetched from.
+    pub fn signers(&self) -> impl Iterator<Item = &Author> {
+        self.signatures.keys()
+    }
+
+    /// Verifies that the batch info was signed by a quorum of the validators.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        validator
+            .verify_aggregated_struct_signature(&self.info, &self.signatures)
+            .context("Failed to verify ProofOfStore")
+    }
+}
+
+/// RPC to fetch a batch that is missing locally from one of the signers of its proof.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchRequest {
+    epoch: u64,
+    digest: HashValue,
+}
+
+impl Display for BatchRequest {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[BatchRequest epoch: {}, digest: {}]",
+            self.epoch, self.digest
+        )
+    }
+}
+
+impl BatchRequest {
+    pub fn new(epoch: u64, digest: HashValue) -> Self {
+        Self { epoch, digest }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+}
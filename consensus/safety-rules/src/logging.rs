@@ -55,6 +55,7 @@ pub enum LogEntry {
     State,
     Waypoint,
     SignCommitVote,
+    SignBatchInfo,
 }
 
 impl LogEntry {
@@ -75,6 +76,7 @@ impl LogEntry {
             LogEntry::State => "state",
             LogEntry::Waypoint => "waypoint",
             LogEntry::SignCommitVote => "sign_commit_vote",
+            LogEntry::SignBatchInfo => "sign_batch_info",
         }
     }
 }
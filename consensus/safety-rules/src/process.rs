@@ -6,7 +6,7 @@ use crate::{
     remote_service::{self, RemoteService},
     safety_rules_manager,
 };
-use diem_config::config::{SafetyRulesConfig, SafetyRulesService};
+use diem_config::config::{NoiseKeysConfig, SafetyRulesConfig, SafetyRulesService};
 
 use std::net::SocketAddr;
 
@@ -25,6 +25,7 @@ impl Process {
             _ => panic!("Unexpected SafetyRules service: {:?}", config.service),
         };
         let server_addr = service.server_address();
+        let noise_keys = service.noise.clone();
 
         Self {
             data: Some(ProcessData {
@@ -34,6 +35,7 @@ impl Process {
                 export_consensus_key,
                 network_timeout: config.network_timeout_ms,
                 decoupled_execution: config.decoupled_execution,
+                noise_keys,
             }),
         }
     }
@@ -47,6 +49,7 @@ impl Process {
             data.export_consensus_key,
             data.network_timeout,
             data.decoupled_execution,
+            data.noise_keys,
         );
     }
 }
@@ -59,18 +62,25 @@ struct ProcessData {
     // Timeout in Seconds for network operations
     network_timeout: u64,
     decoupled_execution: bool,
+    noise_keys: Option<NoiseKeysConfig>,
 }
 
 pub struct ProcessService {
     server_addr: SocketAddr,
     network_timeout_ms: u64,
+    noise_keys: Option<NoiseKeysConfig>,
 }
 
 impl ProcessService {
-    pub fn new(server_addr: SocketAddr, network_timeout: u64) -> Self {
+    pub fn new(
+        server_addr: SocketAddr,
+        network_timeout: u64,
+        noise_keys: Option<NoiseKeysConfig>,
+    ) -> Self {
         Self {
             server_addr,
             network_timeout_ms: network_timeout,
+            noise_keys,
         }
     }
 }
@@ -83,4 +93,8 @@ impl RemoteService for ProcessService {
     fn network_timeout_ms(&self) -> u64 {
         self.network_timeout_ms
     }
+
+    fn noise_keys(&self) -> Option<&NoiseKeysConfig> {
+        self.noise_keys.as_ref()
+    }
 }
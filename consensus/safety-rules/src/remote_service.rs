@@ -6,17 +6,27 @@ use crate::{
     serializer::{SafetyRulesInput, SerializerClient, SerializerService, TSerializerClient},
     Error, SafetyRules, TSafetyRules,
 };
+use diem_config::config::NoiseKeysConfig;
 use diem_logger::warn;
 use diem_secure_net::{NetworkClient, NetworkServer};
 use std::net::SocketAddr;
 
 pub trait RemoteService {
     fn client(&self) -> SerializerClient {
-        let network_client = NetworkClient::new(
-            "safety-rules",
-            self.server_address(),
-            self.network_timeout_ms(),
-        );
+        let network_client = match self.noise_keys() {
+            Some(keys) => NetworkClient::new_with_noise(
+                "safety-rules",
+                self.server_address(),
+                self.network_timeout_ms(),
+                keys.private_key.private_key(),
+                keys.remote_public_key,
+            ),
+            None => NetworkClient::new(
+                "safety-rules",
+                self.server_address(),
+                self.network_timeout_ms(),
+            ),
+        };
         let service = Box::new(RemoteClient::new(network_client));
         SerializerClient::new_client(service)
     }
@@ -25,6 +35,11 @@ pub trait RemoteService {
 
     /// Network Timeout in milliseconds.
     fn network_timeout_ms(&self) -> u64;
+
+    /// Keys authenticating the connection to the server, if any.
+    fn noise_keys(&self) -> Option<&NoiseKeysConfig> {
+        None
+    }
 }
 
 pub fn execute(
@@ -34,6 +49,7 @@ pub fn execute(
     export_consensus_key: bool,
     network_timeout_ms: u64,
     decoupled_execution: bool,
+    noise_keys: Option<NoiseKeysConfig>,
 ) {
     let mut safety_rules = SafetyRules::new(
         storage,
@@ -46,7 +62,16 @@ pub fn execute(
     }
 
     let mut serializer_service = SerializerService::new(safety_rules);
-    let mut network_server = NetworkServer::new("safety-rules", listen_addr, network_timeout_ms);
+    let mut network_server = match noise_keys {
+        Some(keys) => NetworkServer::new_with_noise(
+            "safety-rules",
+            listen_addr,
+            network_timeout_ms,
+            keys.private_key.private_key(),
+            vec![keys.remote_public_key].into_iter().collect(),
+        ),
+        None => NetworkServer::new("safety-rules", listen_addr, network_timeout_ms),
+    };
 
     loop {
         if let Err(e) = process_one_message(&mut network_server, &mut serializer_service) {
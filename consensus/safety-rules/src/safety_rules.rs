@@ -14,6 +14,7 @@ use consensus_types::{
     block::Block,
     block_data::BlockData,
     common::{Author, Round},
+    proof_of_store::BatchInfo,
     quorum_cert::QuorumCert,
     safety_data::SafetyData,
     timeout::Timeout,
@@ -511,6 +512,19 @@ impl SafetyRules {
 
         Ok(signature)
     }
+
+    fn guarded_sign_batch_info(
+        &mut self,
+        batch_info: &BatchInfo,
+    ) -> Result<Ed25519Signature, Error> {
+        self.signer()?;
+
+        let safety_data = self.persistent_storage.safety_data()?;
+        self.verify_epoch(batch_info.epoch(), &safety_data)?;
+
+        let signature = self.sign(batch_info)?;
+        Ok(signature)
+    }
 }
 
 impl TSafetyRules for SafetyRules {
@@ -581,6 +595,15 @@ impl TSafetyRules for SafetyRules {
         let cb = || self.guarded_sign_commit_vote(ledger_info, new_ledger_info);
         run_and_log(cb, |log| log, LogEntry::SignCommitVote)
     }
+
+    fn sign_batch_info(&mut self, batch_info: &BatchInfo) -> Result<Ed25519Signature, Error> {
+        let cb = || self.guarded_sign_batch_info(batch_info);
+        run_and_log(
+            cb,
+            |log| log.epoch(batch_info.epoch()),
+            LogEntry::SignBatchInfo,
+        )
+    }
 }
 
 fn run_and_log<F, L, R>(callback: F, log_cb: L, log_entry: LogEntry) -> Result<R, Error>
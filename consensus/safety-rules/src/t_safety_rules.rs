@@ -4,6 +4,7 @@
 use crate::{ConsensusState, Error};
 use consensus_types::{
     block_data::BlockData,
+    proof_of_store::BatchInfo,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
     vote::Vote,
@@ -62,4 +63,8 @@ pub trait TSafetyRules {
         ledger_info: LedgerInfoWithSignatures,
         new_ledger_info: LedgerInfo,
     ) -> Result<Ed25519Signature, Error>;
+
+    /// As the holder of the private key, SafetyRules also signs the info of the batches stored
+    /// by the quorum store. This returns the signature for the batch info.
+    fn sign_batch_info(&mut self, batch_info: &BatchInfo) -> Result<Ed25519Signature, Error>;
 }
@@ -3,6 +3,7 @@
 
 mod local;
 mod networking;
+mod process;
 mod safety_rules;
 mod serializer;
 mod suite;
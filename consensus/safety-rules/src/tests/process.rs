@@ -0,0 +1,112 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{remote_service, test_utils, tests::suite, SafetyRulesManager, TSafetyRules};
+use diem_config::{config::NoiseKeysConfig, utils};
+use diem_crypto::{ed25519::Ed25519PrivateKey, x25519, Uniform};
+use diem_types::validator_signer::ValidatorSigner;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    thread,
+};
+
+/// Test value for network_timeout, in milliseconds.
+const NETWORK_TIMEOUT: u64 = 5_000;
+
+#[test]
+fn test() {
+    let boolean_values = [false, true];
+    for verify_vote_proposal_signature in &boolean_values {
+        for decoupled_execution in &boolean_values {
+            suite::run_test_suite(
+                &safety_rules(*verify_vote_proposal_signature, *decoupled_execution),
+                *decoupled_execution,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_unknown_peer_rejected() {
+    let mut rng = StdRng::from_seed([1u8; 32]);
+    let signer = ValidatorSigner::from_int(0);
+    let (server_addr, server_public_key) = start_server(&signer, &mut rng);
+
+    // A client using a key unknown to the server can't establish a session
+    let unknown_key = x25519::PrivateKey::generate(&mut rng);
+    let mut network_client = diem_secure_net::NetworkClient::new_with_noise(
+        "safety-rules",
+        server_addr,
+        NETWORK_TIMEOUT,
+        unknown_key,
+        server_public_key,
+    );
+    assert!(network_client.write(b"consensus_state").is_err());
+}
+
+/// Starts a safety rules server accepting the client key generated right after the server key.
+fn start_server(signer: &ValidatorSigner, rng: &mut StdRng) -> (SocketAddr, x25519::PublicKey) {
+    let server_key = x25519::PrivateKey::generate(rng);
+    let server_public_key = server_key.public_key();
+    let client_key = x25519::PrivateKey::generate(rng);
+    let server_keys = NoiseKeysConfig::new(server_key, client_key.public_key());
+
+    let storage = test_utils::test_storage(signer);
+    let server_port = utils::get_available_port();
+    let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+    thread::spawn(move || {
+        remote_service::execute(
+            storage,
+            server_addr,
+            false,
+            false,
+            NETWORK_TIMEOUT,
+            false,
+            Some(server_keys),
+        )
+    });
+    (server_addr, server_public_key)
+}
+
+fn safety_rules(
+    verify_vote_proposal_signature: bool,
+    decoupled_execution: bool,
+) -> suite::Callback {
+    Box::new(move || {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let server_key = x25519::PrivateKey::generate(&mut rng);
+        let client_key = x25519::PrivateKey::generate(&mut rng);
+        let server_keys = NoiseKeysConfig::new(server_key, client_key.public_key());
+        let client_keys = NoiseKeysConfig::new(client_key, server_keys.private_key.public_key());
+
+        let signer = ValidatorSigner::from_int(0);
+        let storage = test_utils::test_storage(&signer);
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        thread::spawn(move || {
+            remote_service::execute(
+                storage,
+                server_addr,
+                verify_vote_proposal_signature,
+                false,
+                NETWORK_TIMEOUT,
+                decoupled_execution,
+                Some(server_keys),
+            )
+        });
+
+        let safety_rules_manager =
+            SafetyRulesManager::new_process(server_addr, NETWORK_TIMEOUT, Some(client_keys));
+        let safety_rules: Box<dyn TSafetyRules + Send + Sync> = safety_rules_manager.client();
+        (
+            safety_rules,
+            signer,
+            if verify_vote_proposal_signature {
+                Some(Ed25519PrivateKey::generate_for_testing())
+            } else {
+                None
+            },
+        )
+    })
+}
@@ -4,7 +4,8 @@
 use crate::{test_utils, test_utils::make_timeout_cert, Error, SafetyRules, TSafetyRules};
 use consensus_types::{
     block::block_test_utils::random_payload,
-    common::Round,
+    common::{Payload, Round},
+    proof_of_store::{Batch, SignedBatchInfo},
     quorum_cert::QuorumCert,
     timeout::Timeout,
     timeout_2chain::{TwoChainTimeout, TwoChainTimeoutCertificate},
@@ -35,7 +36,14 @@ fn make_proposal_with_qc_and_proof(
     signer: &ValidatorSigner,
     exec_key: Option<&Ed25519PrivateKey>,
 ) -> MaybeSignedVoteProposal {
-    test_utils::make_proposal_with_qc_and_proof(vec![], round, proof, qc, signer, exec_key)
+    test_utils::make_proposal_with_qc_and_proof(
+        Payload::empty(false),
+        round,
+        proof,
+        qc,
+        signer,
+        exec_key,
+    )
 }
 
 fn make_proposal_with_parent(
@@ -45,7 +53,14 @@ fn make_proposal_with_parent(
     signer: &ValidatorSigner,
     exec_key: Option<&Ed25519PrivateKey>,
 ) -> MaybeSignedVoteProposal {
-    test_utils::make_proposal_with_parent(vec![], round, parent, committed, signer, exec_key)
+    test_utils::make_proposal_with_parent(
+        Payload::empty(false),
+        round,
+        parent,
+        committed,
+        signer,
+        exec_key,
+    )
 }
 
 pub type Callback = Box<
@@ -75,6 +90,7 @@ pub fn run_test_suite(safety_rules: &Callback, decoupled_execution: bool) {
     test_key_not_in_store(safety_rules);
     test_2chain_rules(safety_rules);
     test_2chain_timeout(safety_rules);
+    test_sign_batch_info(safety_rules);
     if decoupled_execution {
         test_sign_commit_vote(safety_rules);
     } else {
@@ -452,7 +468,7 @@ fn test_voting_bad_epoch(safety_rules: &Callback) {
 
     let a1 = test_utils::make_proposal_with_qc(round + 1, genesis_qc, &signer, key.as_ref());
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 3,
         &a1,
         None,
@@ -664,7 +680,7 @@ fn test_validator_not_in_set(safety_rules: &Callback) {
     next_epoch_state.verifier =
         ValidatorVerifier::new_single(rand_signer.author(), rand_signer.public_key());
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 2,
         &a1,
         Some(&a1),
@@ -711,7 +727,7 @@ fn test_reconcile_key(_safety_rules: &Callback) {
     next_epoch_state.epoch = 2;
     next_epoch_state.verifier = ValidatorVerifier::new_single(signer.author(), new_pub_key);
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 2,
         &a1,
         Some(&a1),
@@ -728,7 +744,7 @@ fn test_reconcile_key(_safety_rules: &Callback) {
     // Verification fails for proposal signed by the outdated key
     let outdated_signer = &signer;
     let a3 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 3,
         &a2,
         Some(&a2),
@@ -762,7 +778,7 @@ fn test_key_not_in_store(safety_rules: &Callback) {
     next_epoch_state.verifier =
         ValidatorVerifier::new_single(signer.author(), rand_signer.public_key());
     let a2 = test_utils::make_proposal_with_parent_and_overrides(
-        vec![],
+        Payload::empty(false),
         round + 2,
         &a1,
         Some(&a1),
@@ -967,6 +983,30 @@ fn test_2chain_timeout(constructor: &Callback) {
 }
 
 /// Test that we can succesfully sign a valid commit vote
+fn test_sign_batch_info(safety_rules: &Callback) {
+    let (mut safety_rules, signer, _key) = safety_rules();
+
+    let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+    let epoch = genesis_qc.certified_block().epoch();
+    safety_rules.initialize(&proof).unwrap();
+
+    let batch = Batch::new(signer.author(), epoch, 0, vec![]);
+    let signature = safety_rules.sign_batch_info(batch.info()).unwrap();
+    let verifier = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    SignedBatchInfo::new(batch.info().clone(), signer.author(), signature)
+        .verify(&verifier)
+        .unwrap();
+
+    // Verify batches from other epochs are not signed
+    let other_batch = Batch::new(signer.author(), epoch + 1, 1, vec![]);
+    assert_eq!(
+        safety_rules
+            .sign_batch_info(other_batch.info())
+            .unwrap_err(),
+        Error::IncorrectEpoch(epoch + 1, epoch)
+    );
+}
+
 fn test_sign_commit_vote(constructor: &Callback) {
     // we construct a chain of proposals
     // genesis -- a1 -- a2 -- a3
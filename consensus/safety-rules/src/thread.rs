@@ -45,6 +45,7 @@ impl ThreadService {
                 export_consensus_key,
                 timeout,
                 decoupled_execution,
+                None,
             )
         });
 
@@ -2,10 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_config::{
-    config::{NodeConfig, PersistableConfig, RemoteService, SafetyRulesService},
+    config::{NodeConfig, NoiseKeysConfig, PersistableConfig, RemoteService, SafetyRulesService},
     utils,
 };
+use diem_crypto::{x25519, Uniform};
 use diem_types::validator_signer::ValidatorSigner;
+use rand::{rngs::StdRng, SeedableRng};
 use safety_rules::{test_utils, SafetyRulesManager};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
@@ -22,11 +24,27 @@ fn test_consensus_state() {
 
     let server_port = utils::get_available_port();
     let server_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port).into();
-    config.service = SafetyRulesService::Process(RemoteService { server_address });
+
+    // The safety rules process and consensus only trust each other's key
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let server_key = x25519::PrivateKey::generate(&mut rng);
+    let client_key = x25519::PrivateKey::generate(&mut rng);
+    let server_keys = NoiseKeysConfig::new(server_key, client_key.public_key());
+    let client_keys = NoiseKeysConfig::new(client_key, server_keys.private_key.public_key());
+
+    let mut server_config = config.clone();
+    server_config.service = SafetyRulesService::Process(RemoteService {
+        server_address: server_address.clone(),
+        noise: Some(server_keys),
+    });
+    config.service = SafetyRulesService::Process(RemoteService {
+        server_address,
+        noise: Some(client_keys),
+    });
 
     let config_path = diem_temppath::TempPath::new();
     config_path.create_as_file().unwrap();
-    config.save_config(config_path.path()).unwrap();
+    server_config.save_config(config_path.path()).unwrap();
 
     let mut command = std::process::Command::new(BINARY);
     command
@@ -4,18 +4,19 @@
 use crate::{
     block_storage::{block_store::BlockStore, BlockReader},
     persistent_liveness_storage::{LedgerRecoveryData, RecoveryData, RootMetadata},
+    quorum_store::BatchStore,
     state_computer::ExecutionProxy,
     test_utils::{EmptyStorage, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
 use consensus_types::{block::Block, quorum_cert::QuorumCert};
-use diem_config::config::NodeConfig;
+use diem_config::config::{ConsensusConfig, NodeConfig};
 use diem_crypto::{ed25519::Ed25519PrivateKey, Uniform};
 use diem_types::validator_signer::ValidatorSigner;
 use execution_correctness::{ExecutionCorrectness, ExecutionCorrectnessManager};
 use executor_test_helpers::start_storage_service;
 use executor_types::ExecutedTrees;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use storage_interface::DbReader;
 #[allow(clippy::needless_borrow)]
 fn get_initial_data_and_qc(db: &dyn DbReader) -> (RecoveryData, QuorumCert) {
@@ -69,6 +70,11 @@ fn build_inserter(
     let state_computer = Arc::new(ExecutionProxy::new(
         lec_client,
         Box::new(consensus_notifier),
+        Arc::new(BatchStore::new_in_memory(
+            ConsensusConfig::default().quorum_store_max_batches_per_author,
+            Duration::from_millis(ConsensusConfig::default().quorum_store_batch_expiry_ms),
+        )),
+        None,
     ));
 
     TreeInserter::new_with_store(
@@ -7,6 +7,7 @@ use crate::{
     network::NetworkSender,
     network_interface::ConsensusMsg,
     persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
+    quorum_store::{fetch_missing_batches, BatchStore},
     state_replication::StateComputer,
 };
 use anyhow::{bail, format_err};
@@ -159,6 +160,7 @@ impl BlockStore {
         while let Some(block) = pending.pop() {
             let block_qc = block.quorum_cert().clone();
             self.insert_single_quorum_cert(block_qc)?;
+            retriever.fetch_missing_batches(&block).await?;
             self.execute_and_insert_block(block)?;
         }
         self.insert_single_quorum_cert(qc)
@@ -255,6 +257,11 @@ impl BlockStore {
             assert_eq!(block.id(), quorum_certs[i].certified_block().id());
         }
 
+        // The blocks above the commit root are re-executed when the tree is rebuilt.
+        for block in blocks.iter().take(num_blocks as usize - 1) {
+            retriever.fetch_missing_batches(block).await?;
+        }
+
         // If a node restarts in the middle of state synchronization, it is going to try to catch up
         // to the stored quorum certs as the new root.
         storage.save_tree(blocks.clone(), quorum_certs.clone())?;
@@ -275,16 +282,28 @@ impl BlockStore {
 pub struct BlockRetriever {
     network: NetworkSender,
     preferred_peer: Author,
+    batch_store: Arc<BatchStore>,
 }
 
 impl BlockRetriever {
-    pub fn new(network: NetworkSender, preferred_peer: Author) -> Self {
+    pub fn new(
+        network: NetworkSender,
+        preferred_peer: Author,
+        batch_store: Arc<BatchStore>,
+    ) -> Self {
         Self {
             network,
             preferred_peer,
+            batch_store,
         }
     }
 
+    /// Retrieve the batches referred by the payload of the block that are missing locally, so
+    /// that the block can be executed.
+    async fn fetch_missing_batches(&mut self, block: &Block) -> anyhow::Result<()> {
+        fetch_missing_batches(&mut self.network, &self.batch_store, block.payload()).await
+    }
+
     /// Retrieve n blocks for given block_id from peers
     ///
     /// Returns Result with Vec that has a guaranteed size of num_blocks
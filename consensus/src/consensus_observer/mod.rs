@@ -0,0 +1,20 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consensus observer: validators publish the blocks they commit to the subscribed fullnodes,
+//! which execute and commit them without waiting for state sync to poll them. Validator
+//! fullnodes can both observe their validator and publish to the public fullnodes.
+//!
+//! Without decoupled execution, the proof ordering the blocks also commits them, so a validator
+//! publishes the `OrderedBlock` right before committing it locally and the `CommitDecision`
+//! right after: observers trail their validator by the local commit, not by a full round.
+
+pub mod network;
+mod observer;
+mod publisher;
+
+#[cfg(test)]
+mod publisher_test;
+
+pub use observer::ConsensusObserver;
+pub use publisher::ConsensusPublisher;
@@ -0,0 +1,141 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interface between the consensus observer and the Network layer.
+
+use crate::counters;
+use channel::message_queues::QueueStyle;
+use consensus_types::block::Block;
+use diem_metrics::IntCounterVec;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction, PeerId};
+use network::{
+    error::NetworkError,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::{NetworkEvents, NetworkSender, NewNetworkSender},
+    ProtocolId,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Messages exchanged between the publishers (validators and validator fullnodes) and the
+/// subscribed observers (fullnodes).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ObserverMessage {
+    /// Sent by an observer to start receiving the blocks committed by the publisher.
+    Subscribe,
+    /// Sent by an observer to stop receiving them.
+    Unsubscribe,
+    /// Blocks ordered by consensus, to be executed by the observers.
+    OrderedBlock(Box<OrderedBlock>),
+    /// LedgerInfo certifying the commit of previously ordered blocks.
+    CommitDecision(Box<LedgerInfoWithSignatures>),
+}
+
+/// A chain of ordered blocks, with the transactions their payloads resolve to, and the LedgerInfo
+/// ordering the last of them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OrderedBlock {
+    blocks: Vec<(Block, Vec<SignedTransaction>)>,
+    ordered_proof: LedgerInfoWithSignatures,
+}
+
+impl Display for OrderedBlock {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "OrderedBlock: [num_blocks: {}, {}]",
+            self.blocks.len(),
+            self.ordered_proof.ledger_info().commit_info()
+        )
+    }
+}
+
+impl OrderedBlock {
+    pub fn new(
+        blocks: Vec<(Block, Vec<SignedTransaction>)>,
+        ordered_proof: LedgerInfoWithSignatures,
+    ) -> Self {
+        Self {
+            blocks,
+            ordered_proof,
+        }
+    }
+
+    pub fn blocks(&self) -> &[(Block, Vec<SignedTransaction>)] {
+        &self.blocks
+    }
+
+    pub fn ordered_proof(&self) -> &LedgerInfoWithSignatures {
+        &self.ordered_proof
+    }
+
+    pub fn into_parts(
+        self,
+    ) -> (
+        Vec<(Block, Vec<SignedTransaction>)>,
+        LedgerInfoWithSignatures,
+    ) {
+        (self.blocks, self.ordered_proof)
+    }
+}
+
+/// The interface from the Network layer to the consensus observer.
+pub type ObserverNetworkEvents = NetworkEvents<ObserverMessage>;
+
+/// The interface from the consensus observer to the Network layer.
+#[derive(Clone)]
+pub struct ObserverNetworkSender {
+    inner: NetworkSender<ObserverMessage>,
+}
+
+impl NewNetworkSender for ObserverNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl ObserverNetworkSender {
+    pub fn send_to(
+        &mut self,
+        recipient: PeerId,
+        message: ObserverMessage,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send_to(recipient, ProtocolId::ConsensusObserverDirectSend, message)
+    }
+
+    pub fn send_to_many(
+        &mut self,
+        recipients: impl Iterator<Item = PeerId>,
+        message: ObserverMessage,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send_to_many(recipients, ProtocolId::ConsensusObserverDirectSend, message)
+    }
+}
+
+/// Configuration for the network endpoints to support the consensus observer.
+pub fn network_endpoint_config(
+    channel_size: usize,
+) -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![],
+        vec![ProtocolId::ConsensusObserverDirectSend],
+        // Blocks must be processed in order, the observer falls back to state sync when
+        // messages are dropped
+        QueueStyle::FIFO,
+        channel_size,
+        Some(&counters::PENDING_OBSERVER_NETWORK_EVENTS),
+    )
+}
@@ -0,0 +1,428 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensus_observer::{
+        network::{ObserverMessage, ObserverNetworkEvents, ObserverNetworkSender, OrderedBlock},
+        publisher::ConsensusPublisher,
+    },
+    counters,
+};
+use anyhow::{bail, ensure, format_err};
+use consensus_notifications::ConsensusNotificationSender;
+use consensus_types::executed_block::ExecutedBlock;
+use diem_config::{
+    config::{ConsensusObserverConfig, PeerNetworkId, RoleType},
+    network_id::NodeNetworkId,
+};
+use diem_crypto::HashValue;
+use diem_logger::prelude::*;
+use diem_metrics::monitor;
+use diem_types::{
+    block_info::BlockInfo, epoch_change::Verifier, epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction,
+};
+use executor_types::BlockExecutor;
+use futures::{stream::select_all, StreamExt};
+use network::{protocols::network::Event, transport::ConnectionMetadata};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use storage_interface::DbReader;
+
+#[cfg(test)]
+#[path = "observer_test.rs"]
+mod observer_test;
+
+/// Ordered blocks executed by the observer, waiting for their commit decision.
+struct PendingBlocks {
+    // The executed blocks with the transactions their payloads resolve to.
+    blocks: Vec<(ExecutedBlock, Vec<SignedTransaction>)>,
+    ordered_proof: LedgerInfoWithSignatures,
+}
+
+/// ConsensusObserver lets a fullnode follow consensus without participating in it: it subscribes
+/// to an upstream peer publishing the blocks ordered by consensus, executes them as they arrive
+/// and commits them once the commit decision is received. Whenever the observer can't make
+/// progress from the published blocks (e.g., messages were dropped), it falls back to state sync
+/// to catch up with the last verified commit. A subscription that delivers nothing within the
+/// subscription timeout is replaced by one to another upstream peer, while state sync resumes
+/// polling on its own if the observer stops committing altogether.
+pub struct ConsensusObserver {
+    role: RoleType,
+    max_pending_blocks: usize,
+    progress_check_interval: Duration,
+    subscription_timeout: Duration,
+    network_senders: HashMap<NodeNetworkId, ObserverNetworkSender>,
+    // Relays the verified messages to the downstream subscribers (validator fullnodes).
+    publisher: Option<Arc<ConsensusPublisher>>,
+    state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    block_executor: Box<dyn BlockExecutor>,
+    db: Arc<dyn DbReader>,
+    epoch_state: EpochState,
+    // The upstream peers that can be subscribed to.
+    upstream_peers: HashSet<PeerNetworkId>,
+    subscription: Option<PeerNetworkId>,
+    // The last time the subscription was created or delivered a message processed successfully.
+    last_subscription_progress: Instant,
+    pending_blocks: Vec<PendingBlocks>,
+}
+
+impl ConsensusObserver {
+    pub fn new(
+        role: RoleType,
+        config: &ConsensusObserverConfig,
+        network_senders: HashMap<NodeNetworkId, ObserverNetworkSender>,
+        publisher: Option<Arc<ConsensusPublisher>>,
+        state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+        block_executor: Box<dyn BlockExecutor>,
+        db: Arc<dyn DbReader>,
+    ) -> Self {
+        let epoch_state = Self::load_epoch_state(db.as_ref());
+        Self {
+            role,
+            max_pending_blocks: config.max_pending_blocks,
+            progress_check_interval: Duration::from_millis(config.progress_check_interval_ms),
+            subscription_timeout: Duration::from_millis(config.subscription_timeout_ms),
+            network_senders,
+            publisher,
+            state_sync_notifier,
+            block_executor,
+            db,
+            epoch_state,
+            upstream_peers: HashSet::new(),
+            subscription: None,
+            last_subscription_progress: Instant::now(),
+            pending_blocks: vec![],
+        }
+    }
+
+    fn load_epoch_state(db: &dyn DbReader) -> EpochState {
+        db.get_startup_info()
+            .expect("Failed to read the startup info")
+            .expect("DiemDB must be bootstrapped")
+            .get_epoch_state()
+            .clone()
+    }
+
+    fn last_block_id(&self) -> anyhow::Result<HashValue> {
+        match self.pending_blocks.last() {
+            Some(pending) => Ok(pending.blocks.last().expect("must not be empty").0.id()),
+            None => Ok(self.block_executor.committed_block_id()?),
+        }
+    }
+
+    fn is_committed(&self, block_info: &BlockInfo) -> anyhow::Result<bool> {
+        let committed = self.db.get_latest_ledger_info()?;
+        let committed_info = committed.ledger_info().commit_info();
+        Ok((block_info.epoch(), block_info.round())
+            <= (committed_info.epoch(), committed_info.round()))
+    }
+
+    fn send(&self, peer: &PeerNetworkId, message: ObserverMessage) {
+        let PeerNetworkId(network_id, peer_id) = peer;
+        if let Some(sender) = self.network_senders.get(network_id) {
+            if let Err(e) = sender.clone().send_to(*peer_id, message) {
+                warn!(
+                    remote_peer = peer_id,
+                    error = ?e,
+                    "[ConsensusObserver] Failed to send message"
+                );
+            }
+        }
+    }
+
+    /// Subscribes to one of the connected upstream peers if there is no subscription.
+    fn subscribe(&mut self) {
+        self.subscribe_excluding(None)
+    }
+
+    /// Subscribes to one of the connected upstream peers other than `excluded`, or to `excluded`
+    /// if it's the only one, if there is no subscription.
+    fn subscribe_excluding(&mut self, excluded: Option<&PeerNetworkId>) {
+        if self.subscription.is_some() {
+            return;
+        }
+        let peer = self
+            .upstream_peers
+            .iter()
+            .find(|peer| Some(*peer) != excluded)
+            .or_else(|| excluded.filter(|peer| self.upstream_peers.contains(*peer)))
+            .cloned();
+        if let Some(peer) = peer {
+            info!(
+                remote_peer = peer.1,
+                "[ConsensusObserver] Subscribing to upstream peer"
+            );
+            self.send(&peer, ObserverMessage::Subscribe);
+            self.subscription = Some(peer);
+            self.last_subscription_progress = Instant::now();
+        }
+    }
+
+    /// Replaces the subscription if it delivered nothing within the subscription timeout.
+    fn check_progress(&mut self, now: Instant) {
+        let peer = match &self.subscription {
+            Some(peer) => peer.clone(),
+            None => {
+                self.subscribe();
+                return;
+            }
+        };
+        if now.duration_since(self.last_subscription_progress) < self.subscription_timeout {
+            return;
+        }
+        warn!(
+            remote_peer = peer.1,
+            "[ConsensusObserver] Subscription timed out, resubscribing"
+        );
+        counters::OBSERVER_SUBSCRIPTION_TIMEOUTS.inc();
+        self.send(&peer, ObserverMessage::Unsubscribe);
+        self.subscription = None;
+        self.subscribe_excluding(Some(&peer));
+    }
+
+    fn process_new_peer(&mut self, network_id: NodeNetworkId, metadata: ConnectionMetadata) {
+        if network_id
+            .network_id()
+            .upstream_roles(&self.role)
+            .contains(&metadata.role)
+        {
+            self.upstream_peers
+                .insert(PeerNetworkId(network_id, metadata.remote_peer_id));
+            self.subscribe();
+        }
+    }
+
+    fn process_lost_peer(&mut self, network_id: NodeNetworkId, metadata: ConnectionMetadata) {
+        let peer = PeerNetworkId(network_id, metadata.remote_peer_id);
+        self.upstream_peers.remove(&peer);
+        if self.subscription.as_ref() == Some(&peer) {
+            self.subscription = None;
+            self.subscribe();
+        }
+    }
+
+    /// Verifies the ordered blocks and executes them on top of the pending ones.
+    fn process_ordered_block(&mut self, ordered_block: &OrderedBlock) -> anyhow::Result<()> {
+        let ordered_proof = ordered_block.ordered_proof();
+        let commit_info = ordered_proof.ledger_info().commit_info();
+        if self.is_committed(commit_info)?
+            || self.pending_blocks.iter().any(|pending| {
+                pending.ordered_proof.ledger_info().commit_info().round() >= commit_info.round()
+                    && pending.ordered_proof.ledger_info().epoch() == commit_info.epoch()
+            })
+        {
+            // Already processed.
+            return Ok(());
+        }
+        self.epoch_state.verify(ordered_proof)?;
+        let blocks = ordered_block.blocks();
+        ensure!(!blocks.is_empty(), "Empty {}", ordered_block);
+        for pair in blocks.windows(2) {
+            ensure!(
+                pair[1].0.parent_id() == pair[0].0.id(),
+                "Blocks of {} don't form a chain",
+                ordered_block
+            );
+        }
+        ensure!(
+            blocks.last().expect("must not be empty").0.id() == commit_info.id(),
+            "Last block of {} is not certified by the ordered proof",
+            ordered_block
+        );
+        if self.pending_blocks.len() >= self.max_pending_blocks {
+            bail!("Too many pending blocks");
+        }
+        let mut parent_id = self.last_block_id()?;
+        ensure!(
+            blocks[0].0.parent_id() == parent_id,
+            "{} doesn't extend the last known block {}",
+            ordered_block,
+            parent_id
+        );
+
+        let mut executed_blocks = vec![];
+        for (block, txns) in blocks {
+            let result = monitor!(
+                "observer_execute_block",
+                self.block_executor.execute_block(
+                    (block.id(), block.transactions_to_execute(txns.clone())),
+                    parent_id,
+                )?
+            );
+            parent_id = block.id();
+            executed_blocks.push((ExecutedBlock::new(block.clone(), result), txns.clone()));
+        }
+        self.pending_blocks.push(PendingBlocks {
+            blocks: executed_blocks,
+            ordered_proof: ordered_proof.clone(),
+        });
+        Ok(())
+    }
+
+    /// Commits the pending blocks certified by the commit decision.
+    async fn process_commit_decision(
+        &mut self,
+        commit_proof: &LedgerInfoWithSignatures,
+    ) -> anyhow::Result<()> {
+        let commit_info = commit_proof.ledger_info().commit_info();
+        if self.is_committed(commit_info)? {
+            // Already committed.
+            return Ok(());
+        }
+        self.epoch_state.verify(commit_proof)?;
+        let index = self
+            .pending_blocks
+            .iter()
+            .position(|pending| {
+                pending.blocks.last().expect("must not be empty").0.id() == commit_info.id()
+            })
+            .ok_or_else(|| format_err!("Unknown block committed by {}", commit_info))?;
+        let committed: Vec<_> = self.pending_blocks.drain(..=index).collect();
+        let (last_block, _) = committed
+            .last()
+            .and_then(|pending| pending.blocks.last())
+            .expect("must not be empty");
+        ensure!(
+            last_block.compute_result().root_hash() == commit_info.executed_state_id()
+                && last_block.compute_result().version() == commit_info.version(),
+            "Executed state of block {} doesn't match {}",
+            last_block.id(),
+            commit_info
+        );
+
+        let mut block_ids = vec![];
+        let mut txns = vec![];
+        let mut reconfig_events = vec![];
+        for pending in committed {
+            for (block, payload_txns) in pending.blocks {
+                block_ids.push(block.id());
+                txns.extend(block.transactions_to_commit(payload_txns));
+                reconfig_events.extend(block.reconfig_event());
+            }
+        }
+        let num_blocks = block_ids.len();
+        monitor!(
+            "observer_commit_blocks",
+            self.block_executor
+                .commit_blocks(block_ids, commit_proof.clone())?
+        );
+        counters::OBSERVER_COMMITTED_BLOCKS.inc_by(num_blocks as u64);
+        if let Err(e) = self
+            .state_sync_notifier
+            .notify_new_commit(txns, reconfig_events)
+            .await
+        {
+            error!(error = ?e, "[ConsensusObserver] Failed to notify state synchronizer");
+        }
+
+        if let Some(epoch_state) = commit_proof.ledger_info().next_epoch_state() {
+            info!(
+                epoch = epoch_state.epoch,
+                "[ConsensusObserver] Entering new epoch"
+            );
+            self.epoch_state = epoch_state.clone();
+            // The blocks ordered in the previous epoch after the reconfiguration are dropped.
+            self.pending_blocks.clear();
+        }
+        Ok(())
+    }
+
+    /// Catches up to the target with state sync, dropping the pending blocks.
+    async fn fall_back_to_state_sync(&mut self, target: LedgerInfoWithSignatures) {
+        counters::OBSERVER_STATE_SYNC_FALLBACKS.inc();
+        self.pending_blocks.clear();
+        if !self
+            .is_committed(target.ledger_info().commit_info())
+            .unwrap_or(false)
+        {
+            if let Err(e) = self.state_sync_notifier.sync_to_target(target).await {
+                error!(error = ?e, "[ConsensusObserver] Failed to sync to target");
+            }
+        }
+        if let Err(e) = self.block_executor.reset() {
+            error!(error = ?e, "[ConsensusObserver] Failed to reset the executor");
+        }
+        self.epoch_state = Self::load_epoch_state(self.db.as_ref());
+    }
+
+    async fn process_message(&mut self, peer: PeerNetworkId, message: ObserverMessage) {
+        if self.subscription.as_ref() != Some(&peer) {
+            return;
+        }
+        let (result, target) = match &message {
+            ObserverMessage::OrderedBlock(ordered_block) => (
+                self.process_ordered_block(ordered_block),
+                ordered_block.ordered_proof().clone(),
+            ),
+            ObserverMessage::CommitDecision(commit_proof) => (
+                self.process_commit_decision(commit_proof).await,
+                commit_proof.as_ref().clone(),
+            ),
+            _ => return,
+        };
+        match result {
+            Ok(()) => {
+                self.last_subscription_progress = Instant::now();
+                if let Some(publisher) = &self.publisher {
+                    publisher.publish(message);
+                }
+            }
+            Err(e) => {
+                warn!(
+                    remote_peer = peer.1,
+                    error = ?e,
+                    "[ConsensusObserver] Failed to process message, falling back to state sync"
+                );
+                // Only sync to targets certified by the current epoch.
+                if self.epoch_state.verify(&target).is_ok() {
+                    self.fall_back_to_state_sync(target).await;
+                }
+            }
+        }
+    }
+
+    pub async fn start(mut self, network_events: Vec<(NodeNetworkId, ObserverNetworkEvents)>) {
+        let events: Vec<_> = network_events
+            .into_iter()
+            .map(|(network_id, events)| events.map(move |e| (network_id.clone(), e)))
+            .collect();
+        let mut network_events = select_all(events);
+        let mut progress_check_interval = tokio::time::interval(self.progress_check_interval);
+        loop {
+            let (network_id, event) = tokio::select! {
+                event = network_events.next() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                _ = progress_check_interval.tick() => {
+                    self.check_progress(Instant::now());
+                    continue;
+                }
+            };
+            let event = match &self.publisher {
+                Some(publisher) => match publisher.process_network_event(network_id, event) {
+                    Some(event) => event,
+                    None => continue,
+                },
+                None => (network_id, event),
+            };
+            match event {
+                (network_id, Event::NewPeer(metadata)) => {
+                    self.process_new_peer(network_id, metadata)
+                }
+                (network_id, Event::LostPeer(metadata)) => {
+                    self.process_lost_peer(network_id, metadata)
+                }
+                (network_id, Event::Message(peer_id, message)) => {
+                    self.process_message(PeerNetworkId(network_id, peer_id), message)
+                        .await
+                }
+                (_, Event::RpcRequest(..)) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::ConsensusObserver;
+use crate::consensus_observer::network::{ObserverMessage, ObserverNetworkSender, OrderedBlock};
+use channel::{diem_channel, message_queues::QueueStyle};
+use consensus_notifications::new_consensus_notifier_listener_pair;
+use diem_config::{
+    config::{ConsensusObserverConfig, PeerNetworkId, PeerRole, RoleType},
+    network_id::{NetworkId, NodeNetworkId},
+};
+use diem_crypto::HashValue;
+use diem_temppath::TempPath;
+use diem_types::{
+    block_info::BlockInfo,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    transaction::{Transaction, WriteSetPayload},
+    PeerId,
+};
+use executor_test_helpers::integration_test_impl::create_db_and_executor;
+use futures::{executor::block_on, StreamExt};
+use netcore::transport::ConnectionOrigin;
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::network::NewNetworkSender,
+    transport::ConnectionMetadata,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+use storage_interface::DbReader;
+
+const SUBSCRIPTION_TIMEOUT_MS: u64 = 1_000;
+
+/// Creates an observer on top of a genesis DB, with the receiver of the messages it sends.
+fn create_observer() -> (
+    ConsensusObserver,
+    diem_channel::Receiver<(PeerId, network::ProtocolId), PeerManagerRequest>,
+    NodeNetworkId,
+    TempPath,
+) {
+    let config = ConsensusObserverConfig {
+        subscription_timeout_ms: SUBSCRIPTION_TIMEOUT_MS,
+        ..ConsensusObserverConfig::default()
+    };
+    let network_id = NodeNetworkId::new(NetworkId::vfn_network(), 0);
+    let (network_reqs_tx, network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let sender = ObserverNetworkSender::new(
+        PeerManagerRequestSender::new(network_reqs_tx),
+        ConnectionRequestSender::new(connection_reqs_tx),
+    );
+    let mut network_senders = HashMap::new();
+    network_senders.insert(network_id.clone(), sender);
+
+    let (genesis, _) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let path = TempPath::new();
+    path.create_as_dir().unwrap();
+    let (_, db_rw, executor, _) = create_db_and_executor(path.path(), &genesis_txn);
+    let (state_sync_notifier, _) = new_consensus_notifier_listener_pair(1_000);
+
+    let observer = ConsensusObserver::new(
+        RoleType::FullNode,
+        &config,
+        network_senders,
+        None,
+        Box::new(state_sync_notifier),
+        Box::new(executor),
+        db_rw.reader,
+    );
+    (observer, network_reqs_rx, network_id, path)
+}
+
+fn connect_validator(observer: &mut ConsensusObserver, network_id: &NodeNetworkId) -> PeerId {
+    let peer_id = PeerId::random();
+    observer.process_new_peer(
+        network_id.clone(),
+        ConnectionMetadata::mock_with_role_and_origin(
+            peer_id,
+            PeerRole::Validator,
+            ConnectionOrigin::Outbound,
+        ),
+    );
+    peer_id
+}
+
+fn next_message(
+    network_reqs_rx: &mut diem_channel::Receiver<(PeerId, network::ProtocolId), PeerManagerRequest>,
+) -> (PeerId, ObserverMessage) {
+    match block_on(network_reqs_rx.next()) {
+        Some(PeerManagerRequest::SendDirectSend(peer_id, msg)) => {
+            (peer_id, msg.protocol_id.from_bytes(&msg.mdata).unwrap())
+        }
+        request => panic!("Unexpected request {:?}", request),
+    }
+}
+
+#[test]
+fn test_subscription_timeout() {
+    let (mut observer, mut network_reqs_rx, network_id, _path) = create_observer();
+
+    // Subscribes to the first upstream peer
+    let first_peer = connect_validator(&mut observer, &network_id);
+    let second_peer = connect_validator(&mut observer, &network_id);
+    let (peer_id, message) = next_message(&mut network_reqs_rx);
+    assert_eq!(peer_id, first_peer);
+    assert!(matches!(message, ObserverMessage::Subscribe));
+    assert_eq!(
+        observer.subscription,
+        Some(PeerNetworkId(network_id.clone(), first_peer))
+    );
+
+    // The subscription is kept within the timeout
+    let now = Instant::now();
+    observer.check_progress(now);
+    assert_eq!(
+        observer.subscription,
+        Some(PeerNetworkId(network_id.clone(), first_peer))
+    );
+
+    // Then replaced by one to the other peer
+    observer.check_progress(now + Duration::from_millis(SUBSCRIPTION_TIMEOUT_MS));
+    let (peer_id, message) = next_message(&mut network_reqs_rx);
+    assert_eq!(peer_id, first_peer);
+    assert!(matches!(message, ObserverMessage::Unsubscribe));
+    let (peer_id, message) = next_message(&mut network_reqs_rx);
+    assert_eq!(peer_id, second_peer);
+    assert!(matches!(message, ObserverMessage::Subscribe));
+
+    // Losing the peer subscribes back to the first one
+    observer.process_lost_peer(
+        network_id.clone(),
+        ConnectionMetadata::mock_with_role_and_origin(
+            second_peer,
+            PeerRole::Validator,
+            ConnectionOrigin::Outbound,
+        ),
+    );
+    let (peer_id, message) = next_message(&mut network_reqs_rx);
+    assert_eq!(peer_id, first_peer);
+    assert!(matches!(message, ObserverMessage::Subscribe));
+
+    // The only upstream peer is subscribed to again after a timeout
+    observer.check_progress(Instant::now() + Duration::from_millis(SUBSCRIPTION_TIMEOUT_MS));
+    let (_, message) = next_message(&mut network_reqs_rx);
+    assert!(matches!(message, ObserverMessage::Unsubscribe));
+    let (peer_id, message) = next_message(&mut network_reqs_rx);
+    assert_eq!(peer_id, first_peer);
+    assert!(matches!(message, ObserverMessage::Subscribe));
+}
+
+#[test]
+fn test_downstream_peers_are_not_subscribed() {
+    let (mut observer, _network_reqs_rx, _, _path) = create_observer();
+
+    let network_id = NodeNetworkId::new(NetworkId::Public, 0);
+    observer.process_new_peer(
+        network_id,
+        ConnectionMetadata::mock_with_role_and_origin(
+            PeerId::random(),
+            PeerRole::Unknown,
+            ConnectionOrigin::Inbound,
+        ),
+    );
+    assert!(observer.upstream_peers.is_empty());
+    assert!(observer.subscription.is_none());
+}
+
+#[test]
+fn test_ordered_block_verification() {
+    let (mut observer, _network_reqs_rx, _, _path) = create_observer();
+
+    // Blocks ordered by the committed ledger info are skipped
+    let committed = observer.db.get_latest_ledger_info().unwrap();
+    observer
+        .process_ordered_block(&OrderedBlock::new(vec![], committed))
+        .unwrap();
+    assert!(observer.pending_blocks.is_empty());
+
+    // Blocks ordered by a ledger info without the signatures of the epoch are rejected
+    let epoch = observer.epoch_state.epoch;
+    let unsigned = LedgerInfoWithSignatures::new(
+        LedgerInfo::new(
+            BlockInfo::new(
+                epoch,
+                1, /* round */
+                HashValue::random(),
+                HashValue::zero(),
+                1, /* version */
+                0, /* timestamp_usecs */
+                None,
+            ),
+            HashValue::zero(),
+        ),
+        BTreeMap::new(),
+    );
+    observer
+        .process_ordered_block(&OrderedBlock::new(vec![], unsigned))
+        .unwrap_err();
+    assert!(observer.pending_blocks.is_empty());
+}
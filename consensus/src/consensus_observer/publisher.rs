@@ -0,0 +1,128 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consensus_observer::network::{ObserverMessage, ObserverNetworkEvents, ObserverNetworkSender},
+    counters,
+};
+use diem_config::{
+    config::{ConsensusObserverConfig, PeerNetworkId},
+    network_id::NodeNetworkId,
+};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use futures::{stream::select_all, StreamExt};
+use network::protocols::network::Event;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// ConsensusPublisher sends the blocks committed locally to the subscribed fullnodes. Validators
+/// publish the blocks they commit, validator fullnodes running an observer relay the ones they
+/// receive.
+pub struct ConsensusPublisher {
+    network_senders: HashMap<NodeNetworkId, ObserverNetworkSender>,
+    subscribers: Mutex<HashSet<PeerNetworkId>>,
+    max_subscribers: usize,
+}
+
+impl ConsensusPublisher {
+    pub fn new(
+        config: &ConsensusObserverConfig,
+        network_senders: HashMap<NodeNetworkId, ObserverNetworkSender>,
+    ) -> Self {
+        Self {
+            network_senders,
+            subscribers: Mutex::new(HashSet::new()),
+            max_subscribers: config.max_subscribers,
+        }
+    }
+
+    /// Sends the message to every subscriber, failures are only logged: a subscriber that misses
+    /// messages catches up through state sync.
+    pub fn publish(&self, message: ObserverMessage) {
+        let mut peers_per_network: HashMap<NodeNetworkId, Vec<_>> = HashMap::new();
+        for PeerNetworkId(network_id, peer_id) in self.subscribers.lock().iter() {
+            peers_per_network
+                .entry(network_id.clone())
+                .or_default()
+                .push(*peer_id);
+        }
+        for (network_id, peers) in peers_per_network {
+            if let Some(sender) = self.network_senders.get(&network_id) {
+                if let Err(e) = sender
+                    .clone()
+                    .send_to_many(peers.into_iter(), message.clone())
+                {
+                    warn!(
+                        network_id = network_id,
+                        error = ?e,
+                        "[ConsensusPublisher] Failed to publish"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Handles the subscriptions, returns the event if it's meant for an observer.
+    pub fn process_network_event(
+        &self,
+        network_id: NodeNetworkId,
+        event: Event<ObserverMessage>,
+    ) -> Option<(NodeNetworkId, Event<ObserverMessage>)> {
+        match event {
+            Event::Message(peer_id, ObserverMessage::Subscribe) => {
+                let mut subscribers = self.subscribers.lock();
+                let peer = PeerNetworkId(network_id, peer_id);
+                if subscribers.len() >= self.max_subscribers && !subscribers.contains(&peer) {
+                    warn!(
+                        remote_peer = peer_id,
+                        "[ConsensusPublisher] Too many subscribers, ignore subscription"
+                    );
+                } else if subscribers.insert(peer) {
+                    info!(remote_peer = peer_id, "[ConsensusPublisher] New subscriber");
+                }
+                counters::OBSERVER_SUBSCRIBERS.set(subscribers.len() as i64);
+                None
+            }
+            Event::Message(peer_id, ObserverMessage::Unsubscribe) => {
+                self.remove_subscriber(PeerNetworkId(network_id, peer_id));
+                None
+            }
+            Event::LostPeer(metadata) => {
+                self.remove_subscriber(PeerNetworkId(network_id.clone(), metadata.remote_peer_id));
+                Some((network_id, Event::LostPeer(metadata)))
+            }
+            event => Some((network_id, event)),
+        }
+    }
+
+    fn remove_subscriber(&self, peer: PeerNetworkId) {
+        let mut subscribers = self.subscribers.lock();
+        subscribers.remove(&peer);
+        counters::OBSERVER_SUBSCRIBERS.set(subscribers.len() as i64);
+    }
+
+    /// Handles the subscriptions of a node that only publishes (i.e., a validator).
+    pub async fn start(
+        self: Arc<Self>,
+        network_events: Vec<(NodeNetworkId, ObserverNetworkEvents)>,
+    ) {
+        let events: Vec<_> = network_events
+            .into_iter()
+            .map(|(network_id, events)| events.map(move |e| (network_id.clone(), e)))
+            .collect();
+        let mut network_events = select_all(events);
+        while let Some((network_id, event)) = network_events.next().await {
+            if let Some((_, Event::Message(peer_id, message))) =
+                self.process_network_event(network_id, event)
+            {
+                debug!(
+                    remote_peer = peer_id,
+                    "[ConsensusPublisher] Unexpected message {:?}", message
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::consensus_observer::{
+    network::{ObserverMessage, ObserverNetworkSender},
+    ConsensusPublisher,
+};
+use channel::{diem_channel, message_queues::QueueStyle};
+use diem_config::{
+    config::ConsensusObserverConfig,
+    network_id::{NetworkId, NodeNetworkId},
+};
+use diem_crypto::HashValue;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, on_chain_config::ValidatorSet, PeerId};
+use futures::{executor::block_on, StreamExt};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::network::{Event, NewNetworkSender},
+};
+use std::collections::HashMap;
+
+fn commit_decision() -> ObserverMessage {
+    ObserverMessage::CommitDecision(Box::new(LedgerInfoWithSignatures::genesis(
+        HashValue::zero(),
+        ValidatorSet::empty(),
+    )))
+}
+
+#[test]
+fn test_subscriptions() {
+    let config = ConsensusObserverConfig {
+        max_subscribers: 2,
+        ..ConsensusObserverConfig::default()
+    };
+    let network_id = NodeNetworkId::new(NetworkId::vfn_network(), 0);
+    let (network_reqs_tx, mut network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+    let sender = ObserverNetworkSender::new(
+        PeerManagerRequestSender::new(network_reqs_tx),
+        ConnectionRequestSender::new(connection_reqs_tx),
+    );
+    let mut network_senders = HashMap::new();
+    network_senders.insert(network_id.clone(), sender);
+    let publisher = ConsensusPublisher::new(&config, network_senders);
+
+    let peers: Vec<_> = (0..3).map(|_| PeerId::random()).collect();
+    for peer in &peers {
+        assert!(publisher
+            .process_network_event(
+                network_id.clone(),
+                Event::Message(*peer, ObserverMessage::Subscribe)
+            )
+            .is_none());
+    }
+    // The third subscription is over the limit, until the first peer unsubscribes
+    publisher.process_network_event(
+        network_id.clone(),
+        Event::Message(peers[0], ObserverMessage::Unsubscribe),
+    );
+    publisher.process_network_event(
+        network_id.clone(),
+        Event::Message(peers[2], ObserverMessage::Subscribe),
+    );
+
+    publisher.publish(commit_decision());
+    let mut recipients: Vec<_> = (0..2)
+        .map(|_| match block_on(network_reqs_rx.next()) {
+            Some(PeerManagerRequest::SendDirectSend(peer_id, _)) => peer_id,
+            request => panic!("Unexpected request {:?}", request),
+        })
+        .collect();
+    recipients.sort();
+    let mut expected = vec![peers[1], peers[2]];
+    expected.sort();
+    assert_eq!(recipients, expected);
+
+    // Other messages are left to the observer
+    assert!(publisher
+        .process_network_event(
+            network_id.clone(),
+            Event::Message(peers[1], ObserverMessage::Unsubscribe)
+        )
+        .is_none());
+    assert!(publisher
+        .process_network_event(network_id, Event::Message(peers[1], commit_decision()))
+        .is_some());
+}
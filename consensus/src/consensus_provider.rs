@@ -2,26 +2,33 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensus_observer::{
+        network::{ObserverNetworkEvents, ObserverNetworkSender},
+        ConsensusObserver, ConsensusPublisher,
+    },
     counters,
     epoch_manager::EpochManager,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     persistent_liveness_storage::StorageWriteProxy,
+    quorum_store::BatchStore,
     state_computer::ExecutionProxy,
     txn_manager::MempoolProxy,
     util::time_service::ClockTimeService,
 };
 use channel::diem_channel;
 use consensus_notifications::ConsensusNotificationSender;
-use diem_config::config::NodeConfig;
+use diem_config::{config::NodeConfig, network_id::NodeNetworkId};
 use diem_infallible::RwLock;
 use diem_logger::prelude::*;
 use diem_mempool::ConsensusRequest;
 use diem_types::on_chain_config::OnChainConfigPayload;
+use diem_vm::DiemVM;
 use execution_correctness::ExecutionCorrectnessManager;
+use executor::Executor;
 use futures::channel::mpsc;
-use std::{collections::HashMap, sync::Arc};
-use storage_interface::DbReader;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use storage_interface::{DbReader, DbReaderWriter};
 use tokio::runtime::{self, Runtime};
 
 /// Helper function to start consensus based on configuration and return the runtime
@@ -33,6 +40,7 @@ pub fn start_consensus(
     consensus_to_mempool_sender: mpsc::Sender<ConsensusRequest>,
     diem_db: Arc<dyn DbReader>,
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    observer_network_handles: Vec<(NodeNetworkId, ObserverNetworkSender, ObserverNetworkEvents)>,
 ) -> Runtime {
     let runtime = runtime::Builder::new_multi_thread()
         .thread_name("consensus")
@@ -40,6 +48,11 @@ pub fn start_consensus(
         .build()
         .expect("Failed to create Tokio runtime!");
     let storage = Arc::new(StorageWriteProxy::new(node_config, diem_db));
+    let batch_store = Arc::new(BatchStore::new(
+        storage.consensus_db(),
+        node_config.consensus.quorum_store_max_batches_per_author,
+        Duration::from_millis(node_config.consensus.quorum_store_batch_expiry_ms),
+    ));
     let txn_manager = Arc::new(MempoolProxy::new(
         consensus_to_mempool_sender,
         node_config.consensus.mempool_poll_count,
@@ -47,10 +60,17 @@ pub fn start_consensus(
         node_config.consensus.mempool_executed_txn_timeout_ms,
     ));
     let execution_correctness_manager = ExecutionCorrectnessManager::new(node_config);
+    let (publisher, observer_network_events) =
+        create_publisher(node_config, observer_network_handles);
+    if let Some(publisher) = &publisher {
+        runtime.spawn(publisher.clone().start(observer_network_events));
+    }
 
     let state_computer = Arc::new(ExecutionProxy::new(
         execution_correctness_manager.client(),
         state_sync_notifier,
+        batch_store.clone(),
+        publisher,
     ));
 
     let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
@@ -69,6 +89,7 @@ pub fn start_consensus(
         txn_manager,
         state_computer,
         storage,
+        batch_store,
         reconfig_events,
     );
 
@@ -81,3 +102,62 @@ pub fn start_consensus(
     debug!("Consensus started.");
     runtime
 }
+
+/// Helper function to start the consensus observer of a fullnode and return the runtime
+pub fn start_consensus_observer(
+    node_config: &NodeConfig,
+    observer_network_handles: Vec<(NodeNetworkId, ObserverNetworkSender, ObserverNetworkEvents)>,
+    state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    db_rw: DbReaderWriter,
+) -> Runtime {
+    let runtime = runtime::Builder::new_multi_thread()
+        .thread_name("consensus-observer")
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime!");
+    let network_senders = observer_network_handles
+        .iter()
+        .map(|(network_id, sender, _)| (network_id.clone(), sender.clone()))
+        .collect();
+    let (publisher, observer_network_events) =
+        create_publisher(node_config, observer_network_handles);
+    let observer = ConsensusObserver::new(
+        node_config.base.role,
+        &node_config.consensus_observer,
+        network_senders,
+        publisher,
+        state_sync_notifier,
+        Box::new(Executor::<DiemVM>::new(db_rw.clone())),
+        db_rw.reader,
+    );
+    runtime.spawn(observer.start(observer_network_events));
+
+    debug!("Consensus observer started.");
+    runtime
+}
+
+/// Creates the publisher if enabled and splits the network events from the handles.
+#[allow(clippy::type_complexity)]
+fn create_publisher(
+    node_config: &NodeConfig,
+    observer_network_handles: Vec<(NodeNetworkId, ObserverNetworkSender, ObserverNetworkEvents)>,
+) -> (
+    Option<Arc<ConsensusPublisher>>,
+    Vec<(NodeNetworkId, ObserverNetworkEvents)>,
+) {
+    let mut network_senders = HashMap::new();
+    let mut network_events = vec![];
+    for (network_id, sender, events) in observer_network_handles {
+        network_senders.insert(network_id.clone(), sender);
+        network_events.push((network_id, events));
+    }
+    let publisher = if node_config.consensus_observer.publisher_enabled {
+        Some(Arc::new(ConsensusPublisher::new(
+            &node_config.consensus_observer,
+            network_senders,
+        )))
+    } else {
+        None
+    };
+    (publisher, network_events)
+}
@@ -4,6 +4,7 @@
 use super::*;
 use consensus_types::block::block_test_utils::certificate_for_genesis;
 use diem_temppath::TempPath;
+use diem_types::account_address::AccountAddress;
 
 #[test]
 fn test_put_get() {
@@ -73,3 +74,20 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_put_get_delete_batch() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    assert_eq!(db.get_batches().unwrap().len(), 0);
+
+    let batch = Batch::new(AccountAddress::random(), 1, 0, vec![]);
+    db.save_batch(&batch).unwrap();
+    let batches = db.get_batches().unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches.get(&batch.digest()), Some(&batch));
+
+    db.delete_batches(vec![batch.digest()]).unwrap();
+    assert_eq!(db.get_batches().unwrap().len(), 0);
+}
@@ -0,0 +1,12 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use diem_types::account_address::AccountAddress;
+use schemadb::schema::assert_encode_decode;
+
+#[test]
+fn test_encode_decode() {
+    let batch = Batch::new(AccountAddress::random(), 1, 0, vec![]);
+    assert_encode_decode::<BatchSchema>(&batch.digest(), &batch);
+}
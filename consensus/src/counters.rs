@@ -98,6 +98,16 @@ pub static COMMITTED_VOTES_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Failed rounds in the current reputation window when using StakeWeightedReputation as the
+/// ProposerElection
+pub static FAILED_ROUNDS_IN_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_failed_rounds_in_window",
+        "Total number of failed rounds in the current reputation window"
+    )
+    .unwrap()
+});
+
 //////////////////////
 // RoundState COUNTERS
 //////////////////////
@@ -273,6 +283,26 @@ pub static BLOCK_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counters(queued,dequeued,dropped) related to quorum store channel
+pub static QUORUM_STORE_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_quorum_store_channel_msgs_count",
+        "Counters(queued,dequeued,dropped) related to quorum store channel",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Counters(queued,dequeued,dropped) related to batch retrieval channel
+pub static BATCH_RETRIEVAL_CHANNEL_MSGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_batch_retrieval_channel_msgs_count",
+        "Counters(queued,dequeued,dropped) related to batch retrieval channel",
+        &["state"]
+    )
+    .unwrap()
+});
+
 ///////////////////
 // DECOUPLED EXECUTION CHANNEL COUNTERS
 ///////////////////
@@ -336,3 +366,52 @@ pub static DECOUPLED_EXECUTION__EXECUTION_PHASE_RESET_CHANNEL: Lazy<IntGauge> =
     )
     .unwrap()
 });
+
+//////////////////////
+// CONSENSUS OBSERVER COUNTERS
+//////////////////////
+/// Counter of pending network events to the consensus observer
+pub static PENDING_OBSERVER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_observer_pending_network_events",
+        "Counters(queued,dequeued,dropped) related to pending network notifications to the consensus observer",
+        &["state"]
+    )
+    .unwrap()
+});
+
+/// Number of fullnodes subscribed to the blocks published by this node
+pub static OBSERVER_SUBSCRIBERS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_consensus_observer_subscribers",
+        "Number of fullnodes subscribed to the blocks published by this node"
+    )
+    .unwrap()
+});
+
+/// Number of blocks executed and committed by the consensus observer
+pub static OBSERVER_COMMITTED_BLOCKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_observer_committed_blocks",
+        "Number of blocks executed and committed by the consensus observer"
+    )
+    .unwrap()
+});
+
+/// Number of times the consensus observer fell back to state sync
+pub static OBSERVER_STATE_SYNC_FALLBACKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_observer_state_sync_fallbacks",
+        "Number of times the consensus observer fell back to state sync"
+    )
+    .unwrap()
+});
+
+/// Number of times the subscription of the consensus observer timed out
+pub static OBSERVER_SUBSCRIPTION_TIMEOUTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_consensus_observer_subscription_timeouts",
+        "Number of times the subscription of the consensus observer timed out"
+    )
+    .unwrap()
+});
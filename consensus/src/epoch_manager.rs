@@ -12,12 +12,17 @@ use crate::{
         rotating_proposer_election::{choose_leader, RotatingProposer},
         round_proposer_election::RoundProposer,
         round_state::{ExponentialTimeInterval, RoundState, RoundStateLogSchema},
+        stake_weighted_reputation::{DiemDBEpochHistory, StakeWeightedReputation},
     },
     logging::{LogEvent, LogSchema},
     metrics_safety_rules::MetricsSafetyRules,
-    network::{IncomingBlockRetrievalRequest, NetworkReceivers, NetworkSender},
+    network::{
+        IncomingBatchRetrievalRequest, IncomingBlockRetrievalRequest, NetworkReceivers,
+        NetworkSender,
+    },
     network_interface::{ConsensusMsg, ConsensusNetworkSender},
     persistent_liveness_storage::{LedgerRecoveryData, PersistentLivenessStorage, RecoveryData},
+    quorum_store::{BatchStore, QuorumStore},
     round_manager::{RecoveryManager, RoundManager, UnverifiedEvent, VerifiedEvent},
     state_replication::{StateComputer, TxnManager},
     util::time_service::TimeService,
@@ -83,8 +88,10 @@ pub struct EpochManager {
     txn_manager: Arc<dyn TxnManager>,
     commit_state_computer: Arc<dyn StateComputer>,
     storage: Arc<dyn PersistentLivenessStorage>,
+    batch_store: Arc<BatchStore>,
     safety_rules_manager: SafetyRulesManager,
     processor: Option<RoundProcessor>,
+    quorum_store: Option<Arc<QuorumStore>>,
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
     commit_msg_tx: Option<Sender<VerifiedEvent>>,
     back_pressure: Arc<AtomicU64>,
@@ -100,6 +107,7 @@ impl EpochManager {
         txn_manager: Arc<dyn TxnManager>,
         commit_state_computer: Arc<dyn StateComputer>,
         storage: Arc<dyn PersistentLivenessStorage>,
+        batch_store: Arc<BatchStore>,
         reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
     ) -> Self {
         let author = node_config.validator_network.as_ref().unwrap().peer_id();
@@ -120,8 +128,10 @@ impl EpochManager {
             txn_manager,
             commit_state_computer,
             storage,
+            batch_store,
             safety_rules_manager,
             processor: None,
+            quorum_store: None,
             reconfig_events,
             commit_msg_tx: None,
             back_pressure,
@@ -189,6 +199,29 @@ impl EpochManager {
                 ));
                 Box::new(LeaderReputation::new(proposers, backend, heuristic))
             }
+            ConsensusProposerType::StakeWeightedReputation(reputation_config) => {
+                let window_size = proposers.len();
+                let backend = Box::new(DiemDBEpochHistory::new(self.storage.diem_db()));
+                let voting_powers = proposers
+                    .iter()
+                    .map(|author| {
+                        epoch_state
+                            .verifier
+                            .get_voting_power(author)
+                            .expect("Proposers must be validators")
+                    })
+                    .collect();
+                Box::new(StakeWeightedReputation::new(
+                    epoch_state.epoch,
+                    proposers,
+                    voting_powers,
+                    window_size,
+                    backend,
+                    reputation_config.active_weights,
+                    reputation_config.inactive_weights,
+                    reputation_config.failed_weights,
+                ))
+            }
             ConsensusProposerType::RoundProposer(round_proposers) => {
                 // Hardcoded to the first proposer
                 let default_proposer = proposers.get(0).unwrap();
@@ -296,6 +329,7 @@ impl EpochManager {
     ) {
         // Release the previous RoundManager, especially the SafetyRule client
         self.processor = None;
+        self.quorum_store = None;
         let epoch = epoch_state.epoch;
         counters::EPOCH.set(epoch_state.epoch as i64);
         counters::CURRENT_EPOCH_VALIDATORS.set(epoch_state.verifier.len() as i64);
@@ -334,6 +368,37 @@ impl EpochManager {
 
         let safety_rules_container = Arc::new(Mutex::new(safety_rules));
 
+        info!(epoch = epoch, "Create QuorumStore");
+        if let Err(error) = self.batch_store.new_epoch(epoch) {
+            error!(
+                epoch = epoch,
+                error = ?error,
+                "Unable to prune the batches of the previous epochs.",
+            );
+        }
+        // Every validator stores and signs the batches of the others, but only the ones with the
+        // quorum store enabled create batches and propose proofs of store.
+        let quorum_store = Arc::new(QuorumStore::new(
+            self.author,
+            epoch_state.clone(),
+            self.txn_manager.clone(),
+            self.batch_store.clone(),
+            network_sender.clone(),
+            safety_rules_container.clone(),
+            self.config.quorum_store_max_batch_size,
+            Duration::from_millis(self.config.quorum_store_proof_timeout_ms),
+            Duration::from_millis(self.config.quorum_store_batch_expiry_ms / 2),
+        ));
+        let proposal_txn_manager: Arc<dyn TxnManager> = if self.config.quorum_store_enabled {
+            quorum_store.start_batch_generator(Duration::from_millis(
+                self.config.quorum_store_batch_interval_ms,
+            ));
+            quorum_store.clone()
+        } else {
+            self.txn_manager.clone()
+        };
+        self.quorum_store = Some(quorum_store);
+
         // TODO: prepare decoupled execution
         let mut processor = {
             info!(epoch = epoch, "Create BlockStore");
@@ -351,7 +416,7 @@ impl EpochManager {
             let proposal_generator = ProposalGenerator::new(
                 self.author,
                 block_store.clone(),
-                self.txn_manager.clone(),
+                proposal_txn_manager,
                 self.time_service.clone(),
                 self.config.max_block_size,
             );
@@ -365,6 +430,7 @@ impl EpochManager {
                 safety_rules_container,
                 network_sender,
                 self.txn_manager.clone(),
+                self.batch_store.clone(),
                 self.storage.clone(),
                 self.config.sync_only,
                 onchain_config,
@@ -401,6 +467,7 @@ impl EpochManager {
             network_sender,
             self.storage.clone(),
             self.commit_state_computer.clone(),
+            self.batch_store.clone(),
             ledger_recovery_data.commit_round(),
             onchain_config,
         )));
@@ -450,6 +517,13 @@ impl EpochManager {
                         error = ?err,
                         unverified_event = unverified_event
                     );
+                    if let Err(e) = self.network_sender.report_invalid_message(peer_id) {
+                        warn!(
+                            remote_peer = peer_id,
+                            error = ?e,
+                            "[EpochManager] Failed to report peer"
+                        );
+                    }
                     err
                 })?;
 
@@ -583,6 +657,45 @@ impl EpochManager {
         }
     }
 
+    async fn process_quorum_store_message(
+        &mut self,
+        peer_id: AccountAddress,
+        msg: ConsensusMsg,
+    ) -> anyhow::Result<()> {
+        let quorum_store = match &self.quorum_store {
+            Some(quorum_store) => quorum_store.clone(),
+            None => bail!("[EpochManager] QuorumStore not started yet"),
+        };
+        match msg {
+            ConsensusMsg::BatchMsg(batch) => quorum_store.process_batch(peer_id, *batch).await,
+            ConsensusMsg::SignedBatchInfoMsg(signed_info) => {
+                quorum_store
+                    .process_signed_batch_info(peer_id, *signed_info)
+                    .await
+            }
+            ConsensusMsg::ProofOfStoreMsg(proof) => quorum_store.process_proof(*proof),
+            _ => bail!("[EpochManager] Unexpected quorum store message: {:?}", msg),
+        }
+    }
+
+    fn process_batch_retrieval(
+        &self,
+        request: IncomingBatchRetrievalRequest,
+    ) -> anyhow::Result<()> {
+        let batch = self
+            .batch_store
+            .get_batch(&request.req.digest())
+            .ok_or_else(|| anyhow!("[EpochManager] Batch {} not found", request.req.digest()))?;
+        bcs::to_bytes(&ConsensusMsg::BatchMsg(Box::new(batch)))
+            .and_then(|bytes| {
+                request
+                    .response_sender
+                    .send(Ok(bytes.into()))
+                    .map_err(|e| bcs::Error::Custom(format!("{:?}", e)))
+            })
+            .context("[EpochManager] Failed to process batch retrieval")
+    }
+
     async fn process_local_timeout(&mut self, round: u64) -> anyhow::Result<()> {
         match self.processor_mut() {
             RoundProcessor::Normal(p) => p.process_local_timeout(round).await,
@@ -616,6 +729,13 @@ impl EpochManager {
                     block_retrieval = network_receivers.block_retrieval.select_next_some() => {
                         monitor!("process_block_retrieval", self.process_block_retrieval(block_retrieval).await)
                     }
+                    msg = network_receivers.quorum_store_messages.select_next_some() => {
+                        let (peer, msg) = (msg.0, msg.1);
+                        monitor!("process_quorum_store_message", self.process_quorum_store_message(peer, msg).await.with_context(|| format!("from peer: {}", peer)))
+                    }
+                    batch_retrieval = network_receivers.batch_retrieval.select_next_some() => {
+                        monitor!("process_batch_retrieval", self.process_batch_retrieval(batch_retrieval))
+                    }
                     round = round_timeout_sender_rx.select_next_some() => {
                         monitor!("process_local_timeout", self.process_local_timeout(round).await)
                     }
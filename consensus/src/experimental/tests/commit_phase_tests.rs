@@ -17,7 +17,10 @@ use futures::{SinkExt, StreamExt};
 use crate::{
     experimental::ordering_state_computer::OrderingStateComputer, state_replication::StateComputer,
 };
-use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
+use consensus_types::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    common::Payload,
+};
 use diem_crypto::{ed25519::Ed25519Signature, hash::ACCUMULATOR_PLACEHOLDER_HASH};
 
 use diem_types::{
@@ -231,7 +234,13 @@ mod commit_phase_e2e_tests {
         ) = prepare_commit_phase(&runtime);
 
         let genesis_qc = certificate_for_genesis();
-        let block = Block::new_proposal(vec![], 1, 1, genesis_qc, signers.first().unwrap());
+        let block = Block::new_proposal(
+            Payload::empty(false),
+            1,
+            1,
+            genesis_qc,
+            signers.first().unwrap(),
+        );
         let compute_result = state_computer
             .compute(&block, *ACCUMULATOR_PLACEHOLDER_HASH)
             .unwrap();
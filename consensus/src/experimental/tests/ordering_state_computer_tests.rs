@@ -9,7 +9,7 @@ use crate::{
     test_utils::EmptyStateComputer,
 };
 use channel::Receiver;
-use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use consensus_types::{block::Block, common::Payload, quorum_cert::QuorumCert};
 use diem_types::validator_signer::ValidatorSigner;
 use futures::channel::oneshot;
 use rand::Rng;
@@ -36,7 +36,13 @@ pub fn prepare_ordering_state_computer(
 
 pub fn random_empty_block(signer: &ValidatorSigner, qc: QuorumCert) -> Block {
     let mut rng = rand::thread_rng();
-    Block::new_proposal(vec![], rng.gen::<u64>(), rng.gen::<u64>(), qc, signer)
+    Block::new_proposal(
+        Payload::empty(false),
+        rng.gen::<u64>(),
+        rng.gen::<u64>(),
+        qc,
+        signer,
+    )
 }
 
 #[test]
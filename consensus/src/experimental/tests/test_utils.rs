@@ -20,6 +20,7 @@ use crate::{
 use channel::{diem_channel, message_queues::QueueStyle, Receiver, Sender};
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
+    common::Payload,
     executed_block::ExecutedBlock,
 };
 use diem_crypto::{
@@ -177,7 +178,7 @@ pub fn prepare_executed_blocks_with_ledger_info(
     consensus_hash: HashValue,
 ) -> (Vec<ExecutedBlock>, LedgerInfoWithSignatures) {
     let genesis_qc = certificate_for_genesis();
-    let block = Block::new_proposal(vec![], 1, 1, genesis_qc, signer);
+    let block = Block::new_proposal(Payload::empty(false), 1, 1, genesis_qc, signer);
     let compute_result = StateComputeResult::new(
         executed_hash,
         vec![], // dummy subtree
@@ -26,6 +26,7 @@ mod network;
 mod network_tests;
 mod pending_votes;
 mod persistent_liveness_storage;
+mod quorum_store;
 mod round_manager;
 mod state_computer;
 mod state_replication;
@@ -36,6 +37,8 @@ mod twins;
 mod txn_manager;
 mod util;
 
+/// Consensus observer publishing the committed blocks to fullnodes.
+pub mod consensus_observer;
 /// DiemBFT implementation
 pub mod consensus_provider;
 /// DiemNet interface.
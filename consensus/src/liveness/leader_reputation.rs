@@ -206,39 +206,46 @@ impl ProposerElection for LeaderReputation {
     /// This function will return true for at most one proposal per valid proposer for a given round.
     fn is_valid_proposal(&self, block: &Block) -> bool {
         block.author().map_or(false, |author| {
-            let valid = self.is_valid_proposer(author, block.round());
-            let mut already_proposed = self.already_proposed.lock();
-            if !valid {
-                return false;
-            }
-            // detect if the leader proposes more than once in this round
-            match block.round().cmp(&already_proposed.0) {
-                Ordering::Greater => {
-                    already_proposed.0 = block.round();
-                    already_proposed.1.clear();
-                    already_proposed.1.insert(author, block.id());
-                    true
-                }
-                Ordering::Equal => {
-                    if already_proposed
-                        .1
-                        .get(&author)
-                        .map_or(false, |id| *id != block.id())
-                    {
-                        error!(
-                            SecurityEvent::InvalidConsensusProposal,
-                            "Multiple proposals from {} for round {}",
-                            author,
-                            block.round()
-                        );
-                        false
-                    } else {
-                        already_proposed.1.insert(author, block.id());
-                        true
-                    }
-                }
-                Ordering::Less => false,
-            }
+            self.is_valid_proposer(author, block.round())
+                && is_first_proposal(&self.already_proposed, author, block)
         })
     }
 }
+
+/// Records the proposal of a valid proposer and returns false if it already proposed a different
+/// block for the same round (or if the round is older than the last recorded one).
+pub(crate) fn is_first_proposal(
+    already_proposed: &Mutex<(Round, HashMap<Author, HashValue>)>,
+    author: Author,
+    block: &Block,
+) -> bool {
+    let mut already_proposed = already_proposed.lock();
+    // detect if the leader proposes more than once in this round
+    match block.round().cmp(&already_proposed.0) {
+        Ordering::Greater => {
+            already_proposed.0 = block.round();
+            already_proposed.1.clear();
+            already_proposed.1.insert(author, block.id());
+            true
+        }
+        Ordering::Equal => {
+            if already_proposed
+                .1
+                .get(&author)
+                .map_or(false, |id| *id != block.id())
+            {
+                error!(
+                    SecurityEvent::InvalidConsensusProposal,
+                    "Multiple proposals from {} for round {}",
+                    author,
+                    block.round()
+                );
+                false
+            } else {
+                already_proposed.1.insert(author, block.id());
+                true
+            }
+        }
+        Ordering::Less => false,
+    }
+}
@@ -9,7 +9,7 @@ use crate::liveness::{
 };
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
-    common::{Author, Round},
+    common::{Author, Payload, Round},
 };
 use diem_types::{block_metadata::NewBlockEvent, validator_signer::ValidatorSigner};
 
@@ -130,7 +130,7 @@ fn test_api() {
     assert!(proposer_election.is_valid_proposer(proposers[expected_index], 42));
     assert!(!proposer_election.is_valid_proposer(proposers[unexpected_index], 42));
     let good_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(false),
         round,
         1,
         certificate_for_genesis(),
@@ -138,7 +138,7 @@ fn test_api() {
     );
     assert!(proposer_election.is_valid_proposal(&good_proposal));
     let bad_proposal = Block::new_proposal(
-        vec![],
+        Payload::empty(false),
         round,
         1,
         certificate_for_genesis(),
@@ -146,7 +146,7 @@ fn test_api() {
     );
     assert!(!proposer_election.is_valid_proposal(&bad_proposal));
     let bad_proposal_2 = Block::new_proposal(
-        vec![],
+        Payload::empty(false),
         round,
         2,
         certificate_for_genesis(),
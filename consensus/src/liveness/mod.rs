@@ -7,6 +7,7 @@ pub(crate) mod proposer_election;
 pub(crate) mod rotating_proposer_election;
 pub(crate) mod round_proposer_election;
 pub(crate) mod round_state;
+pub(crate) mod stake_weighted_reputation;
 
 #[cfg(test)]
 mod leader_reputation_test;
@@ -16,3 +17,5 @@ mod rotating_proposer_test;
 mod round_proposer_test;
 #[cfg(test)]
 mod round_state_test;
+#[cfg(test)]
+mod stake_weighted_reputation_test;
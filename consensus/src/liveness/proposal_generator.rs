@@ -8,7 +8,7 @@ use anyhow::{bail, ensure, format_err, Context};
 use consensus_types::{
     block::Block,
     block_data::BlockData,
-    common::{Author, Round},
+    common::{Author, Payload, Round},
     quorum_cert::QuorumCert,
 };
 
@@ -97,7 +97,10 @@ impl ProposalGenerator {
         let (payload, timestamp) = if hqc.certified_block().has_reconfiguration() {
             // Reconfiguration rule - we propose empty blocks with parents' timestamp
             // after reconfiguration until it's committed
-            (vec![], hqc.certified_block().timestamp_usecs())
+            (
+                Payload::empty(false),
+                hqc.certified_block().timestamp_usecs(),
+            )
         } else {
             // One needs to hold the blocks with the references to the payloads while get_block is
             // being executed: pending blocks vector keeps all the pending ancestors of the extended branch.
@@ -111,7 +114,7 @@ impl ProposalGenerator {
 
             // Exclude all the pending transactions: these are all the ancestors of
             // parent (including) up to the root (including).
-            let exclude_payload: Vec<&Vec<_>> = pending_blocks
+            let exclude_payload: Vec<&Payload> = pending_blocks
                 .iter()
                 .flat_map(|block| block.payload())
                 .collect();
@@ -0,0 +1,390 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters::FAILED_ROUNDS_IN_WINDOW,
+    liveness::{
+        leader_reputation::is_first_proposal,
+        proposer_election::{next, ProposerElection},
+    },
+};
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::block_metadata::{new_block_event_key, NewBlockEvent};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+use storage_interface::{DbReader, Order};
+
+/// The leader of round r is chosen from the history committed up to round r - ROUND_GAP, so that
+/// the honest nodes have all committed it by the time they need the leader.
+const ROUND_GAP: Round = 4;
+
+/// Number of NewBlockEvents read from the DB at once.
+const EVENTS_PAGE_SIZE: u64 = 100;
+
+/// Number of windows of NewBlockEvents kept in memory by DiemDBEpochHistory.
+const CACHED_WINDOWS: usize = 4;
+
+/// Interface to query the blocks committed in the current epoch.
+pub trait EpochHistory: Send + Sync {
+    /// Return the latest `window_size` NewBlockEvents of the current epoch with a round of at
+    /// most `target_round`, in descending round order, or all of them if there are fewer.
+    fn get_history(
+        &self,
+        target_round: Round,
+        window_size: usize,
+    ) -> anyhow::Result<Vec<NewBlockEvent>>;
+}
+
+/// EpochHistory reading the NewBlockEvents from DiemDB. Only the events are read, never the
+/// transactions, and the reads stop at the first block of the epoch.
+pub struct DiemDBEpochHistory {
+    diem_db: Arc<dyn DbReader>,
+    events: Mutex<EpochEvents>,
+}
+
+/// Contiguous NewBlockEvents of the current epoch with their sequence numbers, in ascending order.
+#[derive(Default)]
+struct EpochEvents {
+    events: VecDeque<(u64, NewBlockEvent)>,
+    // The events start at the first block of the epoch.
+    at_epoch_start: bool,
+    // The event after the last one belongs to the next epoch.
+    at_epoch_end: bool,
+}
+
+impl DiemDBEpochHistory {
+    pub fn new(diem_db: Arc<dyn DbReader>) -> Self {
+        Self {
+            diem_db,
+            events: Mutex::new(EpochEvents::default()),
+        }
+    }
+
+    fn read_events(&self, start: u64, order: Order) -> anyhow::Result<Vec<(u64, NewBlockEvent)>> {
+        self.diem_db
+            .get_events(&new_block_event_key(), start, order, EVENTS_PAGE_SIZE)?
+            .into_iter()
+            .map(|(_, event)| {
+                Ok((
+                    event.sequence_number(),
+                    bcs::from_bytes::<NewBlockEvent>(event.event_data())?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Read the events committed after the known ones. The rounds restart with each epoch, so an
+    /// event whose round doesn't increase belongs to the next epoch.
+    fn read_newer(&self, cache: &mut EpochEvents) -> anyhow::Result<()> {
+        while !cache.at_epoch_end {
+            let start = match cache.events.back() {
+                Some((seq, _)) => seq + 1,
+                None => {
+                    cache.at_epoch_start = false;
+                    return self.read_older(cache);
+                }
+            };
+            let events = self.read_events(start, Order::Ascending)?;
+            let page_full = events.len() as u64 == EVENTS_PAGE_SIZE;
+            for (seq, event) in events {
+                if let Some((_, last)) = cache.events.back() {
+                    if event.round() <= last.round() {
+                        cache.at_epoch_end = true;
+                        break;
+                    }
+                }
+                cache.events.push_back((seq, event));
+            }
+            if !page_full {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the events committed before the known ones, or the latest ones if none is known. The
+    /// rounds restart with each epoch, so an event whose round doesn't decrease belongs to the
+    /// previous epoch.
+    fn read_older(&self, cache: &mut EpochEvents) -> anyhow::Result<()> {
+        if cache.at_epoch_start {
+            return Ok(());
+        }
+        let start = match cache.events.front() {
+            Some((0, _)) => {
+                cache.at_epoch_start = true;
+                return Ok(());
+            }
+            Some((seq, _)) => seq - 1,
+            None => u64::max_value(),
+        };
+        let events = self.read_events(start, Order::Descending)?;
+        if (events.len() as u64) < EVENTS_PAGE_SIZE {
+            cache.at_epoch_start = true;
+        }
+        for (seq, event) in events {
+            if let Some((_, first)) = cache.events.front() {
+                if event.round() >= first.round() {
+                    cache.at_epoch_start = true;
+                    break;
+                }
+            }
+            cache.events.push_front((seq, event));
+        }
+        Ok(())
+    }
+}
+
+impl EpochHistory for DiemDBEpochHistory {
+    fn get_history(
+        &self,
+        target_round: Round,
+        window_size: usize,
+    ) -> anyhow::Result<Vec<NewBlockEvent>> {
+        let mut cache = self.events.lock();
+        if cache
+            .events
+            .back()
+            .map_or(true, |(_, last)| last.round() < target_round)
+        {
+            self.read_newer(&mut cache)?;
+        }
+        let window_len = |cache: &EpochEvents| {
+            cache
+                .events
+                .iter()
+                .filter(|(_, event)| event.round() <= target_round)
+                .count()
+        };
+        while !cache.at_epoch_start && window_len(&cache) < window_size {
+            self.read_older(&mut cache)?;
+        }
+        let history = cache
+            .events
+            .iter()
+            .rev()
+            .map(|(_, event)| event)
+            .filter(|event| event.round() <= target_round)
+            .take(window_size)
+            .cloned()
+            .collect();
+        while cache.events.len() > CACHED_WINDOWS * window_size {
+            cache.events.pop_front();
+            cache.at_epoch_start = false;
+        }
+        Ok(history)
+    }
+}
+
+/// Committed history based proposer election where the chance of a validator to be the leader is
+/// proportional to its voting power, scaled by its reputation in the window:
+/// - `failed_weight` if it was the leader of a failed round,
+/// - `active_weight` if it proposed or voted for a committed block,
+/// - `inactive_weight` otherwise.
+///
+/// A round fails when it ends with a timeout certificate instead of a quorum cert: the next
+/// leader enters its round without a quorum cert for the failed one, so it extends an older block
+/// and the failed round is missing from the committed history. Conversely a round missing between
+/// two committed blocks ended with a timeout certificate, as its block would otherwise have been
+/// extended. The failed rounds are thus the rounds missing between the committed blocks of the
+/// window, and their leaders are elected again exactly as they were, failure penalties included,
+/// from the history committed up to ROUND_GAP rounds before them. That history is final, so every
+/// node attributes the failures identically, and the leaders of the failed rounds are kept while
+/// they're in the window.
+///
+/// The leader is sampled with a seed derived from the epoch and the round, so all the honest
+/// nodes agree on it given the same committed history.
+pub struct StakeWeightedReputation {
+    epoch: u64,
+    proposers: Vec<Author>,
+    voting_powers: Vec<u64>,
+    window_size: usize,
+    backend: Box<dyn EpochHistory>,
+    active_weight: u64,
+    inactive_weight: u64,
+    failed_weight: u64,
+    failed_round_leaders: Mutex<BTreeMap<Round, Author>>,
+    already_proposed: Mutex<(Round, HashMap<Author, HashValue>)>,
+}
+
+impl StakeWeightedReputation {
+    pub fn new(
+        epoch: u64,
+        proposers: Vec<Author>,
+        voting_powers: Vec<u64>,
+        window_size: usize,
+        backend: Box<dyn EpochHistory>,
+        active_weight: u64,
+        inactive_weight: u64,
+        failed_weight: u64,
+    ) -> Self {
+        assert_eq!(proposers.len(), voting_powers.len());
+        Self {
+            epoch,
+            proposers,
+            voting_powers,
+            window_size,
+            backend,
+            active_weight,
+            inactive_weight,
+            failed_weight,
+            failed_round_leaders: Mutex::new(BTreeMap::new()),
+            already_proposed: Mutex::new((0, HashMap::new())),
+        }
+    }
+
+    /// Return the weights of the proposers given the history and the leaders of failed rounds.
+    pub fn get_weights(
+        &self,
+        history: &[NewBlockEvent],
+        failed_leaders: &HashSet<Author>,
+    ) -> Vec<u128> {
+        let active = history.iter().fold(HashSet::new(), |mut set, meta| {
+            set.insert(meta.proposer());
+            set.extend(meta.votes());
+            set
+        });
+        self.proposers
+            .iter()
+            .zip(self.voting_powers.iter())
+            .map(|(author, voting_power)| {
+                let reputation = if failed_leaders.contains(author) {
+                    self.failed_weight
+                } else if active.contains(author) {
+                    self.active_weight
+                } else {
+                    self.inactive_weight
+                };
+                *voting_power as u128 * reputation as u128
+            })
+            .collect()
+    }
+
+    /// Return the leaders of the rounds missing between the committed blocks of the history.
+    pub fn get_failed_leaders(&self, history: &[NewBlockEvent]) -> anyhow::Result<HashSet<Author>> {
+        let failed_rounds = get_failed_rounds(history);
+        FAILED_ROUNDS_IN_WINDOW.set(failed_rounds.len() as i64);
+
+        let mut leaders = self.failed_round_leaders.lock();
+        // The leader of a failed round depends on the leaders of the failed rounds before it, so
+        // the missing ones are elected oldest first.
+        let mut pending: Vec<Round> = failed_rounds
+            .iter()
+            .filter(|round| !leaders.contains_key(round))
+            .copied()
+            .collect();
+        while let Some(round) = pending.last().copied() {
+            if leaders.contains_key(&round) {
+                pending.pop();
+                continue;
+            }
+            let round_history = self
+                .backend
+                .get_history(round.saturating_sub(ROUND_GAP), self.window_size)?;
+            let round_failed_rounds = get_failed_rounds(&round_history);
+            let missing: Vec<Round> = round_failed_rounds
+                .iter()
+                .filter(|round| !leaders.contains_key(round))
+                .copied()
+                .collect();
+            if missing.is_empty() {
+                let round_failed_leaders = round_failed_rounds
+                    .iter()
+                    .map(|round| leaders[round])
+                    .collect();
+                let leader = self.choose(
+                    round,
+                    &self.get_weights(&round_history, &round_failed_leaders),
+                );
+                leaders.insert(round, leader);
+                pending.pop();
+            } else {
+                pending.extend(missing);
+            }
+        }
+        let failed_leaders = failed_rounds.iter().map(|round| leaders[round]).collect();
+        // Forget the leaders of the failed rounds that left the window.
+        if let Some(oldest) = history.iter().map(|meta| meta.round()).min() {
+            *leaders = leaders.split_off(&oldest);
+        }
+        Ok(failed_leaders)
+    }
+
+    /// Sample a proposer with a probability proportional to its weight.
+    fn choose(&self, round: Round, weights: &[u128]) -> Author {
+        assert_eq!(weights.len(), self.proposers.len());
+        let mut total_weight: u128 = weights.iter().sum();
+        let mut cumulative_weights = Vec::with_capacity(weights.len());
+        if total_weight == 0 {
+            // Every validator is penalized down to zero, fall back to the voting power.
+            for voting_power in &self.voting_powers {
+                total_weight += *voting_power as u128;
+                cumulative_weights.push(total_weight);
+            }
+        } else {
+            let mut accumulator = 0;
+            for w in weights {
+                accumulator += *w;
+                cumulative_weights.push(accumulator);
+            }
+        }
+        let mut state = self.epoch.to_le_bytes().to_vec();
+        state.extend_from_slice(&round.to_le_bytes());
+        let chosen_weight =
+            (((next(&mut state) as u128) << 64) | next(&mut state) as u128) % total_weight;
+        let chosen_index = cumulative_weights.partition_point(|w| *w <= chosen_weight);
+        self.proposers[chosen_index]
+    }
+}
+
+impl ProposerElection for StakeWeightedReputation {
+    fn get_valid_proposer(&self, round: Round) -> Author {
+        let target_round = round.saturating_sub(ROUND_GAP);
+        let (history, failed_leaders) = match self
+            .backend
+            .get_history(target_round, self.window_size)
+            .and_then(|history| {
+                let failed_leaders = self.get_failed_leaders(&history)?;
+                Ok((history, failed_leaders))
+            }) {
+            Ok(result) => result,
+            Err(e) => {
+                error!(
+                    error = ?e, "[stake weighted reputation] Fail to read the committed history",
+                );
+                (vec![], HashSet::new())
+            }
+        };
+        self.choose(round, &self.get_weights(&history, &failed_leaders))
+    }
+
+    /// This function will return true for at most one proposal per valid proposer for a given round.
+    fn is_valid_proposal(&self, block: &Block) -> bool {
+        block.author().map_or(false, |author| {
+            self.is_valid_proposer(author, block.round())
+                && is_first_proposal(&self.already_proposed, author, block)
+        })
+    }
+}
+
+/// Return the rounds missing between the committed blocks of the history, the latest first.
+fn get_failed_rounds(history: &[NewBlockEvent]) -> Vec<Round> {
+    let committed_rounds: HashSet<Round> = history.iter().map(|meta| meta.round()).collect();
+    let (first, last) = match (committed_rounds.iter().min(), committed_rounds.iter().max()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return vec![],
+    };
+    // Bound the work done per election after a long period without commits.
+    (first + 1..last)
+        .rev()
+        .filter(|round| !committed_rounds.contains(round))
+        .take(history.len())
+        .collect()
+}
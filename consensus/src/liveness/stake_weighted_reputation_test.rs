@@ -0,0 +1,220 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::liveness::{
+    proposer_election::ProposerElection,
+    stake_weighted_reputation::{EpochHistory, StakeWeightedReputation},
+};
+use consensus_types::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    common::{Author, Payload, Round},
+};
+use diem_types::{block_metadata::NewBlockEvent, validator_signer::ValidatorSigner};
+use std::collections::HashSet;
+
+struct MockHistory {
+    data: Vec<NewBlockEvent>,
+}
+
+impl EpochHistory for MockHistory {
+    fn get_history(
+        &self,
+        target_round: Round,
+        window_size: usize,
+    ) -> anyhow::Result<Vec<NewBlockEvent>> {
+        let mut history: Vec<_> = self
+            .data
+            .iter()
+            .filter(|meta| meta.round() <= target_round)
+            .cloned()
+            .collect();
+        history.sort_by_key(|meta| std::cmp::Reverse(meta.round()));
+        history.truncate(window_size);
+        Ok(history)
+    }
+}
+
+const ACTIVE_WEIGHT: u64 = 9;
+const INACTIVE_WEIGHT: u64 = 1;
+const WINDOW_SIZE: usize = 10;
+
+fn create_block(round: Round, proposer: Author, voters: Vec<Author>) -> NewBlockEvent {
+    NewBlockEvent::new(round, proposer, voters, 0)
+}
+
+fn create_signers(num: u8) -> Vec<ValidatorSigner> {
+    (0..num).map(|i| ValidatorSigner::random([i; 32])).collect()
+}
+
+fn create_election(
+    signers: &[ValidatorSigner],
+    voting_powers: Vec<u64>,
+    history: Vec<NewBlockEvent>,
+    failed_weight: u64,
+) -> StakeWeightedReputation {
+    StakeWeightedReputation::new(
+        1,
+        signers.iter().map(|signer| signer.author()).collect(),
+        voting_powers,
+        WINDOW_SIZE,
+        Box::new(MockHistory { data: history }),
+        ACTIVE_WEIGHT,
+        INACTIVE_WEIGHT,
+        failed_weight,
+    )
+}
+
+#[test]
+fn test_weights() {
+    let signers = create_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let election = create_election(&signers, vec![1, 2, 3, 4], vec![], 0);
+    let history = vec![create_block(1, proposers[0], vec![proposers[1]])];
+    let failed_leaders = vec![proposers[1]].into_iter().collect();
+    assert_eq!(
+        election.get_weights(&history, &failed_leaders),
+        vec![
+            ACTIVE_WEIGHT as u128,
+            0,
+            3 * INACTIVE_WEIGHT as u128,
+            4 * INACTIVE_WEIGHT as u128
+        ]
+    );
+    // Without history every validator is weighted by its voting power only
+    assert_eq!(election.get_weights(&[], &HashSet::new()), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_stake_weighted_sampling() {
+    let signers = create_signers(4);
+    let election = create_election(&signers, vec![1, 0, 0, 99], vec![], 0);
+    let mut counts = vec![0; 4];
+    for round in 1..=1000 {
+        let proposer = election.get_valid_proposer(round);
+        let index = signers
+            .iter()
+            .position(|signer| signer.author() == proposer)
+            .unwrap();
+        counts[index] += 1;
+    }
+    assert_eq!(counts[1], 0);
+    assert_eq!(counts[2], 0);
+    assert!(counts[3] > 900);
+
+    // All the nodes agree on the leaders
+    let other = create_election(&signers, vec![1, 0, 0, 99], vec![], 0);
+    for round in 1..=100 {
+        assert_eq!(
+            election.get_valid_proposer(round),
+            other.get_valid_proposer(round)
+        );
+    }
+}
+
+#[test]
+fn test_failed_leaders() {
+    let signers = create_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    // Only the third validator has voting power, so it was the leader of every round
+    let election = create_election(&signers, vec![0, 0, 5, 0], vec![], 0);
+    let contiguous = vec![
+        create_block(1, proposers[2], vec![]),
+        create_block(2, proposers[2], vec![]),
+        create_block(3, proposers[2], vec![]),
+    ];
+    assert!(election.get_failed_leaders(&contiguous).unwrap().is_empty());
+    // Rounds 3 and 4 timed out
+    let with_gap = vec![
+        create_block(1, proposers[2], vec![]),
+        create_block(2, proposers[2], vec![]),
+        create_block(5, proposers[2], vec![]),
+    ];
+    let failed_leaders = election.get_failed_leaders(&with_gap).unwrap();
+    assert_eq!(failed_leaders, vec![proposers[2]].into_iter().collect());
+    // Once every validator with voting power is penalized to zero, fall back to the voting power
+    let election = create_election(&signers, vec![0, 0, 5, 0], with_gap, 0);
+    assert_eq!(election.get_valid_proposer(10), proposers[2]);
+}
+
+#[test]
+fn test_failed_leaders_are_not_elected() {
+    let signers = create_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let voters: Vec<_> = proposers.clone();
+    let history: Vec<_> = [1, 2, 4, 5, 7, 8, 10]
+        .iter()
+        .map(|round| create_block(*round, proposers[0], voters.clone()))
+        .collect();
+    let election = create_election(&signers, vec![1, 1, 1, 1], history.clone(), 0);
+    let failed_leaders = election.get_failed_leaders(&history).unwrap();
+    assert!(!failed_leaders.is_empty());
+    assert!(failed_leaders.len() < proposers.len());
+    // From round 14 on, the whole history is committed up to the round gap
+    for round in 14..200 {
+        assert!(!failed_leaders.contains(&election.get_valid_proposer(round)));
+    }
+}
+
+#[test]
+fn test_failed_leaders_are_elected_with_penalties() {
+    let signers = create_signers(4);
+    let proposers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let history: Vec<_> = [1, 2, 4, 5, 8, 10]
+        .iter()
+        .map(|round| create_block(*round, proposers[0], proposers.clone()))
+        .collect();
+    let election = create_election(&signers, vec![1, 1, 1, 1], history.clone(), 0);
+    // Round 3 failed, so its leader couldn't be elected in round 9
+    let leader_3 = election.get_failed_leaders(&history[1..3]).unwrap();
+    let leader_9 = election.get_failed_leaders(&history[4..]).unwrap();
+    assert_eq!(leader_3.len(), 1);
+    assert_eq!(leader_9.len(), 1);
+    assert!(leader_3.is_disjoint(&leader_9));
+    // Every node attributes the failures identically, whatever it computed before
+    let other = create_election(&signers, vec![1, 1, 1, 1], history.clone(), 0);
+    assert_eq!(other.get_failed_leaders(&history[4..]).unwrap(), leader_9);
+}
+
+#[test]
+fn test_api() {
+    let signers = create_signers(4);
+    let election = create_election(&signers, vec![1, 1, 1, 1], vec![], 1);
+    let round = 42;
+    let proposer = election.get_valid_proposer(round);
+    let signer = signers
+        .iter()
+        .find(|signer| signer.author() == proposer)
+        .unwrap();
+    let other_signer = signers
+        .iter()
+        .find(|signer| signer.author() != proposer)
+        .unwrap();
+    let proposer_election: Box<dyn ProposerElection> = Box::new(election);
+    let good_proposal = Block::new_proposal(
+        Payload::empty(false),
+        round,
+        1,
+        certificate_for_genesis(),
+        signer,
+    );
+    assert!(proposer_election.is_valid_proposal(&good_proposal));
+    let bad_proposal = Block::new_proposal(
+        Payload::empty(false),
+        round,
+        1,
+        certificate_for_genesis(),
+        other_signer,
+    );
+    assert!(!proposer_election.is_valid_proposal(&bad_proposal));
+    let equivocation = Block::new_proposal(
+        Payload::empty(false),
+        round,
+        2,
+        certificate_for_genesis(),
+        signer,
+    );
+    // another proposal from the valid proposer should fail
+    assert!(!proposer_election.is_valid_proposal(&equivocation));
+    // good proposal still passes
+    assert!(proposer_election.is_valid_proposal(&good_proposal));
+}
@@ -10,6 +10,7 @@ use consensus_types::{
     block_retrieval::{BlockRetrievalRequest, BlockRetrievalResponse},
     epoch_retrieval::EpochRetrievalRequest,
     experimental::{commit_decision::CommitDecision, commit_vote::CommitVote},
+    proof_of_store::{Batch, BatchRequest, ProofOfStore, SignedBatchInfo},
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
@@ -22,6 +23,7 @@ use network::{
     constants::NETWORK_CHANNEL_SIZE,
     error::NetworkError,
     peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    peer_reputation::ReputationEvent,
     protocols::{
         network::{NetworkEvents, NetworkSender, NewNetworkSender},
         rpc::error::RpcError,
@@ -59,6 +61,15 @@ pub enum ConsensusMsg {
     /// than 2f + 1 signatures on the commit proposal. This part is not on the critical path, but
     /// it can save slow machines to quickly confirm the execution result.
     CommitDecisionMsg(Box<CommitDecision>),
+    /// Batch of transactions broadcast by its author to the quorum store of the validators, also
+    /// carries the response to a BatchRequest.
+    BatchMsg(Box<Batch>),
+    /// Signature of a validator over the info of a batch it stored, sent back to the author.
+    SignedBatchInfoMsg(Box<SignedBatchInfo>),
+    /// Proof that a quorum of validators stored a batch, broadcast by the author of the batch.
+    ProofOfStoreMsg(Box<ProofOfStore>),
+    /// RPC to get a batch that is missing locally from one of the signers of its proof.
+    BatchRequestMsg(Box<BatchRequest>),
 }
 
 /// The interface from Network to Consensus layer.
@@ -170,6 +181,12 @@ impl ConsensusNetworkSender {
             .await
     }
 
+    /// Report a peer that sent an invalid message to the network.
+    pub fn report_invalid_message(&mut self, peer: PeerId) -> Result<(), NetworkError> {
+        self.network_sender
+            .report_peer(peer, ReputationEvent::InvalidMessage)
+    }
+
     /// Initialize a shared hashmap about connections metadata that is updated by the receiver.
     pub fn initialize(&mut self, connections: Arc<RwLock<HashMap<PeerId, SupportedProtocols>>>) {
         self.peers_protocols = Some(connections);
@@ -9,7 +9,7 @@ use crate::{
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
-    common::Author,
+    common::{Author, Payload},
     proposal_msg::ProposalMsg,
     sync_info::SyncInfo,
     vote::Vote,
@@ -370,30 +370,61 @@ impl NetworkPlayground {
     pub async fn start(mut self) {
         // Take the next queued message
         while let Some((src_twin_id, net_req)) = self.outbound_msgs_rx.next().await {
-            // Convert PeerManagerRequest to corresponding PeerManagerNotification,
-            // and extract destination peer
-            let (dst, msg) = match &net_req {
-                PeerManagerRequest::SendDirectSend(dst_inner, msg_inner) => {
-                    (*dst_inner, msg_inner.clone())
-                }
-                msg_inner => panic!(
-                    "[network playground] Unexpected PeerManagerRequest: {:?}",
-                    msg_inner
-                ),
-            };
+            self.route_message(src_twin_id, net_req, None).await;
+        }
+    }
 
-            let dst_twin_ids = self.get_twin_ids(dst);
+    /// Delivers messages like `start`, except for the proposals and votes of the rounds above
+    /// `max_round`, and returns once no message was sent for `idle_timeout`.
+    ///
+    /// Meant for nodes running on mock time, which only make progress through messages: once
+    /// the messages of the rounds up to `max_round` are exchanged the nodes are stuck, so the
+    /// outcome doesn't depend on how long the nodes run.
+    pub async fn run_until_round(mut self, max_round: u64, idle_timeout: Duration) {
+        while let Ok(Some((src_twin_id, net_req))) =
+            tokio::time::timeout(idle_timeout, self.outbound_msgs_rx.next()).await
+        {
+            self.route_message(src_twin_id, net_req, Some(max_round))
+                .await;
+        }
+    }
 
-            for dst_twin_id in dst_twin_ids.iter() {
-                let msg_notif =
-                    PeerManagerNotification::RecvMessage(src_twin_id.author, msg.clone());
-                let consensus_msg = msg.protocol_id.from_bytes(&msg.mdata).unwrap();
+    /// Delivers the message to all the twins of its destination, unless dropped by the drop
+    /// config or above `max_round`.
+    async fn route_message(
+        &mut self,
+        src_twin_id: TwinId,
+        net_req: PeerManagerRequest,
+        max_round: Option<u64>,
+    ) {
+        // Convert PeerManagerRequest to corresponding PeerManagerNotification,
+        // and extract destination peer
+        let (dst, msg) = match &net_req {
+            PeerManagerRequest::SendDirectSend(dst_inner, msg_inner) => {
+                (*dst_inner, msg_inner.clone())
+            }
+            msg_inner => panic!(
+                "[network playground] Unexpected PeerManagerRequest: {:?}",
+                msg_inner
+            ),
+        };
+        if let Some(max_round) = max_round {
+            let consensus_msg = msg.protocol_id.from_bytes(&msg.mdata).unwrap();
+            if Self::get_message_round(consensus_msg).map_or(false, |r| r > max_round) {
+                return;
+            }
+        }
 
-                // Deliver and copy message it if it's not dropped
-                if !self.is_message_dropped(&src_twin_id, dst_twin_id, consensus_msg) {
-                    self.deliver_message(src_twin_id, *dst_twin_id, msg_notif)
-                        .await;
-                }
+        let dst_twin_ids = self.get_twin_ids(dst);
+
+        for dst_twin_id in dst_twin_ids.iter() {
+            let msg_notif = PeerManagerNotification::RecvMessage(src_twin_id.author, msg.clone());
+            let consensus_msg = msg.protocol_id.from_bytes(&msg.mdata).unwrap();
+
+            // Deliver and copy message it if it's not dropped
+            if !self.is_message_dropped(&src_twin_id, dst_twin_id, consensus_msg) {
+                self.deliver_message(src_twin_id, *dst_twin_id, msg_notif)
+                    .await;
             }
         }
     }
@@ -596,7 +627,13 @@ mod tests {
         );
         let previous_qc = certificate_for_genesis();
         let proposal = ProposalMsg::new(
-            Block::new_proposal(vec![], 1, 1, previous_qc.clone(), &signers[0]),
+            Block::new_proposal(
+                Payload::empty(false),
+                1,
+                1,
+                previous_qc.clone(),
+                &signers[0],
+            ),
             SyncInfo::new(previous_qc.clone(), previous_qc, None, None),
         );
         timed_block_on(&mut runtime, async {
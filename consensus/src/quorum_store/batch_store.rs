@@ -0,0 +1,344 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{consensusdb::ConsensusDB, network::NetworkSender};
+use anyhow::{bail, ensure, format_err};
+use consensus_types::{
+    common::{Author, Payload},
+    proof_of_store::{Batch, BatchRequest, ProofOfStore},
+};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::transaction::SignedTransaction;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+#[path = "batch_store_test.rs"]
+mod batch_store_test;
+
+/// Number of committed batches that are kept around to serve the validators that are still
+/// executing the blocks referring to them.
+const COMMITTED_BATCHES_TO_KEEP: usize = 100;
+
+const BATCH_RETRIEVAL_TIMEOUT: Duration = Duration::from_millis(1000);
+
+struct StoredBatch {
+    batch: Batch,
+    received: Instant,
+}
+
+struct BatchStoreInner {
+    batches: HashMap<HashValue, StoredBatch>,
+    // Committed digests in commit order, the oldest ones are deleted from the store.
+    committed: VecDeque<HashValue>,
+    committed_set: HashSet<HashValue>,
+    // Number of stored batches per author that are not committed yet.
+    uncommitted_per_author: HashMap<Author, usize>,
+}
+
+impl BatchStoreInner {
+    fn recount_uncommitted(&mut self) {
+        self.uncommitted_per_author.clear();
+        for (digest, stored) in &self.batches {
+            if !self.committed_set.contains(digest) {
+                *self
+                    .uncommitted_per_author
+                    .entry(stored.batch.author())
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    fn remove_uncommitted(&mut self, digest: &HashValue) {
+        if let Some(stored) = self.batches.get(digest) {
+            if let Some(count) = self.uncommitted_per_author.get_mut(&stored.batch.author()) {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// BatchStore keeps the batches received by the quorum store, so that the payloads of the blocks
+/// that only carry proofs of store can be resolved back to their transactions for execution.
+/// The batches are persisted in ConsensusDB (if provided) to be able to re-execute the pending
+/// blocks after a restart.
+/// To bound the memory a validator can make the others spend on its batches, every author can
+/// only have `max_batches_per_author` uncommitted batches stored, and uncommitted batches are
+/// dropped after `batch_expiry`.
+pub struct BatchStore {
+    db: Option<Arc<ConsensusDB>>,
+    max_batches_per_author: usize,
+    batch_expiry: Duration,
+    inner: Mutex<BatchStoreInner>,
+}
+
+impl BatchStore {
+    pub fn new(
+        db: Arc<ConsensusDB>,
+        max_batches_per_author: usize,
+        batch_expiry: Duration,
+    ) -> Self {
+        let batches = db
+            .get_batches()
+            .expect("Failed to load the batches of the quorum store");
+        Self::new_internal(Some(db), batches, max_batches_per_author, batch_expiry)
+    }
+
+    pub fn new_in_memory(max_batches_per_author: usize, batch_expiry: Duration) -> Self {
+        Self::new_internal(None, HashMap::new(), max_batches_per_author, batch_expiry)
+    }
+
+    fn new_internal(
+        db: Option<Arc<ConsensusDB>>,
+        batches: HashMap<HashValue, Batch>,
+        max_batches_per_author: usize,
+        batch_expiry: Duration,
+    ) -> Self {
+        // The batches loaded after a restart are given a full expiry period.
+        let now = Instant::now();
+        let mut inner = BatchStoreInner {
+            batches: batches
+                .into_iter()
+                .map(|(digest, batch)| {
+                    (
+                        digest,
+                        StoredBatch {
+                            batch,
+                            received: now,
+                        },
+                    )
+                })
+                .collect(),
+            committed: VecDeque::new(),
+            committed_set: HashSet::new(),
+            uncommitted_per_author: HashMap::new(),
+        };
+        inner.recount_uncommitted();
+        Self {
+            db,
+            max_batches_per_author,
+            batch_expiry,
+            inner: Mutex::new(inner),
+        }
+    }
+
+    /// Stores a batch broadcast by its author, the caller is responsible for verifying it.
+    /// Fails if the author already has `max_batches_per_author` uncommitted batches stored.
+    pub fn insert(&self, batch: Batch) -> anyhow::Result<()> {
+        self.insert_internal(batch, true)
+    }
+
+    /// Stores a batch certified by a proof of store, e.g. fetched to execute a block: it doesn't
+    /// count against the quota of its author as a quorum of validators already stored it.
+    pub fn insert_certified(&self, batch: Batch) -> anyhow::Result<()> {
+        self.insert_internal(batch, false)
+    }
+
+    fn insert_internal(&self, batch: Batch, enforce_quota: bool) -> anyhow::Result<()> {
+        self.expire(Instant::now())?;
+        if self.contains(&batch.digest()) {
+            return Ok(());
+        }
+        if enforce_quota {
+            let inner = self.inner.lock();
+            let num_batches = inner
+                .uncommitted_per_author
+                .get(&batch.author())
+                .copied()
+                .unwrap_or(0);
+            ensure!(
+                num_batches < self.max_batches_per_author,
+                "Author {} already has {} uncommitted batches stored",
+                batch.author(),
+                num_batches
+            );
+        }
+        if let Some(db) = &self.db {
+            db.save_batch(&batch)?;
+        }
+        let mut inner = self.inner.lock();
+        let digest = batch.digest();
+        if !inner.committed_set.contains(&digest) {
+            *inner
+                .uncommitted_per_author
+                .entry(batch.author())
+                .or_insert(0) += 1;
+        }
+        inner.batches.insert(
+            digest,
+            StoredBatch {
+                batch,
+                received: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Drops the uncommitted batches received more than `batch_expiry` before `now`.
+    pub fn expire(&self, now: Instant) -> anyhow::Result<()> {
+        let mut to_delete = vec![];
+        {
+            let mut inner = self.inner.lock();
+            for (digest, stored) in &inner.batches {
+                if !inner.committed_set.contains(digest)
+                    && now.saturating_duration_since(stored.received) >= self.batch_expiry
+                {
+                    to_delete.push(*digest);
+                }
+            }
+            for digest in &to_delete {
+                inner.remove_uncommitted(digest);
+                inner.batches.remove(digest);
+            }
+        }
+        if !to_delete.is_empty() {
+            debug!("[QuorumStore] Expired {} batches", to_delete.len());
+            if let Some(db) = &self.db {
+                db.delete_batches(to_delete)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, digest: &HashValue) -> bool {
+        self.inner.lock().batches.contains_key(digest)
+    }
+
+    pub fn get_batch(&self, digest: &HashValue) -> Option<Batch> {
+        self.inner
+            .lock()
+            .batches
+            .get(digest)
+            .map(|stored| stored.batch.clone())
+    }
+
+    pub fn is_committed(&self, digest: &HashValue) -> bool {
+        self.inner.lock().committed_set.contains(digest)
+    }
+
+    /// Resolves the payload of a block to the transactions to execute.
+    pub fn get_transactions(
+        &self,
+        payload: Option<&Payload>,
+    ) -> anyhow::Result<Vec<SignedTransaction>> {
+        match payload {
+            None => Ok(vec![]),
+            Some(Payload::DirectMempool(txns)) => Ok(txns.clone()),
+            Some(Payload::InQuorumStore(proofs)) => {
+                let inner = self.inner.lock();
+                let mut txns = vec![];
+                for proof in proofs {
+                    let stored = inner.batches.get(&proof.digest()).ok_or_else(|| {
+                        format_err!("Batch {} is missing from the quorum store", proof.digest())
+                    })?;
+                    txns.extend(stored.batch.txns().iter().cloned());
+                }
+                Ok(txns)
+            }
+        }
+    }
+
+    /// Records the batches of the committed payloads, so that their proofs are not proposed again.
+    pub fn mark_committed(&self, payloads: Vec<&Payload>) -> anyhow::Result<()> {
+        let mut to_delete = vec![];
+        {
+            let mut inner = self.inner.lock();
+            for payload in payloads {
+                if let Payload::InQuorumStore(proofs) = payload {
+                    for proof in proofs {
+                        if inner.committed_set.contains(&proof.digest()) {
+                            continue;
+                        }
+                        inner.remove_uncommitted(&proof.digest());
+                        inner.committed_set.insert(proof.digest());
+                        inner.committed.push_back(proof.digest());
+                    }
+                }
+            }
+            while inner.committed.len() > COMMITTED_BATCHES_TO_KEEP {
+                let digest = inner.committed.pop_front().expect("must exist");
+                inner.committed_set.remove(&digest);
+                inner.batches.remove(&digest);
+                to_delete.push(digest);
+            }
+        }
+        if let Some(db) = &self.db {
+            db.delete_batches(to_delete)?;
+        }
+        Ok(())
+    }
+
+    /// Drops the batches of the previous epochs: their proofs can't be proposed anymore.
+    pub fn new_epoch(&self, epoch: u64) -> anyhow::Result<()> {
+        let mut to_delete = vec![];
+        {
+            let mut inner = self.inner.lock();
+            inner.batches.retain(|digest, stored| {
+                if stored.batch.epoch() < epoch {
+                    to_delete.push(*digest);
+                    false
+                } else {
+                    true
+                }
+            });
+            inner.committed.clear();
+            inner.committed_set.clear();
+            inner.recount_uncommitted();
+        }
+        if let Some(db) = &self.db {
+            db.delete_batches(to_delete)?;
+        }
+        Ok(())
+    }
+}
+
+/// Retrieves the batches of the payload that are missing locally from the signers of their
+/// proofs: a quorum of validators stored the batch, so at least one honest validator can serve it.
+pub async fn fetch_missing_batches(
+    network: &mut NetworkSender,
+    batch_store: &BatchStore,
+    payload: Option<&Payload>,
+) -> anyhow::Result<()> {
+    let proofs = match payload {
+        Some(Payload::InQuorumStore(proofs)) => proofs,
+        _ => return Ok(()),
+    };
+    for proof in proofs {
+        if batch_store.contains(&proof.digest()) {
+            continue;
+        }
+        let batch = fetch_batch(network, proof).await?;
+        batch_store.insert_certified(batch)?;
+    }
+    Ok(())
+}
+
+async fn fetch_batch(network: &mut NetworkSender, proof: &ProofOfStore) -> anyhow::Result<Batch> {
+    let signers: Vec<Author> = proof.signers().cloned().collect();
+    for signer in signers {
+        if signer == network.author() {
+            continue;
+        }
+        match network
+            .request_batch(
+                BatchRequest::new(proof.epoch(), proof.digest()),
+                signer,
+                BATCH_RETRIEVAL_TIMEOUT,
+            )
+            .await
+        {
+            Ok(batch) => return Ok(batch),
+            Err(e) => warn!(
+                remote_peer = signer,
+                digest = proof.digest(),
+                error = ?e, "Failed to fetch batch, trying another signer",
+            ),
+        }
+    }
+    bail!("Failed to fetch batch {} from the signers", proof.digest())
+}
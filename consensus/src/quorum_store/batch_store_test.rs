@@ -0,0 +1,165 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::quorum_store::BatchStore;
+use consensus_types::{
+    block::block_test_utils::random_payload,
+    common::Payload,
+    proof_of_store::{Batch, ProofOfStore},
+};
+use diem_types::account_address::AccountAddress;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+const MAX_BATCHES_PER_AUTHOR: usize = 2;
+const BATCH_EXPIRY: Duration = Duration::from_secs(60);
+
+fn new_batch_store() -> BatchStore {
+    BatchStore::new_in_memory(MAX_BATCHES_PER_AUTHOR, BATCH_EXPIRY)
+}
+
+fn random_batch(epoch: u64, batch_id: u64) -> Batch {
+    let txns = match random_payload(3) {
+        Payload::DirectMempool(txns) => txns,
+        Payload::InQuorumStore(_) => unreachable!(),
+    };
+    Batch::new(AccountAddress::random(), epoch, batch_id, txns)
+}
+
+fn payload_for(batches: &[&Batch]) -> Payload {
+    Payload::InQuorumStore(
+        batches
+            .iter()
+            .map(|batch| ProofOfStore::new(batch.info().clone(), BTreeMap::new()))
+            .collect(),
+    )
+}
+
+#[test]
+fn test_get_transactions() {
+    let batch_store = new_batch_store();
+    let batch_1 = random_batch(1, 0);
+    let batch_2 = random_batch(1, 1);
+    let payload = payload_for(&[&batch_1, &batch_2]);
+
+    assert!(batch_store.get_transactions(Some(&payload)).is_err());
+
+    batch_store.insert(batch_1.clone()).unwrap();
+    batch_store.insert(batch_2.clone()).unwrap();
+    let mut expected = batch_1.txns().to_vec();
+    expected.extend(batch_2.txns().iter().cloned());
+    assert_eq!(
+        batch_store.get_transactions(Some(&payload)).unwrap(),
+        expected
+    );
+
+    let direct = Payload::DirectMempool(batch_1.txns().to_vec());
+    assert_eq!(
+        batch_store.get_transactions(Some(&direct)).unwrap(),
+        batch_1.txns()
+    );
+    assert!(batch_store.get_transactions(None).unwrap().is_empty());
+}
+
+#[test]
+fn test_mark_committed() {
+    let batch_store = new_batch_store();
+    let batch = random_batch(1, 0);
+    batch_store.insert(batch.clone()).unwrap();
+    assert!(!batch_store.is_committed(&batch.digest()));
+
+    batch_store
+        .mark_committed(vec![&payload_for(&[&batch])])
+        .unwrap();
+    assert!(batch_store.is_committed(&batch.digest()));
+    // the batch is kept to serve the validators that are behind
+    assert!(batch_store.contains(&batch.digest()));
+}
+
+#[test]
+fn test_new_epoch() {
+    let batch_store = new_batch_store();
+    let old_batch = random_batch(1, 0);
+    let new_batch = random_batch(2, 0);
+    batch_store.insert(old_batch.clone()).unwrap();
+    batch_store.insert(new_batch.clone()).unwrap();
+    batch_store
+        .mark_committed(vec![&payload_for(&[&old_batch])])
+        .unwrap();
+
+    batch_store.new_epoch(2).unwrap();
+    assert!(!batch_store.contains(&old_batch.digest()));
+    assert!(!batch_store.is_committed(&old_batch.digest()));
+    assert_eq!(batch_store.get_batch(&new_batch.digest()), Some(new_batch));
+}
+
+#[test]
+fn test_batches_per_author() {
+    let batch_store = new_batch_store();
+    let author = AccountAddress::random();
+    let batches: Vec<_> = (0..3)
+        .map(|batch_id| {
+            Batch::new(
+                author,
+                1,
+                batch_id,
+                random_batch(1, batch_id).into_transactions(),
+            )
+        })
+        .collect();
+    batch_store.insert(batches[0].clone()).unwrap();
+    batch_store.insert(batches[1].clone()).unwrap();
+    // Storing the same batch again doesn't count against the quota
+    batch_store.insert(batches[1].clone()).unwrap();
+    batch_store.insert(batches[2].clone()).unwrap_err();
+    assert!(!batch_store.contains(&batches[2].digest()));
+
+    // Batches certified by a proof of store are stored regardless of the quota
+    batch_store.insert_certified(batches[2].clone()).unwrap();
+    assert!(batch_store.contains(&batches[2].digest()));
+
+    // Committed batches don't count against the quota
+    batch_store
+        .mark_committed(vec![&payload_for(&[&batches[0], &batches[1], &batches[2]])])
+        .unwrap();
+    batch_store
+        .insert(Batch::new(
+            author,
+            1,
+            3,
+            random_batch(1, 3).into_transactions(),
+        ))
+        .unwrap();
+}
+
+#[test]
+fn test_expire() {
+    let batch_store = new_batch_store();
+    let committed = random_batch(1, 0);
+    let uncommitted = random_batch(1, 1);
+    batch_store.insert(committed.clone()).unwrap();
+    batch_store.insert(uncommitted.clone()).unwrap();
+    batch_store
+        .mark_committed(vec![&payload_for(&[&committed])])
+        .unwrap();
+
+    batch_store.expire(Instant::now()).unwrap();
+    assert!(batch_store.contains(&uncommitted.digest()));
+
+    // Only the uncommitted batches expire, and their authors can store new ones
+    batch_store.expire(Instant::now() + BATCH_EXPIRY).unwrap();
+    assert!(batch_store.contains(&committed.digest()));
+    assert!(!batch_store.contains(&uncommitted.digest()));
+    for batch_id in 2..(2 + MAX_BATCHES_PER_AUTHOR as u64) {
+        batch_store
+            .insert(Batch::new(
+                uncommitted.author(),
+                1,
+                batch_id,
+                random_batch(1, batch_id).into_transactions(),
+            ))
+            .unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The quorum store decouples the dissemination of the transactions from the proposals: every
+//! validator periodically pulls a batch of transactions from mempool and broadcasts it, the
+//! validators that stored the batch send back their signature over its info, and once a quorum of
+//! signatures is gathered the author broadcasts the resulting proof of store. Proposals then only
+//! carry proofs of store, which are resolved back to the transactions from the BatchStore before
+//! execution.
+
+use crate::{
+    error::MempoolError, metrics_safety_rules::MetricsSafetyRules, network::NetworkSender,
+    network_interface::ConsensusMsg, state_replication::TxnManager,
+};
+use anyhow::{bail, ensure, Context};
+use consensus_types::{
+    common::{Author, Payload},
+    proof_of_store::{Batch, BatchInfo, ProofOfStore, SignedBatchInfo},
+};
+use diem_crypto::{ed25519::Ed25519Signature, HashValue};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::{epoch_state::EpochState, transaction::SignedTransaction};
+use executor_types::StateComputeResult;
+use safety_rules::TSafetyRules;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+mod batch_store;
+
+pub use batch_store::{fetch_missing_batches, BatchStore};
+
+/// A batch created by this validator that is still pending: its transactions are excluded from the
+/// next pulls from mempool until it is committed or dropped.
+struct LocalBatch {
+    info: BatchInfo,
+    txns: Payload,
+    signatures: BTreeMap<Author, Ed25519Signature>,
+    created: Instant,
+    certified: bool,
+}
+
+struct QuorumStoreState {
+    next_batch_id: u64,
+    local_batches: HashMap<HashValue, LocalBatch>,
+    // Proofs that can be proposed with the time they were received, in the order they were
+    // received.
+    proofs: Vec<(ProofOfStore, Instant)>,
+    proof_digests: HashSet<HashValue>,
+}
+
+/// QuorumStore is the per-epoch part of the quorum store: it creates and broadcasts the batches of
+/// this validator, stores and signs the batches of the others and aggregates the proofs of store.
+/// It implements TxnManager so that the ProposalGenerator pulls proofs of store instead of
+/// transactions.
+pub struct QuorumStore {
+    author: Author,
+    epoch_state: EpochState,
+    mempool: Arc<dyn TxnManager>,
+    batch_store: Arc<BatchStore>,
+    network: NetworkSender,
+    safety_rules: Arc<Mutex<MetricsSafetyRules>>,
+    max_batch_size: u64,
+    proof_timeout: Duration,
+    // Proofs are no longer proposed after this long: the batches they certify may have expired
+    // from the BatchStores, which would require fetching them before execution.
+    proof_expiry: Duration,
+    state: Mutex<QuorumStoreState>,
+}
+
+impl QuorumStore {
+    pub fn new(
+        author: Author,
+        epoch_state: EpochState,
+        mempool: Arc<dyn TxnManager>,
+        batch_store: Arc<BatchStore>,
+        network: NetworkSender,
+        safety_rules: Arc<Mutex<MetricsSafetyRules>>,
+        max_batch_size: u64,
+        proof_timeout: Duration,
+        proof_expiry: Duration,
+    ) -> Self {
+        Self {
+            author,
+            epoch_state,
+            mempool,
+            batch_store,
+            network,
+            safety_rules,
+            max_batch_size,
+            proof_timeout,
+            proof_expiry,
+            state: Mutex::new(QuorumStoreState {
+                next_batch_id: 0,
+                local_batches: HashMap::new(),
+                proofs: vec![],
+                proof_digests: HashSet::new(),
+            }),
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch_state.epoch
+    }
+
+    /// Spawns the task creating a new batch every `interval`, it stops once the QuorumStore is
+    /// dropped at the end of the epoch.
+    pub fn start_batch_generator(self: &Arc<Self>, interval: Duration) {
+        let quorum_store = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let quorum_store = match quorum_store.upgrade() {
+                    Some(quorum_store) => quorum_store,
+                    None => break,
+                };
+                if let Err(e) = quorum_store.create_batch().await {
+                    warn!(error = ?e, "[QuorumStore] Failed to create batch");
+                }
+            }
+        });
+    }
+
+    /// Pulls the transactions that are not part of any pending local batch from mempool and
+    /// broadcasts them as a new batch (including to self, so that it's stored and signed locally).
+    pub async fn create_batch(&self) -> anyhow::Result<()> {
+        let exclude = {
+            let mut state = self.state.lock();
+            let batch_store = &self.batch_store;
+            let proof_timeout = self.proof_timeout;
+            state.local_batches.retain(|digest, batch| {
+                !batch_store.is_committed(digest)
+                    && (batch.certified || batch.created.elapsed() < proof_timeout)
+            });
+            state
+                .local_batches
+                .values()
+                .map(|batch| batch.txns.clone())
+                .collect::<Vec<_>>()
+        };
+        let txns = match self
+            .mempool
+            .pull_txns(self.max_batch_size, exclude.iter().collect())
+            .await?
+        {
+            Payload::DirectMempool(txns) => txns,
+            Payload::InQuorumStore(_) => bail!("[QuorumStore] Mempool returned proofs of store"),
+        };
+        if txns.is_empty() {
+            return Ok(());
+        }
+        let batch = {
+            let mut state = self.state.lock();
+            let batch = Batch::new(self.author, self.epoch(), state.next_batch_id, txns);
+            state.next_batch_id += 1;
+            state.local_batches.insert(
+                batch.digest(),
+                LocalBatch {
+                    info: batch.info().clone(),
+                    txns: Payload::DirectMempool(batch.txns().to_vec()),
+                    signatures: BTreeMap::new(),
+                    created: Instant::now(),
+                    certified: false,
+                },
+            );
+            batch
+        };
+        debug!("[QuorumStore] Broadcast {}", batch.info());
+        self.network
+            .clone()
+            .broadcast(ConsensusMsg::BatchMsg(Box::new(batch)))
+            .await;
+        Ok(())
+    }
+
+    /// Stores the batch and sends back the signature over its info to the author.
+    pub async fn process_batch(&self, peer: Author, batch: Batch) -> anyhow::Result<()> {
+        ensure!(
+            batch.author() == peer,
+            "[QuorumStore] Batch from {} is authored by {}",
+            peer,
+            batch.author()
+        );
+        ensure!(
+            batch.epoch() == self.epoch(),
+            "[QuorumStore] Batch from epoch {}, local epoch {}",
+            batch.epoch(),
+            self.epoch()
+        );
+        ensure!(
+            self.epoch_state
+                .verifier
+                .get_voting_power(&batch.author())
+                .is_some(),
+            "[QuorumStore] Batch author {} is not a validator",
+            batch.author()
+        );
+        batch.verify()?;
+        let info = batch.info().clone();
+        self.batch_store
+            .insert(batch)
+            .context("[QuorumStore] Failed to store batch")?;
+        let signature = self.safety_rules.lock().sign_batch_info(&info)?;
+        let signed_info = SignedBatchInfo::new(info, self.author, signature);
+        self.network
+            .send(
+                ConsensusMsg::SignedBatchInfoMsg(Box::new(signed_info)),
+                vec![peer],
+            )
+            .await;
+        Ok(())
+    }
+
+    /// Aggregates the signatures over a local batch and broadcasts its proof of store once a
+    /// quorum of validators stored it.
+    pub async fn process_signed_batch_info(
+        &self,
+        peer: Author,
+        signed_info: SignedBatchInfo,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            signed_info.signer() == peer,
+            "[QuorumStore] SignedBatchInfo from {} is signed by {}",
+            peer,
+            signed_info.signer()
+        );
+        signed_info.verify(&self.epoch_state.verifier)?;
+        let proof = {
+            let mut state = self.state.lock();
+            let batch = match state.local_batches.get_mut(&signed_info.info().digest()) {
+                Some(batch) => batch,
+                // The batch was already committed or dropped.
+                None => return Ok(()),
+            };
+            ensure!(
+                &batch.info == signed_info.info(),
+                "[QuorumStore] SignedBatchInfo doesn't match local {}",
+                batch.info
+            );
+            batch
+                .signatures
+                .insert(signed_info.signer(), signed_info.signature().clone());
+            if batch.certified
+                || self
+                    .epoch_state
+                    .verifier
+                    .check_voting_power(batch.signatures.keys())
+                    .is_err()
+            {
+                return Ok(());
+            }
+            batch.certified = true;
+            ProofOfStore::new(batch.info.clone(), batch.signatures.clone())
+        };
+        debug!("[QuorumStore] Broadcast {}", proof);
+        self.network
+            .clone()
+            .broadcast(ConsensusMsg::ProofOfStoreMsg(Box::new(proof)))
+            .await;
+        Ok(())
+    }
+
+    /// Adds the proof of store to the ones that can be proposed.
+    pub fn process_proof(&self, proof: ProofOfStore) -> anyhow::Result<()> {
+        ensure!(
+            proof.epoch() == self.epoch(),
+            "[QuorumStore] ProofOfStore from epoch {}, local epoch {}",
+            proof.epoch(),
+            self.epoch()
+        );
+        proof.verify(&self.epoch_state.verifier)?;
+        if self.batch_store.is_committed(&proof.digest()) {
+            return Ok(());
+        }
+        let mut state = self.state.lock();
+        self.prune_proofs(&mut state, Instant::now());
+        if state.proof_digests.insert(proof.digest()) {
+            state.proofs.push((proof, Instant::now()));
+        }
+        Ok(())
+    }
+
+    /// Drops the proofs of the committed batches and the ones received more than `proof_expiry`
+    /// before `now`.
+    fn prune_proofs(&self, state: &mut QuorumStoreState, now: Instant) {
+        let batch_store = &self.batch_store;
+        let proof_expiry = self.proof_expiry;
+        let mut pruned = vec![];
+        state.proofs.retain(|(proof, received)| {
+            if batch_store.is_committed(&proof.digest())
+                || now.saturating_duration_since(*received) >= proof_expiry
+            {
+                pruned.push(proof.digest());
+                false
+            } else {
+                true
+            }
+        });
+        for digest in pruned {
+            state.proof_digests.remove(&digest);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TxnManager for QuorumStore {
+    /// Returns the proofs of store of batches that are neither committed nor pending in the
+    /// branch being extended, with at most `max_size` transactions in total.
+    async fn pull_txns(
+        &self,
+        max_size: u64,
+        exclude_payloads: Vec<&Payload>,
+    ) -> Result<Payload, MempoolError> {
+        let mut exclude_digests = HashSet::new();
+        for payload in exclude_payloads {
+            if let Payload::InQuorumStore(proofs) = payload {
+                exclude_digests.extend(proofs.iter().map(|proof| proof.digest()));
+            }
+        }
+        let mut state = self.state.lock();
+        self.prune_proofs(&mut state, Instant::now());
+
+        let mut num_txns = 0;
+        let mut proofs = vec![];
+        for (proof, _) in &state.proofs {
+            let batch_txns = proof.info().num_txns();
+            if exclude_digests.contains(&proof.digest()) || num_txns + batch_txns > max_size {
+                continue;
+            }
+            num_txns += batch_txns;
+            proofs.push(proof.clone());
+        }
+        Ok(Payload::InQuorumStore(proofs))
+    }
+
+    async fn notify(
+        &self,
+        txns: &[SignedTransaction],
+        compute_result: &StateComputeResult,
+    ) -> Result<(), MempoolError> {
+        self.mempool.notify(txns, compute_result).await
+    }
+}
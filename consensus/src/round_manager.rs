@@ -19,6 +19,7 @@ use crate::{
     network_interface::ConsensusMsg,
     pending_votes::VoteReceptionResult,
     persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
+    quorum_store::{fetch_missing_batches, BatchStore},
     state_replication::{StateComputer, TxnManager},
 };
 use anyhow::{bail, ensure, Context, Result};
@@ -136,6 +137,7 @@ pub struct RecoveryManager {
     network: NetworkSender,
     storage: Arc<dyn PersistentLivenessStorage>,
     state_computer: Arc<dyn StateComputer>,
+    batch_store: Arc<BatchStore>,
     last_committed_round: Round,
     onchain_config: OnChainConsensusConfig,
 }
@@ -146,6 +148,7 @@ impl RecoveryManager {
         network: NetworkSender,
         storage: Arc<dyn PersistentLivenessStorage>,
         state_computer: Arc<dyn StateComputer>,
+        batch_store: Arc<BatchStore>,
         last_committed_round: Round,
         onchain_config: OnChainConsensusConfig,
     ) -> Self {
@@ -154,6 +157,7 @@ impl RecoveryManager {
             network,
             storage,
             state_computer,
+            batch_store,
             last_committed_round,
             onchain_config,
         }
@@ -186,7 +190,8 @@ impl RecoveryManager {
             sync_info.epoch() == self.epoch_state.epoch,
             "[RecoveryManager] Received sync info is in different epoch than committed block"
         );
-        let mut retriever = BlockRetriever::new(self.network.clone(), peer);
+        let mut retriever =
+            BlockRetriever::new(self.network.clone(), peer, self.batch_store.clone());
         let recovery_data = BlockStore::fast_forward_sync(
             sync_info.highest_ordered_cert(),
             sync_info.highest_ledger_info().clone(),
@@ -222,6 +227,7 @@ pub struct RoundManager {
     safety_rules: Arc<Mutex<MetricsSafetyRules>>,
     network: NetworkSender,
     txn_manager: Arc<dyn TxnManager>,
+    batch_store: Arc<BatchStore>,
     storage: Arc<dyn PersistentLivenessStorage>,
     sync_only: bool,
     back_pressure: Arc<AtomicU64>,
@@ -240,6 +246,7 @@ impl RoundManager {
         safety_rules: Arc<Mutex<MetricsSafetyRules>>,
         network: NetworkSender,
         txn_manager: Arc<dyn TxnManager>,
+        batch_store: Arc<BatchStore>,
         storage: Arc<dyn PersistentLivenessStorage>,
         sync_only: bool,
         onchain_config: OnChainConsensusConfig,
@@ -261,6 +268,7 @@ impl RoundManager {
             safety_rules,
             network,
             txn_manager,
+            batch_store,
             storage,
             sync_only,
             back_pressure: Arc::new(AtomicU64::new(0)), // dummy value
@@ -279,6 +287,7 @@ impl RoundManager {
         safety_rules: Arc<Mutex<MetricsSafetyRules>>,
         network: NetworkSender,
         txn_manager: Arc<dyn TxnManager>,
+        batch_store: Arc<BatchStore>,
         storage: Arc<dyn PersistentLivenessStorage>,
         sync_only: bool,
         back_pressure: Arc<AtomicU64>,
@@ -294,6 +303,7 @@ impl RoundManager {
             safety_rules,
             network,
             txn_manager,
+            batch_store,
             storage,
             sync_only,
             back_pressure,
@@ -308,7 +318,7 @@ impl RoundManager {
     }
 
     fn create_block_retriever(&self, author: Author) -> BlockRetriever {
-        BlockRetriever::new(self.network.clone(), author)
+        BlockRetriever::new(self.network.clone(), author, self.batch_store.clone())
     }
 
     /// Leader:
@@ -655,6 +665,10 @@ impl RoundManager {
 
         observe_block(proposal.timestamp_usecs(), BlockStage::SYNCED);
 
+        fetch_missing_batches(&mut self.network, &self.batch_store, proposal.payload())
+            .await
+            .context("[RoundManager] Failed to fetch the batches of the proposal")?;
+
         let proposal_round = proposal.round();
         let vote = self
             .execute_and_vote(proposal)
@@ -686,11 +700,11 @@ impl RoundManager {
         if !self.decoupled_execution {
             // notify mempool about failed txn
             let compute_result = executed_block.compute_result();
-            if let Err(e) = self
-                .txn_manager
-                .notify(executed_block.block(), compute_result)
-                .await
-            {
+            let txns = self
+                .batch_store
+                .get_transactions(executed_block.payload())
+                .context("[RoundManager] Failed to get the executed transactions")?;
+            if let Err(e) = self.txn_manager.notify(&txns, compute_result).await {
                 error!(
                     error = ?e, "[RoundManager] Failed to notify mempool of rejected txns",
                 );
@@ -12,12 +12,14 @@ use crate::{
     network::NetworkSender,
     network_interface::ConsensusNetworkSender,
     persistent_liveness_storage::{PersistentLivenessStorage, RecoveryData},
+    quorum_store::BatchStore,
     round_manager::RoundManager,
     test_utils::{EmptyStateComputer, MockStorage, MockTransactionManager},
     util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use consensus_types::proposal_msg::ProposalMsg;
+use diem_config::config::ConsensusConfig;
 use diem_infallible::Mutex;
 use diem_types::{
     epoch_change::EpochChangeProof,
@@ -165,6 +167,10 @@ fn create_node_for_fuzzing() -> RoundManager {
         ))),
         network,
         Arc::new(MockTransactionManager::new(None)),
+        Arc::new(BatchStore::new_in_memory(
+            ConsensusConfig::default().quorum_store_max_batches_per_author,
+            Duration::from_millis(ConsensusConfig::default().quorum_store_batch_expiry_ms),
+        )),
         storage,
         false,
         OnChainConsensusConfig::default(),
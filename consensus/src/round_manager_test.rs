@@ -14,6 +14,7 @@ use crate::{
     network_interface::{ConsensusMsg, ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
     persistent_liveness_storage::RecoveryData,
+    quorum_store::BatchStore,
     round_manager::RoundManager,
     test_utils::{
         consensus_runtime, timed_block_on, MockStateComputer, MockStorage, MockTransactionManager,
@@ -35,6 +36,7 @@ use consensus_types::{
     timeout_certificate::TimeoutCertificate,
     vote_msg::VoteMsg,
 };
+use diem_config::config::ConsensusConfig;
 use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, Uniform};
 use diem_infallible::Mutex;
 use diem_secure_storage::Storage;
@@ -42,6 +44,7 @@ use diem_types::{
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     on_chain_config::OnChainConsensusConfig,
+    transaction::SignedTransaction,
     validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
     waypoint::Waypoint,
@@ -71,7 +74,7 @@ pub struct NodeSetup {
     safety_rules_manager: SafetyRulesManager,
     all_events: Box<dyn Stream<Item = Event<ConsensusMsg>> + Send + Unpin>,
     commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
-    _state_sync_receiver: mpsc::UnboundedReceiver<Payload>,
+    _state_sync_receiver: mpsc::UnboundedReceiver<Vec<SignedTransaction>>,
     id: usize,
 }
 
@@ -181,10 +184,15 @@ impl NodeSetup {
         let last_vote_sent = initial_data.last_vote();
         let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
         let (state_sync_client, _state_sync_receiver) = mpsc::unbounded();
+        let batch_store = Arc::new(BatchStore::new_in_memory(
+            ConsensusConfig::default().quorum_store_max_batches_per_author,
+            Duration::from_millis(ConsensusConfig::default().quorum_store_batch_expiry_ms),
+        ));
         let state_computer = Arc::new(MockStateComputer::new(
             state_sync_client,
             commit_cb_sender,
             Arc::clone(&storage),
+            batch_store.clone(),
         ));
         let time_service = Arc::new(ClockTimeService::new(executor));
 
@@ -219,6 +227,7 @@ impl NodeSetup {
             Arc::new(Mutex::new(safety_rules)),
             network,
             Arc::new(MockTransactionManager::new(None)),
+            batch_store,
             storage.clone(),
             false,
             OnChainConsensusConfig::default(),
@@ -342,7 +351,13 @@ fn vote_on_successful_proposal() {
         // Start round 1 and clear the message queue
         node.next_proposal().await;
 
-        let proposal = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+        let proposal = Block::new_proposal(
+            Payload::empty(false),
+            1,
+            1,
+            genesis_qc.clone(),
+            &node.signer,
+        );
         let proposal_id = proposal.id();
         node.round_manager.process_proposal(proposal).await.unwrap();
         let vote_msg = node.next_vote().await;
@@ -367,9 +382,15 @@ fn no_vote_on_old_proposal() {
     let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 1);
     let node = &mut nodes[0];
     let genesis_qc = certificate_for_genesis();
-    let new_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let new_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
     let new_block_id = new_block.id();
-    let old_block = Block::new_proposal(vec![], 1, 2, genesis_qc, &node.signer);
+    let old_block = Block::new_proposal(Payload::empty(false), 1, 2, genesis_qc, &node.signer);
     let old_block_id = old_block.id();
     timed_block_on(&mut runtime, async {
         // clear the message queue
@@ -403,8 +424,20 @@ fn no_vote_on_mismatch_round() {
         .pop()
         .unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let correct_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
+    let block_skip_round = Block::new_proposal(
+        Payload::empty(false),
+        2,
+        2,
+        genesis_qc.clone(),
+        &node.signer,
+    );
     timed_block_on(&mut runtime, async {
         let bad_proposal = ProposalMsg::new(
             block_skip_round,
@@ -484,9 +517,20 @@ fn no_vote_on_invalid_proposer() {
     let incorrect_proposer = nodes.pop().unwrap();
     let mut node = nodes.pop().unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_incorrect_proposer =
-        Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &incorrect_proposer.signer);
+    let correct_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
+    let block_incorrect_proposer = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &incorrect_proposer.signer,
+    );
     timed_block_on(&mut runtime, async {
         let bad_proposal = ProposalMsg::new(
             block_incorrect_proposer,
@@ -520,8 +564,20 @@ fn new_round_on_timeout_certificate() {
         .pop()
         .unwrap();
     let genesis_qc = certificate_for_genesis();
-    let correct_block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
-    let block_skip_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &node.signer);
+    let correct_block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
+    let block_skip_round = Block::new_proposal(
+        Payload::empty(false),
+        2,
+        2,
+        genesis_qc.clone(),
+        &node.signer,
+    );
     let timeout = Timeout::new(1, 1);
     let timeout_signature = timeout.sign(&node.signer);
 
@@ -558,7 +614,13 @@ fn response_on_block_retrieval() {
         .unwrap();
 
     let genesis_qc = certificate_for_genesis();
-    let block = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &node.signer);
+    let block = Block::new_proposal(
+        Payload::empty(false),
+        1,
+        1,
+        genesis_qc.clone(),
+        &node.signer,
+    );
     let block_id = block.id();
     let proposal = ProposalMsg::new(
         block,
@@ -659,7 +721,8 @@ fn recover_on_restart() {
     let num_proposals = 100;
     // insert a few successful proposals
     for i in 1..=num_proposals {
-        let proposal = inserter.create_block_with_qc(genesis_qc.clone(), i, i, vec![]);
+        let proposal =
+            inserter.create_block_with_qc(genesis_qc.clone(), i, i, Payload::empty(false));
         let timeout = Timeout::new(1, i - 1);
         let mut tc = TimeoutCertificate::new(timeout.clone());
         tc.add_signature(inserter.signer().author(), inserter.signer().sign(&timeout));
@@ -769,7 +832,7 @@ fn sync_info_sent_on_stale_sync_info() {
     let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.handle().clone(), 2);
     runtime.spawn(playground.start());
     let genesis_qc = certificate_for_genesis();
-    let block_0 = Block::new_proposal(vec![], 1, 1, genesis_qc, &nodes[0].signer);
+    let block_0 = Block::new_proposal(Payload::empty(false), 1, 1, genesis_qc, &nodes[0].signer);
     let parent_block_info = block_0.quorum_cert().certified_block();
     let block_0_quorum_cert = gen_test_certificate(
         vec![&nodes[0].signer, &nodes[1].signer],
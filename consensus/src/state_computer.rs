@@ -2,7 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    consensus_observer::{
+        network::{ObserverMessage, OrderedBlock},
+        ConsensusPublisher,
+    },
     error::StateSyncError,
+    quorum_store::BatchStore,
     state_replication::{StateComputer, StateComputerCommitCallBackType},
 };
 use anyhow::Result;
@@ -22,16 +27,24 @@ use std::{boxed::Box, sync::Arc};
 pub struct ExecutionProxy {
     execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
     state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+    // Resolves the payloads carrying proofs of store to their transactions.
+    batch_store: Arc<BatchStore>,
+    // Publishes the committed blocks to the consensus observers, if enabled.
+    publisher: Option<Arc<ConsensusPublisher>>,
 }
 
 impl ExecutionProxy {
     pub fn new(
         execution_correctness_client: Box<dyn ExecutionCorrectness + Send + Sync>,
         state_sync_notifier: Box<dyn ConsensusNotificationSender>,
+        batch_store: Arc<BatchStore>,
+        publisher: Option<Arc<ConsensusPublisher>>,
     ) -> Self {
         Self {
             execution_correctness_client,
             state_sync_notifier,
+            batch_store,
+            publisher,
         }
     }
 }
@@ -56,11 +69,16 @@ impl StateComputer for ExecutionProxy {
             "Executing block",
         );
 
+        let payload_txns = self.batch_store.get_transactions(block.payload())?;
+
         // TODO: figure out error handling for the prologue txn
         monitor!(
             "execute_block",
-            self.execution_correctness_client
-                .execute_block(block.clone(), parent_block_id)
+            self.execution_correctness_client.execute_block(
+                block.clone(),
+                payload_txns,
+                parent_block_id
+            )
         )
     }
 
@@ -74,19 +92,40 @@ impl StateComputer for ExecutionProxy {
         let mut block_ids = Vec::new();
         let mut txns = Vec::new();
         let mut reconfig_events = Vec::new();
+        let mut ordered_blocks = Vec::new();
 
         for block in blocks {
             block_ids.push(block.id());
-            txns.extend(block.transactions_to_commit());
+            let payload_txns = self.batch_store.get_transactions(block.payload())?;
+            if self.publisher.is_some() {
+                ordered_blocks.push((block.block().clone(), payload_txns.clone()));
+            }
+            txns.extend(block.transactions_to_commit(payload_txns));
             reconfig_events.extend(block.reconfig_event());
         }
 
+        // Without decoupled execution, blocks are ordered and committed by the same proof: the
+        // observers get the blocks before the local commit, so they execute them concurrently,
+        // and the commit decision after it.
+        if let Some(publisher) = &self.publisher {
+            publisher.publish(ObserverMessage::OrderedBlock(Box::new(OrderedBlock::new(
+                ordered_blocks,
+                finality_proof.clone(),
+            ))));
+        }
+
         monitor!(
             "commit_block",
             self.execution_correctness_client
                 .commit_blocks(block_ids, finality_proof.clone())?
         );
 
+        if let Some(publisher) = &self.publisher {
+            publisher.publish(ObserverMessage::CommitDecision(Box::new(
+                finality_proof.clone(),
+            )));
+        }
+
         if let Err(e) = monitor!(
             "notify_state_sync",
             self.state_sync_notifier
@@ -96,6 +135,13 @@ impl StateComputer for ExecutionProxy {
             error!(error = ?e, "Failed to notify state synchronizer");
         }
 
+        if let Err(e) = self
+            .batch_store
+            .mark_committed(blocks.iter().flat_map(|block| block.payload()).collect())
+        {
+            error!(error = ?e, "Failed to mark the committed batches");
+        }
+
         callback(blocks, finality_proof);
 
         Ok(())
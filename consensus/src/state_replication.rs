@@ -5,7 +5,7 @@ use crate::error::{MempoolError, StateSyncError};
 use anyhow::Result;
 use consensus_types::{block::Block, common::Payload, executed_block::ExecutedBlock};
 use diem_crypto::HashValue;
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use executor_types::{Error as ExecutionError, StateComputeResult};
 use std::sync::Arc;
 
@@ -28,11 +28,11 @@ pub trait TxnManager: Send + Sync {
         exclude: Vec<&Payload>,
     ) -> Result<Payload, MempoolError>;
 
-    /// Notifies TxnManager about the executed result of the block,
+    /// Notifies TxnManager about the executed result of the block transactions,
     /// which includes the specifics of what transactions succeeded and failed.
     async fn notify(
         &self,
-        block: &Block,
+        txns: &[SignedTransaction],
         compute_result: &StateComputeResult,
     ) -> Result<(), MempoolError>;
 
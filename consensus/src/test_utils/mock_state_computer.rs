@@ -3,38 +3,42 @@
 
 use crate::{
     error::StateSyncError,
+    quorum_store::BatchStore,
     state_replication::{StateComputer, StateComputerCommitCallBackType},
     test_utils::mock_storage::MockStorage,
 };
 use anyhow::{format_err, Result};
-use consensus_types::{block::Block, common::Payload, executed_block::ExecutedBlock};
+use consensus_types::{block::Block, executed_block::ExecutedBlock};
 use diem_crypto::HashValue;
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::SignedTransaction};
 use executor_types::{Error, StateComputeResult};
 use futures::channel::mpsc;
 use std::{collections::HashMap, sync::Arc};
 use termion::color::*;
 
 pub struct MockStateComputer {
-    state_sync_client: mpsc::UnboundedSender<Payload>,
+    state_sync_client: mpsc::UnboundedSender<Vec<SignedTransaction>>,
     commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
     consensus_db: Arc<MockStorage>,
-    block_cache: Mutex<HashMap<HashValue, Payload>>,
+    block_cache: Mutex<HashMap<HashValue, Vec<SignedTransaction>>>,
+    batch_store: Arc<BatchStore>,
 }
 
 impl MockStateComputer {
     pub fn new(
-        state_sync_client: mpsc::UnboundedSender<Payload>,
+        state_sync_client: mpsc::UnboundedSender<Vec<SignedTransaction>>,
         commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
         consensus_db: Arc<MockStorage>,
+        batch_store: Arc<BatchStore>,
     ) -> Self {
         MockStateComputer {
             state_sync_client,
             commit_callback,
             consensus_db,
             block_cache: Mutex::new(HashMap::new()),
+            batch_store,
         }
     }
 }
@@ -46,9 +50,8 @@ impl StateComputer for MockStateComputer {
         block: &Block,
         _parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error> {
-        self.block_cache
-            .lock()
-            .insert(block.id(), block.payload().unwrap_or(&vec![]).clone());
+        let txns = self.batch_store.get_transactions(block.payload())?;
+        self.block_cache.lock().insert(block.id(), txns);
         let result = StateComputeResult::new_dummy();
         Ok(result)
     }
@@ -72,6 +75,8 @@ impl StateComputer for MockStateComputer {
                 .ok_or_else(|| format_err!("Cannot find block"))?;
             txns.append(&mut payload);
         }
+        self.batch_store
+            .mark_committed(blocks.iter().flat_map(|block| block.payload()).collect())?;
         // they may fail during shutdown
         let _ = self.state_sync_client.unbounded_send(txns);
 
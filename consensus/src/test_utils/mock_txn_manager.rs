@@ -3,13 +3,10 @@
 
 use crate::{error::MempoolError, state_replication::TxnManager, txn_manager::MempoolProxy};
 use anyhow::Result;
-use consensus_types::{
-    block::{block_test_utils::random_payload, Block},
-    common::Payload,
-};
+use consensus_types::{block::block_test_utils::random_payload, common::Payload};
 use diem_mempool::ConsensusRequest;
 use diem_types::{
-    transaction::TransactionStatus,
+    transaction::{SignedTransaction, TransactionStatus},
     vm_status::{KeptVMStatus, StatusCode},
 };
 use executor_types::StateComputeResult;
@@ -18,7 +15,7 @@ use rand::Rng;
 
 #[derive(Clone)]
 pub struct MockTransactionManager {
-    rejected_txns: Payload,
+    rejected_txns: Vec<SignedTransaction>,
     // used non-mocked TxnManager to test interaction with shared mempool
     mempool_proxy: Option<MempoolProxy>,
 }
@@ -61,7 +58,7 @@ impl TxnManager for MockTransactionManager {
 
     async fn notify(
         &self,
-        block: &Block,
+        txns: &[SignedTransaction],
         compute_results: &StateComputeResult,
     ) -> Result<(), MempoolError> {
         if self.mempool_proxy.is_some() {
@@ -72,7 +69,7 @@ impl TxnManager for MockTransactionManager {
                 compute_results.parent_frozen_subtree_roots().clone(),
                 compute_results.parent_num_leaves(),
                 compute_results.epoch_state().clone(),
-                mock_transaction_status(block.payload().map_or(0, |txns| txns.len())),
+                mock_transaction_status(txns.len()),
                 compute_results.transaction_info_hashes().clone(),
                 compute_results.reconfig_events().to_vec(),
             );
@@ -80,7 +77,7 @@ impl TxnManager for MockTransactionManager {
                 .mempool_proxy
                 .as_ref()
                 .unwrap()
-                .notify(block, &mock_compute_result)
+                .notify(txns, &mock_compute_result)
                 .await
                 .is_ok());
         }
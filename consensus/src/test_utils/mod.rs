@@ -128,7 +128,7 @@ impl TreeInserter {
                 parent_qc,
                 parent.timestamp_usecs() + 1,
                 round,
-                vec![],
+                Payload::empty(false),
             ))
             .unwrap()
     }
@@ -7,7 +7,10 @@ use crate::{
     test_utils::{consensus_runtime, timed_block_on},
     twins::twins_node::SMRNode,
 };
-use consensus_types::{block::Block, common::Round};
+use consensus_types::{
+    block::Block,
+    common::{Payload, Round},
+};
 use diem_config::config::ConsensusProposerType::{FixedProposer, RotatingProposer, RoundProposer};
 use futures::StreamExt;
 use std::collections::HashMap;
@@ -33,6 +36,7 @@ fn basic_start_test() {
         &mut playground,
         RotatingProposer,
         None,
+        /* quorum_store_enabled = */ false,
     );
     let genesis = Block::make_genesis_block_from_ledger_info(&nodes[0].storage.get_ledger_info());
     timed_block_on(&mut runtime, async {
@@ -85,6 +89,7 @@ fn drop_config_test() {
         &mut playground,
         FixedProposer,
         None,
+        /* quorum_store_enabled = */ false,
     );
 
     // 4 honest nodes
@@ -141,6 +146,7 @@ fn twins_vote_dedup_test() {
         &mut playground,
         RotatingProposer,
         None,
+        /* quorum_store_enabled = */ false,
     );
 
     // 4 honest nodes
@@ -208,6 +214,7 @@ fn twins_proposer_test() {
         &mut playground,
         RoundProposer(HashMap::new()),
         Some(round_proposers),
+        /* quorum_store_enabled = */ false,
     );
 
     // 4 honest nodes
@@ -291,6 +298,7 @@ fn twins_commit_test() {
         &mut playground,
         RoundProposer(HashMap::new()),
         Some(round_proposers),
+        /* quorum_store_enabled = */ false,
     );
     runtime.spawn(playground.start());
 
@@ -310,3 +318,121 @@ fn twins_commit_test() {
         }
     });
 }
+
+#[test]
+/// This test checks that with the quorum store enabled the proposals carry proofs of store
+/// and that the committed batches are resolved back to their transactions.
+///
+/// Setup:
+///
+/// 4 honest nodes with the quorum store enabled, and 0 twins
+///
+/// Run the test:
+/// cargo xtest -p consensus quorum_store_commit_test -- --nocapture
+fn quorum_store_commit_test() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let num_nodes = 4;
+    let num_twins = 0;
+    let mut nodes = SMRNode::start_num_nodes_with_twins(
+        num_nodes,
+        num_twins,
+        &mut playground,
+        RotatingProposer,
+        None,
+        /* quorum_store_enabled = */ true,
+    );
+    timed_block_on(&mut runtime, async {
+        // Wait for a proposal that only carries proofs of store
+        loop {
+            let msg = playground
+                .wait_for_messages(1, NetworkPlayground::proposals_only)
+                .await;
+            let proposal = match &msg[0].1 {
+                ConsensusMsg::ProposalMsg(proposal) => proposal,
+                _ => panic!("Unexpected message found"),
+            };
+            match proposal.proposal().payload() {
+                Some(Payload::InQuorumStore(proofs)) => {
+                    if !proofs.is_empty() {
+                        break;
+                    }
+                }
+                Some(Payload::DirectMempool(_)) => {
+                    panic!("Proposal carries transactions with the quorum store enabled")
+                }
+                None => (),
+            }
+        }
+        tokio::spawn(playground.start());
+
+        // The transactions of the batches are committed
+        loop {
+            let txns = nodes[0]
+                .state_sync
+                .next()
+                .await
+                .expect("[TwinsTest] State sync channel closed");
+            if !txns.is_empty() {
+                break;
+            }
+        }
+    });
+}
+
+#[test]
+/// This test checks that with the quorum store enabled a twin equivocating on its batches and
+/// proposals doesn't make the honest nodes diverge: the twins author batches with the same ids
+/// but different transactions, only one of which can gather a proof of store.
+///
+/// Setup:
+///
+/// 4 honest nodes (n0, n1, n2, n3) with the quorum store enabled, and 1 twin (twin0)
+/// Let n0 (and implicitly twin0) be proposers
+///
+/// Test:
+///
+/// Extract enough votes to form commits. Check that all the nodes commit the same block.
+///
+/// Run the test:
+/// cargo xtest -p consensus quorum_store_twins_commit_test -- --nocapture
+fn quorum_store_twins_commit_test() {
+    let mut runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let num_nodes = 4;
+    let num_twins = 1;
+
+    let mut round_proposers: HashMap<Round, usize> = HashMap::new();
+    // Leaders are n0 and twin0 for round 1..10
+    for i in 1..10 {
+        round_proposers.insert(i, 0);
+    }
+
+    let mut nodes = SMRNode::start_num_nodes_with_twins(
+        num_nodes,
+        num_twins,
+        &mut playground,
+        RoundProposer(HashMap::new()),
+        Some(round_proposers),
+        /* quorum_store_enabled = */ true,
+    );
+    let n0_twin_id = nodes[0].id;
+    let twin0_twin_id = nodes[4].id;
+    assert_eq!(n0_twin_id.author, twin0_twin_id.author);
+    runtime.spawn(playground.start());
+
+    timed_block_on(&mut runtime, async {
+        let mut commit_ids = vec![];
+        for node in &mut nodes {
+            let commit = node
+                .commit_cb_receiver
+                .next()
+                .await
+                .expect("[TwinsTest] Test failed due to no commit(s)");
+            commit_ids.push(commit.ledger_info().commit_info().id());
+        }
+        // Proposals from both node0 and twin0 are going to race, but only one of them will
+        // form a commit
+        assert!(commit_ids.iter().all(|id| *id == commit_ids[0]));
+    });
+}
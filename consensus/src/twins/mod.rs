@@ -2,4 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod basic_twins_test;
+mod twins_fuzzer_test;
 mod twins_node;
+mod twins_scenario;
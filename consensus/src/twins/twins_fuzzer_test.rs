@@ -0,0 +1,168 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::twins::twins_scenario::{check_safety, run_scenario, ScenarioGenerator, TwinsScenario};
+use diem_crypto::HashValue;
+use diem_types::{
+    block_info::BlockInfo,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::{BTreeMap, HashSet},
+    env,
+    path::PathBuf,
+};
+
+fn regression_scenarios_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/twins/scenarios")
+}
+
+fn ledger_info(round: u64, version: u64, id: HashValue) -> LedgerInfoWithSignatures {
+    LedgerInfoWithSignatures::new(
+        LedgerInfo::new(
+            BlockInfo::new(1, round, id, HashValue::zero(), version, round, None),
+            HashValue::zero(),
+        ),
+        BTreeMap::new(),
+    )
+}
+
+#[test]
+fn test_scenario_json_roundtrip() {
+    let generator = ScenarioGenerator::new(4, 2, 10, 3);
+    let scenario = generator.random_scenario(&mut StdRng::seed_from_u64(0));
+    assert_eq!(scenario.round_leaders.len(), 10);
+    assert_eq!(
+        TwinsScenario::from_json(&scenario.to_json()).unwrap(),
+        scenario
+    );
+
+    let mut invalid = scenario;
+    invalid
+        .round_partitions
+        .insert(1, vec![vec![0, 1, 2, 3, 4, 5, 6]]);
+    assert!(TwinsScenario::from_json(&invalid.to_json()).is_err());
+}
+
+#[test]
+fn test_random_scenario_partitions_all_nodes() {
+    let generator = ScenarioGenerator::new(4, 1, 5, 3);
+    let mut rng = StdRng::seed_from_u64(42);
+    for _ in 0..10 {
+        let scenario = generator.random_scenario(&mut rng);
+        scenario.verify().unwrap();
+        for partitions in scenario.round_partitions.values() {
+            assert!(partitions.len() <= 3);
+            let mut nodes: Vec<_> = partitions.iter().flatten().cloned().collect();
+            nodes.sort_unstable();
+            assert_eq!(nodes, (0..5).collect::<Vec<_>>());
+        }
+    }
+}
+
+#[test]
+fn test_partitions() {
+    // Stirling numbers of the second kind: S(4, 1) + S(4, 2) = 1 + 7
+    assert_eq!(ScenarioGenerator::new(4, 0, 1, 2).partitions().len(), 8);
+    // Bell number B(5)
+    let partitions = ScenarioGenerator::new(4, 1, 1, 5).partitions();
+    assert_eq!(partitions.len(), 52);
+    let unique: HashSet<_> = partitions.iter().collect();
+    assert_eq!(unique.len(), partitions.len());
+}
+
+#[test]
+fn test_enumerate() {
+    let generator = ScenarioGenerator::new(4, 0, 2, 2);
+    let partitions = generator.partitions();
+    let scenarios: Vec<_> = generator.enumerate(partitions.clone()).collect();
+    assert_eq!(scenarios.len(), (4 * partitions.len()).pow(2));
+    let unique: HashSet<_> = scenarios.iter().map(|s| s.to_json()).collect();
+    assert_eq!(unique.len(), scenarios.len());
+}
+
+#[test]
+fn test_check_safety() {
+    let a = HashValue::random();
+    let b = HashValue::random();
+    let commits = vec![
+        vec![ledger_info(1, 1, a)],
+        vec![ledger_info(1, 1, a), ledger_info(2, 2, b)],
+    ];
+    assert!(check_safety(&commits).is_ok());
+    // Different blocks committed at the same round
+    assert!(check_safety(&[vec![ledger_info(1, 1, a)], vec![ledger_info(1, 1, b)]]).is_err());
+    // Different blocks at the same version are fine, the mock state computer gives every block
+    // the same version
+    assert!(check_safety(&[vec![ledger_info(1, 0, a)], vec![ledger_info(2, 0, b)]]).is_ok());
+}
+
+#[test]
+/// Replays the scenarios checked in under `src/twins/scenarios`.
+///
+/// Run the test:
+/// cargo xtest -p consensus twins_regression_test -- --nocapture
+fn twins_regression_test() {
+    let mut paths: Vec<_> = std::fs::read_dir(regression_scenarios_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let scenario = TwinsScenario::load(&path).unwrap();
+        let commits = run_scenario(&scenario);
+        if let Err(e) = check_safety(&commits) {
+            panic!("[TwinsTest] Scenario {:?} failed: {}", path, e);
+        }
+    }
+}
+
+#[test]
+#[ignore]
+/// Long-running safety fuzzer: runs random Twins scenarios and writes the failing ones as
+/// JSON, they can be checked in under `src/twins/scenarios` as regression tests.
+///
+/// Configured through the environment:
+/// TWINS_FUZZ_ITERATIONS (default 100), TWINS_FUZZ_SEED (default random),
+/// TWINS_FUZZ_OUTPUT (default the temp directory).
+///
+/// Run the test:
+/// cargo xtest -p consensus twins_safety_fuzzer -- --ignored --nocapture
+fn twins_safety_fuzzer() {
+    let iterations = env::var("TWINS_FUZZ_ITERATIONS")
+        .map(|value| value.parse().expect("Invalid TWINS_FUZZ_ITERATIONS"))
+        .unwrap_or(100);
+    let seed = env::var("TWINS_FUZZ_SEED")
+        .map(|value| value.parse().expect("Invalid TWINS_FUZZ_SEED"))
+        .unwrap_or_else(|_| rand::thread_rng().gen());
+    let output = env::var("TWINS_FUZZ_OUTPUT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir());
+    println!("[TwinsTest] Fuzzing with seed {}", seed);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let generator = ScenarioGenerator::new(4, 1, 10, 3);
+    let mut failures = vec![];
+    for iteration in 0..iterations {
+        let scenario = generator.random_scenario(&mut rng);
+        let commits = run_scenario(&scenario);
+        if let Err(e) = check_safety(&commits) {
+            let path = scenario
+                .save(&output, &format!("twins_{}_{}", seed, iteration))
+                .unwrap();
+            println!(
+                "[TwinsTest] Safety violation: {}, scenario saved to {:?}",
+                e, path
+            );
+            failures.push(path);
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "[TwinsTest] {} failing scenarios: {:?}",
+        failures.len(),
+        failures
+    );
+}
@@ -7,11 +7,15 @@ use crate::{
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     network_tests::{NetworkPlayground, TwinId},
+    quorum_store::BatchStore,
     test_utils::{MockStateComputer, MockStorage, MockTransactionManager},
-    util::time_service::ClockTimeService,
+    util::{
+        mock_time_service::SimulatedTimeService,
+        time_service::{ClockTimeService, TimeService},
+    },
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
-use consensus_types::common::{Author, Payload, Round};
+use consensus_types::common::{Author, Round};
 use diem_config::{
     config::{
         ConsensusProposerType::{self, RoundProposer},
@@ -23,6 +27,7 @@ use diem_mempool::mocks::MockSharedMempool;
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     on_chain_config::{OnChainConfig, OnChainConfigPayload, ValidatorSet},
+    transaction::SignedTransaction,
     validator_info::ValidatorInfo,
     waypoint::Waypoint,
 };
@@ -32,7 +37,7 @@ use network::{
     protocols::network::{NewNetworkEvents, NewNetworkSender},
     ProtocolId,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::runtime::{Builder, Runtime};
 
 /// Auxiliary struct that is preparing SMR for the test
@@ -41,8 +46,8 @@ pub struct SMRNode {
     pub storage: Arc<MockStorage>,
     pub commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
     _runtime: Runtime,
+    pub state_sync: mpsc::UnboundedReceiver<Vec<SignedTransaction>>,
     _shared_mempool: MockSharedMempool,
-    _state_sync: mpsc::UnboundedReceiver<Payload>,
 }
 
 fn author_from_config(config: &NodeConfig) -> Author {
@@ -55,6 +60,7 @@ impl SMRNode {
         config: NodeConfig,
         storage: Arc<MockStorage>,
         twin_id: TwinId,
+        mock_time: Option<SimulatedTimeService>,
     ) -> Self {
         let (network_reqs_tx, network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
         let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
@@ -74,10 +80,15 @@ impl SMRNode {
         let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
         let shared_mempool = MockSharedMempool::new(None);
         let consensus_to_mempool_sender = shared_mempool.consensus_sender.clone();
+        let batch_store = Arc::new(BatchStore::new_in_memory(
+            config.consensus.quorum_store_max_batches_per_author,
+            Duration::from_millis(config.consensus.quorum_store_batch_expiry_ms),
+        ));
         let state_computer = Arc::new(MockStateComputer::new(
             state_sync_client,
             commit_cb_sender,
             Arc::clone(&storage),
+            batch_store.clone(),
         ));
         let txn_manager = Arc::new(MockTransactionManager::new(Some(
             consensus_to_mempool_sender,
@@ -101,7 +112,10 @@ impl SMRNode {
             .build()
             .unwrap();
 
-        let time_service = Arc::new(ClockTimeService::new(runtime.handle().clone()));
+        let time_service: Arc<dyn TimeService> = match mock_time {
+            Some(mock_time) => Arc::new(mock_time),
+            None => Arc::new(ClockTimeService::new(runtime.handle().clone())),
+        };
 
         let (timeout_sender, timeout_receiver) =
             channel::new(1_024, &counters::PENDING_ROUND_TIMEOUTS);
@@ -116,6 +130,7 @@ impl SMRNode {
             txn_manager,
             state_computer,
             storage.clone(),
+            batch_store,
             reconfig_events,
         );
         let (network_task, network_receiver) =
@@ -128,11 +143,10 @@ impl SMRNode {
             _runtime: runtime,
             commit_cb_receiver,
             storage,
+            state_sync,
             _shared_mempool: shared_mempool,
-            _state_sync: state_sync,
         }
     }
-    #[allow(clippy::redundant_closure)]
     /// Starts a given number of nodes and their twins
     pub fn start_num_nodes_with_twins(
         num_nodes: usize,
@@ -140,6 +154,50 @@ impl SMRNode {
         playground: &mut NetworkPlayground,
         proposer_type: ConsensusProposerType,
         round_proposers_idx: Option<HashMap<Round, usize>>,
+        quorum_store_enabled: bool,
+    ) -> Vec<Self> {
+        Self::start_num_nodes_with_twins_impl(
+            num_nodes,
+            num_twins,
+            playground,
+            proposer_type,
+            round_proposers_idx,
+            quorum_store_enabled,
+            None,
+        )
+    }
+
+    /// Starts a given number of nodes and their twins, all running on the given mock time: time
+    /// only advances when nodes wait for it, so round timeouts never fire and the nodes only make
+    /// progress through the messages delivered by the `NetworkPlayground`.
+    pub fn start_num_nodes_with_twins_in_mock_time(
+        num_nodes: usize,
+        num_twins: usize,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+        mock_time: SimulatedTimeService,
+    ) -> Vec<Self> {
+        Self::start_num_nodes_with_twins_impl(
+            num_nodes,
+            num_twins,
+            playground,
+            proposer_type,
+            round_proposers_idx,
+            /* quorum_store_enabled = */ false,
+            Some(mock_time),
+        )
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn start_num_nodes_with_twins_impl(
+        num_nodes: usize,
+        num_twins: usize,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        round_proposers_idx: Option<HashMap<Round, usize>>,
+        quorum_store_enabled: bool,
+        mock_time: Option<SimulatedTimeService>,
     ) -> Vec<Self> {
         assert!(num_nodes >= num_twins);
         let ValidatorSwarm {
@@ -216,12 +274,19 @@ impl SMRNode {
             config.consensus.safety_rules.verify_vote_proposal_signature = false;
             // Disable timeout in twins test to avoid flakiness
             config.consensus.round_initial_timeout_ms = 2_000_000;
+            config.consensus.quorum_store_enabled = quorum_store_enabled;
 
             let author = author_from_config(&config);
 
             let twin_id = TwinId { id: smr_id, author };
 
-            smr_nodes.push(Self::start(playground, config, storage, twin_id));
+            smr_nodes.push(Self::start(
+                playground,
+                config,
+                storage,
+                twin_id,
+                mock_time.clone(),
+            ));
         }
         smr_nodes
     }
@@ -0,0 +1,309 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network_tests::{NetworkPlayground, TwinId},
+    test_utils::consensus_runtime,
+    twins::twins_node::SMRNode,
+    util::mock_time_service::SimulatedTimeService,
+};
+use anyhow::{ensure, Context};
+use consensus_types::common::Round;
+use diem_config::config::ConsensusProposerType::RoundProposer;
+use diem_types::{block_info::BlockInfo, ledger_info::LedgerInfoWithSignatures};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// A Twins scenario: the leader and the network partitions of every round.
+///
+/// Nodes are identified by their index in the swarm started by
+/// `SMRNode::start_num_nodes_with_twins`: `0..num_nodes` are the honest nodes and
+/// `num_nodes + i` is the twin of node `i`. Leaders are indices of honest nodes (a twin is
+/// a leader whenever its node is), partitions may contain any node or twin.
+///
+/// Scenarios are serialized to JSON so that failing ones can be replayed as regression tests.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwinsScenario {
+    pub num_nodes: usize,
+    pub num_twins: usize,
+    /// Rounds without a leader default to node 0.
+    pub round_leaders: BTreeMap<Round, usize>,
+    /// Messages are dropped between the partitions of a round, rounds without partitions are
+    /// fully connected.
+    pub round_partitions: BTreeMap<Round, Vec<Vec<usize>>>,
+}
+
+impl TwinsScenario {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("TwinsScenario serialization must not fail")
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let scenario: Self = serde_json::from_str(json)?;
+        scenario.verify()?;
+        Ok(scenario)
+    }
+
+    /// Checks that the scenario only refers to the nodes of its swarm.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.num_twins <= self.num_nodes,
+            "{} twins for {} nodes",
+            self.num_twins,
+            self.num_nodes
+        );
+        for (round, leader) in &self.round_leaders {
+            ensure!(
+                *leader < self.num_nodes,
+                "Leader {} of round {} is not an honest node",
+                leader,
+                round
+            );
+        }
+        let num_total = self.num_nodes + self.num_twins;
+        for (round, partitions) in &self.round_partitions {
+            for node in partitions.iter().flatten() {
+                ensure!(
+                    *node < num_total,
+                    "Unknown node {} in the partitions of round {}",
+                    node,
+                    round
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the scenario as JSON in `dir`, returns the path of the file.
+    pub fn save(&self, dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.json", name));
+        fs::write(&path, self.to_json())
+            .with_context(|| format!("Failed to write Twins scenario {:?}", path))?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read Twins scenario {:?}", path))?;
+        Self::from_json(&json).with_context(|| format!("Invalid Twins scenario {:?}", path))
+    }
+}
+
+/// Generates Twins scenarios over `num_rounds` rounds, with at most `max_partitions` partitions
+/// per round.
+pub struct ScenarioGenerator {
+    num_nodes: usize,
+    num_twins: usize,
+    num_rounds: Round,
+    max_partitions: usize,
+}
+
+impl ScenarioGenerator {
+    pub fn new(
+        num_nodes: usize,
+        num_twins: usize,
+        num_rounds: Round,
+        max_partitions: usize,
+    ) -> Self {
+        assert!(num_nodes >= num_twins);
+        assert!(max_partitions > 0);
+        Self {
+            num_nodes,
+            num_twins,
+            num_rounds,
+            max_partitions,
+        }
+    }
+
+    fn num_total(&self) -> usize {
+        self.num_nodes + self.num_twins
+    }
+
+    /// Samples the leader and the partitions of every round uniformly at random.
+    pub fn random_scenario<R: Rng>(&self, rng: &mut R) -> TwinsScenario {
+        let mut scenario = self.empty_scenario();
+        for round in 1..=self.num_rounds {
+            scenario
+                .round_leaders
+                .insert(round, rng.gen_range(0..self.num_nodes));
+            let num_partitions = rng.gen_range(1..=self.max_partitions);
+            let mut partitions = vec![vec![]; num_partitions];
+            for node in 0..self.num_total() {
+                partitions[rng.gen_range(0..num_partitions)].push(node);
+            }
+            partitions.retain(|partition| !partition.is_empty());
+            scenario.round_partitions.insert(round, partitions);
+        }
+        scenario
+    }
+
+    /// All the ways to split the nodes in at most `max_partitions` non-empty partitions.
+    pub fn partitions(&self) -> Vec<Vec<Vec<usize>>> {
+        // Enumerate the restricted growth strings: node i joins one of the partitions used by
+        // the previous nodes or opens the next one, so that each partitioning is seen once.
+        let mut result = vec![];
+        let mut assignment = vec![0; self.num_total()];
+        self.partitions_internal(&mut assignment, 1, 1, &mut result);
+        result
+    }
+
+    fn partitions_internal(
+        &self,
+        assignment: &mut Vec<usize>,
+        node: usize,
+        num_partitions: usize,
+        result: &mut Vec<Vec<Vec<usize>>>,
+    ) {
+        if node >= assignment.len() {
+            let mut partitions = vec![vec![]; num_partitions];
+            for (node, partition) in assignment.iter().enumerate() {
+                partitions[*partition].push(node);
+            }
+            result.push(partitions);
+            return;
+        }
+        let max_partition = std::cmp::min(num_partitions + 1, self.max_partitions);
+        for partition in 0..max_partition {
+            assignment[node] = partition;
+            self.partitions_internal(
+                assignment,
+                node + 1,
+                std::cmp::max(num_partitions, partition + 1),
+                result,
+            );
+        }
+    }
+
+    /// Lazily enumerates every combination of leader and partitions (taken from `partitions`)
+    /// for every round, i.e. `(num_nodes * partitions.len()) ^ num_rounds` scenarios.
+    pub fn enumerate(
+        &self,
+        partitions: Vec<Vec<Vec<usize>>>,
+    ) -> impl Iterator<Item = TwinsScenario> + '_ {
+        assert!(!partitions.is_empty());
+        let choices_per_round = (self.num_nodes * partitions.len()) as u128;
+        let num_scenarios = choices_per_round
+            .checked_pow(self.num_rounds as u32)
+            .expect("Too many Twins scenarios to enumerate");
+        (0..num_scenarios).map(move |mut index| {
+            let mut scenario = self.empty_scenario();
+            for round in 1..=self.num_rounds {
+                let choice = (index % choices_per_round) as usize;
+                index /= choices_per_round;
+                scenario
+                    .round_leaders
+                    .insert(round, choice % self.num_nodes);
+                scenario
+                    .round_partitions
+                    .insert(round, partitions[choice / self.num_nodes].clone());
+            }
+            scenario
+        })
+    }
+
+    fn empty_scenario(&self) -> TwinsScenario {
+        TwinsScenario {
+            num_nodes: self.num_nodes,
+            num_twins: self.num_twins,
+            ..TwinsScenario::default()
+        }
+    }
+}
+
+/// Rounds run after the last round of a scenario, for its blocks to get committed.
+const COMMIT_ROUNDS: Round = 3;
+
+/// Returns once no message was exchanged for this long, nodes running on mock time are then stuck.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Runs the scenario on a swarm of `SMRNode`s over the `NetworkPlayground` and returns the
+/// LedgerInfos committed by every node (and twin).
+///
+/// The nodes run on mock time, so they only move on through the messages of the scenario's rounds
+/// (and the few after them needed to commit), which makes replaying a scenario reproducible
+/// instead of depending on how far the swarm got in some wall-clock duration.
+pub fn run_scenario(scenario: &TwinsScenario) -> Vec<Vec<LedgerInfoWithSignatures>> {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.handle().clone());
+    let round_leaders: HashMap<Round, usize> = scenario
+        .round_leaders
+        .iter()
+        .map(|(round, leader)| (*round, *leader))
+        .collect();
+    let mut nodes = SMRNode::start_num_nodes_with_twins_in_mock_time(
+        scenario.num_nodes,
+        scenario.num_twins,
+        &mut playground,
+        RoundProposer(HashMap::new()),
+        Some(round_leaders),
+        SimulatedTimeService::new(),
+    );
+    let twin_ids: Vec<TwinId> = nodes.iter().map(|node| node.id).collect();
+    let round_partitions = scenario
+        .round_partitions
+        .iter()
+        .map(|(round, partitions)| {
+            let partitions = partitions
+                .iter()
+                .map(|partition| partition.iter().map(|node| twin_ids[*node]).collect())
+                .collect();
+            (*round, partitions)
+        })
+        .collect();
+    playground.split_network_round(&round_partitions);
+    let last_round = scenario
+        .round_leaders
+        .keys()
+        .chain(scenario.round_partitions.keys())
+        .max()
+        .copied()
+        .unwrap_or(0);
+    runtime.block_on(playground.run_until_round(last_round + COMMIT_ROUNDS, IDLE_TIMEOUT));
+
+    nodes
+        .iter_mut()
+        .map(|node| {
+            let mut commits = vec![];
+            while let Ok(Some(commit)) = node.commit_cb_receiver.try_next() {
+                commits.push(commit);
+            }
+            commits
+        })
+        .collect()
+}
+
+/// Checks that the nodes didn't commit conflicting blocks: all the commits at a given epoch and
+/// round must carry the same BlockInfo.
+///
+/// Versions are not compared: the nodes run on `MockStateComputer`, which gives the same dummy
+/// version to every block.
+pub fn check_safety(commits: &[Vec<LedgerInfoWithSignatures>]) -> anyhow::Result<()> {
+    let mut by_round: HashMap<(u64, Round), (usize, &BlockInfo)> = HashMap::new();
+    for (node, node_commits) in commits.iter().enumerate() {
+        for commit in node_commits {
+            let info = commit.ledger_info().commit_info();
+            let (other, other_info) = *by_round
+                .entry((info.epoch(), info.round()))
+                .or_insert((node, info));
+            ensure!(
+                other_info == info,
+                "Conflicting commits at epoch {} round {}: node {} committed {}, node {} \
+                committed {}",
+                info.epoch(),
+                info.round(),
+                other,
+                other_info,
+                node,
+                info
+            );
+        }
+    }
+    Ok(())
+}
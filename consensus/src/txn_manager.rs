@@ -3,11 +3,11 @@
 
 use crate::{error::MempoolError, state_replication::TxnManager};
 use anyhow::{format_err, Result};
-use consensus_types::{block::Block, common::Payload};
+use consensus_types::common::Payload;
 use diem_logger::prelude::*;
 use diem_mempool::{ConsensusRequest, ConsensusResponse, TransactionSummary};
 use diem_metrics::monitor;
-use diem_types::transaction::TransactionStatus;
+use diem_types::transaction::{SignedTransaction, TransactionStatus};
 use executor_types::StateComputeResult;
 use fail::fail_point;
 use futures::channel::{mpsc, oneshot};
@@ -51,7 +51,7 @@ impl MempoolProxy {
         &self,
         max_size: u64,
         exclude_txns: Vec<TransactionSummary>,
-    ) -> Result<Payload, MempoolError> {
+    ) -> Result<Vec<SignedTransaction>, MempoolError> {
         let (callback, callback_rcv) = oneshot::channel();
         let req = ConsensusRequest::GetBlockRequest(max_size, exclude_txns.clone(), callback);
         // send to shared mempool
@@ -92,12 +92,15 @@ impl TxnManager for MempoolProxy {
             Err(anyhow::anyhow!("Injected error in pull_txns").into())
         });
         let mut exclude_txns = vec![];
+        // Payloads in the quorum store are excluded through their batches instead.
         for payload in exclude_payloads {
-            for transaction in payload {
-                exclude_txns.push(TransactionSummary {
-                    sender: transaction.sender(),
-                    sequence_number: transaction.sequence_number(),
-                });
+            if let Payload::DirectMempool(txns) = payload {
+                for transaction in txns {
+                    exclude_txns.push(TransactionSummary {
+                        sender: transaction.sender(),
+                        sequence_number: transaction.sequence_number(),
+                    });
+                }
             }
         }
         let no_pending_txns = exclude_txns.is_empty();
@@ -116,20 +119,19 @@ impl TxnManager for MempoolProxy {
             poll_count = self.poll_count - count,
             "Pull txn from mempool"
         );
-        Ok(txns)
+        Ok(Payload::DirectMempool(txns))
     }
 
     // Consensus notifies mempool of executed transactions
     async fn notify(
         &self,
-        block: &Block,
+        txns: &[SignedTransaction],
         compute_results: &StateComputeResult,
     ) -> Result<(), MempoolError> {
         let mut rejected_txns = vec![];
-        let txns = match block.payload() {
-            Some(txns) => txns,
-            None => return Ok(()),
-        };
+        if txns.is_empty() {
+            return Ok(());
+        }
         // skip the block metadata txn result
         for (txn, status) in txns
             .iter()
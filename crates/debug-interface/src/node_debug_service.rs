@@ -3,14 +3,28 @@
 
 //! Debug interface to access information in a specific node.
 
+use diem_infallible::RwLock;
 use diem_logger::{info, json_log, Filter, Logger};
-use std::{net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, fmt, net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
-use warp::Filter as _;
+use warp::{http::StatusCode, Filter as _};
+
+/// Returns the current state of a node component, see [`NodeDebugService::register_state`].
+pub type StateProvider = Box<dyn Fn() -> serde_json::Value + Send + Sync>;
+
+#[derive(Clone, Default)]
+struct StateProviders(Arc<RwLock<HashMap<String, StateProvider>>>);
+
+impl fmt::Debug for StateProviders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.read().keys()).finish()
+    }
+}
 
 #[derive(Debug)]
 pub struct NodeDebugService {
     runtime: Runtime,
+    state_providers: StateProviders,
 }
 
 impl NodeDebugService {
@@ -28,6 +42,23 @@ impl NodeDebugService {
         // GET /events
         let events = warp::path("events").map(|| warp::reply::json(&json_log::pop_last_entries()));
 
+        // GET /state/<name>
+        let state_providers = StateProviders::default();
+        let state = {
+            let state_providers = state_providers.clone();
+            warp::path!("state" / String).map(move |name: String| {
+                match state_providers.0.read().get(&name) {
+                    Some(provider) => {
+                        warp::reply::with_status(warp::reply::json(&provider()), StatusCode::OK)
+                    }
+                    None => warp::reply::with_status(
+                        warp::reply::json(&serde_json::Value::Null),
+                        StatusCode::NOT_FOUND,
+                    ),
+                }
+            })
+        };
+
         // Post /log/filter
         let local_filter = {
             let logger = logger.clone();
@@ -65,13 +96,22 @@ impl NodeDebugService {
             .and(warp::path("log"))
             .and(local_filter.or(remote_filter));
 
-        let routes = log.or(warp::get().and(metrics.or(events)));
+        let routes = log.or(warp::get().and(metrics.or(events).or(state)));
 
         runtime
             .handle()
             .spawn(async move { warp::serve(routes).bind(address).await });
 
-        Self { runtime }
+        Self {
+            runtime,
+            state_providers,
+        }
+    }
+
+    /// Serves the state returned by `provider` as JSON at `GET /state/<name>`, replacing any
+    /// provider previously registered under `name`.
+    pub fn register_state(&self, name: impl Into<String>, provider: StateProvider) {
+        self.state_providers.0.write().insert(name.into(), provider);
     }
 
     pub fn runtime(&self) -> &Runtime {
@@ -13,16 +13,16 @@ use crate::{
     views::{
         AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
         AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionView,
+        EventWithProofView, MetadataView, SimulationView, StateProofView, TransactionView,
         TransactionsWithProofsView,
     },
     Error, Result, Retry, State,
 };
-use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_crypto::{ed25519::Ed25519PublicKey, hash::CryptoHash, HashValue};
 use diem_types::{
     account_address::AccountAddress,
     event::EventKey,
-    transaction::{SignedTransaction, Transaction},
+    transaction::{RawTransaction, SignedTransaction, Transaction},
 };
 use move_core_types::move_resource::{MoveResource, MoveStructType};
 use serde::{de::DeserializeOwned, Serialize};
@@ -139,6 +139,18 @@ impl BlockingClient {
         self.send_without_retry(&request, true)
     }
 
+    pub fn simulate(&self, txn: &SignedTransaction) -> Result<Response<SimulationView>> {
+        self.send(MethodRequest::simulate(txn).map_err(Error::request)?)
+    }
+
+    pub fn simulate_unsigned(
+        &self,
+        txn: &RawTransaction,
+        public_key: &Ed25519PublicKey,
+    ) -> Result<Response<SimulationView>> {
+        self.send(MethodRequest::simulate_unsigned(txn, public_key).map_err(Error::request)?)
+    }
+
     pub fn get_metadata_by_version(&self, version: u64) -> Result<Response<MetadataView>> {
         self.send(MethodRequest::get_metadata_by_version(version))
     }
@@ -159,6 +171,17 @@ impl BlockingClient {
         self.send(MethodRequest::get_account_by_version(address, version))
     }
 
+    pub fn get_account_by_timestamp(
+        &self,
+        address: AccountAddress,
+        timestamp_usecs: u64,
+    ) -> Result<Response<Option<AccountView>>> {
+        self.send(MethodRequest::get_account_by_timestamp(
+            address,
+            timestamp_usecs,
+        ))
+    }
+
     pub fn get_transactions(
         &self,
         start_seq: u64,
@@ -217,6 +240,10 @@ impl BlockingClient {
         self.send(MethodRequest::get_network_status())
     }
 
+    pub fn get_version_by_timestamp(&self, timestamp_usecs: u64) -> Result<Response<u64>> {
+        self.send(MethodRequest::get_version_by_timestamp(timestamp_usecs))
+    }
+
     //
     // Experimental APIs
     //
@@ -318,6 +318,11 @@ pub enum WaitForTransactionError {
     // Transaction not found, latest known block (ledger info) timestamp is more recent
     // than expiration_time_secs argument.
     TransactionExpired,
+    // Transaction was evicted or rejected, or replaced by another transaction in mempool
+    TransactionDropped(diem_json_rpc_types::views::TransactionStatusView),
+    // Transaction status subscription error
+    #[cfg(feature = "websocket")]
+    SubscriptionError(StreamError),
 }
 
 impl std::fmt::Display for WaitForTransactionError {
@@ -330,6 +335,8 @@ impl std::error::Error for WaitForTransactionError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             WaitForTransactionError::GetTransactionError(e) => Some(e),
+            #[cfg(feature = "websocket")]
+            WaitForTransactionError::SubscriptionError(e) => Some(e),
             _ => None,
         }
     }
@@ -77,6 +77,7 @@ pub enum Method {
     GetEvents,
     GetCurrencies,
     GetNetworkStatus,
+    GetVersionByTimestamp,
 
     //
     // Experimental APIs
@@ -88,6 +89,7 @@ pub enum Method {
     GetAccountTransactionsWithProofs,
     GetEventsWithProofs,
     GetEventByVersionWithProof,
+    Simulate,
 }
 
 cfg_async_or_blocking! {
@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::{JsonRpcVersion, Method};
+use diem_crypto::ed25519::Ed25519PublicKey;
 use diem_types::{
-    account_address::AccountAddress, event::EventKey, transaction::SignedTransaction,
+    account_address::AccountAddress,
+    event::EventKey,
+    transaction::{RawTransaction, SignedTransaction},
 };
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::AtomicU64;
@@ -14,13 +17,14 @@ use std::sync::atomic::AtomicU64;
 pub enum MethodRequest {
     Submit((String,)),
     GetMetadata((Option<u64>,)),
-    GetAccount(AccountAddress, Option<u64>),
+    GetAccount(AccountAddress, Option<u64>, Option<u64>),
     GetTransactions(u64, u64, bool),
     GetAccountTransaction(AccountAddress, u64, bool),
     GetAccountTransactions(AccountAddress, u64, u64, bool),
     GetEvents(EventKey, u64, u64),
     GetCurrencies([(); 0]),
     GetNetworkStatus([(); 0]),
+    GetVersionByTimestamp((u64,)),
 
     //
     // Experimental APIs
@@ -32,6 +36,7 @@ pub enum MethodRequest {
     GetAccountTransactionsWithProofs(AccountAddress, u64, u64, bool, Option<u64>),
     GetEventsWithProofs(EventKey, u64, u64),
     GetEventByVersionWithProof(EventKey, Option<u64>),
+    Simulate(String, Option<String>),
 }
 
 impl MethodRequest {
@@ -49,11 +54,15 @@ impl MethodRequest {
     }
 
     pub fn get_account_by_version(address: AccountAddress, version: u64) -> Self {
-        Self::GetAccount(address, Some(version))
+        Self::GetAccount(address, Some(version), None)
+    }
+
+    pub fn get_account_by_timestamp(address: AccountAddress, timestamp_usecs: u64) -> Self {
+        Self::GetAccount(address, None, Some(timestamp_usecs))
     }
 
     pub fn get_account(address: AccountAddress) -> Self {
-        Self::GetAccount(address, None)
+        Self::GetAccount(address, None, None)
     }
 
     pub fn get_transactions(start_seq: u64, limit: u64, include_events: bool) -> Self {
@@ -89,6 +98,10 @@ impl MethodRequest {
         Self::GetNetworkStatus([])
     }
 
+    pub fn get_version_by_timestamp(timestamp_usecs: u64) -> Self {
+        Self::GetVersionByTimestamp((timestamp_usecs,))
+    }
+
     //
     // Experimental APIs
     //
@@ -144,17 +157,34 @@ impl MethodRequest {
         Self::GetEventByVersionWithProof(key, version)
     }
 
+    pub fn simulate(txn: &SignedTransaction) -> Result<Self, bcs::Error> {
+        let txn_payload = hex::encode(bcs::to_bytes(txn)?);
+        Ok(Self::Simulate(txn_payload, None))
+    }
+
+    pub fn simulate_unsigned(
+        txn: &RawTransaction,
+        public_key: &Ed25519PublicKey,
+    ) -> Result<Self, bcs::Error> {
+        let txn_payload = hex::encode(bcs::to_bytes(txn)?);
+        Ok(Self::Simulate(
+            txn_payload,
+            Some(hex::encode(public_key.to_bytes())),
+        ))
+    }
+
     pub fn method(&self) -> Method {
         match self {
             MethodRequest::Submit(_) => Method::Submit,
             MethodRequest::GetMetadata(_) => Method::GetMetadata,
-            MethodRequest::GetAccount(_, _) => Method::GetAccount,
+            MethodRequest::GetAccount(_, _, _) => Method::GetAccount,
             MethodRequest::GetTransactions(_, _, _) => Method::GetTransactions,
             MethodRequest::GetAccountTransaction(_, _, _) => Method::GetAccountTransaction,
             MethodRequest::GetAccountTransactions(_, _, _, _) => Method::GetAccountTransactions,
             MethodRequest::GetEvents(_, _, _) => Method::GetEvents,
             MethodRequest::GetCurrencies(_) => Method::GetCurrencies,
             MethodRequest::GetNetworkStatus(_) => Method::GetNetworkStatus,
+            MethodRequest::GetVersionByTimestamp(_) => Method::GetVersionByTimestamp,
             MethodRequest::GetStateProof(_) => Method::GetStateProof,
             MethodRequest::GetAccumulatorConsistencyProof(_, _) => {
                 Method::GetAccumulatorConsistencyProof
@@ -166,6 +196,7 @@ impl MethodRequest {
             }
             MethodRequest::GetEventsWithProofs(_, _, _) => Method::GetEventsWithProofs,
             MethodRequest::GetEventByVersionWithProof(_, _) => Method::GetEventByVersionWithProof,
+            MethodRequest::Simulate(_, _) => Method::Simulate,
         }
     }
 }
@@ -6,7 +6,7 @@ use crate::{
     views::{
         AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
         AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionView,
+        EventWithProofView, MetadataView, SimulationView, StateProofView, TransactionView,
         TransactionsWithProofsView,
     },
     Error, State,
@@ -65,6 +65,7 @@ pub enum MethodResponse {
     GetEvents(Vec<EventView>),
     GetCurrencies(Vec<CurrencyInfoView>),
     GetNetworkStatus(u64),
+    GetVersionByTimestamp(u64),
 
     //
     // Experimental APIs
@@ -76,6 +77,7 @@ pub enum MethodResponse {
     GetAccountTransactionsWithProofs(AccountTransactionsWithProofView),
     GetEventsWithProofs(Vec<EventWithProofView>),
     GetEventByVersionWithProof(EventByVersionWithProofView),
+    Simulate(SimulationView),
 }
 
 impl MethodResponse {
@@ -98,6 +100,9 @@ impl MethodResponse {
             Method::GetNetworkStatus => {
                 MethodResponse::GetNetworkStatus(serde_json::from_value(json)?)
             }
+            Method::GetVersionByTimestamp => {
+                MethodResponse::GetVersionByTimestamp(serde_json::from_value(json)?)
+            }
             Method::GetStateProof => MethodResponse::GetStateProof(serde_json::from_value(json)?),
             Method::GetAccumulatorConsistencyProof => {
                 MethodResponse::GetAccumulatorConsistencyProof(serde_json::from_value(json)?)
@@ -117,6 +122,7 @@ impl MethodResponse {
             Method::GetEventByVersionWithProof => {
                 MethodResponse::GetEventByVersionWithProof(serde_json::from_value(json)?)
             }
+            Method::Simulate => MethodResponse::Simulate(serde_json::from_value(json)?),
         };
 
         Ok(response)
@@ -133,6 +139,7 @@ impl MethodResponse {
             MethodResponse::GetEvents(_) => Method::GetEvents,
             MethodResponse::GetCurrencies(_) => Method::GetCurrencies,
             MethodResponse::GetNetworkStatus(_) => Method::GetNetworkStatus,
+            MethodResponse::GetVersionByTimestamp(_) => Method::GetVersionByTimestamp,
             MethodResponse::GetStateProof(_) => Method::GetStateProof,
             MethodResponse::GetAccumulatorConsistencyProof(_) => {
                 Method::GetAccumulatorConsistencyProof
@@ -144,6 +151,7 @@ impl MethodResponse {
             }
             MethodResponse::GetEventsWithProofs(_) => Method::GetEventsWithProofs,
             MethodResponse::GetEventByVersionWithProof(_) => Method::GetEventByVersionWithProof,
+            MethodResponse::Simulate(_) => Method::Simulate,
         }
     }
 
@@ -291,4 +299,14 @@ impl MethodResponse {
             ))),
         }
     }
+
+    pub fn try_into_get_version_by_timestamp(self) -> Result<u64, Error> {
+        match self {
+            MethodResponse::GetVersionByTimestamp(version) => Ok(version),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetVersionByTimestamp found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
 }
@@ -1,15 +1,24 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{stream::websocket_transport::WebsocketTransport, StreamError, StreamResult};
+use crate::{
+    stream::websocket_transport::WebsocketTransport, StreamError, StreamResult,
+    WaitForTransactionError,
+};
+use diem_crypto::HashValue;
 use diem_json_rpc_types::{
     stream::{
-        request::{StreamMethodRequest, SubscribeToEventsParams, SubscribeToTransactionsParams},
-        response::StreamJsonRpcResponse,
+        request::{
+            StreamMethod, StreamMethodRequest, SubscribeToAccountParams, SubscribeToBlocksParams,
+            SubscribeToEventsParams, SubscribeToTransactionStatusParams,
+            SubscribeToTransactionsParams,
+        },
+        response::{StreamJsonRpcResponse, StreamJsonRpcResponseView},
     },
+    views::{TransactionStateView, TransactionStatusView, TransactionView},
     Id,
 };
-use diem_types::event::EventKey;
+use diem_types::{account_address::AccountAddress, event::EventKey};
 use futures::Stream;
 use std::{
     collections::HashMap,
@@ -159,6 +168,102 @@ impl StreamingClient {
         self.send_subscription(request).await
     }
 
+    pub async fn subscribe_account(
+        &mut self,
+        account_address: AccountAddress,
+    ) -> StreamResult<SubscriptionStream> {
+        let request =
+            StreamMethodRequest::SubscribeToAccount(SubscribeToAccountParams { account_address });
+        self.send_subscription(request).await
+    }
+
+    pub async fn subscribe_blocks(
+        &mut self,
+        starting_version: u64,
+    ) -> StreamResult<SubscriptionStream> {
+        let request =
+            StreamMethodRequest::SubscribeToBlocks(SubscribeToBlocksParams { starting_version });
+        self.send_subscription(request).await
+    }
+
+    pub async fn subscribe_transaction_status(
+        &mut self,
+        sender: AccountAddress,
+        sequence_number: u64,
+    ) -> StreamResult<SubscriptionStream> {
+        let request =
+            StreamMethodRequest::SubscribeToTransactionStatus(SubscribeToTransactionStatusParams {
+                sender: Some(sender),
+                sequence_number: Some(sequence_number),
+                hash: None,
+            });
+        self.send_subscription(request).await
+    }
+
+    pub async fn subscribe_transaction_status_by_hash(
+        &mut self,
+        hash: HashValue,
+    ) -> StreamResult<SubscriptionStream> {
+        let request =
+            StreamMethodRequest::SubscribeToTransactionStatus(SubscribeToTransactionStatusParams {
+                sender: None,
+                sequence_number: None,
+                hash: Some(hash),
+            });
+        self.send_subscription(request).await
+    }
+
+    /// Waits for the transaction to be committed, following its status as it moves through
+    /// mempool instead of polling `get_account_transaction`
+    pub async fn wait_for_transaction(
+        &mut self,
+        address: AccountAddress,
+        seq: u64,
+        txn_hash: HashValue,
+        timeout_duration: Option<Duration>,
+    ) -> Result<TransactionView, WaitForTransactionError> {
+        const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+        let mut subscription = self
+            .subscribe_transaction_status(address, seq)
+            .await
+            .map_err(WaitForTransactionError::SubscriptionError)?;
+
+        let wait = async {
+            loop {
+                let status = next_transaction_status(&mut subscription)
+                    .await
+                    .map_err(WaitForTransactionError::SubscriptionError)?;
+                match status.state {
+                    TransactionStateView::Accepted => continue,
+                    TransactionStateView::Expired => {
+                        return Err(WaitForTransactionError::TransactionExpired)
+                    }
+                    TransactionStateView::Evicted | TransactionStateView::Rejected => {
+                        return Err(WaitForTransactionError::TransactionDropped(status))
+                    }
+                    TransactionStateView::Replaced | TransactionStateView::Committed => {
+                        let txn = match status.transaction {
+                            Some(txn) => txn,
+                            None => {
+                                return Err(WaitForTransactionError::TransactionDropped(status))
+                            }
+                        };
+                        if txn.hash != txn_hash {
+                            return Err(WaitForTransactionError::TransactionHashMismatchError(txn));
+                        }
+                        return Ok(txn);
+                    }
+                }
+            }
+        };
+
+        match timeout(timeout_duration.unwrap_or(DEFAULT_TIMEOUT), wait).await {
+            Ok(result) => result,
+            Err(_) => Err(WaitForTransactionError::Timeout),
+        }
+    }
+
     pub(crate) async fn send_unsubscribe(&mut self, id: &Id) -> StreamResult<()> {
         debug!("StreamingClient sending unsubscribe for: {:?}", id);
         self.client
@@ -317,3 +422,20 @@ impl StreamingClient {
         Ok(SubscriptionStream::new(id, receiver, self.clone()))
     }
 }
+
+async fn next_transaction_status(
+    subscription: &mut SubscriptionStream,
+) -> StreamResult<TransactionStatusView> {
+    loop {
+        let msg = subscription.wait_for_msg().await??;
+        if let Some(err) = msg.error {
+            return Err(StreamError::subscription_json_rpc_error(err));
+        }
+        if let Some(StreamJsonRpcResponseView::TransactionStatus(status)) = msg
+            .parse_result(&StreamMethod::SubscribeToTransactionStatus)
+            .map_err(StreamError::decode)?
+        {
+            return Ok(status);
+        }
+    }
+}
@@ -9,14 +9,17 @@ use crate::{
     retry::Retry,
     verifying_client::{methods::VerifyingBatch, state_store::StateStore},
 };
-use diem_crypto::hash::{CryptoHash, HashValue};
+use diem_crypto::{
+    ed25519::Ed25519PublicKey,
+    hash::{CryptoHash, HashValue},
+};
 use diem_json_rpc_types::views::{
-    AccountView, CurrencyInfoView, EventView, MetadataView, TransactionView,
+    AccountView, CurrencyInfoView, EventView, MetadataView, SimulationView, TransactionView,
 };
 use diem_types::{
     account_address::AccountAddress,
     event::EventKey,
-    transaction::{SignedTransaction, Transaction, Version},
+    transaction::{RawTransaction, SignedTransaction, Transaction, Version},
     trusted_state::TrustedState,
     waypoint::Waypoint,
 };
@@ -236,6 +239,24 @@ impl<S: StateStore> VerifyingClient<S> {
         self.inner.submit(txn).await
     }
 
+    /// Simulate a signed user transaction against the server's latest state.
+    ///
+    /// Like `submit`, the simulation result can't be verified: it is computed
+    /// by the server and isn't backed by any proof.
+    pub async fn simulate(&self, txn: &SignedTransaction) -> Result<Response<SimulationView>> {
+        self.inner.simulate(txn).await
+    }
+
+    /// Simulate an unsigned user transaction on behalf of the holder of
+    /// `public_key`. The result is unverified, see `simulate`.
+    pub async fn simulate_unsigned(
+        &self,
+        txn: &RawTransaction,
+        public_key: &Ed25519PublicKey,
+    ) -> Result<Response<SimulationView>> {
+        self.inner.simulate_unsigned(txn, public_key).await
+    }
+
     pub async fn get_metadata_by_version(
         &self,
         version: Version,
@@ -256,7 +256,7 @@ impl From<MethodRequest> for VerifyingRequest {
             MethodRequest::Submit((txn,)) => submit(txn),
             MethodRequest::GetMetadata((None,)) => get_latest_metadata(),
             MethodRequest::GetMetadata((Some(version),)) => get_historical_metadata(version),
-            MethodRequest::GetAccount(address, version) => get_account(address, version),
+            MethodRequest::GetAccount(address, version, None) => get_account(address, version),
             MethodRequest::GetTransactions(start_version, limit, include_events) => {
                 get_transactions(start_version, limit, include_events)
             }
@@ -272,6 +272,7 @@ impl From<MethodRequest> for VerifyingRequest {
             MethodRequest::GetEvents(key, start_seq, limit) => get_events(key, start_seq, limit),
             MethodRequest::GetCurrencies([]) => get_currencies(),
             MethodRequest::GetNetworkStatus([]) => get_network_status(),
+            MethodRequest::Simulate(txn, public_key) => simulate(txn, public_key),
             _ => panic!(
                 "unsupported verifying client method: {:?}",
                 request.method()
@@ -298,6 +299,20 @@ fn submit(txn: String) -> VerifyingRequest {
     VerifyingRequest::new(request, subrequests, callback)
 }
 
+fn simulate(txn: String, public_key: Option<String>) -> VerifyingRequest {
+    let request = MethodRequest::Simulate(txn, public_key);
+    let subrequests = vec![request.clone()];
+    // Simulation results aren't backed by any proof, so we just pass them through.
+    let callback: RequestCallback = Box::new(move |_ctxt, subresponses| match subresponses {
+        [MethodResponse::Simulate(simulation)] => Ok(MethodResponse::Simulate(simulation.clone())),
+        subresponses => Err(Error::rpc_response(format!(
+            "expected [Simulate] subresponses, received: {:?}",
+            subresponses,
+        ))),
+    });
+    VerifyingRequest::new(request, subrequests, callback)
+}
+
 fn get_latest_metadata() -> VerifyingRequest {
     let request = MethodRequest::GetMetadata((None,));
     let subrequests = vec![MethodRequest::GetAccountStateWithProof(
@@ -426,7 +441,7 @@ fn get_historical_metadata(version: Version) -> VerifyingRequest {
 }
 
 fn get_account(address: AccountAddress, version: Option<Version>) -> VerifyingRequest {
-    let request = MethodRequest::GetAccount(address, version);
+    let request = MethodRequest::GetAccount(address, version, None);
     let subrequests = vec![MethodRequest::GetAccountStateWithProof(
         address, version, None,
     )];
@@ -2,16 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use backup_service::start_backup_service;
-use consensus::{consensus_provider::start_consensus, gen_consensus_reconfig_subscription};
+use consensus::{
+    consensus_provider::{start_consensus, start_consensus_observer},
+    gen_consensus_reconfig_subscription,
+};
 use debug_interface::node_debug_service::NodeDebugService;
 use diem_config::{
-    config::{NetworkConfig, NodeConfig, PersistableConfig},
+    config::{BootstrappingMode, NetworkConfig, NodeConfig, PersistableConfig},
     network_id::NodeNetworkId,
     utils::get_genesis_txn,
 };
 use diem_json_rpc::bootstrap_from_config as bootstrap_rpc;
 use diem_logger::{prelude::*, Logger};
-use diem_mempool::gen_mempool_reconfig_subscription;
+use diem_mempool::{gen_mempool_reconfig_subscription, TxnStatusCache};
 use diem_metrics::metric_server;
 use diem_time_service::TimeService;
 use diem_types::{
@@ -23,8 +26,11 @@ use diemdb::DiemDB;
 use executor::{db_bootstrapper::maybe_bootstrap, Executor};
 use executor_types::ChunkExecutor;
 use futures::{channel::mpsc::channel, executor::block_on};
+#[cfg(feature = "testing")]
+use netcore::transport::simulated::NetworkSimulator;
 use network_builder::builder::NetworkBuilder;
 use state_sync_v1::bootstrapper::StateSyncBootstrapper;
+use state_sync_v2::service::StateSyncService;
 use std::{
     boxed::Box,
     convert::TryFrom,
@@ -51,6 +57,7 @@ pub struct DiemHandle {
     _rpc: Runtime,
     _mempool: Runtime,
     _state_sync_bootstrapper: StateSyncBootstrapper,
+    _state_sync_service: StateSyncService,
     _network_runtimes: Vec<Runtime>,
     _consensus_runtime: Option<Runtime>,
     _debug: NodeDebugService,
@@ -253,6 +260,32 @@ async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
 }
 
 pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> DiemHandle {
+    setup_environment_with_network_hook(node_config, logger, |_| {})
+}
+
+/// Sets up the node like [`setup_environment`], but routes the connections of its '/memory/<port>'
+/// networks through `network_simulator`. This lets several nodes run in the same process over a
+/// simulated network.
+#[cfg(feature = "testing")]
+pub fn setup_environment_with_network_simulator(
+    node_config: &NodeConfig,
+    logger: Option<Arc<Logger>>,
+    network_simulator: NetworkSimulator,
+) -> DiemHandle {
+    setup_environment_with_network_hook(node_config, logger, |network_builder| {
+        network_builder.set_network_simulator(network_simulator.clone());
+    })
+}
+
+/// `configure_network` is applied to every network builder before it's built.
+fn setup_environment_with_network_hook<F>(
+    node_config: &NodeConfig,
+    logger: Option<Arc<Logger>>,
+    configure_network: F,
+) -> DiemHandle
+where
+    F: Fn(&mut NetworkBuilder),
+{
     let debug_if = setup_debug_interface(node_config, logger);
 
     let metrics_port = node_config.debug_interface.metrics_server_port;
@@ -270,6 +303,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             &node_config.storage.dir(),
             false, /* readonly */
             node_config.storage.prune_window,
+            node_config.storage.ledger_prune_window,
             node_config.storage.rocksdb_config,
         )
         .expect("DB should open."),
@@ -303,8 +337,11 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     let chain_id = fetch_chain_id(&db_rw);
     let mut network_runtimes = vec![];
     let mut state_sync_network_handles = vec![];
+    let mut state_sync_v2_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
+    let mut consensus_observer_network_handles = vec![];
+    let consensus_observer_config = &node_config.consensus_observer;
     let mut reconfig_subscriptions = vec![];
 
     let (mempool_reconfig_subscription, mempool_reconfig_events) =
@@ -342,8 +379,16 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             network_config,
             TimeService::real(),
         );
+        configure_network(&mut network_builder);
         let network_id = network_config.network_id.clone();
 
+        // Expose the scores and bans of the peers on the debug interface.
+        let peer_reputation = network_builder.peer_reputation();
+        debug_if.register_state(
+            format!("peer_reputation_{}", network_id.as_str()),
+            Box::new(move || peer_reputation.debug_state()),
+        );
+
         // Create the endpoints to connect the Network to State Sync.
         let (state_sync_sender, state_sync_events) =
             network_builder.add_protocol_handler(state_sync_v1::network::network_endpoint_config());
@@ -352,6 +397,13 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             state_sync_sender,
             state_sync_events,
         ));
+        let (state_sync_v2_sender, state_sync_v2_events) =
+            network_builder.add_protocol_handler(state_sync_v2::network::network_endpoint_config());
+        state_sync_v2_network_handles.push((
+            NodeNetworkId::new(network_id.clone(), idx),
+            state_sync_v2_sender,
+            state_sync_v2_events,
+        ));
 
         // Create the endpoints to connect the Network to mempool.
         let (mempool_sender, mempool_events) = network_builder.add_protocol_handler(
@@ -375,6 +427,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
                 network_builder
                     .add_protocol_handler(consensus::network_interface::network_endpoint_config()),
             );
+        } else if consensus_observer_config.publisher_enabled
+            || consensus_observer_config.observer_enabled
+        {
+            // Blocks are published to (and observed from) the fullnode networks only.
+            let (observer_sender, observer_events) = network_builder.add_protocol_handler(
+                consensus::consensus_observer::network::network_endpoint_config(
+                    consensus_observer_config.network_channel_size,
+                ),
+            );
+            consensus_observer_network_handles.push((
+                NodeNetworkId::new(network_id.clone(), idx),
+                observer_sender,
+                observer_events,
+            ));
         }
 
         reconfig_subscriptions.append(network_builder.reconfig_subscriptions());
@@ -397,6 +463,23 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             node_config.state_sync.client_commit_timeout_ms,
         );
 
+    // Serve state sync v2 requests and, if configured so, bootstrap from the latest state
+    // snapshot before state sync v1 continues from there.
+    let state_sync_service = StateSyncService::bootstrap(
+        state_sync_v2_network_handles,
+        db_rw.clone(),
+        node_config,
+        genesis_waypoint,
+    );
+    // The snapshot is synced in the background, state sync v1 waits for it before starting.
+    let state_snapshot_synced = if node_config.state_sync.bootstrapping_mode
+        == BootstrappingMode::DownloadLatestAccountStates
+    {
+        Some(state_sync_service.start_state_snapshot_sync())
+    } else {
+        None
+    };
+
     // Create state sync bootstrapper
     let state_sync_bootstrapper = StateSyncBootstrapper::bootstrap(
         state_sync_network_handles,
@@ -407,10 +490,18 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         node_config,
         genesis_waypoint,
         reconfig_subscriptions,
+        state_snapshot_synced,
     );
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
+    let txn_status_cache = TxnStatusCache::new(&node_config.mempool);
 
-    let rpc_runtime = bootstrap_rpc(node_config, chain_id, diem_db.clone(), mp_client_sender);
+    let rpc_runtime = bootstrap_rpc(
+        node_config,
+        chain_id,
+        diem_db.clone(),
+        mp_client_sender,
+        txn_status_cache.clone(),
+    );
 
     let mut consensus_runtime = None;
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
@@ -424,6 +515,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         consensus_requests,
         mempool_listener,
         mempool_reconfig_events,
+        txn_status_cache,
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
 
@@ -452,8 +544,29 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_to_mempool_sender,
             diem_db,
             consensus_reconfig_events,
+            consensus_observer_network_handles,
         ));
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+    } else if consensus_observer_config.observer_enabled {
+        let state_sync_client = state_sync_bootstrapper.create_client();
+
+        // The observer executes on top of the state synced up to the waypoint.
+        debug!("Wait until state sync is initialized");
+        block_on(state_sync_client.wait_until_initialized())
+            .expect("State sync initialization failure");
+        debug!("State sync initialization complete.");
+
+        instant = Instant::now();
+        consensus_runtime = Some(start_consensus_observer(
+            node_config,
+            consensus_observer_network_handles,
+            Box::new(consensus_notifier),
+            db_rw.clone(),
+        ));
+        debug!(
+            "Consensus observer started in {} ms",
+            instant.elapsed().as_millis()
+        );
     }
 
     // Spawn a task which will periodically dump some interesting state
@@ -467,6 +580,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         _rpc: rpc_runtime,
         _mempool: mempool,
         _state_sync_bootstrapper: state_sync_bootstrapper,
+        _state_sync_service: state_sync_service,
         _consensus_runtime: consensus_runtime,
         _debug: debug_if,
         _backup: backup_service,
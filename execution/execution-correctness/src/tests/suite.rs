@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::execution_correctness::ExecutionCorrectness;
-use consensus_types::{block::Block, vote_proposal::VoteProposal};
+use consensus_types::{
+    block::{block_test_utils::random_payload, Block},
+    common::Payload,
+    vote_proposal::VoteProposal,
+};
 use diem_crypto::{ed25519::*, traits::Signature};
 use executor_test_helpers::{extract_signer, gen_ledger_info_with_sigs};
 
@@ -15,8 +19,17 @@ pub fn run_test_suite(executor_pair: (Box<dyn ExecutionCorrectness>, Option<Ed25
     let block = Block::make_genesis_block();
     let block_id = block.id();
 
+    // Transactions that the payload of the block doesn't resolve to are not executed
+    let txns = match random_payload(1) {
+        Payload::DirectMempool(txns) => txns,
+        Payload::InQuorumStore(_) => unreachable!(),
+    };
+    executor
+        .execute_block(block.clone(), txns, parent_block_id)
+        .unwrap_err();
+
     let result = executor
-        .execute_block(block.clone(), parent_block_id)
+        .execute_block(block.clone(), vec![], parent_block_id)
         .unwrap();
 
     if let Some(sig) = result.signature().as_ref() {
@@ -373,6 +373,7 @@ pub fn create_storage_service_and_executor(
             &config.storage.dir(),
             false, /* readonly */
             None,  /* pruner */
+            None,  /* ledger_pruner */
             RocksdbConfig::default(),
         )
         .expect("DB should open."),
@@ -22,7 +22,8 @@ use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     proof::{accumulator::InMemoryAccumulator, AccumulatorExtensionProof},
     transaction::{
-        Transaction, TransactionInfo, TransactionListWithProof, TransactionStatus, Version,
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionOutputListWithProof,
+        TransactionStatus, Version,
     },
 };
 use serde::{Deserialize, Serialize};
@@ -45,6 +46,17 @@ pub trait ChunkExecutor: Send + Sync {
         // carrying any epoch change LI.
         epoch_change_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<Vec<ContractEvent>>;
+
+    /// Verifies the transaction outputs based on the provided proofs and ledger info. If they are
+    /// valid, applies their write sets without executing the transactions and commits immediately
+    /// if the resulting state matches the proofs.
+    /// Returns a vector of reconfiguration events in the chunk
+    fn apply_and_commit_chunk(
+        &self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>>;
 }
 
 pub trait BlockExecutor: Send + Sync {
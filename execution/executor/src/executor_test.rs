@@ -15,12 +15,17 @@ use diem_types::{
     account_address::AccountAddress,
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    transaction::{Transaction, TransactionListWithProof, Version},
+    transaction::{
+        Transaction, TransactionListWithProof, TransactionOutput, TransactionOutputListWithProof,
+        Version,
+    },
+    write_set::WriteSet,
 };
 use diemdb::DiemDB;
 use proptest::prelude::*;
 use rand::Rng;
 use std::collections::BTreeMap;
+use storage_interface::DbReader;
 
 fn execute_and_commit_block(
     executor: &TestExecutor,
@@ -247,6 +252,36 @@ fn test_executor_execute_same_block_multiple_times() {
 fn create_transaction_chunks(
     chunk_ranges: Vec<std::ops::Range<Version>>,
 ) -> (Vec<TransactionListWithProof>, LedgerInfoWithSignatures) {
+    create_chunks(chunk_ranges, |reader, range, ledger_version| {
+        reader
+            .get_transactions(
+                range.start,
+                range.end - range.start,
+                ledger_version,
+                false, /* fetch_events */
+            )
+            .unwrap()
+    })
+}
+
+/// Generates a list of `TransactionOutputListWithProof`s according to the given ranges.
+fn create_transaction_output_chunks(
+    chunk_ranges: Vec<std::ops::Range<Version>>,
+) -> (
+    Vec<TransactionOutputListWithProof>,
+    LedgerInfoWithSignatures,
+) {
+    create_chunks(chunk_ranges, |reader, range, ledger_version| {
+        reader
+            .get_transaction_outputs(range.start, range.end - range.start, ledger_version)
+            .unwrap()
+    })
+}
+
+fn create_chunks<T>(
+    chunk_ranges: Vec<std::ops::Range<Version>>,
+    get_chunk: impl Fn(&dyn DbReader, std::ops::Range<Version>, Version) -> T,
+) -> (Vec<T>, LedgerInfoWithSignatures) {
     assert_eq!(chunk_ranges.first().unwrap().start, 1);
     for i in 1..chunk_ranges.len() {
         let previous_range = &chunk_ranges[i - 1];
@@ -258,7 +293,7 @@ fn create_transaction_chunks(
     }
 
     // To obtain the batches of transactions, we first execute and save all these transactions in a
-    // separate DB. Then we call get_transactions to retrieve them.
+    // separate DB. Then we call `get_chunk` to retrieve them.
     let TestExecutor {
         _path,
         db: _,
@@ -283,18 +318,7 @@ fn create_transaction_chunks(
 
     let batches: Vec<_> = chunk_ranges
         .into_iter()
-        .map(|range| {
-            executor
-                .db
-                .reader
-                .get_transactions(
-                    range.start,
-                    range.end - range.start,
-                    ledger_version,
-                    false, /* fetch_events */
-                )
-                .unwrap()
-        })
+        .map(|range| get_chunk(&*executor.db.reader, range, ledger_version))
         .collect();
 
     (batches, ledger_info)
@@ -451,6 +475,68 @@ fn test_executor_execute_and_commit_chunk_local_result_mismatch() {
         .is_err());
 }
 
+#[test]
+fn test_executor_apply_and_commit_chunk() {
+    let first_batch_size = 30;
+    let second_batch_size = 40;
+    let overlapping_size = 5;
+
+    let (chunks, ledger_info) = {
+        let first_batch_start = 1;
+        let second_batch_start = first_batch_start + first_batch_size - overlapping_size;
+        create_transaction_output_chunks(vec![
+            first_batch_start..first_batch_start + first_batch_size,
+            second_batch_start..second_batch_start + second_batch_size,
+        ])
+    };
+
+    let TestExecutor {
+        _path,
+        db,
+        executor,
+    } = TestExecutor::new();
+
+    // Apply the first chunk. After that we should still get the genesis ledger info from DB.
+    executor
+        .apply_and_commit_chunk(chunks[0].clone(), ledger_info.clone(), None)
+        .unwrap();
+    let li = db.reader.get_latest_ledger_info().unwrap();
+    assert_eq!(li.ledger_info().version(), 0);
+    assert_eq!(li.ledger_info().consensus_block_id(), HashValue::zero());
+
+    // Apply the second chunk, overlapping with the first one. After that we should get the new
+    // ledger info.
+    executor
+        .apply_and_commit_chunk(chunks[1].clone(), ledger_info.clone(), None)
+        .unwrap();
+    let li = db.reader.get_latest_ledger_info().unwrap();
+    assert_eq!(li, ledger_info);
+}
+
+#[test]
+fn test_executor_apply_and_commit_chunk_write_set_mismatch() {
+    let (mut chunks, ledger_info) = create_transaction_output_chunks(vec![1..11]);
+
+    let TestExecutor {
+        _path,
+        db: _,
+        executor,
+    } = TestExecutor::new();
+
+    // Drop the write set of a transaction. The resulting state root hash doesn't match the one in
+    // the transaction info anymore, so the chunk should be rejected.
+    let (_, output) = &mut chunks[0].transactions_and_outputs[5];
+    *output = TransactionOutput::new(
+        WriteSet::default(),
+        output.events().to_vec(),
+        output.gas_used(),
+        output.status().clone(),
+    );
+    assert!(executor
+        .apply_and_commit_chunk(chunks[0].clone(), ledger_info, None)
+        .is_err());
+}
+
 #[test]
 fn test_noop_block_after_reconfiguration() {
     let executor = TestExecutor::new();
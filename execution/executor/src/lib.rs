@@ -19,10 +19,10 @@ pub mod db_bootstrapper;
 use crate::{
     logging::{LogEntry, LogSchema},
     metrics::{
-        DIEM_EXECUTOR_COMMIT_BLOCKS_SECONDS, DIEM_EXECUTOR_ERRORS,
-        DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS, DIEM_EXECUTOR_EXECUTE_BLOCK_SECONDS,
-        DIEM_EXECUTOR_SAVE_TRANSACTIONS_SECONDS, DIEM_EXECUTOR_TRANSACTIONS_SAVED,
-        DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
+        DIEM_EXECUTOR_APPLY_AND_COMMIT_CHUNK_SECONDS, DIEM_EXECUTOR_COMMIT_BLOCKS_SECONDS,
+        DIEM_EXECUTOR_ERRORS, DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS,
+        DIEM_EXECUTOR_EXECUTE_BLOCK_SECONDS, DIEM_EXECUTOR_SAVE_TRANSACTIONS_SECONDS,
+        DIEM_EXECUTOR_TRANSACTIONS_SAVED, DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
     },
     speculation_cache::SpeculationCache,
     types::{ProcessedVMOutput, TransactionData},
@@ -34,7 +34,7 @@ use diem_crypto::{
 };
 use diem_infallible::{RwLock, RwLockReadGuard};
 use diem_logger::prelude::*;
-use diem_state_view::StateViewId;
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state::AccountState,
@@ -43,10 +43,11 @@ use diem_types::{
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     on_chain_config,
-    proof::accumulator::InMemoryAccumulator,
+    proof::{accumulator::InMemoryAccumulator, TransactionListProof},
     transaction::{
         Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
-        TransactionPayload, TransactionStatus, TransactionToCommit, Version,
+        TransactionOutputListWithProof, TransactionPayload, TransactionStatus, TransactionToCommit,
+        Version,
     },
     write_set::{WriteOp, WriteSet},
 };
@@ -178,18 +179,65 @@ where
             txn_list_with_proof.first_transaction_version,
         )?;
 
-        // Return empty if there's no work to do.
-        if txn_list_with_proof.transactions.is_empty() {
-            return Ok((Vec::new(), Vec::new()));
+        // 2. Verify that skipped transactions match what's already persisted (no fork):
+        let num_txns_to_skip = self.verify_txns_to_skip(
+            txn_list_with_proof.first_transaction_version,
+            &txn_list_with_proof.proof,
+        )?;
+
+        // 3. Return verified transactions to be applied.
+        let mut txns: Vec<_> = txn_list_with_proof.transactions;
+        txns.drain(0..num_txns_to_skip);
+        let (_, mut txn_infos) = txn_list_with_proof.proof.unpack();
+        txn_infos.drain(0..num_txns_to_skip);
+
+        Ok((txns, txn_infos))
+    }
+
+    /// Same as `verify_chunk`, for a chunk of transaction outputs. Note that the write sets are
+    /// only verified once applied, by checking the resulting state root hashes.
+    fn verify_output_chunk(
+        &self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        verified_target_li: &LedgerInfoWithSignatures,
+    ) -> Result<(Vec<(Transaction, TransactionOutput)>, Vec<TransactionInfo>)> {
+        // 1. Verify that input transactions belongs to the ledger represented by the ledger info,
+        // and that the outputs match their transaction infos.
+        output_list_with_proof.verify(
+            verified_target_li.ledger_info(),
+            output_list_with_proof.first_transaction_output_version,
+        )?;
+
+        // 2. Verify that skipped transactions match what's already persisted (no fork):
+        let num_txns_to_skip = self.verify_txns_to_skip(
+            output_list_with_proof.first_transaction_output_version,
+            &output_list_with_proof.proof,
+        )?;
+
+        // 3. Return verified transaction outputs to be applied.
+        let mut txns_and_outputs = output_list_with_proof.transactions_and_outputs;
+        txns_and_outputs.drain(0..num_txns_to_skip);
+        let (_, mut txn_infos) = output_list_with_proof.proof.unpack();
+        txn_infos.drain(0..num_txns_to_skip);
+
+        Ok((txns_and_outputs, txn_infos))
+    }
+
+    /// Returns how many transactions at the beginning of a verified chunk are already persisted,
+    /// after verifying that they match what's persisted (no fork).
+    fn verify_txns_to_skip(
+        &self,
+        first_txn_version: Option<Version>,
+        proof: &TransactionListProof,
+    ) -> Result<usize> {
+        // Return early if there's no work to do.
+        let num_txns = proof.transaction_infos().len();
+        if num_txns == 0 {
+            return Ok(0);
         }
-        let first_txn_version = match txn_list_with_proof.first_transaction_version {
-            Some(tx) => tx as Version,
-            None => {
-                bail!(
-                    "first_transaction_version doesn't exist in {:?}",
-                    txn_list_with_proof
-                );
-            }
+        let first_txn_version = match first_txn_version {
+            Some(version) => version,
+            None => bail!("first_transaction_version doesn't exist in a non-empty chunk."),
         };
         let read_lock = self.cache.read();
 
@@ -200,28 +248,23 @@ where
             num_committed_txns,
             first_txn_version
         );
-        let versions_between_first_and_committed = num_committed_txns - first_txn_version;
-        if txn_list_with_proof.transactions.len() <= versions_between_first_and_committed as usize {
+        let num_txns_to_skip = num_committed_txns - first_txn_version;
+        if num_txns <= num_txns_to_skip as usize {
             // All already in DB, nothing to do.
-            return Ok((Vec::new(), Vec::new()));
+            return Ok(num_txns);
         }
 
-        // 2. Verify that skipped transactions match what's already persisted (no fork):
-        let num_txns_to_skip = num_committed_txns - first_txn_version;
-
         debug!(
             LogSchema::new(LogEntry::ChunkExecutor).num(num_txns_to_skip),
             "skipping_chunk_txns"
         );
 
         // If the proof is verified, then the length of txn_infos and txns must be the same.
-        let skipped_transaction_infos =
-            &txn_list_with_proof.proof.transaction_infos()[..num_txns_to_skip as usize];
+        let skipped_transaction_infos = &proof.transaction_infos()[..num_txns_to_skip as usize];
 
         // Left side of the proof happens to be the frozen subtree roots of the accumulator
         // right before the list of txns are applied.
-        let frozen_subtree_roots_from_proof = txn_list_with_proof
-            .proof
+        let frozen_subtree_roots_from_proof = proof
             .left_siblings()
             .iter()
             .rev()
@@ -243,13 +286,7 @@ where
             "Fork happens because the current synced_trees doesn't match the txn list provided."
         );
 
-        // 3. Return verified transactions to be applied.
-        let mut txns: Vec<_> = txn_list_with_proof.transactions;
-        txns.drain(0..num_txns_to_skip as usize);
-        let (_, mut txn_infos) = txn_list_with_proof.proof.unpack();
-        txn_infos.drain(0..num_txns_to_skip as usize);
-
-        Ok((txns, txn_infos))
+        Ok(num_txns_to_skip as usize)
     }
 
     /// Post-processing of what the VM outputs. Returns the entire block's output.
@@ -354,6 +391,7 @@ where
             txn_data.push(TransactionData::new(
                 blobs,
                 new_node_hashes,
+                vm_output.write_set().clone(),
                 vm_output.events().to_vec(),
                 vm_output.status().clone(),
                 state_tree_hash,
@@ -371,6 +409,7 @@ where
                 TransactionData::new(
                     HashMap::new(),
                     HashMap::new(),
+                    WriteSet::default(),
                     vec![],
                     TransactionStatus::Retry,
                     current_state_tree.root_hash(),
@@ -509,6 +548,94 @@ where
             read_lock.synced_trees(),
         )?;
 
+        let (txns_to_commit, events, txns_to_retry, txn_infos_to_retry) =
+            Self::collect_txns_to_commit(first_version, transactions, transaction_infos, &output)?;
+
+        Ok((
+            output,
+            txns_to_commit,
+            events,
+            txns_to_retry,
+            txn_infos_to_retry,
+        ))
+    }
+
+    /// Applies the outputs of transactions that have already been executed and committed by other
+    /// validators, without running the VM. The write sets are verified against the transaction
+    /// infos by comparing the resulting state root hashes.
+    fn apply_transaction_outputs(
+        &self,
+        first_version: u64,
+        transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+        transaction_infos: Vec<TransactionInfo>,
+    ) -> Result<(
+        ProcessedVMOutput,
+        Vec<TransactionToCommit>,
+        Vec<ContractEvent>,
+    )> {
+        let read_lock = self.cache.read();
+        let state_view = VerifiedStateView::new(
+            StateViewId::ChunkExecution { first_version },
+            Arc::clone(&self.db.reader),
+            read_lock.synced_trees().version(),
+            read_lock.synced_trees().state_root(),
+            read_lock.synced_trees().state_tree(),
+        );
+
+        // Since other validators have committed these transactions, their status should all be
+        // TransactionStatus::Keep.
+        for (_, output) in &transactions_and_outputs {
+            if !matches!(output.status(), TransactionStatus::Keep(_)) {
+                bail!(
+                    "Applying a transaction output with status {:?}.",
+                    output.status()
+                );
+            }
+            // Load the states and proofs of the touched accounts, so that the write sets can be
+            // applied on top of them.
+            for (access_path, _) in output.write_set() {
+                state_view.get(access_path)?;
+            }
+        }
+
+        let (account_to_state, account_to_proof) = state_view.into();
+        let (transactions, outputs): (Vec<_>, Vec<_>) =
+            transactions_and_outputs.into_iter().unzip();
+
+        let output = Self::process_vm_outputs(
+            account_to_state,
+            account_to_proof,
+            &transactions,
+            outputs,
+            read_lock.synced_trees(),
+        )?;
+
+        let (txns_to_commit, events, txns_to_retry, _txn_infos_to_retry) =
+            Self::collect_txns_to_commit(first_version, transactions, transaction_infos, &output)?;
+        ensure!(
+            txns_to_retry.is_empty(),
+            "The transaction at version {} got the status of 'Retry'",
+            first_version
+                .checked_add(txns_to_commit.len() as u64)
+                .ok_or_else(|| format_err!("integer overflow occurred"))?,
+        );
+
+        Ok((output, txns_to_commit, events))
+    }
+
+    /// Verifies that each TransactionInfo matches what has been computed locally and returns the
+    /// transactions to commit together with their events, followed by the transactions to retry.
+    fn collect_txns_to_commit(
+        first_version: u64,
+        transactions: Vec<Transaction>,
+        transaction_infos: Vec<TransactionInfo>,
+        output: &ProcessedVMOutput,
+    ) -> Result<(
+        Vec<TransactionToCommit>,
+        Vec<ContractEvent>,
+        Vec<Transaction>,
+        Vec<TransactionInfo>,
+    )> {
         // Since we have verified the proofs, we just need to verify that each TransactionInfo
         // object matches what we have computed locally.
         let mut txns_to_commit = vec![];
@@ -553,6 +680,7 @@ where
                 txn,
                 txn_data.account_blobs().clone(),
                 Some(txn_data.jf_node_hashes().clone()),
+                txn_data.write_set().clone(),
                 txn_data.events().to_vec(),
                 txn_data.gas_used(),
                 recorded_status,
@@ -560,13 +688,7 @@ where
             events.append(&mut txn_data.events().to_vec());
         }
 
-        Ok((
-            output,
-            txns_to_commit,
-            events,
-            txns_to_retry,
-            txn_infos_to_retry,
-        ))
+        Ok((txns_to_commit, events, txns_to_retry, txn_infos_to_retry))
     }
 
     fn execute_chunk(
@@ -596,6 +718,55 @@ where
 
         Ok((processed_vm_output, txns_to_commit, events))
     }
+
+    /// Commits the output of a verified chunk to the DB, together with the ledger info ending
+    /// the chunk if any, and updates the cache accordingly.
+    fn commit_chunk(
+        &self,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+        first_version: Version,
+        output: ProcessedVMOutput,
+        txns_to_commit: Vec<TransactionToCommit>,
+    ) -> Result<()> {
+        let ledger_info_to_commit =
+            Self::find_chunk_li(verified_target_li, epoch_change_li, &output)?;
+        if ledger_info_to_commit.is_none() && txns_to_commit.is_empty() {
+            return Ok(());
+        }
+        fail_point!("executor::commit_chunk", |_| {
+            Err(anyhow::anyhow!("Injected error in commit_chunk"))
+        });
+        self.db.writer.save_transactions(
+            &txns_to_commit,
+            first_version,
+            ledger_info_to_commit.as_ref(),
+        )?;
+
+        // Cache maintenance.
+        let mut write_lock = self.cache.write();
+        let output_trees = output.executed_trees().clone();
+        if let Some(ledger_info_with_sigs) = &ledger_info_to_commit {
+            write_lock.update_block_tree_root(output_trees, ledger_info_with_sigs.ledger_info());
+        } else {
+            write_lock.update_synced_trees(output_trees);
+        }
+        write_lock.reset();
+
+        info!(
+            LogSchema::new(LogEntry::ChunkExecutor)
+                .synced_to_version(
+                    write_lock
+                        .synced_trees()
+                        .version()
+                        .expect("version must exist")
+                )
+                .committed_with_ledger_info(ledger_info_to_commit.is_some()),
+            "sync_finished",
+        );
+
+        Ok(())
+    }
 }
 
 impl<V: VMExecutor> ChunkExecutor for Executor<V> {
@@ -631,43 +802,59 @@ impl<V: VMExecutor> ChunkExecutor for Executor<V> {
         let (output, txns_to_commit, events) =
             self.execute_chunk(first_version, transactions, transaction_infos)?;
 
-        // 4. Commit to DB.
-        let ledger_info_to_commit =
-            Self::find_chunk_li(verified_target_li, epoch_change_li, &output)?;
-        if ledger_info_to_commit.is_none() && txns_to_commit.is_empty() {
-            return Ok(events);
-        }
-        fail_point!("executor::commit_chunk", |_| {
-            Err(anyhow::anyhow!("Injected error in commit_chunk"))
-        });
-        self.db.writer.save_transactions(
-            &txns_to_commit,
+        // 4. Commit to DB and update the cache.
+        self.commit_chunk(
+            verified_target_li,
+            epoch_change_li,
             first_version,
-            ledger_info_to_commit.as_ref(),
+            output,
+            txns_to_commit,
         )?;
 
-        // 5. Cache maintenance.
-        let mut write_lock = self.cache.write();
-        let output_trees = output.executed_trees().clone();
-        if let Some(ledger_info_with_sigs) = &ledger_info_to_commit {
-            write_lock.update_block_tree_root(output_trees, ledger_info_with_sigs.ledger_info());
-        } else {
-            write_lock.update_synced_trees(output_trees);
-        }
-        write_lock.reset();
+        Ok(events)
+    }
+
+    fn apply_and_commit_chunk(
+        &self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>> {
+        let _timer = DIEM_EXECUTOR_APPLY_AND_COMMIT_CHUNK_SECONDS.start_timer();
+        // 1. Update the cache in executor to be consistent with latest synced state.
+        self.reset_cache()?;
+        let read_lock = self.cache.read();
 
         info!(
             LogSchema::new(LogEntry::ChunkExecutor)
-                .synced_to_version(
-                    write_lock
-                        .synced_trees()
-                        .version()
-                        .expect("version must exist")
-                )
-                .committed_with_ledger_info(ledger_info_to_commit.is_some()),
-            "sync_finished",
+                .local_synced_version(read_lock.synced_trees().txn_accumulator().num_leaves() - 1)
+                .first_version_in_request(output_list_with_proof.first_transaction_output_version)
+                .num_txns_in_request(output_list_with_proof.len()),
+            "sync_request_received",
         );
 
+        // 2. Verify input transaction output list.
+        let (transactions_and_outputs, transaction_infos) =
+            self.verify_output_chunk(output_list_with_proof, &verified_target_li)?;
+
+        // 3. Apply transaction outputs.
+        let first_version = read_lock.synced_trees().txn_accumulator().num_leaves();
+        drop(read_lock);
+        let (output, txns_to_commit, events) = self.apply_transaction_outputs(
+            first_version,
+            transactions_and_outputs,
+            transaction_infos,
+        )?;
+
+        // 4. Commit to DB and update the cache.
+        self.commit_chunk(
+            verified_target_li,
+            epoch_change_li,
+            first_version,
+            output,
+            txns_to_commit,
+        )?;
+
         Ok(events)
     }
 }
@@ -890,6 +1077,7 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
                     txn.clone(),
                     txn_data.account_blobs().clone(),
                     Some(txn_data.jf_node_hashes().clone()),
+                    txn_data.write_set().clone(),
                     txn_data.events().to_vec(),
                     txn_data.gas_used(),
                     recorded_status.clone(),
@@ -755,6 +755,64 @@ impl PublicUsageTest for ReSubmittingTransactionWontFail {
     }
 }
 
+pub struct SimulateTransaction;
+
+impl Test for SimulateTransaction {
+    fn name(&self) -> &'static str {
+        "jsonrpc::simulate-transaction"
+    }
+}
+
+impl PublicUsageTest for SimulateTransaction {
+    fn run<'t>(&self, ctx: &mut PublicUsageContext<'t>) -> Result<()> {
+        let env = JsonRpcTestHelper::new(ctx.url().to_owned());
+        let factory = ctx.transaction_factory();
+        let (_parent, mut child1, child2) =
+            env.create_parent_and_child_accounts(ctx, 1_000_000_000)?;
+
+        // A signed transaction that would be executed successfully
+        let txn = child1.sign_with_transaction_builder(factory.peer_to_peer(
+            Currency::XUS,
+            child2.address(),
+            200,
+        ));
+        let txn_hex = hex::encode(bcs::to_bytes(&txn)?);
+        let resp = env.send("simulate", json!([txn_hex]));
+        let result = resp.result.unwrap();
+        assert_eq!(
+            result["transaction"]["vm_status"],
+            json!({"type": "executed"})
+        );
+        assert!(result["transaction"]["gas_used"].as_u64().unwrap() > 0);
+        assert!(!result["transaction"]["events"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert!(!result["write_set"].as_array().unwrap().is_empty());
+
+        // Nothing is committed by simulating a transaction
+        assert_eq!(env.get_account_sequence(child1.address())?, 0);
+        assert_eq!(env.get_balance(child2.address(), "XUS"), 1_000_000_000);
+
+        // An unsigned transaction that would abort
+        let raw_txn = factory
+            .peer_to_peer(Currency::XUS, child2.address(), 200000000000000)
+            .sender(child1.address())
+            .sequence_number(0)
+            .build();
+        let raw_txn_hex = hex::encode(bcs::to_bytes(&raw_txn)?);
+        let public_key_hex = hex::encode(child1.public_key().to_bytes());
+        let resp = env.send("simulate", json!([raw_txn_hex, public_key_hex]));
+        let result = resp.result.unwrap();
+        assert_eq!(result["transaction"]["vm_status"]["type"], "move_abort");
+        assert_eq!(result["transaction"]["vm_status"]["abort_code"], 1288);
+        // The aborted transaction is still kept and charged for gas
+        assert!(!result["write_set"].as_array().unwrap().is_empty());
+
+        Ok(())
+    }
+}
+
 pub struct MempoolValidationError;
 
 impl Test for MempoolValidationError {
@@ -19,6 +19,7 @@ fn main() -> Result<()> {
             &PeerToPeerWithEvents,
             &PeerToPeerErrorExplination,
             &ReSubmittingTransactionWontFail,
+            &SimulateTransaction,
             &MempoolValidationError,
             &ExpiredTransaction,
             &RotateComplianceKeyEvent,
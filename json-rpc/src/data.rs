@@ -5,23 +5,31 @@ use crate::{
     errors::JsonRpcError,
     views::{
         AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView,
+        AccumulatorConsistencyProofView, BlockView, CurrencyInfoView, EventByVersionWithProofView,
+        EventView, EventWithProofView, MetadataView, SimulationView, StateProofView,
+        TransactionListView, TransactionView, TransactionsWithProofsView,
     },
 };
 use anyhow::Result;
+use diem_scratchpad::SparseMerkleTree;
+use diem_state_view::StateViewId;
 use diem_types::{
-    account_address::AccountAddress, account_config::diem_root_address,
-    account_state::AccountState, chain_id::ChainId, event::EventKey,
+    account_address::AccountAddress,
+    account_config::diem_root_address,
+    account_state::AccountState,
+    chain_id::ChainId,
+    event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
+    transaction::{SignedTransaction, Transaction, TransactionStatus},
 };
+use diem_vm::DiemVM;
 use resource_viewer::{AnnotatedMoveStruct, MoveValueAnnotator};
 use std::{
     collections::BTreeMap,
     convert::{TryFrom, TryInto},
+    sync::Arc,
 };
-use storage_interface::{MoveDbReader, Order};
+use storage_interface::{state_view::VerifiedStateView, MoveDbReader, Order};
 
 pub fn get_account_state(
     db: &dyn MoveDbReader,
@@ -77,6 +85,54 @@ pub fn get_account(
     )?))
 }
 
+/// Returns up to `limit` blocks whose block metadata transaction is at or after `start_version`.
+/// A block spans from its block metadata transaction up to (but excluding) the next one; the
+/// last block spans up to `ledger_version`, as a ledger info is only ever committed on a block
+/// boundary. Transactions before the first block metadata transaction are skipped.
+pub fn get_blocks(
+    db: &dyn MoveDbReader,
+    ledger_version: u64,
+    start_version: u64,
+    limit: u64,
+) -> Result<Vec<BlockView>, JsonRpcError> {
+    let mut blocks = vec![];
+    if limit == 0 {
+        return Ok(blocks);
+    }
+
+    let mut pending_block = None;
+    let mut cursor = start_version;
+    while cursor <= ledger_version && (blocks.len() as u64) < limit {
+        let txns = db
+            .get_transactions(cursor, limit, ledger_version, false)?
+            .transactions;
+        if txns.is_empty() {
+            break;
+        }
+        let num_txns = txns.len() as u64;
+        for (idx, txn) in txns.into_iter().enumerate() {
+            let version = cursor + idx as u64;
+            if let Transaction::BlockMetadata(block_metadata) = txn {
+                if let Some((metadata, first_version)) = pending_block.take() {
+                    blocks.push(BlockView::new(metadata, first_version, version - 1));
+                    if blocks.len() as u64 == limit {
+                        return Ok(blocks);
+                    }
+                }
+                pending_block = Some((block_metadata, version));
+            }
+        }
+        cursor += num_txns;
+    }
+
+    if cursor > ledger_version {
+        if let Some((metadata, first_version)) = pending_block {
+            blocks.push(BlockView::new(metadata, first_version, ledger_version));
+        }
+    }
+    Ok(blocks)
+}
+
 /// Returns transactions by range
 pub fn get_transactions(
     db: &dyn MoveDbReader,
@@ -243,6 +299,15 @@ pub fn get_network_status(_role: &str) -> Result<u64, JsonRpcError> {
     Ok(0)
 }
 
+/// Returns the version of the last transaction committed before the given timestamp
+pub fn get_version_by_timestamp(
+    db: &dyn MoveDbReader,
+    ledger_version: u64,
+    timestamp_usecs: u64,
+) -> Result<u64, JsonRpcError> {
+    Ok(db.get_last_version_before_timestamp(timestamp_usecs, ledger_version)?)
+}
+
 /// Returns proof of new state relative to version known to client
 pub fn get_state_proof(
     db: &dyn MoveDbReader,
@@ -317,3 +382,34 @@ pub fn get_resources(
     }
     Ok(resources)
 }
+
+/// Executes `txn` against the latest state, without verifying its signature or committing it.
+/// Discarded transactions are reported as errors, the same way `submit` reports them.
+pub fn simulate(
+    db: Arc<dyn MoveDbReader>,
+    txn: SignedTransaction,
+) -> Result<SimulationView, JsonRpcError> {
+    let (version, state_root) = db.get_latest_state_root()?;
+    let smt = SparseMerkleTree::new(state_root);
+    let state_view = VerifiedStateView::new(
+        StateViewId::Miscellaneous,
+        db.into_db_reader(),
+        Some(version),
+        state_root,
+        &smt,
+    );
+
+    let (_vm_status, output) = DiemVM::simulate_signed_transaction(txn.clone(), &state_view);
+    match output.status() {
+        TransactionStatus::Keep(status) => Ok(SimulationView::try_from_txn_and_output(
+            version + 1,
+            txn,
+            status,
+            &output,
+        )?),
+        TransactionStatus::Discard(status) => Err(JsonRpcError::vm_status(*status)),
+        TransactionStatus::Retry => Err(JsonRpcError::internal_error(
+            "unexpected retry status for simulated transaction".to_string(),
+        )),
+    }
+}
@@ -8,8 +8,8 @@ use crate::{
     views::{
         AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
         AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView,
+        EventWithProofView, MetadataView, SimulationView, StateProofView, TransactionListView,
+        TransactionView, TransactionsWithProofsView,
     },
 };
 use anyhow::Result;
@@ -20,7 +20,8 @@ use diem_json_rpc_types::request::{
     GetAccumulatorConsistencyProofParams, GetCurrenciesParams, GetEventByVersionWithProof,
     GetEventsParams, GetEventsWithProofsParams, GetMetadataParams, GetNetworkStatusParams,
     GetResourcesParams, GetStateProofParams, GetTransactionsParams,
-    GetTransactionsWithProofsParams, MethodRequest, SubmitParams,
+    GetTransactionsWithProofsParams, GetVersionByTimestampParams, MethodRequest, SimulateParams,
+    SubmitParams,
 };
 use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
@@ -142,6 +143,36 @@ impl<'a> Handler<'a> {
         Ok(version)
     }
 
+    /// Resolves the version an account should be read at, given either a `version` or a
+    /// `timestamp_usecs` param, defaulting to the latest ledger version.
+    fn version_or_timestamp_param(
+        &self,
+        version: Option<u64>,
+        timestamp_usecs: Option<u64>,
+    ) -> Result<u64, JsonRpcError> {
+        match (version, timestamp_usecs) {
+            (Some(_), Some(_)) => Err(JsonRpcError::invalid_param(
+                "version and timestamp_usecs can't be both provided",
+            )),
+            (_, Some(timestamp_usecs)) => self.version_by_timestamp(timestamp_usecs),
+            (version, None) => self.version_param(version, "version"),
+        }
+    }
+
+    fn version_by_timestamp(&self, timestamp_usecs: u64) -> Result<u64, JsonRpcError> {
+        let latest_ledger_timestamp = self.ledger_info.ledger_info().timestamp_usecs();
+
+        // Blocks with a timestamp before `timestamp_usecs` may still be committed otherwise
+        if timestamp_usecs > latest_ledger_timestamp {
+            return Err(JsonRpcError::invalid_param(&format!(
+                "timestamp_usecs should be <= known latest ledger timestamp {}",
+                latest_ledger_timestamp,
+            )));
+        }
+
+        data::get_version_by_timestamp(self.service.db.borrow(), self.version(), timestamp_usecs)
+    }
+
     pub async fn handle(&self, method_request: MethodRequest) -> Result<Value, JsonRpcError> {
         let response: Value = match method_request {
             MethodRequest::Submit(params) => self.submit(params).await?.into(),
@@ -169,6 +200,9 @@ impl<'a> Handler<'a> {
             MethodRequest::GetNetworkStatus(params) => {
                 serde_json::to_value(self.get_network_status(params).await?)?
             }
+            MethodRequest::GetVersionByTimestamp(params) => {
+                serde_json::to_value(self.get_version_by_timestamp(params).await?)?
+            }
             MethodRequest::GetResources(params) => {
                 serde_json::to_value(self.get_resources(params).await?)?
             }
@@ -193,6 +227,7 @@ impl<'a> Handler<'a> {
             MethodRequest::GetEventByVersionWithProof(params) => {
                 serde_json::to_value(self.get_event_by_version_with_proof(params).await?)?
             }
+            MethodRequest::Simulate(params) => serde_json::to_value(self.simulate(params).await?)?,
         };
         Ok(response)
     }
@@ -209,6 +244,14 @@ impl<'a> Handler<'a> {
         }
     }
 
+    /// Executes a transaction against the latest state, without committing it
+    async fn simulate(&self, params: SimulateParams) -> Result<SimulationView, JsonRpcError> {
+        let db = self.service.db.clone();
+        tokio::task::spawn_blocking(move || data::simulate(db, params.data))
+            .await
+            .map_err(|e| JsonRpcError::internal_error(e.to_string()))?
+    }
+
     /// Returns the blockchain metadata for a specified version. If no version is specified, default to
     /// returning the current blockchain metadata
     /// Can be used to verify that target Full Node is up-to-date
@@ -224,7 +267,7 @@ impl<'a> Handler<'a> {
         params: GetAccountParams,
     ) -> Result<Option<AccountView>, JsonRpcError> {
         let account_address = params.account;
-        let version = self.version_param(params.version, "version")?;
+        let version = self.version_or_timestamp_param(params.version, params.timestamp_usecs)?;
         data::get_account(self.service.db.borrow(), account_address, version)
     }
 
@@ -392,12 +435,20 @@ impl<'a> Handler<'a> {
         data::get_network_status(self.service.role.as_str())
     }
 
+    /// Returns the version of the last transaction committed before a given timestamp
+    async fn get_version_by_timestamp(
+        &self,
+        params: GetVersionByTimestampParams,
+    ) -> Result<u64, JsonRpcError> {
+        self.version_by_timestamp(params.timestamp_usecs)
+    }
+
     /// Returns all resources in the account specified by `params`
     async fn get_resources(
         &self,
         params: GetResourcesParams,
     ) -> Result<BTreeMap<String, AnnotatedMoveStruct>, JsonRpcError> {
-        let version = self.version_param(params.version, "version")?;
+        let version = self.version_or_timestamp_param(params.version, params.timestamp_usecs)?;
         data::get_resources(
             self.service.db.borrow(),
             self.version(),
@@ -16,7 +16,7 @@ use anyhow::{ensure, Result};
 use diem_config::config::{NodeConfig, RoleType, StreamConfig};
 use diem_json_rpc_types::Method;
 use diem_logger::{debug, Schema};
-use diem_mempool::MempoolClientSender;
+use diem_mempool::{MempoolClientSender, TxnStatusCache};
 use diem_types::{chain_id::ChainId, ledger_info::LedgerInfoWithSignatures};
 use futures::future::{join_all, Either};
 use rand::{rngs::OsRng, RngCore};
@@ -107,6 +107,7 @@ pub fn bootstrap(
     tls_key_path: &Option<String>,
     diem_db: Arc<dyn MoveDbReader>,
     mp_sender: MempoolClientSender,
+    txn_status_cache: TxnStatusCache,
     role: RoleType,
     chain_id: ChainId,
     stream_config: &StreamConfig,
@@ -192,6 +193,7 @@ pub fn bootstrap(
                 stream_config,
                 content_len_limit as u64,
                 diem_db,
+                txn_status_cache,
             ));
 
     let server = match tls_cert_path {
@@ -214,6 +216,7 @@ pub fn bootstrap_from_config(
     chain_id: ChainId,
     diem_db: Arc<dyn MoveDbReader>,
     mp_sender: MempoolClientSender,
+    txn_status_cache: TxnStatusCache,
 ) -> Runtime {
     bootstrap(
         config.json_rpc.address,
@@ -224,6 +227,7 @@ pub fn bootstrap_from_config(
         &config.json_rpc.tls_key_path,
         diem_db,
         mp_sender,
+        txn_status_cache,
         config.base.role,
         chain_id,
         &config.json_rpc.stream_rpc,
@@ -21,6 +21,7 @@ use diem_json_rpc_types::{
     Id,
 };
 use diem_logger::debug;
+use diem_mempool::TxnStatusCache;
 use std::{collections::HashMap, str::FromStr, sync::Arc};
 use storage_interface::MoveDbReader;
 
@@ -149,7 +150,12 @@ impl ClientConnection {
         }
     }
 
-    pub async fn received_message(&self, db: Arc<dyn MoveDbReader>, message: String) {
+    pub async fn received_message(
+        &self,
+        db: Arc<dyn MoveDbReader>,
+        txn_status_cache: TxnStatusCache,
+        message: String,
+    ) {
         match StreamJsonRpcRequest::from_str(&message) {
             Ok(mut request) => {
                 debug!(
@@ -167,7 +173,7 @@ impl ClientConnection {
                     },
                     "subscription request"
                 );
-                if let Err(err) = self.handle_rpc_request(db, &mut request) {
+                if let Err(err) = self.handle_rpc_request(db, txn_status_cache, &mut request) {
                     self.send_error(Some(request.method_request.method()), Some(request.id), err)
                         .await
                         .ok();
@@ -217,6 +223,7 @@ impl ClientConnection {
     fn handle_rpc_request(
         &self,
         db: Arc<dyn MoveDbReader>,
+        txn_status_cache: TxnStatusCache,
         request: &mut StreamJsonRpcRequest,
     ) -> Result<(), JsonRpcError> {
         // No task needs to spawn for an unsubscribe
@@ -240,7 +247,8 @@ impl ClientConnection {
         }
 
         match CallableStreamMethod(request.method_request).call_method(
-            db.clone(),
+            db,
+            txn_status_cache,
             self.clone(),
             request.id.clone(),
         ) {
@@ -285,7 +293,9 @@ fn metric_subscription_rpc_received(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stream_rpc::tests::util::{create_client_connection, timeout};
+    use crate::stream_rpc::tests::util::{
+        create_client_connection, create_txn_status_cache, timeout,
+    };
 
     #[tokio::test]
     async fn test_send_raw() {
@@ -313,7 +323,11 @@ mod tests {
         })
         .to_string();
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(
+                Arc::new(mock_db.clone()),
+                create_txn_status_cache(),
+                request,
+            )
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
@@ -361,7 +375,11 @@ mod tests {
         })
         .to_string();
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(
+                Arc::new(mock_db.clone()),
+                create_txn_status_cache(),
+                request,
+            )
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
@@ -388,7 +406,11 @@ mod tests {
         .to_string();
 
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(
+                Arc::new(mock_db.clone()),
+                create_txn_status_cache(),
+                request,
+            )
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
@@ -413,7 +435,11 @@ mod tests {
 
         let request = "{\"bad_json".to_string();
         client_connection
-            .received_message(Arc::new(mock_db.clone()), request)
+            .received_message(
+                Arc::new(mock_db.clone()),
+                create_txn_status_cache(),
+                request,
+            )
             .await;
 
         let result = timeout(50, receiver.recv(), "message 1")
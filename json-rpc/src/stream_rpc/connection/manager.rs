@@ -8,6 +8,7 @@ use crate::stream_rpc::{
 };
 use diem_infallible::RwLock;
 use diem_logger::debug;
+use diem_mempool::TxnStatusCache;
 use futures::StreamExt;
 use std::{
     collections::HashMap,
@@ -23,16 +24,22 @@ use storage_interface::MoveDbReader;
 pub struct ConnectionManager {
     pub clients: Arc<RwLock<HashMap<u64, ClientConnection>>>,
     pub diem_db: Arc<dyn MoveDbReader>,
+    pub txn_status_cache: TxnStatusCache,
     pub config: Arc<SubscriptionConfig>,
     /// Our unique user id counter.
     next_user_id: Arc<AtomicU64>,
 }
 
 impl ConnectionManager {
-    pub fn new(diem_db: Arc<dyn MoveDbReader>, config: Arc<SubscriptionConfig>) -> Self {
+    pub fn new(
+        diem_db: Arc<dyn MoveDbReader>,
+        txn_status_cache: TxnStatusCache,
+        config: Arc<SubscriptionConfig>,
+    ) -> Self {
         Self {
             clients: Arc::new(RwLock::new(HashMap::new())),
             diem_db,
+            txn_status_cache,
             config,
             next_user_id: Arc::new(AtomicU64::new(0)),
         }
@@ -119,12 +126,19 @@ impl ConnectionManager {
         // TODO: reap idle connections without any subscriptions or which haven't accepted a message in a while?
         let task_client = client.clone();
         let task_db = self.get_db();
+        let task_txn_status_cache = self.txn_status_cache.clone();
         let recv_task = tokio::task::spawn(async move {
             while let Some(result) = client_rcv.next().await {
                 match result {
                     Ok(msg) => {
                         if let Some(message) = msg {
-                            task_client.received_message(task_db.clone(), message).await;
+                            task_client
+                                .received_message(
+                                    task_db.clone(),
+                                    task_txn_status_cache.clone(),
+                                    message,
+                                )
+                                .await;
                         }
                     }
                     Err(e) => {
@@ -9,9 +9,13 @@ use tokio::task::JoinHandle;
 use crate::stream_rpc::{
     connection::ClientConnection,
     subscription_types::{Subscription, SubscriptionHelper},
-    subscriptions::{EventsSubscription, TransactionsSubscription},
+    subscriptions::{
+        AccountSubscription, BlocksSubscription, EventsSubscription, TransactionStatusSubscription,
+        TransactionsSubscription,
+    },
 };
 use diem_json_rpc_types::{stream::request::StreamMethodRequest, Id};
+use diem_mempool::TxnStatusCache;
 
 pub struct CallableStreamMethod(pub StreamMethodRequest);
 
@@ -19,11 +23,12 @@ impl CallableStreamMethod {
     pub fn call_method(
         self,
         db: Arc<dyn MoveDbReader>,
+        txn_status_cache: TxnStatusCache,
         client: ClientConnection,
         jsonrpc_id: Id,
     ) -> Result<JoinHandle<()>, JsonRpcError> {
         let method = self.0.method();
-        let helper = SubscriptionHelper::new(db, client, jsonrpc_id, method);
+        let helper = SubscriptionHelper::new(db, txn_status_cache, client, jsonrpc_id, method);
         match self.0 {
             StreamMethodRequest::SubscribeToTransactions(params) => {
                 TransactionsSubscription::default().run(helper, params)
@@ -31,6 +36,15 @@ impl CallableStreamMethod {
             StreamMethodRequest::SubscribeToEvents(params) => {
                 EventsSubscription::default().run(helper, params)
             }
+            StreamMethodRequest::SubscribeToAccount(params) => {
+                AccountSubscription::default().run(helper, params)
+            }
+            StreamMethodRequest::SubscribeToBlocks(params) => {
+                BlocksSubscription::default().run(helper, params)
+            }
+            StreamMethodRequest::SubscribeToTransactionStatus(params) => {
+                TransactionStatusSubscription::default().run(helper, params)
+            }
             // This is handled in the `handle_rpc_request` function, as we don't spawn a task
             StreamMethodRequest::Unsubscribe => unreachable!(),
         }
@@ -3,6 +3,7 @@
 
 use crate::stream_rpc::transport::websocket::get_websocket_routes;
 use diem_config::config::StreamConfig;
+use diem_mempool::TxnStatusCache;
 use std::sync::Arc;
 use storage_interface::MoveDbReader;
 use warp::{filters::BoxedFilter, Filter, Reply};
@@ -14,8 +15,16 @@ pub fn get_stream_routes(
     config: &StreamConfig,
     content_length_limit: u64,
     diem_db: Arc<dyn MoveDbReader>,
+    txn_status_cache: TxnStatusCache,
 ) -> BoxedFilter<(impl Reply,)> {
-    let wss_routes = get_websocket_routes(config, content_length_limit, diem_db.clone(), None).0;
+    let wss_routes = get_websocket_routes(
+        config,
+        content_length_limit,
+        diem_db.clone(),
+        txn_status_cache,
+        None,
+    )
+    .0;
 
     // If streaming rpc isn't enabled, return a 404
     // We do this here because we can't build routes conditionally as if/else types won't match
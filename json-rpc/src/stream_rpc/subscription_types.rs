@@ -41,6 +41,7 @@ use diem_json_rpc_types::{
     Id,
 };
 use diem_logger::debug;
+use diem_mempool::TxnStatusCache;
 use serde::Serialize;
 use std::{iter::Map, sync::Arc, time::Duration};
 use storage_interface::MoveDbReader;
@@ -73,6 +74,7 @@ pub fn create_backoff(poll_interval_ms: u64, max_poll_interval_ms: u64) -> Jitte
 #[derive(Clone)]
 pub struct SubscriptionHelper {
     pub db: Arc<dyn MoveDbReader>,
+    pub txn_status_cache: TxnStatusCache,
     pub client: ClientConnection,
     pub jsonrpc_id: Id,
     pub method: StreamMethod,
@@ -82,6 +84,7 @@ pub struct SubscriptionHelper {
 impl SubscriptionHelper {
     pub fn new(
         db: Arc<dyn MoveDbReader>,
+        txn_status_cache: TxnStatusCache,
         client: ClientConnection,
         jsonrpc_id: Id,
         method: StreamMethod,
@@ -90,6 +93,7 @@ impl SubscriptionHelper {
         let max_poll_interval_ms = client.config.max_poll_interval_ms;
         Self {
             db,
+            txn_status_cache,
             client,
             jsonrpc_id,
             method,
@@ -213,7 +217,7 @@ mod tests {
     use crate::{
         stream_rpc::{
             errors::StreamError,
-            tests::util::{create_client_connection, timeout},
+            tests::util::{create_client_connection, create_txn_status_cache, timeout},
         },
         tests::utils::MockDiemDB,
     };
@@ -279,6 +283,7 @@ mod tests {
         // The 'method' does not matter for this test (it's used for logging)
         let subscription_helper = SubscriptionHelper::new(
             Arc::new(mock_db.clone()),
+            create_txn_status_cache(),
             client_connection.clone(),
             Id::Number(1010101),
             StreamMethod::SubscribeToTransactions,
@@ -2,15 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    data::{get_events, get_transactions},
+    data::{get_account, get_account_transaction, get_blocks, get_events, get_transactions},
     errors::JsonRpcError,
     stream_rpc::subscription_types::{Subscription, SubscriptionHelper},
-    views::{EventView, TransactionView},
+    views::{
+        AccountView, BlockView, EventView, TransactionStateView, TransactionStatusView,
+        TransactionView,
+    },
 };
 use diem_json_rpc_types::stream::request::{
-    SubscribeToEventsParams, SubscribeToTransactionsParams,
+    SubscribeToAccountParams, SubscribeToBlocksParams, SubscribeToEventsParams,
+    SubscribeToTransactionStatusParams, SubscribeToTransactionsParams,
 };
 use diem_logger::warn;
+use diem_mempool::TxnStatus;
 use std::borrow::Borrow;
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -99,3 +104,213 @@ impl Subscription<SubscribeToEventsParams, EventView> for EventsSubscription {
         }
     }
 }
+
+#[derive(Clone, Debug, Default)]
+pub struct AccountSubscription {
+    pub(crate) latest_account: Option<AccountView>,
+}
+
+impl AccountSubscription {
+    /// `AccountView.version` moves with the ledger, so it is ignored when deciding whether the
+    /// account state itself has changed since it was last sent
+    fn has_changed(&self, account: &AccountView) -> bool {
+        match &self.latest_account {
+            None => true,
+            Some(latest) => {
+                *latest
+                    != AccountView {
+                        version: latest.version,
+                        ..account.clone()
+                    }
+            }
+        }
+    }
+}
+
+impl Subscription<SubscribeToAccountParams, AccountView> for AccountSubscription {
+    fn init(
+        &mut self,
+        _helper: &SubscriptionHelper,
+        _params: &SubscribeToAccountParams,
+    ) -> Result<(), JsonRpcError> {
+        Ok(())
+    }
+
+    fn next(
+        &self,
+        helper: &SubscriptionHelper,
+        params: &SubscribeToAccountParams,
+    ) -> Vec<AccountView> {
+        let latest_version = helper.db.get_latest_version().unwrap_or(0);
+        if let Some(latest) = &self.latest_account {
+            if latest.version >= Some(latest_version) {
+                return vec![];
+            }
+        }
+        match get_account(helper.db.borrow(), params.account_address, latest_version) {
+            Ok(Some(account)) if self.has_changed(&account) => vec![account],
+            Ok(_) => vec![],
+            Err(e) => {
+                warn!("Client#{} Could not fetch account: {}", helper.client.id, e);
+                vec![]
+            }
+        }
+    }
+
+    fn on_send(&mut self, account: Option<&AccountView>) {
+        if let Some(account) = account {
+            self.latest_account = Some(account.clone());
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlocksSubscription {
+    pub(crate) next_version: u64,
+}
+
+impl Subscription<SubscribeToBlocksParams, BlockView> for BlocksSubscription {
+    fn init(
+        &mut self,
+        _helper: &SubscriptionHelper,
+        params: &SubscribeToBlocksParams,
+    ) -> Result<(), JsonRpcError> {
+        self.next_version = params.starting_version;
+        Ok(())
+    }
+
+    fn next(
+        &self,
+        helper: &SubscriptionHelper,
+        _params: &SubscribeToBlocksParams,
+    ) -> Vec<BlockView> {
+        match get_blocks(
+            helper.db.borrow(),
+            helper.db.get_latest_version().unwrap_or(0),
+            self.next_version,
+            helper.client.config.fetch_size,
+        ) {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                warn!("Client#{} Could not fetch blocks: {}", helper.client.id, e);
+                vec![]
+            }
+        }
+    }
+
+    fn on_send(&mut self, block: Option<&BlockView>) {
+        if let Some(block) = block {
+            self.next_version = block.last_version + 1;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TransactionStatusSubscription {
+    pub(crate) latest_status: Option<TransactionStatusView>,
+}
+
+impl TransactionStatusSubscription {
+    /// The on-chain state of a transaction takes precedence over what mempool last saw of it
+    fn get_status(
+        helper: &SubscriptionHelper,
+        params: &SubscribeToTransactionStatusParams,
+    ) -> Result<Option<TransactionStatusView>, JsonRpcError> {
+        let (sender, sequence_number) = match (params.sender, params.sequence_number, params.hash) {
+            (Some(sender), Some(sequence_number), _) => (sender, sequence_number),
+            (_, _, Some(hash)) => match helper.txn_status_cache.get_txn_pointer(&hash) {
+                Some(txn_pointer) => txn_pointer,
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+
+        let ledger_version = helper.db.get_latest_version().unwrap_or(0);
+        if let Some(txn) = get_account_transaction(
+            helper.db.borrow(),
+            sender,
+            sequence_number,
+            true,
+            ledger_version,
+        )? {
+            let state = match params.hash {
+                Some(hash) if hash != txn.hash => TransactionStateView::Replaced,
+                _ => TransactionStateView::Committed,
+            };
+            return Ok(Some(TransactionStatusView {
+                sender,
+                sequence_number,
+                hash: params.hash.unwrap_or(txn.hash),
+                state,
+                transaction: Some(txn),
+            }));
+        }
+
+        let (hash, status) = match helper.txn_status_cache.get(&sender, sequence_number) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let state = match (params.hash, status) {
+            (Some(expected_hash), _) if expected_hash != hash => TransactionStateView::Replaced,
+            (_, TxnStatus::Accepted) => TransactionStateView::Accepted,
+            (_, TxnStatus::Evicted) => TransactionStateView::Evicted,
+            (_, TxnStatus::Expired) => TransactionStateView::Expired,
+            (_, TxnStatus::Rejected) => TransactionStateView::Rejected,
+            // Wait for storage to serve the committed transaction
+            (_, TxnStatus::Committed) => return Ok(None),
+        };
+        Ok(Some(TransactionStatusView {
+            sender,
+            sequence_number,
+            hash: params.hash.unwrap_or(hash),
+            state,
+            transaction: None,
+        }))
+    }
+}
+
+impl Subscription<SubscribeToTransactionStatusParams, TransactionStatusView>
+    for TransactionStatusSubscription
+{
+    fn init(
+        &mut self,
+        _helper: &SubscriptionHelper,
+        params: &SubscribeToTransactionStatusParams,
+    ) -> Result<(), JsonRpcError> {
+        match (params.sender, params.sequence_number, params.hash) {
+            (Some(_), Some(_), None) | (None, None, Some(_)) => Ok(()),
+            _ => Err(JsonRpcError::invalid_param(
+                "either sender and sequence_number, or hash must be provided",
+            )),
+        }
+    }
+
+    fn next(
+        &self,
+        helper: &SubscriptionHelper,
+        params: &SubscribeToTransactionStatusParams,
+    ) -> Vec<TransactionStatusView> {
+        if let Some(latest_status) = &self.latest_status {
+            if latest_status.is_final() {
+                return vec![];
+            }
+        }
+        match Self::get_status(helper, params) {
+            Ok(Some(status)) if self.latest_status.as_ref() != Some(&status) => vec![status],
+            Ok(_) => vec![],
+            Err(e) => {
+                warn!(
+                    "Client#{} Could not fetch transaction status: {}",
+                    helper.client.id, e
+                );
+                vec![]
+            }
+        }
+    }
+
+    fn on_send(&mut self, status: Option<&TransactionStatusView>) {
+        if let Some(status) = status {
+            self.latest_status = Some(status.clone());
+        }
+    }
+}
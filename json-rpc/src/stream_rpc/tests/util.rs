@@ -6,7 +6,8 @@ use std::{future::Future, sync::Arc};
 // use proptest::prelude::*;
 use warp::{test::WsClient, ws::Message};
 
-use diem_config::config::StreamConfig;
+use diem_config::config::{MempoolConfig, StreamConfig};
+use diem_mempool::TxnStatusCache;
 
 use crate::{
     stream_rpc::{
@@ -41,7 +42,8 @@ pub async fn connect_to_ws(
     config: &StreamConfig,
     cm: Option<ConnectionManager>,
 ) -> (WsClient, ConnectionManager) {
-    let (routes, cm) = get_websocket_routes(config, 1024 * 10, db.clone(), cm);
+    let (routes, cm) =
+        get_websocket_routes(config, 1024 * 10, db.clone(), create_txn_status_cache(), cm);
     let ws_client = warp::test::ws()
         .path("/v1/stream/ws")
         .header("user-agent", "diem-client-sdk-python / 0.1.22")
@@ -140,6 +142,10 @@ pub async fn timeout<F: Future<Output = T>, T>(duration_millis: u64, future: F,
         .unwrap_or_else(|_| panic!("{}: Timed out", name))
 }
 
+pub fn create_txn_status_cache() -> TxnStatusCache {
+    TxnStatusCache::new(&MempoolConfig::default())
+}
+
 pub fn create_client_connection() -> (
     MockDiemDB,
     ClientConnection,
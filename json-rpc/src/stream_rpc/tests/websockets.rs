@@ -2,21 +2,37 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    stream_rpc::tests::util::{
-        close_ws, connect_to_ws, get_latest_client, next_message, num_clients, num_tasks, timeout,
-        verify_ok, ws_test_setup,
+    stream_rpc::{
+        tests::util::{
+            close_ws, connect_to_ws, create_txn_status_cache, get_latest_client, next_message,
+            num_clients, num_tasks, timeout, verify_ok, ws_test_setup,
+        },
+        transport::websocket::get_websocket_routes,
     },
     tests::utils::create_db_and_runtime,
 };
-use diem_json_rpc_types::stream::response::StreamJsonRpcResponse;
+use diem_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, Uniform};
+use diem_json_rpc_types::{
+    stream::{
+        request::StreamMethod,
+        response::{StreamJsonRpcResponse, StreamJsonRpcResponseView},
+    },
+    views::{BlockView, TransactionStateView, TransactionStatusView},
+};
+use diem_mempool::TxnStatus;
+use diem_types::{
+    account_address::AccountAddress, block_metadata::BlockMetadata,
+    test_helpers::transaction_test_helpers::get_test_signed_txn, transaction::Transaction,
+};
 use futures::{SinkExt, StreamExt};
 use reqwest::Url;
 use serde_json::json;
-use std::convert::TryFrom;
+use std::{convert::TryFrom, sync::Arc};
 use tokio_tungstenite::{
     connect_async,
     tungstenite::{http, Message},
 };
+use warp::test::WsClient;
 
 #[tokio::test]
 async fn test_websocket_call_and_response() {
@@ -33,6 +49,11 @@ async fn test_websocket_call_and_response() {
             json!({"id": "client-generated-id", "method": "subscribe_to_events", "params": {"event_key": 1337}, "jsonrpc": "2.0"}).to_string(),
             json!({"jsonrpc":"2.0","id":"client-generated-id","error":{"code":-32602,"message":"Invalid params for method 'subscribe_to_events'","data":null}}).to_string(),
         ),
+        (
+            "subscribe_to_transaction_status: missing sequence number",
+            json!({"id": "client-generated-id", "method": "subscribe_to_transaction_status", "params": {"sender": AccountAddress::random()}, "jsonrpc": "2.0"}).to_string(),
+            json!({"jsonrpc":"2.0","id":"client-generated-id","error":{"code":-32602,"message":"Invalid param either sender and sequence_number, or hash must be provided","data":null}}).to_string(),
+        ),
     ];
 
     for (name, request, expected) in calls {
@@ -49,7 +70,12 @@ async fn test_websocket_call_and_response() {
 async fn test_invalid_params() {
     let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
 
-    let endpoint_names = vec!["subscribe_to_transactions", "subscribe_to_events"];
+    let endpoint_names = vec![
+        "subscribe_to_transactions",
+        "subscribe_to_events",
+        "subscribe_to_account",
+        "subscribe_to_blocks",
+    ];
 
     for endpoint_name in endpoint_names {
         let name = format!("{}: invalid param", &endpoint_name);
@@ -120,6 +146,186 @@ async fn test_websocket_fetching_data() {
     }
 }
 
+#[tokio::test]
+async fn test_websocket_account_subscription() {
+    let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
+    let name = "subscribe_to_account: get account data";
+
+    let address = *mock_db.all_accounts.keys().next().unwrap();
+    let request = json!({"id": "client-generated-id", "method": "subscribe_to_account", "params": {"account_address": address}, "jsonrpc": "2.0"});
+
+    let (mut ws_client, cm) = connect_to_ws(mock_db.clone(), &config, None).await;
+    ws_client.send_text(request.to_string()).await;
+    verify_ok(next_message(&mut ws_client, name).await, name);
+
+    let msg = next_message(&mut ws_client, name).await;
+    let resp: StreamJsonRpcResponse =
+        serde_json::from_str(msg.to_str().expect("response")).unwrap();
+    assert!(resp.error.is_none());
+    assert_eq!(
+        resp.result.unwrap().get("address").unwrap(),
+        &json!(address)
+    );
+
+    // The account does not change, so it should not be sent again
+    let client = get_latest_client(&cm);
+    assert_eq!(num_tasks(&client), 1);
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_millis(500), ws_client.recv())
+            .await
+            .is_err(),
+        "{}: unchanged account was sent again",
+        name
+    );
+
+    close_ws(ws_client, name).await;
+    assert_eq!(num_clients(&cm), 0);
+}
+
+#[tokio::test]
+async fn test_websocket_block_subscription() {
+    let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
+    let name = "subscribe_to_blocks: get block data";
+
+    let user_txn = mock_db
+        .all_txns
+        .iter()
+        .find(|(txn, _)| !matches!(txn, Transaction::BlockMetadata(_)))
+        .unwrap()
+        .clone();
+    let block = |round| {
+        let metadata = BlockMetadata::new(
+            HashValue::random(),
+            round,
+            round,
+            vec![],
+            AccountAddress::ZERO,
+        );
+        (Transaction::BlockMetadata(metadata), user_txn.1.clone())
+    };
+
+    // [user txn] [block 1: 1..=3] [block 2: 4..=6] [block 3: 7..=7]
+    let mut mock_db = (*mock_db).clone();
+    mock_db.all_txns = vec![
+        user_txn.clone(),
+        block(1),
+        user_txn.clone(),
+        user_txn.clone(),
+        block(2),
+        user_txn.clone(),
+        user_txn.clone(),
+        block(3),
+    ];
+    mock_db.version = 7;
+    mock_db.timestamps = vec![0; 8];
+    let mock_db = Arc::new(mock_db);
+
+    let request = json!({"id": "client-generated-id", "method": "subscribe_to_blocks", "params": {"starting_version": 0}, "jsonrpc": "2.0"});
+    let (mut ws_client, cm) = connect_to_ws(mock_db.clone(), &config, None).await;
+    ws_client.send_text(request.to_string()).await;
+    verify_ok(next_message(&mut ws_client, name).await, name);
+
+    for (round, first_version, last_version) in vec![(1, 1, 3), (2, 4, 6), (3, 7, 7)] {
+        let msg = next_message(&mut ws_client, &format!("{} round {}", name, round)).await;
+        let resp: StreamJsonRpcResponse =
+            serde_json::from_str(msg.to_str().expect("response")).unwrap();
+        let block: BlockView = match resp
+            .parse_result(&StreamMethod::SubscribeToBlocks)
+            .unwrap()
+            .unwrap()
+        {
+            StreamJsonRpcResponseView::Block(block) => block,
+            other => panic!("{}: unexpected response: {:?}", name, other),
+        };
+        assert_eq!(block.round, round);
+        assert_eq!(block.first_version, first_version);
+        assert_eq!(block.last_version, last_version);
+    }
+
+    close_ws(ws_client, name).await;
+    assert_eq!(num_clients(&cm), 0);
+}
+
+async fn next_transaction_status(ws_client: &mut WsClient, name: &str) -> TransactionStatusView {
+    let msg = timeout(2_000, ws_client.recv(), name)
+        .await
+        .unwrap_or_else(|e| panic!("{}: message not ok. {:?}", name, e));
+    let resp: StreamJsonRpcResponse =
+        serde_json::from_str(msg.to_str().expect("response")).unwrap();
+    match resp
+        .parse_result(&StreamMethod::SubscribeToTransactionStatus)
+        .unwrap()
+        .unwrap()
+    {
+        StreamJsonRpcResponseView::TransactionStatus(status) => status,
+        other => panic!("{}: unexpected response: {:?}", name, other),
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_transaction_status_subscription() {
+    let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
+    let txn_status_cache = create_txn_status_cache();
+    let (_, cm) = get_websocket_routes(
+        &config,
+        1024 * 10,
+        mock_db.clone(),
+        txn_status_cache.clone(),
+        None,
+    );
+
+    // A transaction which is only known to mempool
+    let name = "subscribe_to_transaction_status: pending transaction";
+    let privkey = Ed25519PrivateKey::generate_for_testing();
+    let txn = get_test_signed_txn(
+        AccountAddress::random(),
+        0,
+        &privkey,
+        privkey.public_key(),
+        None,
+    );
+    let hash = Transaction::UserTransaction(txn.clone()).hash();
+    txn_status_cache.update(&txn, TxnStatus::Accepted);
+
+    let (mut ws_client, cm) = connect_to_ws(mock_db.clone(), &config, Some(cm)).await;
+    let request = json!({"id": "client-generated-id", "method": "subscribe_to_transaction_status", "params": {"hash": hash}, "jsonrpc": "2.0"});
+    ws_client.send_text(request.to_string()).await;
+    verify_ok(next_message(&mut ws_client, name).await, name);
+
+    let status = next_transaction_status(&mut ws_client, name).await;
+    assert_eq!(status.sender, txn.sender());
+    assert_eq!(status.hash, hash);
+    assert_eq!(status.state, TransactionStateView::Accepted);
+
+    txn_status_cache.update(&txn, TxnStatus::Expired);
+    let status = next_transaction_status(&mut ws_client, name).await;
+    assert_eq!(status.state, TransactionStateView::Expired);
+    assert!(status.transaction.is_none());
+    close_ws(ws_client, name).await;
+
+    // A transaction which is already committed
+    let name = "subscribe_to_transaction_status: committed transaction";
+    let committed_txn = mock_db
+        .all_txns
+        .iter()
+        .find_map(|(txn, _)| txn.as_signed_user_txn().ok().cloned())
+        .unwrap();
+    let (mut ws_client, _) = connect_to_ws(mock_db.clone(), &config, Some(cm.clone())).await;
+    let request = json!({"id": "client-generated-id", "method": "subscribe_to_transaction_status", "params": {"sender": committed_txn.sender(), "sequence_number": committed_txn.sequence_number()}, "jsonrpc": "2.0"});
+    ws_client.send_text(request.to_string()).await;
+    verify_ok(next_message(&mut ws_client, name).await, name);
+
+    let status = next_transaction_status(&mut ws_client, name).await;
+    assert_eq!(status.state, TransactionStateView::Committed);
+    assert_eq!(
+        status.hash,
+        Transaction::UserTransaction(committed_txn).hash()
+    );
+    assert_eq!(status.hash, status.transaction.unwrap().hash);
+    close_ws(ws_client, name).await;
+    assert_eq!(num_clients(&cm), 0);
+}
+
 #[tokio::test]
 async fn test_multiple_subscriptions_and_response() {
     let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
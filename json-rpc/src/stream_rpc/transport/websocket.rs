@@ -10,6 +10,7 @@ use warp::{filters::BoxedFilter, ws::Message, Filter, Rejection, Reply};
 
 use diem_config::config::StreamConfig;
 use diem_logger::debug;
+use diem_mempool::TxnStatusCache;
 use storage_interface::MoveDbReader;
 
 use crate::stream_rpc::{
@@ -25,6 +26,7 @@ pub fn get_websocket_routes(
     config: &StreamConfig,
     content_length_limit: u64,
     diem_db: Arc<dyn MoveDbReader>,
+    txn_status_cache: TxnStatusCache,
     connection_manager: Option<ConnectionManager>,
 ) -> (BoxedFilter<(impl Reply,)>, ConnectionManager) {
     let sub_config = Arc::new(SubscriptionConfig {
@@ -35,7 +37,7 @@ pub fn get_websocket_routes(
     });
 
     let connection_manager = match connection_manager {
-        None => ConnectionManager::new(diem_db.clone(), sub_config),
+        None => ConnectionManager::new(diem_db.clone(), txn_status_cache, sub_config),
         Some(cm) => cm,
     };
 
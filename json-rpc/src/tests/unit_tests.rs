@@ -302,6 +302,38 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "diem_ledger_version": version
             }),
         ),
+        (
+            "get_account: both version and timestamp",
+            json!({"jsonrpc": "2.0", "method": "get_account", "params": ["e1b3d22871989e9fd9dc6814b2f4fc41", 1, 1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid param version and timestamp_usecs can't be both provided",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
+        (
+            "get_version_by_timestamp: timestamp is too large",
+            json!({"jsonrpc": "2.0", "method": "get_version_by_timestamp", "params": [timestamp+1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": format!("Invalid param timestamp_usecs should be <= known latest ledger timestamp {}", timestamp),
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
         (
             "submit invalid data",
             json!({"jsonrpc": "2.0", "method": "submit", "params": ["helloworld"], "id": 1}),
@@ -318,6 +350,38 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "diem_ledger_version": version
             }),
         ),
+        (
+            "simulate invalid data",
+            json!({"jsonrpc": "2.0", "method": "simulate", "params": ["helloworld"], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid params for method 'simulate'",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
+        (
+            "simulate invalid public key",
+            json!({"jsonrpc": "2.0", "method": "simulate", "params": ["00", "helloworld"], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid params for method 'simulate'",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
         (
             "get_transactions: invalid start_version param",
             json!({"jsonrpc": "2.0", "method": "get_transactions", "params": ["helloworld", 1, true], "id": 1}),
@@ -1178,6 +1242,36 @@ fn test_get_account() {
     }
 }
 
+#[test]
+fn test_get_version_by_timestamp() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let (_, timestamp) = mock_db.get_latest_commit_metadata().unwrap();
+    let expected_version = mock_db
+        .timestamps
+        .iter()
+        .position(|t| *t >= timestamp)
+        .unwrap() as u64
+        - 1;
+
+    let version = client
+        .get_version_by_timestamp(timestamp)
+        .unwrap()
+        .into_inner();
+    assert_eq!(version, expected_version);
+
+    let (account, _) = mock_db.all_accounts.iter().next().unwrap();
+    let account_by_timestamp = client
+        .get_account_by_timestamp(*account, timestamp)
+        .unwrap()
+        .into_inner();
+    let account_by_version = client
+        .get_account_by_version(*account, expected_version)
+        .unwrap()
+        .into_inner();
+    assert_eq!(account_by_timestamp, account_by_version);
+}
+
 #[test]
 fn test_get_metadata_latest() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -4,15 +4,15 @@
 use anyhow::{format_err, Error, Result};
 use diem_config::{
     config::{
-        RoleType, StreamConfig, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT,
-        DEFAULT_PAGE_SIZE_LIMIT, DEFAULT_STREAM_RPC_MAX_POLL_INTERVAL_MS,
-        DEFAULT_STREAM_RPC_POLL_INTERVAL_MS, DEFAULT_STREAM_RPC_SEND_QUEUE_SIZE,
-        DEFAULT_STREAM_RPC_SUBSCRIPTION_FETCH_SIZE,
+        MempoolConfig, RoleType, StreamConfig, DEFAULT_BATCH_SIZE_LIMIT,
+        DEFAULT_CONTENT_LENGTH_LIMIT, DEFAULT_PAGE_SIZE_LIMIT,
+        DEFAULT_STREAM_RPC_MAX_POLL_INTERVAL_MS, DEFAULT_STREAM_RPC_POLL_INTERVAL_MS,
+        DEFAULT_STREAM_RPC_SEND_QUEUE_SIZE, DEFAULT_STREAM_RPC_SUBSCRIPTION_FETCH_SIZE,
     },
     utils,
 };
 use diem_crypto::HashValue;
-use diem_mempool::{MempoolClientSender, SubmissionStatus};
+use diem_mempool::{MempoolClientSender, SubmissionStatus, TxnStatusCache};
 use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
@@ -85,6 +85,7 @@ pub fn test_bootstrap(
         &None,
         diem_db,
         mp_sender,
+        TxnStatusCache::new(&MempoolConfig::default()),
         RoleType::Validator,
         ChainId::test(),
         &stream_config,
@@ -390,6 +391,22 @@ impl DbReader for MockDiemDB {
     fn get_accumulator_root_hash(&self, _version: Version) -> Result<HashValue> {
         Ok(HashValue::zero())
     }
+
+    fn get_last_version_before_timestamp(
+        &self,
+        timestamp: u64,
+        ledger_version: Version,
+    ) -> Result<Version> {
+        let version = self
+            .timestamps
+            .iter()
+            .take(ledger_version as usize + 1)
+            .position(|t| *t >= timestamp)
+            .ok_or_else(|| format_err!("No block found beyond timestamp {}", timestamp))?;
+        (version as u64)
+            .checked_sub(1)
+            .ok_or_else(|| format_err!("First block started at or after timestamp {}", timestamp))
+    }
 }
 
 impl ModuleResolver for MockDiemDB {
@@ -428,7 +445,11 @@ impl ResourceResolver for MockDiemDB {
     }
 }
 
-impl MoveDbReader for MockDiemDB {}
+impl MoveDbReader for MockDiemDB {
+    fn into_db_reader(self: Arc<Self>) -> Arc<dyn DbReader> {
+        self
+    }
+}
 
 // returns MockDiemDB for unit-testing
 #[allow(unused)]
@@ -47,6 +47,7 @@ pub enum Method {
     GetEvents,
     GetCurrencies,
     GetNetworkStatus,
+    GetVersionByTimestamp,
 
     //
     // Experimental APIs
@@ -59,6 +60,7 @@ pub enum Method {
     GetAccountTransactionsWithProofs,
     GetEventsWithProofs,
     GetEventByVersionWithProof,
+    Simulate,
 }
 
 impl Method {
@@ -73,6 +75,7 @@ impl Method {
             Method::GetEvents => "get_events",
             Method::GetCurrencies => "get_currencies",
             Method::GetNetworkStatus => "get_network_status",
+            Method::GetVersionByTimestamp => "get_version_by_timestamp",
             Method::GetResources => "get_resources",
             Method::GetStateProof => "get_state_proof",
             Method::GetAccumulatorConsistencyProof => "get_accumulator_consistency_proof",
@@ -81,6 +84,7 @@ impl Method {
             Method::GetAccountTransactionsWithProofs => "get_account_transactions_with_proofs",
             Method::GetEventsWithProofs => "get_events_with_proofs",
             Method::GetEventByVersionWithProof => "get_event_by_version_with_proof",
+            Method::Simulate => "simulate",
         }
     }
 }
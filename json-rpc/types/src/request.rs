@@ -3,11 +3,14 @@
 
 use super::{Id, JsonRpcVersion, Method};
 use crate::{errors::JsonRpcError, views::BytesView};
+use diem_crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
 use diem_types::{
-    account_address::AccountAddress, event::EventKey, transaction::SignedTransaction,
+    account_address::AccountAddress,
+    event::EventKey,
+    transaction::{RawTransaction, SignedTransaction},
 };
 use serde::{de, Deserialize, Serialize};
-use std::fmt;
+use std::{convert::TryFrom, fmt};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct JsonRpcRequest {
@@ -73,6 +76,7 @@ pub enum MethodRequest {
     GetEvents(GetEventsParams),
     GetCurrencies(GetCurrenciesParams),
     GetNetworkStatus(GetNetworkStatusParams),
+    GetVersionByTimestamp(GetVersionByTimestampParams),
 
     //
     // Experimental APIs
@@ -85,6 +89,7 @@ pub enum MethodRequest {
     GetAccountTransactionsWithProofs(GetAccountTransactionsWithProofsParams),
     GetEventsWithProofs(GetEventsWithProofsParams),
     GetEventByVersionWithProof(GetEventByVersionWithProof),
+    Simulate(SimulateParams),
 }
 
 impl MethodRequest {
@@ -107,6 +112,9 @@ impl MethodRequest {
             Method::GetNetworkStatus => {
                 MethodRequest::GetNetworkStatus(serde_json::from_value(value)?)
             }
+            Method::GetVersionByTimestamp => {
+                MethodRequest::GetVersionByTimestamp(serde_json::from_value(value)?)
+            }
             Method::GetResources => MethodRequest::GetResources(serde_json::from_value(value)?),
             Method::GetStateProof => MethodRequest::GetStateProof(serde_json::from_value(value)?),
             Method::GetAccumulatorConsistencyProof => {
@@ -127,6 +135,7 @@ impl MethodRequest {
             Method::GetEventByVersionWithProof => {
                 MethodRequest::GetEventByVersionWithProof(serde_json::from_value(value)?)
             }
+            Method::Simulate => MethodRequest::Simulate(serde_json::from_value(value)?),
         };
 
         Ok(method_request)
@@ -143,6 +152,7 @@ impl MethodRequest {
             MethodRequest::GetEvents(_) => Method::GetEvents,
             MethodRequest::GetCurrencies(_) => Method::GetCurrencies,
             MethodRequest::GetNetworkStatus(_) => Method::GetNetworkStatus,
+            MethodRequest::GetVersionByTimestamp(_) => Method::GetVersionByTimestamp,
             MethodRequest::GetResources(_) => Method::GetResources,
             MethodRequest::GetStateProof(_) => Method::GetStateProof,
             MethodRequest::GetAccumulatorConsistencyProof(_) => {
@@ -155,6 +165,7 @@ impl MethodRequest {
             }
             MethodRequest::GetEventsWithProofs(_) => Method::GetEventsWithProofs,
             MethodRequest::GetEventByVersionWithProof(_) => Method::GetEventByVersionWithProof,
+            MethodRequest::Simulate(_) => Method::Simulate,
         }
     }
 }
@@ -188,6 +199,45 @@ where
         .map_err(|_| D::Error::custom("expected hex-encoded SignedTransaction"))
 }
 
+/// Params of `simulate`: either a signed transaction, or an unsigned `RawTransaction` along with
+/// the public key of its sender.
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulateParams {
+    #[serde(serialize_with = "serialize_signed_transaction")]
+    pub data: SignedTransaction,
+}
+
+impl<'de> Deserialize<'de> for SimulateParams {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        struct Params {
+            data: BytesView,
+            #[serde(default)]
+            public_key: Option<Ed25519PublicKey>,
+        }
+
+        let params = Params::deserialize(deserializer)?;
+        let data = match params.public_key {
+            Some(public_key) => {
+                let raw_txn: RawTransaction = bcs::from_bytes(params.data.inner())
+                    .map_err(|_| D::Error::custom("expected hex-encoded RawTransaction"))?;
+                // the signature is never verified when simulating a transaction
+                let signature = Ed25519Signature::try_from(&[0u8; Ed25519Signature::LENGTH][..])
+                    .map_err(D::Error::custom)?;
+                SignedTransaction::new(raw_txn, public_key, signature)
+            }
+            None => bcs::from_bytes(params.data.inner())
+                .map_err(|_| D::Error::custom("expected hex-encoded SignedTransaction"))?,
+        };
+        Ok(SimulateParams { data })
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct GetMetadataParams {
     #[serde(default)]
@@ -220,6 +270,8 @@ pub struct GetAccountParams {
     pub account: AccountAddress,
     #[serde(default)]
     pub version: Option<u64>,
+    #[serde(default)]
+    pub timestamp_usecs: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -279,6 +331,11 @@ impl<'de> Deserialize<'de> for GetNetworkStatusParams {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetVersionByTimestampParams {
+    pub timestamp_usecs: u64,
+}
+
 /// A de::Visitor implementation for jsonrpc param structs without any parameters
 struct NoParamsVisitor(&'static str);
 impl<'de> de::Visitor<'de> for NoParamsVisitor {
@@ -329,6 +386,8 @@ pub struct GetResourcesParams {
     pub account: AccountAddress,
     #[serde(default)]
     pub version: Option<u64>,
+    #[serde(default)]
+    pub timestamp_usecs: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -438,8 +497,13 @@ mod test {
         // Array with wrong optional param
         parse_err(json!([account, "foo"]));
 
+        // Array with timestamp instead of version
+        let params = parse_ok(json!([account, null, 1_000_000]));
+        assert_eq!(params.version, None);
+        assert_eq!(params.timestamp_usecs, Some(1_000_000));
+
         // Array with too many params
-        parse_err(json!([account, 10, 1]));
+        parse_err(json!([account, 10, 1, 1]));
 
         // Empty array without required params should fail
         parse_err(json!([]));
@@ -458,6 +522,14 @@ mod test {
             "version": 10,
         }));
 
+        // Object with timestamp
+        let params = parse_ok(json!({
+            "account": account,
+            "timestamp_usecs": 1_000_000,
+        }));
+        assert_eq!(params.version, None);
+        assert_eq!(params.timestamp_usecs, Some(1_000_000));
+
         // Object with more params
         parse_ok(json!({
             "account": account,
@@ -746,6 +818,34 @@ mod test {
         serde_json::from_value::<JsonRpcRequest>(request).unwrap();
     }
 
+    #[test]
+    fn get_version_by_timestamp() {
+        let parse_ok =
+            |value| serde_json::from_value::<GetVersionByTimestampParams>(value).unwrap();
+        let parse_err =
+            |value| serde_json::from_value::<GetVersionByTimestampParams>(value).unwrap_err();
+
+        // Array with all params
+        parse_ok(json!([1_000_000]));
+
+        // Array with too many params
+        parse_err(json!([1_000_000, 10]));
+
+        // Array with wrong param
+        parse_err(json!(["foo"]));
+
+        // Empty array without required params should fail
+        parse_err(json!([]));
+
+        // Object with all params
+        parse_ok(json!({
+            "timestamp_usecs": 1_000_000,
+        }));
+
+        // Object without required params should fail
+        parse_err(json!({}));
+    }
+
     #[test]
     fn get_state_proof() {
         let parse_ok = |value| serde_json::from_value::<GetStateProofParams>(value).unwrap();
@@ -997,4 +1097,52 @@ mod test {
         // Object with more params
         parse_ok(json!({ "key": key, "version": 10, "foo": 99 }));
     }
+
+    #[test]
+    fn simulate() {
+        use diem_crypto::ed25519::Ed25519PrivateKey;
+        use diem_types::{chain_id::ChainId, transaction::Script};
+
+        let parse = serde_json::from_value::<SimulateParams>;
+        let parse_ok = |value| parse(value).unwrap();
+        let parse_err = |value| parse(value).unwrap_err();
+
+        let private_key = Ed25519PrivateKey::try_from(&[1u8; 32][..]).unwrap();
+        let public_key = Ed25519PublicKey::from(&private_key);
+        let raw_txn = RawTransaction::new_script(
+            AccountAddress::ZERO,
+            0,
+            Script::new(vec![], vec![], vec![]),
+            1_000_000,
+            0,
+            "XUS".to_owned(),
+            0,
+            ChainId::test(),
+        );
+        let signed_txn = raw_txn
+            .clone()
+            .sign(&private_key, public_key.clone())
+            .unwrap()
+            .into_inner();
+        let raw_txn_hex = hex::encode(bcs::to_bytes(&raw_txn).unwrap());
+        let signed_txn_hex = hex::encode(bcs::to_bytes(&signed_txn).unwrap());
+        let public_key_hex = hex::encode(public_key.to_bytes());
+
+        // Signed transaction
+        assert_eq!(parse_ok(json!([signed_txn_hex])).data, signed_txn);
+        assert_eq!(parse_ok(json!({ "data": signed_txn_hex })).data, signed_txn);
+
+        // Unsigned transaction with public key
+        let unsigned_txn = parse_ok(json!([raw_txn_hex, public_key_hex])).data;
+        assert_eq!(unsigned_txn.into_raw_transaction(), raw_txn);
+        parse_ok(json!({ "data": raw_txn_hex, "public_key": public_key_hex }));
+
+        // Incorrect arguments
+        parse_err(json!([raw_txn_hex]));
+        parse_err(json!([signed_txn_hex, public_key_hex]));
+        parse_err(json!([raw_txn_hex, "foo"]));
+        parse_err(json!(["foo"]));
+        parse_err(json!([]));
+        parse_err(json!({}));
+    }
 }
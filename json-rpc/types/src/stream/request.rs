@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{errors::JsonRpcError, request::RawJsonRpcRequest, Id, JsonRpcVersion};
-use diem_types::event::EventKey;
+use diem_crypto::HashValue;
+use diem_types::{account_address::AccountAddress, event::EventKey};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
@@ -74,6 +75,9 @@ impl FromStr for StreamJsonRpcRequest {
 pub enum StreamMethodRequest {
     SubscribeToTransactions(SubscribeToTransactionsParams),
     SubscribeToEvents(SubscribeToEventsParams),
+    SubscribeToAccount(SubscribeToAccountParams),
+    SubscribeToBlocks(SubscribeToBlocksParams),
+    SubscribeToTransactionStatus(SubscribeToTransactionStatusParams),
     Unsubscribe,
 }
 
@@ -93,6 +97,15 @@ impl StreamMethodRequest {
             StreamMethod::SubscribeToEvents => {
                 StreamMethodRequest::SubscribeToEvents(serde_json::from_value(value)?)
             }
+            StreamMethod::SubscribeToAccount => {
+                StreamMethodRequest::SubscribeToAccount(serde_json::from_value(value)?)
+            }
+            StreamMethod::SubscribeToBlocks => {
+                StreamMethodRequest::SubscribeToBlocks(serde_json::from_value(value)?)
+            }
+            StreamMethod::SubscribeToTransactionStatus => {
+                StreamMethodRequest::SubscribeToTransactionStatus(serde_json::from_value(value)?)
+            }
             StreamMethod::Unsubscribe => StreamMethodRequest::Unsubscribe,
         };
 
@@ -105,6 +118,11 @@ impl StreamMethodRequest {
                 StreamMethod::SubscribeToTransactions
             }
             StreamMethodRequest::SubscribeToEvents(_) => StreamMethod::SubscribeToEvents,
+            StreamMethodRequest::SubscribeToAccount(_) => StreamMethod::SubscribeToAccount,
+            StreamMethodRequest::SubscribeToBlocks(_) => StreamMethod::SubscribeToBlocks,
+            StreamMethodRequest::SubscribeToTransactionStatus(_) => {
+                StreamMethod::SubscribeToTransactionStatus
+            }
             StreamMethodRequest::Unsubscribe => StreamMethod::Unsubscribe,
         }
     }
@@ -115,6 +133,9 @@ impl StreamMethodRequest {
 pub enum StreamMethod {
     SubscribeToTransactions,
     SubscribeToEvents,
+    SubscribeToAccount,
+    SubscribeToBlocks,
+    SubscribeToTransactionStatus,
     Unsubscribe,
 }
 
@@ -123,6 +144,9 @@ impl StreamMethod {
         match self {
             StreamMethod::SubscribeToTransactions => "subscribe_to_transactions",
             StreamMethod::SubscribeToEvents => "subscribe_to_events",
+            StreamMethod::SubscribeToAccount => "subscribe_to_account",
+            StreamMethod::SubscribeToBlocks => "subscribe_to_blocks",
+            StreamMethod::SubscribeToTransactionStatus => "subscribe_to_transaction_status",
             StreamMethod::Unsubscribe => "unsubscribe",
         }
     }
@@ -139,3 +163,21 @@ pub struct SubscribeToTransactionsParams {
     pub starting_version: u64,
     pub include_events: Option<bool>,
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct SubscribeToAccountParams {
+    pub account_address: AccountAddress,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct SubscribeToBlocksParams {
+    pub starting_version: u64,
+}
+
+/// A transaction is identified either by its `sender` and `sequence_number`, or by its `hash`
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct SubscribeToTransactionStatusParams {
+    pub sender: Option<AccountAddress>,
+    pub sequence_number: Option<u64>,
+    pub hash: Option<HashValue>,
+}
@@ -4,7 +4,7 @@
 use crate::{
     errors::JsonRpcError,
     stream::request::StreamMethod,
-    views::{EventView, TransactionView},
+    views::{AccountView, BlockView, EventView, TransactionStatusView, TransactionView},
     Id, JsonRpcVersion,
 };
 use serde::{Deserialize, Serialize};
@@ -15,6 +15,9 @@ use std::str::FromStr;
 pub enum StreamJsonRpcResponseView {
     Transaction(TransactionView),
     Event(EventView),
+    Account(AccountView),
+    Block(BlockView),
+    TransactionStatus(TransactionStatusView),
     SubscribeResult(SubscribeResult),
     UnsubscribeResult(UnsubscribeResult),
 }
@@ -37,6 +40,11 @@ impl StreamJsonRpcResponseView {
                 Self::Transaction(serde_json::from_value(value)?)
             }
             StreamMethod::SubscribeToEvents => Self::Event(serde_json::from_value(value)?),
+            StreamMethod::SubscribeToAccount => Self::Account(serde_json::from_value(value)?),
+            StreamMethod::SubscribeToBlocks => Self::Block(serde_json::from_value(value)?),
+            StreamMethod::SubscribeToTransactionStatus => {
+                Self::TransactionStatus(serde_json::from_value(value)?)
+            }
             StreamMethod::Unsubscribe => Self::UnsubscribeResult(serde_json::from_value(value)?),
         })
     }
@@ -136,6 +144,7 @@ mod tests {
     use super::*;
     use crate::views::{BytesView, TransactionDataView, VMStatusView};
     use diem_crypto::HashValue;
+    use diem_types::account_address::AccountAddress;
 
     fn response_view_helper(method: &StreamMethod, input: String) -> StreamJsonRpcResponseView {
         let response: StreamJsonRpcResponse =
@@ -209,4 +218,37 @@ mod tests {
         });
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_block_result_parsing() {
+        let input = serde_json::json!({
+          "jsonrpc": "2.0",
+          "id": "my-id",
+          "result": {
+            "id": "496176cd664651d81673832598c2dcdc47e9d2f900121a464351610bfa6d29fa",
+            "round": 7,
+            "timestamp_usecs": 1624389817286906_u64,
+            "proposer": "000000000000000000000000000000dd",
+            "previous_block_votes": [],
+            "first_version": 124,
+            "last_version": 130
+          }
+        })
+        .to_string();
+        let result = response_view_helper(&StreamMethod::SubscribeToBlocks, input);
+
+        let expected = StreamJsonRpcResponseView::Block(BlockView {
+            id: HashValue::from_hex(
+                "496176cd664651d81673832598c2dcdc47e9d2f900121a464351610bfa6d29fa",
+            )
+            .expect("Could not parse HashValue hex"),
+            round: 7,
+            timestamp_usecs: 1624389817286906,
+            proposer: AccountAddress::from_hex_literal("0xdd").unwrap(),
+            previous_block_votes: vec![],
+            first_version: 124,
+            last_version: 130,
+        });
+        assert_eq!(result, expected);
+    }
 }
@@ -14,6 +14,7 @@ use diem_types::{
     },
     account_state::AccountState,
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    block_metadata::BlockMetadata,
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     diem_id_identifier::DiemIdVaspDomainIdentifier,
     event::EventKey,
@@ -23,10 +24,12 @@ use diem_types::{
     },
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, Script, ScriptFunction, Transaction, TransactionArgument,
-        TransactionInfo, TransactionListWithProof, TransactionPayload,
+        AccountTransactionsWithProof, Script, ScriptFunction, SignedTransaction, Transaction,
+        TransactionArgument, TransactionInfo, TransactionListWithProof, TransactionOutput,
+        TransactionPayload,
     },
     vm_status::KeptVMStatus,
+    write_set::WriteOp,
 };
 use hex::FromHex;
 use move_core_types::{
@@ -615,6 +618,35 @@ impl MetadataView {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BlockView {
+    pub id: HashValue,
+    pub round: u64,
+    pub timestamp_usecs: u64,
+    pub proposer: AccountAddress,
+    pub previous_block_votes: Vec<AccountAddress>,
+    // the version of the block metadata transaction, which opens the block
+    pub first_version: u64,
+    // the version of the last transaction committed as part of the block
+    pub last_version: u64,
+}
+
+impl BlockView {
+    pub fn new(block_metadata: BlockMetadata, first_version: u64, last_version: u64) -> Self {
+        let id = block_metadata.id();
+        let (round, timestamp_usecs, previous_block_votes, proposer) = block_metadata.into_inner();
+        Self {
+            id,
+            round,
+            timestamp_usecs,
+            proposer,
+            previous_block_votes,
+            first_version,
+            last_version,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct BytesView(Box<[u8]>);
 
@@ -850,6 +882,103 @@ impl TransactionView {
     }
 }
 
+/// Result of simulating a transaction against the latest state, without committing it
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SimulationView {
+    /// The transaction as if it was committed at the next version
+    pub transaction: TransactionView,
+    /// Changes the transaction would make to the state
+    pub write_set: Vec<WriteSetChangeView>,
+}
+
+impl SimulationView {
+    pub fn try_from_txn_and_output(
+        version: u64,
+        txn: SignedTransaction,
+        status: &KeptVMStatus,
+        output: &TransactionOutput,
+    ) -> Result<Self> {
+        let tx = Transaction::UserTransaction(txn);
+        let events = output
+            .events()
+            .iter()
+            .cloned()
+            .map(|event| EventView::try_from((version, event)))
+            .collect::<Result<Vec<_>>>()?;
+        let write_set = output
+            .write_set()
+            .iter()
+            .map(|(access_path, write_op)| WriteSetChangeView {
+                address: access_path.address,
+                path: BytesView::new(access_path.path.clone()),
+                value: match write_op {
+                    WriteOp::Value(value) => Some(BytesView::new(value.clone())),
+                    WriteOp::Deletion => None,
+                },
+            })
+            .collect();
+
+        Ok(SimulationView {
+            transaction: TransactionView {
+                version,
+                hash: tx.hash(),
+                bytes: BytesView::new(bcs::to_bytes(&tx)?),
+                transaction: TransactionDataView::from(tx),
+                events,
+                vm_status: VMStatusView::from(status),
+                gas_used: output.gas_used(),
+            },
+            write_set,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct WriteSetChangeView {
+    pub address: AccountAddress,
+    /// BCS-encoded path of the changed resource or module under `address`
+    pub path: BytesView,
+    /// BCS-encoded new value, or null if the resource or module is deleted
+    pub value: Option<BytesView>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStateView {
+    /// Accepted into mempool
+    Accepted,
+    /// Evicted from a full mempool
+    Evicted,
+    /// Expired before it could be committed
+    Expired,
+    /// Rejected by consensus
+    Rejected,
+    /// Another transaction with the same sender and sequence number took its place
+    Replaced,
+    /// Committed on chain
+    Committed,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TransactionStatusView {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub hash: HashValue,
+    pub state: TransactionStateView,
+    // the transaction committed on chain for `sender` and `sequence_number`, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionView>,
+}
+
+impl TransactionStatusView {
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self.state,
+            TransactionStateView::Replaced | TransactionStateView::Committed
+        )
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct TransactionListView(pub Vec<TransactionView>);
 
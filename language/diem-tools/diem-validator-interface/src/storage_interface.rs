@@ -23,6 +23,7 @@ impl DBDebuggerInterface {
             db_root_path,
             true,
             None,
+            None,
             RocksdbConfig::default(),
         )?)))
     }
@@ -7,11 +7,17 @@ use std::{
     collections::{btree_map::BTreeMap, HashMap},
     fmt::Debug,
     hash::Hash,
+    ops::Deref,
 };
 
+mod speculative;
 #[cfg(test)]
 mod unit_tests;
 
+pub use speculative::{
+    Incarnation, ReadDescriptor, SpeculativeMVHashMap, SpeculativeMVHashMapView,
+};
+
 /// A structure that holds placeholders for each write to the database
 //
 //  The structure is created by one thread creating the scheduling, and
@@ -226,17 +232,34 @@ where
     }
 }
 
+/// The view of the versioned data that a transaction executing at `version()` observes.
+pub trait VersionedView<K, V> {
+    /// The handle through which a read value is accessed.
+    type Value: Deref<Target = V>;
+
+    /// Get the value of `key` written by the closest lower version.
+    /// Returns Ok(val) if such key is already assigned by previous transactions.
+    /// Returns Err(None) if no previous transactions write to `key`.
+    /// Returns Err(Some(version)) if such key is dependent on the `version`-th transaction.
+    fn read(&self, key: &K) -> Result<Self::Value, Option<Version>>;
+
+    /// The version of the transaction this view belongs to.
+    fn version(&self) -> Version;
+}
+
 pub struct MVHashMapView<'a, K, V> {
     map: &'a MVHashMap<K, V>,
     version: Version,
 }
 
-impl<'a, K: Hash + Clone + Eq, V> MVHashMapView<'a, K, V> {
-    pub fn read(&self, key: &K) -> Result<&V, Option<Version>> {
+impl<'a, K: Hash + Clone + Eq, V> VersionedView<K, V> for MVHashMapView<'a, K, V> {
+    type Value = &'a V;
+
+    fn read(&self, key: &K) -> Result<&'a V, Option<Version>> {
         self.map.read(key, self.version)
     }
 
-    pub fn version(&self) -> Version {
+    fn version(&self) -> Version {
         self.version
     }
 }
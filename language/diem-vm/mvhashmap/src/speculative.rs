@@ -0,0 +1,151 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Version, VersionedView};
+use std::{
+    cell::RefCell,
+    collections::{btree_map::BTreeMap, HashMap},
+    hash::Hash,
+    sync::{Arc, RwLock},
+};
+
+/// How many times a transaction has been (re-)executed before, 0 for the first execution.
+pub type Incarnation = usize;
+
+/// Identifies what a read observed: the version and incarnation of the transaction whose write was
+/// read, or `None` if no lower version wrote the key and the read went to storage.
+pub type ReadDescriptor = Option<(Version, Incarnation)>;
+
+struct Entry<V> {
+    incarnation: Incarnation,
+    value: Arc<V>,
+}
+
+/// A multi-version structure for speculative execution, where the write set of a transaction is
+/// not known before executing it, and a transaction can be executed multiple times.
+//
+//  Unlike `MVHashMap`, entries are added, overwritten and removed while transactions are being
+//  executed. Every entry carries the incarnation of the transaction that wrote it, so that a read
+//  can be validated later by checking it would still observe the same write.
+//
+pub struct SpeculativeMVHashMap<K, V> {
+    data: RwLock<HashMap<K, RwLock<BTreeMap<Version, Entry<V>>>>>,
+}
+
+impl<K: Hash + Clone + Eq, V> SpeculativeMVHashMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            data: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Write `value` to `key` at `version`, overwriting the write of a previous incarnation.
+    pub fn write(&self, key: &K, version: Version, incarnation: Incarnation, value: V) {
+        let entry = Entry {
+            incarnation,
+            value: Arc::new(value),
+        };
+
+        // Fast path: the key is already in the map, only the per key lock needs to be taken for
+        // write.
+        {
+            let data = self.data.read().unwrap();
+            if let Some(tree) = data.get(key) {
+                tree.write().unwrap().insert(version, entry);
+                return;
+            }
+        }
+
+        self.data
+            .write()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| RwLock::new(BTreeMap::new()))
+            .write()
+            .unwrap()
+            .insert(version, entry);
+    }
+
+    /// Remove the write to `key` at `version`, if any. Used when a new incarnation of a transaction
+    /// no longer writes to a key written by a previous incarnation.
+    pub fn delete(&self, key: &K, version: Version) {
+        if let Some(tree) = self.data.read().unwrap().get(key) {
+            tree.write().unwrap().remove(&version);
+        }
+    }
+
+    /// Get the value of `key` written by the closest version lower than `version`, together with
+    /// where it comes from.
+    /// Returns None if no lower version writes to `key`.
+    pub fn read(&self, key: &K, version: Version) -> Option<(Version, Incarnation, Arc<V>)> {
+        let data = self.data.read().unwrap();
+        let tree = data.get(key)?.read().unwrap();
+        tree.range(0..version)
+            .next_back()
+            .map(|(entry_version, entry)| {
+                (*entry_version, entry.incarnation, Arc::clone(&entry.value))
+            })
+    }
+
+    /// Check if a read of `key` at `version` still observes what `descriptor` recorded.
+    pub fn validate_read(&self, key: &K, version: Version, descriptor: &ReadDescriptor) -> bool {
+        let current = self
+            .read(key, version)
+            .map(|(entry_version, incarnation, _)| (entry_version, incarnation));
+        current == *descriptor
+    }
+
+    /// Get the number of keys in the SpeculativeMVHashMap.
+    pub fn len(&self) -> usize {
+        self.data.read().unwrap().len()
+    }
+
+    pub fn view(&self, version: Version) -> SpeculativeMVHashMapView<K, V> {
+        SpeculativeMVHashMapView {
+            map: self,
+            version,
+            captured_reads: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl<K: Hash + Clone + Eq, V> Default for SpeculativeMVHashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A view of the `SpeculativeMVHashMap` that records every read for later validation.
+pub struct SpeculativeMVHashMapView<'a, K, V> {
+    map: &'a SpeculativeMVHashMap<K, V>,
+    version: Version,
+    captured_reads: RefCell<Vec<(K, ReadDescriptor)>>,
+}
+
+impl<'a, K: Hash + Clone + Eq, V> SpeculativeMVHashMapView<'a, K, V> {
+    /// Consume the view and return the reads performed through it.
+    pub fn take_reads(self) -> Vec<(K, ReadDescriptor)> {
+        self.captured_reads.into_inner()
+    }
+}
+
+impl<'a, K: Hash + Clone + Eq, V> VersionedView<K, V> for SpeculativeMVHashMapView<'a, K, V> {
+    type Value = Arc<V>;
+
+    /// Never returns Err(Some(_)): a read is not blocked by lower transactions but observes
+    /// whatever they have written so far.
+    fn read(&self, key: &K) -> Result<Arc<V>, Option<Version>> {
+        let (descriptor, result) = match self.map.read(key, self.version) {
+            Some((version, incarnation, value)) => (Some((version, incarnation)), Ok(value)),
+            None => (None, Err(None)),
+        };
+        self.captured_reads
+            .borrow_mut()
+            .push((key.clone(), descriptor));
+        result
+    }
+
+    fn version(&self) -> Version {
+        self.version
+    }
+}
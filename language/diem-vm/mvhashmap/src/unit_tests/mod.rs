@@ -56,3 +56,49 @@ fn create_write_read_placeholder_struct() {
     let r1 = mvtbl.read(&ap2, 25);
     assert_eq!(Ok(&Some(vec![0, 0, 0])), r1);
 }
+
+#[test]
+fn speculative_write_read_validate() {
+    let ap1 = b"/foo/b".to_vec();
+    let ap2 = b"/foo/c".to_vec();
+
+    let mvtbl = SpeculativeMVHashMap::new();
+
+    // Nothing written yet, reads go to storage.
+    assert!(mvtbl.read(&ap1, 10).is_none());
+    let view = mvtbl.view(10);
+    assert_eq!(Err(None), view.read(&ap1));
+
+    mvtbl.write(&ap1, 5, 0, vec![0, 0, 0]);
+    mvtbl.write(&ap2, 10, 0, vec![1, 1, 1]);
+    assert_eq!(2, mvtbl.len());
+
+    // Reads at a version return the previous versions, not this version.
+    assert!(mvtbl.read(&ap1, 5).is_none());
+    assert!(mvtbl.read(&ap2, 10).is_none());
+    let (version, incarnation, value) = mvtbl.read(&ap1, 10).unwrap();
+    assert_eq!(
+        (5, 0, vec![0, 0, 0]),
+        (version, incarnation, (*value).clone())
+    );
+
+    // The read captured before the write no longer validates.
+    let reads = view.take_reads();
+    assert_eq!(vec![(ap1.clone(), None)], reads);
+    assert!(!mvtbl.validate_read(&ap1, 10, &reads[0].1));
+
+    // A read of the current write validates until the writer is re-executed.
+    let view = mvtbl.view(10);
+    assert_eq!(vec![0, 0, 0], *view.read(&ap1).unwrap());
+    let reads = view.take_reads();
+    assert_eq!(vec![(ap1.clone(), Some((5, 0)))], reads);
+    assert!(mvtbl.validate_read(&ap1, 10, &reads[0].1));
+
+    mvtbl.write(&ap1, 5, 1, vec![0, 0, 0]);
+    assert!(!mvtbl.validate_read(&ap1, 10, &reads[0].1));
+
+    // Deleting the write makes reads fall through to storage again.
+    mvtbl.delete(&ap1, 5);
+    assert!(mvtbl.read(&ap1, 10).is_none());
+    assert!(mvtbl.validate_read(&ap1, 10, &None));
+}
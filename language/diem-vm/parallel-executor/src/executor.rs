@@ -114,7 +114,7 @@ where
 
                         // Process the output of a transaction
                         let commit_result =
-                            match task.execute_transaction(versioned_data_cache.view(idx), txn) {
+                            match task.execute_transaction(&versioned_data_cache.view(idx), txn) {
                                 ExecutionStatus::Success(output) => {
                                     // Commit the side effects to the versioned_data_cache.
                                     if output.get_writes().into_iter().all(|(k, v)| {
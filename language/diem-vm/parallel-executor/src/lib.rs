@@ -6,6 +6,7 @@ pub mod executor;
 mod outcome_array;
 pub mod proptest_types;
 mod scheduler;
+pub mod speculative_executor;
 pub mod task;
 #[cfg(test)]
 mod unit_tests;
@@ -6,16 +6,27 @@ use crate::{
     proptest_types::types::{
         ExpectedOutput, ImpreciseInferencer, Inferencer, Task, Transaction, TransactionGen,
     },
+    speculative_executor::SpeculativeTransactionExecutor,
 };
 use proptest::{collection::vec, prelude::*, sample::Index, strategy::Strategy};
 use std::{fmt::Debug, hash::Hash};
 
+#[derive(Clone, Copy)]
+enum ExecutorMode {
+    /// Parallel execution with precise read/write set estimation.
+    Precise,
+    /// Parallel execution with read set estimation missing some reads.
+    ImpreciseRead,
+    /// Speculative parallel execution without read/write set estimation.
+    Speculative,
+}
+
 fn run_transactions<K, V>(
     key_universe: Vec<K>,
     transaction_gens: Vec<TransactionGen<V>>,
     abort_transactions: Vec<Index>,
     skip_rest_transactions: Vec<Index>,
-    imprecise_read: bool,
+    mode: ExecutorMode,
 ) -> bool
 where
     K: Hash + Clone + Debug + Eq + Send + Sync + PartialOrd + Ord + 'static,
@@ -38,16 +49,23 @@ where
 
     let baseline = ExpectedOutput::generate_baseline(&transactions);
 
-    let output = if imprecise_read {
-        ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, ImpreciseInferencer<K, V>>::new(
-            ImpreciseInferencer::new(),
-        )
+    let output = match mode {
+        ExecutorMode::Precise => {
+            ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, Inferencer<K, V>>::new(
+                Inferencer::new(),
+            )
             .execute_transactions_parallel((), transactions)
-    } else {
-        ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, Inferencer<K, V>>::new(
-            Inferencer::new(),
-        )
-        .execute_transactions_parallel((), transactions)
+        }
+        ExecutorMode::ImpreciseRead => ParallelTransactionExecutor::<
+            Transaction<K, V>,
+            Task<K, V>,
+            ImpreciseInferencer<K, V>,
+        >::new(ImpreciseInferencer::new())
+        .execute_transactions_parallel((), transactions),
+        ExecutorMode::Speculative => {
+            SpeculativeTransactionExecutor::<Transaction<K, V>, Task<K, V>>::new()
+                .execute_transactions_parallel((), transactions)
+        }
     };
 
     baseline.check_output(&output)
@@ -62,7 +80,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 0),
         skip_rest_transactions in vec(any::<Index>(), 0),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Precise));
     }
 
     #[test]
@@ -72,7 +90,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 0),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Precise));
     }
 
     #[test]
@@ -82,7 +100,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 0),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Precise));
     }
 
 
@@ -93,7 +111,7 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, false));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Precise));
     }
 
     #[test]
@@ -103,6 +121,36 @@ proptest! {
         abort_transactions in vec(any::<Index>(), 5),
         skip_rest_transactions in vec(any::<Index>(), 5),
     ) {
-        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, true));
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::ImpreciseRead));
+    }
+
+    #[test]
+    fn speculative_no_early_termination(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 5000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 0),
+        skip_rest_transactions in vec(any::<Index>(), 0),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Speculative));
+    }
+
+    #[test]
+    fn speculative_mixed_transactions(
+        universe in vec(any::<[u8; 32]>(), 100),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 5000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 5),
+        skip_rest_transactions in vec(any::<Index>(), 5),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Speculative));
+    }
+
+    #[test]
+    fn speculative_high_contention(
+        universe in vec(any::<[u8; 32]>(), 10),
+        transaction_gen in vec(any::<TransactionGen<[u8;32]>>(), 1000).no_shrink(),
+        abort_transactions in vec(any::<Index>(), 1),
+        skip_rest_transactions in vec(any::<Index>(), 1),
+    ) {
+        prop_assert!(run_transactions(universe, transaction_gen, abort_transactions, skip_rest_transactions, ExecutorMode::Speculative));
     }
 }
@@ -9,7 +9,7 @@ use crate::{
     },
 };
 use anyhow::Result as AResult;
-use mvhashmap::MVHashMapView;
+use mvhashmap::VersionedView;
 use proptest::{
     arbitrary::Arbitrary, collection::vec, prelude::*, proptest, sample::Index, strategy::Strategy,
 };
@@ -240,7 +240,7 @@ where
 
     fn execute_transaction(
         &self,
-        view: MVHashMapView<K, V>,
+        view: &impl VersionedView<K, V>,
         txn: &Self::T,
     ) -> ExecutionStatus<Self::Output, Self::Error> {
         match txn {
@@ -253,7 +253,7 @@ where
                 let mut reads_result = vec![];
                 for k in reads.iter() {
                     reads_result.push(match view.read(k) {
-                        Ok(v) => Some(v.clone()),
+                        Ok(v) => Some((*v).clone()),
                         Err(None) => None,
                         Err(Some(v)) => return ExecutionStatus::Retry(v),
                     })
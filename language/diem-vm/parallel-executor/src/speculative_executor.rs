@@ -0,0 +1,232 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    errors::*,
+    outcome_array::OutcomeArray,
+    task::{ExecutionStatus, ExecutorTask, Transaction, TransactionOutput},
+};
+use mvhashmap::{Incarnation, ReadDescriptor, SpeculativeMVHashMap, Version};
+use rayon::prelude::*;
+use std::{cmp::max, collections::HashSet, marker::PhantomData, sync::Mutex};
+
+/// The result of the latest execution of a transaction, together with the reads it performed and
+/// the keys it wrote to.
+struct ExecutionRecord<K, O, E> {
+    incarnation: Incarnation,
+    reads: Vec<(K, ReadDescriptor)>,
+    writes: Vec<K>,
+    status: ExecutionStatus<O, E>,
+}
+
+type ExecutionRecordCell<T, E> = Mutex<
+    Option<
+        ExecutionRecord<
+            <T as Transaction>::Key,
+            <E as ExecutorTask>::Output,
+            <E as ExecutorTask>::Error,
+        >,
+    >,
+>;
+
+/// Executes a block of transactions in parallel without knowing their read/write sets up front.
+///
+/// Transactions are executed optimistically against a `SpeculativeMVHashMap` and the reads each
+/// execution performs are recorded. Transactions are then committed in order: a transaction is only
+/// committed if all its reads would still observe the same writes, otherwise it's re-executed.
+/// The outputs are therefore identical to executing the block sequentially.
+pub struct SpeculativeTransactionExecutor<T: Transaction, E: ExecutorTask> {
+    num_cpus: usize,
+    phantom: PhantomData<(T, E)>,
+}
+
+impl<T, E> SpeculativeTransactionExecutor<T, E>
+where
+    T: Transaction,
+    E: ExecutorTask<T = T>,
+{
+    pub fn new() -> Self {
+        Self {
+            num_cpus: num_cpus::get(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn execute_transactions_parallel(
+        &self,
+        task_initial_arguments: E::Argument,
+        signature_verified_block: Vec<T>,
+    ) -> Result<Vec<E::Output>, E::Error> {
+        let num_txns = signature_verified_block.len();
+        let versioned_data_cache = SpeculativeMVHashMap::new();
+        let records: Vec<ExecutionRecordCell<T, E>> =
+            (0..num_txns).map(|_| Mutex::new(None)).collect();
+
+        // Used to re-execute transactions in order on the current thread.
+        let task = E::init(task_initial_arguments);
+
+        let mut txns_to_execute: Vec<Version> = (0..num_txns).collect();
+        let mut num_committed = 0;
+        let valid_results_length = loop {
+            // Optimistically execute the transactions in parallel, each sees whatever the lower
+            // transactions have written so far.
+            let chunks_size = max(1, txns_to_execute.len() / self.num_cpus);
+            txns_to_execute
+                .par_iter()
+                .with_min_len(chunks_size)
+                .for_each_init(
+                    || E::init(task_initial_arguments),
+                    |task, idx| {
+                        Self::execute(
+                            task,
+                            &signature_verified_block[*idx],
+                            *idx,
+                            &versioned_data_cache,
+                            &records[*idx],
+                        )
+                    },
+                );
+
+            // Commit the longest prefix of transactions whose reads are still valid.
+            let mut stopped = false;
+            while !stopped
+                && num_committed < num_txns
+                && Self::validate(
+                    num_committed,
+                    &versioned_data_cache,
+                    &records[num_committed],
+                )
+            {
+                stopped = Self::stops_execution(&records[num_committed]);
+                num_committed += 1;
+            }
+            if stopped || num_committed == num_txns {
+                break num_committed;
+            }
+
+            // All transactions before the first invalid one are committed, so re-executing it
+            // gives its final output.
+            Self::execute(
+                &task,
+                &signature_verified_block[num_committed],
+                num_committed,
+                &versioned_data_cache,
+                &records[num_committed],
+            );
+            stopped = Self::stops_execution(&records[num_committed]);
+            num_committed += 1;
+            if stopped {
+                break num_committed;
+            }
+
+            // Re-execute the rest of the invalidated transactions in the next round.
+            txns_to_execute = (num_committed..num_txns)
+                .filter(|idx| !Self::validate(*idx, &versioned_data_cache, &records[*idx]))
+                .collect();
+        };
+
+        let outcomes = OutcomeArray::new(num_txns);
+        for (idx, record) in records.into_iter().take(valid_results_length).enumerate() {
+            let status = match record.into_inner().unwrap() {
+                Some(ExecutionRecord { status, .. }) => match status {
+                    ExecutionStatus::Success(output) => ExecutionStatus::Success(output),
+                    ExecutionStatus::SkipRest(output) => ExecutionStatus::SkipRest(output),
+                    ExecutionStatus::Abort(err) => ExecutionStatus::Abort(Error::UserError(err)),
+                    // Reads from the speculative view never block, so a transaction asking to be
+                    // retried is an implementation error.
+                    ExecutionStatus::Retry(_) => ExecutionStatus::Abort(Error::InvariantViolation),
+                },
+                None => return Err(Error::InvariantViolation),
+            };
+            outcomes.set_result(idx, status);
+        }
+
+        // Dropping large structures is expensive -- do this is a separate thread.
+        ::std::thread::spawn(move || {
+            drop(signature_verified_block); // Explicit drops to measure their cost.
+            drop(versioned_data_cache);
+        });
+
+        outcomes.get_all_results(valid_results_length)
+    }
+
+    /// Executes the transaction at `idx` and publishes its writes to `versioned_data_cache`,
+    /// replacing the writes of its previous incarnation.
+    fn execute(
+        task: &E,
+        txn: &T,
+        idx: Version,
+        versioned_data_cache: &SpeculativeMVHashMap<T::Key, T::Value>,
+        record: &ExecutionRecordCell<T, E>,
+    ) {
+        let mut record = record.lock().unwrap();
+        let (incarnation, prev_writes) = match record.take() {
+            Some(prev) => (prev.incarnation + 1, prev.writes),
+            None => (0, vec![]),
+        };
+
+        let view = versioned_data_cache.view(idx);
+        let status = task.execute_transaction(&view, txn);
+        let reads = view.take_reads();
+
+        let writes = match &status {
+            ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
+                output.get_writes()
+            }
+            ExecutionStatus::Abort(_) | ExecutionStatus::Retry(_) => vec![],
+        };
+        let keys_written: HashSet<_> = writes.iter().map(|(k, _)| k).collect();
+        for key in prev_writes.iter().filter(|key| !keys_written.contains(key)) {
+            versioned_data_cache.delete(key, idx);
+        }
+        let writes: Vec<_> = writes
+            .into_iter()
+            .map(|(k, v)| {
+                versioned_data_cache.write(&k, idx, incarnation, v);
+                k
+            })
+            .collect();
+
+        *record = Some(ExecutionRecord {
+            incarnation,
+            reads,
+            writes,
+            status,
+        });
+    }
+
+    /// Checks if all reads of the latest execution of the transaction at `idx` would still observe
+    /// the same writes.
+    fn validate(
+        idx: Version,
+        versioned_data_cache: &SpeculativeMVHashMap<T::Key, T::Value>,
+        record: &ExecutionRecordCell<T, E>,
+    ) -> bool {
+        match &*record.lock().unwrap() {
+            Some(record) => record
+                .reads
+                .iter()
+                .all(|(key, descriptor)| versioned_data_cache.validate_read(key, idx, descriptor)),
+            None => false,
+        }
+    }
+
+    /// Whether a committed transaction ends the block, either by skipping the rest of the
+    /// transactions or by aborting the execution.
+    fn stops_execution(record: &ExecutionRecordCell<T, E>) -> bool {
+        match &*record.lock().unwrap() {
+            Some(record) => !matches!(record.status, ExecutionStatus::Success(_)),
+            None => false,
+        }
+    }
+}
+
+impl<T, E> Default for SpeculativeTransactionExecutor<T, E>
+where
+    T: Transaction,
+    E: ExecutorTask<T = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -4,6 +4,7 @@
 use crate::{
     executor::ParallelTransactionExecutor,
     proptest_types::types::{ExpectedOutput, Inferencer, Task, Transaction},
+    speculative_executor::SpeculativeTransactionExecutor,
 };
 use rand::random;
 use std::{fmt::Debug, hash::Hash};
@@ -19,8 +20,11 @@ where
         ParallelTransactionExecutor::<Transaction<K, V>, Task<K, V>, Inferencer<K, V>>::new(
             Inferencer::new(),
         )
-        .execute_transactions_parallel((), transactions);
+        .execute_transactions_parallel((), transactions.clone());
+    assert!(baseline.check_output(&output));
 
+    let output = SpeculativeTransactionExecutor::<Transaction<K, V>, Task<K, V>>::new()
+        .execute_transactions_parallel((), transactions);
     assert!(baseline.check_output(&output))
 }
 
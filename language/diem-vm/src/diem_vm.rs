@@ -698,6 +698,24 @@ impl DiemVM {
         BLOCK_TRANSACTION_COUNT.observe(count as f64);
         Ok(res)
     }
+
+    /// Executes a single user transaction against `state_view` without verifying its signature,
+    /// e.g. to find out how much gas it uses before signing it. The returned output must never be
+    /// committed.
+    #[cfg(feature = "simulation")]
+    pub fn simulate_signed_transaction(
+        txn: SignedTransaction,
+        state_view: &dyn StateView,
+    ) -> (VMStatus, TransactionOutput) {
+        let state_view_cache = StateViewCache::new(state_view);
+        let vm = DiemVM::new(&state_view_cache);
+        let log_context = AdapterLogSchema::new(state_view_cache.id(), 0);
+        vm.execute_user_transaction(
+            &state_view_cache,
+            &txn.into_unchecked_for_simulation(),
+            &log_context,
+        )
+    }
 }
 
 // Executor external API
@@ -61,6 +61,11 @@ impl PriorityIndex {
         }
     }
 
+    /// Returns the key of the lowest-ranked transaction in the index.
+    pub(crate) fn lowest(&self) -> Option<&OrderedQueueKey> {
+        self.data.iter().next()
+    }
+
     pub(crate) fn iter(&self) -> PriorityQueueIter {
         self.data.iter().rev()
     }
@@ -9,6 +9,7 @@ use crate::{
         transaction::{MempoolTransaction, TimelineState},
         transaction_store::TransactionStore,
         ttl_cache::TtlCache,
+        txn_status::TxnStatusCache,
     },
     counters,
     logging::{LogEntry, LogSchema, TxnsLog},
@@ -36,13 +37,24 @@ pub struct Mempool {
     // This is used to measure e2e latency of transactions in the system, as well as the time it
     // takes to pick it up by consensus.
     pub(crate) metrics_cache: TtlCache<(AccountAddress, u64), SystemTime>,
+    // Shared with `transactions`, which records status updates; kept here for garbage collection.
+    txn_status_cache: TxnStatusCache,
     pub system_transaction_timeout: Duration,
 }
 
 impl Mempool {
     pub fn new(config: &NodeConfig) -> Self {
+        Self::new_with_txn_status_cache(config, TxnStatusCache::new(&config.mempool))
+    }
+
+    /// Creates a mempool which records the status of its transactions in `txn_status_cache`
+    pub fn new_with_txn_status_cache(
+        config: &NodeConfig,
+        txn_status_cache: TxnStatusCache,
+    ) -> Self {
         Mempool {
-            transactions: TransactionStore::new(&config.mempool),
+            transactions: TransactionStore::new(&config.mempool, txn_status_cache.clone()),
+            txn_status_cache,
             sequence_number_cache: TtlCache::new(config.mempool.capacity, Duration::from_secs(100)),
             metrics_cache: TtlCache::new(config.mempool.capacity, Duration::from_secs(100)),
             system_transaction_timeout: Duration::from_secs(
@@ -270,6 +282,18 @@ impl Mempool {
         self.transactions.gc_by_system_ttl(&self.metrics_cache);
         self.metrics_cache.gc(now);
         self.sequence_number_cache.gc(now);
+        self.txn_status_cache.gc(now);
+    }
+
+    /// Returns the txns that were committed, rejected, expired or evicted since the last call.
+    /// Always empty unless mempool is configured to persist txns.
+    pub(crate) fn take_removed_txns(&mut self) -> Vec<TxnPointer> {
+        self.transactions.take_removed_txns()
+    }
+
+    /// Returns whether a txn with the given sender and sequence number is in mempool.
+    pub(crate) fn contains_txn(&self, sender: &AccountAddress, sequence_number: u64) -> bool {
+        self.transactions.get(sender, sequence_number).is_some()
     }
 
     /// Garbage collection based on client-specified expiration time.
@@ -6,7 +6,13 @@ mod mempool;
 mod transaction;
 mod transaction_store;
 mod ttl_cache;
+mod txn_status;
 
 #[cfg(test)]
 pub use self::ttl_cache::TtlCache;
-pub use self::{index::TxnPointer, mempool::Mempool as CoreMempool, transaction::TimelineState};
+pub use self::{
+    index::TxnPointer,
+    mempool::Mempool as CoreMempool,
+    transaction::TimelineState,
+    txn_status::{TxnStatus, TxnStatusCache},
+};
@@ -5,10 +5,11 @@ use crate::{
     core_mempool::{
         index::{
             AccountTransactions, ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
-            TimelineIndex,
+            TimelineIndex, TxnPointer,
         },
         transaction::{MempoolTransaction, TimelineState},
         ttl_cache::TtlCache,
+        txn_status::{TxnStatus, TxnStatusCache},
     },
     counters,
     logging::{LogEntry, LogEvent, LogSchema, TxnsLog},
@@ -44,13 +45,20 @@ pub struct TransactionStore {
     // keeps track of "non-ready" txns (transactions that can't be included in next block)
     parking_lot_index: ParkingLotIndex,
 
+    // last known status of recent transactions, shared with clients outside of mempool
+    txn_status_cache: TxnStatusCache,
+    // txns that left mempool since they were last taken, only tracked if txns are persisted
+    removed_txns: Option<Vec<TxnPointer>>,
+
     // configuration
     capacity: usize,
     capacity_per_user: usize,
+    evict_lowest_ranked_txns: bool,
+    replace_by_fee_min_bump_pct: u64,
 }
 
 impl TransactionStore {
-    pub(crate) fn new(config: &MempoolConfig) -> Self {
+    pub(crate) fn new(config: &MempoolConfig, txn_status_cache: TxnStatusCache) -> Self {
         Self {
             // main DS
             transactions: HashMap::new(),
@@ -64,9 +72,18 @@ impl TransactionStore {
             timeline_index: TimelineIndex::new(),
             parking_lot_index: ParkingLotIndex::new(),
 
+            txn_status_cache,
+            removed_txns: if config.persist_txns {
+                Some(vec![])
+            } else {
+                None
+            },
+
             // configuration
             capacity: config.capacity,
             capacity_per_user: config.capacity_per_user,
+            evict_lowest_ranked_txns: config.evict_lowest_ranked_txns,
+            replace_by_fee_min_bump_pct: config.replace_by_fee_min_bump_pct,
         }
     }
 
@@ -105,8 +122,10 @@ impl TransactionStore {
 
         // check if transaction is already present in Mempool
         // e.g. given request is update
-        // we allow increase in gas price to speed up process.
+        // we allow increase in gas price to speed up process (replace-by-fee),
+        // as long as it's bumped by at least `replace_by_fee_min_bump_pct`.
         // ignores the case transaction hash is same for retrying submit transaction.
+        let min_bump_pct = self.replace_by_fee_min_bump_pct;
         if let Some(txns) = self.transactions.get_mut(&address) {
             if let Some(current_version) =
                 txns.get_mut(&sequence_number.transaction_sequence_number)
@@ -114,18 +133,25 @@ impl TransactionStore {
                 if current_version.txn == txn.txn {
                     return MempoolStatus::new(MempoolStatusCode::Accepted);
                 }
+                let min_gas_price =
+                    min_replacement_gas_price(current_version.get_gas_price(), min_bump_pct);
                 if current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
                     && current_version.txn.payload() == txn.txn.payload()
                     && current_version.txn.expiration_timestamp_secs()
                         == txn.txn.expiration_timestamp_secs()
-                    && current_version.get_gas_price() < txn.get_gas_price()
+                    && txn.get_gas_price() >= min_gas_price
                 {
                     if let Some(txn) = txns.remove(&txn.sequence_info.transaction_sequence_number) {
                         self.index_remove(&txn);
+                        counters::CORE_MEMPOOL_REPLACED_TXNS.inc();
                     }
                 } else {
                     return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        format!("Failed to update gas price to {}", txn.get_gas_price()),
+                        format!(
+                            "Failed to update gas price to {}, minimum replacement gas price: {}",
+                            txn.get_gas_price(),
+                            min_gas_price,
+                        ),
                     );
                 }
             }
@@ -166,6 +192,7 @@ impl TransactionStore {
             // insert into storage and other indexes
             self.system_ttl_index.insert(&txn);
             self.expiration_time_index.insert(&txn);
+            self.txn_status_cache.update(&txn.txn, TxnStatus::Accepted);
             txns.insert(sequence_number.transaction_sequence_number, txn);
             self.track_indices();
         }
@@ -197,7 +224,8 @@ impl TransactionStore {
     }
 
     /// Checks if Mempool is full.
-    /// If it's full, tries to free some space by evicting transactions from the ParkingLot.
+    /// If it's full, tries to free some space by evicting transactions from the ParkingLot, and
+    /// failing that (if enabled), by evicting the lowest-ranked ready transaction.
     /// We only evict on attempt to insert a transaction that would be ready for broadcast upon insertion.
     fn check_is_full_after_eviction(
         &mut self,
@@ -221,12 +249,52 @@ impl TransactionStore {
                         ))
                     );
                     self.index_remove(&txn);
+                    self.mark_removed(&txn.txn, TxnStatus::Evicted);
+                    counters::CORE_MEMPOOL_EVICTED_TXNS
+                        .with_label_values(&[counters::EVICT_PARKING_LOT_LABEL])
+                        .inc();
                 }
+            } else if self.evict_lowest_ranked_txns {
+                self.evict_lowest_ranked_txn(txn);
             }
         }
         self.system_ttl_index.size() >= self.capacity
     }
 
+    /// Tries to free some space in Mempool by evicting the lowest-ranked ready txn, if it's ranked
+    /// strictly lower than `txn`.
+    /// Txns of the same account with higher sequence numbers would no longer be ready, so they are
+    /// evicted as well.
+    fn evict_lowest_ranked_txn(&mut self, txn: &MempoolTransaction) {
+        let lowest = match self.priority_index.lowest() {
+            Some(key) => key.clone(),
+            None => return,
+        };
+        // never evict a txn of the same account, as the incoming txn might depend on it
+        if lowest.address == txn.get_sender()
+            || (lowest.governance_role.priority(), lowest.gas_ranking_score)
+                >= (txn.governance_role.priority(), txn.ranking_score)
+        {
+            return;
+        }
+        if let Some(txns) = self.transactions.get_mut(&lowest.address) {
+            let evicted = txns.split_off(&lowest.sequence_number.transaction_sequence_number);
+            let mut txns_log = TxnsLog::new();
+            for evicted_txn in evicted.values() {
+                txns_log.add(
+                    evicted_txn.get_sender(),
+                    evicted_txn.sequence_info.transaction_sequence_number,
+                );
+                self.index_remove(evicted_txn);
+                self.mark_removed(&evicted_txn.txn, TxnStatus::Evicted);
+            }
+            counters::CORE_MEMPOOL_EVICTED_TXNS
+                .with_label_values(&[counters::EVICT_LOWEST_RANKED_LABEL])
+                .inc_by(evicted.len() as u64);
+            debug!(LogSchema::new(LogEntry::MempoolFullEvictedTxn).txns(txns_log));
+        }
+    }
+
     /// Check if a transaction would be ready for broadcast in mempool upon insertion (without inserting it).
     /// Two ways this can happen:
     /// 1. txn sequence number == curr_sequence_number
@@ -339,6 +407,7 @@ impl TransactionStore {
                     transaction.sequence_info.transaction_sequence_number,
                 );
                 self.index_remove(transaction);
+                self.mark_removed(&transaction.txn, TxnStatus::Committed);
             }
             trace!(
                 LogSchema::new(LogEntry::CleanCommittedTxn).txns(rm_txns),
@@ -370,11 +439,29 @@ impl TransactionStore {
                     transaction.sequence_info.transaction_sequence_number,
                 );
                 self.index_remove(transaction);
+                self.mark_removed(&transaction.txn, TxnStatus::Rejected);
             }
             debug!(LogSchema::new(LogEntry::CleanRejectedTxn).txns(txns_log));
         }
     }
 
+    /// Records that `txn` left mempool for good, with the given status.
+    fn mark_removed(&mut self, txn: &SignedTransaction, status: TxnStatus) {
+        self.txn_status_cache.update(txn, status);
+        if let Some(removed_txns) = &mut self.removed_txns {
+            removed_txns.push((txn.sender(), txn.sequence_number()));
+        }
+    }
+
+    /// Returns the txns that left mempool since the last call.
+    /// Always empty unless mempool is configured to persist txns.
+    pub(crate) fn take_removed_txns(&mut self) -> Vec<TxnPointer> {
+        self.removed_txns
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
     /// Removes transaction from all indexes.
     fn index_remove(&mut self, txn: &MempoolTransaction) {
         counters::CORE_MEMPOOL_REMOVED_TXNS.inc();
@@ -507,6 +594,7 @@ impl TransactionStore {
 
                     // remove txn
                     self.index_remove(&txn);
+                    self.mark_removed(&txn.txn, TxnStatus::Expired);
                 }
             }
         }
@@ -543,3 +631,10 @@ impl TransactionStore {
         self.parking_lot_index.size()
     }
 }
+
+/// Returns the minimum gas price a resubmitted txn needs to replace a txn with `gas_price` that is
+/// already in Mempool. The replacement always has to pay strictly more.
+fn min_replacement_gas_price(gas_price: u64, min_bump_pct: u64) -> u64 {
+    let bump = (gas_price as u128 * min_bump_pct as u128 + 99) / 100;
+    gas_price.saturating_add(bump.max(1).min(u64::MAX as u128) as u64)
+}
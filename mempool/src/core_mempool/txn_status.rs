@@ -0,0 +1,91 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::core_mempool::{index::TxnPointer, ttl_cache::TtlCache};
+use diem_config::config::MempoolConfig;
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_infallible::Mutex;
+use diem_types::{
+    account_address::AccountAddress,
+    transaction::{SignedTransaction, Transaction},
+};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// How long the last known status of a transaction is kept around after it was last updated
+const TXN_STATUS_TTL: Duration = Duration::from_secs(600);
+
+/// Lifecycle status of a transaction, as observed by core mempool
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TxnStatus {
+    /// Accepted into mempool
+    Accepted,
+    /// Evicted from a full mempool to make room for a transaction that is ready for broadcast
+    Evicted,
+    /// Garbage collected after its client-specified or system expiration time
+    Expired,
+    /// Rejected by consensus, or dropped along with a rejected transaction of the same sender
+    Rejected,
+    /// Removed from mempool because its sequence number was committed
+    Committed,
+}
+
+struct TxnStatusCacheInner {
+    statuses: TtlCache<TxnPointer, (HashValue, TxnStatus)>,
+    hashes: TtlCache<HashValue, TxnPointer>,
+}
+
+/// Keeps the last known `TxnStatus` of recent transactions, so clients outside of mempool
+/// (e.g. the JSON-RPC stream endpoint) can follow a transaction until it is committed or dropped.
+/// Cloning it is cheap, and all clones share the same cache.
+#[derive(Clone)]
+pub struct TxnStatusCache {
+    inner: Arc<Mutex<TxnStatusCacheInner>>,
+}
+
+impl TxnStatusCache {
+    pub fn new(config: &MempoolConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(TxnStatusCacheInner {
+                statuses: TtlCache::new(config.capacity, TXN_STATUS_TTL),
+                hashes: TtlCache::new(config.capacity, TXN_STATUS_TTL),
+            })),
+        }
+    }
+
+    /// Records the new status of `txn`
+    pub fn update(&self, txn: &SignedTransaction, status: TxnStatus) {
+        let key = (txn.sender(), txn.sequence_number());
+        let hash = Transaction::UserTransaction(txn.clone()).hash();
+        let mut inner = self.inner.lock();
+        inner.statuses.insert(key, (hash, status));
+        inner.hashes.insert(hash, key);
+    }
+
+    /// Returns the hash and last known status of the transaction with the given sender and
+    /// sequence number
+    pub fn get(
+        &self,
+        sender: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<(HashValue, TxnStatus)> {
+        self.inner
+            .lock()
+            .statuses
+            .get(&(*sender, sequence_number))
+            .cloned()
+    }
+
+    /// Returns the sender and sequence number of the transaction with the given hash
+    pub fn get_txn_pointer(&self, hash: &HashValue) -> Option<TxnPointer> {
+        self.inner.lock().hashes.get(hash).cloned()
+    }
+
+    pub(crate) fn gc(&self, gc_time: SystemTime) {
+        let mut inner = self.inner.lock();
+        inner.statuses.gc(gc_time);
+        inner.hashes.gc(gc_time);
+    }
+}
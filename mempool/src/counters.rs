@@ -24,6 +24,10 @@ pub const GET_BLOCK_STAGE_LABEL: &str = "get_block";
 pub const COMMIT_ACCEPTED_LABEL: &str = "commit_accepted";
 pub const COMMIT_REJECTED_LABEL: &str = "commit_rejected";
 
+// Core mempool eviction type labels
+pub const EVICT_PARKING_LOT_LABEL: &str = "parking_lot";
+pub const EVICT_LOWEST_RANKED_LABEL: &str = "lowest_ranked";
+
 // Core mempool GC type labels
 pub const GC_SYSTEM_TTL_LABEL: &str = "system_ttl";
 pub const GC_CLIENT_EXP_LABEL: &str = "client_expiration";
@@ -100,6 +104,25 @@ pub static CORE_MEMPOOL_REMOVED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter tracking number of txns evicted from a full core mempool, by eviction type
+pub static CORE_MEMPOOL_EVICTED_TXNS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_core_mempool_evicted_txns_count",
+        "Number of txns evicted from core mempool to make room for new txns",
+        &["type"]
+    )
+    .unwrap()
+});
+
+/// Counter tracking number of txns replaced in core mempool by a resubmission with a higher gas price
+pub static CORE_MEMPOOL_REPLACED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_core_mempool_replaced_txns_count",
+        "Number of txns replaced in core mempool by a resubmission with a higher gas price"
+    )
+    .unwrap()
+});
+
 /// Counter tracking latency of txns reaching various stages in committing
 /// (e.g. time from txn entering core mempool to being pulled in consensus block)
 pub static CORE_MEMPOOL_TXN_COMMIT_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
@@ -57,6 +57,7 @@
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod tests;
+pub use core_mempool::{TxnPointer, TxnStatus, TxnStatusCache};
 pub use shared_mempool::{
     bootstrap, network,
     types::{
@@ -70,4 +71,5 @@ pub use tests::{fuzzing, mocks};
 mod core_mempool;
 mod counters;
 mod logging;
+mod mempooldb;
 mod shared_mempool;
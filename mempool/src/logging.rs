@@ -148,6 +148,7 @@ pub enum LogEntry {
     UpstreamNetwork,
     UnexpectedNetworkMsg,
     MempoolSnapshot,
+    MempoolJournal,
 }
 
 #[derive(Clone, Copy, Serialize)]
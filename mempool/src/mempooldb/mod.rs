@@ -0,0 +1,118 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! On-disk journal of the transactions accepted into mempool, so that pending transactions
+//! survive node restarts.
+
+mod schema;
+
+use crate::{core_mempool::TxnPointer, mempooldb::schema::transaction::TransactionSchema};
+use anyhow::Result;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::transaction::SignedTransaction;
+use schema::TRANSACTION_CF_NAME;
+use schemadb::{Options, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME};
+use std::{path::Path, time::Instant};
+
+pub struct MempoolDB {
+    db: DB,
+    /// Updates queued while holding the mempool lock, in the order core mempool applied them.
+    pending: Mutex<Option<SchemaBatch>>,
+    /// Serializes the flushes, so that queued updates reach the DB in order.
+    flush_lock: Mutex<()>,
+}
+
+impl MempoolDB {
+    pub fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
+        let column_families = vec![/* UNUSED CF = */ DEFAULT_CF_NAME, TRANSACTION_CF_NAME];
+
+        let path = db_root_path.as_ref().join("mempooldb");
+        let instant = Instant::now();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open(path.clone(), "mempool", column_families, &opts)
+            .expect("MempoolDB open failed; unable to continue");
+
+        info!(
+            "Opened MempoolDB at {:?} in {} ms",
+            path,
+            instant.elapsed().as_millis()
+        );
+
+        Self {
+            db,
+            pending: Mutex::new(None),
+            flush_lock: Mutex::new(()),
+        }
+    }
+
+    /// Persists the given transactions, overwriting any transaction previously persisted with the
+    /// same sender and sequence number.
+    pub fn save_transactions(&self, transactions: &[SignedTransaction]) -> Result<()> {
+        if transactions.is_empty() {
+            return Ok(());
+        }
+        let mut batch = SchemaBatch::new();
+        transactions.iter().try_for_each(|txn| {
+            batch.put::<TransactionSchema>(&(txn.sender(), txn.sequence_number()), txn)
+        })?;
+        self.db.write_schemas(batch)
+    }
+
+    /// Deletes the transactions with the given sender and sequence number, if persisted.
+    pub fn delete_transactions(&self, txn_pointers: &[TxnPointer]) -> Result<()> {
+        if txn_pointers.is_empty() {
+            return Ok(());
+        }
+        let mut batch = SchemaBatch::new();
+        txn_pointers
+            .iter()
+            .try_for_each(|pointer| batch.delete::<TransactionSchema>(pointer))?;
+        self.db.write_schemas(batch)
+    }
+
+    /// Queues the given transactions to be persisted by the next `flush`.
+    pub fn queue_save_transactions(&self, transactions: &[SignedTransaction]) -> Result<()> {
+        if transactions.is_empty() {
+            return Ok(());
+        }
+        let mut pending = self.pending.lock();
+        let pending = pending.get_or_insert_with(SchemaBatch::new);
+        transactions.iter().try_for_each(|txn| {
+            pending.put::<TransactionSchema>(&(txn.sender(), txn.sequence_number()), txn)
+        })
+    }
+
+    /// Queues the transactions with the given sender and sequence number to be deleted by the next
+    /// `flush`.
+    pub fn queue_delete_transactions(&self, txn_pointers: &[TxnPointer]) -> Result<()> {
+        if txn_pointers.is_empty() {
+            return Ok(());
+        }
+        let mut pending = self.pending.lock();
+        let pending = pending.get_or_insert_with(SchemaBatch::new);
+        txn_pointers
+            .iter()
+            .try_for_each(|pointer| pending.delete::<TransactionSchema>(pointer))
+    }
+
+    /// Writes all queued updates to the DB in a single batch. A later update of the same
+    /// transaction supersedes an earlier one.
+    pub fn flush(&self) -> Result<()> {
+        let _flush_guard = self.flush_lock.lock();
+        let batch = self.pending.lock().take();
+        match batch {
+            Some(batch) => self.db.write_schemas(batch),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns all persisted transactions, ordered by sender and sequence number.
+    pub fn get_transactions(&self) -> Result<Vec<SignedTransaction>> {
+        let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|res| res.map(|(_pointer, txn)| txn)).collect()
+    }
+}
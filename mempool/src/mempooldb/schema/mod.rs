@@ -0,0 +1,8 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+pub(crate) mod transaction;
+
+use schemadb::ColumnFamilyName;
+
+pub(super) const TRANSACTION_CF_NAME: ColumnFamilyName = "transaction";
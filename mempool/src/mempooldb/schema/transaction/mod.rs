@@ -0,0 +1,57 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for transactions accepted into mempool.
+//!
+//! Serialized signed transaction bytes identified by sender and sequence number.
+//! ```text
+//! |<--------------key-------------->|<----value---->|
+//! | sender | sequence_number (BE)   |  signed txn   |
+//! ```
+
+use super::TRANSACTION_CF_NAME;
+use crate::core_mempool::TxnPointer;
+use anyhow::{ensure, Result};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use diem_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use schemadb::schema::{KeyCodec, Schema, ValueCodec};
+use std::{convert::TryFrom, mem::size_of};
+
+pub struct TransactionSchema;
+
+impl Schema for TransactionSchema {
+    const COLUMN_FAMILY_NAME: schemadb::ColumnFamilyName = TRANSACTION_CF_NAME;
+    type Key = TxnPointer;
+    type Value = SignedTransaction;
+}
+
+impl KeyCodec<TransactionSchema> for TxnPointer {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (sender, sequence_number) = self;
+        let mut encoded = sender.to_vec();
+        encoded.write_u64::<BigEndian>(*sequence_number)?;
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure!(
+            data.len() == AccountAddress::LENGTH + size_of::<u64>(),
+            "Unexpected data len {}, expected {}.",
+            data.len(),
+            AccountAddress::LENGTH + size_of::<u64>(),
+        );
+        let sender = AccountAddress::try_from(&data[..AccountAddress::LENGTH])?;
+        let sequence_number = (&data[AccountAddress::LENGTH..]).read_u64::<BigEndian>()?;
+        Ok((sender, sequence_number))
+    }
+}
+
+impl ValueCodec<TransactionSchema> for SignedTransaction {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(bcs::from_bytes(data)?)
+    }
+}
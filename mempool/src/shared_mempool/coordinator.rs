@@ -7,6 +7,7 @@ use crate::{
     core_mempool::{CoreMempool, TimelineState},
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
+    mempooldb::MempoolDB,
     network::{MempoolNetworkEvents, MempoolSyncMsg},
     shared_mempool::{
         tasks,
@@ -66,6 +67,9 @@ pub(crate) async fn coordinator<V>(
     let mut events = select_all(smp_events).fuse();
     let mut scheduled_broadcasts = FuturesUnordered::new();
 
+    // Restore the txns that were pending before the last shutdown, if they're persisted.
+    tasks::replay_journal(&smp).await;
+
     // Use a BoundedExecutor to restrict only `workers_available` concurrent
     // worker tasks that can process incoming transactions.
     let workers_available = smp.config.shared_mempool_max_concurrent_inbound_syncs;
@@ -78,7 +82,7 @@ pub(crate) async fn coordinator<V>(
                 handle_client_event(&mut smp, &bounded_executor, msg, callback).await;
             },
             msg = consensus_requests.select_next_some() => {
-                tasks::process_consensus_request(&smp.mempool, msg).await;
+                tasks::process_consensus_request(&smp.mempool, smp.journal.as_deref(), msg).await;
             },
             msg = mempool_listener.select_next_some() => {
                 handle_state_sync_request(&mut smp, msg, &mut mempool_listener).await;
@@ -156,6 +160,8 @@ async fn handle_state_sync_request<V>(
         false,
     )
     .await;
+    tasks::prune_journal(&mut smp.mempool.lock(), smp.journal.as_deref());
+    tasks::flush_journal(smp.journal.as_deref());
     let counter_result = if mempool_listener.ack_commit_notification(msg).await.is_err() {
         error!(LogSchema::event_log(
             LogEntry::StateSyncCommit,
@@ -288,7 +294,11 @@ async fn handle_event<V>(
 }
 
 /// Garbage collect all expired transactions by SystemTTL.
-pub(crate) async fn gc_coordinator(mempool: Arc<Mutex<CoreMempool>>, gc_interval_ms: u64) {
+pub(crate) async fn gc_coordinator(
+    mempool: Arc<Mutex<CoreMempool>>,
+    journal: Option<Arc<MempoolDB>>,
+    gc_interval_ms: u64,
+) {
     info!(LogSchema::event_log(LogEntry::GCRuntime, LogEvent::Start));
     let mut interval = IntervalStream::new(interval(Duration::from_millis(gc_interval_ms)));
     while let Some(_interval) = interval.next().await {
@@ -296,7 +306,12 @@ pub(crate) async fn gc_coordinator(mempool: Arc<Mutex<CoreMempool>>, gc_interval
             SampleRate::Duration(Duration::from_secs(60)),
             info!(LogSchema::event_log(LogEntry::GCRuntime, LogEvent::Live))
         );
-        mempool.lock().gc();
+        {
+            let mut pool = mempool.lock();
+            pool.gc();
+            tasks::prune_journal(&mut pool, journal.as_deref());
+        }
+        tasks::flush_journal(journal.as_deref());
     }
 
     error!(LogSchema::event_log(
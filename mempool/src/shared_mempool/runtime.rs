@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::CoreMempool,
+    core_mempool::{CoreMempool, TxnStatusCache},
+    mempooldb::MempoolDB,
     network::{MempoolNetworkEvents, MempoolNetworkSender},
     shared_mempool::{
         coordinator::{coordinator, gc_coordinator, snapshot_job},
@@ -57,6 +58,12 @@ pub(crate) fn start_shared_mempool<V>(
         network_senders.insert(network_id, network_sender);
     }
 
+    let journal = if config.mempool.persist_txns {
+        Some(Arc::new(MempoolDB::new(config.storage.dir())))
+    } else {
+        None
+    };
+
     let smp = SharedMempool {
         mempool: mempool.clone(),
         config: config.mempool.clone(),
@@ -65,6 +72,7 @@ pub(crate) fn start_shared_mempool<V>(
         validator,
         peer_manager,
         subscribers,
+        journal: journal.clone(),
     };
 
     executor.spawn(coordinator(
@@ -79,6 +87,7 @@ pub(crate) fn start_shared_mempool<V>(
 
     executor.spawn(gc_coordinator(
         mempool.clone(),
+        journal,
         config.mempool.system_transaction_gc_interval_ms,
     ));
 
@@ -98,13 +107,17 @@ pub fn bootstrap(
     consensus_requests: Receiver<ConsensusRequest>,
     mempool_listener: MempoolNotificationListener,
     mempool_reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    txn_status_cache: TxnStatusCache,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
         .thread_name("shared-mem")
         .enable_all()
         .build()
         .expect("[shared mempool] failed to create runtime");
-    let mempool = Arc::new(Mutex::new(CoreMempool::new(config)));
+    let mempool = Arc::new(Mutex::new(CoreMempool::new_with_txn_status_cache(
+        config,
+        txn_status_cache,
+    )));
     let vm_validator = Arc::new(RwLock::new(VMValidator::new(Arc::clone(&db))));
     start_shared_mempool(
         runtime.handle(),
@@ -7,6 +7,7 @@ use crate::{
     core_mempool::{CoreMempool, TimelineState, TxnPointer},
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
+    mempooldb::MempoolDB,
     network::MempoolSyncMsg,
     shared_mempool::types::{
         notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification,
@@ -257,6 +258,7 @@ where
         .collect::<Vec<_>>();
     vm_validation_timer.stop_and_record();
 
+    let mut accepted = vec![];
     {
         let mut mempool = smp.mempool.lock();
         for (idx, (transaction, crsn_or_seqno)) in transactions.into_iter().enumerate() {
@@ -274,6 +276,9 @@ where
                             timeline_state,
                             governance_role,
                         );
+                        if mempool_status.code == MempoolStatusCode::Accepted {
+                            accepted.push(transaction.clone());
+                        }
                         statuses.push((transaction, (mempool_status, None)));
                     }
                     Some(validation_status) => {
@@ -288,11 +293,83 @@ where
                 }
             }
         }
+        if let Some(journal) = smp.journal.as_deref() {
+            // Txns accepted earlier in this batch may have been evicted by later ones.
+            prune_journal(&mut mempool, Some(journal));
+            accepted.retain(|txn| mempool.contains_txn(&txn.sender(), txn.sequence_number()));
+            if let Err(e) = journal.queue_save_transactions(&accepted) {
+                error!(LogSchema::new(LogEntry::MempoolJournal).error(&e));
+            }
+        }
     }
+    flush_journal(smp.journal.as_deref());
     notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
     statuses
 }
 
+/// Replays the txns persisted in the mempool journal into core mempool.
+/// They go through the same validation as freshly submitted txns, and the ones that are no longer
+/// accepted (e.g. committed or expired while the node was down) are dropped from the journal.
+pub(crate) async fn replay_journal<V>(smp: &SharedMempool<V>)
+where
+    V: TransactionValidation,
+{
+    let journal = match &smp.journal {
+        Some(journal) => journal.clone(),
+        None => return,
+    };
+    let transactions = match journal.get_transactions() {
+        Ok(transactions) => transactions,
+        Err(e) => {
+            error!(LogSchema::new(LogEntry::MempoolJournal).error(&e));
+            return;
+        }
+    };
+    if transactions.is_empty() {
+        return;
+    }
+
+    let statuses = process_incoming_transactions(smp, transactions, TimelineState::NotReady).await;
+    let dropped: Vec<_> = statuses
+        .iter()
+        .filter(|(_, (mempool_status, _))| mempool_status.code != MempoolStatusCode::Accepted)
+        .map(|(txn, _)| (txn.sender(), txn.sequence_number()))
+        .collect();
+    info!(
+        LogSchema::new(LogEntry::MempoolJournal),
+        "replayed {} txns from mempool journal, {} dropped",
+        statuses.len() - dropped.len(),
+        dropped.len()
+    );
+    if let Err(e) = journal.delete_transactions(&dropped) {
+        error!(LogSchema::new(LogEntry::MempoolJournal).error(&e));
+    }
+}
+
+/// Queues the removal of the txns that left core mempool (committed, rejected, expired or evicted)
+/// from the mempool journal.
+/// Must be called while holding the mempool lock, so that the journal is updated in the same order
+/// as core mempool. The removals are written by `flush_journal`, once the lock is released.
+pub(crate) fn prune_journal(mempool: &mut CoreMempool, journal: Option<&MempoolDB>) {
+    let removed_txns = mempool.take_removed_txns();
+    if let Some(journal) = journal {
+        if let Err(e) = journal.queue_delete_transactions(&removed_txns) {
+            error!(LogSchema::new(LogEntry::MempoolJournal).error(&e));
+        }
+    }
+}
+
+/// Writes the queued journal updates to disk.
+/// Should be called after releasing the mempool lock, so that the disk IO doesn't block core
+/// mempool.
+pub(crate) fn flush_journal(journal: Option<&MempoolDB>) {
+    if let Some(journal) = journal {
+        if let Err(e) = journal.flush() {
+            error!(LogSchema::new(LogEntry::MempoolJournal).error(&e));
+        }
+    }
+}
+
 fn log_txn_process_results(results: &[SubmissionStatusBundle], sender: Option<PeerNetworkId>) {
     let (network, sender) = match sender {
         Some(peer) => (
@@ -338,7 +415,11 @@ fn log_txn_process_results(results: &[SubmissionStatusBundle], sender: Option<Pe
 // intra-node communication handlers //
 // ================================= //
 
-pub(crate) async fn process_consensus_request(mempool: &Mutex<CoreMempool>, req: ConsensusRequest) {
+pub(crate) async fn process_consensus_request(
+    mempool: &Mutex<CoreMempool>,
+    journal: Option<&MempoolDB>,
+    req: ConsensusRequest,
+) {
     // Start latency timer
     let start_time = Instant::now();
     debug!(LogSchema::event_log(LogEntry::Consensus, LogEvent::Received).consensus_msg(&req));
@@ -356,9 +437,11 @@ pub(crate) async fn process_consensus_request(mempool: &Mutex<CoreMempool>, req:
                 // Note: this gc operation relies on the fact that consensus uses the system time to determine block timestamp
                 let curr_time = diem_infallible::duration_since_epoch();
                 mempool.gc_by_expiration_time(curr_time);
+                prune_journal(&mut mempool, journal);
                 let block_size = cmp::max(max_block_size, 1);
                 txns = mempool.get_block(block_size, exclude_transactions);
             }
+            flush_journal(journal);
             counters::mempool_service_transactions(counters::GET_BLOCK_LABEL, txns.len());
             txns.len();
             let pulled_block = txns.drain(..).map(SignedTransaction::into).collect();
@@ -375,6 +458,8 @@ pub(crate) async fn process_consensus_request(mempool: &Mutex<CoreMempool>, req:
                 transactions.len(),
             );
             commit_txns(mempool, transactions, 0, true).await;
+            prune_journal(&mut mempool.lock(), journal);
+            flush_journal(journal);
             (
                 ConsensusResponse::CommitResponse(),
                 callback,
@@ -5,6 +5,7 @@
 
 use crate::{
     core_mempool::CoreMempool,
+    mempooldb::MempoolDB,
     shared_mempool::{network::MempoolNetworkSender, peer_manager::PeerManager},
 };
 use anyhow::Result;
@@ -45,6 +46,7 @@ where
     pub validator: Arc<RwLock<V>>,
     pub peer_manager: Arc<PeerManager>,
     pub subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
+    pub journal: Option<Arc<MempoolDB>>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -2,16 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TtlCache},
+    core_mempool::{CoreMempool, TimelineState, TtlCache, TxnStatus, TxnStatusCache},
     tests::common::{
         add_signed_txn, add_txn, add_txns_to_mempool, exist_in_metrics_cache, setup_mempool,
         TestTransaction,
     },
 };
 use diem_config::config::NodeConfig;
+use diem_crypto::hash::CryptoHash;
 use diem_types::{
     account_config::AccountSequenceInfo,
-    transaction::{GovernanceRole, SignedTransaction},
+    transaction::{GovernanceRole, SignedTransaction, Transaction},
 };
 use std::{
     collections::HashSet,
@@ -321,6 +322,23 @@ fn test_fail_for_same_gas_amount_and_not_same_expiration_time_crsn() {
     assert!(ret.is_err())
 }
 
+#[test]
+fn test_replace_by_fee_min_bump() {
+    let mut config = NodeConfig::random();
+    config.mempool.replace_by_fee_min_bump_pct = 50;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 10)).unwrap();
+
+    // Bump below the minimum is rejected.
+    assert!(add_txn(&mut pool, TestTransaction::new(0, 0, 14)).is_err());
+    // Bump of exactly the minimum replaces the txn.
+    add_txn(&mut pool, TestTransaction::new(0, 0, 15)).unwrap();
+
+    let block = pool.get_block(10, HashSet::new());
+    assert_eq!(block.len(), 1);
+    assert_eq!(block[0].gas_unit_price(), 15);
+}
+
 #[test]
 fn test_update_invalid_transaction_in_mempool() {
     let (mut mempool, mut consensus) = setup_mempool();
@@ -570,6 +588,44 @@ fn test_parking_lot_eviction() {
     assert!(add_txn(&mut pool, TestTransaction::new(0, 2, 1)).is_err());
 }
 
+#[test]
+fn test_evict_lowest_ranked_txns() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 4;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 3)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 1, 5)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(2, 0, 2)).unwrap();
+
+    // Mempool is full and parking lot is empty, txns ranked no higher than the lowest are refused.
+    assert!(add_txn(&mut pool, TestTransaction::new(3, 0, 1)).is_err());
+
+    // A higher-ranked txn evicts the lowest-ranked txn, along with the txns that depend on it.
+    add_txn(&mut pool, TestTransaction::new(3, 0, 4)).unwrap();
+    let mut txns: Vec<_> = pool
+        .get_block(10, HashSet::new())
+        .iter()
+        .map(SignedTransaction::gas_unit_price)
+        .collect();
+    txns.sort_unstable();
+    assert_eq!(txns, vec![2, 3, 4]);
+
+    // Never evicts txns of the sender of the incoming txn.
+    add_txn(&mut pool, TestTransaction::new(4, 0, 5)).unwrap();
+    assert!(add_txn(&mut pool, TestTransaction::new(2, 1, 10)).is_err());
+}
+
+#[test]
+fn test_evict_lowest_ranked_txns_disabled() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 1;
+    config.mempool.evict_lowest_ranked_txns = false;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 0, 10)).is_err());
+}
+
 #[test]
 fn test_parking_lot_evict_only_for_ready_txn_insertion() {
     let mut config = NodeConfig::random();
@@ -684,3 +740,49 @@ fn test_ttl_cache() {
         .unwrap());
     assert_eq!(cache.size(), 0);
 }
+
+#[test]
+fn test_txn_status_cache() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 5;
+    let txn_status_cache = TxnStatusCache::new(&config.mempool);
+    let mut pool = CoreMempool::new_with_txn_status_cache(&config, txn_status_cache.clone());
+    let status = |address, seq| {
+        txn_status_cache
+            .get(&TestTransaction::get_address(address), seq)
+            .map(|(_, status)| status)
+    };
+
+    // Ready txns, plus two parked txns that can be evicted.
+    for seq in &[0, 1, 9, 10] {
+        add_txn(&mut pool, TestTransaction::new(1, *seq, 1)).unwrap();
+        assert_eq!(status(1, *seq), Some(TxnStatus::Accepted));
+    }
+    let txn = TestTransaction::new(0, 0, 1).make_signed_transaction_with_expiration_time(0);
+    add_signed_txn(&mut pool, txn.clone()).unwrap();
+    let hash = Transaction::UserTransaction(txn).hash();
+    assert_eq!(
+        txn_status_cache.get_txn_pointer(&hash),
+        Some((TestTransaction::get_address(0), 0))
+    );
+    assert_eq!(
+        txn_status_cache.get(&TestTransaction::get_address(0), 0),
+        Some((hash, TxnStatus::Accepted))
+    );
+
+    // Mempool is full: inserting a ready txn evicts a parked one.
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+    assert!(status(1, 9) == Some(TxnStatus::Evicted) || status(1, 10) == Some(TxnStatus::Evicted));
+
+    pool.gc_by_expiration_time(Duration::from_secs(1));
+    assert_eq!(status(0, 0), Some(TxnStatus::Expired));
+
+    pool.remove_transaction(&TestTransaction::get_address(1), 0, false);
+    assert_eq!(status(1, 0), Some(TxnStatus::Committed));
+
+    pool.remove_transaction(&TestTransaction::get_address(1), 1, true);
+    assert_eq!(status(1, 1), Some(TxnStatus::Rejected));
+
+    // Never seen by mempool.
+    assert_eq!(status(2, 0), None);
+}
@@ -43,6 +43,7 @@ pub fn test_mempool_process_incoming_transactions_impl(
         validator: vm_validator,
         peer_manager: Arc::new(PeerManager::new(config.base.role, config.mempool)),
         subscribers: vec![],
+        journal: None,
     };
 
     let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state);
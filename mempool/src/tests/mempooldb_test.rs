@@ -0,0 +1,237 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    core_mempool::{CoreMempool, TimelineState, TxnPointer},
+    mempooldb::MempoolDB,
+    shared_mempool::{peer_manager::PeerManager, tasks, types::SharedMempool},
+    tests::common::TestTransaction,
+};
+use diem_config::config::NodeConfig;
+use diem_infallible::{Mutex, RwLock};
+use diem_temppath::TempPath;
+use diem_types::{mempool_status::MempoolStatusCode, transaction::SignedTransaction};
+use futures::executor::block_on;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use storage_interface::mock::MockDbReader;
+use vm_validator::mocks::mock_vm_validator::MockVMValidator;
+
+#[test]
+fn test_save_delete_get_transactions() {
+    let tmp_dir = TempPath::new();
+    let db = MempoolDB::new(&tmp_dir);
+    assert!(db.get_transactions().unwrap().is_empty());
+
+    let txns = vec![
+        TestTransaction::new(1, 1, 1).make_signed_transaction(),
+        TestTransaction::new(0, 0, 1).make_signed_transaction(),
+        TestTransaction::new(1, 0, 1).make_signed_transaction(),
+    ];
+    db.save_transactions(&txns).unwrap();
+    assert_eq!(db.get_transactions().unwrap().len(), 3);
+
+    // Saving a transaction with the same sender and sequence number replaces the old one.
+    let replacement = TestTransaction::new(0, 0, 5).make_signed_transaction();
+    db.save_transactions(&[replacement.clone()]).unwrap();
+    db.delete_transactions(&[(txns[2].sender(), txns[2].sequence_number())])
+        .unwrap();
+
+    // Transactions survive reopening the db.
+    drop(db);
+    let db = MempoolDB::new(&tmp_dir);
+    let mut persisted = db.get_transactions().unwrap();
+    persisted.sort_by_key(|txn| txn.gas_unit_price());
+    assert_eq!(persisted, vec![txns[0].clone(), replacement]);
+}
+
+#[test]
+fn test_queue_and_flush_transactions() {
+    let tmp_dir = TempPath::new();
+    let db = MempoolDB::new(&tmp_dir);
+    let txn = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    let pointer = (txn.sender(), txn.sequence_number());
+
+    // Queued updates are only written on flush.
+    db.queue_save_transactions(&[txn.clone()]).unwrap();
+    assert!(db.get_transactions().unwrap().is_empty());
+    db.flush().unwrap();
+    assert_eq!(db.get_transactions().unwrap(), vec![txn.clone()]);
+
+    // A later update of the same transaction supersedes an earlier one.
+    db.queue_delete_transactions(&[pointer]).unwrap();
+    db.queue_save_transactions(&[txn.clone()]).unwrap();
+    db.queue_delete_transactions(&[pointer]).unwrap();
+    db.flush().unwrap();
+    assert!(db.get_transactions().unwrap().is_empty());
+
+    db.queue_save_transactions(&[txn.clone()]).unwrap();
+    db.queue_delete_transactions(&[pointer]).unwrap();
+    db.queue_save_transactions(&[txn.clone()]).unwrap();
+    db.flush().unwrap();
+    assert_eq!(db.get_transactions().unwrap(), vec![txn]);
+
+    // Flushing with nothing queued is a no-op.
+    db.flush().unwrap();
+}
+
+fn setup_shared_mempool(db_root_path: &TempPath) -> SharedMempool<MockVMValidator> {
+    let mut config = NodeConfig::random();
+    config.mempool.persist_txns = true;
+    config.mempool.capacity = 3;
+    SharedMempool {
+        mempool: Arc::new(Mutex::new(CoreMempool::new(&config))),
+        config: config.mempool.clone(),
+        network_senders: HashMap::new(),
+        db: Arc::new(MockDbReader),
+        validator: Arc::new(RwLock::new(MockVMValidator)),
+        peer_manager: Arc::new(PeerManager::new(config.base.role, config.mempool)),
+        subscribers: vec![],
+        journal: Some(Arc::new(MempoolDB::new(db_root_path))),
+    }
+}
+
+fn add_txns(smp: &SharedMempool<MockVMValidator>, txns: Vec<SignedTransaction>) {
+    let statuses = block_on(tasks::process_incoming_transactions(
+        smp,
+        txns,
+        TimelineState::NotReady,
+    ));
+    for (_, (mempool_status, _)) in statuses {
+        assert_eq!(mempool_status.code, MempoolStatusCode::Accepted);
+    }
+}
+
+fn journal_txns(smp: &SharedMempool<MockVMValidator>) -> HashSet<TxnPointer> {
+    smp.journal
+        .as_ref()
+        .unwrap()
+        .get_transactions()
+        .unwrap()
+        .iter()
+        .map(|txn| (txn.sender(), txn.sequence_number()))
+        .collect()
+}
+
+fn pointer(address: usize, sequence_number: u64) -> TxnPointer {
+    (TestTransaction::get_address(address), sequence_number)
+}
+
+#[test]
+fn test_replay_journal_after_restart() {
+    let tmp_dir = TempPath::new();
+    let txns = vec![
+        TestTransaction::new(0, 0, 1).make_signed_transaction(),
+        TestTransaction::new(0, 1, 1).make_signed_transaction(),
+        TestTransaction::new(1, 0, 1).make_signed_transaction(),
+    ];
+    let smp = setup_shared_mempool(&tmp_dir);
+    add_txns(&smp, txns.clone());
+    drop(smp);
+
+    // A restarted node starts with an empty mempool, and refills it from the journal.
+    let smp = setup_shared_mempool(&tmp_dir);
+    block_on(tasks::replay_journal(&smp));
+    {
+        let pool = smp.mempool.lock();
+        for txn in &txns {
+            assert!(pool.contains_txn(&txn.sender(), txn.sequence_number()));
+        }
+    }
+    assert_eq!(
+        journal_txns(&smp),
+        vec![pointer(0, 0), pointer(0, 1), pointer(1, 0)]
+            .into_iter()
+            .collect()
+    );
+}
+
+#[test]
+fn test_prune_journal() {
+    let tmp_dir = TempPath::new();
+    let smp = setup_shared_mempool(&tmp_dir);
+    let journal = smp.journal.as_deref();
+
+    // Committing a txn removes it, along with the txns of the same sender with lower sequence
+    // numbers.
+    add_txns(
+        &smp,
+        vec![
+            TestTransaction::new(0, 0, 1).make_signed_transaction(),
+            TestTransaction::new(0, 1, 1).make_signed_transaction(),
+        ],
+    );
+    {
+        let mut pool = smp.mempool.lock();
+        pool.remove_transaction(&TestTransaction::get_address(0), 0, false);
+        tasks::prune_journal(&mut pool, journal);
+    }
+    tasks::flush_journal(journal);
+    assert_eq!(
+        journal_txns(&smp),
+        vec![pointer(0, 1)].into_iter().collect()
+    );
+
+    // Rejecting a txn removes all txns of its sender.
+    add_txns(
+        &smp,
+        vec![
+            TestTransaction::new(1, 0, 1).make_signed_transaction(),
+            TestTransaction::new(1, 1, 1).make_signed_transaction(),
+        ],
+    );
+    {
+        let mut pool = smp.mempool.lock();
+        pool.remove_transaction(&TestTransaction::get_address(1), 0, true);
+        tasks::prune_journal(&mut pool, journal);
+    }
+    tasks::flush_journal(journal);
+    assert_eq!(
+        journal_txns(&smp),
+        vec![pointer(0, 1)].into_iter().collect()
+    );
+
+    // Expired txns are garbage collected.
+    add_txns(
+        &smp,
+        vec![TestTransaction::new(2, 0, 1).make_signed_transaction_with_expiration_time(1)],
+    );
+    {
+        let mut pool = smp.mempool.lock();
+        pool.gc_by_expiration_time(Duration::from_secs(2));
+        tasks::prune_journal(&mut pool, journal);
+    }
+    tasks::flush_journal(journal);
+    assert_eq!(
+        journal_txns(&smp),
+        vec![pointer(0, 1)].into_iter().collect()
+    );
+
+    // A parked txn is evicted from a full mempool by a txn that is ready for broadcast.
+    add_txns(
+        &smp,
+        vec![
+            TestTransaction::new(3, 5, 1).make_signed_transaction(),
+            TestTransaction::new(0, 2, 1).make_signed_transaction(),
+        ],
+    );
+    assert_eq!(
+        journal_txns(&smp),
+        vec![pointer(0, 1), pointer(0, 2), pointer(3, 5)]
+            .into_iter()
+            .collect()
+    );
+    add_txns(
+        &smp,
+        vec![TestTransaction::new(3, 0, 1).make_signed_transaction()],
+    );
+    assert_eq!(
+        journal_txns(&smp),
+        vec![pointer(0, 1), pointer(0, 2), pointer(3, 0)]
+            .into_iter()
+            .collect()
+    );
+}
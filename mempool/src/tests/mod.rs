@@ -6,6 +6,8 @@ mod common;
 #[cfg(test)]
 mod core_mempool_test;
 #[cfg(test)]
+mod mempooldb_test;
+#[cfg(test)]
 mod multi_node_test;
 #[cfg(test)]
 mod node;
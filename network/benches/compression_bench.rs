@@ -0,0 +1,81 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+// Allow KiB, MiB consts
+#![allow(non_upper_case_globals, non_snake_case)]
+// Allow writing 1 * KiB or 1 * MiB
+#![allow(clippy::identity_op)]
+
+//! Bandwidth and CPU tradeoff of the compressed protocols: the throughput of the compression and
+//! of the decompression is measured by criterion, the compression ratio of each payload is
+//! printed along.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use network::{protocols::wire::compression, ProtocolId};
+use rand::{rngs::SmallRng, Rng, RngCore, SeedableRng};
+
+const KiB: usize = 1 << 10;
+const MiB: usize = 1 << 20;
+const PROTOCOL: ProtocolId = ProtocolId::StateSyncRpcCompressed;
+
+/// A BCS payload resembling a chunk of transactions: a few accounts sending transactions with
+/// increasing sequence numbers and mostly similar arguments.
+fn bcs_payload(rng: &mut SmallRng, len: usize) -> Vec<u8> {
+    let accounts: Vec<[u8; 16]> = (0..8).map(|_| rng.gen()).collect();
+    let mut txns: Vec<([u8; 16], u64, u64, Vec<u8>)> = Vec::new();
+    let mut payload_len = 0;
+    while payload_len < len {
+        let account = accounts[rng.gen_range(0..accounts.len())];
+        let sequence_number = txns.len() as u64;
+        let mut args = vec![0u8; 64];
+        rng.fill_bytes(&mut args[..8]);
+        payload_len += 16 + 8 + 8 + 1 + args.len();
+        txns.push((account, sequence_number, 1_000_000, args));
+    }
+    let mut payload = bcs::to_bytes(&txns).unwrap();
+    payload.truncate(len);
+    payload
+}
+
+fn random_payload(rng: &mut SmallRng, len: usize) -> Vec<u8> {
+    let mut payload = vec![0u8; len];
+    rng.fill_bytes(&mut payload);
+    payload
+}
+
+fn compression_benchmark(c: &mut Criterion) {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let msg_lens = [1 * KiB, 64 * KiB, 1 * MiB, 4 * MiB];
+    let payloads: Vec<(&str, fn(&mut SmallRng, usize) -> Vec<u8>)> =
+        vec![("bcs", bcs_payload), ("random", random_payload)];
+
+    for (name, generate) in payloads {
+        let mut group = c.benchmark_group(format!("compression_{}", name));
+        group.sample_size(10);
+        for &msg_len in &msg_lens {
+            let payload = generate(&mut rng, msg_len);
+            let compressed = compression::compress(PROTOCOL, &payload).unwrap();
+            println!(
+                "{} payload of {} bytes compressed to {} bytes ({:.1}%)",
+                name,
+                msg_len,
+                compressed.len(),
+                100.0 * compressed.len() as f64 / msg_len as f64
+            );
+
+            group.throughput(Throughput::Bytes(msg_len as u64));
+            group.bench_with_input(BenchmarkId::new("compress", msg_len), &payload, |b, p| {
+                b.iter(|| compression::compress(PROTOCOL, p).unwrap())
+            });
+            group.bench_with_input(
+                BenchmarkId::new("decompress", msg_len),
+                &compressed,
+                |b, c| b.iter(|| compression::decompress(PROTOCOL, c.clone(), msg_len).unwrap()),
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, compression_benchmark);
+criterion_main!(benches);
@@ -12,14 +12,15 @@
 use channel::{self, message_queues::QueueStyle};
 use diem_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, RateLimitConfig, RoleType,
-        CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONCURRENT_NETWORK_REQS,
-        MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE, MAX_FULLNODE_OUTBOUND_CONNECTIONS,
-        MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
+        DiscoveryMethod, NetworkConfig, Peer, PeerReputationConfig, PeerRole, PeerSet,
+        RateLimitConfig, RoleType, CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS,
+        MAX_CONCURRENT_NETWORK_REQS, MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE,
+        MAX_FULLNODE_OUTBOUND_CONNECTIONS, MAX_INBOUND_CONNECTIONS, MAX_MESSAGE_SIZE,
+        NETWORK_CHANNEL_SIZE,
     },
     network_id::NetworkContext,
 };
-use diem_crypto::x25519::PublicKey;
+use diem_crypto::x25519;
 use diem_infallible::RwLock;
 use diem_logger::prelude::*;
 use diem_metrics::IntCounterVec;
@@ -27,6 +28,8 @@ use diem_network_address_encryption::Encryptor;
 use diem_secure_storage::Storage;
 use diem_time_service::TimeService;
 use diem_types::{chain_id::ChainId, network_address::NetworkAddress};
+#[cfg(any(test, feature = "testing"))]
+use netcore::transport::simulated::NetworkSimulator;
 use network::{
     application::storage::PeerMetadataStorage,
     connectivity_manager::{builder::ConnectivityManagerBuilder, ConnectivityRequest},
@@ -35,13 +38,16 @@ use network::{
         builder::{AuthenticationMode, PeerManagerBuilder},
         ConnectionRequestSender,
     },
+    peer_reputation::PeerReputation,
     protocols::{
         health_checker::{self, builder::HealthCheckerBuilder},
         network::{NewNetworkEvents, NewNetworkSender},
     },
     ProtocolId,
 };
-use network_discovery::{gen_simple_discovery_reconfig_subscription, DiscoveryChangeListener};
+use network_discovery::{
+    gen_simple_discovery_reconfig_subscription, peer_exchange, DiscoveryChangeListener,
+};
 use std::{
     clone::Clone,
     collections::{HashMap, HashSet},
@@ -72,6 +78,7 @@ pub struct NetworkBuilder {
     health_checker_builder: Option<HealthCheckerBuilder>,
     peer_manager_builder: PeerManagerBuilder,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
+    peer_reputation: Arc<PeerReputation>,
 
     // (StateSync) ReconfigSubscriptions required by internal Network components.
     reconfig_subscriptions: Vec<ReconfigSubscription>,
@@ -80,6 +87,7 @@ pub struct NetworkBuilder {
 impl NetworkBuilder {
     /// Return a new NetworkBuilder initialized with default configuration values.
     // TODO:  Remove `pub`.  NetworkBuilder should only be created thorugh `::create()`
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         chain_id: ChainId,
         trusted_peers: Arc<RwLock<PeerSet>>,
@@ -88,14 +96,22 @@ impl NetworkBuilder {
         listen_address: NetworkAddress,
         authentication_mode: AuthenticationMode,
         max_frame_size: usize,
+        max_message_size: usize,
         enable_proxy_protocol: bool,
+        enable_compression: bool,
         network_channel_size: usize,
         max_concurrent_network_reqs: usize,
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        peer_reputation_config: PeerReputationConfig,
     ) -> Self {
         let peer_metadata_storage = Arc::new(PeerMetadataStorage::new());
+        let peer_reputation = Arc::new(PeerReputation::new(
+            network_context.clone(),
+            time_service.clone(),
+            peer_reputation_config,
+        ));
         // A network cannot exist without a PeerManager
         // TODO:  construct this in create and pass it to new() as a parameter. The complication is manual construction of NetworkBuilder in various tests.
         let peer_manager_builder = PeerManagerBuilder::create(
@@ -109,10 +125,13 @@ impl NetworkBuilder {
             network_channel_size,
             max_concurrent_network_reqs,
             max_frame_size,
+            max_message_size,
             enable_proxy_protocol,
+            enable_compression,
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            peer_reputation.clone(),
         );
 
         NetworkBuilder {
@@ -126,6 +145,7 @@ impl NetworkBuilder {
             peer_manager_builder,
             reconfig_subscriptions: vec![],
             peer_metadata_storage,
+            peer_reputation,
         }
     }
 
@@ -148,12 +168,15 @@ impl NetworkBuilder {
             listen_address,
             authentication_mode,
             MAX_FRAME_SIZE,
+            MAX_MESSAGE_SIZE,
             false, /* Disable proxy protocol */
+            true,  /* Enable compression */
             NETWORK_CHANNEL_SIZE,
             MAX_CONCURRENT_NETWORK_REQS,
             MAX_INBOUND_CONNECTIONS,
             None,
             None,
+            PeerReputationConfig::default(),
         );
 
         builder.add_connectivity_manager(
@@ -179,7 +202,6 @@ impl NetworkBuilder {
     ) -> NetworkBuilder {
         let peer_id = config.peer_id();
         let identity_key = config.identity_key();
-        let pubkey = identity_key.public_key();
 
         let authentication_mode = if config.mutual_authentication {
             AuthenticationMode::Mutual(identity_key)
@@ -203,12 +225,15 @@ impl NetworkBuilder {
             config.listen_address.clone(),
             authentication_mode,
             config.max_frame_size,
+            config.max_message_size,
             config.enable_proxy_protocol,
+            config.enable_compression,
             config.network_channel_size,
             config.max_concurrent_network_reqs,
             config.max_inbound_connections,
             config.inbound_rate_limit_config,
             config.outbound_rate_limit_config,
+            config.peer_reputation_config,
         );
 
         network_builder.add_connection_monitoring(
@@ -232,10 +257,12 @@ impl NetworkBuilder {
         );
 
         network_builder.discovery_listeners = Some(Vec::new());
+        // The identity key was moved into the authentication mode
+        let identity_key = config.identity_key();
         for discovery_method in config.discovery_methods() {
             network_builder.add_discovery_change_listener(
                 discovery_method,
-                pubkey,
+                &identity_key,
                 config.encryptor(),
             );
         }
@@ -256,6 +283,16 @@ impl NetworkBuilder {
         network_builder
     }
 
+    /// Route the connections of a '/memory/<port>' listen address through the simulator. Must be
+    /// set before the Networking components are built.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn set_network_simulator(&mut self, network_simulator: NetworkSimulator) -> &mut Self {
+        assert_eq!(self.state, State::CREATED);
+        self.peer_manager_builder
+            .set_network_simulator(network_simulator);
+        self
+    }
+
     /// Create the configured Networking components.
     pub fn build(&mut self, executor: Handle) -> &mut Self {
         assert_eq!(self.state, State::CREATED);
@@ -306,6 +343,11 @@ impl NetworkBuilder {
         &mut self.reconfig_subscriptions
     }
 
+    /// The reputation of the peers of this network.
+    pub fn peer_reputation(&self) -> Arc<PeerReputation> {
+        self.peer_reputation.clone()
+    }
+
     pub fn network_context(&self) -> Arc<NetworkContext> {
         self.network_context.clone()
     }
@@ -361,6 +403,7 @@ impl NetworkBuilder {
             pm_conn_mgr_notifs_rx,
             outbound_connection_limit,
             mutual_authentication,
+            self.peer_reputation.clone(),
         ));
         self
     }
@@ -368,7 +411,7 @@ impl NetworkBuilder {
     fn add_discovery_change_listener(
         &mut self,
         discovery_method: &DiscoveryMethod,
-        pubkey: PublicKey,
+        identity_key: &x25519::PrivateKey,
         encryptor: Encryptor<Storage>,
     ) {
         let conn_mgr_reqs_tx = self
@@ -384,7 +427,7 @@ impl NetworkBuilder {
                 DiscoveryChangeListener::validator_set(
                     self.network_context.clone(),
                     conn_mgr_reqs_tx,
-                    pubkey,
+                    identity_key.public_key(),
                     encryptor,
                     simple_discovery_reconfig_rx,
                 )
@@ -396,6 +439,19 @@ impl NetworkBuilder {
                 *interval_duration,
                 self.time_service.clone(),
             ),
+            DiscoveryMethod::PeerExchange(peer_exchange_config) => {
+                let (network_tx, network_rx) =
+                    self.add_protocol_handler(peer_exchange::network_endpoint_config());
+                DiscoveryChangeListener::peer_exchange(
+                    self.network_context.clone(),
+                    conn_mgr_reqs_tx,
+                    identity_key,
+                    peer_exchange_config,
+                    network_tx,
+                    network_rx,
+                    self.time_service.clone(),
+                )
+            }
             DiscoveryMethod::None => return,
         };
 
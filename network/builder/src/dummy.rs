@@ -12,9 +12,13 @@ use diem_config::{
 use diem_crypto::{test_utils::TEST_SEED, x25519, Uniform};
 use diem_infallible::RwLock;
 use diem_metrics::IntCounterVec;
+#[cfg(any(test, feature = "testing"))]
+use diem_time_service::MockTimeService;
 use diem_time_service::TimeService;
 use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
 use futures::{executor::block_on, StreamExt};
+#[cfg(any(test, feature = "testing"))]
+use netcore::transport::simulated::NetworkSimulator;
 use netcore::transport::ConnectionOrigin;
 use network::{
     error::NetworkError,
@@ -38,6 +42,9 @@ use tokio::runtime::Runtime;
 
 const TEST_RPC_PROTOCOL: ProtocolId = ProtocolId::ConsensusRpc;
 const TEST_DIRECT_SEND_PROTOCOL: ProtocolId = ProtocolId::ConsensusDirectSend;
+/// How far the mock time is advanced at once while waiting for simulated network events.
+#[cfg(any(test, feature = "testing"))]
+const MOCK_TIME_STEP: Duration = Duration::from_millis(100);
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct DummyMsg(pub Vec<u8>);
@@ -100,15 +107,106 @@ impl DummyNetworkSender {
 pub struct DummyNetwork {
     pub runtime: Runtime,
     pub dialer_peer_id: PeerId,
+    pub dialer_address: NetworkAddress,
     pub dialer_events: DummyNetworkEvents,
     pub dialer_sender: DummyNetworkSender,
     pub listener_peer_id: PeerId,
+    pub listener_address: NetworkAddress,
     pub listener_events: DummyNetworkEvents,
     pub listener_sender: DummyNetworkSender,
 }
 
+impl DummyNetwork {
+    fn check_new_peer_events(
+        &self,
+        first_dialer_event: Event<DummyMsg>,
+        first_listener_event: Event<DummyMsg>,
+    ) {
+        if let Event::NewPeer(metadata) = first_dialer_event {
+            assert_eq!(metadata.remote_peer_id, self.listener_peer_id);
+            assert_eq!(metadata.origin, ConnectionOrigin::Outbound);
+            assert_eq!(metadata.role, PeerRole::Validator);
+        } else {
+            panic!(
+                "No NewPeer event on dialer received instead: {:?}",
+                first_dialer_event
+            );
+        }
+
+        if let Event::NewPeer(metadata) = first_listener_event {
+            assert_eq!(metadata.remote_peer_id, self.dialer_peer_id);
+            assert_eq!(metadata.origin, ConnectionOrigin::Inbound);
+            assert_eq!(metadata.role, PeerRole::Validator);
+        } else {
+            panic!(
+                "No NewPeer event on listener received instead: {:?}",
+                first_listener_event
+            );
+        }
+    }
+}
+
 /// The following sets up a 2 peer network and verifies connectivity.
 pub fn setup_network() -> DummyNetwork {
+    let addr: NetworkAddress = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
+    let mut network = build_network(TimeService::real(), addr, |_| {});
+
+    // Wait for establishing connection
+    let first_dialer_event = block_on(network.dialer_events.next()).unwrap();
+    let first_listener_event = block_on(network.listener_events.next()).unwrap();
+    network.check_new_peer_events(first_dialer_event, first_listener_event);
+    network
+}
+
+/// Sets up a 2 peer network whose connections go through the `network_simulator`, and verifies
+/// connectivity. The simulator and the peers run on `time_service`, which must be a mock.
+#[cfg(any(test, feature = "testing"))]
+pub fn setup_simulated_network(
+    network_simulator: &NetworkSimulator,
+    time_service: TimeService,
+) -> DummyNetwork {
+    let time = time_service.clone().into_mock();
+    let addr: NetworkAddress = "/memory/0".parse().unwrap();
+    let mut network = build_network(time_service, addr, |network_builder| {
+        network_builder.set_network_simulator(network_simulator.clone());
+    });
+
+    // Wait for establishing connection, the connectivity manager only dials as time passes
+    let first_dialer_event = network
+        .runtime
+        .block_on(next_event_with_mock_time(&mut network.dialer_events, &time));
+    let first_listener_event = network.runtime.block_on(next_event_with_mock_time(
+        &mut network.listener_events,
+        &time,
+    ));
+    network.check_new_peer_events(first_dialer_event, first_listener_event);
+    network
+}
+
+/// Waits for the next event, advancing the mock `time` by small steps while there is none.
+#[cfg(any(test, feature = "testing"))]
+pub async fn next_event_with_mock_time(
+    events: &mut DummyNetworkEvents,
+    time: &MockTimeService,
+) -> Event<DummyMsg> {
+    loop {
+        if let Ok(event) = tokio::time::timeout(Duration::from_millis(10), events.next()).await {
+            return event.expect("Network events stream ended");
+        }
+        time.advance_async(MOCK_TIME_STEP).await;
+    }
+}
+
+/// Builds and starts the listener, then the dialer seeded with the listener's address, both
+/// listening on `addr`. `configure_network` is applied to both builders before they're built.
+fn build_network<F>(
+    time_service: TimeService,
+    addr: NetworkAddress,
+    configure_network: F,
+) -> DummyNetwork
+where
+    F: Fn(&mut NetworkBuilder),
+{
     let runtime = Runtime::new().unwrap();
     let role = RoleType::Validator;
     let network_id = NetworkId::Validator;
@@ -125,10 +223,6 @@ pub fn setup_network() -> DummyNetwork {
     // Setup keys for listener.
     let listener_identity_private_key = x25519::PrivateKey::generate(&mut rng);
 
-    // Setup listen addresses
-    let dialer_addr: NetworkAddress = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
-    let listener_addr: NetworkAddress = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
-
     // Setup seed peers
     let mut seeds = PeerSet::new();
     seeds.insert(
@@ -150,20 +244,21 @@ pub fn setup_network() -> DummyNetwork {
         seeds.clone(),
         trusted_peers,
         network_context,
-        TimeService::real(),
-        listener_addr,
+        time_service.clone(),
+        addr.clone(),
         authentication_mode,
     );
+    configure_network(&mut network_builder);
 
-    let (listener_sender, mut listener_events) = network_builder
+    let (listener_sender, listener_events) = network_builder
         .add_protocol_handler::<DummyNetworkSender, DummyNetworkEvents>(network_endpoint_config());
     network_builder.build(runtime.handle().clone()).start();
 
     // Add the listener address with port
-    let listener_addr = network_builder.listen_address();
+    let listener_address = network_builder.listen_address();
     seeds.insert(
         listener_peer_id,
-        Peer::from_addrs(PeerRole::Validator, vec![listener_addr]),
+        Peer::from_addrs(PeerRole::Validator, vec![listener_address.clone()]),
     );
 
     let authentication_mode = AuthenticationMode::Mutual(dialer_identity_private_key);
@@ -178,46 +273,25 @@ pub fn setup_network() -> DummyNetwork {
         seeds,
         trusted_peers,
         network_context,
-        TimeService::real(),
-        dialer_addr,
+        time_service,
+        addr,
         authentication_mode,
     );
+    configure_network(&mut network_builder);
 
-    let (dialer_sender, mut dialer_events) = network_builder
+    let (dialer_sender, dialer_events) = network_builder
         .add_protocol_handler::<DummyNetworkSender, DummyNetworkEvents>(network_endpoint_config());
     network_builder.build(runtime.handle().clone()).start();
-
-    // Wait for establishing connection
-    let first_dialer_event = block_on(dialer_events.next()).unwrap();
-    if let Event::NewPeer(metadata) = first_dialer_event {
-        assert_eq!(metadata.remote_peer_id, listener_peer_id);
-        assert_eq!(metadata.origin, ConnectionOrigin::Outbound);
-        assert_eq!(metadata.role, PeerRole::Validator);
-    } else {
-        panic!(
-            "No NewPeer event on dialer received instead: {:?}",
-            first_dialer_event
-        );
-    }
-
-    let first_listener_event = block_on(listener_events.next()).unwrap();
-    if let Event::NewPeer(metadata) = first_listener_event {
-        assert_eq!(metadata.remote_peer_id, dialer_peer_id);
-        assert_eq!(metadata.origin, ConnectionOrigin::Inbound);
-        assert_eq!(metadata.role, PeerRole::Validator);
-    } else {
-        panic!(
-            "No NewPeer event on listener received instead: {:?}",
-            first_listener_event
-        );
-    }
+    let dialer_address = network_builder.listen_address();
 
     DummyNetwork {
         runtime,
         dialer_peer_id,
+        dialer_address,
         dialer_events,
         dialer_sender,
         listener_peer_id,
+        listener_address,
         listener_events,
         listener_sender,
     }
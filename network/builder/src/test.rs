@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Integration tests for validator_network.
-use crate::dummy::{setup_network, DummyMsg};
+use crate::dummy::{next_event_with_mock_time, setup_network, setup_simulated_network, DummyMsg};
+use diem_time_service::TimeService;
+use diem_types::network_address::{parse_memory, NetworkAddress};
 use futures::{future::join, StreamExt};
+use netcore::transport::simulated::{LinkConfig, NetworkSimulator};
 use network::protocols::network::Event;
 use std::time::Duration;
 
@@ -106,3 +109,67 @@ fn test_rpc() {
     let (res_msg, _) = tn.runtime.block_on(join(f_send, f_respond));
     assert_eq!(res_msg.unwrap(), msg);
 }
+
+#[test]
+fn test_simulated_partition_and_heal() {
+    ::diem_logger::Logger::init_for_testing();
+    let time_service = TimeService::mock();
+    let time = time_service.clone().into_mock();
+    let simulator = NetworkSimulator::new(time_service.clone(), 0, LinkConfig::default());
+    let start = time.now();
+    let tn = setup_simulated_network(&simulator, time_service);
+    let dialer_peer_id = tn.dialer_peer_id;
+    let mut dialer_events = tn.dialer_events;
+    let mut dialer_sender = tn.dialer_sender;
+    let listener_peer_id = tn.listener_peer_id;
+    let mut listener_events = tn.listener_events;
+    let mut listener_sender = tn.listener_sender;
+
+    // Partition the peers for the next 10 seconds
+    let memory_port = |addr: &NetworkAddress| parse_memory(addr.as_slice()).unwrap().0;
+    let partition_start = time.now().duration_since(start);
+    let partition_end = partition_start + Duration::from_secs(10);
+    simulator.partition(
+        &[memory_port(&tn.dialer_address)],
+        &[memory_port(&tn.listener_address)],
+        partition_start,
+        partition_end,
+    );
+
+    let msg = DummyMsg(vec![1, 2, 3]);
+    dialer_sender
+        .send_to(listener_peer_id, msg.clone())
+        .unwrap();
+
+    // The message is held back until the partition heals
+    let msg_clone = msg.clone();
+    tn.runtime.block_on(async {
+        time.advance_async(Duration::from_secs(9)).await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), listener_events.next())
+                .await
+                .is_err()
+        );
+
+        match next_event_with_mock_time(&mut listener_events, &time).await {
+            Event::Message(peer_id, incoming_msg) => {
+                assert_eq!(peer_id, dialer_peer_id);
+                assert_eq!(incoming_msg, msg_clone);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+        assert!(time.now().duration_since(start) >= partition_end);
+    });
+
+    // Once healed, messages go through without waiting
+    listener_sender
+        .send_to(dialer_peer_id, msg.clone())
+        .unwrap();
+    match tn.runtime.block_on(dialer_events.next()).unwrap() {
+        Event::Message(peer_id, incoming_msg) => {
+            assert_eq!(peer_id, listener_peer_id);
+            assert_eq!(incoming_msg, msg);
+        }
+        event => panic!("Unexpected event {:?}", event),
+    }
+}
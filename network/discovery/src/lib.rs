@@ -1,9 +1,17 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters::DISCOVERY_COUNTS, file::FileStream, validator_set::ValidatorSetStream};
+use crate::{
+    counters::DISCOVERY_COUNTS,
+    file::FileStream,
+    peer_exchange::{PeerExchangeNetworkEvents, PeerExchangeNetworkSender, PeerExchangeStream},
+    validator_set::ValidatorSetStream,
+};
 use channel::{diem_channel, diem_channel::Receiver};
-use diem_config::{config::PeerSet, network_id::NetworkContext};
+use diem_config::{
+    config::{PeerExchangeConfig, PeerSet},
+    network_id::NetworkContext,
+};
 use diem_crypto::x25519;
 use diem_logger::prelude::*;
 use diem_network_address_encryption::Encryptor;
@@ -28,6 +36,7 @@ use tokio::runtime::Handle;
 
 mod counters;
 mod file;
+pub mod peer_exchange;
 mod validator_set;
 
 #[derive(Debug)]
@@ -47,6 +56,7 @@ pub struct DiscoveryChangeListener {
 enum DiscoveryChangeStream {
     ValidatorSet(ValidatorSetStream),
     File(FileStream),
+    PeerExchange(PeerExchangeStream),
 }
 
 impl Stream for DiscoveryChangeStream {
@@ -56,6 +66,7 @@ impl Stream for DiscoveryChangeStream {
         match self.get_mut() {
             Self::ValidatorSet(stream) => Pin::new(stream).poll_next(cx),
             Self::File(stream) => Pin::new(stream).poll_next(cx),
+            Self::PeerExchange(stream) => Pin::new(stream).poll_next(cx),
         }
     }
 }
@@ -102,6 +113,31 @@ impl DiscoveryChangeListener {
         }
     }
 
+    pub fn peer_exchange(
+        network_context: Arc<NetworkContext>,
+        update_channel: channel::Sender<ConnectivityRequest>,
+        identity_key: &x25519::PrivateKey,
+        config: &PeerExchangeConfig,
+        network_tx: PeerExchangeNetworkSender,
+        network_rx: PeerExchangeNetworkEvents,
+        time_service: TimeService,
+    ) -> Self {
+        let source_stream = DiscoveryChangeStream::PeerExchange(PeerExchangeStream::new(
+            network_context.clone(),
+            identity_key,
+            config,
+            network_tx,
+            network_rx,
+            time_service,
+        ));
+        DiscoveryChangeListener {
+            discovery_source: DiscoverySource::PeerExchange,
+            network_context,
+            update_channel,
+            source_stream,
+        }
+    }
+
     pub fn start(self, executor: &Handle) {
         executor.spawn(Box::pin(self).run());
     }
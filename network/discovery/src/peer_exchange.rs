@@ -0,0 +1,696 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Discovery of public peers through peer exchange.
+//!
+//! Every `interval_ms`, a node signs the list of the public peers it knows to be reachable and
+//! sends it to all of its connected peers. The list holds the node itself at its
+//! `advertised_addresses`, and the peers it dialed, at the addresses it dialed them at. The lists
+//! received are merged into the peers discovered, which are handed to the `ConnectivityManager`
+//! as the lowest priority [`DiscoverySource`].
+//!
+//! Lists are signed with an Ed25519 key derived from the network identity key of the sender. The
+//! first list of a connection pins the signing key of the sender, and the lists signed by other
+//! keys or replayed are rejected. Only the addresses whose noise key derives the advertised
+//! `PeerId` are accepted, so a peer can't be hijacked by advertising addresses with another key.
+//!
+//! A node is exposed to the peers it is connected to, so the peer exchange is capped: lists over
+//! `max_peers_per_list` are rejected, at most `max_discovered_peers` are discovered, and at most
+//! `max_changes_per_interval` new peers or address updates are accepted per interval.
+//!
+//! [`DiscoverySource`]: network::connectivity_manager::DiscoverySource
+
+use crate::{counters::DISCOVERY_COUNTS, DiscoveryError};
+use anyhow::{ensure, format_err};
+use channel::message_queues::QueueStyle;
+use diem_config::{
+    config::{Peer, PeerExchangeConfig, PeerRole, PeerSet, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::HashValue,
+    x25519, PrivateKey, Signature, SigningKey, ValidCryptoMaterial,
+};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_logger::prelude::*;
+use diem_metrics::IntCounterVec;
+use diem_time_service::{Interval, TimeService, TimeServiceTrait};
+use diem_types::{
+    account_address::from_identity_public_key, network_address::NetworkAddress, PeerId,
+};
+use futures::{Stream, StreamExt};
+use netcore::transport::ConnectionOrigin;
+use network::{
+    counters::inc_by_with_context,
+    error::NetworkError,
+    logging::NetworkSchema,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    peer_reputation::ReputationEvent,
+    protocols::network::{Event, NetworkEvents, NetworkSender, NewNetworkSender},
+    transport::ConnectionMetadata,
+    ProtocolId,
+};
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Domain separator of the derivation of the signing key from the identity key.
+const SIGNING_KEY_SALT: &[u8] = b"DIEM_PEER_EXCHANGE_SIGNING_KEY";
+/// Addresses of a peer beyond this are ignored.
+const MAX_ADDRESSES_PER_PEER: usize = 4;
+
+/// Messages of the peer exchange protocol.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PeerExchangeMsg {
+    PeerList(SignedPeerList),
+}
+
+/// The public peers a node knows to be reachable.
+#[derive(Clone, Debug, Deserialize, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct PeerList {
+    /// The node that signed the list, it must also be the one sending it.
+    pub signer: PeerId,
+    /// Strictly increasing for a signer, lists that are not newer than the last one are replays.
+    pub timestamp_usecs: u64,
+    pub peers: Vec<(PeerId, Vec<NetworkAddress>)>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SignedPeerList {
+    pub list: PeerList,
+    pub public_key: Ed25519PublicKey,
+    pub signature: Ed25519Signature,
+}
+
+/// Configuration for the network endpoints to support peer exchange.
+pub fn network_endpoint_config() -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![],
+        vec![ProtocolId::DiscoveryDirectSend],
+        // Only the latest list of a peer matters
+        QueueStyle::LIFO,
+        1,
+        None,
+    )
+}
+
+pub type PeerExchangeNetworkEvents = NetworkEvents<PeerExchangeMsg>;
+
+#[derive(Clone)]
+pub struct PeerExchangeNetworkSender {
+    inner: NetworkSender<PeerExchangeMsg>,
+}
+
+impl NewNetworkSender for PeerExchangeNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl PeerExchangeNetworkSender {
+    pub fn send_to_many(
+        &mut self,
+        recipients: impl Iterator<Item = PeerId>,
+        message: PeerExchangeMsg,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send_to_many(recipients, ProtocolId::DiscoveryDirectSend, message)
+    }
+
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        event: ReputationEvent,
+    ) -> Result<(), NetworkError> {
+        self.inner.report_peer(peer, event)
+    }
+}
+
+/// Derives the key signing the peer lists, it's stable across restarts of the node.
+pub fn signing_key(identity_key: &x25519::PrivateKey) -> Ed25519PrivateKey {
+    let seed = HashValue::sha3_256_of(&[SIGNING_KEY_SALT, &identity_key.to_bytes()].concat());
+    Ed25519PrivateKey::try_from(seed.to_vec().as_slice()).expect("Any 32 bytes are a valid seed")
+}
+
+struct ConnectedPeer {
+    metadata: ConnectionMetadata,
+    /// Signing key and timestamp of the last list received on this connection
+    last_list: Option<(Ed25519PublicKey, u64)>,
+}
+
+struct DiscoveredPeer {
+    addresses: Vec<NetworkAddress>,
+    last_seen: Instant,
+}
+
+pub struct PeerExchangeStream {
+    network_context: Arc<NetworkContext>,
+    config: PeerExchangeConfig,
+    time_service: TimeService,
+    signing_key: Ed25519PrivateKey,
+    advertised_addresses: Vec<NetworkAddress>,
+    network_tx: PeerExchangeNetworkSender,
+    network_rx: PeerExchangeNetworkEvents,
+    interval: Pin<Box<Interval>>,
+    connected: HashMap<PeerId, ConnectedPeer>,
+    discovered: HashMap<PeerId, DiscoveredPeer>,
+    /// Peers added or updated since the last interval
+    changes: usize,
+}
+
+impl PeerExchangeStream {
+    pub(crate) fn new(
+        network_context: Arc<NetworkContext>,
+        identity_key: &x25519::PrivateKey,
+        config: &PeerExchangeConfig,
+        network_tx: PeerExchangeNetworkSender,
+        network_rx: PeerExchangeNetworkEvents,
+        time_service: TimeService,
+    ) -> Self {
+        let pubkey = identity_key.public_key();
+        let advertised_addresses = config
+            .advertised_addresses
+            .iter()
+            .map(|addr| addr.clone().append_prod_protos(pubkey, HANDSHAKE_VERSION))
+            .collect();
+        PeerExchangeStream {
+            network_context,
+            config: config.clone(),
+            interval: Box::pin(time_service.interval(Duration::from_millis(config.interval_ms))),
+            time_service,
+            signing_key: signing_key(identity_key),
+            advertised_addresses,
+            network_tx,
+            network_rx,
+            connected: HashMap::new(),
+            discovered: HashMap::new(),
+            changes: 0,
+        }
+    }
+
+    fn handle_network_event(&mut self, event: Event<PeerExchangeMsg>) {
+        match event {
+            Event::NewPeer(metadata) => {
+                self.connected.insert(
+                    metadata.remote_peer_id,
+                    ConnectedPeer {
+                        metadata,
+                        last_list: None,
+                    },
+                );
+            }
+            Event::LostPeer(metadata) => {
+                self.connected.remove(&metadata.remote_peer_id);
+            }
+            Event::Message(peer_id, PeerExchangeMsg::PeerList(signed_list)) => {
+                self.handle_peer_list(peer_id, signed_list)
+            }
+            Event::RpcRequest(peer_id, msg, _) => {
+                debug!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    "{} Unexpected peer exchange rpc from {}: {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    msg
+                );
+            }
+        }
+    }
+
+    fn handle_peer_list(&mut self, peer_id: PeerId, signed_list: SignedPeerList) {
+        inc_by_with_context(
+            &DISCOVERY_COUNTS,
+            &self.network_context,
+            "peer_exchange_received",
+            1,
+        );
+        if let Err(error) = self.verify_peer_list(peer_id, &signed_list) {
+            inc_by_with_context(
+                &DISCOVERY_COUNTS,
+                &self.network_context,
+                "peer_exchange_invalid",
+                1,
+            );
+            warn!(
+                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                error = %error,
+                "{} Invalid peer list from {}", self.network_context, peer_id.short_str()
+            );
+            if let Err(error) = self
+                .network_tx
+                .report_peer(peer_id, ReputationEvent::InvalidMessage)
+            {
+                warn!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    error = %error,
+                    "{} Failed to report peer {}", self.network_context, peer_id.short_str()
+                );
+            }
+            return;
+        }
+        self.merge_peer_list(signed_list.list);
+    }
+
+    fn verify_peer_list(
+        &mut self,
+        peer_id: PeerId,
+        signed_list: &SignedPeerList,
+    ) -> anyhow::Result<()> {
+        let list = &signed_list.list;
+        ensure!(
+            list.signer == peer_id,
+            "List signed by another peer {}",
+            list.signer
+        );
+        ensure!(
+            list.peers.len() <= self.config.max_peers_per_list,
+            "List of {} peers is over the limit of {}",
+            list.peers.len(),
+            self.config.max_peers_per_list
+        );
+        let connected_peer = self
+            .connected
+            .get_mut(&peer_id)
+            .ok_or_else(|| format_err!("Peer is not connected"))?;
+        if let Some((public_key, timestamp_usecs)) = &connected_peer.last_list {
+            ensure!(
+                public_key == &signed_list.public_key,
+                "List signed by another key"
+            );
+            ensure!(
+                list.timestamp_usecs > *timestamp_usecs,
+                "Replayed list from {}us",
+                list.timestamp_usecs
+            );
+        }
+        signed_list
+            .signature
+            .verify(list, &signed_list.public_key)?;
+        connected_peer.last_list = Some((signed_list.public_key.clone(), list.timestamp_usecs));
+        Ok(())
+    }
+
+    fn merge_peer_list(&mut self, list: PeerList) {
+        let now = self.time_service.now();
+        let own_peer_id = self.network_context.peer_id();
+        let mut limited = 0;
+        for (peer_id, addresses) in list.peers {
+            if peer_id == own_peer_id {
+                continue;
+            }
+            let addresses: Vec<_> = addresses
+                .into_iter()
+                .filter(|addr| is_valid_address(peer_id, addr))
+                .take(MAX_ADDRESSES_PER_PEER)
+                .collect();
+            if addresses.is_empty() {
+                continue;
+            }
+
+            let discovered_peer = self.discovered.get_mut(&peer_id);
+            let is_new = discovered_peer.is_none();
+            if let Some(discovered_peer) = discovered_peer {
+                if discovered_peer.addresses == addresses {
+                    discovered_peer.last_seen = now;
+                    continue;
+                }
+            }
+            if self.changes >= self.config.max_changes_per_interval
+                || (is_new && self.discovered.len() >= self.config.max_discovered_peers)
+            {
+                limited += 1;
+                continue;
+            }
+            self.changes += 1;
+            self.discovered.insert(
+                peer_id,
+                DiscoveredPeer {
+                    addresses,
+                    last_seen: now,
+                },
+            );
+        }
+        if limited > 0 {
+            inc_by_with_context(
+                &DISCOVERY_COUNTS,
+                &self.network_context,
+                "peer_exchange_limited",
+                limited,
+            );
+        }
+    }
+
+    /// The node itself and the peers it dialed, which are known to be reachable.
+    fn reachable_peers(&self) -> Vec<(PeerId, Vec<NetworkAddress>)> {
+        let own_peer = if self.advertised_addresses.is_empty() {
+            None
+        } else {
+            Some((
+                self.network_context.peer_id(),
+                self.advertised_addresses.clone(),
+            ))
+        };
+        let dialed_peers = self
+            .connected
+            .values()
+            .filter(|peer| peer.metadata.origin == ConnectionOrigin::Outbound)
+            .map(|peer| {
+                (
+                    peer.metadata.remote_peer_id,
+                    vec![peer.metadata.addr.clone()],
+                )
+            });
+        own_peer
+            .into_iter()
+            .chain(dialed_peers)
+            .take(self.config.max_peers_per_list)
+            .collect()
+    }
+
+    fn broadcast_peer_list(&mut self) {
+        if self.connected.is_empty() {
+            return;
+        }
+        let list = PeerList {
+            signer: self.network_context.peer_id(),
+            timestamp_usecs: self.time_service.now_unix_time().as_micros() as u64,
+            peers: self.reachable_peers(),
+        };
+        let msg = PeerExchangeMsg::PeerList(SignedPeerList {
+            signature: self.signing_key.sign(&list),
+            public_key: self.signing_key.public_key(),
+            list,
+        });
+        let recipients: Vec<_> = self.connected.keys().copied().collect();
+        let num_recipients = recipients.len() as u64;
+        if let Err(error) = self.network_tx.send_to_many(recipients.into_iter(), msg) {
+            warn!(
+                NetworkSchema::new(&self.network_context),
+                error = %error,
+                "{} Failed to send peer list", self.network_context
+            );
+        } else {
+            inc_by_with_context(
+                &DISCOVERY_COUNTS,
+                &self.network_context,
+                "peer_exchange_sent",
+                num_recipients,
+            );
+        }
+    }
+
+    /// Forgets the expired peers, and returns the peers left.
+    fn discovered_peers(&mut self) -> PeerSet {
+        let now = self.time_service.now();
+        let expiry = Duration::from_millis(self.config.expiry_ms);
+        self.discovered
+            .retain(|_, peer| now.saturating_duration_since(peer.last_seen) < expiry);
+        self.discovered
+            .iter()
+            .map(|(peer_id, peer)| {
+                (
+                    *peer_id,
+                    Peer::from_addrs(PeerRole::Unknown, peer.addresses.clone()),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Only addresses that can be dialed and authenticate as the peer are accepted.
+fn is_valid_address(peer_id: PeerId, addr: &NetworkAddress) -> bool {
+    addr.is_diemnet_addr()
+        && addr
+            .find_noise_proto()
+            .map_or(false, |pubkey| from_identity_public_key(pubkey) == peer_id)
+}
+
+impl Stream for PeerExchangeStream {
+    type Item = Result<PeerSet, DiscoveryError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Process the received lists before waiting for the next interval
+        loop {
+            match self.network_rx.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => self.handle_network_event(event),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => break,
+            }
+        }
+        futures::ready!(self.interval.as_mut().poll_next(cx));
+
+        self.broadcast_peer_list();
+        self.changes = 0;
+        Poll::Ready(Some(Ok(self.discovered_peers())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use channel::diem_channel;
+    use diem_crypto::{test_utils::TEST_SEED, Uniform};
+    use futures::executor::block_on;
+    use network::{
+        peer_manager::{conn_notifs_channel, ConnectionRequest, PeerManagerRequest},
+        protocols::network::NewNetworkEvents,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::str::FromStr;
+
+    struct TestPeer {
+        peer_id: PeerId,
+        identity_key: x25519::PrivateKey,
+    }
+
+    impl TestPeer {
+        fn new(rng: &mut StdRng) -> Self {
+            let identity_key = x25519::PrivateKey::generate(rng);
+            TestPeer {
+                peer_id: from_identity_public_key(identity_key.public_key()),
+                identity_key,
+            }
+        }
+
+        fn addr(&self) -> NetworkAddress {
+            NetworkAddress::from_str("/ip4/1.2.3.4/tcp/6180")
+                .unwrap()
+                .append_prod_protos(self.identity_key.public_key(), HANDSHAKE_VERSION)
+        }
+
+        fn sign(&self, timestamp_usecs: u64, peers: &[&TestPeer]) -> PeerExchangeMsg {
+            let list = PeerList {
+                signer: self.peer_id,
+                timestamp_usecs,
+                peers: peers
+                    .iter()
+                    .map(|peer| (peer.peer_id, vec![peer.addr()]))
+                    .collect(),
+            };
+            let signing_key = signing_key(&self.identity_key);
+            PeerExchangeMsg::PeerList(SignedPeerList {
+                signature: signing_key.sign(&list),
+                public_key: signing_key.public_key(),
+                list,
+            })
+        }
+    }
+
+    fn create_stream(
+        rng: &mut StdRng,
+        config: PeerExchangeConfig,
+    ) -> (
+        PeerExchangeStream,
+        diem_channel::Receiver<PeerId, ConnectionRequest>,
+    ) {
+        let (peer_mgr_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let (connection_reqs_tx, connection_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let (_, peer_mgr_notifs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let (_, connection_notifs_rx) = conn_notifs_channel::new();
+        let stream = PeerExchangeStream::new(
+            NetworkContext::mock(),
+            &x25519::PrivateKey::generate(rng),
+            &config,
+            PeerExchangeNetworkSender::new(
+                PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+                ConnectionRequestSender::new(connection_reqs_tx),
+            ),
+            PeerExchangeNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx),
+            TimeService::mock(),
+        );
+        (stream, connection_reqs_rx)
+    }
+
+    fn connect(stream: &mut PeerExchangeStream, peer: &TestPeer) {
+        stream.handle_network_event(Event::NewPeer(
+            ConnectionMetadata::mock_with_role_and_origin(
+                peer.peer_id,
+                PeerRole::Unknown,
+                ConnectionOrigin::Outbound,
+            ),
+        ));
+    }
+
+    #[test]
+    fn test_merge_peer_list() {
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let (mut stream, _) = create_stream(&mut rng, PeerExchangeConfig::default());
+        let sender = TestPeer::new(&mut rng);
+        let other = TestPeer::new(&mut rng);
+        connect(&mut stream, &sender);
+
+        // An address with a key that doesn't match the peer is dropped
+        let hijacked = TestPeer {
+            peer_id: PeerId::random(),
+            identity_key: x25519::PrivateKey::generate(&mut rng),
+        };
+        stream.handle_network_event(Event::Message(
+            sender.peer_id,
+            sender.sign(1, &[&sender, &other, &hijacked]),
+        ));
+        let peers = stream.discovered_peers();
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[&other.peer_id].addresses, vec![other.addr()]);
+        assert!(!peers.contains_key(&hijacked.peer_id));
+
+        // Peers not shared anymore expire
+        stream
+            .time_service
+            .clone()
+            .into_mock()
+            .advance(Duration::from_millis(stream.config.expiry_ms));
+        assert!(stream.discovered_peers().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_peer_list() {
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let (mut stream, mut connection_reqs_rx) = create_stream(
+            &mut rng,
+            PeerExchangeConfig {
+                max_peers_per_list: 1,
+                ..PeerExchangeConfig::default()
+            },
+        );
+        let sender = TestPeer::new(&mut rng);
+        let other = TestPeer::new(&mut rng);
+        connect(&mut stream, &sender);
+
+        let PeerExchangeMsg::PeerList(mut tampered) = sender.sign(1, &[&sender]);
+        tampered.list.timestamp_usecs += 1;
+        let invalid_lists = vec![
+            // Sent by another peer
+            (sender.peer_id, other.sign(1, &[&other])),
+            // Over the size limit
+            (sender.peer_id, sender.sign(1, &[&sender, &other])),
+            // Tampered with after being signed
+            (sender.peer_id, PeerExchangeMsg::PeerList(tampered)),
+        ];
+        for (peer_id, msg) in invalid_lists {
+            stream.handle_network_event(Event::Message(peer_id, msg));
+            match block_on(connection_reqs_rx.next()) {
+                Some(ConnectionRequest::ReportPeer(reported, ReputationEvent::InvalidMessage)) => {
+                    assert_eq!(reported, sender.peer_id)
+                }
+                request => panic!("Unexpected request {:?}", request),
+            }
+        }
+
+        // Replayed list
+        stream.handle_network_event(Event::Message(sender.peer_id, sender.sign(2, &[&sender])));
+        stream.handle_network_event(Event::Message(sender.peer_id, sender.sign(2, &[&sender])));
+        assert!(matches!(
+            block_on(connection_reqs_rx.next()),
+            Some(ConnectionRequest::ReportPeer(
+                _,
+                ReputationEvent::InvalidMessage
+            ))
+        ));
+
+        // Signed by another key than the first list of the connection
+        let PeerExchangeMsg::PeerList(signed_list) = sender.sign(3, &[&sender]);
+        let other_key = signing_key(&other.identity_key);
+        let msg = PeerExchangeMsg::PeerList(SignedPeerList {
+            signature: other_key.sign(&signed_list.list),
+            public_key: other_key.public_key(),
+            list: signed_list.list,
+        });
+        stream.handle_network_event(Event::Message(sender.peer_id, msg));
+        assert!(matches!(
+            block_on(connection_reqs_rx.next()),
+            Some(ConnectionRequest::ReportPeer(
+                _,
+                ReputationEvent::InvalidMessage
+            ))
+        ));
+        assert_eq!(stream.discovered_peers().len(), 1);
+    }
+
+    #[test]
+    fn test_churn_limit() {
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let (mut stream, _) = create_stream(
+            &mut rng,
+            PeerExchangeConfig {
+                max_changes_per_interval: 2,
+                max_discovered_peers: 3,
+                ..PeerExchangeConfig::default()
+            },
+        );
+        let sender = TestPeer::new(&mut rng);
+        connect(&mut stream, &sender);
+        let peers: Vec<_> = (0..4).map(|_| TestPeer::new(&mut rng)).collect();
+        let peer_refs: Vec<_> = peers.iter().collect();
+
+        stream.handle_network_event(Event::Message(sender.peer_id, sender.sign(1, &peer_refs)));
+        assert_eq!(stream.discovered_peers().len(), 2);
+
+        // The next interval, only one more fits in the discovered peers
+        stream.changes = 0;
+        stream.handle_network_event(Event::Message(sender.peer_id, sender.sign(2, &peer_refs)));
+        assert_eq!(stream.discovered_peers().len(), 3);
+    }
+
+    #[test]
+    fn test_reachable_peers() {
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let (mut stream, _) = create_stream(
+            &mut rng,
+            PeerExchangeConfig {
+                advertised_addresses: vec![
+                    NetworkAddress::from_str("/dns/example.com/tcp/6182").unwrap()
+                ],
+                ..PeerExchangeConfig::default()
+            },
+        );
+        let dialed = TestPeer::new(&mut rng);
+        connect(&mut stream, &dialed);
+        let inbound = PeerId::random();
+        stream.handle_network_event(Event::NewPeer(ConnectionMetadata::mock(inbound)));
+
+        let peers = stream.reachable_peers();
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].0, stream.network_context.peer_id());
+        assert!(peers[0].1[0].is_diemnet_addr());
+        assert_eq!(peers[1].0, dialed.peer_id);
+    }
+}
@@ -21,6 +21,8 @@ pub mod boxed;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 pub mod memory;
 pub mod proxy_protocol;
+#[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+pub mod simulated;
 pub mod tcp;
 
 /// Origin of how a Connection was established.
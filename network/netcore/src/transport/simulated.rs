@@ -0,0 +1,689 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Simulated transport for deterministic network tests.
+//!
+//! A [`NetworkSimulator`] connects the [`SimulatedTransport`]s of in-process nodes, like the
+//! [`MemoryTransport`](crate::transport::memory::MemoryTransport) does, but over links whose
+//! latency, bandwidth and loss are programmable per pair of nodes and can change over time,
+//! including timed partitions. Every delay is driven by the [`TimeService`] of the simulator and
+//! every loss is drawn from its seeded rng, so a test using a mock [`TimeService`] on a single
+//! threaded executor replays deterministically.
+//!
+//! Nodes are identified by the port of their `/memory/<port>` listening address. Like tcp, the
+//! connections are reliable byte streams: a lost write is retransmitted after the
+//! `retransmit_timeout` rather than dropped, and a write over a partitioned link is delivered
+//! once the partition heals. Dialing across a partition fails.
+
+use crate::transport::Transport;
+use bytes::{Buf, Bytes};
+use diem_infallible::Mutex;
+use diem_time_service::{Sleep, TimeService, TimeServiceTrait};
+use diem_types::{
+    network_address::{parse_memory, NetworkAddress, Protocol},
+    PeerId,
+};
+use futures::{
+    channel::mpsc,
+    future::{self, Future},
+    io::{AsyncRead, AsyncWrite},
+    stream::Stream,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    cmp::{max, min},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt, io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+/// Writes are split in chunks of at most this size, for the bandwidth to spread them over time.
+const MAX_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The properties of a one-way link between two nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinkConfig {
+    /// Delay of every write over the link.
+    pub latency: Duration,
+    /// Bytes per second the link can carry, unlimited if `None`.
+    pub bandwidth: Option<u64>,
+    /// Probability, below 1, that a write is lost and has to be retransmitted.
+    pub loss: f64,
+    /// Delay before a lost write is retransmitted.
+    pub retransmit_timeout: Duration,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            latency: Duration::from_millis(0),
+            bandwidth: None,
+            loss: 0.0,
+            retransmit_timeout: Duration::from_millis(200),
+        }
+    }
+}
+
+struct Partition {
+    group_a: HashSet<u16>,
+    group_b: HashSet<u16>,
+    start: Instant,
+    end: Instant,
+}
+
+impl Partition {
+    fn separates(&self, from: u16, to: u16) -> bool {
+        (self.group_a.contains(&from) && self.group_b.contains(&to))
+            || (self.group_b.contains(&from) && self.group_a.contains(&to))
+    }
+}
+
+struct Inner {
+    /// Time at which the simulation started, the schedules are relative to it.
+    start: Instant,
+    rng: StdRng,
+    default_link: LinkConfig,
+    /// The successive configs of every one-way link, by the time they take effect.
+    links: HashMap<(u16, u16), BTreeMap<Instant, LinkConfig>>,
+    partitions: Vec<Partition>,
+    listeners: HashMap<u16, mpsc::UnboundedSender<SimulatedSocket>>,
+    next_port: u16,
+}
+
+impl Inner {
+    fn link_config(&self, from: u16, to: u16, at: Instant) -> LinkConfig {
+        self.links
+            .get(&(from, to))
+            .and_then(|schedule| schedule.range(..=at).next_back())
+            .map_or(self.default_link, |(_, config)| *config)
+    }
+
+    /// Returns the end of the partitions separating the nodes at `at`, if any.
+    fn partitioned_until(&self, from: u16, to: u16, at: Instant) -> Option<Instant> {
+        let mut until = None;
+        let mut at = at;
+        // Partitions can follow each other
+        while let Some(end) = self
+            .partitions
+            .iter()
+            .filter(|p| p.separates(from, to) && p.start <= at && at < p.end)
+            .map(|p| p.end)
+            .max()
+        {
+            until = Some(end);
+            at = end;
+        }
+        until
+    }
+
+    /// Returns when the chunk written at `now` is delivered over the link.
+    fn delivery_time(
+        &mut self,
+        from: u16,
+        to: u16,
+        pipe: &mut Pipe,
+        now: Instant,
+        len: usize,
+    ) -> Instant {
+        // The link transmits one chunk at a time, and nothing while partitioned
+        let mut send_at = max(now, pipe.busy_until);
+        if let Some(end) = self.partitioned_until(from, to, send_at) {
+            send_at = end;
+        }
+        let config = self.link_config(from, to, send_at);
+        let transmission = config
+            .bandwidth
+            .map_or(Duration::from_secs(0), |bandwidth| {
+                Duration::from_secs_f64(len as f64 / bandwidth as f64)
+            });
+        pipe.busy_until = send_at + transmission;
+
+        let mut deliver_at = pipe.busy_until + config.latency;
+        while config.loss > 0.0 && self.rng.gen_bool(config.loss) {
+            deliver_at += config.retransmit_timeout;
+        }
+        // The stream is delivered in order
+        deliver_at = max(deliver_at, pipe.last_delivery);
+        pipe.last_delivery = deliver_at;
+        deliver_at
+    }
+}
+
+/// The network connecting the [`SimulatedTransport`]s of a simulation.
+#[derive(Clone)]
+pub struct NetworkSimulator {
+    inner: Arc<Mutex<Inner>>,
+    time_service: TimeService,
+}
+
+impl NetworkSimulator {
+    /// Creates a network whose links all have the `default_link` config, until changed. The
+    /// losses are drawn from an rng seeded with `seed`.
+    pub fn new(time_service: TimeService, seed: u64, default_link: LinkConfig) -> Self {
+        assert!(default_link.loss < 1.0, "loss must be below 1");
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                start: time_service.now(),
+                rng: StdRng::seed_from_u64(seed),
+                default_link,
+                links: HashMap::new(),
+                partitions: Vec::new(),
+                listeners: HashMap::new(),
+                next_port: 1,
+            })),
+            time_service,
+        }
+    }
+
+    /// Changes the link from the node `from` to the node `to` to `config`, `at` the given time
+    /// since the start of the simulation.
+    pub fn set_link(&self, from: u16, to: u16, at: Duration, config: LinkConfig) {
+        assert!(config.loss < 1.0, "loss must be below 1");
+        let mut inner = self.inner.lock();
+        let at = inner.start + at;
+        inner
+            .links
+            .entry((from, to))
+            .or_insert_with(BTreeMap::new)
+            .insert(at, config);
+    }
+
+    /// Changes the links between the nodes `a` and `b`, both ways.
+    pub fn set_links_between(&self, a: u16, b: u16, at: Duration, config: LinkConfig) {
+        self.set_link(a, b, at, config);
+        self.set_link(b, a, at, config);
+    }
+
+    /// Partitions the nodes of `group_a` from the nodes of `group_b` between the given times
+    /// since the start of the simulation.
+    pub fn partition(&self, group_a: &[u16], group_b: &[u16], start: Duration, end: Duration) {
+        let mut inner = self.inner.lock();
+        let partition = Partition {
+            group_a: group_a.iter().copied().collect(),
+            group_b: group_b.iter().copied().collect(),
+            start: inner.start + start,
+            end: inner.start + end,
+        };
+        inner.partitions.push(partition);
+    }
+
+    /// A transport for a node of the network.
+    pub fn transport(&self) -> SimulatedTransport {
+        SimulatedTransport {
+            simulator: self.clone(),
+            local_port: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    fn connect(&self, from: u16, to: u16) -> io::Result<SimulatedSocket> {
+        let mut inner = self.inner.lock();
+        let now = self.time_service.now();
+        if inner.partitioned_until(from, to, now).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Port {} is partitioned from port {}", to, from),
+            ));
+        }
+        let listener = inner.listeners.get_mut(&to).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Port {} is not listening", to),
+            )
+        })?;
+
+        let outgoing = Arc::new(Mutex::new(Pipe::new(now)));
+        let incoming = Arc::new(Mutex::new(Pipe::new(now)));
+        let inbound =
+            SimulatedSocket::new(self.clone(), to, from, outgoing.clone(), incoming.clone());
+        listener.unbounded_send(inbound).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Port {} is no longer listening", to),
+            )
+        })?;
+        Ok(SimulatedSocket::new(
+            self.clone(),
+            from,
+            to,
+            incoming,
+            outgoing,
+        ))
+    }
+}
+
+/// Transport of a node of a [`NetworkSimulator`].
+#[derive(Clone)]
+pub struct SimulatedTransport {
+    simulator: NetworkSimulator,
+    /// The port the node listens on, identifying it in the links it dials.
+    local_port: Arc<Mutex<u16>>,
+}
+
+impl fmt::Debug for SimulatedTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimulatedTransport")
+            .field("local_port", &*self.local_port.lock())
+            .finish()
+    }
+}
+
+impl Transport for SimulatedTransport {
+    type Output = SimulatedSocket;
+    type Error = io::Error;
+    type Listener = Listener;
+    type Inbound = future::Ready<Result<Self::Output, Self::Error>>;
+    type Outbound = future::Ready<Result<Self::Output, Self::Error>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let port = match addr.as_slice() {
+            [Protocol::Memory(port)] => *port,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Unexpected listening network address: '{}', \
+                         expected format: '/memory/<port>'",
+                        addr
+                    ),
+                ))
+            }
+        };
+
+        let mut inner = self.simulator.inner.lock();
+        let port = if port == 0 {
+            while inner.listeners.contains_key(&inner.next_port) {
+                inner.next_port += 1;
+            }
+            inner.next_port
+        } else {
+            port
+        };
+        if inner.listeners.contains_key(&port) {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("Port {} is already in use", port),
+            ));
+        }
+        let (sockets_tx, sockets_rx) = mpsc::unbounded();
+        inner.listeners.insert(port, sockets_tx);
+        *self.local_port.lock() = port;
+
+        let listen_addr = NetworkAddress::from(Protocol::Memory(port));
+        Ok((Listener { sockets_rx }, listen_addr))
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let (port, _addr_suffix) = parse_memory(addr.as_slice()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unexpected dialing network address: '{}', \
+                     expected format: '/memory/<port>/..'",
+                    addr
+                ),
+            )
+        })?;
+        let local_port = *self.local_port.lock();
+        Ok(future::ready(self.simulator.connect(local_port, port)))
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct Listener {
+    sockets_rx: mpsc::UnboundedReceiver<SimulatedSocket>,
+}
+
+impl Stream for Listener {
+    type Item = io::Result<(future::Ready<io::Result<SimulatedSocket>>, NetworkAddress)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.sockets_rx).poll_next(context) {
+            Poll::Ready(Some(socket)) => {
+                // Like for the MemoryTransport, use port 0 to ensure the dialer address isn't
+                // used as an address to dial.
+                let dialer_addr = NetworkAddress::from(Protocol::Memory(0));
+                Poll::Ready(Some(Ok((future::ready(Ok(socket)), dialer_addr))))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// One direction of a connection: the chunks written, with the time they are delivered at.
+struct Pipe {
+    chunks: VecDeque<(Instant, Bytes)>,
+    /// The link is busy transmitting the previous chunks until then.
+    busy_until: Instant,
+    /// Delivery time of the last chunk written.
+    last_delivery: Instant,
+    reader_waker: Option<Waker>,
+    /// The writer closed its end.
+    closed: bool,
+    /// The reader dropped its end.
+    reader_dropped: bool,
+}
+
+impl Pipe {
+    fn new(now: Instant) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            busy_until: now,
+            last_delivery: now,
+            reader_waker: None,
+            closed: false,
+            reader_dropped: false,
+        }
+    }
+
+    fn wake_reader(&mut self) {
+        if let Some(waker) = self.reader_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A connection between two nodes of a [`NetworkSimulator`].
+pub struct SimulatedSocket {
+    simulator: NetworkSimulator,
+    local_port: u16,
+    remote_port: u16,
+    incoming: Arc<Mutex<Pipe>>,
+    outgoing: Arc<Mutex<Pipe>>,
+    /// Timer until the delivery of the next incoming chunk.
+    delivery_timer: Option<(Instant, Pin<Box<Sleep>>)>,
+}
+
+impl SimulatedSocket {
+    fn new(
+        simulator: NetworkSimulator,
+        local_port: u16,
+        remote_port: u16,
+        incoming: Arc<Mutex<Pipe>>,
+        outgoing: Arc<Mutex<Pipe>>,
+    ) -> Self {
+        Self {
+            simulator,
+            local_port,
+            remote_port,
+            incoming,
+            outgoing,
+            delivery_timer: None,
+        }
+    }
+}
+
+impl fmt::Debug for SimulatedSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimulatedSocket")
+            .field("local_port", &self.local_port)
+            .field("remote_port", &self.remote_port)
+            .finish()
+    }
+}
+
+impl AsyncRead for SimulatedSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let now = self.simulator.time_service.now();
+            let deadline = {
+                let mut incoming = self.incoming.lock();
+                match incoming.chunks.front_mut() {
+                    Some((deliver_at, chunk)) if *deliver_at <= now => {
+                        let len = min(buf.len(), chunk.len());
+                        buf[..len].copy_from_slice(&chunk[..len]);
+                        chunk.advance(len);
+                        if chunk.is_empty() {
+                            incoming.chunks.pop_front();
+                        }
+                        return Poll::Ready(Ok(len));
+                    }
+                    Some((deliver_at, _)) => {
+                        let deliver_at = *deliver_at;
+                        incoming.reader_waker = Some(context.waker().clone());
+                        deliver_at
+                    }
+                    None if incoming.closed => return Poll::Ready(Ok(0)),
+                    None => {
+                        incoming.reader_waker = Some(context.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            };
+
+            // Wait for the delivery of the next chunk
+            if !matches!(&self.delivery_timer, Some((timer_deadline, _)) if *timer_deadline == deadline)
+            {
+                let timer = Box::pin(self.simulator.time_service.sleep_until(deadline));
+                self.delivery_timer = Some((deadline, timer));
+            }
+            let (_, timer) = self.delivery_timer.as_mut().unwrap();
+            if timer.as_mut().poll(context).is_pending() {
+                return Poll::Pending;
+            }
+            self.delivery_timer = None;
+        }
+    }
+}
+
+impl AsyncWrite for SimulatedSocket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut outgoing = self.outgoing.lock();
+        if outgoing.reader_dropped {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        let chunk = Bytes::copy_from_slice(&buf[..min(buf.len(), MAX_CHUNK_SIZE)]);
+        let now = self.simulator.time_service.now();
+        let deliver_at = self.simulator.inner.lock().delivery_time(
+            self.local_port,
+            self.remote_port,
+            &mut outgoing,
+            now,
+            chunk.len(),
+        );
+        let len = chunk.len();
+        outgoing.chunks.push_back((deliver_at, chunk));
+        outgoing.wake_reader();
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _context: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _context: &mut Context) -> Poll<io::Result<()>> {
+        let mut outgoing = self.outgoing.lock();
+        outgoing.closed = true;
+        outgoing.wake_reader();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for SimulatedSocket {
+    fn drop(&mut self) {
+        let mut outgoing = self.outgoing.lock();
+        outgoing.closed = true;
+        outgoing.wake_reader();
+        self.incoming.lock().reader_dropped = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_time_service::MockTimeService;
+    use futures::{
+        executor::block_on,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::StreamExt,
+        FutureExt,
+    };
+
+    fn connect(simulator: &NetworkSimulator) -> (SimulatedSocket, SimulatedSocket, u16, u16) {
+        let listener_transport = simulator.transport();
+        let (mut listener, addr) = listener_transport
+            .listen_on("/memory/0".parse().unwrap())
+            .unwrap();
+        let dialer_transport = simulator.transport();
+        let (_, dialer_addr) = dialer_transport
+            .listen_on("/memory/0".parse().unwrap())
+            .unwrap();
+        let outbound = block_on(
+            dialer_transport
+                .dial(PeerId::random(), addr.clone())
+                .unwrap(),
+        );
+        let (inbound, _) = block_on(listener.next()).unwrap().unwrap();
+        let port = |addr: &NetworkAddress| parse_memory(addr.as_slice()).unwrap().0;
+        (
+            outbound.unwrap(),
+            block_on(inbound).unwrap(),
+            port(&dialer_addr),
+            port(&addr),
+        )
+    }
+
+    /// Reads `len` bytes, advancing the mock time until they are delivered, and returns the time
+    /// it took.
+    fn read_exact(socket: &mut SimulatedSocket, time: &MockTimeService, len: usize) -> Duration {
+        let start = time.now();
+        let mut buf = vec![0; len];
+        let mut read = socket.read_exact(&mut buf).boxed();
+        loop {
+            if let Some(result) = (&mut read).now_or_never() {
+                result.unwrap();
+                return time.now() - start;
+            }
+            time.advance_next().expect("Nothing left to deliver");
+        }
+    }
+
+    #[test]
+    fn test_latency_and_bandwidth() {
+        let time_service = TimeService::mock();
+        let time = time_service.clone().into_mock();
+        let simulator = NetworkSimulator::new(
+            time_service,
+            0,
+            LinkConfig {
+                latency: Duration::from_millis(50),
+                bandwidth: Some(1000),
+                ..LinkConfig::default()
+            },
+        );
+        let (mut dialer, mut listener, _, _) = connect(&simulator);
+
+        block_on(dialer.write_all(&[1; 500])).unwrap();
+        assert_eq!(
+            read_exact(&mut listener, &time, 500),
+            Duration::from_millis(550)
+        );
+
+        // Chunks are delivered in order, after the previous ones are transmitted
+        block_on(dialer.write_all(&[2; 100])).unwrap();
+        block_on(dialer.write_all(&[3; 100])).unwrap();
+        assert_eq!(
+            read_exact(&mut listener, &time, 200),
+            Duration::from_millis(250)
+        );
+    }
+
+    #[test]
+    fn test_timed_partition() {
+        let time_service = TimeService::mock();
+        let time = time_service.clone().into_mock();
+        let simulator = NetworkSimulator::new(time_service.clone(), 0, LinkConfig::default());
+        let (mut dialer, mut listener, dialer_port, listener_port) = connect(&simulator);
+        simulator.partition(
+            &[dialer_port],
+            &[listener_port],
+            Duration::from_secs(1),
+            Duration::from_secs(3),
+        );
+
+        time.advance_secs(1);
+        // New connections are refused, writes are delivered once the partition heals
+        let transport = simulator.transport();
+        *transport.local_port.lock() = dialer_port;
+        let listener_addr = NetworkAddress::from(Protocol::Memory(listener_port));
+        assert!(block_on(transport.dial(PeerId::random(), listener_addr).unwrap()).is_err());
+        block_on(dialer.write_all(b"hello")).unwrap();
+        assert_eq!(read_exact(&mut listener, &time, 5), Duration::from_secs(2));
+
+        // The link changes over time
+        simulator.set_links_between(
+            dialer_port,
+            listener_port,
+            Duration::from_secs(10),
+            LinkConfig {
+                latency: Duration::from_millis(100),
+                ..LinkConfig::default()
+            },
+        );
+        block_on(listener.write_all(b"world")).unwrap();
+        assert_eq!(read_exact(&mut dialer, &time, 5), Duration::from_secs(0));
+        time.advance_secs(10);
+        block_on(listener.write_all(b"world")).unwrap();
+        assert_eq!(
+            read_exact(&mut dialer, &time, 5),
+            Duration::from_millis(100)
+        );
+    }
+
+    #[test]
+    fn test_deterministic_loss() {
+        let delays = |seed| {
+            let time_service = TimeService::mock();
+            let time = time_service.clone().into_mock();
+            let simulator = NetworkSimulator::new(
+                time_service,
+                seed,
+                LinkConfig {
+                    latency: Duration::from_millis(10),
+                    loss: 0.5,
+                    ..LinkConfig::default()
+                },
+            );
+            let (mut dialer, mut listener, _, _) = connect(&simulator);
+            (0..20)
+                .map(|_| {
+                    block_on(dialer.write_all(b"ping")).unwrap();
+                    read_exact(&mut listener, &time, 4)
+                })
+                .collect::<Vec<_>>()
+        };
+        let run = delays(7);
+        assert_eq!(run, delays(7));
+        // Some writes were lost and retransmitted
+        assert!(run.iter().any(|delay| *delay > Duration::from_millis(10)));
+        assert!(run.iter().all(|delay| {
+            (delay.as_millis() - 10) % LinkConfig::default().retransmit_timeout.as_millis() == 0
+        }));
+    }
+
+    #[test]
+    fn test_close() {
+        let simulator = NetworkSimulator::new(TimeService::mock(), 0, LinkConfig::default());
+        let (mut dialer, mut listener, _, _) = connect(&simulator);
+        block_on(dialer.write_all(b"bye")).unwrap();
+        drop(dialer);
+        let mut buf = Vec::new();
+        block_on(listener.read_to_end(&mut buf)).unwrap();
+        assert_eq!(buf, b"bye");
+        assert!(block_on(listener.write_all(b"gone")).is_err());
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
     connectivity_manager::{ConnectivityManager, ConnectivityRequest},
     counters,
     peer_manager::{conn_notifs_channel, ConnectionRequestSender},
+    peer_reputation::PeerReputation,
 };
 use diem_config::{config::PeerSet, network_id::NetworkContext};
 use diem_infallible::RwLock;
@@ -34,6 +35,7 @@ impl ConnectivityManagerBuilder {
         connection_notifs_rx: conn_notifs_channel::Receiver,
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
+        peer_reputation: Arc<PeerReputation>,
     ) -> Self {
         let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new(
             channel_size,
@@ -55,6 +57,7 @@ impl ConnectivityManagerBuilder {
                 Duration::from_millis(max_connection_delay_ms),
                 outbound_connection_limit,
                 mutual_authentication,
+                peer_reputation,
             )),
         }
     }
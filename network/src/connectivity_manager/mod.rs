@@ -30,6 +30,7 @@ use crate::{
     counters,
     logging::NetworkSchema,
     peer_manager::{self, conn_notifs_channel, ConnectionRequestSender, PeerManagerError},
+    peer_reputation::PeerReputation,
     transport::ConnectionMetadata,
 };
 use diem_config::{
@@ -113,16 +114,19 @@ pub struct ConnectivityManager<TBackoff> {
     rng: SmallRng,
     /// Whether we are using mutual authentication or not
     mutual_authentication: bool,
+    /// Reputation of the peers, banned peers are not dialed
+    peer_reputation: Arc<PeerReputation>,
 }
 
 /// Different sources for peer addresses, ordered by priority (Onchain=highest,
-/// Config=lowest).
+/// PeerExchange=lowest).
 #[repr(u8)]
 #[derive(Copy, Clone, Eq, Hash, PartialEq, Ord, PartialOrd, NumVariants, Serialize)]
 pub enum DiscoverySource {
     OnChainValidatorSet,
     File,
     Config,
+    PeerExchange,
 }
 
 impl fmt::Debug for DiscoverySource {
@@ -140,6 +144,7 @@ impl fmt::Display for DiscoverySource {
                 DiscoverySource::OnChainValidatorSet => "OnChainValidatorSet",
                 DiscoverySource::File => "File",
                 DiscoverySource::Config => "Config",
+                DiscoverySource::PeerExchange => "PeerExchange",
             }
         )
     }
@@ -263,6 +268,7 @@ where
         max_delay: Duration,
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
+        peer_reputation: Arc<PeerReputation>,
     ) -> Self {
         assert!(
             eligible.read().is_empty(),
@@ -293,6 +299,7 @@ where
             outbound_connection_limit,
             rng: SmallRng::from_entropy(),
             mutual_authentication,
+            peer_reputation,
         };
 
         // set the initial config addresses and pubkeys
@@ -449,6 +456,7 @@ where
                 && !self.connected.contains_key(peer_id) // The node is not already connected.
                 && !self.dial_queue.contains_key(peer_id) // There is no pending dial to this node.
                 && roles_to_dial.contains(&peer.role) // We can dial this role
+                && !self.peer_reputation.is_peer_banned(peer_id) // The node is not banned.
             })
             .collect();
 
@@ -589,6 +597,8 @@ where
         // Dial peers which are eligible but are neither connected nor queued for dialing in the
         // future.
         self.dial_eligible_peers(pending_dials);
+        // Forget the peers that recovered from their misbehaviors.
+        self.peer_reputation.garbage_collect();
     }
 
     fn reset_dial_state(&mut self, peer_id: &PeerId) {
@@ -5,10 +5,11 @@ use super::*;
 use crate::{
     peer::DisconnectReason,
     peer_manager::{conn_notifs_channel, ConnectionRequest},
+    peer_reputation::ReputationEvent,
     transport::ConnectionMetadata,
 };
 use channel::{diem_channel, message_queues::QueueStyle};
-use diem_config::config::{Peer, PeerRole, PeerSet, HANDSHAKE_VERSION};
+use diem_config::config::{Peer, PeerReputationConfig, PeerRole, PeerSet, HANDSHAKE_VERSION};
 use diem_crypto::{test_utils::TEST_SEED, x25519, Uniform};
 use diem_logger::info;
 use diem_time_service::{MockTimeService, TimeService};
@@ -79,6 +80,7 @@ struct TestHarness {
     connection_reqs_rx: diem_channel::Receiver<PeerId, ConnectionRequest>,
     connection_notifs_tx: conn_notifs_channel::Sender,
     conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    peer_reputation: Arc<PeerReputation>,
 }
 
 impl TestHarness {
@@ -89,6 +91,11 @@ impl TestHarness {
         let (connection_notifs_tx, connection_notifs_rx) = conn_notifs_channel::new();
         let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new_test(0);
         let trusted_peers = Arc::new(RwLock::new(HashMap::new()));
+        let peer_reputation = Arc::new(PeerReputation::new(
+            network_context.clone(),
+            time_service.clone(),
+            PeerReputationConfig::default(),
+        ));
 
         let conn_mgr = ConnectivityManager::new(
             network_context,
@@ -103,6 +110,7 @@ impl TestHarness {
             MAX_CONNECTION_DELAY,
             Some(MAX_TEST_CONNECTIONS),
             true, /* mutual_authentication */
+            peer_reputation.clone(),
         );
         let mock = Self {
             trusted_peers,
@@ -110,6 +118,7 @@ impl TestHarness {
             connection_reqs_rx,
             connection_notifs_tx,
             conn_mgr_reqs_tx,
+            peer_reputation,
         };
         (mock, conn_mgr)
     }
@@ -354,6 +363,45 @@ fn connect_to_seeds_on_startup() {
     block_on(future::join(conn_mgr.start(), test));
 }
 
+#[test]
+fn banned_peer_not_dialed() {
+    let (seed_peer_id, seed_peer, _, seed_addr) = test_peer(1);
+    let seeds = hashmap! {seed_peer_id => seed_peer};
+    let (mut mock, conn_mgr) = TestHarness::new(seeds);
+
+    let test = async move {
+        mock.trigger_connectivity_check().await;
+        mock.trigger_pending_dials().await;
+        mock.expect_one_dial_success(seed_peer_id, seed_addr.clone())
+            .await;
+
+        // The peer gets banned and disconnected.
+        assert!(mock.peer_reputation.report(
+            seed_peer_id,
+            &seed_addr,
+            PeerRole::Unknown,
+            ReputationEvent::FrameSizeViolation
+        ));
+        mock.send_lost_peer_await_delivery(seed_peer_id, seed_addr.clone())
+            .await;
+
+        // No dial while the ban lasts.
+        mock.trigger_connectivity_check().await;
+        assert_eq!(0, mock.get_dial_queue_size().await);
+
+        // The peer is dialed again once the ban expires.
+        mock.mock_time
+            .advance_async(Duration::from_millis(
+                PeerReputationConfig::default().ban_duration_ms,
+            ))
+            .await;
+        mock.trigger_connectivity_check().await;
+        mock.trigger_pending_dials().await;
+        mock.expect_one_dial_success(seed_peer_id, seed_addr).await;
+    };
+    block_on(future::join(conn_mgr.start(), test));
+}
+
 #[test]
 fn addr_change() {
     let (other_peer_id, other_peer, _, other_addr) = test_peer(0);
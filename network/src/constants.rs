@@ -13,10 +13,16 @@ pub const INBOUND_RPC_TIMEOUT_MS: u64 = 10_000;
 pub const MAX_CONCURRENT_OUTBOUND_RPCS: u32 = 100;
 /// Limit on concurrent Inbound RPC requests before backpressure is applied
 pub const MAX_CONCURRENT_INBOUND_RPCS: u32 = 100;
+/// Limit on concurrent inbound streams of rpc messages too large for a single frame
+pub const MAX_CONCURRENT_INBOUND_STREAMS: usize = 4;
+/// Limit on the bytes buffered by the inbound streams of a connection, raised to the
+/// `max_message_size` if lower
+pub const MAX_BUFFERED_INBOUND_STREAM_BYTES: usize = 128 * 1024 * 1024; /* 128 MiB */
 
 // These are only used in tests
 // TODO: Fix this so the tests and the defaults in config are the same
 pub const NETWORK_CHANNEL_SIZE: usize = 1024;
 pub const MAX_FRAME_SIZE: usize = 8 * 1024 * 1024; /* 8 MiB */
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024; /* 64 MiB */
 pub const MAX_CONCURRENT_NETWORK_REQS: usize = 100;
 pub const MAX_CONCURRENT_NETWORK_NOTIFS: usize = 100;
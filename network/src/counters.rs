@@ -63,6 +63,42 @@ pub fn connections_rejected(
     ])
 }
 
+pub static DIEM_NETWORK_PEER_REPUTATION_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_peer_reputation_events",
+        "Number of misbehaviors reported to the peer reputation, per type",
+        &["role_type", "network_id", "peer_id", "event"]
+    )
+    .unwrap()
+});
+
+pub fn peer_reputation_events(network_context: &NetworkContext, event: &str) -> IntCounter {
+    DIEM_NETWORK_PEER_REPUTATION_EVENTS.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        event,
+    ])
+}
+
+pub static DIEM_NETWORK_BANNED: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_network_banned",
+        "Number of currently banned peers and IP addresses",
+        &["role_type", "network_id", "peer_id", "kind"]
+    )
+    .unwrap()
+});
+
+pub fn banned(network_context: &NetworkContext, kind: &str) -> IntGauge {
+    DIEM_NETWORK_BANNED.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        kind,
+    ])
+}
+
 pub static DIEM_NETWORK_PEER_CONNECTED: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_network_peer_connected",
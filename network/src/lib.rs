@@ -19,6 +19,7 @@ pub mod logging;
 pub mod noise;
 pub mod peer;
 pub mod peer_manager;
+pub mod peer_reputation;
 pub mod protocols;
 pub mod transport;
 
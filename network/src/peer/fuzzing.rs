@@ -4,6 +4,7 @@
 use crate::{
     constants,
     peer::Peer,
+    peer_reputation::PeerReputation,
     protocols::wire::{
         handshake::v1::{MessagingProtocolVersion, SupportedProtocols},
         messaging::v1::{NetworkMessage, NetworkMessageSink},
@@ -13,7 +14,10 @@ use crate::{
     ProtocolId,
 };
 use channel::{diem_channel, message_queues::QueueStyle};
-use diem_config::{config::PeerRole, network_id::NetworkContext};
+use diem_config::{
+    config::{PeerReputationConfig, PeerRole},
+    network_id::NetworkContext,
+};
 use diem_proptest_helpers::ValueGenerator;
 use diem_time_service::TimeService;
 use diem_types::{network_address::NetworkAddress, PeerId};
@@ -21,7 +25,7 @@ use futures::{executor::block_on, future, io::AsyncReadExt, sink::SinkExt, strea
 use memsocket::MemorySocket;
 use netcore::transport::ConnectionOrigin;
 use proptest::{arbitrary::any, collection::vec};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 /// Generate a sequence of `NetworkMessage`, bcs serialize them, and write them
 /// out to a buffer using our length-prefixed message codec.
@@ -94,6 +98,12 @@ pub fn fuzz(data: &[u8]) {
     let (peer_reqs_tx, peer_reqs_rx) = diem_channel::new(QueueStyle::FIFO, channel_size, None);
     let (peer_notifs_tx, peer_notifs_rx) = diem_channel::new(QueueStyle::FIFO, channel_size, None);
 
+    let peer_reputation = Arc::new(PeerReputation::new(
+        network_context.clone(),
+        TimeService::mock(),
+        PeerReputationConfig::default(),
+    ));
+
     // Spin up a new `Peer` actor
     let peer = Peer::new(
         network_context,
@@ -106,9 +116,12 @@ pub fn fuzz(data: &[u8]) {
         Duration::from_millis(constants::INBOUND_RPC_TIMEOUT_MS),
         constants::MAX_CONCURRENT_INBOUND_RPCS,
         constants::MAX_CONCURRENT_OUTBOUND_RPCS,
+        constants::MAX_CONCURRENT_INBOUND_STREAMS,
         constants::MAX_FRAME_SIZE,
+        constants::MAX_MESSAGE_SIZE,
         None,
         None,
+        peer_reputation,
     );
     executor.spawn(peer.start());
 
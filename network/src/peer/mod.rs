@@ -15,15 +15,24 @@
 //! [`PeerManager`]: crate::peer_manager::PeerManager
 
 use crate::{
+    constants,
     counters::{self, RECEIVED_LABEL, SENT_LABEL},
     logging::NetworkSchema,
     peer_manager::{PeerManagerError, TransportNotification},
+    peer_reputation::{PeerReputation, ReputationEvent},
     protocols::{
         direct_send::Message,
-        rpc::{InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs},
-        wire::messaging::v1::{
-            DirectSendMsg, ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
-            Priority, ReadError, WriteError,
+        rpc::{
+            error::RpcError,
+            stream::{InboundStreams, OutboundStreams, StreamError, STREAM_EXPIRY_INTERVAL},
+            InboundRpcRequest, InboundRpcs, OutboundRpcRequest, OutboundRpcs,
+        },
+        wire::{
+            compression,
+            messaging::v1::{
+                DirectSendMsg, ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
+                Priority, ReadError, RpcRequest, StreamId, StreamedMessage, WriteError,
+            },
         },
     },
     transport::{self, Connection, ConnectionMetadata},
@@ -80,11 +89,13 @@ pub enum PeerNotification {
 /// For example, if the remote peer closed the connection or the connection was
 /// lost, the disconnect reason will be `ConnectionLost`. In contrast, if the
 /// [`PeerManager`](crate::peer_manager::PeerManager) requested us to close this
-/// connection, then the disconnect reason will be `Requested`.
+/// connection, then the disconnect reason will be `Requested`. A peer banned by the
+/// [`PeerReputation`] is disconnected with `Banned`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 pub enum DisconnectReason {
     Requested,
     ConnectionLost,
+    Banned,
 }
 
 impl fmt::Display for DisconnectReason {
@@ -92,6 +103,7 @@ impl fmt::Display for DisconnectReason {
         let s = match self {
             DisconnectReason::Requested => "Requested",
             DisconnectReason::ConnectionLost => "ConnectionLost",
+            DisconnectReason::Banned => "Banned",
         };
         write!(f, "{}", s)
     }
@@ -125,21 +137,26 @@ pub struct Peer<TSocket> {
     inbound_rpcs: InboundRpcs,
     /// Outbound rpc request queue for sending requests to remote peer and handling responses.
     outbound_rpcs: OutboundRpcs,
+    /// Reassembles the rpc messages streamed over multiple frames by the remote peer.
+    inbound_streams: InboundStreams,
     /// Flag to indicate if the actor is being shut down.
     state: State,
-    /// The maximum size of an inbound or outbound request frame
-    /// Currently, requests are only a single frame
+    /// The maximum size of an inbound or outbound frame. Larger rpc messages
+    /// are streamed over multiple frames.
     max_frame_size: usize,
     /// Optional inbound rate limiter
     inbound_rate_limiter: Option<SharedBucket>,
     /// Optional outbound rate limiter
     outbound_rate_limiter: Option<SharedBucket>,
+    /// Reputation of the peers of the network, the remote peer is disconnected once banned.
+    peer_reputation: Arc<PeerReputation>,
 }
 
 impl<TSocket> Peer<TSocket>
 where
     TSocket: AsyncRead + AsyncWrite + Send + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network_context: Arc<NetworkContext>,
         executor: Handle,
@@ -151,9 +168,12 @@ where
         inbound_rpc_timeout: Duration,
         max_concurrent_inbound_rpcs: u32,
         max_concurrent_outbound_rpcs: u32,
+        max_concurrent_inbound_streams: usize,
         max_frame_size: usize,
+        max_message_size: usize,
         inbound_rate_limiter: Option<SharedBucket>,
         outbound_rate_limiter: Option<SharedBucket>,
+        peer_reputation: Arc<PeerReputation>,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
@@ -175,17 +195,27 @@ where
                 remote_peer_id,
                 inbound_rpc_timeout,
                 max_concurrent_inbound_rpcs,
+                max_message_size,
             ),
             outbound_rpcs: OutboundRpcs::new(
                 network_context,
-                time_service,
+                time_service.clone(),
                 remote_peer_id,
                 max_concurrent_outbound_rpcs,
+                max_message_size,
+            ),
+            inbound_streams: InboundStreams::new(
+                time_service,
+                max_concurrent_inbound_streams,
+                max_message_size,
+                constants::MAX_BUFFERED_INBOUND_STREAM_BYTES.max(max_message_size),
+                inbound_rpc_timeout,
             ),
             state: State::Connected,
             max_frame_size,
             inbound_rate_limiter,
             outbound_rate_limiter,
+            peer_reputation,
         }
     }
 
@@ -229,8 +259,12 @@ where
             self.connection_metadata.clone(),
             self.network_context.clone(),
             writer,
+            self.max_frame_size,
         );
 
+        // Inbound streams which don't complete in time are dropped.
+        let mut stream_expiry = self.time_service.interval(STREAM_EXPIRY_INTERVAL);
+
         // Start main Peer event loop.
         let reason = loop {
             if let State::ShuttingDown(reason) = self.state {
@@ -271,6 +305,7 @@ where
                 // Drive the queue of pending inbound rpcs. When one is fulfilled
                 // by an upstream protocol, send the response to the remote peer.
                 maybe_response = self.inbound_rpcs.next_completed_response() => {
+                    let invalid_request = matches!(maybe_response, Err(RpcError::InvalidRpcRequest));
                     if let Err(err) = self.inbound_rpcs.send_outbound_response(&mut write_reqs_tx, maybe_response).await {
                         warn!(
                             NetworkSchema::new(&self.network_context).connection_metadata(&self.connection_metadata),
@@ -278,11 +313,31 @@ where
                             "{} Error in handling inbound rpc request, error: {}", self.network_context, err,
                         );
                     }
+                    if invalid_request {
+                        self.report(ReputationEvent::RpcDeserializationFailure);
+                    }
                 },
                 // Poll the queue of pending outbound rpc tasks for the next
                 // successfully or unsuccessfully completed request.
                 (request_id, maybe_completed_request) = self.outbound_rpcs.next_completed_request() => {
+                    // Nobody waits for the rest of the response once the rpc failed.
+                    if maybe_completed_request.is_err() {
+                        self.inbound_streams.discard_response(request_id);
+                    }
                     self.outbound_rpcs.handle_completed_request(request_id, maybe_completed_request);
+                },
+                _ = stream_expiry.select_next_some() => {
+                    let expired = self.inbound_streams.expire();
+                    if !expired.is_empty() {
+                        debug!(
+                            NetworkSchema::new(&self.network_context)
+                                .connection_metadata(&self.connection_metadata),
+                            "{} Dropped inbound streams {:?} from peer {} which didn't complete in time",
+                            self.network_context,
+                            expired,
+                            remote_peer_id.short_str()
+                        );
+                    }
                 }
             }
         };
@@ -305,6 +360,7 @@ where
         connection_metadata: ConnectionMetadata,
         network_context: Arc<NetworkContext>,
         mut writer: NetworkMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
+        max_frame_size: usize,
     ) -> (
         channel::Sender<(
             NetworkMessage,
@@ -321,16 +377,21 @@ where
             _,
         ) = channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
         let (close_tx, close_rx) = oneshot::channel();
+        let mut outbound_streams = OutboundStreams::new(max_frame_size);
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
             loop {
                 futures::select! {
                     (message, ack_ch) = write_reqs_rx.select_next_some() => {
-                        if let Err(err) = writer
-                            .send(&message)
-                            .map_ok(|_| ack_ch.send(Ok(())))
-                            .await
-                        {
+                        // Rpc messages too large for a frame are streamed over multiple frames
+                        let frames = outbound_streams.fragment(message);
+                        let write_frames = async {
+                            for frame in &frames {
+                                writer.send(frame).await?;
+                            }
+                            Ok::<_, WriteError>(())
+                        };
+                        if let Err(err) = write_frames.map_ok(|_| ack_ch.send(Ok(()))).await {
                             warn!(
                                 NetworkSchema::new(&network_context)
                                     .connection_metadata(&connection_metadata),
@@ -428,11 +489,15 @@ where
 
                     let (ack_tx, _) = oneshot::channel();
                     write_reqs_tx.send((message, ack_tx)).await?;
+                    self.report(ReputationEvent::MalformedMessage);
                     return Err(err.into());
                 }
                 ReadError::IoError(_) => {
                     // IoErrors are mostly unrecoverable so just close the connection.
                     self.shutdown(DisconnectReason::ConnectionLost);
+                    if err.is_frame_size_violation() {
+                        self.report(ReputationEvent::FrameSizeViolation);
+                    }
                     return Err(err.into());
                 }
             },
@@ -451,35 +516,101 @@ where
                     error_msg,
                 );
             }
-            NetworkMessage::RpcRequest(request) => {
-                if let Err(err) = self
-                    .inbound_rpcs
-                    .handle_inbound_request(&mut self.peer_notifs_tx, request)
-                {
-                    warn!(
-                        NetworkSchema::new(&self.network_context)
-                            .connection_metadata(&self.connection_metadata),
-                        error = %err,
-                        "{} Error handling inbound rpc request: {}",
-                        self.network_context,
-                        err
-                    );
-                }
-            }
+            NetworkMessage::RpcRequest(request) => self.handle_inbound_rpc_request(request),
             NetworkMessage::RpcResponse(response) => {
                 self.outbound_rpcs.handle_inbound_response(response)
             }
+            NetworkMessage::StreamHeader(header) => {
+                let stream_id = header.stream_id;
+                // The response to an rpc which timed out already is ignored.
+                let result = match &header.message {
+                    StreamedMessage::RpcResponse(response)
+                        if !self.outbound_rpcs.is_pending(response.request_id) =>
+                    {
+                        self.inbound_streams.discard_header(header)
+                    }
+                    _ => self.inbound_streams.handle_header(header),
+                };
+                if let Err(err) = result {
+                    self.handle_stream_error(stream_id, err);
+                }
+            }
+            NetworkMessage::StreamFragment(fragment) => {
+                let stream_id = fragment.stream_id;
+                match self.inbound_streams.handle_fragment(fragment) {
+                    Ok(Some(StreamedMessage::RpcRequest(request))) => {
+                        self.handle_inbound_rpc_request(request)
+                    }
+                    Ok(Some(StreamedMessage::RpcResponse(response))) => {
+                        self.outbound_rpcs.handle_inbound_response(response)
+                    }
+                    Ok(None) => (),
+                    Err(err) => self.handle_stream_error(stream_id, err),
+                }
+            }
         };
         Ok(())
     }
 
+    fn handle_inbound_rpc_request(&mut self, request: RpcRequest) {
+        if let Err(err) = self
+            .inbound_rpcs
+            .handle_inbound_request(&mut self.peer_notifs_tx, request)
+        {
+            if let RpcError::InvalidRpcRequest = err {
+                self.report(ReputationEvent::MalformedMessage);
+            }
+            warn!(
+                NetworkSchema::new(&self.network_context)
+                    .connection_metadata(&self.connection_metadata),
+                error = %err,
+                "{} Error handling inbound rpc request: {}",
+                self.network_context,
+                err
+            );
+        }
+    }
+
+    /// The streams are written in order by the remote peer, any error is a misbehavior.
+    fn handle_stream_error(&mut self, stream_id: StreamId, err: StreamError) {
+        warn!(
+            NetworkSchema::new(&self.network_context)
+                .connection_metadata(&self.connection_metadata),
+            error = %err,
+            "{} Dropping stream {} from peer {}: {}",
+            self.network_context,
+            stream_id,
+            self.remote_peer_id().short_str(),
+            err
+        );
+        self.report(ReputationEvent::MalformedMessage);
+    }
+
     /// Handle an inbound DirectSendMsg from the remote peer. There's not much to
-    /// do here other than bump some counters and forward the message up to the
-    /// PeerManager.
+    /// do here other than bump some counters, decompress the message if needed and
+    /// forward it up to the PeerManager.
     fn handle_inbound_direct_send(&mut self, message: DirectSendMsg) {
         let peer_id = self.remote_peer_id();
         let protocol_id = message.protocol_id;
-        let data = message.raw_msg;
+        let data = match compression::decompress(protocol_id, message.raw_msg, self.max_frame_size)
+        {
+            Ok(data) => data,
+            Err(err) => {
+                warn!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata(&self.connection_metadata),
+                    error = %err,
+                    "{} Failed to decompress message from peer {} for protocol {}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    protocol_id
+                );
+                self.report(ReputationEvent::MalformedMessage);
+                return;
+            }
+        };
+        // The application only knows the uncompressed protocol
+        let protocol_id = protocol_id.uncompressed();
 
         trace!(
             NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
@@ -528,11 +659,25 @@ where
             // push it onto our outbound writer queue.
             PeerRequest::SendDirectSend(message) => {
                 let message_len = message.mdata.len();
-                let protocol_id = message.protocol_id;
+                let protocol_id = self.wire_protocol(message.protocol_id);
+                let raw_msg = match compression::compress(protocol_id, &message.mdata) {
+                    Ok(raw_msg) => raw_msg,
+                    Err(err) => {
+                        warn!(
+                            NetworkSchema::new(&self.network_context)
+                                .connection_metadata(&self.connection_metadata),
+                            error = %err,
+                            "Failed to compress direct send message for protocol {}: {}",
+                            protocol_id,
+                            err,
+                        );
+                        return;
+                    }
+                };
                 let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
                     protocol_id,
                     priority: Priority::default(),
-                    raw_msg: Vec::from(message.mdata.as_ref()),
+                    raw_msg,
                 });
                 let (ack_tx, _ack_rx) = oneshot::channel();
 
@@ -555,7 +700,8 @@ where
                     }
                 }
             }
-            PeerRequest::SendRpc(request) => {
+            PeerRequest::SendRpc(mut request) => {
+                request.protocol_id = self.wire_protocol(request.protocol_id);
                 let protocol_id = request.protocol_id;
                 if let Err(e) = self
                     .outbound_rpcs
@@ -576,6 +722,31 @@ where
         }
     }
 
+    /// The protocol to send the messages of `protocol_id` over: its compressed counterpart if
+    /// both peers negotiated it in the handshake, `protocol_id` otherwise.
+    fn wire_protocol(&self, protocol_id: ProtocolId) -> ProtocolId {
+        protocol_id
+            .compressed()
+            .filter(|compressed| {
+                self.connection_metadata
+                    .application_protocols
+                    .contains(*compressed)
+            })
+            .unwrap_or(protocol_id)
+    }
+
+    /// Reports a misbehavior of the remote peer, closing the connection if it gets banned.
+    fn report(&mut self, event: ReputationEvent) {
+        if self.peer_reputation.report(
+            self.remote_peer_id(),
+            &self.connection_metadata.addr,
+            self.connection_metadata.role,
+            event,
+        ) {
+            self.shutdown(DisconnectReason::Banned);
+        }
+    }
+
     fn shutdown(&mut self, reason: DisconnectReason) {
         // Set the state of the actor to `State::ShuttingDown` to true ensures that the peer actor
         // will terminate and close the connection.
@@ -3,19 +3,25 @@
 
 use crate::{
     constants::{
-        INBOUND_RPC_TIMEOUT_MS, MAX_CONCURRENT_INBOUND_RPCS, MAX_CONCURRENT_OUTBOUND_RPCS,
-        MAX_FRAME_SIZE, NETWORK_CHANNEL_SIZE,
+        INBOUND_RPC_TIMEOUT_MS, MAX_CONCURRENT_INBOUND_RPCS, MAX_CONCURRENT_INBOUND_STREAMS,
+        MAX_CONCURRENT_OUTBOUND_RPCS, MAX_FRAME_SIZE, MAX_MESSAGE_SIZE, NETWORK_CHANNEL_SIZE,
     },
     peer::{DisconnectReason, Peer, PeerNotification, PeerRequest},
     peer_manager::TransportNotification,
+    peer_reputation::PeerReputation,
     protocols::{
         direct_send::Message,
-        rpc::{error::RpcError, InboundRpcRequest, OutboundRpcRequest},
+        rpc::{
+            error::RpcError,
+            stream::{InboundStreams, OutboundStreams},
+            InboundRpcRequest, OutboundRpcRequest,
+        },
         wire::{
+            compression,
             handshake::v1::MessagingProtocolVersion,
             messaging::v1::{
                 DirectSendMsg, NetworkMessage, NetworkMessageSink, NetworkMessageStream,
-                RpcRequest, RpcResponse,
+                RpcRequest, RpcResponse, StreamedMessage,
             },
         },
     },
@@ -24,7 +30,10 @@ use crate::{
 };
 use bytes::Bytes;
 use channel::{self, diem_channel, message_queues::QueueStyle};
-use diem_config::{config::PeerRole, network_id::NetworkContext};
+use diem_config::{
+    config::{PeerReputationConfig, PeerRole},
+    network_id::NetworkContext,
+};
 use diem_time_service::{MockTimeService, TimeService};
 use diem_types::{network_address::NetworkAddress, PeerId};
 use futures::{
@@ -36,7 +45,7 @@ use futures::{
 };
 use memsocket::MemorySocket;
 use netcore::transport::ConnectionOrigin;
-use std::{collections::HashSet, str::FromStr, time::Duration};
+use std::{collections::HashSet, str::FromStr, sync::Arc, time::Duration};
 use tokio::runtime::{Handle, Runtime};
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -76,6 +85,11 @@ fn build_test_peer(
     let (peer_notifs_tx, peer_notifs_rx) =
         diem_channel::new(QueueStyle::FIFO, NETWORK_CHANNEL_SIZE, None);
 
+    let peer_reputation = Arc::new(PeerReputation::new(
+        NetworkContext::mock(),
+        time_service.clone(),
+        PeerReputationConfig::default(),
+    ));
     let peer = Peer::new(
         NetworkContext::mock(),
         executor,
@@ -87,9 +101,12 @@ fn build_test_peer(
         Duration::from_millis(INBOUND_RPC_TIMEOUT_MS),
         MAX_CONCURRENT_INBOUND_RPCS,
         MAX_CONCURRENT_OUTBOUND_RPCS,
+        MAX_CONCURRENT_INBOUND_STREAMS,
         MAX_FRAME_SIZE,
+        MAX_MESSAGE_SIZE,
         None,
         None,
+        peer_reputation,
     );
     let peer_handle = PeerHandle(peer_reqs_tx);
 
@@ -280,6 +297,43 @@ fn peer_recv_message() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// An inbound DirectSendMsg of a compressed protocol should be decompressed and delivered under
+// the uncompressed protocol.
+#[test]
+fn peer_recv_compressed_message() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+        );
+
+    let data = vec![7u8; 64 * 1024];
+    let send_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::ConsensusDirectSendCompressed,
+        priority: 0,
+        raw_msg: compression::compress(ProtocolId::ConsensusDirectSendCompressed, &data).unwrap(),
+    });
+    let recv_msg = PeerNotification::RecvMessage(Message {
+        protocol_id: ProtocolId::ConsensusDirectSend,
+        mdata: Bytes::from(data),
+    });
+
+    let client = async move {
+        let mut connection = NetworkMessageSink::new(connection, MAX_FRAME_SIZE, None);
+        connection.send(&send_msg).await.unwrap();
+        connection.close().await.unwrap();
+    };
+
+    let server = async move {
+        let received = peer_notifs_rx.next().await.unwrap();
+        assert_eq!(recv_msg, received);
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 // Two connected Peer actors should be able to send/recv a DirectSend from each
 // other and then shutdown gracefully.
 #[test]
@@ -395,6 +449,79 @@ fn peer_recv_rpc() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// Rpc messages too large for a frame should be streamed over multiple frames, both ways.
+#[test]
+fn peer_recv_streamed_rpc() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, mut connection, _connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+        );
+    let (mut client_sink, mut client_stream) = build_network_sink_stream(&mut connection);
+
+    let request_data = vec![1u8; 4 * 1024];
+    let response_data = vec![2u8; MAX_FRAME_SIZE + 1];
+    let request = NetworkMessage::RpcRequest(RpcRequest {
+        request_id: 123,
+        protocol_id: PROTOCOL,
+        priority: 0,
+        raw_request: request_data.clone(),
+    });
+    let recv_msg = PeerNotification::RecvRpc(InboundRpcRequest {
+        protocol_id: PROTOCOL,
+        data: Bytes::from(request_data),
+        res_tx: oneshot::channel().0,
+    });
+    let resp_msg = StreamedMessage::RpcResponse(RpcResponse {
+        request_id: 123,
+        priority: 0,
+        raw_response: response_data.clone(),
+    });
+
+    let client = async move {
+        // Client streams the request with smaller frames than the peer.
+        for frame in OutboundStreams::new(1024).fragment(request) {
+            client_sink.send(&frame).await.unwrap();
+        }
+        // Client should then receive the streamed rpc response.
+        let mut inbound_streams = InboundStreams::new(
+            TimeService::mock(),
+            1,
+            MAX_MESSAGE_SIZE,
+            MAX_MESSAGE_SIZE,
+            Duration::from_millis(INBOUND_RPC_TIMEOUT_MS),
+        );
+        match client_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::StreamHeader(header) => inbound_streams.handle_header(header).unwrap(),
+            message => panic!("Unexpected message: {:?}", message),
+        }
+        match client_stream.next().await.unwrap().unwrap() {
+            NetworkMessage::StreamFragment(fragment) => assert_eq!(
+                inbound_streams.handle_fragment(fragment).unwrap(),
+                Some(resp_msg)
+            ),
+            message => panic!("Unexpected message: {:?}", message),
+        }
+        // Client then closes connection.
+        client_sink.close().await.unwrap();
+    };
+    let server = async move {
+        // Wait to receive the reassembled RpcRequest from Peer.
+        let received = peer_notifs_rx.next().await.unwrap();
+        assert_eq!(recv_msg, received);
+        match received {
+            PeerNotification::RecvRpc(req) => {
+                req.res_tx.send(Ok(Bytes::from(response_data))).unwrap()
+            }
+            _ => panic!("Unexpected PeerNotification: {:?}", received),
+        }
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 #[test]
 fn peer_recv_rpc_concurrent() {
     ::diem_logger::Logger::init_for_testing();
@@ -574,6 +701,49 @@ fn peer_recv_rpc_cancel() {
     rt.block_on(future::join(peer.start(), test));
 }
 
+// Peer is banned and disconnected after too many rpc requests that fail to deserialize.
+#[test]
+fn peer_recv_invalid_rpc_banned() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let (peer, _peer_handle, mut connection, mut connection_notifs_rx, mut peer_notifs_rx) =
+        build_test_peer(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+        );
+    let remote_peer_id = peer.remote_peer_id();
+
+    let test = async move {
+        let (mut client_sink, _client_stream) = build_network_sink_stream(&mut connection);
+        // Every request is penalized 20 points, the ban threshold is at -100
+        for request_id in 0..5 {
+            let send_msg = NetworkMessage::RpcRequest(RpcRequest {
+                request_id,
+                protocol_id: PROTOCOL,
+                priority: 0,
+                raw_request: Vec::from("invalid"),
+            });
+            client_sink.send(&send_msg).await.unwrap();
+
+            // The application fails to deserialize the request.
+            match peer_notifs_rx.next().await.unwrap() {
+                PeerNotification::RecvRpc(req) => {
+                    req.res_tx.send(Err(RpcError::InvalidRpcRequest)).unwrap()
+                }
+                notif => panic!("Unexpected PeerNotification: {:?}", notif),
+            }
+        }
+        assert_disconnected_event(
+            remote_peer_id,
+            DisconnectReason::Banned,
+            &mut connection_notifs_rx,
+        )
+        .await;
+    };
+    rt.block_on(future::join(peer.start(), test));
+}
+
 #[test]
 fn peer_send_rpc() {
     ::diem_logger::Logger::init_for_testing();
@@ -10,6 +10,7 @@ use crate::{
         conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, PeerManager,
         PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
     },
+    peer_reputation::PeerReputation,
     protocols::wire::handshake::v1::SupportedProtocols,
     transport::{self, Connection, DiemNetTransport, DIEM_TCP_TRANSPORT},
     ProtocolId,
@@ -27,7 +28,10 @@ use diem_rate_limiter::rate_limit::TokenBucketRateLimiter;
 use diem_time_service::TimeService;
 use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
-use netcore::transport::memory::MemoryTransport;
+use netcore::transport::{
+    memory::MemoryTransport,
+    simulated::{NetworkSimulator, SimulatedSocket, SimulatedTransport},
+};
 use netcore::transport::{
     tcp::{TcpSocket, TcpTransport},
     Transport,
@@ -56,6 +60,7 @@ struct TransportContext {
     authentication_mode: AuthenticationMode,
     trusted_peers: Arc<RwLock<PeerSet>>,
     enable_proxy_protocol: bool,
+    enable_compression: bool,
 }
 
 impl TransportContext {
@@ -66,6 +71,7 @@ impl TransportContext {
         authentication_mode: AuthenticationMode,
         trusted_peers: Arc<RwLock<PeerSet>>,
         enable_proxy_protocol: bool,
+        enable_compression: bool,
     ) -> Self {
         Self {
             chain_id,
@@ -74,14 +80,22 @@ impl TransportContext {
             authentication_mode,
             trusted_peers,
             enable_proxy_protocol,
+            enable_compression,
         }
     }
 
+    /// The registered protocols, and their compressed counterparts if compression is enabled.
     fn supported_protocols(&self) -> SupportedProtocols {
-        self.direct_send_protocols
-            .iter()
-            .chain(&self.rpc_protocols)
-            .into()
+        let protocols = self.direct_send_protocols.iter().chain(&self.rpc_protocols);
+        let compressed_protocols: Vec<_> = if self.enable_compression {
+            protocols
+                .clone()
+                .filter_map(|protocol| protocol.compressed())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        protocols.chain(&compressed_protocols).into()
     }
 
     fn augment_direct_send_protocols(
@@ -114,9 +128,11 @@ struct PeerManagerContext {
     max_concurrent_network_reqs: usize,
     channel_size: usize,
     max_frame_size: usize,
+    max_message_size: usize,
     inbound_connection_limit: usize,
     inbound_rate_limit_config: Option<RateLimitConfig>,
     outbound_rate_limit_config: Option<RateLimitConfig>,
+    peer_reputation: Arc<PeerReputation>,
 }
 
 impl PeerManagerContext {
@@ -138,9 +154,11 @@ impl PeerManagerContext {
         max_concurrent_network_reqs: usize,
         channel_size: usize,
         max_frame_size: usize,
+        max_message_size: usize,
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        peer_reputation: Arc<PeerReputation>,
     ) -> Self {
         Self {
             pm_reqs_tx,
@@ -156,9 +174,11 @@ impl PeerManagerContext {
             max_concurrent_network_reqs,
             channel_size,
             max_frame_size,
+            max_message_size,
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            peer_reputation,
         }
     }
 
@@ -181,11 +201,16 @@ impl PeerManagerContext {
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 type MemoryPeerManager =
     PeerManager<DiemNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
+#[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+type SimulatedPeerManager =
+    PeerManager<DiemNetTransport<SimulatedTransport>, NoiseStream<SimulatedSocket>>;
 type TcpPeerManager = PeerManager<DiemNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
 
 enum TransportPeerManager {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     Memory(MemoryPeerManager),
+    #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+    Simulated(SimulatedPeerManager),
     Tcp(TcpPeerManager),
 }
 
@@ -198,6 +223,9 @@ pub struct PeerManagerBuilder {
     peer_manager: Option<TransportPeerManager>,
     // ListenAddress will be updated when the PeerManager is built
     listen_address: NetworkAddress,
+    // Replaces the MemoryTransport of '/memory/<port>' addresses when set
+    #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+    network_simulator: Option<NetworkSimulator>,
 }
 
 impl PeerManagerBuilder {
@@ -214,10 +242,13 @@ impl PeerManagerBuilder {
         channel_size: usize,
         max_concurrent_network_reqs: usize,
         max_frame_size: usize,
+        max_message_size: usize,
         enable_proxy_protocol: bool,
+        enable_compression: bool,
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        peer_reputation: Arc<PeerReputation>,
     ) -> Self {
         // Setup channel to send requests to peer manager.
         let (pm_reqs_tx, pm_reqs_rx) = diem_channel::new(
@@ -239,6 +270,7 @@ impl PeerManagerBuilder {
                 authentication_mode,
                 trusted_peers.clone(),
                 enable_proxy_protocol,
+                enable_compression,
             )),
             peer_manager_context: Some(PeerManagerContext::new(
                 pm_reqs_tx,
@@ -252,15 +284,27 @@ impl PeerManagerBuilder {
                 max_concurrent_network_reqs,
                 channel_size,
                 max_frame_size,
+                max_message_size,
                 inbound_connection_limit,
                 inbound_rate_limit_config,
                 outbound_rate_limit_config,
+                peer_reputation,
             )),
             peer_manager: None,
             listen_address,
+            #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+            network_simulator: None,
         }
     }
 
+    /// Routes the connections of a '/memory/<port>' listen address through the simulator, so that
+    /// their latency, loss and partitions can be programmed.
+    #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+    pub fn set_network_simulator(&mut self, network_simulator: NetworkSimulator) -> &mut Self {
+        self.network_simulator = Some(network_simulator);
+        self
+    }
+
     pub fn listen_address(&self) -> NetworkAddress {
         self.listen_address.clone()
     }
@@ -316,20 +360,42 @@ impl PeerManagerBuilder {
                 )))
             }
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
-            [Memory(_)] => Some(TransportPeerManager::Memory(self.build_with_transport(
-                DiemNetTransport::new(
-                    MemoryTransport,
-                    self.network_context.clone(),
-                    self.time_service.clone(),
-                    key,
-                    auth_mode,
-                    HANDSHAKE_VERSION,
-                    chain_id,
-                    protos,
-                    enable_proxy_protocol,
-                ),
-                executor,
-            ))),
+            [Memory(_)] => match self
+                .network_simulator
+                .as_ref()
+                .map(NetworkSimulator::transport)
+            {
+                Some(transport) => {
+                    Some(TransportPeerManager::Simulated(self.build_with_transport(
+                        DiemNetTransport::new(
+                            transport,
+                            self.network_context.clone(),
+                            self.time_service.clone(),
+                            key,
+                            auth_mode,
+                            HANDSHAKE_VERSION,
+                            chain_id,
+                            protos,
+                            enable_proxy_protocol,
+                        ),
+                        executor,
+                    )))
+                }
+                None => Some(TransportPeerManager::Memory(self.build_with_transport(
+                    DiemNetTransport::new(
+                        MemoryTransport,
+                        self.network_context.clone(),
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                    ),
+                    executor,
+                ))),
+            },
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
                  '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
@@ -382,9 +448,11 @@ impl PeerManagerBuilder {
             pm_context.max_concurrent_network_reqs,
             pm_context.channel_size,
             pm_context.max_frame_size,
+            pm_context.max_message_size,
             pm_context.inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            pm_context.peer_reputation,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -414,6 +482,8 @@ impl PeerManagerBuilder {
         {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             TransportPeerManager::Memory(pm) => self.start_peer_manager(pm, executor),
+            #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+            TransportPeerManager::Simulated(pm) => self.start_peer_manager(pm, executor),
             TransportPeerManager::Tcp(pm) => self.start_peer_manager(pm, executor),
         }
     }
@@ -15,6 +15,7 @@ use crate::{
     counters::{self},
     logging::*,
     peer::{Peer, PeerNotification, PeerRequest},
+    peer_reputation::PeerReputation,
     transport::{
         Connection, ConnectionId, ConnectionMetadata, TSocket as TransportTSocket,
         TRANSPORT_TIMEOUT,
@@ -119,12 +120,16 @@ where
     channel_size: usize,
     /// Max network frame size
     max_frame_size: usize,
+    /// Max rpc message size, messages larger than a frame are streamed
+    max_message_size: usize,
     /// Inbound connection limit separate of outbound connections
     inbound_connection_limit: usize,
     /// Keyed storage of all inbound rate limiters
     inbound_rate_limiters: IpAddrTokenBucketLimiter,
     /// Keyed storage of all outbound rate limiters
     outbound_rate_limiters: IpAddrTokenBucketLimiter,
+    /// Reputation of the peers, banned peers are disconnected and can't connect inbound
+    peer_reputation: Arc<PeerReputation>,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -152,9 +157,11 @@ where
         channel_size: usize,
         max_concurrent_network_reqs: usize,
         max_frame_size: usize,
+        max_message_size: usize,
         inbound_connection_limit: usize,
         inbound_rate_limiters: IpAddrTokenBucketLimiter,
         outbound_rate_limiters: IpAddrTokenBucketLimiter,
+        peer_reputation: Arc<PeerReputation>,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -196,9 +203,11 @@ where
             max_concurrent_network_reqs,
             channel_size,
             max_frame_size,
+            max_message_size,
             inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            peer_reputation,
         }
     }
 
@@ -308,6 +317,27 @@ where
                         // Everything below here is meant for unknown peers only, role comes from
                         // Noise handshake and if it's not `Unknown` it is trusted
                         if conn.metadata.role == PeerRole::Unknown {
+                            // Reject banned peers, and any peer connecting from a banned address
+                            if self
+                                .peer_reputation
+                                .is_banned(&conn.metadata.remote_peer_id, &conn.metadata.addr)
+                            {
+                                info!(
+                                    NetworkSchema::new(&self.network_context)
+                                        .connection_metadata_with_address(&conn.metadata),
+                                    "{} Connection rejected from banned peer: {}",
+                                    self.network_context,
+                                    conn.metadata
+                                );
+                                counters::connections_rejected(
+                                    &self.network_context,
+                                    conn.metadata.origin,
+                                )
+                                .inc();
+                                self.disconnect(conn);
+                                return;
+                            }
+
                             // TODO: Keep track of somewhere else to not take this hit in case of DDoS
                             // Count unknown inbound connections
                             let unknown_inbound_conns = self
@@ -495,6 +525,27 @@ where
                     }
                 }
             }
+            ConnectionRequest::ReportPeer(peer_id, event) => {
+                let (addr, role) = match self.active_peers.get(&peer_id) {
+                    Some((conn_metadata, _)) => (conn_metadata.addr.clone(), conn_metadata.role),
+                    // Only connected peers can misbehave, the report is late
+                    None => return,
+                };
+                if self.peer_reputation.report(peer_id, &addr, role, event) {
+                    if let Some((conn_metadata, sender)) = self.active_peers.remove(&peer_id) {
+                        info!(
+                            NetworkSchema::new(&self.network_context)
+                                .connection_metadata_with_address(&conn_metadata),
+                            "{} Disconnecting banned peer {}",
+                            self.network_context,
+                            peer_id.short_str(),
+                        );
+                        self.peer_metadata_storage.remove_connection(&conn_metadata);
+                        // This triggers a disconnect.
+                        drop(sender);
+                    }
+                }
+            }
         }
     }
 
@@ -699,9 +750,12 @@ where
             Duration::from_millis(constants::INBOUND_RPC_TIMEOUT_MS),
             constants::MAX_CONCURRENT_INBOUND_RPCS,
             constants::MAX_CONCURRENT_OUTBOUND_RPCS,
+            constants::MAX_CONCURRENT_INBOUND_STREAMS,
             self.max_frame_size,
+            self.max_message_size,
             Some(inbound_rate_limiter),
             Some(outbound_rate_limiter),
+            self.peer_reputation.clone(),
         );
         self.executor.spawn(peer.start());
 
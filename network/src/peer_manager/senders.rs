@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    peer_reputation::ReputationEvent,
     protocols::{
         direct_send::Message,
         rpc::{error::RpcError, OutboundRpcRequest},
@@ -131,4 +132,14 @@ impl ConnectionRequestSender {
             .push(peer, ConnectionRequest::DisconnectPeer(peer, oneshot_tx))?;
         oneshot_rx.await?
     }
+
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        event: ReputationEvent,
+    ) -> Result<(), PeerManagerError> {
+        self.inner
+            .push(peer, ConnectionRequest::ReportPeer(peer, event))?;
+        Ok(())
+    }
 }
@@ -9,6 +9,7 @@ use crate::{
         conn_notifs_channel, error::PeerManagerError, ConnectionNotification, ConnectionRequest,
         PeerManager, PeerManagerNotification, PeerManagerRequest, TransportNotification,
     },
+    peer_reputation::PeerReputation,
     protocols::wire::{
         handshake::v1::MessagingProtocolVersion,
         messaging::v1::{ErrorCode, NetworkMessage, NetworkMessageSink, NetworkMessageStream},
@@ -21,7 +22,7 @@ use anyhow::anyhow;
 use bytes::Bytes;
 use channel::{diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{PeerRole, MAX_INBOUND_CONNECTIONS},
+    config::{PeerReputationConfig, PeerRole, MAX_INBOUND_CONNECTIONS},
     network_id::NetworkContext,
 };
 use diem_infallible::RwLock;
@@ -98,11 +99,17 @@ fn build_test_peer_manager(
     let (hello_tx, hello_rx) = diem_channel::new(QueueStyle::FIFO, 1, None);
     let (conn_status_tx, conn_status_rx) = conn_notifs_channel::new();
 
+    let network_context = NetworkContext::mock_with_peer_id(peer_id);
+    let peer_reputation = Arc::new(PeerReputation::new(
+        network_context.clone(),
+        TimeService::mock(),
+        PeerReputationConfig::default(),
+    ));
     let peer_manager = PeerManager::new(
         executor,
         TimeService::mock(),
         build_test_transport(),
-        NetworkContext::mock_with_peer_id(peer_id),
+        network_context,
         "/memory/0".parse().unwrap(),
         Arc::new(PeerMetadataStorage::new()),
         Arc::new(RwLock::new(HashMap::new())),
@@ -113,9 +120,11 @@ fn build_test_peer_manager(
         constants::NETWORK_CHANNEL_SIZE,
         constants::MAX_CONCURRENT_NETWORK_REQS,
         constants::MAX_FRAME_SIZE,
+        constants::MAX_MESSAGE_SIZE,
         MAX_INBOUND_CONNECTIONS,
         TokenBucketRateLimiter::open("inbound"),
         TokenBucketRateLimiter::open("outbound"),
+        peer_reputation,
     );
 
     (
@@ -3,6 +3,7 @@
 use crate::{
     peer::DisconnectReason,
     peer_manager::PeerManagerError,
+    peer_reputation::ReputationEvent,
     protocols::{
         direct_send::Message,
         rpc::{InboundRpcRequest, OutboundRpcRequest},
@@ -44,6 +45,8 @@ pub enum ConnectionRequest {
         PeerId,
         #[serde(skip)] oneshot::Sender<Result<(), PeerManagerError>>,
     ),
+    /// Report a misbehavior of the peer, which is disconnected if it gets banned.
+    ReportPeer(PeerId, ReputationEvent),
 }
 
 #[derive(Clone, PartialEq, Serialize)]
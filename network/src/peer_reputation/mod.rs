@@ -0,0 +1,325 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Scoring of misbehaving peers.
+//!
+//! The network layer and the application protocols report misbehaviors (messages that fail to
+//! deserialize or to verify, frames over the `max_frame_size`) to the [`PeerReputation`] of their
+//! network. Every report lowers the score of the peer and of the IP address it connected from,
+//! scores then recover linearly back to zero. Once a score reaches the ban threshold, the peer or
+//! the address is banned for a while:
+//!  * the [`PeerManager`] closes its connection and rejects its inbound connections,
+//!  * the [`ConnectivityManager`] stops dialing it.
+//!
+//! Only untrusted peers (with the `Unknown` role) are scored and banned. The misbehaviors of
+//! trusted peers are still counted and logged, but can't cut a node off its validators or
+//! upstream peers.
+//!
+//! [`PeerManager`]: crate::peer_manager::PeerManager
+//! [`ConnectivityManager`]: crate::connectivity_manager::ConnectivityManager
+
+use crate::{counters, logging::NetworkSchema};
+use diem_config::{
+    config::{PeerReputationConfig, PeerRole},
+    network_id::NetworkContext,
+};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use diem_types::{network_address::NetworkAddress, PeerId};
+use serde::Serialize;
+use serde_json::json;
+use short_hex_str::AsShortHexStr;
+use std::{
+    cmp::min,
+    collections::HashMap,
+    hash::Hash,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod test;
+
+const PEER_LABEL: &str = "peer";
+const ADDRESS_LABEL: &str = "address";
+
+/// Misbehaviors reported to the [`PeerReputation`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum ReputationEvent {
+    /// An application message that failed validation, e.g., with an invalid signature.
+    InvalidMessage,
+    /// A wire message that failed to deserialize.
+    MalformedMessage,
+    /// An inbound rpc request that failed to deserialize into the application message type.
+    RpcDeserializationFailure,
+    /// A frame larger than the `max_frame_size` of the network.
+    FrameSizeViolation,
+}
+
+impl ReputationEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReputationEvent::InvalidMessage => "invalid_message",
+            ReputationEvent::MalformedMessage => "malformed_message",
+            ReputationEvent::RpcDeserializationFailure => "rpc_deserialization_failure",
+            ReputationEvent::FrameSizeViolation => "frame_size_violation",
+        }
+    }
+
+    /// Score removed for every occurrence of the misbehavior.
+    pub fn penalty(self) -> i64 {
+        match self {
+            ReputationEvent::InvalidMessage => 25,
+            ReputationEvent::MalformedMessage | ReputationEvent::RpcDeserializationFailure => 20,
+            // The connection is closed by the codec anyway
+            ReputationEvent::FrameSizeViolation => 100,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Score {
+    value: i64,
+    updated_at: Instant,
+    banned_until: Option<Instant>,
+}
+
+impl Score {
+    fn new(now: Instant) -> Self {
+        Self {
+            value: 0,
+            updated_at: now,
+            banned_until: None,
+        }
+    }
+
+    /// Recover the score for the whole seconds elapsed since the last update.
+    fn recover(&mut self, now: Instant, recovery_per_sec: i64) {
+        let elapsed_secs = now.saturating_duration_since(self.updated_at).as_secs();
+        let recovered = recovery_per_sec.saturating_mul(elapsed_secs as i64);
+        self.value = min(0, self.value.saturating_add(recovered));
+        self.updated_at += Duration::from_secs(elapsed_secs);
+    }
+
+    fn is_banned(&self, now: Instant) -> bool {
+        self.banned_until.map_or(false, |until| now < until)
+    }
+}
+
+#[derive(Default)]
+struct Scores {
+    peers: HashMap<PeerId, Score>,
+    addresses: HashMap<IpAddr, Score>,
+}
+
+/// Scores of the peers and the IP addresses of a network, shared by the components of the
+/// network.
+pub struct PeerReputation {
+    network_context: Arc<NetworkContext>,
+    time_service: TimeService,
+    config: PeerReputationConfig,
+    scores: Mutex<Scores>,
+}
+
+impl PeerReputation {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        time_service: TimeService,
+        config: PeerReputationConfig,
+    ) -> Self {
+        Self {
+            network_context,
+            time_service,
+            config,
+            scores: Mutex::new(Scores::default()),
+        }
+    }
+
+    /// Penalize the peer and its IP address for the misbehavior, unless the peer is trusted
+    /// (`role` is not `Unknown`). Returns true if either of them is banned, in which case the
+    /// connection with the peer should be closed.
+    pub fn report(
+        &self,
+        peer_id: PeerId,
+        addr: &NetworkAddress,
+        role: PeerRole,
+        event: ReputationEvent,
+    ) -> bool {
+        counters::peer_reputation_events(&self.network_context, event.as_str()).inc();
+        if role != PeerRole::Unknown {
+            warn!(
+                NetworkSchema::new(&self.network_context)
+                    .remote_peer(&peer_id)
+                    .network_address(addr),
+                event = event.as_str(),
+                "{} Trusted peer {} ({:?}) misbehaved: {}",
+                self.network_context,
+                peer_id.short_str(),
+                role,
+                event.as_str()
+            );
+            return false;
+        }
+        let now = self.time_service.now();
+        let mut scores = self.scores.lock();
+
+        let peer_score = scores
+            .peers
+            .entry(peer_id)
+            .or_insert_with(|| Score::new(now));
+        if self.penalize(peer_score, now, event) {
+            warn!(
+                NetworkSchema::new(&self.network_context)
+                    .remote_peer(&peer_id)
+                    .network_address(addr),
+                event = event.as_str(),
+                "{} Peer {} banned for {}ms after {}",
+                self.network_context,
+                peer_id.short_str(),
+                self.config.ban_duration_ms,
+                event.as_str()
+            );
+            event!(
+                "peer_banned",
+                "network_id": self.network_context.network_id().as_str(),
+                "peer_id": peer_id.to_string(),
+                "reason": event.as_str(),
+            );
+        }
+        let mut banned = peer_score.is_banned(now);
+
+        if let Some(ip_addr) = addr.find_ip_addr() {
+            let addr_score = scores
+                .addresses
+                .entry(ip_addr)
+                .or_insert_with(|| Score::new(now));
+            if self.penalize(addr_score, now, event) {
+                warn!(
+                    NetworkSchema::new(&self.network_context).network_address(addr),
+                    event = event.as_str(),
+                    "{} Address {} banned for {}ms after {}",
+                    self.network_context,
+                    ip_addr,
+                    self.config.ban_duration_ms,
+                    event.as_str()
+                );
+                event!(
+                    "address_banned",
+                    "network_id": self.network_context.network_id().as_str(),
+                    "address": ip_addr.to_string(),
+                    "reason": event.as_str(),
+                );
+            }
+            banned |= addr_score.is_banned(now);
+        }
+        self.update_banned_counters(&scores, now);
+        banned
+    }
+
+    /// Returns true if the score fell to the threshold and the ban started with this penalty.
+    fn penalize(&self, score: &mut Score, now: Instant, event: ReputationEvent) -> bool {
+        score.recover(now, self.config.recovery_per_sec);
+        score.value = score.value.saturating_sub(event.penalty());
+        if !self.config.enabled || score.is_banned(now) || score.value > self.config.ban_threshold {
+            return false;
+        }
+        // The ban is the punishment, the score starts over once it expires.
+        score.value = 0;
+        score.banned_until = Some(now + Duration::from_millis(self.config.ban_duration_ms));
+        true
+    }
+
+    /// Returns true if the peer or the IP address of `addr` is banned.
+    pub fn is_banned(&self, peer_id: &PeerId, addr: &NetworkAddress) -> bool {
+        let now = self.time_service.now();
+        let scores = self.scores.lock();
+        Self::is_key_banned(&scores.peers, peer_id, now)
+            || addr.find_ip_addr().map_or(false, |ip_addr| {
+                Self::is_key_banned(&scores.addresses, &ip_addr, now)
+            })
+    }
+
+    /// Returns true if the peer is banned, regardless of its addresses.
+    pub fn is_peer_banned(&self, peer_id: &PeerId) -> bool {
+        let now = self.time_service.now();
+        Self::is_key_banned(&self.scores.lock().peers, peer_id, now)
+    }
+
+    fn is_key_banned<K: Eq + Hash>(scores: &HashMap<K, Score>, key: &K, now: Instant) -> bool {
+        scores.get(key).map_or(false, |score| score.is_banned(now))
+    }
+
+    /// The current score of the peer, zero for well behaved peers.
+    pub fn score(&self, peer_id: &PeerId) -> i64 {
+        let now = self.time_service.now();
+        self.scores
+            .lock()
+            .peers
+            .get_mut(peer_id)
+            .map_or(0, |score| {
+                score.recover(now, self.config.recovery_per_sec);
+                score.value
+            })
+    }
+
+    /// The scores and the remaining ban durations of the peers and the addresses which
+    /// misbehaved recently, served by the debug interface.
+    pub fn debug_state(&self) -> serde_json::Value {
+        let now = self.time_service.now();
+        let recovery_per_sec = self.config.recovery_per_sec;
+        let mut scores = self.scores.lock();
+        let entry = |key: String, score: &mut Score| {
+            score.recover(now, recovery_per_sec);
+            json!({
+                "key": key,
+                "score": score.value,
+                "banned_for_ms": score
+                    .banned_until
+                    .filter(|_| score.is_banned(now))
+                    .map(|until| until.saturating_duration_since(now).as_millis() as u64),
+            })
+        };
+        let peers: Vec<_> = scores
+            .peers
+            .iter_mut()
+            .map(|(peer_id, score)| entry(peer_id.to_string(), score))
+            .collect();
+        let addresses: Vec<_> = scores
+            .addresses
+            .iter_mut()
+            .map(|(ip_addr, score)| entry(ip_addr.to_string(), score))
+            .collect();
+        json!({
+            "network": self.network_context.to_string(),
+            "peers": peers,
+            "addresses": addresses,
+        })
+    }
+
+    /// Forget the peers and the addresses that fully recovered and are no longer banned.
+    pub fn garbage_collect(&self) {
+        let now = self.time_service.now();
+        let recovery_per_sec = self.config.recovery_per_sec;
+        let mut scores = self.scores.lock();
+        let is_live = |score: &mut Score| {
+            score.recover(now, recovery_per_sec);
+            score.value < 0 || score.is_banned(now)
+        };
+        scores.peers.retain(|_, score| is_live(score));
+        scores.addresses.retain(|_, score| is_live(score));
+        self.update_banned_counters(&scores, now);
+    }
+
+    fn update_banned_counters(&self, scores: &Scores, now: Instant) {
+        let banned_peers = scores.peers.values().filter(|s| s.is_banned(now)).count();
+        let banned_addresses = scores
+            .addresses
+            .values()
+            .filter(|s| s.is_banned(now))
+            .count();
+        counters::banned(&self.network_context, PEER_LABEL).set(banned_peers as i64);
+        counters::banned(&self.network_context, ADDRESS_LABEL).set(banned_addresses as i64);
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::peer_reputation::{PeerReputation, ReputationEvent};
+use diem_config::{
+    config::{PeerReputationConfig, PeerRole},
+    network_id::NetworkContext,
+};
+use diem_time_service::{MockTimeService, TimeService};
+use diem_types::{network_address::NetworkAddress, PeerId};
+use std::{str::FromStr, time::Duration};
+
+const BAN_DURATION: Duration = Duration::from_secs(600);
+
+fn setup(config: PeerReputationConfig) -> (PeerReputation, MockTimeService) {
+    let time_service = TimeService::mock();
+    let reputation = PeerReputation::new(
+        NetworkContext::mock(),
+        time_service.clone(),
+        PeerReputationConfig {
+            ban_duration_ms: BAN_DURATION.as_millis() as u64,
+            ..config
+        },
+    );
+    (reputation, time_service.into_mock())
+}
+
+fn addr(ip: &str) -> NetworkAddress {
+    NetworkAddress::from_str(&format!("/ip4/{}/tcp/6180", ip)).unwrap()
+}
+
+#[test]
+fn test_ban_below_threshold() {
+    let (reputation, time_service) = setup(PeerReputationConfig::default());
+    let peer_id = PeerId::random();
+    let peer_addr = addr("1.2.3.4");
+
+    // -100 is reached on the fifth malformed message
+    for _ in 0..4 {
+        assert!(!reputation.report(
+            peer_id,
+            &peer_addr,
+            PeerRole::Unknown,
+            ReputationEvent::MalformedMessage
+        ));
+    }
+    assert_eq!(reputation.score(&peer_id), -80);
+    assert!(reputation.report(
+        peer_id,
+        &peer_addr,
+        PeerRole::Unknown,
+        ReputationEvent::MalformedMessage
+    ));
+    assert!(reputation.is_peer_banned(&peer_id));
+    assert!(reputation.is_banned(&peer_id, &peer_addr));
+
+    // Other peers connecting from the same address are banned as well
+    assert!(reputation.is_banned(&PeerId::random(), &peer_addr));
+    assert!(!reputation.is_banned(&PeerId::random(), &addr("1.2.3.5")));
+
+    // The ban expires
+    time_service.advance(BAN_DURATION);
+    assert!(!reputation.is_banned(&peer_id, &peer_addr));
+    assert_eq!(reputation.score(&peer_id), 0);
+}
+
+#[test]
+fn test_frame_size_violation_bans() {
+    let (reputation, _) = setup(PeerReputationConfig::default());
+    let peer_id = PeerId::random();
+    assert!(reputation.report(
+        peer_id,
+        &NetworkAddress::mock(),
+        PeerRole::Unknown,
+        ReputationEvent::FrameSizeViolation
+    ));
+    assert!(reputation.is_peer_banned(&peer_id));
+}
+
+#[test]
+fn test_score_recovery() {
+    let (reputation, time_service) = setup(PeerReputationConfig {
+        recovery_per_sec: 2,
+        ..PeerReputationConfig::default()
+    });
+    let peer_id = PeerId::random();
+    let peer_addr = addr("1.2.3.4");
+    for _ in 0..4 {
+        reputation.report(
+            peer_id,
+            &peer_addr,
+            PeerRole::Unknown,
+            ReputationEvent::InvalidMessage,
+        );
+    }
+    // The score starts over once banned
+    assert!(reputation.is_peer_banned(&peer_id));
+    assert_eq!(reputation.score(&peer_id), 0);
+
+    let peer_id = PeerId::random();
+    let peer_addr = addr("5.6.7.8");
+    for _ in 0..3 {
+        reputation.report(
+            peer_id,
+            &peer_addr,
+            PeerRole::Unknown,
+            ReputationEvent::InvalidMessage,
+        );
+    }
+    time_service.advance_secs(10);
+    assert_eq!(reputation.score(&peer_id), -55);
+    // Recovered enough not to be banned by the next report
+    assert!(!reputation.report(
+        peer_id,
+        &peer_addr,
+        PeerRole::Unknown,
+        ReputationEvent::InvalidMessage
+    ));
+    time_service.advance_secs(60);
+    assert_eq!(reputation.score(&peer_id), 0);
+
+    reputation.garbage_collect();
+    assert!(reputation.scores.lock().peers.get(&peer_id).is_none());
+}
+
+#[test]
+fn test_disabled() {
+    let (reputation, _) = setup(PeerReputationConfig {
+        enabled: false,
+        ..PeerReputationConfig::default()
+    });
+    let peer_id = PeerId::random();
+    assert!(!reputation.report(
+        peer_id,
+        &NetworkAddress::mock(),
+        PeerRole::Unknown,
+        ReputationEvent::FrameSizeViolation
+    ));
+    assert!(!reputation.is_peer_banned(&peer_id));
+    assert_eq!(reputation.score(&peer_id), -100);
+}
+
+#[test]
+fn test_trusted_peers_not_banned() {
+    let (reputation, _) = setup(PeerReputationConfig::default());
+    let peer_id = PeerId::random();
+    let peer_addr = addr("1.2.3.4");
+    for role in [PeerRole::Validator, PeerRole::Upstream] {
+        assert!(!reputation.report(
+            peer_id,
+            &peer_addr,
+            role,
+            ReputationEvent::FrameSizeViolation
+        ));
+    }
+    assert!(!reputation.is_banned(&peer_id, &peer_addr));
+    assert_eq!(reputation.score(&peer_id), 0);
+}
+
+#[test]
+fn test_debug_state() {
+    let (reputation, time_service) = setup(PeerReputationConfig::default());
+    let peer_id = PeerId::random();
+    reputation.report(
+        peer_id,
+        &addr("1.2.3.4"),
+        PeerRole::Unknown,
+        ReputationEvent::FrameSizeViolation,
+    );
+    time_service.advance_secs(60);
+
+    let state = reputation.debug_state();
+    assert_eq!(state["peers"][0]["key"], peer_id.to_string());
+    assert_eq!(state["peers"][0]["score"], 0);
+    assert_eq!(
+        state["peers"][0]["banned_for_ms"],
+        (BAN_DURATION - Duration::from_secs(60)).as_millis() as u64
+    );
+    assert_eq!(state["addresses"][0]["key"], "1.2.3.4");
+}
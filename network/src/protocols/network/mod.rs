@@ -10,6 +10,7 @@ use crate::{
         ConnectionNotification, ConnectionRequestSender, PeerManagerNotification,
         PeerManagerRequestSender,
     },
+    peer_reputation::ReputationEvent,
     transport::ConnectionMetadata,
     ProtocolId,
 };
@@ -132,7 +133,9 @@ impl<TMessage> Stream for NetworkEvents<TMessage> {
 }
 
 /// Deserialize inbound direct send and rpc messages into the application `TMessage`
-/// type, logging and dropping messages that fail to deserialize.
+/// type, logging and dropping messages that fail to deserialize. Rpc requests that fail to
+/// deserialize are answered with [`RpcError::InvalidRpcRequest`], so that the `Peer` actor
+/// reports the remote peer.
 fn peer_mgr_notif_to_event<TMessage: Message>(
     notif: PeerManagerNotification,
 ) -> future::Ready<Option<Event<TMessage>>> {
@@ -149,6 +152,7 @@ fn peer_mgr_notif_to_event<TMessage: Message>(
                         protocol_id = rpc_req.protocol_id,
                         data_prefix = hex::encode(&data[..min(16, data.len())]),
                     );
+                    let _ = rpc_req.res_tx.send(Err(RpcError::InvalidRpcRequest));
                     None
                 }
             }
@@ -246,6 +250,17 @@ impl<TMessage> NetworkSender<TMessage> {
         self.connection_reqs_tx.disconnect_peer(peer).await?;
         Ok(())
     }
+
+    /// Report a misbehavior of the given Peer to the network, which disconnects and temporarily
+    /// bans the Peer once its reputation is too low. The report is not waited for.
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        event: ReputationEvent,
+    ) -> Result<(), NetworkError> {
+        self.connection_reqs_tx.report_peer(peer, event)?;
+        Ok(())
+    }
 }
 
 impl<TMessage: Message> NetworkSender<TMessage> {
@@ -293,7 +308,15 @@ impl<TMessage: Message> NetworkSender<TMessage> {
             .peer_mgr_reqs_tx
             .send_rpc(recipient, protocol, req_data, timeout)
             .await?;
-        let res_msg: TMessage = protocol.from_bytes(&res_data)?;
-        Ok(res_msg)
+        match protocol.from_bytes(&res_data) {
+            Ok(res_msg) => Ok(res_msg),
+            Err(err) => {
+                // Reporting is best effort, the rpc failed either way.
+                let _ = self
+                    .connection_reqs_tx
+                    .report_peer(recipient, ReputationEvent::RpcDeserializationFailure);
+                Err(err.into())
+            }
+        }
     }
 }
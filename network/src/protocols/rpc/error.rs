@@ -27,6 +27,9 @@ pub enum RpcError {
     #[error("Received invalid rpc response message")]
     InvalidRpcResponse,
 
+    #[error("Received rpc request that failed to deserialize")]
+    InvalidRpcRequest,
+
     #[error("Received unexpected rpc response message; expected remote to half-close.")]
     UnexpectedRpcResponse,
 
@@ -45,6 +48,9 @@ pub enum RpcError {
     #[error("Too many pending RPCs: {0}")]
     TooManyPending(u32),
 
+    #[error("Rpc message is over the limit of {0} bytes")]
+    TooLarge(usize),
+
     #[error("Rpc timed out")]
     TimedOut,
 }
@@ -40,6 +40,12 @@
 //! We limit the number of pending inbound and outbound RPC tasks to ensure that
 //! resource usage is bounded.
 //!
+//! ## Streaming:
+//!
+//! Requests and responses are limited to the `max_message_size` rather than to
+//! the `max_frame_size`: the messages too large for a single frame are streamed
+//! over multiple frames, see [`stream`].
+//!
 //! [DiemNet wire protocol v1]: https://github.com/diem/diem/blob/main/specifications/network/messaging-v1.md
 //! [`Peer`]: crate::peer::Peer
 
@@ -51,8 +57,9 @@ use crate::{
     logging::NetworkSchema,
     peer::PeerNotification,
     peer_manager::PeerManagerError,
-    protocols::wire::messaging::v1::{
-        NetworkMessage, Priority, RequestId, RpcRequest, RpcResponse,
+    protocols::wire::{
+        compression,
+        messaging::v1::{NetworkMessage, Priority, RequestId, RpcRequest, RpcResponse},
     },
     ProtocolId,
 };
@@ -75,6 +82,7 @@ use short_hex_str::AsShortHexStr;
 use std::{cmp::PartialEq, collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
 
 pub mod error;
+pub mod stream;
 
 /// A wrapper struct for an inbound rpc request and its associated context.
 #[derive(Debug)]
@@ -178,6 +186,8 @@ pub struct InboundRpcs {
     /// Only allow this many concurrent inbound rpcs at one time from this remote
     /// peer.  New inbound requests exceeding this limit will be dropped.
     max_concurrent_inbound_rpcs: u32,
+    /// The maximum size of a request or a response, after decompression.
+    max_message_size: usize,
 }
 
 impl InboundRpcs {
@@ -187,6 +197,7 @@ impl InboundRpcs {
         remote_peer_id: PeerId,
         inbound_rpc_timeout: Duration,
         max_concurrent_inbound_rpcs: u32,
+        max_message_size: usize,
     ) -> Self {
         Self {
             network_context,
@@ -195,10 +206,12 @@ impl InboundRpcs {
             inbound_rpc_tasks: FuturesUnordered::new(),
             inbound_rpc_timeout,
             max_concurrent_inbound_rpcs,
+            max_message_size,
         }
     }
 
-    /// Handle a new inbound `RpcRequest` message off the wire.
+    /// Handle a new inbound `RpcRequest` message off the wire. Requests that fail to decompress
+    /// are dropped with [`RpcError::InvalidRpcRequest`].
     pub fn handle_inbound_request(
         &mut self,
         peer_notifs_tx: &mut diem_channel::Sender<ProtocolId, PeerNotification>,
@@ -233,30 +246,62 @@ impl InboundRpcs {
         let timer =
             counters::inbound_rpc_handler_latency(network_context, protocol_id).start_timer();
 
-        // Foward request to PeerManager for handling.
+        let raw_request = match compression::decompress(
+            protocol_id,
+            request.raw_request,
+            self.max_message_size,
+        ) {
+            Ok(raw_request) => raw_request,
+            Err(err) => {
+                warn!(
+                    NetworkSchema::new(network_context).remote_peer(&self.remote_peer_id),
+                    error = %err,
+                    "{} Failed to decompress rpc request from peer {} with protocol_id {}",
+                    network_context,
+                    self.remote_peer_id.short_str(),
+                    protocol_id,
+                );
+                counters::rpc_messages(network_context, RESPONSE_LABEL, FAILED_LABEL).inc();
+                return Err(RpcError::InvalidRpcRequest);
+            }
+        };
+
+        // Foward request to PeerManager for handling, the application only knows the
+        // uncompressed protocol.
         let (response_tx, response_rx) = oneshot::channel();
+        let upstream_protocol_id = protocol_id.uncompressed();
         let notif = PeerNotification::RecvRpc(InboundRpcRequest {
-            protocol_id,
-            data: Bytes::from(request.raw_request),
+            protocol_id: upstream_protocol_id,
+            data: Bytes::from(raw_request),
             res_tx: response_tx,
         });
-        if let Err(err) = peer_notifs_tx.push(protocol_id, notif) {
+        if let Err(err) = peer_notifs_tx.push(upstream_protocol_id, notif) {
             counters::rpc_messages(network_context, RESPONSE_LABEL, FAILED_LABEL).inc();
             return Err(err.into());
         }
 
         // Create a new task that waits for a response from the upper layer with a timeout.
+        let max_message_size = self.max_message_size;
         let inbound_rpc_task = self
             .time_service
             .timeout(self.inbound_rpc_timeout, response_rx)
             .map(move |result| {
                 // Flatten the errors
                 let maybe_response = match result {
-                    Ok(Ok(Ok(response_bytes))) => Ok(RpcResponse {
-                        request_id,
-                        priority,
-                        raw_response: Vec::from(response_bytes.as_ref()),
-                    }),
+                    // The response is compressed like the request
+                    Ok(Ok(Ok(response_bytes))) => {
+                        match compression::compress(protocol_id, &response_bytes) {
+                            Ok(raw_response) if raw_response.len() > max_message_size => {
+                                Err(RpcError::TooLarge(max_message_size))
+                            }
+                            Ok(raw_response) => Ok(RpcResponse {
+                                request_id,
+                                priority,
+                                raw_response,
+                            }),
+                            Err(err) => Err(RpcError::Error(err.into())),
+                        }
+                    }
                     Ok(Ok(Err(err))) => Err(err),
                     Ok(Err(oneshot::Canceled)) => Err(RpcError::UnexpectedResponseChannelCancel),
                     Err(timeout::Elapsed) => Err(RpcError::TimedOut),
@@ -354,6 +399,8 @@ pub struct OutboundRpcs {
     /// Only allow this many concurrent outbound rpcs at one time from this remote
     /// peer. New outbound requests exceeding this limit will be dropped.
     max_concurrent_outbound_rpcs: u32,
+    /// The maximum size of a request or a response, after decompression.
+    max_message_size: usize,
 }
 
 impl OutboundRpcs {
@@ -362,6 +409,7 @@ impl OutboundRpcs {
         time_service: TimeService,
         remote_peer_id: PeerId,
         max_concurrent_outbound_rpcs: u32,
+        max_message_size: usize,
     ) -> Self {
         Self {
             network_context,
@@ -371,6 +419,7 @@ impl OutboundRpcs {
             outbound_rpc_tasks: FuturesUnordered::new(),
             pending_outbound_rpcs: HashMap::new(),
             max_concurrent_outbound_rpcs,
+            max_message_size,
         }
     }
 
@@ -410,6 +459,21 @@ impl OutboundRpcs {
             return Err(RpcError::TooManyPending(self.max_concurrent_outbound_rpcs));
         }
 
+        let raw_request = match compression::compress(protocol_id, &request_data) {
+            Ok(raw_request) => raw_request,
+            Err(err) => {
+                counters::rpc_messages(network_context, REQUEST_LABEL, FAILED_LABEL).inc();
+                let _ =
+                    application_response_tx.send(Err(RpcError::Error(anyhow!(err.to_string()))));
+                return Err(RpcError::Error(err.into()));
+            }
+        };
+        if raw_request.len() > self.max_message_size {
+            counters::rpc_messages(network_context, REQUEST_LABEL, DECLINED_LABEL).inc();
+            let _ = application_response_tx.send(Err(RpcError::TooLarge(self.max_message_size)));
+            return Err(RpcError::TooLarge(self.max_message_size));
+        }
+
         let request_id = self.request_id_gen.next();
 
         trace!(
@@ -430,7 +494,7 @@ impl OutboundRpcs {
             protocol_id,
             request_id,
             priority: Priority::default(),
-            raw_request: Vec::from(request_data.as_ref()),
+            raw_request,
         });
         let (ack_tx, _) = oneshot::channel();
         write_reqs_tx.send((message, ack_tx)).await?;
@@ -449,17 +513,26 @@ impl OutboundRpcs {
         // A future that waits for the rpc response with a timeout. We create the
         // timeout out here to start the timer as soon as we push onto the queue
         // (as opposed to whenever it first gets polled on the queue).
-        let wait_for_response = self
-            .time_service
-            .timeout(timeout, response_rx)
-            .map(|result| {
-                // Flatten errors.
-                match result {
-                    Ok(Ok(response)) => Ok(Bytes::from(response.raw_response)),
-                    Ok(Err(oneshot::Canceled)) => Err(RpcError::UnexpectedResponseChannelCancel),
-                    Err(timeout::Elapsed) => Err(RpcError::TimedOut),
-                }
-            });
+        let max_message_size = self.max_message_size;
+        let wait_for_response =
+            self.time_service
+                .timeout(timeout, response_rx)
+                .map(move |result| {
+                    // Flatten errors.
+                    match result {
+                        Ok(Ok(response)) => compression::decompress(
+                            protocol_id,
+                            response.raw_response,
+                            max_message_size,
+                        )
+                        .map(Bytes::from)
+                        .map_err(|_| RpcError::InvalidRpcResponse),
+                        Ok(Err(oneshot::Canceled)) => {
+                            Err(RpcError::UnexpectedResponseChannelCancel)
+                        }
+                        Err(timeout::Elapsed) => Err(RpcError::TimedOut),
+                    }
+                });
 
         // A future that waits for the response and sends it to the application.
         let notify_application = async move {
@@ -517,6 +590,11 @@ impl OutboundRpcs {
     /// Handle a newly completed task from the `self.outbound_rpc_tasks` queue.
     /// At this point, the application layer's request has already been fulfilled;
     /// we just need to clean up this request and update some counters.
+    /// Whether the rpc `request_id` still waits for its response.
+    pub fn is_pending(&self, request_id: RequestId) -> bool {
+        self.pending_outbound_rpcs.contains_key(&request_id)
+    }
+
     pub fn handle_completed_request(
         &mut self,
         request_id: RequestId,
@@ -0,0 +1,569 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Streaming of the rpc messages too large for a single frame.
+//!
+//! The writer task of the [`Peer`] actor splits an [`RpcRequest`] or [`RpcResponse`] whose
+//! serialized size exceeds the `max_frame_size` into a [`StreamHeader`], carrying the message
+//! with the first part of its payload, and as many [`StreamFragment`]s as needed for the rest of
+//! it. The fragments of a stream are written right after its header, in order.
+//!
+//! The receiving [`Peer`] reassembles the message in its [`InboundStreams`] before handing it to
+//! the rpc queues, bounding the number of streams open at once, the size of the reassembled
+//! messages to the `max_message_size` and the bytes buffered by all the streams of the
+//! connection. Streams that don't complete within the inbound rpc timeout, or which carry the
+//! response to an outbound rpc that timed out, are dropped and their remaining fragments ignored.
+//!
+//! [`Peer`]: crate::peer::Peer
+//! [`RpcRequest`]: crate::protocols::wire::messaging::v1::RpcRequest
+//! [`RpcResponse`]: crate::protocols::wire::messaging::v1::RpcResponse
+
+use crate::protocols::wire::messaging::v1::{
+    NetworkMessage, RequestId, StreamFragment, StreamHeader, StreamId, StreamedMessage,
+};
+use diem_time_service::{TimeService, TimeServiceTrait};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Bytes of a frame reserved for the [`StreamHeader`] or [`StreamFragment`] around the payload.
+pub const STREAM_FRAME_OVERHEAD: usize = 64;
+
+/// Interval between two checks for the expired inbound streams.
+pub const STREAM_EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error, PartialEq)]
+pub enum StreamError {
+    #[error("Stream header without fragments for stream {0}")]
+    NoFragments(StreamId),
+
+    #[error("Stream {0} is already open")]
+    DuplicateStream(StreamId),
+
+    #[error("Too many open streams: {0}")]
+    TooManyStreams(usize),
+
+    #[error("Received fragment for unknown stream {0}")]
+    UnknownStream(StreamId),
+
+    #[error("Received fragment {actual} of stream {stream_id}, expected fragment {expected}")]
+    UnexpectedFragment {
+        stream_id: StreamId,
+        expected: u32,
+        actual: u32,
+    },
+
+    #[error("Streamed message is over the limit of {0} bytes")]
+    TooLarge(usize),
+
+    #[error("Streams are over the limit of {0} buffered bytes")]
+    BufferFull(usize),
+}
+
+/// Splits the outbound rpc messages too large for a single frame into streams.
+pub struct OutboundStreams {
+    next_stream_id: StreamId,
+    max_frame_size: usize,
+}
+
+impl OutboundStreams {
+    pub fn new(max_frame_size: usize) -> Self {
+        assert!(
+            max_frame_size > STREAM_FRAME_OVERHEAD,
+            "max_frame_size must be larger than {} bytes",
+            STREAM_FRAME_OVERHEAD
+        );
+        Self {
+            next_stream_id: 0,
+            max_frame_size,
+        }
+    }
+
+    /// Returns the frames to write for the message: the message itself if it fits in a frame, or
+    /// if it can't be streamed; a stream otherwise.
+    pub fn fragment(&mut self, message: NetworkMessage) -> Vec<NetworkMessage> {
+        match bcs::serialized_size(&message) {
+            Ok(size) if size > self.max_frame_size => (),
+            // Serialization errors are left to the writer
+            _ => return vec![message],
+        }
+        let mut streamed = match message {
+            NetworkMessage::RpcRequest(request) => StreamedMessage::RpcRequest(request),
+            NetworkMessage::RpcResponse(response) => StreamedMessage::RpcResponse(response),
+            message => return vec![message],
+        };
+
+        let stream_id = self.next_stream_id;
+        self.next_stream_id = self.next_stream_id.wrapping_add(1);
+
+        let raw_data = std::mem::take(streamed.raw_data_mut());
+        let mut chunks = raw_data.chunks(self.max_frame_size - STREAM_FRAME_OVERHEAD);
+        *streamed.raw_data_mut() = chunks.next().unwrap_or_default().to_vec();
+        let header = NetworkMessage::StreamHeader(StreamHeader {
+            stream_id,
+            num_fragments: chunks.len() as u32,
+            message: streamed,
+        });
+        let fragments = chunks.enumerate().map(|(index, chunk)| {
+            NetworkMessage::StreamFragment(StreamFragment {
+                stream_id,
+                fragment_id: index as u32 + 1,
+                raw_data: chunk.to_vec(),
+            })
+        });
+        std::iter::once(header).chain(fragments).collect()
+    }
+}
+
+struct InboundStream {
+    message: StreamedMessage,
+    num_fragments: u32,
+    next_fragment_id: u32,
+    /// The stream is dropped if it's not complete by then.
+    expires_at: Instant,
+}
+
+/// A stream dropped through no fault of the sender, whose remaining fragments are ignored.
+struct DiscardedStream {
+    num_fragments: u32,
+    /// The stream is forgotten by then, even if its last fragment never arrived.
+    expires_at: Instant,
+}
+
+/// Reassembles the inbound streams of a connection.
+pub struct InboundStreams {
+    time_service: TimeService,
+    streams: HashMap<StreamId, InboundStream>,
+    discarded: HashMap<StreamId, DiscardedStream>,
+    /// Streams opened beyond this limit are rejected.
+    max_concurrent_streams: usize,
+    /// Streams whose reassembled message exceeds this size are dropped.
+    max_message_size: usize,
+    /// Streams that would take the bytes buffered by all the streams over this limit are dropped.
+    max_buffered_bytes: usize,
+    buffered_bytes: usize,
+    /// Streams not complete within this timeout are dropped: the rpc they carry timed out.
+    stream_timeout: Duration,
+}
+
+impl InboundStreams {
+    pub fn new(
+        time_service: TimeService,
+        max_concurrent_streams: usize,
+        max_message_size: usize,
+        max_buffered_bytes: usize,
+        stream_timeout: Duration,
+    ) -> Self {
+        Self {
+            time_service,
+            streams: HashMap::new(),
+            discarded: HashMap::new(),
+            max_concurrent_streams,
+            max_message_size,
+            max_buffered_bytes,
+            buffered_bytes: 0,
+            stream_timeout,
+        }
+    }
+
+    /// Opens the stream of the header, the message is returned once all its fragments arrived.
+    pub fn handle_header(&mut self, header: StreamHeader) -> Result<(), StreamError> {
+        let StreamHeader {
+            stream_id,
+            num_fragments,
+            mut message,
+        } = header;
+        if num_fragments == 0 {
+            return Err(StreamError::NoFragments(stream_id));
+        }
+        if self.streams.contains_key(&stream_id) {
+            // The stream is dropped, the sender is misbehaving
+            self.remove_stream(stream_id);
+            return Err(StreamError::DuplicateStream(stream_id));
+        }
+        if self.streams.len() >= self.max_concurrent_streams {
+            return Err(StreamError::TooManyStreams(self.max_concurrent_streams));
+        }
+        let len = message.raw_data_mut().len();
+        if len > self.max_message_size {
+            return Err(StreamError::TooLarge(self.max_message_size));
+        }
+        if self.buffered_bytes + len > self.max_buffered_bytes {
+            return Err(StreamError::BufferFull(self.max_buffered_bytes));
+        }
+        // Stream ids wrap around
+        self.discarded.remove(&stream_id);
+        self.insert_stream(
+            stream_id,
+            InboundStream {
+                message,
+                num_fragments,
+                next_fragment_id: 1,
+                expires_at: self.time_service.now() + self.stream_timeout,
+            },
+        );
+        Ok(())
+    }
+
+    /// Ignores the stream of the header, e.g., because it carries the response to an rpc which
+    /// timed out already.
+    pub fn discard_header(&mut self, header: StreamHeader) -> Result<(), StreamError> {
+        if header.num_fragments == 0 {
+            return Err(StreamError::NoFragments(header.stream_id));
+        }
+        if self.discarded.len() >= self.max_concurrent_streams {
+            return Err(StreamError::TooManyStreams(self.max_concurrent_streams));
+        }
+        self.discard(header.stream_id, header.num_fragments);
+        Ok(())
+    }
+
+    /// Appends the fragment to its stream, returning the reassembled message after the last
+    /// fragment. The stream is dropped on any error.
+    pub fn handle_fragment(
+        &mut self,
+        fragment: StreamFragment,
+    ) -> Result<Option<StreamedMessage>, StreamError> {
+        let StreamFragment {
+            stream_id,
+            fragment_id,
+            raw_data,
+        } = fragment;
+        if let Some(discarded) = self.discarded.get(&stream_id) {
+            if fragment_id >= discarded.num_fragments {
+                self.discarded.remove(&stream_id);
+            }
+            return Ok(None);
+        }
+        let mut stream = self
+            .remove_stream(stream_id)
+            .ok_or(StreamError::UnknownStream(stream_id))?;
+        if self.time_service.now() >= stream.expires_at {
+            self.discard(stream_id, stream.num_fragments);
+            return Ok(None);
+        }
+        if fragment_id != stream.next_fragment_id {
+            return Err(StreamError::UnexpectedFragment {
+                stream_id,
+                expected: stream.next_fragment_id,
+                actual: fragment_id,
+            });
+        }
+        let payload = stream.message.raw_data_mut();
+        if payload.len() + raw_data.len() > self.max_message_size {
+            return Err(StreamError::TooLarge(self.max_message_size));
+        }
+        if self.buffered_bytes + payload.len() + raw_data.len() > self.max_buffered_bytes {
+            return Err(StreamError::BufferFull(self.max_buffered_bytes));
+        }
+        payload.extend_from_slice(&raw_data);
+
+        if fragment_id == stream.num_fragments {
+            return Ok(Some(stream.message));
+        }
+        stream.next_fragment_id += 1;
+        self.insert_stream(stream_id, stream);
+        Ok(None)
+    }
+
+    /// Drops the stream carrying the response to the rpc `request_id`, if any, once nobody waits
+    /// for it anymore. Returns the id of the dropped stream.
+    pub fn discard_response(&mut self, request_id: RequestId) -> Option<StreamId> {
+        let stream_id = *self
+            .streams
+            .iter()
+            .find(|(_, stream)| {
+                matches!(
+                    &stream.message,
+                    StreamedMessage::RpcResponse(response) if response.request_id == request_id
+                )
+            })?
+            .0;
+        let stream = self.remove_stream(stream_id)?;
+        self.discard(stream_id, stream.num_fragments);
+        Some(stream_id)
+    }
+
+    /// Drops the streams which are not complete within the `stream_timeout`, returning their ids.
+    pub fn expire(&mut self) -> Vec<StreamId> {
+        let now = self.time_service.now();
+        self.discarded
+            .retain(|_, discarded| now < discarded.expires_at);
+        let expired: Vec<_> = self
+            .streams
+            .iter()
+            .filter(|(_, stream)| now >= stream.expires_at)
+            .map(|(stream_id, _)| *stream_id)
+            .collect();
+        for stream_id in &expired {
+            if let Some(stream) = self.remove_stream(*stream_id) {
+                self.discard(*stream_id, stream.num_fragments);
+            }
+        }
+        expired
+    }
+
+    fn discard(&mut self, stream_id: StreamId, num_fragments: u32) {
+        self.discarded.insert(
+            stream_id,
+            DiscardedStream {
+                num_fragments,
+                expires_at: self.time_service.now() + self.stream_timeout,
+            },
+        );
+    }
+
+    fn insert_stream(&mut self, stream_id: StreamId, mut stream: InboundStream) {
+        self.buffered_bytes += stream.message.raw_data_mut().len();
+        self.streams.insert(stream_id, stream);
+    }
+
+    fn remove_stream(&mut self, stream_id: StreamId) -> Option<InboundStream> {
+        let mut stream = self.streams.remove(&stream_id)?;
+        self.buffered_bytes -= stream.message.raw_data_mut().len();
+        Some(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        protocols::wire::messaging::v1::{DirectSendMsg, RpcRequest, RpcResponse},
+        ProtocolId,
+    };
+    use diem_time_service::MockTimeService;
+
+    const MAX_FRAME_SIZE: usize = 1024;
+    const STREAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn inbound_streams(
+        max_concurrent_streams: usize,
+        max_message_size: usize,
+        max_buffered_bytes: usize,
+    ) -> (InboundStreams, MockTimeService) {
+        let time_service = TimeService::mock();
+        let inbound = InboundStreams::new(
+            time_service.clone(),
+            max_concurrent_streams,
+            max_message_size,
+            max_buffered_bytes,
+            STREAM_TIMEOUT,
+        );
+        (inbound, time_service.into_mock())
+    }
+
+    fn rpc_request(len: usize) -> RpcRequest {
+        RpcRequest {
+            protocol_id: ProtocolId::StateSyncRpc,
+            request_id: 7,
+            priority: 0,
+            raw_request: (0..len).map(|i| i as u8).collect(),
+        }
+    }
+
+    fn reassemble(
+        inbound: &mut InboundStreams,
+        frames: Vec<NetworkMessage>,
+    ) -> Result<Option<StreamedMessage>, StreamError> {
+        let mut reassembled = None;
+        for frame in frames {
+            reassembled = match frame {
+                NetworkMessage::StreamHeader(header) => inbound.handle_header(header).map(|_| None),
+                NetworkMessage::StreamFragment(fragment) => inbound.handle_fragment(fragment),
+                frame => panic!("Unexpected frame {:?}", frame),
+            }?;
+        }
+        Ok(reassembled)
+    }
+
+    #[test]
+    fn test_small_messages_are_not_streamed() {
+        let mut outbound = OutboundStreams::new(MAX_FRAME_SIZE);
+        let message = NetworkMessage::RpcRequest(rpc_request(MAX_FRAME_SIZE / 2));
+        assert_eq!(outbound.fragment(message.clone()), vec![message]);
+
+        // Only rpc messages are streamed
+        let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id: ProtocolId::MempoolDirectSend,
+            priority: 0,
+            raw_msg: vec![0; 4 * MAX_FRAME_SIZE],
+        });
+        assert_eq!(outbound.fragment(message.clone()), vec![message]);
+    }
+
+    #[test]
+    fn test_stream_round_trip() {
+        let mut outbound = OutboundStreams::new(MAX_FRAME_SIZE);
+        let (mut inbound, _) = inbound_streams(1, 16 * MAX_FRAME_SIZE, 16 * MAX_FRAME_SIZE);
+
+        let request = rpc_request(10 * MAX_FRAME_SIZE);
+        let frames = outbound.fragment(NetworkMessage::RpcRequest(request.clone()));
+        assert_eq!(frames.len(), 11);
+        for frame in &frames {
+            assert!(bcs::serialized_size(frame).unwrap() <= MAX_FRAME_SIZE);
+        }
+        assert_eq!(
+            reassemble(&mut inbound, frames).unwrap(),
+            Some(StreamedMessage::RpcRequest(request))
+        );
+
+        // The next stream gets another id
+        let response = RpcResponse {
+            request_id: 7,
+            priority: 0,
+            raw_response: vec![1; 2 * MAX_FRAME_SIZE],
+        };
+        let frames = outbound.fragment(NetworkMessage::RpcResponse(response.clone()));
+        assert!(matches!(
+            &frames[0],
+            NetworkMessage::StreamHeader(StreamHeader { stream_id: 1, .. })
+        ));
+        assert_eq!(
+            reassemble(&mut inbound, frames).unwrap(),
+            Some(StreamedMessage::RpcResponse(response))
+        );
+    }
+
+    #[test]
+    fn test_stream_limits() {
+        let mut outbound = OutboundStreams::new(MAX_FRAME_SIZE);
+        let (mut inbound, _) = inbound_streams(1, 4 * MAX_FRAME_SIZE, 4 * MAX_FRAME_SIZE);
+
+        // Over the message size
+        let frames = outbound.fragment(NetworkMessage::RpcRequest(rpc_request(5 * MAX_FRAME_SIZE)));
+        assert_eq!(
+            reassemble(&mut inbound, frames),
+            Err(StreamError::TooLarge(4 * MAX_FRAME_SIZE))
+        );
+        assert!(inbound.streams.is_empty());
+
+        // Over the concurrent streams
+        let mut frames =
+            outbound.fragment(NetworkMessage::RpcRequest(rpc_request(2 * MAX_FRAME_SIZE)));
+        let mut other_frames =
+            outbound.fragment(NetworkMessage::RpcRequest(rpc_request(2 * MAX_FRAME_SIZE)));
+        assert_eq!(reassemble(&mut inbound, vec![frames.remove(0)]), Ok(None));
+        assert_eq!(
+            reassemble(&mut inbound, vec![other_frames.remove(0)]),
+            Err(StreamError::TooManyStreams(1))
+        );
+
+        // Out of order fragments
+        frames.swap(0, 1);
+        assert!(matches!(
+            reassemble(&mut inbound, frames),
+            Err(StreamError::UnexpectedFragment {
+                expected: 1,
+                actual: 2,
+                ..
+            })
+        ));
+        assert!(inbound.streams.is_empty());
+    }
+
+    #[test]
+    fn test_stream_buffered_bytes() {
+        let mut outbound = OutboundStreams::new(MAX_FRAME_SIZE);
+        let (mut inbound, _) = inbound_streams(2, 4 * MAX_FRAME_SIZE, 4 * MAX_FRAME_SIZE);
+
+        let mut frames =
+            outbound.fragment(NetworkMessage::RpcRequest(rpc_request(3 * MAX_FRAME_SIZE)));
+        let other_frames =
+            outbound.fragment(NetworkMessage::RpcRequest(rpc_request(3 * MAX_FRAME_SIZE)));
+        let last_frames = frames.split_off(2);
+        assert_eq!(reassemble(&mut inbound, frames), Ok(None));
+        assert_eq!(
+            reassemble(&mut inbound, other_frames),
+            Err(StreamError::BufferFull(4 * MAX_FRAME_SIZE))
+        );
+
+        // The bytes are released once the message is reassembled
+        assert!(reassemble(&mut inbound, last_frames).unwrap().is_some());
+        assert_eq!(inbound.buffered_bytes, 0);
+    }
+
+    #[test]
+    fn test_stream_expiry() {
+        let mut outbound = OutboundStreams::new(MAX_FRAME_SIZE);
+        let (mut inbound, time_service) =
+            inbound_streams(1, 16 * MAX_FRAME_SIZE, 16 * MAX_FRAME_SIZE);
+
+        let mut frames =
+            outbound.fragment(NetworkMessage::RpcRequest(rpc_request(4 * MAX_FRAME_SIZE)));
+        let last_frames = frames.split_off(2);
+        assert_eq!(reassemble(&mut inbound, frames), Ok(None));
+        assert!(inbound.expire().is_empty());
+
+        time_service.advance(STREAM_TIMEOUT);
+        assert_eq!(inbound.expire(), vec![0]);
+        assert!(inbound.streams.is_empty());
+        assert_eq!(inbound.buffered_bytes, 0);
+
+        // The late fragments are ignored, the next stream is accepted
+        assert_eq!(reassemble(&mut inbound, last_frames), Ok(None));
+        assert!(inbound.discarded.is_empty());
+        let request = rpc_request(2 * MAX_FRAME_SIZE);
+        let frames = outbound.fragment(NetworkMessage::RpcRequest(request.clone()));
+        assert_eq!(
+            reassemble(&mut inbound, frames).unwrap(),
+            Some(StreamedMessage::RpcRequest(request))
+        );
+
+        // A stream past its deadline is dropped on its next fragment
+        let mut frames =
+            outbound.fragment(NetworkMessage::RpcRequest(rpc_request(4 * MAX_FRAME_SIZE)));
+        let last_frames = frames.split_off(2);
+        assert_eq!(reassemble(&mut inbound, frames), Ok(None));
+        time_service.advance(STREAM_TIMEOUT);
+        assert_eq!(reassemble(&mut inbound, last_frames), Ok(None));
+        assert!(inbound.streams.is_empty());
+        assert!(inbound.discarded.is_empty());
+
+        // Discarded streams whose last fragment never arrives are forgotten in the end
+        let mut frames =
+            outbound.fragment(NetworkMessage::RpcRequest(rpc_request(4 * MAX_FRAME_SIZE)));
+        frames.truncate(2);
+        assert_eq!(reassemble(&mut inbound, frames), Ok(None));
+        time_service.advance(STREAM_TIMEOUT);
+        inbound.expire();
+        assert_eq!(inbound.discarded.len(), 1);
+        time_service.advance(STREAM_TIMEOUT);
+        inbound.expire();
+        assert!(inbound.discarded.is_empty());
+    }
+
+    #[test]
+    fn test_stream_discard_response() {
+        let mut outbound = OutboundStreams::new(MAX_FRAME_SIZE);
+        let (mut inbound, _) = inbound_streams(1, 16 * MAX_FRAME_SIZE, 16 * MAX_FRAME_SIZE);
+
+        let response = RpcResponse {
+            request_id: 7,
+            priority: 0,
+            raw_response: vec![1; 4 * MAX_FRAME_SIZE],
+        };
+        let mut frames = outbound.fragment(NetworkMessage::RpcResponse(response.clone()));
+        let last_frames = frames.split_off(2);
+        assert_eq!(reassemble(&mut inbound, frames), Ok(None));
+        assert_eq!(inbound.discard_response(8), None);
+        assert_eq!(inbound.discard_response(7), Some(0));
+        assert_eq!(reassemble(&mut inbound, last_frames), Ok(None));
+
+        // The response to an rpc which timed out before its header arrived
+        let frames = outbound.fragment(NetworkMessage::RpcResponse(response));
+        for frame in frames {
+            match frame {
+                NetworkMessage::StreamHeader(header) => inbound.discard_header(header).unwrap(),
+                NetworkMessage::StreamFragment(fragment) => {
+                    assert_eq!(inbound.handle_fragment(fragment), Ok(None))
+                }
+                frame => panic!("Unexpected frame {:?}", frame),
+            }
+        }
+        assert!(inbound.streams.is_empty());
+        assert!(inbound.discarded.is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compression of the messages of the compressed [`ProtocolId`]s.
+//!
+//! Compression is negotiated per [`ProtocolId`]: with compression enabled, a node advertises in
+//! its `HandshakeMsg` the compressed counterparts of the protocols it supports, e.g.
+//! [`ProtocolId::ConsensusRpcCompressed`] for [`ProtocolId::ConsensusRpc`]. When both nodes
+//! advertised it, the [`Peer`] actor sends the messages of the protocol over its compressed
+//! counterpart, compressing them before they are written on the wire, rpc responses included.
+//! The receiving [`Peer`] decompresses them and hands them to the application under the
+//! uncompressed protocol, so the applications are unaware of the compression.
+//!
+//! Decompressed messages can't be larger than the `max_frame_size`, or the `max_message_size` for
+//! rpc messages, so that a small compressed frame can't be used to allocate an unbounded amount of
+//! memory.
+//!
+//! [`Peer`]: crate::peer::Peer
+
+use crate::ProtocolId;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    #[error("Failed to compress message: {0}")]
+    Compress(io::Error),
+
+    #[error("Failed to decompress message: {0}")]
+    Decompress(io::Error),
+
+    #[error("Decompressed message is over the limit of {0} bytes")]
+    TooLarge(usize),
+}
+
+/// Compresses the message if its protocol is compressed.
+pub fn compress(protocol_id: ProtocolId, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    if !protocol_id.is_compressed() {
+        return Ok(data.to_vec());
+    }
+    // The fast level saves most of the bandwidth of the highly redundant BCS payloads, for a
+    // fraction of the CPU of the default level.
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    encoder
+        .write_all(data)
+        .map_err(CompressionError::Compress)?;
+    encoder.finish().map_err(CompressionError::Compress)
+}
+
+/// Decompresses the message if its protocol is compressed, failing once more than `max_size`
+/// bytes are decompressed.
+pub fn decompress(
+    protocol_id: ProtocolId,
+    data: Vec<u8>,
+    max_size: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    if !protocol_id.is_compressed() {
+        return Ok(data);
+    }
+    let mut decompressed = Vec::new();
+    DeflateDecoder::new(data.as_slice())
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(CompressionError::Decompress)?;
+    if decompressed.len() > max_size {
+        return Err(CompressionError::TooLarge(max_size));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..64 * 1024).map(|i| (i % 7) as u8).collect();
+        let compressed = compress(ProtocolId::StateSyncRpcCompressed, &data).unwrap();
+        assert!(compressed.len() < data.len() / 10);
+        assert_eq!(
+            decompress(ProtocolId::StateSyncRpcCompressed, compressed, data.len()).unwrap(),
+            data
+        );
+
+        // Uncompressed protocols are untouched
+        assert_eq!(compress(ProtocolId::StateSyncRpc, &data).unwrap(), data);
+        assert_eq!(
+            decompress(ProtocolId::StateSyncRpc, data.clone(), 0).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_decompression_bomb() {
+        let data = vec![0u8; 1024 * 1024];
+        let compressed = compress(ProtocolId::ConsensusDirectSendCompressed, &data).unwrap();
+        assert!(matches!(
+            decompress(
+                ProtocolId::ConsensusDirectSendCompressed,
+                compressed,
+                data.len() - 1
+            ),
+            Err(CompressionError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(matches!(
+            decompress(
+                ProtocolId::ConsensusRpcCompressed,
+                vec![0xff; 32],
+                1024 * 1024
+            ),
+            Err(CompressionError::Decompress(_))
+        ));
+    }
+}
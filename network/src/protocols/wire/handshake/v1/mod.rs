@@ -42,6 +42,14 @@ pub enum ProtocolId {
     HealthCheckerRpc = 5,
     // json provides flexibility for backwards compatible upgrade
     ConsensusDirectSendJSON = 6,
+    StateSyncRpc = 7,
+    ConsensusObserverDirectSend = 8,
+    // compressed counterparts of the protocols with large messages, see `wire::compression`
+    ConsensusRpcCompressed = 9,
+    ConsensusDirectSendCompressed = 10,
+    ConsensusDirectSendJSONCompressed = 11,
+    StateSyncDirectSendCompressed = 12,
+    StateSyncRpcCompressed = 13,
 }
 
 impl ProtocolId {
@@ -55,6 +63,13 @@ impl ProtocolId {
             DiscoveryDirectSend => "DiscoveryDirectSend",
             HealthCheckerRpc => "HealthCheckerRpc",
             ConsensusDirectSendJSON => "ConsensusDirectSendJson",
+            StateSyncRpc => "StateSyncRpc",
+            ConsensusObserverDirectSend => "ConsensusObserverDirectSend",
+            ConsensusRpcCompressed => "ConsensusRpcCompressed",
+            ConsensusDirectSendCompressed => "ConsensusDirectSendCompressed",
+            ConsensusDirectSendJSONCompressed => "ConsensusDirectSendJsonCompressed",
+            StateSyncDirectSendCompressed => "StateSyncDirectSendCompressed",
+            StateSyncRpcCompressed => "StateSyncRpcCompressed",
         }
     }
 
@@ -67,11 +82,49 @@ impl ProtocolId {
             ProtocolId::DiscoveryDirectSend,
             ProtocolId::HealthCheckerRpc,
             ProtocolId::ConsensusDirectSendJSON,
+            ProtocolId::StateSyncRpc,
+            ProtocolId::ConsensusObserverDirectSend,
+            ProtocolId::ConsensusRpcCompressed,
+            ProtocolId::ConsensusDirectSendCompressed,
+            ProtocolId::ConsensusDirectSendJSONCompressed,
+            ProtocolId::StateSyncDirectSendCompressed,
+            ProtocolId::StateSyncRpcCompressed,
         ]
     }
 
-    pub fn to_bytes<T: Serialize>(&self, value: &T) -> anyhow::Result<Vec<u8>> {
+    /// The compressed counterpart of the protocol, if it has one.
+    pub fn compressed(self) -> Option<ProtocolId> {
+        use ProtocolId::*;
+        match self {
+            ConsensusRpc => Some(ConsensusRpcCompressed),
+            ConsensusDirectSend => Some(ConsensusDirectSendCompressed),
+            ConsensusDirectSendJSON => Some(ConsensusDirectSendJSONCompressed),
+            StateSyncDirectSend => Some(StateSyncDirectSendCompressed),
+            StateSyncRpc => Some(StateSyncRpcCompressed),
+            _ => None,
+        }
+    }
+
+    /// The protocol the compressed protocol is the counterpart of, the protocol itself otherwise.
+    pub fn uncompressed(self) -> ProtocolId {
+        use ProtocolId::*;
         match self {
+            ConsensusRpcCompressed => ConsensusRpc,
+            ConsensusDirectSendCompressed => ConsensusDirectSend,
+            ConsensusDirectSendJSONCompressed => ConsensusDirectSendJSON,
+            StateSyncDirectSendCompressed => StateSyncDirectSend,
+            StateSyncRpcCompressed => StateSyncRpc,
+            protocol_id => protocol_id,
+        }
+    }
+
+    /// Returns true if the messages of the protocol are compressed on the wire.
+    pub fn is_compressed(self) -> bool {
+        self.uncompressed() != self
+    }
+
+    pub fn to_bytes<T: Serialize>(&self, value: &T) -> anyhow::Result<Vec<u8>> {
+        match self.uncompressed() {
             ProtocolId::ConsensusDirectSendJSON => {
                 serde_json::to_vec(value).map_err(|e| anyhow!("{:?}", e))
             }
@@ -80,7 +133,7 @@ impl ProtocolId {
     }
 
     pub fn from_bytes<'a, T: Deserialize<'a>>(&self, bytes: &'a [u8]) -> anyhow::Result<T> {
-        match self {
+        match self.uncompressed() {
             ProtocolId::ConsensusDirectSendJSON => {
                 serde_json::from_slice(bytes).map_err(|e| anyhow!("{:?}", e))
             }
@@ -28,7 +28,7 @@ use std::{
 };
 use thiserror::Error;
 use tokio_util::{
-    codec::{FramedRead, FramedWrite, LengthDelimitedCodec},
+    codec::{FramedRead, FramedWrite, LengthDelimitedCodec, LengthDelimitedCodecError},
     compat::{Compat, FuturesAsyncReadCompatExt, FuturesAsyncWriteCompatExt},
 };
 
@@ -43,6 +43,8 @@ pub enum NetworkMessage {
     RpcRequest(RpcRequest),
     RpcResponse(RpcResponse),
     DirectSendMsg(DirectSendMsg),
+    StreamHeader(StreamHeader),
+    StreamFragment(StreamFragment),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -123,6 +125,52 @@ pub struct DirectSendMsg {
     pub raw_msg: Vec<u8>,
 }
 
+/// Create alias StreamId for u32.
+pub type StreamId = u32;
+
+/// Rpc messages too large for a single frame are streamed: a `StreamHeader` carrying the message
+/// with the first part of its payload, followed by `StreamFragment`s carrying the rest of it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct StreamHeader {
+    /// StreamId identifying the stream among the open streams of the connection.
+    pub stream_id: StreamId,
+    /// Number of `StreamFragment`s following the header.
+    pub num_fragments: u32,
+    /// The streamed message, its payload truncated to the first part.
+    pub message: StreamedMessage,
+}
+
+/// The messages that can be streamed over multiple frames.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub enum StreamedMessage {
+    RpcRequest(RpcRequest),
+    RpcResponse(RpcResponse),
+}
+
+impl StreamedMessage {
+    /// The payload of the message.
+    pub fn raw_data_mut(&mut self) -> &mut Vec<u8> {
+        match self {
+            StreamedMessage::RpcRequest(request) => &mut request.raw_request,
+            StreamedMessage::RpcResponse(response) => &mut response.raw_response,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct StreamFragment {
+    /// StreamId of the corresponding `StreamHeader`.
+    pub stream_id: StreamId,
+    /// Index of the fragment in the stream, starting at 1 for the first fragment after the header.
+    pub fragment_id: u32,
+    /// Next part of the payload of the streamed message.
+    #[serde(with = "serde_bytes")]
+    pub raw_data: Vec<u8>,
+}
+
 /// Errors from reading and deserializing network messages off the wire.
 #[derive(Debug, Error)]
 pub enum ReadError {
@@ -133,6 +181,18 @@ pub enum ReadError {
     IoError(#[from] io::Error),
 }
 
+impl ReadError {
+    /// Returns true if the frame codec rejected a frame over the `max_frame_size`.
+    pub fn is_frame_size_violation(&self) -> bool {
+        match self {
+            ReadError::IoError(err) => err
+                .get_ref()
+                .map_or(false, |inner| inner.is::<LengthDelimitedCodecError>()),
+            ReadError::DeserializeError(..) => false,
+        }
+    }
+}
+
 /// Errors from serializing and sending network messages on the wire.
 #[derive(Debug, Error)]
 pub enum WriteError {
@@ -119,7 +119,17 @@ fn recv_fails_when_larger_than_frame_limit() {
     let f_recv = message_rx.next();
 
     let (_, res_message) = block_on(future::join(f_send, f_recv));
-    res_message.unwrap().unwrap_err();
+    let err = res_message.unwrap().unwrap_err();
+    assert!(err.is_frame_size_violation());
+}
+
+#[test]
+fn other_invalid_data_is_not_frame_size_violation() {
+    let err = ReadError::IoError(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "bad noise frame",
+    ));
+    assert!(!err.is_frame_size_violation());
 }
 
 fn arb_rpc_request(max_frame_size: usize) -> impl Strategy<Value = RpcRequest> {
@@ -14,12 +14,21 @@
 //!
 //! Internally both the client and server leverage a NetworkStream that communications in blocks
 //! where a block is a length prefixed array of bytes.
+//!
+//! Optionally, the client and server authenticate each other and encrypt the blocks using a Noise
+//! IK handshake upon connection: the client must know the static public key of the server and the
+//! server only accepts the clients whose static public keys it trusts.
 
+use diem_crypto::{
+    noise::{self, NoiseConfig, NoiseError, NoiseSession},
+    x25519,
+};
 use diem_logger::{info, trace, warn, Schema};
 use diem_secure_push_metrics::{register_int_counter_vec, IntCounterVec};
 use once_cell::sync::Lazy;
 use serde::Serialize;
 use std::{
+    collections::HashSet,
     io::{Read, Write},
     net::{Shutdown, SocketAddr, TcpListener, TcpStream},
     thread, time,
@@ -55,6 +64,7 @@ enum LogEvent {
     ConnectionAttempt,
     ConnectionSuccessful,
     ConnectionFailed,
+    HandshakeFailed,
     DisconnectedPeerOnRead,
     DisconnectedPeerOnWrite,
     Shutdown,
@@ -140,12 +150,22 @@ pub enum Error {
     DataTooLarge(usize),
     #[error("Internal network error:")]
     NetworkError(#[from] std::io::Error),
+    #[error("Noise error: {0}")]
+    NoiseError(#[from] NoiseError),
     #[error("No active stream")]
     NoActiveStream,
     #[error("Overflow error: {0}")]
     OverflowError(String),
     #[error("Remote stream cleanly closed")]
     RemoteStreamClosed,
+    #[error("Unknown peer with public key: {0}")]
+    UnknownPeer(x25519::PublicKey),
+}
+
+/// The prologue binds the Noise handshakes to the service, so that a client can't be redirected
+/// to another service using the same keys.
+fn prologue(service: &'static str) -> Vec<u8> {
+    format!("diem-secure-net-{}", service).into_bytes()
 }
 
 pub struct NetworkClient {
@@ -154,6 +174,9 @@ pub struct NetworkClient {
     stream: Option<NetworkStream>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    /// The Noise config of the client and the static public key of the server, if the stream is
+    /// authenticated and encrypted.
+    noise: Option<(NoiseConfig, x25519::PublicKey)>,
 }
 
 impl NetworkClient {
@@ -163,6 +186,21 @@ impl NetworkClient {
             server,
             stream: None,
             timeout_ms,
+            noise: None,
+        }
+    }
+
+    /// Creates a client that performs a Noise IK handshake with the server upon connection.
+    pub fn new_with_noise(
+        service: &'static str,
+        server: SocketAddr,
+        timeout_ms: u64,
+        private_key: x25519::PrivateKey,
+        server_public_key: x25519::PublicKey,
+    ) -> Self {
+        Self {
+            noise: Some((NoiseConfig::new(private_key), server_public_key)),
+            ..Self::new(service, server, timeout_ms)
         }
     }
 
@@ -257,7 +295,23 @@ impl NetworkClient {
 
             let stream = stream?;
             stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, self.server, self.timeout_ms));
+            let mut stream = NetworkStream::new(stream, self.server, self.timeout_ms);
+            if let Some((noise_config, server_public_key)) = &self.noise {
+                if let Err(err) =
+                    stream.upgrade_client(noise_config, *server_public_key, self.service)
+                {
+                    self.increment_counter(Method::Connect, MethodResult::Failure);
+                    warn!(SecureNetLogSchema::new(
+                        self.service,
+                        NetworkMode::Client,
+                        LogEvent::HandshakeFailed,
+                    )
+                    .error(&err)
+                    .remote_peer(&self.server));
+                    return Err(err);
+                }
+            }
+            self.stream = Some(stream);
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -277,6 +331,9 @@ pub struct NetworkServer {
     stream: Option<NetworkStream>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    /// The Noise config of the server and the static public keys of the trusted clients, if the
+    /// stream is authenticated and encrypted.
+    noise: Option<(NoiseConfig, HashSet<x25519::PublicKey>)>,
 }
 
 impl NetworkServer {
@@ -287,6 +344,22 @@ impl NetworkServer {
             listener: Some(listener.unwrap()),
             stream: None,
             timeout_ms,
+            noise: None,
+        }
+    }
+
+    /// Creates a server that performs a Noise IK handshake with the clients upon connection and
+    /// rejects the ones that are not trusted.
+    pub fn new_with_noise(
+        service: &'static str,
+        listen: SocketAddr,
+        timeout_ms: u64,
+        private_key: x25519::PrivateKey,
+        trusted_peers: HashSet<x25519::PublicKey>,
+    ) -> Self {
+        Self {
+            noise: Some((NoiseConfig::new(private_key), trusted_peers)),
+            ..Self::new(service, listen, timeout_ms)
         }
     }
 
@@ -390,6 +463,24 @@ impl NetworkServer {
                 }
             };
 
+            stream.set_nodelay(true)?;
+            let mut stream = NetworkStream::new(stream, stream_addr, self.timeout_ms);
+            if let Some((noise_config, trusted_peers)) = &self.noise {
+                if let Err(err) = stream.upgrade_server(noise_config, trusted_peers, self.service) {
+                    self.increment_counter(Method::Connect, MethodResult::Failure);
+                    warn!(SecureNetLogSchema::new(
+                        self.service,
+                        NetworkMode::Server,
+                        LogEvent::HandshakeFailed,
+                    )
+                    .error(&err)
+                    .remote_peer(&stream_addr));
+                    // Best effort, the client also gives up on a failed handshake
+                    let _ = stream.shutdown();
+                    return Err(err);
+                }
+            }
+
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -398,19 +489,22 @@ impl NetworkServer {
             )
             .remote_peer(&stream_addr));
 
-            stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, stream_addr, self.timeout_ms));
+            self.stream = Some(stream);
         }
 
         self.stream.as_mut().ok_or(Error::NoActiveStream)
     }
 }
 
+/// The maximum plaintext of a Noise message, larger blocks are encrypted in several messages.
+const MAX_NOISE_PLAINTEXT_LEN: usize = noise::MAX_SIZE_NOISE_MSG - noise::AES_GCM_TAGLEN;
+
 struct NetworkStream {
     stream: TcpStream,
     remote: SocketAddr,
     buffer: Vec<u8>,
     temp_buffer: [u8; 1024],
+    session: Option<NoiseSession>,
 }
 
 impl NetworkStream {
@@ -425,11 +519,75 @@ impl NetworkStream {
             remote,
             buffer: Vec::new(),
             temp_buffer: [0; 1024],
+            session: None,
         }
     }
 
+    /// Performs the initiator side of the Noise IK handshake, the following blocks are encrypted.
+    fn upgrade_client(
+        &mut self,
+        noise_config: &NoiseConfig,
+        server_public_key: x25519::PublicKey,
+        service: &'static str,
+    ) -> Result<(), Error> {
+        let mut init_message = vec![0; noise::handshake_init_msg_len(0)];
+        let handshake_state = noise_config.initiate_connection(
+            &mut rand::rngs::OsRng,
+            &prologue(service),
+            server_public_key,
+            None,
+            &mut init_message,
+        )?;
+        self.write(&init_message)?;
+        let response = self.read()?;
+        let (_, session) = noise_config.finalize_connection(handshake_state, &response)?;
+        self.session = Some(session);
+        Ok(())
+    }
+
+    /// Performs the responder side of the Noise IK handshake, the following blocks are encrypted.
+    fn upgrade_server(
+        &mut self,
+        noise_config: &NoiseConfig,
+        trusted_peers: &HashSet<x25519::PublicKey>,
+        service: &'static str,
+    ) -> Result<(), Error> {
+        let init_message = self.read()?;
+        let (remote_public_key, handshake_state, _) =
+            noise_config.parse_client_init_message(&prologue(service), &init_message)?;
+        if !trusted_peers.contains(&remote_public_key) {
+            return Err(Error::UnknownPeer(remote_public_key));
+        }
+        let mut response = vec![0; noise::handshake_resp_msg_len(0)];
+        let session = noise_config.respond_to_client(
+            &mut rand::rngs::OsRng,
+            handshake_state,
+            None,
+            &mut response,
+        )?;
+        self.write(&response)?;
+        self.session = Some(session);
+        Ok(())
+    }
+
     /// Blocking read until able to successfully read an entire message
     pub fn read(&mut self) -> Result<Vec<u8>, Error> {
+        let data = self.read_block()?;
+        match &mut self.session {
+            Some(session) => {
+                let mut data = data;
+                let mut plaintext = Vec::with_capacity(data.len());
+                for message in data.chunks_mut(noise::MAX_SIZE_NOISE_MSG) {
+                    plaintext.extend_from_slice(session.read_message_in_place(message)?);
+                }
+                Ok(plaintext)
+            }
+            None => Ok(data),
+        }
+    }
+
+    /// Blocking read until able to successfully read an entire block
+    fn read_block(&mut self) -> Result<Vec<u8>, Error> {
         let result = self.read_buffer();
         if !result.is_empty() {
             return Ok(result);
@@ -459,6 +617,30 @@ impl NetworkStream {
 
     /// Blocking write until able to successfully send an entire message
     pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        match &mut self.session {
+            Some(session) => {
+                // An empty message is still sent as a Noise message to be authenticated
+                let messages: Vec<&[u8]> = if data.is_empty() {
+                    vec![data]
+                } else {
+                    data.chunks(MAX_NOISE_PLAINTEXT_LEN).collect()
+                };
+                let mut ciphertext =
+                    Vec::with_capacity(data.len() + messages.len() * noise::AES_GCM_TAGLEN);
+                for message in messages {
+                    let mut message = message.to_vec();
+                    let tag = session.write_message_in_place(&mut message)?;
+                    ciphertext.extend(message);
+                    ciphertext.extend(tag);
+                }
+                self.write_block(&ciphertext)
+            }
+            None => self.write_block(data),
+        }
+    }
+
+    /// Blocking write until able to successfully send an entire block
+    fn write_block(&mut self, data: &[u8]) -> Result<(), Error> {
         let u32_max = u32::max_value() as usize;
         if u32_max <= data.len() {
             return Err(Error::DataTooLarge(data.len()));
@@ -518,6 +700,8 @@ impl NetworkStream {
 mod test {
     use super::*;
     use diem_config::utils;
+    use diem_crypto::Uniform;
+    use rand::{rngs::StdRng, SeedableRng};
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
     /// Read, Write, Connect timeout in milliseconds.
@@ -656,4 +840,66 @@ mod test {
         let result2 = server2.read().unwrap();
         assert_eq!(data2, result2);
     }
+
+    #[test]
+    fn test_noise_ping() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let server_key = x25519::PrivateKey::generate(&mut rng);
+        let server_public_key = server_key.public_key();
+        let client_key = x25519::PrivateKey::generate(&mut rng);
+        let trusted_peers = vec![client_key.public_key()].into_iter().collect();
+
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server =
+            NetworkServer::new_with_noise("test", server_addr, TIMEOUT, server_key, trusted_peers);
+        // The handshake blocks the client until the server accepts the connection
+        let client = thread::spawn(move || {
+            let mut client = NetworkClient::new_with_noise(
+                "test",
+                server_addr,
+                TIMEOUT,
+                client_key,
+                server_public_key,
+            );
+            client.write(&[0, 1, 2, 3]).unwrap();
+            client.read().unwrap()
+        });
+
+        let result = server.read().unwrap();
+        assert_eq!(vec![0, 1, 2, 3], result);
+        // Blocks larger than a Noise message are split
+        let data = vec![7; 3 * noise::MAX_SIZE_NOISE_MSG];
+        server.write(&data).unwrap();
+        assert_eq!(data, client.join().unwrap());
+    }
+
+    #[test]
+    fn test_noise_unknown_peer() {
+        let mut rng = StdRng::from_seed([1u8; 32]);
+        let server_key = x25519::PrivateKey::generate(&mut rng);
+        let server_public_key = server_key.public_key();
+        let client_key = x25519::PrivateKey::generate(&mut rng);
+        let trusted_peers = vec![x25519::PrivateKey::generate(&mut rng).public_key()]
+            .into_iter()
+            .collect();
+
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server =
+            NetworkServer::new_with_noise("test", server_addr, TIMEOUT, server_key, trusted_peers);
+        let client = thread::spawn(move || {
+            let mut client = NetworkClient::new_with_noise(
+                "test",
+                server_addr,
+                TIMEOUT,
+                client_key,
+                server_public_key,
+            );
+            client.write(&[0, 1, 2, 3])
+        });
+
+        assert!(matches!(server.read(), Err(Error::UnknownPeer(_))));
+        assert!(client.join().unwrap().is_err());
+    }
 }
@@ -10,7 +10,7 @@ use consensus_notifications::ConsensusNotificationListener;
 use diem_config::{config::NodeConfig, network_id::NodeNetworkId};
 use diem_types::waypoint::Waypoint;
 use executor_types::ChunkExecutor;
-use futures::channel::mpsc;
+use futures::channel::{mpsc, oneshot};
 use mempool_notifications::MempoolNotificationSender;
 use std::{boxed::Box, collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
@@ -25,6 +25,9 @@ pub struct StateSyncBootstrapper {
 }
 
 impl StateSyncBootstrapper {
+    /// Starts state sync. If `state_snapshot_synced` is given, state sync only reads the local
+    /// storage and starts syncing once it fires, so that it continues from the state snapshot
+    /// being synced in the meantime (or from wherever the storage was if that failed).
     pub fn bootstrap<M: MempoolNotificationSender + 'static>(
         network: Vec<(NodeNetworkId, StateSyncSender, StateSyncEvents)>,
         mempool_notifier: M,
@@ -34,6 +37,7 @@ impl StateSyncBootstrapper {
         node_config: &NodeConfig,
         waypoint: Waypoint,
         reconfig_event_subscriptions: Vec<ReconfigSubscription>,
+        state_snapshot_synced: Option<oneshot::Receiver<()>>,
     ) -> Self {
         let runtime = Builder::new_multi_thread()
             .thread_name("state-sync")
@@ -41,16 +45,50 @@ impl StateSyncBootstrapper {
             .build()
             .expect("[State Sync] Failed to create runtime!");
 
-        let executor_proxy = ExecutorProxy::new(storage, executor, reconfig_event_subscriptions);
-        Self::bootstrap_with_executor_proxy(
-            runtime,
-            network,
-            mempool_notifier,
-            consensus_listener,
-            node_config,
-            waypoint,
-            executor_proxy,
-        )
+        let state_snapshot_synced = match state_snapshot_synced {
+            Some(state_snapshot_synced) => state_snapshot_synced,
+            None => {
+                let executor_proxy =
+                    ExecutorProxy::new(storage, executor, reconfig_event_subscriptions);
+                return Self::bootstrap_with_executor_proxy(
+                    runtime,
+                    network,
+                    mempool_notifier,
+                    consensus_listener,
+                    node_config,
+                    waypoint,
+                    executor_proxy,
+                );
+            }
+        };
+
+        // Clients can send requests right away, they are processed once the coordinator starts.
+        let (coordinator_sender, coordinator_receiver) = mpsc::unbounded();
+        let node_config = node_config.clone();
+        runtime.spawn(async move {
+            // The sender is dropped if the state snapshot sync task died, which ends it as well.
+            let _ = state_snapshot_synced.await;
+            // The executor proxy publishes the initial on-chain configs, which must be read from
+            // the synced state.
+            let executor_proxy =
+                ExecutorProxy::new(storage, executor, reconfig_event_subscriptions);
+            Self::create_coordinator(
+                coordinator_receiver,
+                mempool_notifier,
+                consensus_listener,
+                &network,
+                &node_config,
+                waypoint,
+                executor_proxy,
+            )
+            .start(network)
+            .await;
+        });
+
+        Self {
+            _runtime: runtime,
+            coordinator_sender,
+        }
     }
 
     pub fn bootstrap_with_executor_proxy<
@@ -66,6 +104,35 @@ impl StateSyncBootstrapper {
         executor_proxy: E,
     ) -> Self {
         let (coordinator_sender, coordinator_receiver) = mpsc::unbounded();
+        let coordinator = Self::create_coordinator(
+            coordinator_receiver,
+            mempool_notifier,
+            consensus_listener,
+            &network,
+            node_config,
+            waypoint,
+            executor_proxy,
+        );
+        runtime.spawn(coordinator.start(network));
+
+        Self {
+            _runtime: runtime,
+            coordinator_sender,
+        }
+    }
+
+    fn create_coordinator<
+        E: ExecutorProxyTrait + 'static,
+        M: MempoolNotificationSender + 'static,
+    >(
+        coordinator_receiver: mpsc::UnboundedReceiver<CoordinatorMessage>,
+        mempool_notifier: M,
+        consensus_listener: ConsensusNotificationListener,
+        network: &[(NodeNetworkId, StateSyncSender, StateSyncEvents)],
+        node_config: &NodeConfig,
+        waypoint: Waypoint,
+        executor_proxy: E,
+    ) -> StateSyncCoordinator<E, M> {
         let initial_state = executor_proxy
             .get_local_storage_state()
             .expect("[State Sync] Starting failure: cannot sync with storage!");
@@ -74,7 +141,7 @@ impl StateSyncBootstrapper {
             .map(|(network_id, sender, _events)| (network_id.clone(), sender.clone()))
             .collect();
 
-        let coordinator = StateSyncCoordinator::new(
+        StateSyncCoordinator::new(
             coordinator_receiver,
             mempool_notifier,
             consensus_listener,
@@ -84,13 +151,7 @@ impl StateSyncBootstrapper {
             executor_proxy,
             initial_state,
         )
-        .expect("[State Sync] Unable to create state sync coordinator!");
-        runtime.spawn(coordinator.start(network));
-
-        Self {
-            _runtime: runtime,
-            coordinator_sender,
-        }
+        .expect("[State Sync] Unable to create state sync coordinator!")
     }
 
     pub fn create_client(&self) -> StateSyncClient {
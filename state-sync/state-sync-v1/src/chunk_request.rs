@@ -130,3 +130,13 @@ impl fmt::Display for GetChunkRequest {
         )
     }
 }
+
+/// The type of data requested by a chunk request. It is carried by the type of the network
+/// message rather than by the request itself, for backward compatibility.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChunkType {
+    /// Transactions to be executed by the requester.
+    Transactions,
+    /// Transaction outputs to be applied by the requester without executing the transactions.
+    TransactionOutputs,
+}
@@ -3,7 +3,7 @@
 
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
-    transaction::{TransactionListWithProof, Version},
+    transaction::{Transaction, TransactionListWithProof, TransactionOutputListWithProof, Version},
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -83,25 +83,145 @@ impl fmt::Debug for GetChunkResponse {
 
 impl fmt::Display for GetChunkResponse {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let txns_repr = match self.txn_list_with_proof.first_transaction_version {
-            None => "empty".to_string(),
-            Some(first_version) => {
-                let last_version = first_version
-                    .checked_add(self.txn_list_with_proof.len() as u64)
-                    .and_then(|v| v.checked_sub(1)) // last_version = first_version + txns.len() - 1
-                    .map(|v| v.to_string())
-                    .unwrap_or_else(|| "Last version has overflown!".into());
-                format!("versions [{} - {}]", first_version, last_version)
+        write!(
+            f,
+            "[ChunkResponse: response li: {}, txns: {}]",
+            self.response_li,
+            versions_repr(
+                self.txn_list_with_proof.first_transaction_version,
+                self.txn_list_with_proof.len()
+            ),
+        )
+    }
+}
+
+/// The same as `GetChunkResponse`, but carrying transaction outputs that can be applied without
+/// executing the transactions.
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GetOutputChunkResponse {
+    /// The proofs are built relative to the LedgerInfo in `response_li`.
+    /// The specifics of ledger info verification depend on its type.
+    pub response_li: ResponseLedgerInfo,
+    /// Chunk of transaction outputs with proof corresponding to the ledger info carried by the
+    /// response.
+    pub output_list_with_proof: TransactionOutputListWithProof,
+}
+
+impl GetOutputChunkResponse {
+    pub fn new(
+        response_li: ResponseLedgerInfo,
+        output_list_with_proof: TransactionOutputListWithProof,
+    ) -> Self {
+        Self {
+            response_li,
+            output_list_with_proof,
+        }
+    }
+}
+
+impl fmt::Debug for GetOutputChunkResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for GetOutputChunkResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[OutputChunkResponse: response li: {}, txn outputs: {}]",
+            self.response_li,
+            versions_repr(
+                self.output_list_with_proof.first_transaction_output_version,
+                self.output_list_with_proof.len()
+            ),
+        )
+    }
+}
+
+/// A chunk response of either type, as processed by the coordinator.
+#[derive(Clone, Eq, PartialEq)]
+pub enum ChunkResponse {
+    Transactions(GetChunkResponse),
+    TransactionOutputs(GetOutputChunkResponse),
+}
+
+impl ChunkResponse {
+    pub fn response_li(&self) -> &ResponseLedgerInfo {
+        match self {
+            ChunkResponse::Transactions(response) => &response.response_li,
+            ChunkResponse::TransactionOutputs(response) => &response.response_li,
+        }
+    }
+
+    /// The version of the first transaction in the chunk, or `None` if the chunk is empty.
+    pub fn first_version(&self) -> Option<Version> {
+        match self {
+            ChunkResponse::Transactions(response) => {
+                response.txn_list_with_proof.first_transaction_version
+            }
+            ChunkResponse::TransactionOutputs(response) => {
+                response
+                    .output_list_with_proof
+                    .first_transaction_output_version
+            }
+        }
+    }
+
+    /// The number of transactions in the chunk.
+    pub fn len(&self) -> usize {
+        match self {
+            ChunkResponse::Transactions(response) => response.txn_list_with_proof.len(),
+            ChunkResponse::TransactionOutputs(response) => response.output_list_with_proof.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The transactions in the chunk.
+    pub fn transactions(&self) -> Vec<Transaction> {
+        match self {
+            ChunkResponse::Transactions(response) => {
+                response.txn_list_with_proof.transactions.clone()
             }
-        };
-        let response_li_repr = match &self.response_li {
+            ChunkResponse::TransactionOutputs(response) => response
+                .output_list_with_proof
+                .transactions_and_outputs
+                .iter()
+                .map(|(txn, _)| txn.clone())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Debug for ChunkResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for ChunkResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChunkResponse::Transactions(response) => write!(f, "{}", response),
+            ChunkResponse::TransactionOutputs(response) => write!(f, "{}", response),
+        }
+    }
+}
+
+impl fmt::Display for ResponseLedgerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
             ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
-                format!("[verifiable LI {}]", li.ledger_info())
+                write!(f, "[verifiable LI {}]", li.ledger_info())
             }
             ResponseLedgerInfo::ProgressiveLedgerInfo {
                 target_li,
                 highest_li,
-            } => format!(
+            } => write!(
+                f,
                 "[progressive LI: target LI {}, highest LI {}]",
                 target_li.ledger_info(),
                 highest_li.as_ref().unwrap_or(target_li).ledger_info(),
@@ -109,18 +229,28 @@ impl fmt::Display for GetChunkResponse {
             ResponseLedgerInfo::LedgerInfoForWaypoint {
                 waypoint_li,
                 end_of_epoch_li,
-            } => format!(
+            } => write!(
+                f,
                 "[waypoint LI {}, end of epoch LI {}]",
                 waypoint_li.ledger_info(),
                 end_of_epoch_li
                     .as_ref()
                     .map_or("None".to_string(), |li| li.ledger_info().to_string())
             ),
-        };
-        write!(
-            f,
-            "[ChunkResponse: response li: {}, txns: {}]",
-            response_li_repr, txns_repr,
-        )
+        }
+    }
+}
+
+fn versions_repr(first_version: Option<Version>, len: usize) -> String {
+    match first_version {
+        None => "empty".to_string(),
+        Some(first_version) => {
+            let last_version = first_version
+                .checked_add(len as u64)
+                .and_then(|v| v.checked_sub(1)) // last_version = first_version + txns.len() - 1
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "Last version has overflown!".into());
+            format!("versions [{} - {}]", first_version, last_version)
+        }
     }
 }
@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    chunk_request::{GetChunkRequest, TargetType},
-    chunk_response::{GetChunkResponse, ResponseLedgerInfo},
+    chunk_request::{ChunkType, GetChunkRequest, TargetType},
+    chunk_response::{ChunkResponse, GetChunkResponse, GetOutputChunkResponse, ResponseLedgerInfo},
     client::CoordinatorMessage,
     counters,
     error::Error,
@@ -18,14 +18,14 @@ use consensus_notifications::{
     ConsensusSyncNotification,
 };
 use diem_config::{
-    config::{NodeConfig, PeerNetworkId, RoleType, StateSyncConfig},
+    config::{ContinuousSyncingMode, NodeConfig, PeerNetworkId, RoleType, StateSyncConfig},
     network_id::NodeNetworkId,
 };
 use diem_logger::prelude::*;
 use diem_types::{
     contract_event::ContractEvent,
     ledger_info::LedgerInfoWithSignatures,
-    transaction::{Transaction, TransactionListWithProof, Version},
+    transaction::{Transaction, Version},
     waypoint::Waypoint,
     PeerId,
 };
@@ -53,6 +53,7 @@ struct PendingRequestInfo {
     request_epoch: u64,
     target_li: Option<LedgerInfoWithSignatures>,
     chunk_limit: u64,
+    chunk_type: ChunkType,
 }
 
 /// A sync request for a specified target ledger info.
@@ -69,6 +70,7 @@ pub struct SyncRequest {
 /// higher within the timeout interval).
 /// * Validator: the ChunkRequests are generated on demand for a specific target LedgerInfo to
 /// synchronize to.
+/// FullNodes running the consensus observer behave like validators once initialized.
 pub(crate) struct StateSyncCoordinator<T, M> {
     // used to process client requests
     client_events: mpsc::UnboundedReceiver<CoordinatorMessage>,
@@ -84,6 +86,13 @@ pub(crate) struct StateSyncCoordinator<T, M> {
     config: StateSyncConfig,
     // role of node
     role: RoleType,
+    // If the node is a fullnode following consensus with the consensus observer, which commits
+    // the published blocks and sends sync requests like consensus does on validators.
+    observer_enabled: bool,
+    // The last time consensus (or the observer) committed, the observer is considered stalled
+    // and state sync polls the upstream peers again once it's older than the fallback timeout.
+    last_consensus_commit: SystemTime,
+    observer_fallback_timeout: Duration,
     // An initial waypoint: for as long as the local version is less than a version determined by
     // waypoint a node is not going to be abl
     waypoint: Waypoint,
@@ -144,6 +153,12 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             local_state: initial_state,
             config: node_config.state_sync.clone(),
             role,
+            observer_enabled: role == RoleType::FullNode
+                && node_config.consensus_observer.observer_enabled,
+            last_consensus_commit: SystemTime::now(),
+            observer_fallback_timeout: Duration::from_millis(
+                node_config.consensus_observer.state_sync_fallback_ms,
+            ),
             waypoint,
             request_manager,
             subscriptions: HashMap::new(),
@@ -270,59 +285,87 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         let peer = PeerNetworkId(network_id, peer_id);
         match msg {
             StateSyncMessage::GetChunkRequest(request) => {
-                // Time request handling
-                let _timer = counters::PROCESS_MSG_LATENCY
-                    .with_label_values(&[
-                        &peer.raw_network_id().to_string(),
-                        &peer.peer_id().to_string(),
-                        counters::CHUNK_REQUEST_MSG_LABEL,
-                    ])
-                    .start_timer();
-
-                // Process chunk request
-                let process_result = self.process_chunk_request(peer.clone(), *request.clone());
-                if let Err(ref error) = process_result {
-                    error!(
-                        LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::Fail)
-                            .peer(&peer)
-                            .error(&error.clone())
-                            .local_li_version(self.local_state.committed_version())
-                            .chunk_request(*request)
-                    );
-                    counters::PROCESS_CHUNK_REQUEST_COUNT
-                        .with_label_values(&[
-                            &peer.raw_network_id().to_string(),
-                            &peer.peer_id().to_string(),
-                            counters::FAIL_LABEL,
-                        ])
-                        .inc();
-                } else {
-                    counters::PROCESS_CHUNK_REQUEST_COUNT
-                        .with_label_values(&[
-                            &peer.raw_network_id().to_string(),
-                            &peer.peer_id().to_string(),
-                            counters::SUCCESS_LABEL,
-                        ])
-                        .inc();
-                }
-                process_result
+                self.process_chunk_request_message(peer, *request, ChunkType::Transactions)
+            }
+            StateSyncMessage::GetOutputChunkRequest(request) => {
+                self.process_chunk_request_message(peer, *request, ChunkType::TransactionOutputs)
             }
             StateSyncMessage::GetChunkResponse(response) => {
-                // Time response handling
-                let _timer = counters::PROCESS_MSG_LATENCY
-                    .with_label_values(&[
-                        &peer.raw_network_id().to_string(),
-                        &peer.peer_id().to_string(),
-                        counters::CHUNK_RESPONSE_MSG_LABEL,
-                    ])
-                    .start_timer();
-
-                // Process chunk response
-                self.process_chunk_response(&peer, *response).await
+                self.process_chunk_response_message(peer, ChunkResponse::Transactions(*response))
+                    .await
+            }
+            StateSyncMessage::GetOutputChunkResponse(response) => {
+                self.process_chunk_response_message(
+                    peer,
+                    ChunkResponse::TransactionOutputs(*response),
+                )
+                .await
             }
         }
     }
 
+    fn process_chunk_request_message(
+        &mut self,
+        peer: PeerNetworkId,
+        request: GetChunkRequest,
+        chunk_type: ChunkType,
+    ) -> Result<(), Error> {
+        // Time request handling
+        let _timer = counters::PROCESS_MSG_LATENCY
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                counters::CHUNK_REQUEST_MSG_LABEL,
+            ])
+            .start_timer();
+
+        // Process chunk request
+        let process_result = self.process_chunk_request(peer.clone(), request.clone(), chunk_type);
+        if let Err(ref error) = process_result {
+            error!(
+                LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::Fail)
+                    .peer(&peer)
+                    .error(&error.clone())
+                    .local_li_version(self.local_state.committed_version())
+                    .chunk_request(request)
+            );
+            counters::PROCESS_CHUNK_REQUEST_COUNT
+                .with_label_values(&[
+                    &peer.raw_network_id().to_string(),
+                    &peer.peer_id().to_string(),
+                    counters::FAIL_LABEL,
+                ])
+                .inc();
+        } else {
+            counters::PROCESS_CHUNK_REQUEST_COUNT
+                .with_label_values(&[
+                    &peer.raw_network_id().to_string(),
+                    &peer.peer_id().to_string(),
+                    counters::SUCCESS_LABEL,
+                ])
+                .inc();
+        }
+        process_result
+    }
+
+    async fn process_chunk_response_message(
+        &mut self,
+        peer: PeerNetworkId,
+        response: ChunkResponse,
+    ) -> Result<(), Error> {
+        // Time response handling
+        let _timer = counters::PROCESS_MSG_LATENCY
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                counters::CHUNK_RESPONSE_MSG_LABEL,
+            ])
+            .start_timer();
+
+        // Process chunk response
+        self.process_chunk_response(&peer, response).await
+    }
+
     /// Sync up coordinator state with the local storage
     /// and updates the pending ledger info accordingly
     fn sync_state_with_local_storage(&mut self) -> Result<(), Error> {
@@ -377,8 +420,8 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             consensus_sync_notification: sync_notification,
         };
 
-        // Full nodes don't support sync requests
-        if self.role == RoleType::FullNode {
+        // Full nodes don't support sync requests, unless they run the consensus observer
+        if self.role == RoleType::FullNode && !self.observer_enabled {
             return Err(Error::FullNodeSyncRequest);
         }
 
@@ -470,6 +513,9 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         // it's possible to manage some of the highest known versions in memory.
         self.sync_state_with_local_storage()?;
         self.update_sync_state_metrics_and_logs()?;
+        if commit_notification.is_some() {
+            self.last_consensus_commit = SystemTime::now();
+        }
 
         // Notify mempool of the new commit
         let commit_response = self
@@ -654,6 +700,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         &mut self,
         peer: PeerNetworkId,
         request: GetChunkRequest,
+        chunk_type: ChunkType,
     ) -> Result<(), Error> {
         debug!(
             LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::Received)
@@ -676,20 +723,25 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
 
         match request.target.clone() {
-            TargetType::TargetLedgerInfo(li) => {
-                self.process_request_for_target_and_highest(peer, request, Some(li), None)
-            }
+            TargetType::TargetLedgerInfo(li) => self.process_request_for_target_and_highest(
+                peer,
+                request,
+                chunk_type,
+                Some(li),
+                None,
+            ),
             TargetType::HighestAvailable {
                 target_li,
                 timeout_ms,
             } => self.process_request_for_target_and_highest(
                 peer,
                 request,
+                chunk_type,
                 target_li,
                 Some(timeout_ms),
             ),
             TargetType::Waypoint(waypoint_version) => {
-                self.process_request_for_waypoint(peer, request, waypoint_version)
+                self.process_request_for_waypoint(peer, request, chunk_type, waypoint_version)
             }
         }
     }
@@ -743,6 +795,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         &mut self,
         peer: PeerNetworkId,
         request: GetChunkRequest,
+        chunk_type: ChunkType,
         target_li: Option<LedgerInfoWithSignatures>,
         timeout_ms: Option<u64>,
     ) -> Result<(), Error> {
@@ -765,6 +818,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                     request_epoch: request.current_epoch,
                     target_li,
                     chunk_limit,
+                    chunk_type,
                 };
                 self.subscriptions.insert(peer, request_info);
             }
@@ -782,6 +836,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 highest_li,
             },
             chunk_limit,
+            chunk_type,
         )
     }
 
@@ -811,6 +866,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         &mut self,
         peer: PeerNetworkId,
         request: GetChunkRequest,
+        chunk_type: ChunkType,
         waypoint_version: Version,
     ) -> Result<(), Error> {
         let mut limit = std::cmp::min(request.limit, self.config.max_chunk_limit);
@@ -860,12 +916,17 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 end_of_epoch_li,
             },
             limit,
+            chunk_type,
         )
     }
 
     /// Generate and send the ChunkResponse to the given peer.
-    /// The chunk response contains transactions from the local storage with the proofs relative to
-    /// the given target ledger info.
+    /// The chunk response contains transactions (or transaction outputs, depending on
+    /// `chunk_type`) from the local storage with the proofs relative to the given target ledger
+    /// info.
+    /// Write sets aren't stored for versions committed before they were persisted, nor for the
+    /// version of a restored state snapshot, so if the outputs can't be read the chunk falls back
+    /// to transactions (which the requester can always execute).
     /// In case target is None, the ledger info is set to the local highest ledger info.
     fn deliver_chunk(
         &mut self,
@@ -873,15 +934,54 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         known_version: u64,
         response_li: ResponseLedgerInfo,
         limit: u64,
+        chunk_type: ChunkType,
     ) -> Result<(), Error> {
-        let txns = self
-            .executor_proxy
-            .get_chunk(known_version, limit, response_li.version())?;
-        let chunk_response = GetChunkResponse::new(response_li, txns);
+        let target_version = response_li.version();
+        let chunk_response = match chunk_type {
+            ChunkType::Transactions => {
+                let txns = self
+                    .executor_proxy
+                    .get_chunk(known_version, limit, target_version)?;
+                ChunkResponse::Transactions(GetChunkResponse::new(response_li, txns))
+            }
+            ChunkType::TransactionOutputs => {
+                match self
+                    .executor_proxy
+                    .get_output_chunk(known_version, limit, target_version)
+                {
+                    Ok(outputs) => ChunkResponse::TransactionOutputs(GetOutputChunkResponse::new(
+                        response_li,
+                        outputs,
+                    )),
+                    Err(error) => {
+                        debug!(
+                            LogSchema::event_log(
+                                LogEntry::ProcessChunkRequest,
+                                LogEvent::OutputsUnavailable
+                            )
+                            .peer(&peer)
+                            .error(&error),
+                            known_version = known_version,
+                        );
+                        let txns =
+                            self.executor_proxy
+                                .get_chunk(known_version, limit, target_version)?;
+                        ChunkResponse::Transactions(GetChunkResponse::new(response_li, txns))
+                    }
+                }
+            }
+        };
         let log = LogSchema::event_log(LogEntry::ProcessChunkRequest, LogEvent::DeliverChunk)
             .chunk_response(chunk_response.clone())
             .peer(&peer);
-        let msg = StateSyncMessage::GetChunkResponse(Box::new(chunk_response));
+        let msg = match chunk_response {
+            ChunkResponse::Transactions(response) => {
+                StateSyncMessage::GetChunkResponse(Box::new(response))
+            }
+            ChunkResponse::TransactionOutputs(response) => {
+                StateSyncMessage::GetOutputChunkResponse(Box::new(response))
+            }
+        };
         let send_result = self.request_manager.send_chunk_response(&peer, msg);
         let send_result_label = if send_result.is_err() {
             counters::SEND_FAIL_LABEL
@@ -933,11 +1033,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     }
 
     /// Applies (i.e., executes and stores) the chunk to storage iff `response` is valid.
-    fn apply_chunk(
-        &mut self,
-        peer: &PeerNetworkId,
-        response: GetChunkResponse,
-    ) -> Result<(), Error> {
+    fn apply_chunk(&mut self, peer: &PeerNetworkId, response: ChunkResponse) -> Result<(), Error> {
         debug!(
             LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::Received)
                 .chunk_response(response.clone())
@@ -950,32 +1046,23 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         });
 
         // Process the chunk based on the response type
-        let txn_list_with_proof = response.txn_list_with_proof.clone();
-        let chunk_size = response.txn_list_with_proof.len() as u64;
+        let chunk_size = response.len() as u64;
         let known_version = self.local_state.synced_version();
-        match response.response_li {
+        match response.response_li().clone() {
             ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
-                self.process_response_with_target_and_highest(txn_list_with_proof, li, None)
+                self.process_response_with_target_and_highest(response, li, None)
             }
             ResponseLedgerInfo::ProgressiveLedgerInfo {
                 target_li,
                 highest_li,
             } => {
                 let highest_li = highest_li.unwrap_or_else(|| target_li.clone());
-                self.process_response_with_target_and_highest(
-                    txn_list_with_proof,
-                    target_li,
-                    Some(highest_li),
-                )
+                self.process_response_with_target_and_highest(response, target_li, Some(highest_li))
             }
             ResponseLedgerInfo::LedgerInfoForWaypoint {
                 waypoint_li,
                 end_of_epoch_li,
-            } => self.process_response_with_waypoint_li(
-                txn_list_with_proof,
-                waypoint_li,
-                end_of_epoch_li,
-            ),
+            } => self.process_response_with_waypoint_li(response, waypoint_li, end_of_epoch_li),
         }
         .map_err(|error| {
             self.request_manager.process_invalid_chunk(peer);
@@ -1026,7 +1113,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     async fn process_chunk_response(
         &mut self,
         peer: &PeerNetworkId,
-        response: GetChunkResponse,
+        response: ChunkResponse,
     ) -> Result<(), Error> {
         // Ensure consensus isn't running, otherwise we might get a race with storage writes.
         if self.is_consensus_executing() {
@@ -1042,7 +1129,8 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
 
         // Validate the response and store the chunk if possible.
         // Any errors thrown here should be for detecting bad chunks.
-        match self.apply_chunk(peer, response.clone()) {
+        let transactions = response.transactions();
+        match self.apply_chunk(peer, response) {
             Ok(()) => {
                 counters::APPLY_CHUNK_COUNT
                     .with_label_values(&[
@@ -1071,27 +1159,23 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
 
         // Process the newly committed chunk
-        self.process_commit_notification(
-            response.txn_list_with_proof.transactions.clone(),
-            vec![],
-            None,
-            Some(peer),
-        )
-        .await
-        .map_err(|error| {
-            error!(
-                LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::PostCommitFail)
-                    .peer(peer)
-                    .error(&error)
-            );
-            error
-        })
+        self.process_commit_notification(transactions, vec![], None, Some(peer))
+            .await
+            .map_err(|error| {
+                error!(LogSchema::event_log(
+                    LogEntry::ProcessChunkResponse,
+                    LogEvent::PostCommitFail
+                )
+                .peer(peer)
+                .error(&error));
+                error
+            })
     }
 
     fn verify_chunk_response_is_valid(
         &mut self,
         peer: &PeerNetworkId,
-        response: &GetChunkResponse,
+        response: &ChunkResponse,
     ) -> Result<(), Error> {
         // Verify response comes from known peer
         if !self.request_manager.is_known_state_sync_peer(peer) {
@@ -1106,7 +1190,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
 
         // Verify the chunk is not empty and that it starts at the correct version
-        if let Some(first_chunk_version) = response.txn_list_with_proof.first_transaction_version {
+        if let Some(first_chunk_version) = response.first_version() {
             let known_version = self.local_state.synced_version();
             let expected_version = known_version
                 .checked_add(1)
@@ -1125,8 +1209,22 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             return Err(Error::ReceivedEmptyChunk(peer.to_string()));
         }
 
+        // Verify the chunk carries the type of data we request. Transactions are accepted when
+        // requesting outputs, as peers fall back to them for versions without write sets.
+        let chunk_type = match response {
+            ChunkResponse::Transactions(_) => ChunkType::Transactions,
+            ChunkResponse::TransactionOutputs(_) => ChunkType::TransactionOutputs,
+        };
+        if chunk_type != self.chunk_type() && chunk_type != ChunkType::Transactions {
+            return Err(Error::ReceivedWrongChunkType(format!(
+                "Received a chunk of type {:?}, but we're requesting chunks of type {:?}!",
+                chunk_type,
+                self.chunk_type()
+            )));
+        }
+
         // Verify the chunk has the expected type for the current syncing mode
-        match &response.response_li {
+        match response.response_li() {
             ResponseLedgerInfo::LedgerInfoForWaypoint {
                 waypoint_li,
                 end_of_epoch_li,
@@ -1238,13 +1336,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     /// we assume the next chunk will be for our current epoch.
     fn calculate_new_known_version_and_epoch(
         &mut self,
-        txn_list_with_proof: TransactionListWithProof,
+        chunk_size: u64,
         ledger_info: Option<LedgerInfoWithSignatures>,
     ) -> Result<(u64, u64), Error> {
         let new_version = self
             .local_state
             .synced_version()
-            .checked_add(txn_list_with_proof.len() as u64)
+            .checked_add(chunk_size)
             .ok_or_else(|| {
                 Error::IntegerOverflow("Potential state sync version has overflown".into())
             })?;
@@ -1308,14 +1406,14 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     /// current local trusted validator set.
     fn process_response_with_target_and_highest(
         &mut self,
-        txn_list_with_proof: TransactionListWithProof,
+        response: ChunkResponse,
         response_li: LedgerInfoWithSignatures,
         new_highest_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
         // Optimistically calculate the new known version and epoch (assume the current chunk
         // is applied successfully).
         let (known_version, known_epoch) = self.calculate_new_known_version_and_epoch(
-            txn_list_with_proof.clone(),
+            response.len() as u64,
             Some(response_li.clone()),
         )?;
 
@@ -1363,7 +1461,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
 
         // Validate and store the chunk
         self.log_highest_seen_version(new_highest_li.clone());
-        self.validate_and_store_chunk(txn_list_with_proof, response_li, None)?;
+        self.validate_and_store_chunk(response, response_li, None)?;
 
         // Need to sync with local storage to update synced version
         self.sync_state_with_local_storage()?;
@@ -1392,14 +1490,14 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     /// Processing chunk responses that carry a LedgerInfo corresponding to the waypoint.
     fn process_response_with_waypoint_li(
         &mut self,
-        txn_list_with_proof: TransactionListWithProof,
+        response: ChunkResponse,
         waypoint_li: LedgerInfoWithSignatures,
         end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
         // Optimistically calculate the new known version and epoch (assume the current chunk
         // is applied successfully).
         let (known_version, known_epoch) = self.calculate_new_known_version_and_epoch(
-            txn_list_with_proof.clone(),
+            response.len() as u64,
             end_of_epoch_li.clone(),
         )?;
         if known_version < self.waypoint.version() {
@@ -1445,7 +1543,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 Error::UnexpectedError(format!("Waypoint verification failed: {}", error))
             })?;
 
-        self.validate_and_store_chunk(txn_list_with_proof, waypoint_li, end_of_epoch_li_to_commit)?;
+        self.validate_and_store_chunk(response, waypoint_li, end_of_epoch_li_to_commit)?;
         self.log_highest_seen_version(None);
 
         Ok(())
@@ -1454,7 +1552,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     // Assumes that the target LI has been already verified by the caller.
     fn validate_and_store_chunk(
         &mut self,
-        txn_list_with_proof: TransactionListWithProof,
+        response: ChunkResponse,
         target: LedgerInfoWithSignatures,
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
@@ -1473,15 +1571,44 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             return Ok(());
         }
 
-        self.executor_proxy
-            .execute_chunk(txn_list_with_proof, target, intermediate_end_of_epoch_li)
+        match response {
+            ChunkResponse::Transactions(response) => self.executor_proxy.execute_chunk(
+                response.txn_list_with_proof,
+                target,
+                intermediate_end_of_epoch_li,
+            ),
+            ChunkResponse::TransactionOutputs(response) => self.executor_proxy.apply_output_chunk(
+                response.output_list_with_proof,
+                target,
+                intermediate_end_of_epoch_li,
+            ),
+        }
+    }
+
+    /// Returns the type of chunks to request, depending on the configured syncing mode.
+    fn chunk_type(&self) -> ChunkType {
+        match self.config.continuous_syncing_mode {
+            ContinuousSyncingMode::ExecuteTransactions => ChunkType::Transactions,
+            ContinuousSyncingMode::ApplyTransactionOutputs => ChunkType::TransactionOutputs,
+        }
     }
 
     /// Returns true if consensus is currently executing and state sync should
     /// therefore not write to storage. Reads are still permitted (e.g., to
     /// handle chunk requests).
     fn is_consensus_executing(&mut self) -> bool {
-        self.is_initialized() && self.role == RoleType::Validator && self.sync_request.is_none()
+        self.is_initialized()
+            && (self.role == RoleType::Validator
+                || (self.observer_enabled && !self.is_observer_stalled()))
+            && self.sync_request.is_none()
+    }
+
+    /// Returns true if the consensus observer committed nothing within the fallback timeout, in
+    /// which case state sync falls back to polling the upstream peers like on regular fullnodes.
+    fn is_observer_stalled(&self) -> bool {
+        SystemTime::now()
+            .duration_since(self.last_consensus_commit)
+            .map_or(false, |elapsed| elapsed >= self.observer_fallback_timeout)
     }
 
     /// Ensures that state sync is making progress:
@@ -1601,7 +1728,8 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         counters::set_version(counters::VersionType::Target, target_version);
 
         let req = GetChunkRequest::new(known_version, known_epoch, self.config.chunk_limit, target);
-        self.request_manager.send_chunk_request(req)
+        self.request_manager
+            .send_chunk_request(req, self.chunk_type())
     }
 
     fn deliver_subscription(
@@ -1624,6 +1752,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 highest_li,
             },
             request_info.chunk_limit,
+            request_info.chunk_type,
         )
     }
 
@@ -1719,7 +1848,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
 mod tests {
     use crate::{
         chunk_request::{GetChunkRequest, TargetType},
-        chunk_response::{GetChunkResponse, ResponseLedgerInfo},
+        chunk_response::{GetChunkResponse, GetOutputChunkResponse, ResponseLedgerInfo},
         coordinator::StateSyncCoordinator,
         error::Error,
         executor_proxy::ExecutorProxy,
@@ -1746,9 +1875,12 @@ mod tests {
         proof::TransactionListProof,
         transaction::{
             RawTransaction, Script, SignedTransaction, Transaction, TransactionListWithProof,
-            TransactionPayload, Version,
+            TransactionOutput, TransactionOutputListWithProof, TransactionPayload,
+            TransactionStatus, Version,
         },
+        vm_status::KeptVMStatus,
         waypoint::Waypoint,
+        write_set::WriteSet,
         PeerId,
     };
     use futures::{channel::oneshot, executor::block_on};
@@ -1772,6 +1904,21 @@ mod tests {
             );
         }
 
+        // Verify that fullnodes running the consensus observer can process sync requests
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.consensus_observer.observer_enabled = true;
+        let mut observer_coordinator = test_utils::create_coordinator_with_config_and_waypoint(
+            node_config,
+            Waypoint::default(),
+        );
+        let (sync_request, mut callback_receiver) = create_sync_notification_at_version(0);
+        block_on(observer_coordinator.process_sync_request(sync_request)).unwrap();
+        match callback_receiver.try_recv() {
+            Ok(Some(notification_result)) => notification_result.result.unwrap(),
+            result => panic!("Expected okay but got: {:?}", result),
+        };
+
         // Create a coordinator for a validator node
         let mut validator_coordinator = test_utils::create_validator_coordinator();
 
@@ -1965,6 +2112,21 @@ mod tests {
             callback_result => panic!("Expected an error result but got: {:?}", callback_result),
         };
 
+        // Verify fullnodes running the consensus observer only poll once the observer stalls
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.consensus_observer.observer_enabled = true;
+        let mut observer_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config.clone(), Waypoint::default());
+        observer_coordinator.check_progress().unwrap();
+        node_config.consensus_observer.state_sync_fallback_ms = 0;
+        let mut observer_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+        let progress_result = observer_coordinator.check_progress();
+        if !matches!(progress_result, Err(Error::NoAvailablePeers(..))) {
+            panic!("Expected an err result but got: {:?}", progress_result);
+        }
+
         // TODO(joshlind): check request resend after timeout.
 
         // TODO(joshlind): check overflow error returns.
@@ -2209,6 +2371,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_process_output_chunk_response() {
+        // Create a coordinator for a full node (executing transactions by default)
+        let mut full_node_coordinator = test_utils::create_full_node_coordinator();
+
+        // Create a peer for the node and add the peer as a known peer
+        let peer_network_id = PeerNetworkId::random_validator();
+        process_new_peer_event(&mut full_node_coordinator, &peer_network_id);
+
+        // Verify transaction output chunks are rejected when executing transactions
+        let response_ledger_info = ResponseLedgerInfo::ProgressiveLedgerInfo {
+            target_li: create_ledger_info_at_version(100),
+            highest_li: None,
+        };
+        let output_chunk_response = GetOutputChunkResponse::new(
+            response_ledger_info,
+            TransactionOutputListWithProof::new(
+                vec![(
+                    create_test_transaction(),
+                    TransactionOutput::new(
+                        WriteSet::default(),
+                        vec![],
+                        0,
+                        TransactionStatus::Keep(KeptVMStatus::Executed),
+                    ),
+                )],
+                Some(1),
+                TransactionListProof::new_empty(),
+            ),
+        );
+        verify_all_chunk_responses_are_the_wrong_type(
+            &mut full_node_coordinator,
+            &peer_network_id,
+            &[StateSyncMessage::GetOutputChunkResponse(Box::new(
+                output_chunk_response,
+            ))],
+        );
+    }
+
     #[test]
     fn test_process_chunk_response_target() {
         // Create a coordinator for a validator
@@ -269,6 +269,15 @@ pub static EXECUTE_CHUNK_DURATION: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Time it takes for state sync to fully apply a chunk of transaction outputs (via executor proxy)
+pub static APPLY_CHUNK_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "diem_state_sync_apply_chunk_duration_s",
+        "Histogram of time it takes for state sync's executor proxy to fully apply a chunk of transaction outputs"
+    )
+    .unwrap()
+});
+
 /// Number of times a long-poll subscription is successfully delivered
 pub static SUBSCRIPTION_DELIVERY_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -15,7 +15,7 @@ use diem_types::{
     move_resource::MoveStorage,
     on_chain_config,
     on_chain_config::{config_address, ConfigID, OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
-    transaction::TransactionListWithProof,
+    transaction::{TransactionListWithProof, TransactionOutputListWithProof},
 };
 use executor_types::{ChunkExecutor, ExecutedTrees};
 use std::{
@@ -39,6 +39,14 @@ pub trait ExecutorProxyTrait: Send {
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error>;
 
+    /// Apply and commit a batch of transaction outputs, without executing the transactions
+    fn apply_output_chunk(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error>;
+
     /// Gets chunk of transactions given the known version, target version and the max limit.
     fn get_chunk(
         &self,
@@ -47,6 +55,15 @@ pub trait ExecutorProxyTrait: Send {
         target_version: u64,
     ) -> Result<TransactionListWithProof, Error>;
 
+    /// Gets chunk of transaction outputs given the known version, target version and the max
+    /// limit.
+    fn get_output_chunk(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionOutputListWithProof, Error>;
+
     /// Get the epoch changing ledger info for the given epoch so that we can move to next epoch.
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error>;
 
@@ -260,6 +277,38 @@ impl ExecutorProxyTrait for ExecutorProxy {
         Ok(())
     }
 
+    fn apply_output_chunk(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        // track chunk application time
+        let timer = counters::APPLY_CHUNK_DURATION.start_timer();
+        let events = self
+            .executor
+            .apply_and_commit_chunk(
+                output_list_with_proof,
+                verified_target_li,
+                intermediate_end_of_epoch_li,
+            )
+            .map_err(|error| {
+                Error::UnexpectedError(format!("Apply and commit chunk failed: {}", error))
+            })?;
+        timer.stop_and_record();
+        let reconfig_events = extract_reconfig_events(events);
+        if let Err(e) = self.publish_on_chain_config_updates(reconfig_events) {
+            error!(
+                LogSchema::event_log(LogEntry::Reconfig, LogEvent::Fail).error(&e),
+                "Failed to publish reconfig updates in apply_output_chunk"
+            );
+            counters::RECONFIG_PUBLISH_COUNT
+                .with_label_values(&[counters::FAIL_LABEL])
+                .inc();
+        }
+        Ok(())
+    }
+
     fn get_chunk(
         &self,
         known_version: u64,
@@ -276,6 +325,25 @@ impl ExecutorProxyTrait for ExecutorProxy {
             })
     }
 
+    fn get_output_chunk(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionOutputListWithProof, Error> {
+        let starting_version = known_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Starting version has overflown!".into()))?;
+        self.storage
+            .get_transaction_outputs(starting_version, limit, target_version)
+            .map_err(|error| {
+                Error::UnexpectedError(format!(
+                    "Failed to get transaction outputs from storage {}",
+                    error
+                ))
+            })
+    }
+
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error> {
         let next_epoch = epoch
             .checked_add(1)
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    chunk_request::GetChunkRequest, chunk_response::GetChunkResponse, error::Error,
+    chunk_request::GetChunkRequest, chunk_response::ChunkResponse, error::Error,
     request_manager::ChunkRequestInfo,
 };
 use diem_config::{config::PeerNetworkId, network_id::NetworkId};
@@ -25,7 +25,7 @@ pub struct LogSchema<'a> {
     chunk_request: Option<GetChunkRequest>,
     version: Option<u64>,
     #[schema(display)]
-    chunk_response: Option<GetChunkResponse>,
+    chunk_response: Option<ChunkResponse>,
     #[schema(display)]
     waypoint: Option<Waypoint>,
     subscription_name: Option<String>,
@@ -139,6 +139,7 @@ pub enum LogEvent {
     // ProcessChunkRequest events
     PastEpochRequested,
     DeliverChunk,
+    OutputsUnavailable,
 
     // Multicast network events
     Failover,
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    chunk_request::GetChunkRequest,
+    chunk_request::{ChunkType, GetChunkRequest},
     counters,
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
@@ -255,7 +255,11 @@ impl RequestManager {
         chosen_peers
     }
 
-    pub fn send_chunk_request(&mut self, req: GetChunkRequest) -> Result<(), Error> {
+    pub fn send_chunk_request(
+        &mut self,
+        req: GetChunkRequest,
+        chunk_type: ChunkType,
+    ) -> Result<(), Error> {
         let log = LogSchema::new(LogEntry::SendChunkRequest).chunk_request(req.clone());
 
         let peers = self.pick_peers();
@@ -272,7 +276,10 @@ impl RequestManager {
             .event(LogEvent::ChunkRequestInfo)
             .chunk_req_info(&req_info));
 
-        let msg = StateSyncMessage::GetChunkRequest(Box::new(req));
+        let msg = match chunk_type {
+            ChunkType::Transactions => StateSyncMessage::GetChunkRequest(Box::new(req)),
+            ChunkType::TransactionOutputs => StateSyncMessage::GetOutputChunkRequest(Box::new(req)),
+        };
         let mut failed_peer_sends = vec![];
 
         for peer in peers {
@@ -323,6 +323,70 @@ fn test_lagging_upstream_long_poll() {
     env.get_state_sync_peer(2).wait_for_version(600, Some(600));
 }
 
+#[test]
+fn test_output_sync_before_write_sets_were_persisted() {
+    // Write sets are only persisted from version 101 onwards, so the validator serves
+    // transactions for the first chunk and transaction outputs for the second.
+    let (mut env, validator_peer_id, fullnode_peer_id) = setup_output_syncing_environment(1..=100);
+
+    let (_, message) = env.deliver_msg(fullnode_peer_id);
+    check_output_chunk_request(message, 0, None);
+    let (_, message) = env.deliver_msg(validator_peer_id);
+    check_chunk_response(message, 400, 1, 250);
+    assert!(env.get_state_sync_peer(1).wait_for_version(250, None));
+
+    let (_, message) = env.deliver_msg(fullnode_peer_id);
+    check_output_chunk_request(message, 250, Some(400));
+    let (_, message) = env.deliver_msg(validator_peer_id);
+    check_output_chunk_response(message, 400, 251, 150);
+    assert!(env.get_state_sync_peer(1).wait_for_version(400, Some(400)));
+}
+
+#[test]
+fn test_output_sync_at_snapshot_version() {
+    // The validator was restored from a state snapshot at version 300, which has no write set,
+    // so the validator serves transaction outputs for the first chunk and transactions for the
+    // second.
+    let (mut env, validator_peer_id, fullnode_peer_id) =
+        setup_output_syncing_environment(vec![300]);
+
+    let (_, message) = env.deliver_msg(fullnode_peer_id);
+    check_output_chunk_request(message, 0, None);
+    let (_, message) = env.deliver_msg(validator_peer_id);
+    check_output_chunk_response(message, 400, 1, 250);
+    assert!(env.get_state_sync_peer(1).wait_for_version(250, None));
+
+    let (_, message) = env.deliver_msg(fullnode_peer_id);
+    check_output_chunk_request(message, 250, Some(400));
+    let (_, message) = env.deliver_msg(validator_peer_id);
+    check_chunk_response(message, 400, 251, 150);
+    assert!(env.get_state_sync_peer(1).wait_for_version(400, Some(400)));
+}
+
+// Starts a validator that commits version 400 without the write sets of the given versions, and
+// connects it to a fullnode that syncs transaction outputs. Returns the environment along with
+// the peer ids of the validator and the fullnode.
+fn setup_output_syncing_environment(
+    versions_without_write_sets: impl IntoIterator<Item = u64>,
+) -> (StateSyncEnvironment, PeerId, PeerId) {
+    let mut env = StateSyncEnvironment::new(2);
+    env.start_validator_peer(0, true);
+    env.start_output_syncing_fullnode_peer(1, true);
+
+    let validator = env.get_state_sync_peer(0);
+    let fullnode = env.get_state_sync_peer(1);
+    let validator_peer_id = validator.get_peer_id(VALIDATOR_NETWORK.clone());
+    let fullnode_peer_id = fullnode.get_peer_id(VFN_NETWORK.clone());
+
+    validator.commit(400);
+    validator.remove_write_sets(versions_without_write_sets);
+    drop(validator);
+    drop(fullnode);
+
+    send_connection_notifications(&mut env, validator_peer_id, fullnode_peer_id, true);
+    (env, validator_peer_id, fullnode_peer_id)
+}
+
 #[test]
 fn test_fullnode_catch_up_moving_target_epochs() {
     // Create validator and fullnode
@@ -843,13 +907,29 @@ fn check_chunk_request(message: Message, known_version: u64, target_version: Opt
             assert_eq!(chunk_request.known_version, known_version);
             assert_eq!(chunk_request.target.version(), target_version);
         }
-        StateSyncMessage::GetChunkResponse(_) => {
-            panic!("Received chunk response but expecting chunk request!");
+        message => {
+            panic!("Expecting chunk request, but received: {:?}", message);
         }
     }
 }
 
-fn check_chunk_response(
+fn check_output_chunk_request(message: Message, known_version: u64, target_version: Option<u64>) {
+    let chunk_request: StateSyncMessage = bcs::from_bytes(&message.mdata).unwrap();
+    match chunk_request {
+        StateSyncMessage::GetOutputChunkRequest(chunk_request) => {
+            assert_eq!(chunk_request.known_version, known_version);
+            assert_eq!(chunk_request.target.version(), target_version);
+        }
+        message => {
+            panic!(
+                "Expecting output chunk request, but received: {:?}",
+                message
+            );
+        }
+    }
+}
+
+fn check_output_chunk_response(
     message: Message,
     response_li_version: u64,
     chunk_start_version: u64,
@@ -857,9 +937,40 @@ fn check_chunk_response(
 ) {
     let chunk_response: StateSyncMessage = bcs::from_bytes(&message.mdata).unwrap();
     match chunk_response {
-        StateSyncMessage::GetChunkRequest(_) => {
-            panic!("Received chunk response but expecting chunk request!");
+        StateSyncMessage::GetOutputChunkResponse(chunk_response) => {
+            assert_eq!(chunk_response.response_li.version(), response_li_version);
+            assert_eq!(
+                chunk_response
+                    .output_list_with_proof
+                    .first_transaction_output_version
+                    .unwrap(),
+                chunk_start_version
+            );
+            assert_eq!(
+                chunk_response
+                    .output_list_with_proof
+                    .transactions_and_outputs
+                    .len(),
+                chunk_length
+            )
         }
+        message => {
+            panic!(
+                "Expecting output chunk response, but received: {:?}",
+                message
+            );
+        }
+    }
+}
+
+fn check_chunk_response(
+    message: Message,
+    response_li_version: u64,
+    chunk_start_version: u64,
+    chunk_length: usize,
+) {
+    let chunk_response: StateSyncMessage = bcs::from_bytes(&message.mdata).unwrap();
+    match chunk_response {
         StateSyncMessage::GetChunkResponse(chunk_response) => {
             assert_eq!(chunk_response.response_li.version(), response_li_version);
             assert_eq!(
@@ -874,6 +985,9 @@ fn check_chunk_response(
                 chunk_length
             )
         }
+        message => {
+            panic!("Expecting chunk response, but received: {:?}", message);
+        }
     }
 }
 
@@ -4,7 +4,7 @@
 use channel::{diem_channel, message_queues::QueueStyle};
 use consensus_notifications::{ConsensusNotificationSender, ConsensusNotifier};
 use diem_config::{
-    config::{NodeConfig, Peer, PeerRole, RoleType, HANDSHAKE_VERSION},
+    config::{ContinuousSyncingMode, NodeConfig, Peer, PeerRole, RoleType, HANDSHAKE_VERSION},
     network_id::{NetworkContext, NetworkId, NodeNetworkId},
 };
 use diem_crypto::{
@@ -31,13 +31,15 @@ use diem_types::{
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{
         authenticator::AuthenticationKey, SignedTransaction, Transaction, TransactionListWithProof,
-        TransactionPayload,
+        TransactionOutput, TransactionOutputListWithProof, TransactionPayload, TransactionStatus,
     },
     validator_config::ValidatorConfig,
     validator_info::ValidatorInfo,
     validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
+    vm_status::KeptVMStatus,
     waypoint::Waypoint,
+    write_set::WriteSet,
     PeerId,
 };
 use executor_types::ExecutedTrees;
@@ -70,7 +72,7 @@ use state_sync_v1::{
 };
 use std::{
     cell::{Ref, RefCell},
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     ops::DerefMut,
     sync::Arc,
 };
@@ -139,6 +141,16 @@ impl StateSyncPeer {
             .unwrap()
     }
 
+    // Drops the write sets of the given versions from storage, e.g., to mimic versions that were
+    // committed before write sets were persisted.
+    pub fn remove_write_sets(&self, versions: impl IntoIterator<Item = u64>) {
+        self.storage_proxy
+            .as_ref()
+            .unwrap()
+            .write()
+            .remove_write_sets(versions);
+    }
+
     pub fn get_validator_info(&self) -> ValidatorInfo {
         self.public_key.clone()
     }
@@ -261,6 +273,21 @@ impl StateSyncEnvironment {
         );
     }
 
+    // Starts a new state sync peer with the fullnode role, which applies transaction outputs
+    // instead of executing transactions.
+    pub fn start_output_syncing_fullnode_peer(&mut self, peer_index: usize, mock_network: bool) {
+        self.setup_state_sync_peer_with_syncing_mode(
+            peer_index,
+            default_handler(),
+            RoleType::FullNode,
+            Waypoint::default(),
+            60_000,
+            120_000,
+            mock_network,
+            ContinuousSyncingMode::ApplyTransactionOutputs,
+        );
+    }
+
     // Sets up and starts the state sync peer at the given node index.
     pub fn start_state_sync_peer(
         &mut self,
@@ -291,7 +318,32 @@ impl StateSyncEnvironment {
         multicast_timeout_ms: u64,
         mock_network: bool,
     ) {
-        let (config, network_id) = setup_state_sync_config(role, timeout_ms, multicast_timeout_ms);
+        self.setup_state_sync_peer_with_syncing_mode(
+            index,
+            handler,
+            role,
+            waypoint,
+            timeout_ms,
+            multicast_timeout_ms,
+            mock_network,
+            ContinuousSyncingMode::ExecuteTransactions,
+        );
+    }
+
+    fn setup_state_sync_peer_with_syncing_mode(
+        &mut self,
+        index: usize,
+        handler: MockRpcHandler,
+        role: RoleType,
+        waypoint: Waypoint,
+        timeout_ms: u64,
+        multicast_timeout_ms: u64,
+        mock_network: bool,
+        continuous_syncing_mode: ContinuousSyncingMode,
+    ) {
+        let (mut config, network_id) =
+            setup_state_sync_config(role, timeout_ms, multicast_timeout_ms);
+        config.state_sync.continuous_syncing_mode = continuous_syncing_mode;
         let network_handles = self.setup_network_handles(index, &role, mock_network, network_id);
         let validators: Vec<ValidatorInfo> = self
             .peers
@@ -610,6 +662,8 @@ pub struct MockStorage {
     signer: ValidatorSigner,
     // A validator verifier of the latest epoch
     epoch_state: EpochState,
+    // versions whose write sets aren't available
+    versions_without_write_sets: HashSet<u64>,
 }
 
 impl MockStorage {
@@ -625,6 +679,7 @@ impl MockStorage {
             epoch_num,
             signer,
             epoch_state,
+            versions_without_write_sets: HashSet::new(),
         }
     }
 
@@ -697,6 +752,37 @@ impl MockStorage {
         res
     }
 
+    pub fn get_output_chunk(
+        &self,
+        start_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<Vec<(Transaction, TransactionOutput)>, Error> {
+        self.get_chunk(start_version, limit, target_version)
+            .into_iter()
+            .zip(start_version..)
+            .map(|(txn, version)| {
+                if self.versions_without_write_sets.contains(&version) {
+                    return Err(Error::UnexpectedError(format!(
+                        "WriteSet at version {} not found",
+                        version
+                    )));
+                }
+                let output = TransactionOutput::new(
+                    WriteSet::default(),
+                    vec![],
+                    0,
+                    TransactionStatus::Keep(KeptVMStatus::Executed),
+                );
+                Ok((txn, output))
+            })
+            .collect()
+    }
+
+    pub fn remove_write_sets(&mut self, versions: impl IntoIterator<Item = u64>) {
+        self.versions_without_write_sets.extend(versions);
+    }
+
     pub fn add_txns_with_li(
         &mut self,
         mut transactions: Vec<Transaction>,
@@ -860,6 +946,24 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         Ok(())
     }
 
+    fn apply_output_chunk(
+        &mut self,
+        output_list_with_proof: TransactionOutputListWithProof,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        self.storage.write().add_txns_with_li(
+            output_list_with_proof
+                .transactions_and_outputs
+                .into_iter()
+                .map(|(txn, _)| txn)
+                .collect(),
+            ledger_info_with_sigs,
+            intermediate_end_of_epoch_li,
+        );
+        Ok(())
+    }
+
     fn get_chunk(
         &self,
         known_version: u64,
@@ -883,6 +987,27 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         (self.handler)(txns_with_proof)
     }
 
+    fn get_output_chunk(
+        &self,
+        known_version: u64,
+        limit: u64,
+        target_version: u64,
+    ) -> Result<TransactionOutputListWithProof, Error> {
+        let start_version = known_version
+            .checked_add(1)
+            .ok_or_else(|| Error::IntegerOverflow("Known version has overflown!".into()))?;
+        let txns_and_outputs =
+            self.storage
+                .read()
+                .get_output_chunk(start_version, limit, target_version)?;
+        let first_txn_version = txns_and_outputs.first().map(|_| start_version);
+        Ok(TransactionOutputListWithProof::new(
+            txns_and_outputs,
+            first_txn_version,
+            TransactionListProof::new_empty(),
+        ))
+    }
+
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error> {
         self.storage.read().get_epoch_changes(epoch)
     }
@@ -0,0 +1,62 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge};
+use once_cell::sync::Lazy;
+
+// request type labels
+pub const EPOCH_ENDING_LEDGER_INFOS_LABEL: &str = "epoch_ending_ledger_infos";
+pub const STATE_SNAPSHOT_INFO_LABEL: &str = "state_snapshot_info";
+pub const ACCOUNT_STATES_CHUNK_LABEL: &str = "account_states_chunk";
+
+// request result labels
+pub const SUCCESS_LABEL: &str = "success";
+pub const FAIL_LABEL: &str = "fail";
+
+/// Number of requests served to peers, by type and result
+pub static REQUESTS_SERVED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_state_sync_v2_requests_served",
+        "Number of state sync v2 requests served to peers",
+        &["type", "result"]
+    )
+    .unwrap()
+});
+
+/// Number of requests sent to peers, by type and result
+pub static REQUESTS_SENT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_state_sync_v2_requests_sent",
+        "Number of state sync v2 requests sent to peers",
+        &["type", "result"]
+    )
+    .unwrap()
+});
+
+/// Version of the state snapshot being synced
+pub static STATE_SNAPSHOT_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_v2_state_snapshot_version",
+        "Version of the state snapshot being synced"
+    )
+    .unwrap()
+});
+
+/// Number of accounts of the state snapshot restored so far
+pub static STATE_SNAPSHOT_NUM_ACCOUNTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_v2_state_snapshot_num_accounts",
+        "Number of accounts of the state snapshot restored so far"
+    )
+    .unwrap()
+});
+
+/// Counters(queued,dequeued,dropped) related to pending network notifications
+pub static PENDING_STATE_SYNC_V2_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_state_sync_v2_pending_network_events",
+        "Counters(queued,dequeued,dropped) related to pending network notifications for State Sync v2",
+        &["state"]
+    )
+    .unwrap()
+});
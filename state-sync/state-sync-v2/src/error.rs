@@ -0,0 +1,40 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use network::{error::NetworkError, protocols::rpc::error::RpcError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Eq, Serialize)]
+pub enum Error {
+    #[error("Received an invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Received an invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("Encountered a network error: {0}")]
+    NetworkError(String),
+    #[error("Encountered a storage error: {0}")]
+    StorageError(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("Failed to verify a response: {0}")]
+    VerificationError(String),
+}
+
+impl From<NetworkError> for Error {
+    fn from(error: NetworkError) -> Self {
+        Error::NetworkError(error.to_string())
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(error: RpcError) -> Self {
+        Error::NetworkError(error.to_string())
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(error: anyhow::Error) -> Self {
+        Error::StorageError(error.to_string())
+    }
+}
@@ -1,7 +1,17 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-//! This is an empty skeleton library for state sync v2.
-//!
-//! TODO(joshlind): update this description when we start implementing state sync v2.
 #![forbid(unsafe_code)]
+
+//! State sync v2 lets a node catch up without replaying the whole history: it downloads a
+//! verified account state snapshot at the latest epoch ending ledger info from its peers, after
+//! which the node only needs to sync the transactions committed since the snapshot.
+//! Every node also serves the snapshots of its own storage to its peers.
+
+pub mod error;
+pub mod network;
+pub mod server;
+pub mod service;
+pub mod snapshot_syncer;
+
+mod counters;
@@ -0,0 +1,149 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interface between State Sync v2 and Network layers.
+
+use crate::{counters, error::Error};
+use channel::message_queues::QueueStyle;
+use diem_crypto::HashValue;
+use diem_metrics::IntCounterVec;
+use diem_types::{
+    account_state_blob::AccountStatesChunkWithProof,
+    epoch_change::EpochChangeProof,
+    proof::AccumulatorConsistencyProof,
+    transaction::{TransactionOutputListWithProof, Version},
+    PeerId,
+};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::network::{NetworkEvents, NetworkSender, NewNetworkSender},
+    ProtocolId,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const STATE_SYNC_V2_MAX_BUFFER_SIZE: usize = 100;
+
+/// State sync v2 network messages. Requests are sent to peers as RPCs, which are answered with
+/// the corresponding response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum StateSyncMessage {
+    Request(StateSyncRequest),
+    Response(StateSyncResponse),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StateSyncRequest {
+    /// Asks for the epoch ending ledger infos from `start_epoch` up to the latest epoch the peer
+    /// knows about.
+    GetEpochEndingLedgerInfos { start_epoch: u64 },
+    /// Asks for the transaction and its output at `version` and the frozen subtrees of the transaction
+    /// accumulator before it, which are needed to start from a state snapshot at `version`.
+    GetStateSnapshotInfo { version: Version },
+    /// Asks for at most `chunk_size` accounts of the state snapshot at `version`, starting right
+    /// after the account with hashed address `last_key` (from the leftmost account if `None`).
+    GetAccountStatesChunk {
+        version: Version,
+        last_key: Option<HashValue>,
+        chunk_size: u64,
+    },
+}
+
+impl StateSyncRequest {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            StateSyncRequest::GetEpochEndingLedgerInfos { .. } => {
+                counters::EPOCH_ENDING_LEDGER_INFOS_LABEL
+            }
+            StateSyncRequest::GetStateSnapshotInfo { .. } => counters::STATE_SNAPSHOT_INFO_LABEL,
+            StateSyncRequest::GetAccountStatesChunk { .. } => counters::ACCOUNT_STATES_CHUNK_LABEL,
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StateSyncResponse {
+    EpochEndingLedgerInfos(EpochChangeProof),
+    StateSnapshotInfo {
+        /// The transaction at the snapshot version, with its output and proof.
+        output_list_with_proof: TransactionOutputListWithProof,
+        /// The frozen subtrees of the transaction accumulator before the snapshot version.
+        frozen_subtrees: AccumulatorConsistencyProof,
+    },
+    AccountStatesChunk(AccountStatesChunkWithProof),
+    /// The request couldn't be served.
+    Error(Error),
+}
+
+/// The interface from Network to StateSync v2 layer.
+///
+/// `StateSyncEvents` is a `Stream` of `PeerManagerNotification` where the
+/// raw `Bytes` rpc messages are deserialized into `StateSyncMessage` types.
+pub type StateSyncEvents = NetworkEvents<StateSyncMessage>;
+
+/// The interface from StateSync v2 to Networking layer.
+///
+/// This is a thin wrapper around a `NetworkSender<StateSyncMessage>`, so it is easy to clone and
+/// use for concurrent requests.
+#[derive(Clone)]
+pub struct StateSyncSender {
+    inner: NetworkSender<StateSyncMessage>,
+}
+
+impl NewNetworkSender for StateSyncSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl StateSyncSender {
+    /// Sends `request` to `recipient` and waits for its response.
+    pub async fn send_request(
+        &mut self,
+        recipient: PeerId,
+        request: StateSyncRequest,
+        timeout: Duration,
+    ) -> Result<StateSyncResponse, Error> {
+        let protocol = ProtocolId::StateSyncRpc;
+        match self
+            .inner
+            .send_rpc(
+                recipient,
+                protocol,
+                StateSyncMessage::Request(request),
+                timeout,
+            )
+            .await?
+        {
+            StateSyncMessage::Response(StateSyncResponse::Error(error)) => Err(error),
+            StateSyncMessage::Response(response) => Ok(response),
+            StateSyncMessage::Request(request) => Err(Error::InvalidResponse(format!(
+                "Received a request instead of a response: {:?}",
+                request
+            ))),
+        }
+    }
+}
+
+/// Configuration for the network endpoints to support state sync v2.
+pub fn network_endpoint_config() -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![ProtocolId::StateSyncRpc],
+        vec![],
+        QueueStyle::FIFO,
+        STATE_SYNC_V2_MAX_BUFFER_SIZE,
+        Some(&counters::PENDING_STATE_SYNC_V2_NETWORK_EVENTS),
+    )
+}
@@ -0,0 +1,117 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    error::Error,
+    network::{StateSyncRequest, StateSyncResponse},
+};
+use diem_types::{
+    epoch_change::EpochChangeProof, proof::AccumulatorConsistencyProof, transaction::Version,
+};
+use std::sync::Arc;
+use storage_interface::DbReader;
+
+/// Serves the state sync requests of peers from the local storage.
+#[derive(Clone)]
+pub struct StateSyncServer {
+    storage: Arc<dyn DbReader>,
+    max_chunk_size: u64,
+}
+
+impl StateSyncServer {
+    pub fn new(storage: Arc<dyn DbReader>, max_chunk_size: u64) -> Self {
+        Self {
+            storage,
+            max_chunk_size,
+        }
+    }
+
+    /// Builds the response to `request`. Requests that can't be served are answered with
+    /// `StateSyncResponse::Error`.
+    pub fn handle_request(&self, request: StateSyncRequest) -> StateSyncResponse {
+        let label = request.get_label();
+        match self.process_request(request) {
+            Ok(response) => {
+                counters::REQUESTS_SERVED
+                    .with_label_values(&[label, counters::SUCCESS_LABEL])
+                    .inc();
+                response
+            }
+            Err(error) => {
+                counters::REQUESTS_SERVED
+                    .with_label_values(&[label, counters::FAIL_LABEL])
+                    .inc();
+                StateSyncResponse::Error(error)
+            }
+        }
+    }
+
+    fn process_request(&self, request: StateSyncRequest) -> Result<StateSyncResponse, Error> {
+        match request {
+            StateSyncRequest::GetEpochEndingLedgerInfos { start_epoch } => {
+                let end_epoch = self
+                    .storage
+                    .get_latest_ledger_info()?
+                    .ledger_info()
+                    .next_block_epoch();
+                // There is nothing newer to serve if the requester is not behind.
+                let proof = if start_epoch >= end_epoch {
+                    EpochChangeProof::new(vec![], false /* more */)
+                } else {
+                    self.storage
+                        .get_epoch_ending_ledger_infos(start_epoch, end_epoch)?
+                };
+                Ok(StateSyncResponse::EpochEndingLedgerInfos(proof))
+            }
+            StateSyncRequest::GetStateSnapshotInfo { version } => {
+                self.ensure_version_committed(version)?;
+                let output_list_with_proof = self
+                    .storage
+                    .get_transaction_outputs(version, 1 /* batch_size */, version)?;
+                let frozen_subtrees = if version == 0 {
+                    AccumulatorConsistencyProof::new(vec![])
+                } else {
+                    self.storage
+                        .get_accumulator_consistency_proof(None, version - 1)?
+                };
+                Ok(StateSyncResponse::StateSnapshotInfo {
+                    output_list_with_proof,
+                    frozen_subtrees,
+                })
+            }
+            StateSyncRequest::GetAccountStatesChunk {
+                version,
+                last_key,
+                chunk_size,
+            } => {
+                if chunk_size == 0 || chunk_size > self.max_chunk_size {
+                    return Err(Error::InvalidRequest(format!(
+                        "Chunk size {} is not in range [1, {}]",
+                        chunk_size, self.max_chunk_size,
+                    )));
+                }
+                self.ensure_version_committed(version)?;
+                Ok(StateSyncResponse::AccountStatesChunk(
+                    self.storage.get_account_chunk_with_proof(
+                        version,
+                        last_key,
+                        chunk_size as usize,
+                    )?,
+                ))
+            }
+        }
+    }
+
+    fn ensure_version_committed(&self, version: Version) -> Result<(), Error> {
+        let latest_version = self.storage.get_latest_version()?;
+        if version > latest_version {
+            Err(Error::InvalidRequest(format!(
+                "Version {} is ahead of the latest committed version {}",
+                version, latest_version,
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    network::{StateSyncEvents, StateSyncMessage, StateSyncSender},
+    server::StateSyncServer,
+    snapshot_syncer::StateSnapshotSyncer,
+};
+use diem_config::{
+    config::{NodeConfig, PeerNetworkId},
+    network_id::NodeNetworkId,
+};
+use diem_infallible::RwLock;
+use diem_logger::prelude::*;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, waypoint::Waypoint};
+use futures::{channel::oneshot, StreamExt};
+use network::{protocols::network::Event, ProtocolId};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
+use storage_interface::DbReaderWriter;
+use tokio::runtime::{Builder, Runtime};
+
+/// Runs state sync v2: serves the requests of peers from the local storage and lets the node
+/// bootstrap itself from a state snapshot downloaded from its peers.
+pub struct StateSyncService {
+    runtime: Runtime,
+    syncer: Arc<StateSnapshotSyncer>,
+}
+
+impl StateSyncService {
+    pub fn bootstrap(
+        network: Vec<(NodeNetworkId, StateSyncSender, StateSyncEvents)>,
+        storage: DbReaderWriter,
+        node_config: &NodeConfig,
+        waypoint: Waypoint,
+    ) -> Self {
+        let runtime = Builder::new_multi_thread()
+            .thread_name("state-sync-v2")
+            .enable_all()
+            .build()
+            .expect("[State Sync v2] Failed to create runtime!");
+
+        let server = StateSyncServer::new(
+            storage.reader.clone(),
+            node_config.state_sync.max_chunk_limit,
+        );
+        let peers = Arc::new(RwLock::new(HashSet::new()));
+        let mut network_senders = HashMap::new();
+        for (network_id, sender, events) in network {
+            network_senders.insert(network_id.clone(), sender);
+            runtime.spawn(handle_network_events(
+                network_id,
+                events,
+                server.clone(),
+                Arc::clone(&peers),
+            ));
+        }
+
+        let syncer = Arc::new(StateSnapshotSyncer::new(
+            node_config.state_sync.clone(),
+            storage,
+            waypoint,
+            network_senders,
+            peers,
+        ));
+        Self { runtime, syncer }
+    }
+
+    /// Downloads the state snapshot at the latest epoch ending ledger info known by the peers if
+    /// the local storage is behind it, blocking until done. Returns the ledger info of the synced
+    /// snapshot, if any.
+    pub fn sync_state_snapshot(&self) -> Result<Option<LedgerInfoWithSignatures>, Error> {
+        self.runtime.block_on(self.syncer.sync())
+    }
+
+    /// Starts downloading the state snapshot like `sync_state_snapshot`, without blocking. The
+    /// returned receiver fires once the sync is over, whether it succeeded or not: the node can
+    /// then sync the remaining transactions from whatever is in the local storage.
+    pub fn start_state_snapshot_sync(&self) -> oneshot::Receiver<()> {
+        let (done_sender, done_receiver) = oneshot::channel();
+        let syncer = Arc::clone(&self.syncer);
+        self.runtime.spawn(async move {
+            let instant = Instant::now();
+            match syncer.sync().await {
+                Ok(synced_li) => info!(
+                    "State snapshot synced to {:?} in {} ms",
+                    synced_li.map(|li| li.ledger_info().version()),
+                    instant.elapsed().as_millis()
+                ),
+                Err(error) => error!(
+                    "Failed to sync the state snapshot, falling back to syncing transactions: {}",
+                    error
+                ),
+            }
+            let _ = done_sender.send(());
+        });
+        done_receiver
+    }
+}
+
+/// Keeps track of the connected peers of `network_id` and serves their requests.
+async fn handle_network_events(
+    network_id: NodeNetworkId,
+    mut events: StateSyncEvents,
+    server: StateSyncServer,
+    peers: Arc<RwLock<HashSet<PeerNetworkId>>>,
+) {
+    while let Some(event) = events.next().await {
+        match event {
+            Event::NewPeer(metadata) => {
+                peers
+                    .write()
+                    .insert(PeerNetworkId(network_id.clone(), metadata.remote_peer_id));
+            }
+            Event::LostPeer(metadata) => {
+                peers
+                    .write()
+                    .remove(&PeerNetworkId(network_id.clone(), metadata.remote_peer_id));
+            }
+            Event::RpcRequest(peer_id, StateSyncMessage::Request(request), response_sender) => {
+                let server = server.clone();
+                tokio::spawn(async move {
+                    let response =
+                        match tokio::task::spawn_blocking(move || server.handle_request(request))
+                            .await
+                        {
+                            Ok(response) => response,
+                            Err(error) => {
+                                error!("Failed to serve request from peer {}: {}", peer_id, error);
+                                return;
+                            }
+                        };
+                    let result = ProtocolId::StateSyncRpc
+                        .to_bytes(&StateSyncMessage::Response(response))
+                        .map(Into::into)
+                        .map_err(Into::into);
+                    // The requester may have timed out already.
+                    let _ = response_sender.send(result);
+                });
+            }
+            Event::RpcRequest(peer_id, message, _) => {
+                warn!(
+                    "Received an unexpected RPC from peer {}: {:?}",
+                    peer_id, message
+                );
+            }
+            Event::Message(peer_id, message) => {
+                warn!(
+                    "Received an unexpected direct send message from peer {}: {:?}",
+                    peer_id, message
+                );
+            }
+        }
+    }
+}
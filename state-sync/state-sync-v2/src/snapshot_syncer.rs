@@ -0,0 +1,388 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    error::Error,
+    network::{StateSyncRequest, StateSyncResponse, StateSyncSender},
+};
+use anyhow::ensure;
+use diem_config::{
+    config::{PeerNetworkId, StateSyncConfig},
+    network_id::NodeNetworkId,
+};
+use diem_crypto::{
+    hash::{CryptoHash, TransactionAccumulatorHasher},
+    HashValue,
+};
+use diem_infallible::RwLock;
+use diem_jellyfish_merkle::restore::StateSnapshotReceiver;
+use diem_logger::prelude::*;
+use diem_types::{
+    account_state_blob::AccountStatesChunkWithProof,
+    epoch_change::{EpochChangeProof, Verifier},
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{accumulator::InMemoryAccumulator, AccumulatorConsistencyProof},
+    transaction::{TransactionOutputListWithProof, Version},
+    waypoint::Waypoint,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use storage_interface::{DbReaderWriter, StartupInfo};
+
+/// Downloads a verified account state snapshot at the latest epoch ending ledger info known by
+/// the peers and stores it, so that the node can continue syncing from there instead of
+/// replaying all the transactions since genesis.
+pub struct StateSnapshotSyncer {
+    config: StateSyncConfig,
+    storage: DbReaderWriter,
+    waypoint: Waypoint,
+    network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+    // The peers that are currently connected, shared with the network event handlers.
+    peers: Arc<RwLock<HashSet<PeerNetworkId>>>,
+}
+
+impl StateSnapshotSyncer {
+    pub fn new(
+        config: StateSyncConfig,
+        storage: DbReaderWriter,
+        waypoint: Waypoint,
+        network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+        peers: Arc<RwLock<HashSet<PeerNetworkId>>>,
+    ) -> Self {
+        Self {
+            config,
+            storage,
+            waypoint,
+            network_senders,
+            peers,
+        }
+    }
+
+    /// Syncs the state snapshot at the latest epoch ending ledger info known by the peers. Returns
+    /// the ledger info of the synced snapshot, or `None` if the local storage is not behind it.
+    ///
+    /// Gives up after `state_snapshot_sync_timeout_ms`. The snapshot is only committed to the
+    /// ledger once complete, so the local storage can still be synced from where it was then.
+    pub async fn sync(&self) -> Result<Option<LedgerInfoWithSignatures>, Error> {
+        let timeout = Duration::from_millis(self.config.state_snapshot_sync_timeout_ms);
+        tokio::time::timeout(timeout, self.sync_impl())
+            .await
+            .map_err(|_| {
+                Error::Timeout(format!(
+                    "State snapshot sync didn't finish in {} ms.",
+                    timeout.as_millis()
+                ))
+            })?
+    }
+
+    async fn sync_impl(&self) -> Result<Option<LedgerInfoWithSignatures>, Error> {
+        let epoch_ending_lis = self
+            .fetch_epoch_ending_ledger_infos(self.storage.reader.get_startup_info()?)
+            .await?;
+        let target_li = match epoch_ending_lis.last() {
+            Some(li) => li.clone(),
+            None => return Ok(None),
+        };
+        let version = target_li.ledger_info().version();
+        if let Some((local_version, _)) =
+            self.storage.reader.get_latest_transaction_info_option()?
+        {
+            if local_version >= version {
+                info!(
+                    "Local storage at version {} is not behind the state snapshot at version {}.",
+                    local_version, version,
+                );
+                return Ok(None);
+            }
+        }
+
+        info!("Start syncing the state snapshot at version {}.", version);
+        counters::STATE_SNAPSHOT_VERSION.set(version as i64);
+        let (output_list_with_proof, frozen_subtrees) = self
+            .fetch_state_snapshot_info(target_li.ledger_info())
+            .await;
+        let state_root_hash = output_list_with_proof.proof.transaction_infos()[0].state_root_hash();
+        self.fetch_account_states(version, state_root_hash).await?;
+        self.storage.writer.finalize_state_snapshot(
+            version,
+            output_list_with_proof,
+            frozen_subtrees.into_subtrees(),
+            &epoch_ending_lis,
+        )?;
+        info!(
+            "Finished syncing the state snapshot at version {}.",
+            version
+        );
+
+        Ok(Some(target_li))
+    }
+
+    /// Fetches and verifies the epoch ending ledger infos newer than the local storage, starting
+    /// from the local epoch state if it's past the waypoint, or from the waypoint otherwise.
+    async fn fetch_epoch_ending_ledger_infos(
+        &self,
+        startup_info: Option<StartupInfo>,
+    ) -> Result<Vec<LedgerInfoWithSignatures>, Error> {
+        let (mut verifier, mut start_epoch): (Box<dyn Verifier>, u64) = match startup_info {
+            Some(startup_info)
+                if startup_info.latest_ledger_info.ledger_info().version()
+                    >= self.waypoint.version() =>
+            {
+                let epoch_state = startup_info.get_epoch_state().clone();
+                let epoch = epoch_state.epoch;
+                (Box::new(epoch_state), epoch)
+            }
+            _ => (Box::new(self.waypoint), 0),
+        };
+
+        let mut epoch_ending_lis = vec![];
+        loop {
+            let request = StateSyncRequest::GetEpochEndingLedgerInfos { start_epoch };
+            let (ledger_infos, more) = self
+                .send_request(request, |response| match response {
+                    StateSyncResponse::EpochEndingLedgerInfos(proof) => {
+                        verify_epoch_ending_ledger_infos(verifier.as_ref(), proof)
+                    }
+                    response => Err(unexpected_response(response)),
+                })
+                .await;
+            let latest_li = match ledger_infos.last() {
+                Some(li) => li.ledger_info().clone(),
+                None => break,
+            };
+            // The proof verification guarantees every ledger info carries the next epoch state.
+            verifier = Box::new(latest_li.next_epoch_state().cloned().ok_or_else(|| {
+                Error::VerificationError("Missing the next epoch state.".to_string())
+            })?);
+            start_epoch = latest_li.next_block_epoch();
+            epoch_ending_lis.extend(ledger_infos);
+            if !more {
+                break;
+            }
+        }
+        Ok(epoch_ending_lis)
+    }
+
+    /// Fetches the transaction and its output at the version of `ledger_info` and the frozen
+    /// subtrees of the transaction accumulator before it.
+    async fn fetch_state_snapshot_info(
+        &self,
+        ledger_info: &LedgerInfo,
+    ) -> (TransactionOutputListWithProof, AccumulatorConsistencyProof) {
+        let request = StateSyncRequest::GetStateSnapshotInfo {
+            version: ledger_info.version(),
+        };
+        self.send_request(request, |response| match response {
+            StateSyncResponse::StateSnapshotInfo {
+                output_list_with_proof,
+                frozen_subtrees,
+            } => {
+                verify_state_snapshot_info(ledger_info, &output_list_with_proof, &frozen_subtrees)
+                    .map_err(|e| Error::VerificationError(e.to_string()))?;
+                Ok((output_list_with_proof, frozen_subtrees))
+            }
+            response => Err(unexpected_response(response)),
+        })
+        .await
+    }
+
+    /// Fetches all the account states at `version` chunk by chunk and feeds them to the snapshot
+    /// receiver of the local storage, which verifies them against `state_root_hash`. Peers sending
+    /// a chunk that fails the verification are not asked for the following ones.
+    async fn fetch_account_states(
+        &self,
+        version: Version,
+        state_root_hash: HashValue,
+    ) -> Result<(), Error> {
+        let mut receiver = self
+            .storage
+            .writer
+            .get_state_snapshot_receiver(version, state_root_hash)?;
+        let mut last_key = None;
+        let mut num_accounts = 0;
+        let mut bad_peers = HashSet::new();
+        loop {
+            let request = StateSyncRequest::GetAccountStatesChunk {
+                version,
+                last_key,
+                chunk_size: self.config.state_snapshot_chunk_size,
+            };
+            let (peer, chunk): (_, AccountStatesChunkWithProof) = self
+                .send_request_excluding(request, &bad_peers, |response| match response {
+                    StateSyncResponse::AccountStatesChunk(chunk) => {
+                        if chunk.version != version || chunk.account_blobs.is_empty() {
+                            Err(Error::InvalidResponse(format!(
+                                "Expecting a non-empty chunk at version {}, got {} accounts at version {}.",
+                                version,
+                                chunk.account_blobs.len(),
+                                chunk.version,
+                            )))
+                        } else {
+                            Ok(chunk)
+                        }
+                    }
+                    response => Err(unexpected_response(response)),
+                })
+                .await;
+
+            let is_last_chunk = chunk.is_last_chunk();
+            let chunk_last_key = chunk.last_key();
+            let chunk_len = chunk.account_blobs.len();
+            // A chunk failing the verification leaves the receiver in an unknown state, so start
+            // over with a new one.
+            if let Err(error) = receiver.add_chunk(chunk.account_blobs, chunk.proof) {
+                warn!(
+                    "Failed to add account states chunk at version {} from peer {}: {}. \
+                    Restarting without this peer.",
+                    version, peer, error,
+                );
+                bad_peers.insert(peer);
+                receiver = self
+                    .storage
+                    .writer
+                    .get_state_snapshot_receiver(version, state_root_hash)?;
+                last_key = None;
+                num_accounts = 0;
+                continue;
+            }
+            last_key = chunk_last_key;
+            num_accounts += chunk_len;
+            counters::STATE_SNAPSHOT_NUM_ACCOUNTS.set(num_accounts as i64);
+            if is_last_chunk {
+                break;
+            }
+        }
+        receiver.finish_box()?;
+        Ok(())
+    }
+
+    /// Sends `request` to the connected peers in turn until one of them answers with a response
+    /// accepted by `process`. Retries every tick while no peer is able to serve the request.
+    async fn send_request<T>(
+        &self,
+        request: StateSyncRequest,
+        process: impl Fn(StateSyncResponse) -> Result<T, Error>,
+    ) -> T {
+        self.send_request_excluding(request, &HashSet::new(), process)
+            .await
+            .1
+    }
+
+    /// Same as `send_request`, but skips `excluded_peers` and also returns the peer which
+    /// answered.
+    async fn send_request_excluding<T>(
+        &self,
+        request: StateSyncRequest,
+        excluded_peers: &HashSet<PeerNetworkId>,
+        process: impl Fn(StateSyncResponse) -> Result<T, Error>,
+    ) -> (PeerNetworkId, T) {
+        let label = request.get_label();
+        let timeout = Duration::from_millis(self.config.state_snapshot_request_timeout_ms);
+        loop {
+            let peers: Vec<_> = self
+                .peers
+                .read()
+                .iter()
+                .filter(|peer| !excluded_peers.contains(peer))
+                .cloned()
+                .collect();
+            for peer in peers {
+                let mut sender = match self.network_senders.get(&peer.network_id()) {
+                    Some(sender) => sender.clone(),
+                    None => continue,
+                };
+                match sender
+                    .send_request(peer.peer_id(), request.clone(), timeout)
+                    .await
+                    .and_then(&process)
+                {
+                    Ok(value) => {
+                        counters::REQUESTS_SENT
+                            .with_label_values(&[label, counters::SUCCESS_LABEL])
+                            .inc();
+                        return (peer, value);
+                    }
+                    Err(error) => {
+                        counters::REQUESTS_SENT
+                            .with_label_values(&[label, counters::FAIL_LABEL])
+                            .inc();
+                        warn!("Request {:?} to peer {} failed: {}", request, peer, error);
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(self.config.tick_interval_ms)).await;
+        }
+    }
+}
+
+/// Verifies `proof` against `verifier` and returns the ledger infos that are not in the trusted
+/// prefix yet, together with whether the peer has more of them.
+fn verify_epoch_ending_ledger_infos(
+    verifier: &dyn Verifier,
+    proof: EpochChangeProof,
+) -> Result<(Vec<LedgerInfoWithSignatures>, bool), Error> {
+    if proof
+        .ledger_info_with_sigs
+        .iter()
+        .all(|li| verifier.is_ledger_info_stale(li.ledger_info()))
+    {
+        // The peer is not ahead of us.
+        return Ok((vec![], false));
+    }
+    proof
+        .verify(verifier)
+        .map_err(|e| Error::VerificationError(e.to_string()))?;
+    let EpochChangeProof {
+        ledger_info_with_sigs,
+        more,
+    } = proof;
+    Ok((
+        ledger_info_with_sigs
+            .into_iter()
+            .filter(|li| !verifier.is_ledger_info_stale(li.ledger_info()))
+            .collect(),
+        more,
+    ))
+}
+
+/// Verifies the transaction and its output at the version of `ledger_info` and the frozen subtrees
+/// of the transaction accumulator before it against `ledger_info`.
+///
+/// The write set of the output is not covered by the transaction info, only the state it results
+/// in is, which the account states of the snapshot are verified against.
+fn verify_state_snapshot_info(
+    ledger_info: &LedgerInfo,
+    output_list_with_proof: &TransactionOutputListWithProof,
+    frozen_subtrees: &AccumulatorConsistencyProof,
+) -> anyhow::Result<()> {
+    let version = ledger_info.version();
+    ensure!(
+        output_list_with_proof.len() == 1,
+        "Expecting exactly one transaction output, got {}.",
+        output_list_with_proof.len(),
+    );
+    output_list_with_proof.verify(ledger_info, Some(version))?;
+
+    let txn_info_hash = output_list_with_proof.proof.transaction_infos()[0].hash();
+    let root_hash = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+        frozen_subtrees.subtrees().to_vec(),
+        version,
+    )?
+    .append(&[txn_info_hash])
+    .root_hash();
+    ensure!(
+        root_hash == ledger_info.transaction_accumulator_hash(),
+        "Frozen subtrees don't match the transaction accumulator root hash. Expected: {}, actual: {}.",
+        ledger_info.transaction_accumulator_hash(),
+        root_hash,
+    );
+    Ok(())
+}
+
+fn unexpected_response(response: StateSyncResponse) -> Error {
+    Error::InvalidResponse(format!("Unexpected response: {:?}", response))
+}
@@ -0,0 +1,210 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_config::{
+    config::{NodeConfig, Peer, PeerRole, RoleType, HANDSHAKE_VERSION},
+    network_id::{NetworkContext, NetworkId, NodeNetworkId},
+};
+use diem_crypto::{test_utils::TEST_SEED, x25519, Uniform};
+use diem_infallible::RwLock;
+use diem_proptest_helpers::ValueGenerator;
+use diem_temppath::TempPath;
+use diem_time_service::TimeService;
+use diem_types::{
+    chain_id::ChainId,
+    ledger_info::LedgerInfoWithSignatures,
+    network_address::{parse_memory, NetworkAddress, Protocol},
+    transaction::TransactionToCommit,
+    waypoint::Waypoint,
+    PeerId,
+};
+use diemdb::{test_helper::arb_blocks_to_commit, DiemDB};
+use memsocket::MemoryListener;
+use network::peer_manager::builder::AuthenticationMode;
+use network_builder::builder::NetworkBuilder;
+use rand::{rngs::StdRng, SeedableRng};
+use state_sync_v2::{
+    error::Error,
+    network::{network_endpoint_config, StateSyncEvents, StateSyncSender},
+    service::StateSyncService,
+};
+use std::{collections::HashMap, sync::Arc};
+use storage_interface::{DbReader, DbReaderWriter};
+use tokio::runtime::Runtime;
+
+type Blocks = Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>;
+
+struct TestPeer {
+    peer_id: PeerId,
+    network_key: x25519::PrivateKey,
+    network_addr: NetworkAddress,
+}
+
+/// Creates `count` peers listening on unused `/memory/<port>` addresses.
+fn create_peers(count: usize) -> Vec<TestPeer> {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    (0..count)
+        .map(|_| {
+            let network_key = x25519::PrivateKey::generate(&mut rng);
+            // Reserve an unused port by binding port 0; the listener is discarded right away and
+            // the network rebinds to the address later.
+            let port = MemoryListener::bind(0).unwrap().local_addr();
+            let network_addr = NetworkAddress::from(Protocol::Memory(port))
+                .append_prod_protos(network_key.public_key(), HANDSHAKE_VERSION);
+            TestPeer {
+                peer_id: PeerId::random(),
+                network_key,
+                network_addr,
+            }
+        })
+        .collect()
+}
+
+/// Starts the validator network of `peers[index]` on `runtime`, with all the `peers` as seeds.
+fn start_network(
+    runtime: &Runtime,
+    peers: &[TestPeer],
+    index: usize,
+) -> (NodeNetworkId, StateSyncSender, StateSyncEvents) {
+    let peer = &peers[index];
+    let seeds: HashMap<_, _> = peers
+        .iter()
+        .map(|peer| {
+            (
+                peer.peer_id,
+                Peer::from_addrs(PeerRole::Validator, vec![peer.network_addr.clone()]),
+            )
+        })
+        .collect();
+    let network_context = Arc::new(NetworkContext::new(
+        RoleType::Validator,
+        NetworkId::Validator,
+        peer.peer_id,
+    ));
+    // Recover the base address we bound previously.
+    let (port, _suffix) = parse_memory(peer.network_addr.as_slice()).unwrap();
+    let listen_addr = NetworkAddress::from(Protocol::Memory(port));
+
+    let mut network_builder = NetworkBuilder::new_for_test(
+        ChainId::default(),
+        seeds,
+        Arc::new(RwLock::new(HashMap::new())),
+        network_context,
+        TimeService::real(),
+        listen_addr,
+        AuthenticationMode::Mutual(peer.network_key.clone()),
+    );
+    let (sender, events) = network_builder.add_protocol_handler(network_endpoint_config());
+    network_builder.build(runtime.handle().clone()).start();
+    (NodeNetworkId::new(NetworkId::Validator, 0), sender, events)
+}
+
+fn test_config() -> NodeConfig {
+    let mut config = NodeConfig::default();
+    config.state_sync.tick_interval_ms = 50;
+    config.state_sync.state_snapshot_chunk_size = 2;
+    config
+}
+
+fn start_service(
+    runtime: &Runtime,
+    peers: &[TestPeer],
+    index: usize,
+    storage: DbReaderWriter,
+    waypoint: Waypoint,
+    config: &NodeConfig,
+) -> StateSyncService {
+    StateSyncService::bootstrap(
+        vec![start_network(runtime, peers, index)],
+        storage,
+        config,
+        waypoint,
+    )
+}
+
+fn commit_blocks(db: &DiemDB, blocks: &Blocks) {
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in blocks {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+}
+
+#[test]
+fn test_sync_state_snapshot() {
+    let blocks: Blocks = ValueGenerator::new().generate(arb_blocks_to_commit());
+    let waypoint = Waypoint::new_epoch_boundary(blocks[0].1.ledger_info()).unwrap();
+    let expected_li = blocks
+        .iter()
+        .rev()
+        .map(|(_, li)| li)
+        .find(|li| li.ledger_info().ends_epoch())
+        .unwrap()
+        .clone();
+    let version = expected_li.ledger_info().version();
+
+    let server_dir = TempPath::new();
+    let (server_db, server_storage) = DbReaderWriter::wrap(DiemDB::new_for_test(&server_dir));
+    commit_blocks(&server_db, &blocks);
+    let client_dir = TempPath::new();
+    let (client_db, client_storage) = DbReaderWriter::wrap(DiemDB::new_for_test(&client_dir));
+
+    let runtime = Runtime::new().unwrap();
+    let peers = create_peers(2);
+    let config = test_config();
+    let _server = start_service(&runtime, &peers, 0, server_storage, waypoint, &config);
+    let client = start_service(&runtime, &peers, 1, client_storage, waypoint, &config);
+
+    let synced_li = client.sync_state_snapshot().unwrap();
+    assert_eq!(synced_li, Some(expected_li.clone()));
+    assert_eq!(client_db.get_latest_ledger_info().unwrap(), expected_li);
+    assert_eq!(
+        client_db.get_accumulator_root_hash(version).unwrap(),
+        server_db.get_accumulator_root_hash(version).unwrap(),
+    );
+    for (txns_to_commit, _) in &blocks {
+        for txn_to_commit in txns_to_commit {
+            for address in txn_to_commit.account_states().keys() {
+                assert_eq!(
+                    client_db
+                        .get_account_state_with_proof_by_version(*address, version)
+                        .unwrap(),
+                    server_db
+                        .get_account_state_with_proof_by_version(*address, version)
+                        .unwrap(),
+                );
+            }
+        }
+    }
+
+    // The client is not behind the latest epoch ending ledger info anymore.
+    assert_eq!(client.sync_state_snapshot().unwrap(), None);
+}
+
+#[test]
+fn test_sync_state_snapshot_timeout() {
+    let blocks: Blocks = ValueGenerator::new().generate(arb_blocks_to_commit());
+    let waypoint = Waypoint::new_epoch_boundary(blocks[0].1.ledger_info()).unwrap();
+    let client_dir = TempPath::new();
+    let (client_db, client_storage) = DbReaderWriter::wrap(DiemDB::new_for_test(&client_dir));
+
+    // No peer is there to serve the client.
+    let runtime = Runtime::new().unwrap();
+    let peers = create_peers(1);
+    let mut config = test_config();
+    config.state_sync.state_snapshot_sync_timeout_ms = 500;
+    let client = start_service(&runtime, &peers, 0, client_storage, waypoint, &config);
+
+    assert!(matches!(
+        client.sync_state_snapshot(),
+        Err(Error::Timeout(_))
+    ));
+    assert_eq!(
+        client_db.get_latest_transaction_info_option().unwrap(),
+        None
+    );
+
+    // The non-blocking sync reports that it's over as well.
+    assert!(runtime.block_on(client.start_state_snapshot_sync()).is_ok());
+}
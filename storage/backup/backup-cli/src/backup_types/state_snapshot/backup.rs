@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    backup_types::state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+    backup_types::state_snapshot::{
+        manifest::{StateSnapshotBackup, StateSnapshotChunk},
+        reader::{diff, AccountRecord, StateSnapshotReader},
+    },
     metadata::Metadata,
     storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
     utils::{
@@ -10,7 +13,7 @@ use crate::{
         should_cut_chunk, storage_ext::BackupStorageExt, GlobalBackupOpt,
     },
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Result};
 use bytes::Bytes;
 use diem_crypto::HashValue;
 use diem_logger::prelude::*;
@@ -30,10 +33,18 @@ pub struct StateSnapshotBackupOpt {
         help = "Version at which a state snapshot to be taken."
     )]
     pub version: Version,
+
+    #[structopt(
+        long = "base-state-manifest",
+        help = "Take an incremental state snapshot, storing only the accounts changed since the \
+        state snapshot of this manifest."
+    )]
+    pub base_manifest: Option<FileHandle>,
 }
 
 pub struct StateSnapshotBackupController {
     version: Version,
+    base_manifest: Option<FileHandle>,
     max_chunk_size: usize,
     client: Arc<BackupServiceClient>,
     storage: Arc<dyn BackupStorage>,
@@ -48,6 +59,7 @@ impl StateSnapshotBackupController {
     ) -> Self {
         Self {
             version: opt.version,
+            base_manifest: opt.base_manifest,
             max_chunk_size: global_opt.max_chunk_size,
             client,
             storage,
@@ -73,6 +85,20 @@ impl StateSnapshotBackupController {
             .create_backup_with_random_suffix(&self.backup_name())
             .await?;
 
+        let mut base = match &self.base_manifest {
+            Some(base_handle) => {
+                let base_manifest: StateSnapshotBackup =
+                    self.storage.load_json_file(base_handle).await?;
+                ensure!(
+                    base_manifest.version < self.version,
+                    "Base state snapshot at version {} is not older than version {}.",
+                    base_manifest.version,
+                    self.version,
+                );
+                Some(StateSnapshotReader::new(Arc::clone(&self.storage), base_manifest).await?)
+            }
+            None => None,
+        };
         let mut chunks = vec![];
 
         let mut state_snapshot_file = self.client.get_state_snapshot(self.version).await?;
@@ -96,6 +122,7 @@ impl StateSnapshotBackupController {
                         current_idx,
                         chunk_first_key,
                         Self::parse_key(&prev_record_bytes)?,
+                        base.as_mut().map(|base| (base, false)),
                     )
                     .await?;
                 chunks.push(chunk);
@@ -119,6 +146,7 @@ impl StateSnapshotBackupController {
                 current_idx,
                 chunk_first_key,
                 Self::parse_key(&prev_record_bytes)?,
+                base.as_mut().map(|base| (base, true)),
             )
             .await?;
         chunks.push(chunk);
@@ -129,7 +157,11 @@ impl StateSnapshotBackupController {
 
 impl StateSnapshotBackupController {
     fn backup_name(&self) -> String {
-        format!("state_ver_{}", self.version)
+        if self.base_manifest.is_some() {
+            format!("state_incr_ver_{}", self.version)
+        } else {
+            format!("state_ver_{}", self.version)
+        }
     }
 
     fn manifest_name() -> &'static ShellSafeName {
@@ -159,6 +191,34 @@ impl StateSnapshotBackupController {
         Ok(key)
     }
 
+    /// Only the accounts changed since the base are written for an incremental snapshot, the
+    /// chunk boundaries are still decided on all the accounts.
+    async fn delta_chunk_bytes(
+        chunk_bytes: &[u8],
+        last_key: HashValue,
+        base: &mut StateSnapshotReader,
+        is_last_chunk: bool,
+    ) -> Result<Vec<u8>> {
+        let mut records: Vec<AccountRecord> = Vec::new();
+        let mut reader = chunk_bytes;
+        while let Some(record_bytes) = reader.read_record_bytes().await? {
+            records.push(bcs::from_bytes(&record_bytes)?);
+        }
+        // Accounts removed after the last key go with the last chunk.
+        let base_records = base
+            .take_until(if is_last_chunk { None } else { Some(last_key) })
+            .await?;
+
+        let mut delta_bytes = Vec::new();
+        for record in diff(base_records, records) {
+            let record_bytes = bcs::to_bytes(&record)?;
+            delta_bytes.extend(&(record_bytes.len() as u32).to_be_bytes());
+            delta_bytes.extend(&record_bytes);
+        }
+        Ok(delta_bytes)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn write_chunk(
         &self,
         backup_handle: &BackupHandleRef,
@@ -167,12 +227,20 @@ impl StateSnapshotBackupController {
         last_idx: usize,
         first_key: HashValue,
         last_key: HashValue,
+        base: Option<(&mut StateSnapshotReader, bool)>,
     ) -> Result<StateSnapshotChunk> {
         let (chunk_handle, mut chunk_file) = self
             .storage
             .create_for_write(backup_handle, &Self::chunk_name(first_idx))
             .await?;
-        chunk_file.write_all(chunk_bytes).await?;
+        match base {
+            Some((base, is_last_chunk)) => {
+                let delta_bytes =
+                    Self::delta_chunk_bytes(chunk_bytes, last_key, base, is_last_chunk).await?;
+                chunk_file.write_all(&delta_bytes).await?;
+            }
+            None => chunk_file.write_all(chunk_bytes).await?,
+        }
         chunk_file.shutdown().await?;
         let (proof_handle, mut proof_file) = self
             .storage
@@ -216,6 +284,7 @@ impl StateSnapshotBackupController {
 
         let manifest = StateSnapshotBackup {
             version: self.version,
+            base: self.base_manifest.clone(),
             root_hash: txn_info.transaction_info().state_root_hash(),
             chunks,
             proof: proof_handle,
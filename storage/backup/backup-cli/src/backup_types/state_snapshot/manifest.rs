@@ -19,7 +19,9 @@ pub struct StateSnapshotChunk {
     /// key of the last account in this chunk.
     pub last_key: HashValue,
     /// Repeated `len(record) + record` where `record` is BCS serialized tuple
-    /// `(key, account_state_blob)`
+    /// `(key, account_state_blob)`, or `(key, Option<account_state_blob>)` in an incremental
+    /// snapshot, holding only the accounts changed (`Some`) or removed (`None`) in the key range
+    /// since the base snapshot.
     pub blobs: FileHandle,
     /// BCS serialized `SparseMerkleRangeProof` that proves this chunk adds up to the root hash
     /// indicated in the backup (`StateSnapshotBackup::root_hash`).
@@ -31,6 +33,11 @@ pub struct StateSnapshotChunk {
 pub struct StateSnapshotBackup {
     /// Version at which this state snapshot is taken.
     pub version: Version,
+    /// Manifest of the older state snapshot this one is incremental upon, if any. The chunks still
+    /// cover all accounts at `version`, so that each of them is proven by its range proof once
+    /// merged with the base.
+    #[serde(default)]
+    pub base: Option<FileHandle>,
     /// Hash of the state tree root.
     pub root_hash: HashValue,
     /// All account blobs in chunks.
@@ -3,6 +3,7 @@
 
 pub mod backup;
 pub mod manifest;
+pub(crate) mod reader;
 pub mod restore;
 
 #[cfg(test)]
@@ -0,0 +1,155 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+    storage::{BackupStorage, FileHandleRef},
+    utils::{read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt},
+};
+use anyhow::{ensure, Result};
+use diem_crypto::HashValue;
+use diem_types::account_state_blob::AccountStateBlob;
+use futures::future::{BoxFuture, FutureExt};
+use itertools::{EitherOrBoth, Itertools};
+use serde::de::DeserializeOwned;
+use std::{collections::VecDeque, sync::Arc};
+
+pub(crate) type AccountRecord = (HashValue, AccountStateBlob);
+/// An account changed (`Some`) or removed (`None`) by an incremental state snapshot.
+pub(crate) type AccountDelta = (HashValue, Option<AccountStateBlob>);
+
+/// Reads all the accounts of a state snapshot in key order, chunk by chunk, resolving the chain of
+/// base snapshots of an incremental one.
+pub(crate) struct StateSnapshotReader {
+    storage: Arc<dyn BackupStorage>,
+    chunks: VecDeque<StateSnapshotChunk>,
+    base: Option<Box<StateSnapshotReader>>,
+    /// Accounts read from a chunk but not yet taken, when reading as a base.
+    buffered: VecDeque<AccountRecord>,
+}
+
+impl StateSnapshotReader {
+    pub fn new(
+        storage: Arc<dyn BackupStorage>,
+        manifest: StateSnapshotBackup,
+    ) -> BoxFuture<'static, Result<Self>> {
+        async move {
+            let base = match &manifest.base {
+                Some(base_handle) => {
+                    let base_manifest: StateSnapshotBackup =
+                        storage.load_json_file(base_handle).await?;
+                    ensure!(
+                        base_manifest.version < manifest.version,
+                        "Base state snapshot at version {} is not older than version {}.",
+                        base_manifest.version,
+                        manifest.version,
+                    );
+                    Some(Box::new(
+                        Self::new(Arc::clone(&storage), base_manifest).await?,
+                    ))
+                }
+                None => None,
+            };
+            Ok(Self {
+                storage,
+                chunks: manifest.chunks.into(),
+                base,
+                buffered: VecDeque::new(),
+            })
+        }
+        .boxed()
+    }
+
+    /// Returns the next chunk of the manifest along with all the accounts in its key range.
+    pub fn next_chunk(
+        &mut self,
+    ) -> BoxFuture<'_, Result<Option<(StateSnapshotChunk, Vec<AccountRecord>)>>> {
+        async move {
+            let chunk = match self.chunks.pop_front() {
+                Some(chunk) => chunk,
+                None => return Ok(None),
+            };
+            let records = match self.base.as_mut() {
+                None => read_records(&self.storage, &chunk.blobs).await?,
+                Some(base) => {
+                    let delta: Vec<AccountDelta> =
+                        read_records(&self.storage, &chunk.blobs).await?;
+                    // The accounts after the last chunk can only be removed ones.
+                    let last_key = if self.chunks.is_empty() {
+                        None
+                    } else {
+                        Some(chunk.last_key)
+                    };
+                    apply_delta(base.take_until(last_key).await?, delta)
+                }
+            };
+            Ok(Some((chunk, records)))
+        }
+        .boxed()
+    }
+
+    /// Takes the accounts up to `last_key` (inclusive), all the remaining ones if `None`.
+    pub fn take_until(
+        &mut self,
+        last_key: Option<HashValue>,
+    ) -> BoxFuture<'_, Result<Vec<AccountRecord>>> {
+        async move {
+            let mut records = Vec::new();
+            loop {
+                while let Some((key, _)) = self.buffered.front() {
+                    if last_key.map_or(false, |last_key| *key > last_key) {
+                        return Ok(records);
+                    }
+                    records.extend(self.buffered.pop_front());
+                }
+                match self.next_chunk().await? {
+                    Some((_chunk, chunk_records)) => self.buffered = chunk_records.into(),
+                    None => return Ok(records),
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+async fn read_records<T: DeserializeOwned>(
+    storage: &Arc<dyn BackupStorage>,
+    file_handle: &FileHandleRef,
+) -> Result<Vec<T>> {
+    let mut file = storage.open_for_read(file_handle).await?;
+    let mut records = Vec::new();
+    while let Some(record_bytes) = file.read_record_bytes().await? {
+        records.push(bcs::from_bytes(&record_bytes)?);
+    }
+    Ok(records)
+}
+
+/// Applies the changes of an incremental snapshot to the base accounts, both sorted by key.
+pub(crate) fn apply_delta(
+    base: Vec<AccountRecord>,
+    delta: Vec<AccountDelta>,
+) -> Vec<AccountRecord> {
+    base.into_iter()
+        .merge_join_by(delta, |(base_key, _), (key, _)| base_key.cmp(key))
+        .filter_map(|entry| match entry {
+            EitherOrBoth::Left(record) => Some(record),
+            EitherOrBoth::Right((key, blob)) | EitherOrBoth::Both(_, (key, blob)) => {
+                blob.map(|blob| (key, blob))
+            }
+        })
+        .collect()
+}
+
+/// Computes the changes from the base accounts to the current ones, both sorted by key.
+pub(crate) fn diff(base: Vec<AccountRecord>, current: Vec<AccountRecord>) -> Vec<AccountDelta> {
+    base.into_iter()
+        .merge_join_by(current, |(base_key, _), (key, _)| base_key.cmp(key))
+        .filter_map(|entry| match entry {
+            EitherOrBoth::Left((key, _)) => Some((key, None)),
+            EitherOrBoth::Right((key, blob)) => Some((key, Some(blob))),
+            EitherOrBoth::Both((_, base_blob), (key, blob)) => {
+                (base_blob != blob).then(|| (key, Some(blob)))
+            }
+        })
+        .collect()
+}
@@ -3,7 +3,8 @@
 
 use crate::{
     backup_types::{
-        epoch_ending::restore::EpochHistory, state_snapshot::manifest::StateSnapshotBackup,
+        epoch_ending::restore::EpochHistory,
+        state_snapshot::{manifest::StateSnapshotBackup, reader::StateSnapshotReader},
     },
     metrics::{
         restore::{
@@ -15,17 +16,12 @@ use crate::{
         },
     },
     storage::{BackupStorage, FileHandle},
-    utils::{
-        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, GlobalRestoreOptions,
-        RestoreRunMode,
-    },
+    utils::{storage_ext::BackupStorageExt, GlobalRestoreOptions, RestoreRunMode},
 };
 use anyhow::{anyhow, ensure, Result};
-use diem_crypto::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
-    account_state_blob::AccountStateBlob, ledger_info::LedgerInfoWithSignatures,
-    proof::TransactionInfoWithProof, transaction::Version,
+    ledger_info::LedgerInfoWithSignatures, proof::TransactionInfoWithProof, transaction::Version,
 };
 use std::sync::Arc;
 use structopt::StructOpt;
@@ -129,8 +125,10 @@ impl StateSnapshotRestoreController {
         // FIXME update counters
         ver_gauge.set(self.version as i64);
         tgt_leaf_idx.set(manifest.chunks.last().map_or(0, |c| c.last_idx as i64));
-        for chunk in manifest.chunks {
-            let blobs = self.read_account_state_chunk(chunk.blobs).await?;
+        // An incremental snapshot is merged with its bases chunk by chunk, the range proofs of its
+        // chunks verify the merged accounts.
+        let mut reader = StateSnapshotReader::new(Arc::clone(&self.storage), manifest).await?;
+        while let Some((chunk, blobs)) = reader.next_chunk().await? {
             let proof = self.storage.load_bcs_file(&chunk.proof).await?;
 
             receiver.add_chunk(blobs, proof)?;
@@ -140,19 +138,4 @@ impl StateSnapshotRestoreController {
         receiver.finish()?;
         Ok(())
     }
-
-    async fn read_account_state_chunk(
-        &self,
-        file_handle: FileHandle,
-    ) -> Result<Vec<(HashValue, AccountStateBlob)>> {
-        let mut file = self.storage.open_for_read(&file_handle).await?;
-
-        let mut chunk = vec![];
-
-        while let Some(record_bytes) = file.read_record_bytes().await? {
-            chunk.push(bcs::from_bytes(&record_bytes)?);
-        }
-
-        Ok(chunk)
-    }
 }
@@ -6,7 +6,7 @@ use crate::{
         backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
     },
-    storage::{local_fs::LocalFs, BackupStorage},
+    storage::{local_fs::LocalFs, transform::TransformedStorage, BackupStorage},
     utils::{
         backup_service_client::BackupServiceClient,
         test_utils::{start_local_backup_service, tmp_db_with_random_content},
@@ -43,7 +43,10 @@ fn end_to_end() {
     let manifest_handle = rt
         .block_on(
             StateSnapshotBackupController::new(
-                StateSnapshotBackupOpt { version },
+                StateSnapshotBackupOpt {
+                    version,
+                    base_manifest: None,
+                },
                 GlobalBackupOpt {
                     max_chunk_size: 500,
                 },
@@ -81,6 +84,98 @@ fn end_to_end() {
         &tgt_db_dir,
         true, /* read_only */
         None, /* pruner */
+        None, /* ledger_pruner */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        tgt_db
+            .get_latest_tree_state()
+            .unwrap()
+            .account_state_root_hash,
+        state_root_hash,
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
+
+#[test]
+fn incremental_end_to_end() {
+    let (_src_db_dir, src_db, _blocks) = tmp_db_with_random_content();
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(TransformedStorage::new(
+        Arc::new(LocalFs::new(backup_dir.path().to_path_buf())),
+        true,          /* compress */
+        Some([7; 32]), /* key */
+    ));
+
+    let latest_tree_state = src_db.get_latest_tree_state().unwrap();
+    let version = latest_tree_state.num_transactions - 1;
+    let state_root_hash = latest_tree_state.account_state_root_hash;
+    // The random content can be a single transaction, leaving nothing to increment upon.
+    if version == 0 {
+        return;
+    }
+
+    let (rt, port) = start_local_backup_service(src_db);
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+
+    // A full snapshot at genesis, then an incremental one on top of it.
+    let mut manifest_handle = None;
+    for snapshot_version in [0, version] {
+        manifest_handle = Some(
+            rt.block_on(
+                StateSnapshotBackupController::new(
+                    StateSnapshotBackupOpt {
+                        version: snapshot_version,
+                        base_manifest: manifest_handle,
+                    },
+                    GlobalBackupOpt {
+                        max_chunk_size: 500,
+                    },
+                    Arc::clone(&client),
+                    Arc::clone(&store),
+                )
+                .run(),
+            )
+            .unwrap(),
+        );
+    }
+
+    rt.block_on(
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: manifest_handle.unwrap(),
+                version: PRE_GENESIS_VERSION,
+            },
+            GlobalRestoreOpt {
+                dry_run: false,
+                db_dir: Some(tgt_db_dir.path().to_path_buf()),
+                target_version: None, // max
+                trusted_waypoints: TrustedWaypointOpt::default(),
+                rocksdb_opt: RocksdbOpt::default(),
+                concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            }
+            .try_into()
+            .unwrap(),
+            store,
+            None, /* epoch_history */
+        )
+        .run(),
+    )
+    .unwrap();
+
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* read_only */
+        None, /* pruner */
+        None, /* ledger_pruner */
         RocksdbConfig::default(),
     )
     .unwrap();
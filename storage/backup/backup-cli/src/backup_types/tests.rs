@@ -3,6 +3,7 @@
 
 use crate::{
     backup_types::{
+        epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
         state_snapshot::{
             backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
             restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
@@ -12,22 +13,44 @@ use crate::{
             restore::{TransactionRestoreController, TransactionRestoreOpt},
         },
     },
-    storage::{local_fs::LocalFs, BackupStorage},
+    coordinators::{
+        gc::{GcCoordinator, GcCoordinatorOpt},
+        restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+        verify::VerifyCoordinator,
+    },
+    metadata::cache::MetadataCacheOpt,
+    storage::{
+        local_fs::LocalFs, BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef,
+        ShellSafeName, TextLine,
+    },
     utils::{
-        backup_service_client::BackupServiceClient, test_utils::start_local_backup_service,
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, GlobalRestoreOptions,
-        RocksdbOpt, TrustedWaypointOpt,
+        backup_service_client::BackupServiceClient, storage_ext::BackupStorageExt,
+        test_utils::start_local_backup_service, ConcurrentDownloadsOpt, GlobalBackupOpt,
+        GlobalRestoreOpt, GlobalRestoreOptions, RocksdbOpt, TrustedWaypointOpt,
     },
 };
+use anyhow::{bail, Result};
+use async_trait::async_trait;
 use diem_config::config::RocksdbConfig;
 use diem_temppath::TempPath;
-use diem_types::transaction::Version;
+use diem_types::transaction::{Transaction, Version};
 use diemdb::DiemDB;
 use executor_test_helpers::integration_test_impl::test_execution_with_storage_impl;
 use proptest::prelude::*;
-use std::{convert::TryInto, sync::Arc};
+use std::{
+    convert::TryInto,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 use storage_interface::DbReader;
-use tokio::time::Duration;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    runtime::Runtime,
+    time::Duration,
+};
 
 #[derive(Debug)]
 struct TestData {
@@ -82,7 +105,10 @@ fn test_end_to_end_impl(d: TestData) {
     let state_snapshot_manifest = d.state_snapshot_ver.map(|version| {
         rt.block_on(
             StateSnapshotBackupController::new(
-                StateSnapshotBackupOpt { version },
+                StateSnapshotBackupOpt {
+                    version,
+                    base_manifest: None,
+                },
                 global_backup_opt.clone(),
                 Arc::clone(&client),
                 Arc::clone(&store),
@@ -153,6 +179,7 @@ fn test_end_to_end_impl(d: TestData) {
         &tgt_db_dir,
         false, /* read_only */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         RocksdbConfig::default(),
     )
     .unwrap();
@@ -197,3 +224,439 @@ proptest! {
         test_end_to_end_impl(d)
     }
 }
+
+/// Restores the backups with the coordinator, returning the latest version restored.
+fn restore_with_coordinator(
+    rt: &Runtime,
+    store: &Arc<dyn BackupStorage>,
+    target_version: Option<Version>,
+    target_timestamp_usecs: Option<u64>,
+) -> Version {
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    let metadata_cache_dir = TempPath::new();
+
+    rt.block_on(
+        RestoreCoordinator::new(
+            RestoreCoordinatorOpt {
+                metadata_cache_opt: MetadataCacheOpt::new(Some(
+                    metadata_cache_dir.path().to_path_buf(),
+                )),
+                replay_all: false,
+                target_timestamp_usecs,
+            },
+            GlobalRestoreOpt {
+                dry_run: false,
+                db_dir: Some(tgt_db_dir.path().to_path_buf()),
+                target_version,
+                trusted_waypoints: TrustedWaypointOpt::default(),
+                rocksdb_opt: RocksdbOpt::default(),
+                concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            }
+            .try_into()
+            .unwrap(),
+            Arc::clone(store),
+        )
+        .run(),
+    )
+    .unwrap();
+
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* read_only */
+        None, /* pruner */
+        None, /* ledger_pruner */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    tgt_db
+        .get_latest_transaction_info_option()
+        .unwrap()
+        .unwrap()
+        .0
+}
+
+#[test]
+fn test_restore_to_timestamp() {
+    let db = test_execution_with_storage_impl();
+    let latest_ver = db.get_latest_version().unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let (rt, port) = start_local_backup_service(Arc::clone(&db));
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+
+    // Backup everything, with a state snapshot at genesis to replay the rest on.
+    let global_backup_opt = GlobalBackupOpt {
+        max_chunk_size: 2048,
+    };
+    let latest_epoch = db
+        .get_latest_ledger_info()
+        .unwrap()
+        .ledger_info()
+        .next_block_epoch();
+    rt.block_on(
+        EpochEndingBackupController::new(
+            EpochEndingBackupOpt {
+                start_epoch: 0,
+                end_epoch: latest_epoch,
+            },
+            global_backup_opt.clone(),
+            Arc::clone(&client),
+            Arc::clone(&store),
+        )
+        .run(),
+    )
+    .unwrap();
+    rt.block_on(
+        StateSnapshotBackupController::new(
+            StateSnapshotBackupOpt {
+                version: 0,
+                base_manifest: None,
+            },
+            global_backup_opt.clone(),
+            Arc::clone(&client),
+            Arc::clone(&store),
+        )
+        .run(),
+    )
+    .unwrap();
+    rt.block_on(
+        TransactionBackupController::new(
+            TransactionBackupOpt {
+                start_version: 0,
+                num_transactions: latest_ver as usize + 1,
+            },
+            global_backup_opt,
+            Arc::clone(&client),
+            Arc::clone(&store),
+        )
+        .run(),
+    )
+    .unwrap();
+
+    // (version, timestamp) of the blocks.
+    let blocks: Vec<_> = db
+        .get_transactions(0, latest_ver + 1, latest_ver, false /* fetch_events */)
+        .unwrap()
+        .transactions
+        .into_iter()
+        .enumerate()
+        .filter_map(|(version, txn)| match txn {
+            Transaction::BlockMetadata(block_metadata) => {
+                Some((version as Version, block_metadata.timestamp_usec()))
+            }
+            _ => None,
+        })
+        .collect();
+    let (_, timestamp_usecs) = blocks[blocks.len() / 2];
+    let (next_block_ver, _) = *blocks.iter().find(|(_, ts)| *ts > timestamp_usecs).unwrap();
+    let expected_ver = next_block_ver - 1;
+
+    // Restores up to the version right before the first newer block.
+    assert_eq!(
+        restore_with_coordinator(&rt, &store, None, Some(timestamp_usecs)),
+        expected_ver
+    );
+    // The target version is used if smaller.
+    assert_eq!(
+        restore_with_coordinator(&rt, &store, Some(expected_ver - 1), Some(timestamp_usecs)),
+        expected_ver - 1
+    );
+    // Or if the timestamp is later than all the blocks up to it.
+    let (last_block_ver, last_timestamp_usecs) = *blocks.last().unwrap();
+    assert_eq!(
+        restore_with_coordinator(
+            &rt,
+            &store,
+            Some(last_block_ver),
+            Some(last_timestamp_usecs)
+        ),
+        last_block_ver
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
+
+/// Fails the deletions after the first `deletes_left` ones, like a run interrupted midway.
+struct InterruptedStorage {
+    inner: Arc<dyn BackupStorage>,
+    deletes_left: AtomicUsize,
+}
+
+impl InterruptedStorage {
+    fn new(inner: &Arc<dyn BackupStorage>, deletes_left: usize) -> Arc<dyn BackupStorage> {
+        Arc::new(Self {
+            inner: Arc::clone(inner),
+            deletes_left: AtomicUsize::new(deletes_left),
+        })
+    }
+
+    fn delete(&self) -> Result<()> {
+        if self
+            .deletes_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_err()
+        {
+            bail!("Interrupted.");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BackupStorage for InterruptedStorage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        self.inner.create_backup(name).await
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        self.inner.create_for_write(backup_handle, name).await
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        self.inner.open_for_read(file_handle).await
+    }
+
+    async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.inner.save_metadata_line(name, content).await
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        self.inner.list_metadata_files().await
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
+        self.inner.save_metadata_lines(name, lines).await
+    }
+
+    async fn delete_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        self.delete()?;
+        self.inner.delete_metadata_file(file_handle).await
+    }
+
+    async fn list_backups(&self) -> Result<Vec<BackupHandle>> {
+        self.inner.list_backups().await
+    }
+
+    async fn list_backup_files(&self, backup_handle: &BackupHandleRef) -> Result<Vec<FileHandle>> {
+        self.inner.list_backup_files(backup_handle).await
+    }
+
+    async fn delete_backup(&self, backup_handle: &BackupHandleRef) -> Result<()> {
+        self.delete()?;
+        self.inner.delete_backup(backup_handle).await
+    }
+}
+
+fn gc(rt: &Runtime, store: Arc<dyn BackupStorage>, delete_orphans: bool) -> Result<()> {
+    rt.block_on(
+        GcCoordinator::new(
+            GcCoordinatorOpt {
+                state_snapshots_to_keep: 1,
+                transaction_compaction_batch_size: 1_000_000,
+                epoch_ending_compaction_batch_size: 1_000,
+                delete_orphans,
+                dry_run: false,
+                concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            },
+            store,
+        )
+        .run(),
+    )
+}
+
+fn verify(rt: &Runtime, store: &Arc<dyn BackupStorage>) {
+    let metadata_cache_dir = TempPath::new();
+    rt.block_on(
+        VerifyCoordinator::new(
+            Arc::clone(store),
+            MetadataCacheOpt::new(Some(metadata_cache_dir.path().to_path_buf())),
+            TrustedWaypointOpt::default(),
+            ConcurrentDownloadsOpt::default().get(),
+        )
+        .unwrap()
+        .run(),
+    )
+    .unwrap()
+}
+
+/// All the files of all the backups in the storage.
+fn backup_files(rt: &Runtime, store: &Arc<dyn BackupStorage>) -> Vec<FileHandle> {
+    rt.block_on(async {
+        let mut files = Vec::new();
+        for backup_handle in store.list_backups().await.unwrap() {
+            files.extend(store.list_backup_files(&backup_handle).await.unwrap());
+        }
+        files
+    })
+}
+
+#[test]
+fn test_gc_end_to_end() {
+    let db = test_execution_with_storage_impl();
+    let latest_ver = db.get_latest_version().unwrap();
+    let mid_ver = latest_ver / 2;
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+    let (rt, port) = start_local_backup_service(Arc::clone(&db));
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+    let global_backup_opt = GlobalBackupOpt {
+        max_chunk_size: 2048,
+    };
+
+    // Adjacent epoch ending and transaction backups, to be compacted.
+    let latest_epoch = db
+        .get_latest_ledger_info()
+        .unwrap()
+        .ledger_info()
+        .next_block_epoch();
+    let mut epoch_boundaries = vec![0, latest_epoch];
+    if latest_epoch > 1 {
+        epoch_boundaries.insert(1, 1);
+    }
+    for range in epoch_boundaries.windows(2) {
+        rt.block_on(
+            EpochEndingBackupController::new(
+                EpochEndingBackupOpt {
+                    start_epoch: range[0],
+                    end_epoch: range[1],
+                },
+                global_backup_opt.clone(),
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+    }
+    for (start_version, last_version) in [(0, mid_ver), (mid_ver + 1, latest_ver)] {
+        rt.block_on(
+            TransactionBackupController::new(
+                TransactionBackupOpt {
+                    start_version,
+                    num_transactions: (last_version - start_version + 1) as usize,
+                },
+                global_backup_opt.clone(),
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+    }
+
+    // A full snapshot to expire, and the latest snapshot incremental upon a full one, which must
+    // be kept as its base.
+    let backup_state_snapshot = |version, base_manifest| {
+        rt.block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt {
+                    version,
+                    base_manifest,
+                },
+                global_backup_opt.clone(),
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap()
+    };
+    let expired_manifest = backup_state_snapshot(0, None);
+    let base_manifest = backup_state_snapshot(mid_ver, None);
+    let latest_manifest = backup_state_snapshot(latest_ver, Some(base_manifest.clone()));
+
+    // Left by an interrupted backup.
+    rt.block_on(async {
+        let backup_handle = store
+            .create_backup_with_random_suffix("orphan")
+            .await
+            .unwrap();
+        let (_, mut file) = store
+            .create_for_write(&backup_handle, &ShellSafeName::from_str("file").unwrap())
+            .await
+            .unwrap();
+        file.write_all(b"orphan").await.unwrap();
+        file.shutdown().await.unwrap();
+    });
+    let num_backups = rt.block_on(store.list_backups()).unwrap().len();
+    let num_metadata_files = rt.block_on(store.list_metadata_files()).unwrap().len();
+
+    // Interrupted before deleting the old metadata files: both the old and the compacted
+    // metadata are visible.
+    assert!(gc(&rt, InterruptedStorage::new(&store, 0), false).is_err());
+    let num_compacted = if epoch_boundaries.len() > 2 { 2 } else { 1 };
+    assert_eq!(
+        rt.block_on(store.list_backups()).unwrap().len(),
+        num_backups + num_compacted
+    );
+    assert_eq!(
+        rt.block_on(store.list_metadata_files()).unwrap().len(),
+        num_metadata_files + 1
+    );
+    verify(&rt, &store);
+    assert_eq!(
+        restore_with_coordinator(&rt, &store, None, None),
+        latest_ver
+    );
+    assert_eq!(
+        restore_with_coordinator(&rt, &store, Some(mid_ver), None),
+        mid_ver
+    );
+
+    // Interrupted before deleting the expired snapshot: only the compacted metadata is visible.
+    assert!(gc(
+        &rt,
+        InterruptedStorage::new(&store, num_metadata_files + 1),
+        false
+    )
+    .is_err());
+    assert_eq!(rt.block_on(store.list_metadata_files()).unwrap().len(), 1);
+    assert!(backup_files(&rt, &store).contains(&expired_manifest));
+    verify(&rt, &store);
+    assert_eq!(
+        restore_with_coordinator(&rt, &store, None, None),
+        latest_ver
+    );
+
+    // The expired snapshot is left over as an orphan, deleted along with the other orphans.
+    gc(&rt, Arc::clone(&store), false).unwrap();
+    assert!(backup_files(&rt, &store).contains(&expired_manifest));
+    gc(&rt, Arc::clone(&store), true).unwrap();
+    let files = backup_files(&rt, &store);
+    assert!(!files.contains(&expired_manifest));
+    assert!(files.contains(&base_manifest));
+    assert!(files.contains(&latest_manifest));
+    assert!(!rt
+        .block_on(store.list_backups())
+        .unwrap()
+        .iter()
+        .any(|backup_handle| backup_handle.starts_with("orphan")));
+
+    verify(&rt, &store);
+    assert_eq!(
+        restore_with_coordinator(&rt, &store, None, None),
+        latest_ver
+    );
+    assert_eq!(
+        restore_with_coordinator(&rt, &store, Some(mid_ver), None),
+        mid_ver
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
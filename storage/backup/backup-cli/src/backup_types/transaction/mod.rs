@@ -4,6 +4,7 @@
 pub mod backup;
 pub mod manifest;
 pub mod restore;
+pub mod timestamp;
 
 #[cfg(test)]
 pub mod tests;
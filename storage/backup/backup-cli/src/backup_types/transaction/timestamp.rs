@@ -0,0 +1,90 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::transaction::manifest::{TransactionBackup, TransactionChunk},
+    storage::{BackupStorage, FileHandle},
+    utils::{read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX},
+};
+use anyhow::{ensure, Result};
+use diem_logger::prelude::*;
+use diem_types::{
+    contract_event::ContractEvent,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::TransactionAccumulatorRangeProof,
+    transaction::{Transaction, TransactionInfo, Version},
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use std::sync::Arc;
+use tokio::io::BufReader;
+
+/// Resolves the version of the DB as of the block timestamp `timestamp_usecs`, i.e. the version
+/// right before the first block newer than it, by looking into the transaction backups, which are
+/// expected to be continuous in range.
+///
+/// Returns `None` if there's no block newer than the timestamp in the backups.
+pub async fn get_version_by_timestamp(
+    storage: &Arc<dyn BackupStorage>,
+    manifest_handles: &[FileHandle],
+    timestamp_usecs: u64,
+    concurrent_downloads: usize,
+) -> Result<Option<Version>> {
+    let mut chunks = Vec::new();
+    for manifest_handle in manifest_handles {
+        let manifest: TransactionBackup = storage.load_json_file(manifest_handle).await?;
+        manifest.verify()?;
+        chunks.extend(manifest.chunks);
+    }
+
+    // The ledger info in the proof of a chunk is at or after the last version of the chunk, so a
+    // chunk can't contain a block newer than its ledger info, which saves loading most chunks.
+    let futs = chunks.into_iter().map(|chunk| async move {
+        let (_, ledger_info) = storage
+            .load_bcs_file::<(TransactionAccumulatorRangeProof, LedgerInfoWithSignatures)>(
+                &chunk.proof,
+            )
+            .await?;
+        Result::<_>::Ok((chunk, ledger_info.ledger_info().timestamp_usecs()))
+    });
+    let mut candidates = stream::iter(futs)
+        .buffered_x(
+            concurrent_downloads * 2, /* buffer size */
+            concurrent_downloads,     /* concurrency */
+        )
+        .try_filter(|(_chunk, li_timestamp_usecs)| {
+            futures::future::ready(*li_timestamp_usecs > timestamp_usecs)
+        })
+        .boxed();
+
+    while let Some((chunk, _)) = candidates.try_next().await? {
+        if let Some(version) = first_block_newer_than(storage, &chunk, timestamp_usecs).await? {
+            ensure!(version > 0, "Block found at version 0.");
+            info!(
+                "First block newer than timestamp {} is at version {}.",
+                timestamp_usecs, version,
+            );
+            return Ok(Some(version - 1));
+        }
+    }
+    Ok(None)
+}
+
+async fn first_block_newer_than(
+    storage: &Arc<dyn BackupStorage>,
+    chunk: &TransactionChunk,
+    timestamp_usecs: u64,
+) -> Result<Option<Version>> {
+    let mut file = BufReader::new(storage.open_for_read(&chunk.transactions).await?);
+    let mut version = chunk.first_version;
+    while let Some(record_bytes) = file.read_record_bytes().await? {
+        let (txn, _txn_info, _events): (Transaction, TransactionInfo, Vec<ContractEvent>) =
+            bcs::from_bytes(&record_bytes)?;
+        if let Transaction::BlockMetadata(block_metadata) = txn {
+            if block_metadata.timestamp_usec() > timestamp_usecs {
+                return Ok(Some(version));
+            }
+        }
+        version += 1;
+    }
+    Ok(None)
+}
@@ -12,7 +12,10 @@ use backup_cli::{
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
-    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        gc::{GcCoordinator, GcCoordinatorOpt},
+    },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::StorageOpt,
     utils::{
@@ -30,6 +33,11 @@ enum Command {
     OneShot(OneShotCommand),
     #[structopt(about = "Long running process backing up the chain continuously.")]
     Coordinator(CoordinatorCommand),
+    #[structopt(
+        about = "Apply the retention policy to the backups in the storage, compact their metadata \
+        and delete the backups no longer needed."
+    )]
+    Gc(GcOpt),
 }
 
 #[derive(StructOpt)]
@@ -124,6 +132,15 @@ struct CoordinatorRunOpt {
     storage: StorageOpt,
 }
 
+#[derive(StructOpt)]
+struct GcOpt {
+    #[structopt(flatten)]
+    gc: GcCoordinatorOpt,
+
+    #[structopt(subcommand)]
+    storage: StorageOpt,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     main_impl().await.map_err(|e| {
@@ -208,6 +225,11 @@ async fn main_impl() -> Result<()> {
                 .await?;
             }
         },
+        Command::Gc(opt) => {
+            GcCoordinator::new(opt.gc, opt.storage.init_storage().await?)
+                .run()
+                .await?;
+        }
     }
     Ok(())
 }
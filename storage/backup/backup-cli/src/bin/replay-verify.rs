@@ -57,6 +57,7 @@ async fn main_impl() -> Result<()> {
         opt.db_dir,
         false, /* read_only */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         opt.rocksdb_opt.into(),
     )?)
     .get_restore_handler();
@@ -240,6 +240,7 @@ impl BackupCoordinator {
         StateSnapshotBackupController::new(
             StateSnapshotBackupOpt {
                 version: next_snapshot_version,
+                base_manifest: None,
             },
             self.global_opt.clone(),
             Arc::clone(&self.client),
@@ -0,0 +1,521 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::manifest::EpochEndingBackup, state_snapshot::manifest::StateSnapshotBackup,
+        transaction::manifest::TransactionBackup,
+    },
+    metadata::{cache::LoadMetadataLines, view::MetadataView, Metadata},
+    metrics::gc::{
+        GC_COORDINATOR_FAIL_TS, GC_COORDINATOR_START_TS, GC_COORDINATOR_SUCC_TS, GC_DELETED_BACKUPS,
+    },
+    storage::{BackupStorage, FileHandle, ShellSafeName},
+    utils::{
+        storage_ext::BackupStorageExt, stream::StreamX, unix_timestamp_sec, ConcurrentDownloadsOpt,
+    },
+};
+use anyhow::{ensure, Result};
+use diem_logger::prelude::*;
+use futures::{stream, TryStreamExt};
+use rand::random;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    str::FromStr,
+    sync::Arc,
+};
+use structopt::StructOpt;
+use tokio::io::AsyncWriteExt;
+
+#[derive(StructOpt)]
+pub struct GcCoordinatorOpt {
+    // Epoch endings and transactions are all kept, since any restore starts from the first
+    // epoch, and the verify coordinator replays all transactions.
+    #[structopt(
+        long,
+        default_value = "3",
+        help = "Number of the latest state snapshots to keep, along with the base snapshots of the \
+        incremental ones."
+    )]
+    pub state_snapshots_to_keep: usize,
+    #[structopt(
+        long,
+        default_value = "10000000",
+        help = "Adjacent transaction backups are compacted into backups of up to this many \
+        transactions."
+    )]
+    pub transaction_compaction_batch_size: usize,
+    #[structopt(
+        long,
+        default_value = "1000",
+        help = "Adjacent epoch ending backups are compacted into backups of up to this many epochs."
+    )]
+    pub epoch_ending_compaction_batch_size: usize,
+    #[structopt(
+        long,
+        help = "Also delete the backups not referenced by any metadata, like the ones left by \
+        interrupted runs. Only safe while no backup is being taken."
+    )]
+    pub delete_orphans: bool,
+    #[structopt(long, help = "Log what would be done without changing the storage.")]
+    pub dry_run: bool,
+    #[structopt(flatten)]
+    pub concurernt_downloads: ConcurrentDownloadsOpt,
+}
+
+impl GcCoordinatorOpt {
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.state_snapshots_to_keep > 0,
+            "At least one state snapshot must be kept."
+        );
+        ensure!(
+            self.transaction_compaction_batch_size > 0
+                && self.epoch_ending_compaction_batch_size > 0,
+            "Compaction batch sizes must be greater than 0."
+        );
+        Ok(())
+    }
+}
+
+/// Files referenced by the metadata before and after the garbage collection.
+#[derive(Default)]
+struct References {
+    before: HashSet<FileHandle>,
+    after: HashSet<FileHandle>,
+}
+
+impl References {
+    fn add(&mut self, files: Vec<FileHandle>, kept: bool) {
+        if kept {
+            self.after.extend(files.iter().cloned());
+        }
+        self.before.extend(files);
+    }
+}
+
+/// Applies the retention policy to the backups in the storage, compacts the metadata of the kept
+/// ones and deletes the backups no longer referenced.
+///
+/// The kept metadata entries are saved in a single new metadata file before the old ones are
+/// deleted, readers seeing both drop the entries covered by the compacted ones. Backups are only
+/// deleted after that.
+pub struct GcCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    state_snapshots_to_keep: usize,
+    transaction_compaction_batch_size: usize,
+    epoch_ending_compaction_batch_size: usize,
+    delete_orphans: bool,
+    dry_run: bool,
+    concurrent_downloads: usize,
+}
+
+impl GcCoordinator {
+    pub fn new(opt: GcCoordinatorOpt, storage: Arc<dyn BackupStorage>) -> Self {
+        opt.validate().unwrap();
+        Self {
+            storage,
+            state_snapshots_to_keep: opt.state_snapshots_to_keep,
+            transaction_compaction_batch_size: opt.transaction_compaction_batch_size,
+            epoch_ending_compaction_batch_size: opt.epoch_ending_compaction_batch_size,
+            delete_orphans: opt.delete_orphans,
+            dry_run: opt.dry_run,
+            concurrent_downloads: opt.concurernt_downloads.get(),
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!("GC coordinator started.");
+        GC_COORDINATOR_START_TS.set(unix_timestamp_sec());
+
+        let ret = self.run_impl().await;
+
+        if let Err(e) = &ret {
+            error!(
+                error = ?e,
+                "GC coordinator failed."
+            );
+            GC_COORDINATOR_FAIL_TS.set(unix_timestamp_sec());
+        } else {
+            info!("GC coordinator exiting with success.");
+            GC_COORDINATOR_SUCC_TS.set(unix_timestamp_sec());
+        }
+
+        ret
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let metadata_files = self.storage.list_metadata_files().await?;
+        let view = MetadataView::from(self.load_metadata(&metadata_files).await?);
+
+        let mut refs = References::default();
+        let mut kept = Vec::new();
+        self.compact_epoch_endings(&view, &mut refs, &mut kept)
+            .await?;
+        self.compact_transactions(&view, &mut refs, &mut kept)
+            .await?;
+        self.expire_state_snapshots(&view, &mut refs, &mut kept)
+            .await?;
+
+        if !self.dry_run && !metadata_files.is_empty() {
+            self.rewrite_metadata(&metadata_files, &kept).await?;
+        }
+        self.delete_unreferenced_backups(&refs).await
+    }
+
+    async fn load_metadata(&self, metadata_files: &[FileHandle]) -> Result<Vec<Metadata>> {
+        let futs = metadata_files.iter().map(|file_handle| async move {
+            let mut file = self.storage.open_for_read(file_handle).await?;
+            file.load_metadata_lines().await
+        });
+        let metadata: Vec<Vec<Metadata>> = stream::iter(futs)
+            .buffered_x(
+                self.concurrent_downloads * 2, /* buffer size */
+                self.concurrent_downloads,     /* concurrency */
+            )
+            .try_collect()
+            .await?;
+        Ok(metadata.into_iter().flatten().collect())
+    }
+
+    async fn load_manifests<T: DeserializeOwned + Send + 'static>(
+        &self,
+        manifest_handles: Vec<FileHandle>,
+    ) -> Result<Vec<T>> {
+        let futs = manifest_handles
+            .into_iter()
+            .map(|manifest_handle| async move {
+                self.storage.load_json_file::<T>(&manifest_handle).await
+            });
+        stream::iter(futs)
+            .buffered_x(
+                self.concurrent_downloads * 2, /* buffer size */
+                self.concurrent_downloads,     /* concurrency */
+            )
+            .try_collect()
+            .await
+    }
+
+    /// Returns `None` on a dry run.
+    async fn write_manifest<T: Serialize + Sync>(
+        &self,
+        backup_name: &str,
+        manifest_name: &str,
+        manifest: &T,
+    ) -> Result<Option<FileHandle>> {
+        if self.dry_run {
+            return Ok(None);
+        }
+        let backup_handle = self
+            .storage
+            .create_backup_with_random_suffix(backup_name)
+            .await?;
+        let (manifest_handle, mut manifest_file) = self
+            .storage
+            .create_for_write(&backup_handle, &ShellSafeName::from_str(manifest_name)?)
+            .await?;
+        manifest_file
+            .write_all(&serde_json::to_vec(manifest)?)
+            .await?;
+        manifest_file.shutdown().await?;
+        Ok(Some(manifest_handle))
+    }
+
+    async fn compact_epoch_endings(
+        &self,
+        view: &MetadataView,
+        refs: &mut References,
+        kept: &mut Vec<Metadata>,
+    ) -> Result<()> {
+        let runs = compaction_runs(
+            view.epoch_ending_backups(),
+            |e| (e.first_epoch, e.last_epoch),
+            self.epoch_ending_compaction_batch_size,
+        );
+        for run in runs {
+            let manifests: Vec<EpochEndingBackup> = self
+                .load_manifests(run.iter().map(|e| e.manifest.clone()).collect())
+                .await?;
+            let compact = run.len() > 1;
+            for (backup, manifest) in run.iter().zip(&manifests) {
+                let mut files = vec![backup.manifest.clone()];
+                files.extend(manifest.chunks.iter().map(|c| c.ledger_infos.clone()));
+                // The chunks are kept by the compacted manifest.
+                refs.add(files, !compact);
+            }
+            if !compact {
+                kept.extend(run.into_iter().map(Metadata::EpochEndingBackup));
+                continue;
+            }
+
+            let (first, last) = (&run[0], &run[run.len() - 1]);
+            info!(
+                "Compacting {} epoch ending backups of epochs [{}, {}].",
+                run.len(),
+                first.first_epoch,
+                last.last_epoch,
+            );
+            let mut compacted = EpochEndingBackup {
+                first_epoch: first.first_epoch,
+                last_epoch: last.last_epoch,
+                waypoints: Vec::new(),
+                chunks: Vec::new(),
+            };
+            for manifest in manifests {
+                compacted.waypoints.extend(manifest.waypoints);
+                compacted.chunks.extend(manifest.chunks);
+            }
+            compacted.verify()?;
+            refs.after
+                .extend(compacted.chunks.iter().map(|c| c.ledger_infos.clone()));
+
+            if let Some(manifest_handle) = self
+                .write_manifest(
+                    &format!(
+                        "epoch_ending_compacted_{}-{}",
+                        first.first_epoch, last.last_epoch
+                    ),
+                    "epoch_ending.manifest",
+                    &compacted,
+                )
+                .await?
+            {
+                refs.after.insert(manifest_handle.clone());
+                kept.push(Metadata::new_epoch_ending_backup(
+                    first.first_epoch,
+                    last.last_epoch,
+                    first.first_version,
+                    last.last_version,
+                    manifest_handle,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn compact_transactions(
+        &self,
+        view: &MetadataView,
+        refs: &mut References,
+        kept: &mut Vec<Metadata>,
+    ) -> Result<()> {
+        let runs = compaction_runs(
+            view.transaction_backups(),
+            |t| (t.first_version, t.last_version),
+            self.transaction_compaction_batch_size,
+        );
+        for run in runs {
+            let manifests: Vec<TransactionBackup> = self
+                .load_manifests(run.iter().map(|t| t.manifest.clone()).collect())
+                .await?;
+            let compact = run.len() > 1;
+            for (backup, manifest) in run.iter().zip(&manifests) {
+                let mut files = vec![backup.manifest.clone()];
+                for chunk in &manifest.chunks {
+                    files.push(chunk.transactions.clone());
+                    files.push(chunk.proof.clone());
+                }
+                // The chunks are kept by the compacted manifest.
+                refs.add(files, !compact);
+            }
+            if !compact {
+                kept.extend(run.into_iter().map(Metadata::TransactionBackup));
+                continue;
+            }
+
+            let (first, last) = (&run[0], &run[run.len() - 1]);
+            info!(
+                "Compacting {} transaction backups of versions [{}, {}].",
+                run.len(),
+                first.first_version,
+                last.last_version,
+            );
+            let compacted = TransactionBackup {
+                first_version: first.first_version,
+                last_version: last.last_version,
+                chunks: manifests.into_iter().flat_map(|m| m.chunks).collect(),
+            };
+            compacted.verify()?;
+            for chunk in &compacted.chunks {
+                refs.after.insert(chunk.transactions.clone());
+                refs.after.insert(chunk.proof.clone());
+            }
+
+            if let Some(manifest_handle) = self
+                .write_manifest(
+                    &format!(
+                        "transaction_compacted_{}-{}",
+                        first.first_version, last.last_version
+                    ),
+                    "transaction.manifest",
+                    &compacted,
+                )
+                .await?
+            {
+                refs.after.insert(manifest_handle.clone());
+                kept.push(Metadata::new_transaction_backup(
+                    first.first_version,
+                    last.last_version,
+                    manifest_handle,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn expire_state_snapshots(
+        &self,
+        view: &MetadataView,
+        refs: &mut References,
+        kept: &mut Vec<Metadata>,
+    ) -> Result<()> {
+        let snapshots = view.state_snapshot_backups();
+        let manifest_handles: Vec<FileHandle> =
+            snapshots.iter().map(|s| s.manifest.clone()).collect();
+        let mut manifests: HashMap<FileHandle, StateSnapshotBackup> = manifest_handles
+            .clone()
+            .into_iter()
+            .zip(self.load_manifests(manifest_handles).await?)
+            .collect();
+
+        // The latest snapshots, and the chains of base snapshots they are incremental upon.
+        let mut to_keep: Vec<FileHandle> = snapshots
+            .iter()
+            .rev()
+            .take(self.state_snapshots_to_keep)
+            .map(|s| s.manifest.clone())
+            .collect();
+        let mut kept_manifests = HashSet::new();
+        while let Some(manifest_handle) = to_keep.pop() {
+            if !kept_manifests.insert(manifest_handle.clone()) {
+                continue;
+            }
+            if !manifests.contains_key(&manifest_handle) {
+                // A base snapshot whose metadata is lost.
+                let manifest = self.storage.load_json_file(&manifest_handle).await?;
+                manifests.insert(manifest_handle.clone(), manifest);
+            }
+            to_keep.extend(manifests[&manifest_handle].base.clone());
+        }
+
+        for (manifest_handle, manifest) in &manifests {
+            let mut files = vec![manifest_handle.clone(), manifest.proof.clone()];
+            for chunk in &manifest.chunks {
+                files.push(chunk.blobs.clone());
+                files.push(chunk.proof.clone());
+            }
+            refs.add(files, kept_manifests.contains(manifest_handle));
+        }
+        for snapshot in snapshots {
+            if kept_manifests.contains(&snapshot.manifest) {
+                kept.push(Metadata::StateSnapshotBackup(snapshot.clone()));
+            } else {
+                info!("Expiring state snapshot at version {}.", snapshot.version);
+            }
+        }
+        Ok(())
+    }
+
+    async fn rewrite_metadata(
+        &self,
+        metadata_files: &[FileHandle],
+        kept: &[Metadata],
+    ) -> Result<()> {
+        let lines = kept
+            .iter()
+            .map(Metadata::to_text_line)
+            .collect::<Result<Vec<_>>>()?;
+        let name: ShellSafeName = format!(
+            "compacted_{}.{:04x}.meta",
+            unix_timestamp_sec(),
+            random::<u16>()
+        )
+        .try_into()?;
+        self.storage.save_metadata_lines(&name, &lines).await?;
+        for file_handle in metadata_files {
+            self.storage.delete_metadata_file(file_handle).await?;
+        }
+        info!(
+            "Compacted {} metadata files into {}.",
+            metadata_files.len(),
+            name.as_str(),
+        );
+        Ok(())
+    }
+
+    async fn delete_unreferenced_backups(&self, refs: &References) -> Result<()> {
+        let mut num_deleted = 0;
+        for backup_handle in self.storage.list_backups().await? {
+            let files = self.storage.list_backup_files(&backup_handle).await?;
+            if files.iter().any(|file| refs.after.contains(file)) {
+                continue;
+            }
+            let expired = files.iter().any(|file| refs.before.contains(file));
+            if !expired && !self.delete_orphans {
+                continue;
+            }
+            info!(
+                "Deleting {} backup {}{}.",
+                if expired { "expired" } else { "orphaned" },
+                backup_handle,
+                if self.dry_run { " (dry run)" } else { "" },
+            );
+            if !self.dry_run {
+                self.storage.delete_backup(&backup_handle).await?;
+                num_deleted += 1;
+            }
+        }
+        GC_DELETED_BACKUPS.set(num_deleted);
+        Ok(())
+    }
+}
+
+/// Groups the backups, sorted by range, into runs of adjacent ones spanning up to `batch_size`.
+fn compaction_runs<T: Clone>(
+    backups: &[T],
+    range: impl Fn(&T) -> (u64, u64),
+    batch_size: usize,
+) -> Vec<Vec<T>> {
+    let mut runs: Vec<Vec<T>> = Vec::new();
+    for backup in backups {
+        let (first, last) = range(backup);
+        if let Some(run) = runs.last_mut() {
+            let (run_first, _) = range(&run[0]);
+            let (_, run_last) = range(&run[run.len() - 1]);
+            if first == run_last + 1 && last - run_first < batch_size as u64 {
+                run.push(backup.clone());
+                continue;
+            }
+        }
+        runs.push(vec![backup.clone()]);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coordinators::gc::compaction_runs;
+
+    #[test]
+    fn test_compaction_runs() {
+        let runs = |ranges: &[(u64, u64)], batch_size| compaction_runs(ranges, |r| *r, batch_size);
+
+        assert!(runs(&[], 10).is_empty());
+        assert_eq!(runs(&[(0, 9)], 10), vec![vec![(0, 9)]]);
+        assert_eq!(
+            runs(&[(0, 4), (5, 9), (10, 14)], 10),
+            vec![vec![(0, 4), (5, 9)], vec![(10, 14)]]
+        );
+        assert_eq!(
+            runs(&[(0, 4), (5, 9), (10, 14)], 100),
+            vec![vec![(0, 4), (5, 9), (10, 14)]]
+        );
+        // Not adjacent.
+        assert_eq!(
+            runs(&[(0, 4), (6, 9), (10, 14)], 100),
+            vec![vec![(0, 4)], vec![(6, 9), (10, 14)]]
+        );
+    }
+}
@@ -5,7 +5,9 @@ use crate::{
     backup_types::{
         epoch_ending::restore::EpochHistoryRestoreController,
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
-        transaction::restore::TransactionRestoreBatchController,
+        transaction::{
+            restore::TransactionRestoreBatchController, timestamp::get_version_by_timestamp,
+        },
     },
     metadata,
     metadata::{cache::MetadataCacheOpt, TransactionBackupMeta},
@@ -30,6 +32,13 @@ pub struct RestoreCoordinatorOpt {
         help = "Replay all transactions, don't try to use a state snapshot."
     )]
     pub replay_all: bool,
+    #[structopt(
+        long,
+        help = "Restore the DB as of this block timestamp, in microseconds since the unix epoch, \
+        i.e. up to the version right before the first block newer than it. If --target-version is \
+        also specified, the smaller of the two versions is used."
+    )]
+    pub target_timestamp_usecs: Option<u64>,
 }
 
 pub struct RestoreCoordinator {
@@ -37,6 +46,7 @@ pub struct RestoreCoordinator {
     global_opt: GlobalRestoreOptions,
     metadata_cache_opt: MetadataCacheOpt,
     replay_all: bool,
+    target_timestamp_usecs: Option<u64>,
 }
 
 impl RestoreCoordinator {
@@ -50,6 +60,7 @@ impl RestoreCoordinator {
             global_opt,
             metadata_cache_opt: opt.metadata_cache_opt,
             replay_all: opt.replay_all,
+            target_timestamp_usecs: opt.target_timestamp_usecs,
         }
     }
 
@@ -73,7 +84,7 @@ impl RestoreCoordinator {
         ret
     }
 
-    async fn run_impl(self) -> Result<()> {
+    async fn run_impl(mut self) -> Result<()> {
         let metadata_view = metadata::cache::sync_and_load(
             &self.metadata_cache_opt,
             Arc::clone(&self.storage),
@@ -81,7 +92,42 @@ impl RestoreCoordinator {
         )
         .await?;
 
-        let transactions = metadata_view.select_transaction_backups(0, self.target_version())?;
+        let mut transactions =
+            metadata_view.select_transaction_backups(0, self.target_version())?;
+        if let Some(timestamp_usecs) = self.target_timestamp_usecs {
+            match self
+                .resolve_timestamp(timestamp_usecs, &transactions)
+                .await?
+            {
+                Some(version) => {
+                    if version < self.target_version() {
+                        self.global_opt.target_version = version;
+                        transactions = metadata_view.select_transaction_backups(0, version)?;
+                    }
+                }
+                // No block newer than the timestamp up to the target version, which is then the
+                // smaller of the two.
+                None if transactions
+                    .last()
+                    .map_or(false, |b| b.last_version >= self.target_version()) =>
+                {
+                    info!(
+                        "Timestamp {} is later than the target version {}, restoring to the latter.",
+                        timestamp_usecs,
+                        self.target_version(),
+                    );
+                }
+                // Without a newer block, the backups might miss blocks up to the timestamp.
+                None => bail!(
+                    "No block newer than timestamp {} found in the transaction backups{}.",
+                    timestamp_usecs,
+                    match transactions.last() {
+                        Some(b) => format!(", which end at version {}", b.last_version),
+                        None => "".to_string(),
+                    },
+                ),
+            }
+        }
         let actual_target_version = self.get_actual_target_version(&transactions)?;
         let epoch_endings = metadata_view.select_epoch_ending_backups(actual_target_version)?;
         let state_snapshot = if self.replay_all {
@@ -162,6 +208,32 @@ impl RestoreCoordinator {
         self.global_opt.target_version
     }
 
+    /// The version as of the block timestamp, if there's a block newer than it in the backups.
+    async fn resolve_timestamp(
+        &self,
+        timestamp_usecs: u64,
+        transaction_backups: &[TransactionBackupMeta],
+    ) -> Result<Option<Version>> {
+        let manifest_handles = transaction_backups
+            .iter()
+            .map(|b| b.manifest.clone())
+            .collect::<Vec<_>>();
+        let version = get_version_by_timestamp(
+            &self.storage,
+            &manifest_handles,
+            timestamp_usecs,
+            self.global_opt.concurrent_downloads,
+        )
+        .await?;
+        if let Some(version) = version {
+            info!(
+                "Resolved timestamp {} to version {}.",
+                timestamp_usecs, version
+            );
+        }
+        Ok(version)
+    }
+
     fn get_actual_target_version(
         &self,
         transaction_backups: &[TransactionBackupMeta],
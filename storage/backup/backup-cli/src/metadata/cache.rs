@@ -47,6 +47,10 @@ impl MetadataCacheOpt {
     // in cache we save things other than the cached files.
     const SUB_DIR: &'static str = "cache";
 
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
     fn cache_dir(&self) -> PathBuf {
         self.dir
             .clone()
@@ -183,7 +187,7 @@ impl FileHandleHash for FileHandle {
 }
 
 #[async_trait]
-trait LoadMetadataLines {
+pub(crate) trait LoadMetadataLines {
     async fn load_metadata_lines(&mut self) -> Result<Vec<Metadata>>;
 }
 
@@ -7,7 +7,7 @@ use crate::metadata::{
 use anyhow::{anyhow, ensure, Result};
 use diem_types::transaction::Version;
 use itertools::Itertools;
-use std::{fmt, str::FromStr};
+use std::{cmp::Reverse, fmt, str::FromStr};
 
 pub struct MetadataView {
     epoch_ending_backups: Vec<EpochEndingBackupMeta>,
@@ -16,6 +16,18 @@ pub struct MetadataView {
 }
 
 impl MetadataView {
+    pub fn epoch_ending_backups(&self) -> &[EpochEndingBackupMeta] {
+        &self.epoch_ending_backups
+    }
+
+    pub fn state_snapshot_backups(&self) -> &[StateSnapshotBackupMeta] {
+        &self.state_snapshot_backups
+    }
+
+    pub fn transaction_backups(&self) -> &[TransactionBackupMeta] {
+        &self.transaction_backups
+    }
+
     pub fn get_storage_state(&self) -> BackupStorageState {
         let latest_epoch_ending_epoch =
             self.epoch_ending_backups.iter().map(|e| e.last_epoch).max();
@@ -119,14 +131,43 @@ impl From<Vec<Metadata>> for MetadataView {
             }
         }
 
+        state_snapshot_backups.sort();
+        state_snapshot_backups.dedup();
+
         Self {
-            epoch_ending_backups,
+            epoch_ending_backups: drop_covered(epoch_ending_backups, |e| {
+                (e.first_epoch, e.last_epoch)
+            }),
             state_snapshot_backups,
-            transaction_backups,
+            transaction_backups: drop_covered(transaction_backups, |t| {
+                (t.first_version, t.last_version)
+            }),
         }
     }
 }
 
+/// Sorts the backups by range, dropping the ones whose range is covered by another backup, like
+/// the ones compacted by the garbage collection whose metadata is not yet deleted.
+fn drop_covered<T>(mut backups: Vec<T>, range: impl Fn(&T) -> (u64, u64)) -> Vec<T> {
+    backups.sort_by_key(|backup| {
+        let (first, last) = range(backup);
+        (first, Reverse(last))
+    });
+    let mut covered_until = None;
+    backups
+        .into_iter()
+        .filter(|backup| {
+            let (_, last) = range(backup);
+            if covered_until.map_or(false, |covered_until| last <= covered_until) {
+                false
+            } else {
+                covered_until = Some(last);
+                true
+            }
+        })
+        .collect()
+}
+
 pub struct BackupStorageState {
     pub latest_epoch_ending_epoch: Option<u64>,
     pub latest_state_snapshot_version: Option<Version>,
@@ -0,0 +1,37 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_secure_push_metrics::{register_int_gauge, IntGauge};
+use once_cell::sync::Lazy;
+
+pub static GC_COORDINATOR_START_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_gc_coordinator_start_timestamp_s",
+        "Timestamp when the gc coordinator starts."
+    )
+    .unwrap()
+});
+
+pub static GC_COORDINATOR_SUCC_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_gc_coordinator_succeed_timestamp_s",
+        "Timestamp when the gc coordinator exits with success."
+    )
+    .unwrap()
+});
+
+pub static GC_COORDINATOR_FAIL_TS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_gc_coordinator_fail_timestamp_s",
+        "Timestamp when the gc coordinator fails."
+    )
+    .unwrap()
+});
+
+pub static GC_DELETED_BACKUPS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_db_backup_gc_deleted_backups",
+        "Number of backups deleted by the last gc run."
+    )
+    .unwrap()
+});
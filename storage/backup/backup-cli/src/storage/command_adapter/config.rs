@@ -67,6 +67,33 @@ pub struct Commands {
     /// Command line to list all existing metadata file handles.
     /// expected stdout to stream out lines of file handles.
     pub list_metadata_files: String,
+    /// Command line to save multiple lines of metadata into one file, as a whole or not at all.
+    /// Defaults to `save_metadata_line`.
+    /// input env vars:
+    ///     $FILE_NAME
+    /// stdin will be fed with lines of text, each with a trailing newline.
+    #[serde(default)]
+    pub save_metadata_lines: Option<String>,
+    /// Command line to delete a metadata file, only needed by the garbage collection.
+    /// input env vars:
+    ///     $FILE_HANDLE
+    #[serde(default)]
+    pub delete_metadata_file: Option<String>,
+    /// Command line to list all existing backup handles, only needed by the garbage collection.
+    /// expected stdout to stream out lines of backup handles.
+    #[serde(default)]
+    pub list_backups: Option<String>,
+    /// Command line to list the files of a backup, only needed by the garbage collection.
+    /// input env vars:
+    ///     $BACKUP_HANDLE
+    /// expected stdout to stream out lines of file handles.
+    #[serde(default)]
+    pub list_backup_files: Option<String>,
+    /// Command line to delete a backup and all its files, only needed by the garbage collection.
+    /// input env vars:
+    ///     $BACKUP_HANDLE
+    #[serde(default)]
+    pub delete_backup: Option<String>,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -13,12 +13,13 @@ use crate::{
             command::Command,
             config::{CommandAdapterConfig, EnvVar},
         },
+        transform::TransformOpt,
         BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
         TextLine,
     },
     utils::error_notes::ErrorNotes,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::path::PathBuf;
 use structopt::StructOpt;
@@ -31,6 +32,9 @@ pub struct CommandAdapterOpt {
         help = "Config file for the command adapter backup store."
     )]
     config: PathBuf,
+
+    #[structopt(flatten)]
+    pub transform: TransformOpt,
 }
 
 /// A BackupStorage that delegates required APIs to configured command lines.
@@ -53,6 +57,28 @@ impl CommandAdapter {
     fn cmd(&self, cmd_str: &str, env_vars: Vec<EnvVar>) -> Command {
         Command::new(cmd_str, env_vars, self.config.env_vars.clone())
     }
+
+    fn optional_cmd(
+        &self,
+        cmd_str: &Option<String>,
+        name: &str,
+        env_vars: Vec<EnvVar>,
+    ) -> Result<Command> {
+        let cmd_str = cmd_str
+            .as_ref()
+            .ok_or_else(|| anyhow!("Command {} is not configured.", name))?;
+        Ok(self.cmd(cmd_str, env_vars))
+    }
+
+    async fn read_lines(cmd: Command) -> Result<Vec<String>> {
+        let mut buf = String::new();
+        cmd.spawn()?
+            .into_data_source()
+            .read_to_string(&mut buf)
+            .await
+            .err_notes((file!(), line!(), &buf))?;
+        Ok(buf.lines().map(str::to_string).collect())
+    }
 }
 
 #[async_trait]
@@ -131,16 +157,68 @@ impl BackupStorage for CommandAdapter {
     }
 
     async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
-        let child = self
-            .cmd(&self.config.commands.list_metadata_files, vec![])
+        Self::read_lines(self.cmd(&self.config.commands.list_metadata_files, vec![])).await
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
+        let cmd_str = self
+            .config
+            .commands
+            .save_metadata_lines
+            .as_ref()
+            .unwrap_or(&self.config.commands.save_metadata_line);
+        let mut child = self
+            .cmd(cmd_str, vec![EnvVar::file_name(name.to_string())])
             .spawn()?;
 
-        let mut buf = FileHandle::new();
-        child
-            .into_data_source()
-            .read_to_string(&mut buf)
-            .await
-            .err_notes((file!(), line!(), &buf))?;
-        Ok(buf.lines().map(str::to_string).collect())
+        for line in lines {
+            child
+                .stdin()
+                .write_all(line.as_ref().as_bytes())
+                .await
+                .err_notes(name)?;
+        }
+        child.join().await?;
+        Ok(())
+    }
+
+    async fn delete_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        self.optional_cmd(
+            &self.config.commands.delete_metadata_file,
+            "delete_metadata_file",
+            vec![EnvVar::file_handle(file_handle.to_string())],
+        )?
+        .spawn()?
+        .join()
+        .await
+    }
+
+    async fn list_backups(&self) -> Result<Vec<BackupHandle>> {
+        Self::read_lines(self.optional_cmd(
+            &self.config.commands.list_backups,
+            "list_backups",
+            vec![],
+        )?)
+        .await
+    }
+
+    async fn list_backup_files(&self, backup_handle: &BackupHandleRef) -> Result<Vec<FileHandle>> {
+        Self::read_lines(self.optional_cmd(
+            &self.config.commands.list_backup_files,
+            "list_backup_files",
+            vec![EnvVar::backup_handle(backup_handle.to_string())],
+        )?)
+        .await
+    }
+
+    async fn delete_backup(&self, backup_handle: &BackupHandleRef) -> Result<()> {
+        self.optional_cmd(
+            &self.config.commands.delete_backup,
+            "delete_backup",
+            vec![EnvVar::backup_handle(backup_handle.to_string())],
+        )?
+        .spawn()?
+        .join()
+        .await
     }
 }
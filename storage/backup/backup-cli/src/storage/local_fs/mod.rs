@@ -7,15 +7,21 @@ mod tests;
 use super::{BackupHandle, BackupHandleRef, FileHandle, FileHandleRef};
 
 use crate::{
-    storage::{BackupStorage, ShellSafeName, TextLine},
+    storage::{transform::TransformOpt, BackupStorage, ShellSafeName, TextLine},
     utils::{error_notes::ErrorNotes, path_exists, PathToString},
 };
 use anyhow::Result;
 use async_trait::async_trait;
-use std::path::{Path, PathBuf};
+use rand::random;
+use std::{
+    path::{Path, PathBuf},
+    slice,
+};
 use structopt::StructOpt;
 use tokio::{
-    fs::{create_dir, create_dir_all, read_dir, OpenOptions},
+    fs::{
+        create_dir, create_dir_all, hard_link, read_dir, remove_dir_all, remove_file, OpenOptions,
+    },
     io::{AsyncRead, AsyncWrite, AsyncWriteExt},
 };
 
@@ -27,6 +33,9 @@ pub struct LocalFsOpt {
         help = "Target local dir to hold backups."
     )]
     pub dir: PathBuf,
+
+    #[structopt(flatten)]
+    pub transform: TransformOpt,
 }
 
 /// A storage backend that stores everything in a local directory.
@@ -49,6 +58,18 @@ impl LocalFs {
     pub fn metadata_dir(&self) -> PathBuf {
         self.dir.join(Self::METADATA_DIR)
     }
+
+    async fn list_dir(dir: &Path) -> Result<Vec<(String, bool)>> {
+        let mut res = Vec::new();
+        if path_exists(dir).await {
+            let mut entries = read_dir(dir).await.err_notes(dir)?;
+            while let Some(entry) = entries.next_entry().await.err_notes(dir)? {
+                let is_dir = entry.file_type().await.err_notes(dir)?.is_dir();
+                res.push((entry.file_name().path_to_string()?, is_dir))
+            }
+        }
+        Ok(res)
+    }
 }
 
 #[async_trait]
@@ -92,34 +113,79 @@ impl BackupStorage for LocalFs {
     }
 
     async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.save_metadata_lines(name, slice::from_ref(content))
+            .await
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        let rel_path = Path::new(Self::METADATA_DIR);
+        Self::list_dir(&self.metadata_dir())
+            .await?
+            .into_iter()
+            // Skip the files being written by `save_metadata_lines`.
+            .filter(|(name, _is_dir)| !name.starts_with('.'))
+            .map(|(name, _is_dir)| rel_path.join(name).path_to_string())
+            .collect()
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
         let dir = self.metadata_dir();
         create_dir_all(&dir).await.err_notes(name)?; // in case not yet created
 
+        // Written to a hidden file first and hard linked, so that the file appears as a whole.
+        // The temporary file is unique to this call, and linking fails if the metadata file
+        // already exists, so concurrent writers never clobber each other.
         let path = dir.join(name.as_ref());
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(&path)
-            .await
-            .err_notes(&path)?;
-        file.write_all(content.as_ref().as_bytes())
-            .await
-            .err_notes(&path)?;
+        let tmp_path = dir.join(format!(".{}.{:016x}", name.as_ref(), random::<u64>()));
+        let res = async {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&tmp_path)
+                .await
+                .err_notes(&tmp_path)?;
+            for line in lines {
+                file.write_all(line.as_ref().as_bytes())
+                    .await
+                    .err_notes(&tmp_path)?;
+            }
+            file.shutdown().await.err_notes(&tmp_path)?;
+            hard_link(&tmp_path, &path).await.err_notes(&path)
+        }
+        .await;
+        // Best effort, a leftover hidden file is ignored by `list_metadata_files`.
+        let _ = remove_file(&tmp_path).await;
 
+        res
+    }
+
+    async fn delete_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        let path = self.dir.join(file_handle);
+        remove_file(&path).await.err_notes(&path)?;
         Ok(())
     }
 
-    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
-        let dir = self.metadata_dir();
-        let rel_path = Path::new(Self::METADATA_DIR);
+    async fn list_backups(&self) -> Result<Vec<BackupHandle>> {
+        Ok(Self::list_dir(&self.dir)
+            .await?
+            .into_iter()
+            .filter(|(name, is_dir)| *is_dir && name != Self::METADATA_DIR)
+            .map(|(name, _is_dir)| name)
+            .collect())
+    }
 
-        let mut res = Vec::new();
-        if path_exists(&dir).await {
-            let mut entries = read_dir(&dir).await.err_notes(&dir)?;
-            while let Some(entry) = entries.next_entry().await.err_notes(&dir)? {
-                res.push(rel_path.join(entry.file_name()).path_to_string()?)
-            }
-        }
-        Ok(res)
+    async fn list_backup_files(&self, backup_handle: &BackupHandleRef) -> Result<Vec<FileHandle>> {
+        let rel_path = Path::new(backup_handle);
+        Self::list_dir(&self.dir.join(backup_handle))
+            .await?
+            .into_iter()
+            .map(|(name, _is_dir)| rel_path.join(name).path_to_string())
+            .collect()
+    }
+
+    async fn delete_backup(&self, backup_handle: &BackupHandleRef) -> Result<()> {
+        let path = self.dir.join(backup_handle);
+        remove_dir_all(&path).await.err_notes(&path)?;
+        Ok(())
     }
 }
@@ -3,12 +3,13 @@
 
 use super::*;
 use crate::storage::test_util::{
-    arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
+    arb_backups, arb_metadata_files, test_delete_impl, test_save_and_list_metadata_files_impl,
     test_write_and_read_impl,
 };
 use diem_temppath::TempPath;
 use proptest::prelude::*;
-use tokio::runtime::Runtime;
+use std::str::FromStr;
+use tokio::{io::AsyncReadExt, runtime::Runtime};
 
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
@@ -36,4 +37,68 @@ proptest! {
         let rt = Runtime::new().unwrap();
         rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
     }
+
+    #[test]
+    fn test_delete(
+        backups in arb_backups(),
+        input in arb_metadata_files(),
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store = LocalFs::new(tmpdir.path().to_path_buf());
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_delete_impl(Box::new(store), backups, input));
+    }
+}
+
+#[test]
+fn test_save_metadata_lines_no_clobber() {
+    let tmpdir = TempPath::new();
+    tmpdir.create_as_dir().unwrap();
+    let store = LocalFs::new(tmpdir.path().to_path_buf());
+    let name = ShellSafeName::from_str("name.meta").unwrap();
+    let lines: Vec<_> = (0..10)
+        .map(|i| TextLine::new(&i.to_string()).unwrap())
+        .collect();
+
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        // Concurrent writers of the same file, only one of them wins.
+        let results = futures::future::join_all(
+            lines
+                .iter()
+                .map(|line| store.save_metadata_line(&name, line)),
+        )
+        .await;
+        assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 1);
+
+        let file_handles = store.list_metadata_files().await.unwrap();
+        assert_eq!(file_handles.len(), 1);
+        let mut content = String::new();
+        store
+            .open_for_read(&file_handles[0])
+            .await
+            .unwrap()
+            .read_to_string(&mut content)
+            .await
+            .unwrap();
+        // The whole content of one of the writers.
+        assert!(lines.iter().any(|line| line.as_ref() == content));
+
+        // An existing file is not overwritten.
+        assert!(store
+            .save_metadata_line(&name, &TextLine::new("other").unwrap())
+            .await
+            .is_err());
+
+        // No temporary file is left.
+        assert_eq!(
+            LocalFs::list_dir(&store.metadata_dir())
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    });
 }
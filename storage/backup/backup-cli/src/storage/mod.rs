@@ -3,6 +3,7 @@
 
 pub mod command_adapter;
 pub mod local_fs;
+pub mod transform;
 
 #[cfg(test)]
 mod test_util;
@@ -12,6 +13,7 @@ mod tests;
 use crate::storage::{
     command_adapter::{CommandAdapter, CommandAdapterOpt},
     local_fs::{LocalFs, LocalFsOpt},
+    transform::TransformOpt,
 };
 use anyhow::{ensure, Result};
 use async_trait::async_trait;
@@ -100,7 +102,7 @@ impl Arbitrary for ShellSafeName {
     }
 }
 
-#[cfg_attr(test, derive(Debug, Hash, Eq, Ord, PartialEq, PartialOrd))]
+#[cfg_attr(test, derive(Clone, Debug, Hash, Eq, Ord, PartialEq, PartialOrd))]
 pub struct TextLine(String);
 
 impl TextLine {
@@ -167,6 +169,19 @@ pub trait BackupStorage: Send + Sync {
     ///   2. But the cache does expect the content stays the same for a file handle, so when
     /// reorganising metadata files, give them new unique names.
     async fn list_metadata_files(&self) -> Result<Vec<FileHandle>>;
+    /// Saves multiple metadata entries in one metadata file, to compact the metadata. The file
+    /// should either be saved as a whole or not at all.
+    /// See `save_metadata_line`.
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()>;
+    /// Deletes a metadata file returned by `list_metadata_files`, after its entries are compacted
+    /// into another one or expired.
+    async fn delete_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()>;
+    /// Lists the handles of all the backups created by `create_backup`.
+    async fn list_backups(&self) -> Result<Vec<BackupHandle>>;
+    /// Lists the handles of all the files created for a backup by `create_for_write`.
+    async fn list_backup_files(&self, backup_handle: &BackupHandleRef) -> Result<Vec<FileHandle>>;
+    /// Deletes a backup along with all its files.
+    async fn delete_backup(&self, backup_handle: &BackupHandleRef) -> Result<()>;
 }
 
 #[derive(StructOpt)]
@@ -179,9 +194,18 @@ pub enum StorageOpt {
 
 impl StorageOpt {
     pub async fn init_storage(self) -> Result<Arc<dyn BackupStorage>> {
-        Ok(match self {
+        let transform = self.transform_opt().clone();
+        let storage: Arc<dyn BackupStorage> = match self {
             StorageOpt::LocalFs(opt) => Arc::new(LocalFs::new_with_opt(opt)),
             StorageOpt::CommandAdapter(opt) => Arc::new(CommandAdapter::new_with_opt(opt).await?),
-        })
+        };
+        transform.init(storage)
+    }
+
+    fn transform_opt(&self) -> &TransformOpt {
+        match self {
+            StorageOpt::LocalFs(opt) => &opt.transform,
+            StorageOpt::CommandAdapter(opt) => &opt.transform,
+        }
     }
 }
@@ -11,7 +11,7 @@ use proptest::{
     collection::{hash_map, vec},
     prelude::*,
 };
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, str::FromStr};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 fn to_file_name(backup_name: &str, file_name: &str) -> String {
@@ -47,6 +47,92 @@ pub async fn test_write_and_read_impl(
     }
 }
 
+pub async fn test_delete_impl(
+    store: Box<dyn BackupStorage>,
+    mut backups: HashMap<ShellSafeName, HashMap<ShellSafeName, Vec<u8>>>,
+    metadata_files: Vec<(ShellSafeName, TextLine)>,
+) {
+    // Not to be confused with the metadata folder.
+    backups.retain(|backup_name, _| backup_name.as_str() != "metadata");
+    for (backup_name, files) in &backups {
+        let backup_handle = store.create_backup(backup_name).await.unwrap();
+        for (name, content) in files {
+            let (_, mut file) = store.create_for_write(&backup_handle, name).await.unwrap();
+            file.write_all(content).await.unwrap();
+            file.shutdown().await.unwrap();
+        }
+    }
+    for (name, content) in &metadata_files {
+        store.save_metadata_line(name, content).await.unwrap();
+    }
+
+    // List backups and their files.
+    let backup_handles = store.list_backups().await.unwrap();
+    assert_eq!(
+        backup_handles.iter().sorted().collect::<Vec<_>>(),
+        backups
+            .keys()
+            .map(|name| name.as_str())
+            .sorted()
+            .collect::<Vec<_>>(),
+    );
+    for (backup_name, files) in &backups {
+        assert_eq!(
+            store
+                .list_backup_files(backup_name)
+                .await
+                .unwrap()
+                .into_iter()
+                .sorted()
+                .collect::<Vec<_>>(),
+            files
+                .keys()
+                .map(|name| to_file_name(backup_name, name))
+                .sorted()
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    // Compact the metadata into a single file.
+    let metadata_file_handles = store.list_metadata_files().await.unwrap();
+    let lines = metadata_files
+        .into_iter()
+        .map(|(_name, content)| content)
+        .sorted()
+        .collect::<Vec<_>>();
+    let compacted_name = ShellSafeName::from_str("compacted.meta.0").unwrap();
+    store
+        .save_metadata_lines(&compacted_name, &lines)
+        .await
+        .unwrap();
+    for file_handle in &metadata_file_handles {
+        store.delete_metadata_file(file_handle).await.unwrap();
+    }
+    let compacted_file_handles = store.list_metadata_files().await.unwrap();
+    assert_eq!(compacted_file_handles.len(), 1);
+    let mut buf = String::new();
+    store
+        .open_for_read(&compacted_file_handles[0])
+        .await
+        .unwrap()
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    assert_eq!(
+        buf.lines()
+            .map(TextLine::new)
+            .collect::<Result<Vec<_>>>()
+            .unwrap(),
+        lines,
+    );
+
+    // Delete the backups.
+    for backup_handle in &backup_handles {
+        store.delete_backup(backup_handle).await.unwrap();
+    }
+    assert!(store.list_backups().await.unwrap().is_empty());
+}
+
 pub fn arb_backups(
 ) -> impl Strategy<Value = HashMap<ShellSafeName, HashMap<ShellSafeName, Vec<u8>>>> {
     hash_map(
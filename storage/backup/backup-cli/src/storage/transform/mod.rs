@@ -0,0 +1,414 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(test)]
+mod tests;
+
+use crate::storage::{
+    BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+    TextLine,
+};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes256Gcm,
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use async_trait::async_trait;
+use diem_config::config::{PersistableConfig, SecureBackend};
+use diem_infallible::Mutex;
+use diem_secure_storage::{KVStorage, Storage};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use rand::{rngs::OsRng, RngCore};
+use std::{
+    collections::HashSet,
+    convert::TryInto,
+    io::{self, Cursor, Read, Write},
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use structopt::StructOpt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+pub const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 1 << 30;
+
+#[derive(Clone, StructOpt)]
+pub struct TransformOpt {
+    #[structopt(long, help = "Compress the backup files written.")]
+    pub compress: bool,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with = "encryption-key-storage-config",
+        help = "File holding the hex encoded 32 bytes key the backup files are encrypted with, \
+        using AES-256-GCM."
+    )]
+    pub encryption_key_file: Option<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Secure storage config (a yaml SecureBackend) of the storage holding the hex \
+        encoded 32 bytes key the backup files are encrypted with, using AES-256-GCM."
+    )]
+    pub encryption_key_storage_config: Option<PathBuf>,
+    #[structopt(
+        long,
+        default_value = "backup_encryption_key",
+        help = "Name of the encryption key in the secure storage."
+    )]
+    pub encryption_key_name: String,
+    #[structopt(
+        long,
+        help = "Read the backup files that are not encrypted even though an encryption key is \
+        given, e.g. the files of a backup taken before encryption was turned on."
+    )]
+    pub allow_plaintext: bool,
+    // Defaults to 1GB, well above the default max chunk size.
+    #[structopt(
+        long,
+        default_value = "1073741824",
+        help = "Maximum size in bytes of a decompressed backup file, it must be above the max \
+        chunk size the backup was taken with."
+    )]
+    pub max_decompressed_size: u64,
+}
+
+impl TransformOpt {
+    fn load_key(&self) -> Result<Option<[u8; KEY_SIZE]>> {
+        let hex_key = if let Some(path) = &self.encryption_key_file {
+            std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read key file {:?}: {}", path, e))?
+        } else if let Some(path) = &self.encryption_key_storage_config {
+            let backend = SecureBackend::load_config(path)?;
+            Storage::from(&backend)
+                .get::<String>(&self.encryption_key_name)?
+                .value
+        } else {
+            return Ok(None);
+        };
+        let key = hex::decode(hex_key.trim())?;
+        Ok(Some(key.as_slice().try_into().map_err(|_| {
+            anyhow!(
+                "Encryption key must be {} bytes, got {}.",
+                KEY_SIZE,
+                key.len()
+            )
+        })?))
+    }
+
+    /// Wraps the storage in a `TransformedStorage` if compression or encryption is asked for.
+    pub fn init(&self, storage: Arc<dyn BackupStorage>) -> Result<Arc<dyn BackupStorage>> {
+        let key = self.load_key()?;
+        Ok(if self.compress || key.is_some() {
+            Arc::new(
+                TransformedStorage::new(storage, self.compress, key)
+                    .with_allow_plaintext(self.allow_plaintext)
+                    .with_max_decompressed_size(self.max_decompressed_size),
+            )
+        } else {
+            storage
+        })
+    }
+}
+
+/// Compresses and / or encrypts the backup files on top of another `BackupStorage`.
+///
+/// A transformed file is laid out as `MAGIC | flags | [nonce] | payload`, where the payload is
+/// the deflate compressed content if `FLAG_COMPRESSED` is set, sealed by AES-256-GCM with the
+/// header and the file handle as associated data if `FLAG_ENCRYPTED` is set, so that a file can't
+/// be swapped for another one of the backup. When no key is given, files not starting with `MAGIC`
+/// are read as is, so that existing plain backups stay readable. When a key is given, files that
+/// are not encrypted are rejected unless `allow_plaintext` is set, so that they can't be
+/// substituted for encrypted ones. Metadata lines are left in plain text since they only refer to
+/// versions and file handles, and the metadata files listed are read as is.
+pub struct TransformedStorage {
+    inner: Arc<dyn BackupStorage>,
+    transform: Arc<Transform>,
+    /// Metadata files listed so far, they are read as is.
+    metadata_files: Mutex<HashSet<FileHandle>>,
+}
+
+impl TransformedStorage {
+    pub fn new(inner: Arc<dyn BackupStorage>, compress: bool, key: Option<[u8; KEY_SIZE]>) -> Self {
+        Self {
+            inner,
+            transform: Arc::new(Transform {
+                compress,
+                key,
+                allow_plaintext: false,
+                max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+            }),
+            metadata_files: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn with_allow_plaintext(mut self, allow_plaintext: bool) -> Self {
+        Arc::make_mut(&mut self.transform).allow_plaintext = allow_plaintext;
+        self
+    }
+
+    pub fn with_max_decompressed_size(mut self, max_decompressed_size: u64) -> Self {
+        Arc::make_mut(&mut self.transform).max_decompressed_size = max_decompressed_size;
+        self
+    }
+}
+
+#[async_trait]
+impl BackupStorage for TransformedStorage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        self.inner.create_backup(name).await
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        let (file_handle, file) = self.inner.create_for_write(backup_handle, name).await?;
+        Ok((
+            file_handle,
+            Box::new(TransformWriter {
+                transform: Arc::clone(&self.transform),
+                file_handle: file_handle.clone(),
+                buf: Vec::new(),
+                sealed: None,
+                inner: file,
+            }),
+        ))
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        if self.metadata_files.lock().contains(file_handle) {
+            return self.inner.open_for_read(file_handle).await;
+        }
+        let mut bytes = Vec::new();
+        self.inner
+            .open_for_read(file_handle)
+            .await?
+            .read_to_end(&mut bytes)
+            .await?;
+        let content = self
+            .transform
+            .unseal(file_handle, bytes)
+            .map_err(|e| anyhow!("Failed to read {}: {}", file_handle, e))?;
+        Ok(Box::new(Cursor::new(content)))
+    }
+
+    async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.inner.save_metadata_line(name, content).await
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        let file_handles = self.inner.list_metadata_files().await?;
+        self.metadata_files
+            .lock()
+            .extend(file_handles.iter().cloned());
+        Ok(file_handles)
+    }
+
+    async fn save_metadata_lines(&self, name: &ShellSafeName, lines: &[TextLine]) -> Result<()> {
+        self.inner.save_metadata_lines(name, lines).await
+    }
+
+    async fn delete_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        self.inner.delete_metadata_file(file_handle).await
+    }
+
+    async fn list_backups(&self) -> Result<Vec<BackupHandle>> {
+        self.inner.list_backups().await
+    }
+
+    async fn list_backup_files(&self, backup_handle: &BackupHandleRef) -> Result<Vec<FileHandle>> {
+        self.inner.list_backup_files(backup_handle).await
+    }
+
+    async fn delete_backup(&self, backup_handle: &BackupHandleRef) -> Result<()> {
+        self.inner.delete_backup(backup_handle).await
+    }
+}
+
+#[derive(Clone)]
+struct Transform {
+    compress: bool,
+    key: Option<[u8; KEY_SIZE]>,
+    allow_plaintext: bool,
+    max_decompressed_size: u64,
+}
+
+impl Transform {
+    const MAGIC: &'static [u8] = b"DIEMBAK\x01";
+    const FLAG_COMPRESSED: u8 = 0b01;
+    const FLAG_ENCRYPTED: u8 = 0b10;
+
+    fn aead(key: &[u8; KEY_SIZE]) -> Aes256Gcm {
+        Aes256Gcm::new(GenericArray::from_slice(key))
+    }
+
+    /// The header followed by the file handle.
+    fn associated_data(header: &[u8], file_handle: &FileHandleRef) -> Vec<u8> {
+        [header, file_handle.as_bytes()].concat()
+    }
+
+    fn seal(&self, file_handle: &FileHandleRef, content: Vec<u8>) -> Result<Vec<u8>> {
+        let mut flags = 0;
+        let mut payload = content;
+        if self.compress {
+            flags |= Self::FLAG_COMPRESSED;
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&payload)?;
+            payload = encoder.finish()?;
+        }
+        if self.key.is_some() {
+            flags |= Self::FLAG_ENCRYPTED;
+        }
+
+        let mut sealed = Self::MAGIC.to_vec();
+        sealed.push(flags);
+        if let Some(key) = &self.key {
+            let mut nonce = [0u8; NONCE_SIZE];
+            OsRng.fill_bytes(&mut nonce);
+            let ciphertext = Self::aead(key)
+                .encrypt(
+                    GenericArray::from_slice(&nonce),
+                    Payload {
+                        msg: &payload,
+                        aad: &Self::associated_data(&sealed, file_handle),
+                    },
+                )
+                .map_err(|_| anyhow!("Failed to encrypt."))?;
+            sealed.extend_from_slice(&nonce);
+            sealed.extend(ciphertext);
+        } else {
+            sealed.extend(payload);
+        }
+        Ok(sealed)
+    }
+
+    fn unseal(&self, file_handle: &FileHandleRef, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let header_len = Self::MAGIC.len() + 1;
+        if bytes.len() < header_len || !bytes.starts_with(Self::MAGIC) {
+            // Plain file.
+            self.ensure_plaintext_allowed()?;
+            return Ok(bytes);
+        }
+        let (header, body) = bytes.split_at(header_len);
+        let flags = header[header_len - 1];
+        ensure!(
+            flags & !(Self::FLAG_COMPRESSED | Self::FLAG_ENCRYPTED) == 0,
+            "Unknown flags {:#04x}.",
+            flags,
+        );
+
+        if flags & Self::FLAG_ENCRYPTED == 0 {
+            self.ensure_plaintext_allowed()?;
+        }
+
+        let payload = if flags & Self::FLAG_ENCRYPTED != 0 {
+            let key = match &self.key {
+                Some(key) => key,
+                None => bail!("File is encrypted but no encryption key is provided."),
+            };
+            ensure!(body.len() >= NONCE_SIZE, "Truncated file.");
+            let (nonce, ciphertext) = body.split_at(NONCE_SIZE);
+            Self::aead(key)
+                .decrypt(
+                    GenericArray::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: &Self::associated_data(header, file_handle),
+                    },
+                )
+                .map_err(|_| anyhow!("Failed to decrypt, wrong key or corrupted file."))?
+        } else {
+            body.to_vec()
+        };
+
+        if flags & Self::FLAG_COMPRESSED != 0 {
+            let mut content = Vec::new();
+            DeflateDecoder::new(payload.as_slice())
+                .take(self.max_decompressed_size + 1)
+                .read_to_end(&mut content)?;
+            ensure!(
+                content.len() as u64 <= self.max_decompressed_size,
+                "Decompressed file is over the limit of {} bytes.",
+                self.max_decompressed_size,
+            );
+            Ok(content)
+        } else {
+            Ok(payload)
+        }
+    }
+
+    fn ensure_plaintext_allowed(&self) -> Result<()> {
+        ensure!(
+            self.key.is_none() || self.allow_plaintext,
+            "File is not encrypted while an encryption key is given, pass --allow-plaintext to \
+            read it anyway."
+        );
+        Ok(())
+    }
+}
+
+/// Buffers the whole file, which is transformed and written to the inner file on shutdown. Backup
+/// files are bounded by the `max_chunk_size`, so this is affordable.
+struct TransformWriter {
+    transform: Arc<Transform>,
+    file_handle: FileHandle,
+    buf: Vec<u8>,
+    /// The transformed file, and how much of it is written.
+    sealed: Option<(Vec<u8>, usize)>,
+    inner: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl AsyncWrite for TransformWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.sealed.is_some() {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Write after shutdown.",
+            )));
+        }
+        this.buf.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Nothing is written to the inner file before shutdown.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.sealed.is_none() {
+            let content = std::mem::take(&mut this.buf);
+            let sealed = this
+                .transform
+                .seal(&this.file_handle, content)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            this.sealed = Some((sealed, 0));
+        }
+        let (sealed, written) = this.sealed.as_mut().unwrap();
+        while *written < sealed.len() {
+            match Pin::new(&mut this.inner).poll_write(cx, &sealed[*written..]) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                }
+                Poll::Ready(Ok(n)) => *written += n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::storage::{
+    local_fs::LocalFs,
+    test_util::{arb_backups, test_write_and_read_impl},
+};
+use diem_temppath::TempPath;
+use proptest::prelude::*;
+use std::str::FromStr;
+use tokio::{io::AsyncWriteExt, runtime::Runtime};
+
+async fn write_file(store: &dyn BackupStorage, content: &[u8]) -> FileHandle {
+    let backup_handle = store
+        .create_backup(&ShellSafeName::from_str("backup").unwrap())
+        .await
+        .unwrap();
+    let (file_handle, mut file) = store
+        .create_for_write(&backup_handle, &ShellSafeName::from_str("file").unwrap())
+        .await
+        .unwrap();
+    file.write_all(content).await.unwrap();
+    file.shutdown().await.unwrap();
+    file_handle
+}
+
+async fn read_file(store: &dyn BackupStorage, file_handle: &FileHandleRef) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    store
+        .open_for_read(file_handle)
+        .await?
+        .read_to_end(&mut content)
+        .await?;
+    Ok(content)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_write_and_read(
+        backups in arb_backups(),
+        compress in any::<bool>(),
+        key in any::<Option<[u8; KEY_SIZE]>>(),
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let inner = Arc::new(LocalFs::new(tmpdir.path().to_path_buf()));
+        let store = TransformedStorage::new(inner, compress, key);
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_write_and_read_impl(Box::new(store), backups));
+    }
+}
+
+#[test]
+fn test_encrypted_and_plain_files() {
+    let rt = Runtime::new().unwrap();
+    let content = vec![7u8; 4096];
+
+    let tmpdir = TempPath::new();
+    tmpdir.create_as_dir().unwrap();
+    let inner: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(tmpdir.path().to_path_buf()));
+    let store = TransformedStorage::new(Arc::clone(&inner), true, Some([1; KEY_SIZE]));
+    let file_handle = rt.block_on(write_file(&store, &content));
+
+    // Stored compressed and encrypted.
+    let stored = rt
+        .block_on(read_file(inner.as_ref(), &file_handle))
+        .unwrap();
+    assert!(stored.starts_with(Transform::MAGIC));
+    assert!(stored.len() < content.len());
+    assert_eq!(
+        rt.block_on(read_file(&store, &file_handle)).unwrap(),
+        content
+    );
+
+    // Wrong or missing key.
+    let wrong_key = TransformedStorage::new(Arc::clone(&inner), true, Some([2; KEY_SIZE]));
+    assert!(rt.block_on(read_file(&wrong_key, &file_handle)).is_err());
+    let no_key = TransformedStorage::new(Arc::clone(&inner), true, None);
+    assert!(rt.block_on(read_file(&no_key, &file_handle)).is_err());
+
+    // Tampered file.
+    let mut tampered = stored.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(store.transform.unseal(&file_handle, tampered).is_err());
+
+    // The file is bound to its handle.
+    assert!(store
+        .transform
+        .unseal(&format!("{}.moved", file_handle), stored)
+        .is_err());
+
+    // Plain files are only readable with a key when explicitly allowed.
+    let tmpdir = TempPath::new();
+    tmpdir.create_as_dir().unwrap();
+    let inner: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(tmpdir.path().to_path_buf()));
+    let file_handle = rt.block_on(write_file(inner.as_ref(), &content));
+    let store = TransformedStorage::new(Arc::clone(&inner), true, Some([1; KEY_SIZE]));
+    assert!(rt.block_on(read_file(&store, &file_handle)).is_err());
+    let no_key = TransformedStorage::new(Arc::clone(&inner), true, None);
+    assert_eq!(
+        rt.block_on(read_file(&no_key, &file_handle)).unwrap(),
+        content
+    );
+    let store = store.with_allow_plaintext(true);
+    assert_eq!(
+        rt.block_on(read_file(&store, &file_handle)).unwrap(),
+        content
+    );
+
+    // Same for files compressed but not encrypted.
+    let compressed = no_key
+        .transform
+        .seal(&file_handle, content.clone())
+        .unwrap();
+    let store = TransformedStorage::new(inner, true, Some([1; KEY_SIZE]));
+    assert!(store
+        .transform
+        .unseal(&file_handle, compressed.clone())
+        .is_err());
+    let store = store.with_allow_plaintext(true);
+    assert_eq!(
+        store.transform.unseal(&file_handle, compressed).unwrap(),
+        content
+    );
+}
+
+#[test]
+fn test_max_decompressed_size() {
+    let content = vec![7u8; 4096];
+    let store =
+        TransformedStorage::new(Arc::new(LocalFs::new(PathBuf::from("/unused"))), true, None);
+    let sealed = store.transform.seal("file", content.clone()).unwrap();
+
+    let store = store.with_max_decompressed_size(content.len() as u64);
+    assert_eq!(
+        store.transform.unseal("file", sealed.clone()).unwrap(),
+        content
+    );
+    let store = store.with_max_decompressed_size(content.len() as u64 - 1);
+    assert!(store.transform.unseal("file", sealed).is_err());
+}
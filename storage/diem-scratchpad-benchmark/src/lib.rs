@@ -18,6 +18,7 @@ pub fn run_benchmark(num_updates: usize, max_accounts: u64, blob_size: usize, db
         &db_dir,
         false, /* readonly */
         None,  /* pruner */
+        None,  /* ledger_pruner */
         RocksdbConfig::default(),
     )
     .expect("DB should open.");
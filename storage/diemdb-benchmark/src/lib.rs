@@ -15,7 +15,7 @@ use diem_types::{
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     transaction::{ChangeSet, Transaction, TransactionToCommit, WriteSetPayload},
     vm_status::KeptVMStatus,
-    write_set::WriteSetMut,
+    write_set::{WriteSet, WriteSetMut},
 };
 use diemdb::{
     metrics::DIEM_STORAGE_ROCKSDB_PROPERTIES, schema::JELLYFISH_MERKLE_NODE_CF_NAME, DiemDB,
@@ -67,6 +67,7 @@ fn gen_txn_to_commit<R: Rng>(
         txn,
         states,
         None,
+        WriteSet::default(),
         vec![], /* events */
         0,      /* gas_used */
         KeptVMStatus::Executed,
@@ -90,6 +91,7 @@ pub fn run_benchmark(
         &db_dir,
         false,        /* readonly */
         prune_window, /* pruner */
+        None,         /* ledger_pruner */
         RocksdbConfig::default(),
     )
     .expect("DB should open.");
@@ -0,0 +1,619 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This file defines `DbChecker`, which verifies the data in a DiemDB is consistent end to end,
+//! and repairs the inconsistencies in the indexes, which can be derived from the rest of the data.
+
+use crate::{
+    event_store::EventStore,
+    ledger_store::LedgerStore,
+    pruner::get_least_readable_ledger_version,
+    schema::{
+        event::EventSchema, event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
+        ledger_info::LedgerInfoSchema, transaction_by_account::TransactionByAccountSchema,
+    },
+    state_store::StateStore,
+    transaction_store::TransactionStore,
+};
+use anyhow::Result;
+use diem_crypto::{
+    hash::{
+        CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher,
+        ACCUMULATOR_PLACEHOLDER_HASH,
+    },
+    HashValue,
+};
+use diem_logger::prelude::*;
+use diem_types::{
+    account_address::AccountAddress,
+    contract_event::ContractEvent,
+    event::EventKey,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{Transaction, TransactionInfo, Version},
+};
+use schemadb::{ReadOptions, SchemaBatch, DB};
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+/// An inconsistency found in the DB, at `version`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Inconsistency {
+    pub version: Version,
+    pub kind: InconsistencyKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InconsistencyKind {
+    /// The transaction accumulator root recomputed from the `TransactionInfo`s doesn't match the
+    /// one in the `LedgerInfo` at the version.
+    LedgerInfoAccumulatorRoot {
+        expected: HashValue,
+        recomputed: HashValue,
+    },
+    /// The root of the transaction accumulator stored doesn't match the recomputed one.
+    StoredAccumulatorRoot {
+        stored: HashValue,
+        recomputed: HashValue,
+    },
+    /// The state root in the `TransactionInfo` doesn't match the root of the Jellyfish Merkle
+    /// tree at the version, if any.
+    StateRoot {
+        expected: HashValue,
+        jmt: Option<HashValue>,
+    },
+    /// The event root in the `TransactionInfo` doesn't match the one recomputed from the events.
+    EventRoot {
+        expected: HashValue,
+        recomputed: HashValue,
+    },
+    /// The root of the event accumulator stored doesn't match the recomputed one.
+    StoredEventRoot {
+        stored: HashValue,
+        recomputed: HashValue,
+    },
+    /// The `transaction_by_account` entry of the user transaction is missing or wrong.
+    TransactionByAccount {
+        address: AccountAddress,
+        seq_num: u64,
+        indexed: Option<Version>,
+    },
+    /// The `transaction_by_account` entry points to a version with no matching user transaction.
+    DanglingTransactionByAccount {
+        address: AccountAddress,
+        seq_num: u64,
+    },
+    /// The `event_by_key` entry of the event is missing or wrong.
+    EventByKey {
+        key: EventKey,
+        seq_num: u64,
+        index: u64,
+        indexed: Option<(Version, u64)>,
+    },
+    /// The `event_by_version` entry of the event is missing or wrong.
+    EventByVersion {
+        key: EventKey,
+        seq_num: u64,
+        index: u64,
+        indexed: Option<u64>,
+    },
+    /// The `event_by_key` entry points to a position with no matching event.
+    DanglingEventByKey {
+        key: EventKey,
+        seq_num: u64,
+        index: u64,
+    },
+    /// The transaction, `TransactionInfo` or events at the version can't be read, e.g., because
+    /// of a gap. The later versions aren't checked.
+    MissingData { data: &'static str, error: String },
+}
+
+impl Inconsistency {
+    fn new(version: Version, kind: InconsistencyKind) -> Self {
+        Self { version, kind }
+    }
+
+    /// Only the indexes can be derived from the rest of the data, and be repaired.
+    pub fn is_repairable(&self) -> bool {
+        use InconsistencyKind::*;
+        matches!(
+            self.kind,
+            TransactionByAccount { .. }
+                | DanglingTransactionByAccount { .. }
+                | EventByKey { .. }
+                | EventByVersion { .. }
+                | DanglingEventByKey { .. }
+        )
+    }
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InconsistencyKind::*;
+        write!(f, "Version {}: ", self.version)?;
+        match &self.kind {
+            LedgerInfoAccumulatorRoot {
+                expected,
+                recomputed,
+            } => write!(
+                f,
+                "transaction accumulator root in LedgerInfo {:x}, recomputed {:x}.",
+                expected, recomputed,
+            ),
+            StoredAccumulatorRoot { stored, recomputed } => write!(
+                f,
+                "transaction accumulator root stored {:x}, recomputed {:x}.",
+                stored, recomputed,
+            ),
+            StateRoot { expected, jmt } => write!(
+                f,
+                "state root in TransactionInfo {:x}, in the JMT {}.",
+                expected,
+                jmt.map_or_else(|| "not found".to_string(), |h| format!("{:x}", h)),
+            ),
+            EventRoot {
+                expected,
+                recomputed,
+            } => write!(
+                f,
+                "event root in TransactionInfo {:x}, recomputed {:x}.",
+                expected, recomputed,
+            ),
+            StoredEventRoot { stored, recomputed } => write!(
+                f,
+                "event accumulator root stored {:x}, recomputed {:x}.",
+                stored, recomputed,
+            ),
+            TransactionByAccount {
+                address,
+                seq_num,
+                indexed,
+            } => write!(
+                f,
+                "transaction_by_account entry ({}, {}) points to {:?}.",
+                address, seq_num, indexed,
+            ),
+            DanglingTransactionByAccount { address, seq_num } => write!(
+                f,
+                "transaction_by_account entry ({}, {}) has no matching transaction.",
+                address, seq_num,
+            ),
+            EventByKey {
+                key,
+                seq_num,
+                index,
+                indexed,
+            } => write!(
+                f,
+                "event_by_key entry ({}, {}) of event {} points to {:?}.",
+                key, seq_num, index, indexed,
+            ),
+            EventByVersion {
+                key,
+                seq_num,
+                index,
+                indexed,
+            } => write!(
+                f,
+                "event_by_version entry ({}, {}) of event {} points to {:?}.",
+                key, seq_num, index, indexed,
+            ),
+            DanglingEventByKey {
+                key,
+                seq_num,
+                index,
+            } => write!(
+                f,
+                "event_by_key entry ({}, {}) points to event {} which doesn't match.",
+                key, seq_num, index,
+            ),
+            MissingData { data, error } => write!(f, "{} can't be read: {}.", data, error),
+        }
+    }
+}
+
+/// `DbChecker` verifies the data in a DiemDB, usually opened as a secondary of a running node,
+/// and reports the inconsistencies found.
+pub struct DbChecker {
+    db: Arc<DB>,
+    ledger_store: Arc<LedgerStore>,
+    transaction_store: Arc<TransactionStore>,
+    state_store: Arc<StateStore>,
+    event_store: Arc<EventStore>,
+}
+
+impl DbChecker {
+    pub(crate) fn new(
+        db: Arc<DB>,
+        ledger_store: Arc<LedgerStore>,
+        transaction_store: Arc<TransactionStore>,
+        state_store: Arc<StateStore>,
+        event_store: Arc<EventStore>,
+    ) -> Self {
+        Self {
+            db,
+            ledger_store,
+            transaction_store,
+            state_store,
+            event_store,
+        }
+    }
+
+    /// Checks the transactions in `[start_version, end_version]`, `end_version` defaulting to, and
+    /// capped at, the latest transaction in the DB, and `start_version` raised to the least
+    /// readable one:
+    ///   1. transaction accumulator roots recomputed from the `TransactionInfo`s against the
+    ///      `LedgerInfo`s and the accumulator stored,
+    ///   2. state roots in the `TransactionInfo`s against the Jellyfish Merkle tree,
+    ///   3. event roots in the `TransactionInfo`s against the events and the event accumulators
+    ///      stored,
+    ///   4. the `transaction_by_account`, `event_by_key` and `event_by_version` indexes against
+    ///      the transactions and events, both ways.
+    ///
+    /// The indexes are keyed by account and event key rather than version, so finding their
+    /// dangling entries scans them whole, whatever the range: on a large DB, this takes about as
+    /// long as checking all the transactions.
+    ///
+    /// The ledger history before the least readable version was pruned, or never written when the
+    /// DB was restored from a state snapshot, so it isn't reported missing, and the index entries
+    /// pointing to it aren't reported dangling.
+    pub fn check(
+        &self,
+        start_version: Version,
+        end_version: Option<Version>,
+    ) -> Result<Vec<Inconsistency>> {
+        let latest_version = match self.ledger_store.get_latest_transaction_info_option()? {
+            Some((v, _)) => v,
+            None => return Ok(Vec::new()),
+        };
+        let start_version = start_version.max(get_least_readable_ledger_version(&self.db)?);
+        let end_version = end_version.map_or(latest_version, |v| v.min(latest_version));
+        if start_version > end_version {
+            return Ok(Vec::new());
+        }
+        let num_versions = (end_version - start_version + 1) as usize;
+        info!(
+            start_version = start_version,
+            end_version = end_version,
+            "Checking DB."
+        );
+
+        let ledger_info_roots = self.get_ledger_info_roots(start_version, end_version)?;
+        let mut accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            self.ledger_store.get_frozen_subtree_hashes(start_version)?,
+            start_version,
+        )?;
+        // Once the state of a version is found, that of the later versions can't be pruned.
+        let mut state_found = false;
+
+        let mut res = Vec::new();
+        let mut txn_iter = self
+            .transaction_store
+            .get_transaction_iter(start_version, num_versions)?;
+        let mut txn_info_iter = self
+            .ledger_store
+            .get_transaction_info_iter(start_version, num_versions)?;
+        let mut events_iter = self
+            .event_store
+            .get_events_by_version_iter(start_version, num_versions)?;
+        let mut complete = true;
+        for version in start_version..=end_version {
+            let (txn, txn_info, events) = match (
+                next_at(version, "Transaction", &mut txn_iter),
+                next_at(version, "TransactionInfo", &mut txn_info_iter),
+                next_at(version, "Events", &mut events_iter),
+            ) {
+                (Ok(txn), Ok(txn_info), Ok(events)) => (txn, txn_info, events),
+                (txn, txn_info, events) => {
+                    res.extend(txn.err());
+                    res.extend(txn_info.err());
+                    res.extend(events.err());
+                    // The accumulator can't be recomputed past the gap.
+                    complete = false;
+                    break;
+                }
+            };
+
+            accumulator = accumulator.append(&[txn_info.hash()]);
+            if let Some(expected) = ledger_info_roots.get(&version) {
+                let recomputed = accumulator.root_hash();
+                if *expected != recomputed {
+                    res.push(Inconsistency::new(
+                        version,
+                        InconsistencyKind::LedgerInfoAccumulatorRoot {
+                            expected: *expected,
+                            recomputed,
+                        },
+                    ));
+                }
+            }
+
+            let jmt = self.state_store.get_root_hash_option(version)?;
+            if jmt.is_some() || state_found {
+                state_found = true;
+                if jmt != Some(txn_info.state_root_hash()) {
+                    res.push(Inconsistency::new(
+                        version,
+                        InconsistencyKind::StateRoot {
+                            expected: txn_info.state_root_hash(),
+                            jmt,
+                        },
+                    ));
+                }
+            }
+
+            self.check_events(version, &txn_info, &events, &mut res)?;
+            self.check_transaction_index(version, &txn, &mut res)?;
+        }
+
+        if complete {
+            let stored = self.ledger_store.get_root_hash(end_version)?;
+            let recomputed = accumulator.root_hash();
+            if stored != recomputed {
+                res.push(Inconsistency::new(
+                    end_version,
+                    InconsistencyKind::StoredAccumulatorRoot { stored, recomputed },
+                ));
+            }
+        }
+
+        self.check_dangling_transaction_index(start_version, end_version, &mut res)?;
+        self.check_dangling_event_index(start_version, end_version, &mut res)?;
+
+        res.sort_by_key(|i| i.version);
+        info!(
+            num_inconsistencies = res.len(),
+            "Checked DB from version {} to {}.", start_version, end_version,
+        );
+        Ok(res)
+    }
+
+    /// Repairs the inconsistencies in the indexes, by rewriting the entries from the transactions
+    /// and events, and deleting the dangling ones. Returns the number of inconsistencies
+    /// repaired.
+    ///
+    /// Must be called on a DB opened for writing, with nothing else writing to it.
+    pub fn repair(&self, inconsistencies: &[Inconsistency]) -> Result<usize> {
+        use InconsistencyKind::*;
+
+        // Dangling entries are deleted first, not to delete the entries rewritten at the same keys.
+        let (dangling, others): (Vec<_>, Vec<_>) = inconsistencies.iter().partition(|i| {
+            matches!(
+                i.kind,
+                DanglingTransactionByAccount { .. } | DanglingEventByKey { .. }
+            )
+        });
+
+        let mut batch = SchemaBatch::new();
+        let mut num_repaired = 0;
+        for inconsistency in dangling.into_iter().chain(others) {
+            let version = inconsistency.version;
+            match &inconsistency.kind {
+                TransactionByAccount {
+                    address, seq_num, ..
+                } => batch.put::<TransactionByAccountSchema>(&(*address, *seq_num), &version)?,
+                DanglingTransactionByAccount { address, seq_num } => {
+                    batch.delete::<TransactionByAccountSchema>(&(*address, *seq_num))?
+                }
+                EventByKey {
+                    key,
+                    seq_num,
+                    index,
+                    ..
+                } => batch.put::<EventByKeySchema>(&(*key, *seq_num), &(version, *index))?,
+                EventByVersion {
+                    key,
+                    seq_num,
+                    index,
+                    ..
+                } => batch.put::<EventByVersionSchema>(&(*key, version, *seq_num), index)?,
+                DanglingEventByKey { key, seq_num, .. } => {
+                    batch.delete::<EventByKeySchema>(&(*key, *seq_num))?
+                }
+                _ => continue,
+            }
+            info!("Repairing: {}", inconsistency);
+            num_repaired += 1;
+        }
+        self.db.write_schemas(batch)?;
+
+        Ok(num_repaired)
+    }
+}
+
+impl DbChecker {
+    /// Transaction accumulator roots in the `LedgerInfo`s within the range, by version.
+    fn get_ledger_info_roots(
+        &self,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<BTreeMap<Version, HashValue>> {
+        let mut iter = self.db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        let mut res = BTreeMap::new();
+        for item in iter {
+            let (_epoch, ledger_info_with_sigs) = item?;
+            let ledger_info = ledger_info_with_sigs.ledger_info();
+            if (start_version..=end_version).contains(&ledger_info.version()) {
+                res.insert(
+                    ledger_info.version(),
+                    ledger_info.transaction_accumulator_hash(),
+                );
+            }
+        }
+        Ok(res)
+    }
+
+    fn check_events(
+        &self,
+        version: Version,
+        txn_info: &TransactionInfo,
+        events: &[ContractEvent],
+        res: &mut Vec<Inconsistency>,
+    ) -> Result<()> {
+        let event_hashes: Vec<_> = events.iter().map(CryptoHash::hash).collect();
+        let recomputed =
+            InMemoryAccumulator::<EventAccumulatorHasher>::default().append(&event_hashes);
+        let recomputed = recomputed.root_hash();
+        if recomputed != txn_info.event_root_hash() {
+            res.push(Inconsistency::new(
+                version,
+                InconsistencyKind::EventRoot {
+                    expected: txn_info.event_root_hash(),
+                    recomputed,
+                },
+            ));
+        }
+        let stored = if events.is_empty() {
+            *ACCUMULATOR_PLACEHOLDER_HASH
+        } else {
+            self.event_store
+                .get_event_root_hash(version, events.len() as u64)?
+        };
+        if stored != recomputed {
+            res.push(Inconsistency::new(
+                version,
+                InconsistencyKind::StoredEventRoot { stored, recomputed },
+            ));
+        }
+
+        for (index, event) in events.iter().enumerate() {
+            let (key, seq_num, index) = (*event.key(), event.sequence_number(), index as u64);
+            let indexed = self.db.get::<EventByKeySchema>(&(key, seq_num))?;
+            if indexed != Some((version, index)) {
+                res.push(Inconsistency::new(
+                    version,
+                    InconsistencyKind::EventByKey {
+                        key,
+                        seq_num,
+                        index,
+                        indexed,
+                    },
+                ));
+            }
+            let indexed = self
+                .db
+                .get::<EventByVersionSchema>(&(key, version, seq_num))?;
+            if indexed != Some(index) {
+                res.push(Inconsistency::new(
+                    version,
+                    InconsistencyKind::EventByVersion {
+                        key,
+                        seq_num,
+                        index,
+                        indexed,
+                    },
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_transaction_index(
+        &self,
+        version: Version,
+        txn: &Transaction,
+        res: &mut Vec<Inconsistency>,
+    ) -> Result<()> {
+        if let Transaction::UserTransaction(signed_txn) = txn {
+            let (address, seq_num) = (signed_txn.sender(), signed_txn.sequence_number());
+            let indexed = self
+                .db
+                .get::<TransactionByAccountSchema>(&(address, seq_num))?;
+            if indexed != Some(version) {
+                res.push(Inconsistency::new(
+                    version,
+                    InconsistencyKind::TransactionByAccount {
+                        address,
+                        seq_num,
+                        indexed,
+                    },
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the `transaction_by_account` entries pointing into the range, but not to a matching
+    /// user transaction.
+    fn check_dangling_transaction_index(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        res: &mut Vec<Inconsistency>,
+    ) -> Result<()> {
+        let mut iter = self
+            .db
+            .iter::<TransactionByAccountSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        for item in iter {
+            let ((address, seq_num), version) = item?;
+            if version < start_version || version > end_version {
+                continue;
+            }
+            let matches = match self.transaction_store.get_transaction(version) {
+                Ok(Transaction::UserTransaction(signed_txn)) => {
+                    signed_txn.sender() == address && signed_txn.sequence_number() == seq_num
+                }
+                _ => false,
+            };
+            if !matches {
+                res.push(Inconsistency::new(
+                    version,
+                    InconsistencyKind::DanglingTransactionByAccount { address, seq_num },
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the `event_by_key` entries pointing into the range, but not to a matching event.
+    fn check_dangling_event_index(
+        &self,
+        start_version: Version,
+        end_version: Version,
+        res: &mut Vec<Inconsistency>,
+    ) -> Result<()> {
+        let mut iter = self.db.iter::<EventByKeySchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        for item in iter {
+            let ((key, seq_num), (version, index)) = item?;
+            if version < start_version || version > end_version {
+                continue;
+            }
+            let matches = match self.db.get::<EventSchema>(&(version, index))? {
+                Some(event) => *event.key() == key && event.sequence_number() == seq_num,
+                None => false,
+            };
+            if !matches {
+                res.push(Inconsistency::new(
+                    version,
+                    InconsistencyKind::DanglingEventByKey {
+                        key,
+                        seq_num,
+                        index,
+                    },
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The next item of the iterator, expected to be at `version`.
+fn next_at<T>(
+    version: Version,
+    data: &'static str,
+    iter: &mut impl Iterator<Item = Result<T>>,
+) -> std::result::Result<T, Inconsistency> {
+    let error = match iter.next() {
+        Some(Ok(item)) => return Ok(item),
+        Some(Err(err)) => err.to_string(),
+        None => "not found".to_string(),
+    };
+    Err(Inconsistency::new(
+        version,
+        InconsistencyKind::MissingData { data, error },
+    ))
+}
+
+#[cfg(test)]
+mod test;
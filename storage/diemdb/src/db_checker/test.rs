@@ -0,0 +1,137 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db_checker::InconsistencyKind,
+    schema::{
+        event_by_key::EventByKeySchema, transaction::TransactionSchema,
+        transaction_by_account::TransactionByAccountSchema,
+    },
+    test_helper::arb_blocks_to_commit,
+    DiemDB,
+};
+use diem_config::config::RocksdbConfig;
+use diem_temppath::TempPath;
+use diem_types::transaction::Transaction;
+use proptest::prelude::*;
+use schemadb::SchemaBatch;
+use storage_interface::DbWriter;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_check_and_repair(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+
+        let checker = db.get_db_checker();
+        prop_assert!(checker.check(0, None).unwrap().is_empty());
+
+        // Break the indexes of the first user transaction and the first event.
+        let txns_to_commit: Vec<_> = input.iter().flat_map(|(txns, _)| txns).collect();
+        let mut batch = SchemaBatch::new();
+        let mut num_broken = 0;
+        if let Some(Transaction::UserTransaction(signed_txn)) = txns_to_commit
+            .iter()
+            .map(|t| t.transaction())
+            .find(|t| matches!(t, Transaction::UserTransaction(_)))
+        {
+            batch
+                .delete::<TransactionByAccountSchema>(&(
+                    signed_txn.sender(),
+                    signed_txn.sequence_number(),
+                ))
+                .unwrap();
+            num_broken += 1;
+        }
+        if let Some(event) = txns_to_commit.iter().flat_map(|t| t.events()).next() {
+            batch
+                .delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))
+                .unwrap();
+            num_broken += 1;
+        }
+        db.db.write_schemas(batch).unwrap();
+
+        let inconsistencies = checker.check(0, None).unwrap();
+        prop_assert_eq!(inconsistencies.len(), num_broken);
+        for inconsistency in &inconsistencies {
+            prop_assert!(inconsistency.is_repairable());
+            prop_assert!(matches!(
+                inconsistency.kind,
+                InconsistencyKind::TransactionByAccount { indexed: None, .. }
+                    | InconsistencyKind::EventByKey { indexed: None, .. }
+            ));
+        }
+
+        prop_assert_eq!(checker.repair(&inconsistencies).unwrap(), num_broken);
+        prop_assert!(checker.check(0, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_missing_transaction(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+
+        // The end version is capped at the latest one.
+        let checker = db.get_db_checker();
+        prop_assert!(checker.check(0, Some(u64::max_value())).unwrap().is_empty());
+
+        // A missing first version can't be told apart from a pruned one.
+        prop_assume!(cur_ver > 1);
+        let missing_version = cur_ver / 2;
+        let mut batch = SchemaBatch::new();
+        batch.delete::<TransactionSchema>(&missing_version).unwrap();
+        db.db.write_schemas(batch).unwrap();
+
+        let inconsistencies = checker.check(0, None).unwrap();
+        let missing: Vec<_> = inconsistencies
+            .iter()
+            .filter(|i| matches!(i.kind, InconsistencyKind::MissingData { .. }))
+            .collect();
+        prop_assert_eq!(missing.len(), 1);
+        prop_assert_eq!(missing[0].version, missing_version);
+        prop_assert!(!missing[0].is_repairable());
+    }
+
+    #[test]
+    fn test_check_pruned(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::open(
+            &tmp_dir,
+            false,   /* readonly */
+            None,    /* pruner */
+            Some(1), /* ledger_pruner */
+            RocksdbConfig::default(),
+        )
+        .unwrap();
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        db.pruner.as_ref().unwrap().wake_and_wait(cur_ver - 1).unwrap();
+
+        // Neither the pruned versions nor the index entries pointing to them are reported.
+        let checker = db.get_db_checker();
+        let inconsistencies = checker.check(0, None).unwrap();
+        prop_assert!(inconsistencies.is_empty());
+        prop_assert_eq!(checker.repair(&inconsistencies).unwrap(), 0);
+    }
+}
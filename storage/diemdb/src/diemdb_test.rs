@@ -519,6 +519,20 @@ fn verify_committed_transactions(
             .unwrap();
         assert_eq!(txn_list_with_proof.len(), 1);
 
+        let txn_output_list_with_proof = db
+            .get_transaction_outputs(cur_ver, 1, ledger_version)
+            .unwrap();
+        txn_output_list_with_proof
+            .verify(ledger_info, Some(cur_ver))
+            .unwrap();
+        assert_eq!(txn_output_list_with_proof.len(), 1);
+        assert_eq!(
+            txn_output_list_with_proof.transactions_and_outputs[0]
+                .1
+                .write_set(),
+            txn_to_commit.write_set()
+        );
+
         // Fetch and verify account states.
         for (addr, expected_blob) in txn_to_commit.account_states() {
             let account_state_with_proof = db
@@ -545,6 +559,169 @@ fn verify_committed_transactions(
     verify_account_txns(db, group_txns_by_account(txns_to_commit), ledger_info);
 }
 
+fn test_state_snapshot_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let target_li = db.get_latest_ledger_info().unwrap();
+    let version = target_li.ledger_info().version();
+    let (_, txn_info) = db.get_latest_transaction_info_option().unwrap().unwrap();
+
+    // Restore the state tree in small chunks on an empty DB.
+    let tmp_dir2 = TempPath::new();
+    let db2 = DiemDB::new_for_test(&tmp_dir2);
+    let mut receiver = db2
+        .get_state_snapshot_receiver(version, txn_info.state_root_hash())
+        .unwrap();
+    let mut last_key = None;
+    loop {
+        let chunk = db
+            .get_account_chunk_with_proof(version, last_key, 2 /* chunk_size */)
+            .unwrap();
+        let is_last_chunk = chunk.is_last_chunk();
+        last_key = chunk.last_key();
+        receiver
+            .add_chunk(chunk.account_blobs, chunk.proof)
+            .unwrap();
+        if is_last_chunk {
+            break;
+        }
+    }
+    receiver.finish_box().unwrap();
+
+    let frozen_subtrees = if version == 0 {
+        vec![]
+    } else {
+        db.get_accumulator_consistency_proof(None, version - 1)
+            .unwrap()
+            .into_subtrees()
+    };
+    let (mut ledger_infos, _more) = db
+        .get_epoch_ending_ledger_infos(0, target_li.ledger_info().next_block_epoch())
+        .unwrap();
+    if ledger_infos.last() != Some(&target_li) {
+        ledger_infos.push(target_li.clone());
+    }
+    db2.finalize_state_snapshot(
+        version,
+        db.get_transaction_outputs(version, 1, version).unwrap(),
+        frozen_subtrees,
+        &ledger_infos,
+    )
+    .unwrap();
+
+    assert_eq!(
+        db2.get_startup_info().unwrap(),
+        db.get_startup_info().unwrap()
+    );
+    assert_eq!(
+        db2.get_transaction_outputs(version, 1, version).unwrap(),
+        db.get_transaction_outputs(version, 1, version).unwrap(),
+    );
+    for (txns_to_commit, _) in input.iter() {
+        for txn_to_commit in txns_to_commit {
+            for address in txn_to_commit.account_states().keys() {
+                assert_eq!(
+                    db2.get_account_state_with_proof_by_version(*address, version)
+                        .unwrap(),
+                    db.get_account_state_with_proof_by_version(*address, version)
+                        .unwrap(),
+                );
+            }
+        }
+    }
+
+    // The history before the snapshot is reported as pruned, also after reopening the DB.
+    if version > 0 {
+        assert_pruned(db2.get_transactions(version - 1, 1, version, false));
+        assert_pruned(
+            db2.get_account_state_with_proof_by_version(AccountAddress::random(), version - 1),
+        );
+        drop(db2);
+        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        assert_pruned(db2.get_transactions(version - 1, 1, version, false));
+        assert_pruned(
+            db2.get_account_state_with_proof_by_version(AccountAddress::random(), version - 1),
+        );
+    }
+}
+
+fn assert_pruned<T: std::fmt::Debug>(result: Result<T>) {
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<DiemDbError>(),
+        Some(DiemDbError::Pruned(_))
+    ));
+}
+
+fn test_account_iter_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let version = db.get_latest_version().unwrap();
+    let (_, txn_info) = db.get_latest_transaction_info_option().unwrap().unwrap();
+    let state_root_hash = txn_info.state_root_hash();
+    let expected = db
+        .get_backup_handler()
+        .get_account_iter(version)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+    let chunks = db
+        .get_account_iter(version, HashValue::zero())
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert!(chunks.last().unwrap().is_last_chunk());
+    for chunk in &chunks {
+        chunk.verify(state_root_hash).unwrap();
+    }
+    let actual: Vec<_> = chunks
+        .into_iter()
+        .flat_map(|chunk| chunk.account_blobs)
+        .collect();
+    assert_eq!(actual, expected);
+
+    // Start from the account in the middle.
+    let mid = expected.len() / 2;
+    let actual: Vec<_> = db
+        .get_account_iter(version, expected[mid].0)
+        .unwrap()
+        .flat_map(|res| res.unwrap().account_blobs)
+        .collect();
+    assert_eq!(actual, expected[mid..].to_vec());
+
+    // Chunks starting and ending anywhere in the tree can be verified on their own.
+    for chunk_size in 1..=3 {
+        let mut num_accounts = 0;
+        for chunk in db
+            .account_iter(version, expected[mid].0, chunk_size)
+            .unwrap()
+        {
+            let mut chunk = chunk.unwrap();
+            chunk.verify(state_root_hash).unwrap();
+            num_accounts += chunk.account_blobs.len();
+
+            // A chunk missing one of its accounts doesn't verify.
+            if chunk.account_blobs.len() > 2 {
+                chunk.account_blobs.remove(1);
+                assert!(chunk.verify(state_root_hash).is_err());
+            }
+        }
+        assert_eq!(num_accounts, expected.len() - mid);
+    }
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
 
@@ -557,6 +734,16 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_state_snapshot(input in arb_blocks_to_commit()) {
+        test_state_snapshot_impl(input);
+    }
+
+    #[test]
+    fn test_account_iter(input in arb_blocks_to_commit()) {
+        test_account_iter_impl(input);
+    }
 }
 
 #[test]
@@ -598,6 +785,7 @@ fn test_too_many_requested() {
     let db = DiemDB::new_for_test(&tmp_dir);
 
     assert!(db.get_transactions(0, 1001 /* limit */, 0, true).is_err());
+    assert!(db.get_transaction_outputs(0, 1001 /* limit */, 0).is_err());
 }
 
 #[test]
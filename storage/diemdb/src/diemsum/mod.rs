@@ -24,6 +24,7 @@ impl Diemsum {
             db_root_path,
             true, /* read only */
             None, /* no prune_window */
+            None, /* no ledger_prune_window */
             RocksdbConfig::default(),
         )?;
         Ok(Diemsum { db })
@@ -14,4 +14,7 @@ pub enum DiemDbError {
     /// Requested too many items.
     #[error("Too many items requested: at least {0} requested, max is {1}")]
     TooManyRequested(u64, u64),
+    /// A requested item has been pruned.
+    #[error("{0} has been pruned.")]
+    Pruned(String),
 }
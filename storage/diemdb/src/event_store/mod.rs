@@ -119,6 +119,12 @@ impl EventStore {
         Ok((event, proof))
     }
 
+    /// Get the root hash of the event accumulator of the transaction at `version`, from the
+    /// accumulator nodes stored.
+    pub fn get_event_root_hash(&self, version: Version, num_events: u64) -> Result<HashValue> {
+        Accumulator::get_root_hash(&EventHashReader::new(self, version), num_events)
+    }
+
     fn get_txn_ver_by_seq_num(&self, event_key: &EventKey, seq_num: u64) -> Result<u64> {
         let (ver, _) = self
             .db
@@ -154,6 +160,18 @@ impl EventStore {
         ))
     }
 
+    /// Get the earliest sequence number on `event_key` still available, which is not 0 if the
+    /// ledger history has been pruned.
+    fn get_first_sequence_number(&self, event_key: &EventKey) -> Result<Option<u64>> {
+        let mut iter = self.db.iter::<EventByKeySchema>(ReadOptions::default())?;
+        iter.seek(&(*event_key, 0))?;
+
+        Ok(iter
+            .next()
+            .transpose()?
+            .and_then(|((key, seq), _)| if &key == event_key { Some(seq) } else { None }))
+    }
+
     /// Get the next sequence number for specified event key.
     /// Returns 0 if there's no events already in the event stream.
     pub fn get_next_sequence_number(
@@ -194,6 +212,15 @@ impl EventStore {
             if path != *event_key || ver > ledger_version {
                 break;
             }
+            // Events are pruned in the order of sequence numbers, so a gap at the beginning means
+            // the requested ones are gone.
+            if result.is_empty() && seq > cur_seq {
+                return Err(DiemDbError::Pruned(format!(
+                    "Event {} of seq num {}",
+                    event_key, cur_seq
+                ))
+                .into());
+            }
             ensure!(
                 seq == cur_seq,
                 "DB corrupt: Sequence number not continuous, expected: {}, actual: {}.",
@@ -294,7 +321,10 @@ impl EventStore {
     where
         C: FnMut(&ContractEvent) -> Result<bool>,
     {
-        let mut begin = 0u64;
+        let mut begin = match self.get_first_sequence_number(event_key)? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
         let mut end = match self.get_latest_sequence_number(ledger_version, event_key)? {
             Some(s) => s
                 .checked_add(1)
@@ -352,6 +382,13 @@ impl EventStore {
             "First block started at or after timestamp {}.",
             timestamp,
         );
+        if self.get_first_sequence_number(&event_key)? == Some(seq_at_or_after_ts) {
+            return Err(DiemDbError::Pruned(format!(
+                "Last version before timestamp {}",
+                timestamp
+            ))
+            .into());
+        }
 
         let (version, _idx) =
             self.lookup_event_by_key(&event_key, seq_at_or_after_ts, ledger_version)?;
@@ -24,14 +24,16 @@ use diem_types::{
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        definition::LeafCount, position::Position, AccumulatorConsistencyProof,
-        TransactionAccumulatorProof, TransactionAccumulatorRangeProof, TransactionInfoWithProof,
+        definition::LeafCount,
+        position::{FrozenSubTreeIterator, Position},
+        AccumulatorConsistencyProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+        TransactionInfoWithProof,
     },
     transaction::{TransactionInfo, Version},
 };
 use itertools::Itertools;
 use schemadb::{ReadOptions, SchemaIterator, DB};
-use std::{ops::Deref, sync::Arc};
+use std::{collections::HashMap, ops::Deref, sync::Arc};
 use storage_interface::{StartupInfo, TreeState};
 
 #[derive(Debug)]
@@ -348,6 +350,47 @@ impl LedgerStore {
         Ok(root_hash)
     }
 
+    /// Write the `frozen_subtrees` of the accumulator before `version` and `txn_info` at `version`
+    /// to `cs`, for a DB starting from a state snapshot at `version`. The accumulator nodes are
+    /// computed from `frozen_subtrees` instead of read from the DB, so that everything can be
+    /// committed in one batch.
+    pub fn put_transaction_info_on_frozen_subtrees(
+        &self,
+        version: Version,
+        txn_info: &TransactionInfo,
+        frozen_subtrees: &[HashValue],
+        cs: &mut ChangeSet,
+    ) -> Result<HashValue> {
+        let positions: Vec<_> = FrozenSubTreeIterator::new(version).collect();
+        ensure!(
+            positions.len() == frozen_subtrees.len(),
+            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
+            positions.len(),
+            frozen_subtrees.len(),
+        );
+        let frozen_subtrees = FrozenSubtrees(
+            positions
+                .into_iter()
+                .zip(frozen_subtrees.iter().copied())
+                .collect(),
+        );
+        frozen_subtrees
+            .0
+            .iter()
+            .try_for_each(|(pos, hash)| cs.batch.put::<TransactionAccumulatorSchema>(pos, hash))?;
+
+        cs.batch.put::<TransactionInfoSchema>(&version, txn_info)?;
+        let (root_hash, writes) = MerkleAccumulator::<_, TransactionAccumulatorHasher>::append(
+            &frozen_subtrees,
+            version, /* num_existing_leaves */
+            &[txn_info.hash()],
+        )?;
+        writes
+            .iter()
+            .try_for_each(|(pos, hash)| cs.batch.put::<TransactionAccumulatorSchema>(pos, hash))?;
+        Ok(root_hash)
+    }
+
     /// Write `ledger_info` to `cs`.
     pub fn put_ledger_info(
         &self,
@@ -372,6 +415,18 @@ impl LedgerStore {
 
 pub(crate) type Accumulator = MerkleAccumulator<LedgerStore, TransactionAccumulatorHasher>;
 
+/// The frozen subtrees of an accumulator that is not in the DB yet, by position.
+struct FrozenSubtrees(HashMap<Position, HashValue>);
+
+impl HashReader for FrozenSubtrees {
+    fn get(&self, position: Position) -> Result<HashValue> {
+        self.0
+            .get(&position)
+            .copied()
+            .ok_or_else(|| format_err!("{} does not exist.", position))
+    }
+}
+
 impl HashReader for LedgerStore {
     fn get(&self, position: Position) -> Result<HashValue> {
         self.db
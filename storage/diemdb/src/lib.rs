@@ -17,6 +17,7 @@ pub mod diemsum;
 pub mod test_helper;
 
 pub mod backup;
+pub mod db_checker;
 pub mod errors;
 pub mod metrics;
 pub mod schema;
@@ -40,6 +41,7 @@ pub use diemdb_test::test_save_blocks_impl;
 use crate::{
     backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
     change_set::{ChangeSet, SealedChangeSet},
+    db_checker::DbChecker,
     errors::DiemDbError,
     event_store::EventStore,
     ledger_counters::LedgerCounters,
@@ -59,11 +61,16 @@ use crate::{
 use anyhow::{ensure, format_err, Result};
 use diem_config::config::RocksdbConfig;
 use diem_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
+use diem_jellyfish_merkle::{
+    iterator::JellyfishMerkleIterator,
+    restore::{JellyfishMerkleRestore, StateSnapshotReceiver},
+};
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
+    account_config::AccountResource,
     account_state::AccountState,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesChunkWithProof},
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
     event::EventKey,
@@ -74,8 +81,9 @@ use diem_types::{
     },
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof,
-        TransactionToCommit, TransactionWithProof, Version, PRE_GENESIS_VERSION,
+        AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof, TransactionOutput,
+        TransactionOutputListWithProof, TransactionStatus, TransactionToCommit,
+        TransactionWithProof, Version, PRE_GENESIS_VERSION,
     },
 };
 use itertools::{izip, zip_eq};
@@ -90,7 +98,10 @@ use std::{
     convert::TryFrom,
     iter::Iterator,
     path::Path,
-    sync::{mpsc, Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
@@ -217,6 +228,11 @@ pub struct DiemDB {
     system_store: SystemStore,
     rocksdb_property_reporter: RocksdbPropertyReporter,
     pruner: Option<Pruner>,
+    /// Without a pruner, the least readable state version as left by pruning in a previous run, or
+    /// by restoring a state snapshot.
+    least_readable_state_version: AtomicU64,
+    /// Without a pruner, the least readable ledger version, see `least_readable_state_version`.
+    least_readable_ledger_version: AtomicU64,
 }
 
 impl DiemDB {
@@ -235,13 +251,31 @@ impl DiemDB {
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
             TRANSACTION_INFO_CF_NAME,
+            WRITE_SET_CF_NAME,
         ]
     }
 
-    fn new_with_db(db: DB, prune_window: Option<u64>) -> Self {
+    fn new_with_db(
+        db: DB,
+        prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
+    ) -> Result<Self> {
         let db = Arc::new(db);
+        let (pruner, least_readable_state_version, least_readable_ledger_version) =
+            if prune_window.is_some() || ledger_prune_window.is_some() {
+                let pruner = Pruner::new(Arc::clone(&db), prune_window, ledger_prune_window)?;
+                (Some(pruner), 0, 0)
+            } else {
+                // Readonly and secondary instances never prune, but the data might have been
+                // pruned by the instance that wrote it.
+                (
+                    None,
+                    pruner::get_least_readable_state_version(&db)?,
+                    pruner::get_least_readable_ledger_version(&db)?,
+                )
+            };
 
-        DiemDB {
+        Ok(DiemDB {
             db: Arc::clone(&db),
             event_store: Arc::new(EventStore::new(Arc::clone(&db))),
             ledger_store: Arc::new(LedgerStore::new(Arc::clone(&db))),
@@ -249,19 +283,22 @@ impl DiemDB {
             transaction_store: Arc::new(TransactionStore::new(Arc::clone(&db))),
             system_store: SystemStore::new(Arc::clone(&db)),
             rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
-            pruner: prune_window.map(|n| Pruner::new(Arc::clone(&db), n)),
-        }
+            pruner,
+            least_readable_state_version: AtomicU64::new(least_readable_state_version),
+            least_readable_ledger_version: AtomicU64::new(least_readable_ledger_version),
+        })
     }
 
     pub fn open<P: AsRef<Path> + Clone>(
         db_root_path: P,
         readonly: bool,
         prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
         rocksdb_config: RocksdbConfig,
     ) -> Result<Self> {
         ensure!(
-            prune_window.is_none() || !readonly,
-            "Do not set prune_window when opening readonly.",
+            (prune_window.is_none() && ledger_prune_window.is_none()) || !readonly,
+            "Do not set prune_window or ledger_prune_window when opening readonly.",
         );
 
         let path = db_root_path.as_ref().join("diemdb");
@@ -287,7 +324,7 @@ impl DiemDB {
             )?
         };
 
-        let ret = Self::new_with_db(db, prune_window);
+        let ret = Self::new_with_db(db, prune_window, ledger_prune_window)?;
         info!(
             path = path,
             time_ms = %instant.elapsed().as_millis(),
@@ -307,7 +344,7 @@ impl DiemDB {
         rocksdb_config.max_open_files = -1;
         let rocksdb_opts = gen_rocksdb_options(&rocksdb_config);
 
-        Ok(Self::new_with_db(
+        Self::new_with_db(
             DB::open_as_secondary(
                 primary_path,
                 secondary_path,
@@ -316,7 +353,8 @@ impl DiemDB {
                 &rocksdb_opts,
             )?,
             None, // prune_window
-        ))
+            None, // ledger_prune_window
+        )
     }
 
     /// This opens db in non-readonly mode, without the pruner.
@@ -326,6 +364,7 @@ impl DiemDB {
             db_root_path,
             false, /* readonly */
             None,  /* pruner */
+            None,  /* ledger_pruner */
             RocksdbConfig::default(),
         )
         .expect("Unable to open DiemDB")
@@ -404,23 +443,25 @@ impl DiemDB {
         ledger_version: Version,
         fetch_events: bool,
     ) -> Result<TransactionWithProof> {
-        let proof = self
-            .ledger_store
-            .get_transaction_info_with_proof(version, ledger_version)?;
-        let transaction = self.transaction_store.get_transaction(version)?;
+        self.read_unpruned_ledger("Transaction", version, || {
+            let proof = self
+                .ledger_store
+                .get_transaction_info_with_proof(version, ledger_version)?;
+            let transaction = self.transaction_store.get_transaction(version)?;
 
-        // If events were requested, also fetch those.
-        let events = if fetch_events {
-            Some(self.event_store.get_events_by_version(version)?)
-        } else {
-            None
-        };
+            // If events were requested, also fetch those.
+            let events = if fetch_events {
+                Some(self.event_store.get_events_by_version(version)?)
+            } else {
+                None
+            };
 
-        Ok(TransactionWithProof {
-            version,
-            transaction,
-            events,
-            proof,
+            Ok(TransactionWithProof {
+                version,
+                transaction,
+                events,
+                proof,
+            })
         })
     }
 
@@ -436,6 +477,19 @@ impl DiemDB {
         )
     }
 
+    // ============================= Consistency Check APIs =============================
+
+    /// Gets an instance of `DbChecker` for checking the consistency of the data.
+    pub fn get_db_checker(&self) -> DbChecker {
+        DbChecker::new(
+            Arc::clone(&self.db),
+            Arc::clone(&self.ledger_store),
+            Arc::clone(&self.transaction_store),
+            Arc::clone(&self.state_store),
+            Arc::clone(&self.event_store),
+        )
+    }
+
     // ================================== Private APIs ==================================
     fn get_events_with_proof_by_event_key(
         &self,
@@ -569,7 +623,9 @@ impl DiemDB {
         zip_eq(first_version..=last_version, txns_to_commit).try_for_each(
             |(ver, txn_to_commit)| {
                 self.transaction_store
-                    .put_transaction(ver, txn_to_commit.transaction(), cs)
+                    .put_transaction(ver, txn_to_commit.transaction(), cs)?;
+                self.transaction_store
+                    .put_write_set(ver, txn_to_commit.write_set(), cs)
             },
         )?;
 
@@ -608,6 +664,146 @@ impl DiemDB {
             pruner.wake(latest_version)
         }
     }
+
+    fn least_readable_ledger_version(&self) -> Version {
+        self.pruner.as_ref().map_or_else(
+            || self.least_readable_ledger_version.load(Ordering::Relaxed),
+            Pruner::least_readable_ledger_version,
+        )
+    }
+
+    fn least_readable_state_version(&self) -> Version {
+        self.pruner.as_ref().map_or_else(
+            || self.least_readable_state_version.load(Ordering::Relaxed),
+            Pruner::least_readable_state_version,
+        )
+    }
+
+    /// Makes the state and ledger history before `version` unreadable.
+    fn raise_least_readable_versions(&self, version: Version) {
+        match &self.pruner {
+            Some(pruner) => pruner.raise_least_readable_versions(version),
+            None => {
+                self.least_readable_state_version
+                    .fetch_max(version, Ordering::Relaxed);
+                self.least_readable_ledger_version
+                    .fetch_max(version, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn error_if_ledger_pruned(&self, data_type: &str, version: Version) -> Result<()> {
+        let least_readable_version = self.least_readable_ledger_version();
+        if version < least_readable_version {
+            Err(DiemDbError::Pruned(format!(
+                "{} at version {} (least readable ledger version: {})",
+                data_type, version, least_readable_version,
+            ))
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reads ledger history of versions from `version` on with `read`, erroring if they're pruned
+    /// before or while being read. The pruner deletes versions in increasing order, so checking
+    /// `version` covers the whole range read, and checking again afterwards turns the missing data
+    /// of a read racing with the pruner into a `Pruned` error.
+    fn read_unpruned_ledger<T>(
+        &self,
+        data_type: &str,
+        version: Version,
+        read: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        self.error_if_ledger_pruned(data_type, version)?;
+        let result = read();
+        self.error_if_ledger_pruned(data_type, version)?;
+        result
+    }
+
+    /// Errors if the transaction `seq_num` of `address`, not found in the account index at
+    /// `ledger_version`, was pruned along with its index entry. It was committed if the sequence
+    /// number of the account is past it at `ledger_version`, otherwise it was never sent.
+    fn error_if_account_transaction_pruned(
+        &self,
+        address: AccountAddress,
+        seq_num: u64,
+        ledger_version: Version,
+    ) -> Result<()> {
+        let least_readable_version = self.least_readable_ledger_version();
+        if least_readable_version == 0 {
+            return Ok(());
+        }
+        self.error_if_state_pruned(ledger_version)?;
+        let account_seq_num = match self
+            .state_store
+            .get_account_state_with_proof_by_version(address, ledger_version)?
+            .0
+        {
+            Some(blob) => AccountResource::try_from(&blob)?.sequence_number(),
+            None => 0,
+        };
+        if seq_num < account_seq_num {
+            Err(DiemDbError::Pruned(format!(
+                "Transaction {} of account {} (least readable ledger version: {})",
+                seq_num, address, least_readable_version,
+            ))
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn error_if_state_pruned(&self, version: Version) -> Result<()> {
+        let least_readable_version = self.least_readable_state_version();
+        if version < least_readable_version {
+            Err(DiemDbError::Pruned(format!(
+                "State at version {} (least readable state version: {})",
+                version, least_readable_version,
+            ))
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// See `DbReader::get_account_iter`, with chunks of at most `chunk_size` accounts.
+    fn account_iter(
+        &self,
+        version: Version,
+        start_key: HashValue,
+        chunk_size: usize,
+    ) -> Result<Box<dyn Iterator<Item = Result<AccountStatesChunkWithProof>> + Send + '_>> {
+        let mut account_iter =
+            JellyfishMerkleIterator::new(Arc::clone(&self.state_store), version, start_key)?;
+        let chunk_iter: Box<dyn Iterator<Item = Result<AccountStatesChunkWithProof>> + Send + '_> =
+            Box::new(std::iter::from_fn(move || {
+                let account_blobs = match account_iter
+                    .by_ref()
+                    .take(chunk_size)
+                    .collect::<Result<Vec<_>>>()
+                {
+                    Ok(account_blobs) => account_blobs,
+                    Err(err) => return Some(Err(err)),
+                };
+                let leftmost_key = account_blobs.first()?.0;
+                let rightmost_key = account_blobs.last()?.0;
+                let chunk = || -> Result<AccountStatesChunkWithProof> {
+                    let left_siblings = self
+                        .state_store
+                        .get_account_state_siblings(leftmost_key, version)?;
+                    let proof = self
+                        .state_store
+                        .get_account_state_range_proof(rightmost_key, version)?;
+                    Ok(
+                        AccountStatesChunkWithProof::new(version, account_blobs, proof)
+                            .with_left_siblings(left_siblings),
+                    )
+                };
+                Some(chunk())
+            }));
+        Ok(chunk_iter)
+    }
 }
 
 impl DbReader for DiemDB {
@@ -651,12 +847,19 @@ impl DbReader for DiemDB {
         ledger_version: Version,
     ) -> Result<Option<TransactionWithProof>> {
         gauged_api("get_account_transaction", || {
-            self.transaction_store
-                .get_account_transaction_version(address, seq_num, ledger_version)?
-                .map(|txn_version| {
-                    self.get_transaction_with_proof(txn_version, ledger_version, include_events)
-                })
-                .transpose()
+            match self.transaction_store.get_account_transaction_version(
+                address,
+                seq_num,
+                ledger_version,
+            )? {
+                Some(txn_version) => self
+                    .get_transaction_with_proof(txn_version, ledger_version, include_events)
+                    .map(Some),
+                None => {
+                    self.error_if_account_transaction_pruned(address, seq_num, ledger_version)?;
+                    Ok(None)
+                }
+            }
         })
     }
 
@@ -683,7 +886,21 @@ impl DbReader for DiemDB {
                     let (_seq_num, txn_version) = result?;
                     self.get_transaction_with_proof(txn_version, ledger_version, include_events)
                 })
-                .collect::<Result<Vec<_>>>()?;
+                .collect::<Result<Vec<_>>>();
+            // The pruner deletes the index entries of an account in increasing order, so only the
+            // first transactions of the range can be pruned, in which case the index either has
+            // none of them, or isn't contiguous from `start_seq_num`.
+            let txns_with_proofs = match txns_with_proofs {
+                Ok(txns) if !txns.is_empty() => txns,
+                result => {
+                    self.error_if_account_transaction_pruned(
+                        address,
+                        start_seq_num,
+                        ledger_version,
+                    )?;
+                    result?
+                }
+            };
 
             Ok(AccountTransactionsWithProof::new(txns_with_proofs))
         })
@@ -708,37 +925,86 @@ impl DbReader for DiemDB {
             }
 
             let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+            self.read_unpruned_ledger("Transaction", start_version, || {
+                let txns = (start_version..start_version + limit)
+                    .map(|version| self.transaction_store.get_transaction(version))
+                    .collect::<Result<Vec<_>>>()?;
+                let txn_infos = (start_version..start_version + limit)
+                    .map(|version| self.ledger_store.get_transaction_info(version))
+                    .collect::<Result<Vec<_>>>()?;
+                let events = if fetch_events {
+                    Some(
+                        (start_version..start_version + limit)
+                            .map(|version| self.event_store.get_events_by_version(version))
+                            .collect::<Result<Vec<_>>>()?,
+                    )
+                } else {
+                    None
+                };
+                let proof = TransactionListProof::new(
+                    self.ledger_store.get_transaction_range_proof(
+                        Some(start_version),
+                        limit,
+                        ledger_version,
+                    )?,
+                    txn_infos,
+                );
 
-            let txns = (start_version..start_version + limit)
-                .map(|version| self.transaction_store.get_transaction(version))
-                .collect::<Result<Vec<_>>>()?;
-            let txn_infos = (start_version..start_version + limit)
-                .map(|version| self.ledger_store.get_transaction_info(version))
-                .collect::<Result<Vec<_>>>()?;
-            let events = if fetch_events {
-                Some(
-                    (start_version..start_version + limit)
-                        .map(|version| self.event_store.get_events_by_version(version))
-                        .collect::<Result<Vec<_>>>()?,
-                )
-            } else {
-                None
-            };
-            let proof = TransactionListProof::new(
-                self.ledger_store.get_transaction_range_proof(
+                Ok(TransactionListWithProof::new(
+                    txns,
+                    events,
                     Some(start_version),
-                    limit,
-                    ledger_version,
-                )?,
-                txn_infos,
-            );
+                    proof,
+                ))
+            })
+        })
+    }
 
-            Ok(TransactionListWithProof::new(
-                txns,
-                events,
-                Some(start_version),
-                proof,
-            ))
+    fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<TransactionOutputListWithProof> {
+        gauged_api("get_transaction_outputs", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            if start_version > ledger_version || limit == 0 {
+                return Ok(TransactionOutputListWithProof::new_empty());
+            }
+
+            let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+            self.read_unpruned_ledger("Transaction", start_version, || {
+                let (txns_and_outputs, txn_infos) = (start_version..start_version + limit)
+                    .map(|version| {
+                        let txn = self.transaction_store.get_transaction(version)?;
+                        let txn_info = self.ledger_store.get_transaction_info(version)?;
+                        let output = TransactionOutput::new(
+                            self.transaction_store.get_write_set(version)?,
+                            self.event_store.get_events_by_version(version)?,
+                            txn_info.gas_used(),
+                            TransactionStatus::Keep(txn_info.status().clone()),
+                        );
+                        Ok(((txn, output), txn_info))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
+                    .unzip();
+                let proof = TransactionListProof::new(
+                    self.ledger_store.get_transaction_range_proof(
+                        Some(start_version),
+                        limit,
+                        ledger_version,
+                    )?,
+                    txn_infos,
+                );
+
+                Ok(TransactionOutputListWithProof::new(
+                    txns_and_outputs,
+                    Some(start_version),
+                    proof,
+                ))
+            })
         })
     }
 
@@ -868,6 +1134,9 @@ impl DbReader for DiemDB {
                 );
             }
 
+            self.error_if_ledger_pruned("Transaction info", version)?;
+            self.error_if_state_pruned(version)?;
+
             let txn_info_with_proof = self
                 .ledger_store
                 .get_transaction_info_with_proof(version, ledger_version)?;
@@ -895,6 +1164,7 @@ impl DbReader for DiemDB {
         SparseMerkleProof<AccountStateBlob>,
     )> {
         gauged_api("get_account_state_with_proof_by_version", || {
+            self.error_if_state_pruned(version)?;
             self.state_store
                 .get_account_state_with_proof_by_version(address, version)
         })
@@ -935,6 +1205,7 @@ impl DbReader for DiemDB {
 
     fn get_block_timestamp(&self, version: u64) -> Result<u64> {
         gauged_api("get_block_timestamp", || {
+            self.error_if_ledger_pruned("Block timestamp", version)?;
             let ts = match self.transaction_store.get_block_metadata(version)? {
                 Some((_v, block_meta)) => block_meta.into_inner().1,
                 // genesis timestamp is 0
@@ -963,6 +1234,7 @@ impl DbReader for DiemDB {
                 event_version,
                 proof_version,
             );
+            self.error_if_ledger_pruned("Event", event_version)?;
 
             // Get the latest sequence number of an event at or before the
             // requested event_version.
@@ -1056,6 +1328,57 @@ impl DbReader for DiemDB {
                 .get_consistency_proof(client_known_version, ledger_version)
         })
     }
+
+    fn get_account_chunk_with_proof(
+        &self,
+        version: Version,
+        last_key: Option<HashValue>,
+        chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        gauged_api("get_account_chunk_with_proof", || {
+            error_if_too_many_requested(chunk_size as u64, MAX_LIMIT)?;
+            self.error_if_state_pruned(version)?;
+
+            let account_blobs = JellyfishMerkleIterator::new(
+                Arc::clone(&self.state_store),
+                version,
+                last_key.unwrap_or_else(HashValue::zero),
+            )?
+            .skip_while(|res| matches!(res, Ok((key, _blob)) if Some(*key) == last_key))
+            .take(chunk_size)
+            .collect::<Result<Vec<_>>>()?;
+            let rightmost_key = account_blobs
+                .last()
+                .map(|(key, _blob)| *key)
+                .ok_or_else(|| {
+                    DiemDbError::NotFound(format!(
+                        "Accounts after {:?} at version {}",
+                        last_key, version
+                    ))
+                })?;
+            let proof = self
+                .state_store
+                .get_account_state_range_proof(rightmost_key, version)?;
+
+            Ok(AccountStatesChunkWithProof::new(
+                version,
+                account_blobs,
+                proof,
+            ))
+        })
+    }
+
+    fn get_account_iter(
+        &self,
+        version: Version,
+        start_key: HashValue,
+    ) -> Result<Box<dyn Iterator<Item = Result<AccountStatesChunkWithProof>> + Send + '_>> {
+        gauged_api("get_account_iter", || {
+            self.error_if_state_pruned(version)?;
+
+            self.account_iter(version, start_key, MAX_LIMIT as usize)
+        })
+    }
 }
 
 impl ModuleResolver for DiemDB {
@@ -1090,7 +1413,11 @@ impl ResourceResolver for DiemDB {
     }
 }
 
-impl MoveDbReader for DiemDB {}
+impl MoveDbReader for DiemDB {
+    fn into_db_reader(self: Arc<Self>) -> Arc<dyn DbReader> {
+        self
+    }
+}
 
 impl DbWriter for DiemDB {
     /// `first_version` is the version of the first transaction in `txns_to_commit`.
@@ -1176,6 +1503,77 @@ impl DbWriter for DiemDB {
             Ok(())
         })
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
+        gauged_api("get_state_snapshot_receiver", || {
+            Ok(Box::new(JellyfishMerkleRestore::new_overwrite(
+                Arc::clone(&self.state_store),
+                version,
+                expected_root_hash,
+            )?)
+                as Box<dyn StateSnapshotReceiver<AccountStateBlob>>)
+        })
+    }
+
+    fn finalize_state_snapshot(
+        &self,
+        version: Version,
+        output_list_with_proof: TransactionOutputListWithProof,
+        frozen_subtrees: Vec<HashValue>,
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        gauged_api("finalize_state_snapshot", || {
+            ensure!(
+                output_list_with_proof.first_transaction_output_version == Some(version)
+                    && output_list_with_proof.len() == 1,
+                "Expecting exactly the transaction output at version {}.",
+                version,
+            );
+            let TransactionOutputListWithProof {
+                transactions_and_outputs,
+                proof,
+                ..
+            } = output_list_with_proof;
+            let (_range_proof, txn_infos) = proof.unpack();
+            let (txn, output) = &transactions_and_outputs[0];
+
+            // Everything goes in a single batch, so that a crash can't leave the DB with a
+            // partial history.
+            let mut cs = ChangeSet::new();
+            self.ledger_store.put_transaction_info_on_frozen_subtrees(
+                version,
+                &txn_infos[0],
+                &frozen_subtrees,
+                &mut cs,
+            )?;
+            self.transaction_store
+                .put_transaction(version, txn, &mut cs)?;
+            self.transaction_store
+                .put_write_set(version, output.write_set(), &mut cs)?;
+            self.event_store
+                .put_events(version, output.events(), &mut cs)?;
+            for ledger_info in ledger_infos {
+                self.ledger_store.put_ledger_info(ledger_info, &mut cs)?;
+            }
+            self.db.write_schemas(cs.batch)?;
+            // There's no history before the snapshot, report it as pruned rather than not found.
+            self.raise_least_readable_versions(version);
+
+            if let Some(x) = ledger_infos.last() {
+                self.ledger_store.set_latest_ledger_info(x.clone());
+
+                DIEM_STORAGE_LEDGER_VERSION.set(x.ledger_info().version() as i64);
+                DIEM_STORAGE_NEXT_BLOCK_EPOCH.set(x.ledger_info().next_block_epoch() as i64);
+            }
+            DIEM_STORAGE_LATEST_TXN_VERSION.set(version as i64);
+
+            Ok(())
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
@@ -6,18 +6,26 @@
 
 use crate::{
     metrics::{
-        DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION,
-        DIEM_STORAGE_PRUNE_WINDOW,
+        DIEM_STORAGE_LEDGER_PRUNE_WINDOW, DIEM_STORAGE_OTHER_TIMERS_SECONDS,
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_LEDGER_VERSION,
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION, DIEM_STORAGE_PRUNE_WINDOW,
     },
     schema::{
+        event::EventSchema, event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
+        transaction_info::TransactionInfoSchema, write_set::WriteSetSchema,
     },
 };
 use anyhow::Result;
 use diem_infallible::Mutex;
 use diem_jellyfish_merkle::StaleNodeIndex;
 use diem_logger::prelude::*;
-use diem_types::transaction::Version;
+use diem_types::{
+    proof::position::Position,
+    transaction::{Transaction, Version},
+};
 use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
 use std::{
     iter::Peekable,
@@ -26,87 +34,159 @@ use std::{
         mpsc::{channel, Receiver, Sender},
         Arc,
     },
-    thread::{sleep, JoinHandle},
+    thread::JoinHandle,
     time::{Duration, Instant},
 };
 
 /// The `Pruner` is meant to be part of a `DiemDB` instance and runs in the background to prune old
 /// data.
 ///
+/// It prunes two kinds of data independently: stale Jellyfish Merkle nodes (the state) and the
+/// ledger history, i.e. transactions, transaction infos, events, event accumulators and the
+/// indices on them.
+///
 /// It creates a worker thread on construction and joins it on destruction. When destructed, it
 /// quits the worker thread eagerly without waiting for all pending work to be done.
 #[derive(Debug)]
 pub(crate) struct Pruner {
-    /// Other than the latest version, how many historical versions to keep being readable. For
-    /// example, this being 0 means keep only the latest version.
-    historical_versions_to_keep: u64,
+    /// Other than the latest version, how many historical versions of the state to keep being
+    /// readable. For example, this being 0 means keep only the latest version. `None` disables
+    /// state pruning.
+    state_prune_window: Option<u64>,
+    /// Other than the latest version, how many historical versions of the ledger history to keep
+    /// being readable. `None` disables ledger pruning.
+    ledger_prune_window: Option<u64>,
     /// The worker thread handle, created upon Pruner instance construction and joined upon its
     /// destruction. It only becomes `None` after joined in `drop()`.
     worker_thread: Option<JoinHandle<()>>,
     /// The sender side of the channel talking to the worker thread.
     command_sender: Mutex<Sender<Command>>,
-    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress. If it
-    /// sets this atomic value to `V`, all versions before `V` can no longer be accessed.
-    #[allow(dead_code)]
-    worker_progress: Arc<AtomicU64>,
+    /// A way for the worker thread to inform the `Pruner` the state pruning progress. If it sets
+    /// this atomic value to `V`, state of all versions before `V` can no longer be accessed.
+    state_worker_progress: Arc<AtomicU64>,
+    /// Same as `state_worker_progress`, but for the ledger history.
+    ledger_worker_progress: Arc<AtomicU64>,
 }
 
 impl Pruner {
     /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(db: Arc<DB>, historical_versions_to_keep: u64) -> Self {
+    ///
+    /// The pruning progress is found out before returning, so that the data pruned in a previous
+    /// run is never reported as readable.
+    pub fn new(
+        db: Arc<DB>,
+        state_prune_window: Option<u64>,
+        ledger_prune_window: Option<u64>,
+    ) -> Result<Self> {
         let (command_sender, command_receiver) = channel();
 
-        let worker_progress = Arc::new(AtomicU64::new(0));
-        let worker_progress_clone = Arc::clone(&worker_progress);
+        let state_worker_progress =
+            Arc::new(AtomicU64::new(get_least_readable_state_version(&db)?));
+        let state_worker_progress_clone = Arc::clone(&state_worker_progress);
+        let ledger_worker_progress =
+            Arc::new(AtomicU64::new(get_least_readable_ledger_version(&db)?));
+        let ledger_worker_progress_clone = Arc::clone(&ledger_worker_progress);
 
-        DIEM_STORAGE_PRUNE_WINDOW.set(historical_versions_to_keep as i64);
+        if let Some(n) = state_prune_window {
+            DIEM_STORAGE_PRUNE_WINDOW.set(n as i64);
+        }
+        if let Some(n) = ledger_prune_window {
+            DIEM_STORAGE_LEDGER_PRUNE_WINDOW.set(n as i64);
+        }
         let worker_thread = std::thread::Builder::new()
             .name("diemdb_pruner".into())
-            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work())
+            .spawn(move || {
+                Worker::new(
+                    db,
+                    command_receiver,
+                    state_worker_progress_clone,
+                    ledger_worker_progress_clone,
+                )
+                .work()
+            })
             .expect("Creating pruner thread should succeed.");
 
-        Self {
-            historical_versions_to_keep,
+        Ok(Self {
+            state_prune_window,
+            ledger_prune_window,
             worker_thread: Some(worker_thread),
             command_sender: Mutex::new(command_sender),
-            worker_progress,
-        }
+            state_worker_progress,
+            ledger_worker_progress,
+        })
+    }
+
+    /// Versions of the state before this can no longer be read.
+    pub fn least_readable_state_version(&self) -> Version {
+        self.state_worker_progress.load(Ordering::Relaxed)
+    }
+
+    /// Versions of the ledger history before this can no longer be read.
+    pub fn least_readable_ledger_version(&self) -> Version {
+        self.ledger_worker_progress.load(Ordering::Relaxed)
+    }
+
+    /// Makes the state and ledger history before `version` unreadable, e.g. because the DB was
+    /// restored from a state snapshot at `version` and has nothing before it.
+    pub fn raise_least_readable_versions(&self, version: Version) {
+        self.state_worker_progress
+            .fetch_max(version, Ordering::Relaxed);
+        self.ledger_worker_progress
+            .fetch_max(version, Ordering::Relaxed);
     }
 
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
+        let state_least_readable_version =
+            Self::target_least_readable_version(latest_version, self.state_prune_window);
+        let ledger_least_readable_version =
+            Self::target_least_readable_version(latest_version, self.ledger_prune_window);
+
+        if state_least_readable_version.is_some() || ledger_least_readable_version.is_some() {
             self.command_sender
                 .lock()
                 .send(Command::Prune {
-                    least_readable_version,
+                    state_least_readable_version,
+                    ledger_least_readable_version,
                 })
                 .expect("Receiver should not destruct prematurely.");
         }
     }
 
+    fn target_least_readable_version(
+        latest_version: Version,
+        prune_window: Option<u64>,
+    ) -> Option<Version> {
+        prune_window
+            .and_then(|n| latest_version.checked_sub(n))
+            .filter(|v| *v > 0)
+    }
+
     /// (For tests only.) Notifies the worker thread and waits for it to finish its job by polling
-    /// an internal counter.
+    /// internal counters.
     #[cfg(test)]
     pub fn wake_and_wait(&self, latest_version: Version) -> Result<()> {
         self.wake(latest_version);
 
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
-            // Assuming no big pruning chunks will be issued by a test.
-            const TIMEOUT: Duration = Duration::from_secs(10);
-            let end = Instant::now() + TIMEOUT;
-
-            while Instant::now() < end {
-                if self.worker_progress.load(Ordering::Relaxed) >= least_readable_version {
-                    return Ok(());
-                }
-                sleep(Duration::from_millis(1));
+        let state_least_readable_version =
+            Self::target_least_readable_version(latest_version, self.state_prune_window)
+                .unwrap_or(0);
+        let ledger_least_readable_version =
+            Self::target_least_readable_version(latest_version, self.ledger_prune_window)
+                .unwrap_or(0);
+        // Assuming no big pruning chunks will be issued by a test.
+        const TIMEOUT: Duration = Duration::from_secs(10);
+        let end = Instant::now() + TIMEOUT;
+
+        while Instant::now() < end {
+            if self.least_readable_state_version() >= state_least_readable_version
+                && self.least_readable_ledger_version() >= ledger_least_readable_version
+            {
+                return Ok(());
             }
-            anyhow::bail!("Timeout waiting for pruner worker.");
+            std::thread::sleep(Duration::from_millis(1));
         }
-        Ok(())
+        anyhow::bail!("Timeout waiting for pruner worker.");
     }
 }
 
@@ -126,17 +206,23 @@ impl Drop for Pruner {
 
 enum Command {
     Quit,
-    Prune { least_readable_version: Version },
+    Prune {
+        state_least_readable_version: Option<Version>,
+        ledger_least_readable_version: Option<Version>,
+    },
 }
 
 struct Worker {
     db: Arc<DB>,
     command_receiver: Receiver<Command>,
-    target_least_readable_version: Version,
-    /// Keeps a record of the pruning progress. If this equals to version `V`, we know versions
-    /// smaller than `V` are no longer readable.
-    /// This being an atomic value is to communicate the info with the Pruner thread (for tests).
-    least_readable_version: Arc<AtomicU64>,
+    target_least_readable_state_version: Version,
+    /// Keeps a record of the state pruning progress. If this equals to version `V`, we know
+    /// versions smaller than `V` are no longer readable.
+    /// This being an atomic value is to communicate the info with the Pruner thread.
+    least_readable_state_version: Arc<AtomicU64>,
+    target_least_readable_ledger_version: Version,
+    /// Keeps a record of the ledger pruning progress, see `least_readable_state_version`.
+    least_readable_ledger_version: Arc<AtomicU64>,
     /// Indicates if there's NOT any pending work to do currently, to hint
     /// `Self::receive_commands()` to `recv()` blocking-ly.
     blocking_recv: bool,
@@ -150,13 +236,16 @@ impl Worker {
     fn new(
         db: Arc<DB>,
         command_receiver: Receiver<Command>,
-        least_readable_version: Arc<AtomicU64>,
+        least_readable_state_version: Arc<AtomicU64>,
+        least_readable_ledger_version: Arc<AtomicU64>,
     ) -> Self {
         Self {
             db,
             command_receiver,
-            least_readable_version,
-            target_least_readable_version: 0,
+            least_readable_state_version,
+            target_least_readable_state_version: 0,
+            least_readable_ledger_version,
+            target_least_readable_ledger_version: 0,
             blocking_recv: true,
             index_min_nonpurged_version: 0,
             index_purged_at: Instant::now(),
@@ -169,87 +258,120 @@ impl Worker {
         while self.receive_commands() {
             // Process a reasonably small batch of work before trying to receive commands again,
             // in case `Command::Quit` is received (that's when we should quit.)
-            let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
-            match prune_state(
-                Arc::clone(&self.db),
-                least_readable_version,
-                self.target_least_readable_version,
-                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
-            ) {
-                Ok(new_least_readable_version) => {
-                    self.record_progress(new_least_readable_version);
-
-                    // Make next recv() blocking if nothing left to do.
-                    self.blocking_recv = new_least_readable_version == least_readable_version // did nothing
-                        || new_least_readable_version == self.target_least_readable_version; // did all
-
-                    // Try to purge the log.
-                    if let Err(e) = self.maybe_purge_index() {
-                        warn!(
-                            error = ?e,
-                            "Failed purging state node index, ignored.",
-                        );
-                    }
-                }
-                Err(e) => {
-                    error!(
+            let state_done = self.prune_state_batch();
+            let ledger_done = self.prune_ledger_batch();
+
+            // Make next recv() blocking if nothing left to do.
+            self.blocking_recv = state_done && ledger_done;
+        }
+    }
+
+    /// Prunes a batch of stale state nodes. Returns `true` if there's nothing left to do.
+    fn prune_state_batch(&mut self) -> bool {
+        let least_readable_version = self.least_readable_state_version.load(Ordering::Relaxed);
+        match prune_state(
+            Arc::clone(&self.db),
+            least_readable_version,
+            self.target_least_readable_state_version,
+            Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+        ) {
+            Ok(new_least_readable_version) => {
+                self.record_state_progress(new_least_readable_version);
+
+                // Try to purge the log.
+                if let Err(e) = self.maybe_purge_index() {
+                    warn!(
                         error = ?e,
-                        "Error pruning stale state nodes.",
+                        "Failed purging state node index, ignored.",
                     );
-                    // On error, stop retrying vigorously by making next recv() blocking.
-                    self.blocking_recv = true;
                 }
+
+                new_least_readable_version == least_readable_version // did nothing
+                    || new_least_readable_version == self.target_least_readable_state_version
+                // did all
+            }
+            Err(e) => {
+                error!(
+                    error = ?e,
+                    "Error pruning stale state nodes.",
+                );
+                // On error, stop retrying vigorously by making next recv() blocking.
+                true
             }
         }
     }
 
-    /// Find out the first undeleted item in the stale node index.
-    ///
-    /// Seeking from the beginning (version 0) is potentially costly, we do it once upon worker
-    /// thread start, record the progress and seek from that position afterwards.
-    fn initialize(&mut self) {
-        loop {
-            match self.get_least_readable_version() {
-                Ok(least_readable_version) => {
-                    info!(
-                        least_readable_version = least_readable_version,
-                        "[state pruner worker] initialized."
-                    );
-                    self.target_least_readable_version = least_readable_version;
-                    self.record_progress(least_readable_version);
-                    return;
-                }
-                Err(e) => {
-                    error!(
-                        error = ?e,
-                        "[state pruner worker] Error on first seek. Retrying in 1 second.",
-                    );
-                    sleep(Duration::from_secs(1));
-                }
+    /// Prunes a batch of ledger history. Returns `true` if there's nothing left to do.
+    fn prune_ledger_batch(&mut self) -> bool {
+        let least_readable_version = self.least_readable_ledger_version.load(Ordering::Relaxed);
+        if least_readable_version >= self.target_least_readable_ledger_version {
+            return true;
+        }
+        let new_least_readable_version = std::cmp::min(
+            self.target_least_readable_ledger_version,
+            least_readable_version + Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH as u64,
+        );
+
+        // Progress is recorded before the deletion is issued, so that readers get a "pruned"
+        // error instead of seeing partially deleted data. If the deletion fails, the leftovers are
+        // picked up again when the worker is re-initialized upon the next restart.
+        self.record_ledger_progress(new_least_readable_version);
+        match prune_ledger(
+            Arc::clone(&self.db),
+            least_readable_version,
+            new_least_readable_version,
+        ) {
+            Ok(()) => new_least_readable_version == self.target_least_readable_ledger_version,
+            Err(e) => {
+                error!(
+                    error = ?e,
+                    "Error pruning ledger history.",
+                );
+                // On error, stop retrying vigorously by making next recv() blocking.
+                true
             }
         }
     }
 
-    fn get_least_readable_version(&self) -> Result<Version> {
-        let mut iter = self
-            .db
-            .iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
-        iter.seek_to_first();
-        Ok(iter.next().transpose()?.map_or(0, |(index, _)| {
-            index
-                .stale_since_version
-                .checked_sub(1)
-                .expect("Nothing is stale since version 0.")
-        }))
+    /// Carries on from the pruning progress found out by the `Pruner` upon construction.
+    ///
+    /// Seeking from the beginning (version 0) is potentially costly, it's done once on
+    /// construction and the worker seeks from the recorded progress afterwards.
+    fn initialize(&mut self) {
+        let least_readable_state_version =
+            self.least_readable_state_version.load(Ordering::Relaxed);
+        let least_readable_ledger_version =
+            self.least_readable_ledger_version.load(Ordering::Relaxed);
+        info!(
+            least_readable_state_version = least_readable_state_version,
+            least_readable_ledger_version = least_readable_ledger_version,
+            "[pruner worker] initialized."
+        );
+        self.target_least_readable_state_version = least_readable_state_version;
+        self.record_state_progress(least_readable_state_version);
+        self.target_least_readable_ledger_version = least_readable_ledger_version;
+        self.record_ledger_progress(least_readable_ledger_version);
     }
 
-    /// Log the progress.
-    fn record_progress(&mut self, least_readable_version: Version) {
-        self.least_readable_version
-            .store(least_readable_version, Ordering::Relaxed);
+    /// Log the state pruning progress. It never goes backwards, even if the `Pruner` raised it
+    /// meanwhile.
+    fn record_state_progress(&mut self, least_readable_version: Version) {
+        let least_readable_version = self
+            .least_readable_state_version
+            .fetch_max(least_readable_version, Ordering::Relaxed)
+            .max(least_readable_version);
         DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
     }
 
+    /// Log the ledger pruning progress, see `record_state_progress`.
+    fn record_ledger_progress(&mut self, least_readable_version: Version) {
+        let least_readable_version = self
+            .least_readable_ledger_version
+            .fetch_max(least_readable_version, Ordering::Relaxed)
+            .max(least_readable_version);
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_LEDGER_VERSION.set(least_readable_version as i64);
+    }
+
     /// Tries to receive all pending commands, blocking waits for the next command if no work needs
     /// to be done, otherwise quits with `true` to allow the outer loop to do some work before
     /// getting back here.
@@ -276,13 +398,22 @@ impl Worker {
                 // On `Command::Quit` inform the outer loop to quit by returning `false`.
                 Command::Quit => return false,
                 Command::Prune {
-                    least_readable_version,
+                    state_least_readable_version,
+                    ledger_least_readable_version,
                 } => {
-                    if least_readable_version > self.target_least_readable_version {
-                        self.target_least_readable_version = least_readable_version;
-                        // Switch to non-blocking to allow some work to be done after the
-                        // channel has drained.
-                        self.blocking_recv = false;
+                    if let Some(version) = state_least_readable_version {
+                        if version > self.target_least_readable_state_version {
+                            self.target_least_readable_state_version = version;
+                            // Switch to non-blocking to allow some work to be done after the
+                            // channel has drained.
+                            self.blocking_recv = false;
+                        }
+                    }
+                    if let Some(version) = ledger_least_readable_version {
+                        if version > self.target_least_readable_ledger_version {
+                            self.target_least_readable_ledger_version = version;
+                            self.blocking_recv = false;
+                        }
                     }
                 }
             }
@@ -303,7 +434,7 @@ impl Worker {
         // this imposes at most one minute of work in vain after restarting.)
         let now = Instant::now();
         if now - self.index_purged_at > MIN_INTERVAL {
-            let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
+            let least_readable_version = self.least_readable_state_version.load(Ordering::Relaxed);
 
             if least_readable_version - self.index_min_nonpurged_version + 1 > MIN_VERSIONS {
                 let new_min_non_purged_version = least_readable_version + 1;
@@ -320,6 +451,35 @@ impl Worker {
     }
 }
 
+/// Finds out the least readable version of the state from the first undeleted item in the stale
+/// node index, and from the first node of the Jellyfish Merkle tree, which is at the version of
+/// the snapshot on a DB restored from a state snapshot.
+pub(crate) fn get_least_readable_state_version(db: &DB) -> Result<Version> {
+    let mut iter = db.iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
+    iter.seek_to_first();
+    let pruned_version = iter.next().transpose()?.map_or(0, |(index, _)| {
+        index
+            .stale_since_version
+            .checked_sub(1)
+            .expect("Nothing is stale since version 0.")
+    });
+    let mut iter = db.iter::<JellyfishMerkleNodeSchema>(ReadOptions::default())?;
+    iter.seek_to_first();
+    let first_node_version = iter
+        .next()
+        .transpose()?
+        .map_or(0, |(node_key, _)| node_key.version());
+    Ok(pruned_version.max(first_node_version))
+}
+
+/// Finds out the least readable version of the ledger history from the first undeleted
+/// transaction.
+pub(crate) fn get_least_readable_ledger_version(db: &DB) -> Result<Version> {
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek_to_first();
+    Ok(iter.next().transpose()?.map_or(0, |(version, _)| version))
+}
+
 struct StaleNodeIndicesByVersionIterator<'a> {
     inner: Peekable<SchemaIterator<'a, StaleNodeIndexSchema>>,
     target_least_readable_version: Version,
@@ -408,5 +568,64 @@ pub fn prune_state(
     }
 }
 
+/// Deletes ledger history of versions in range [`least_readable_version`,
+/// `target_least_readable_version`), including transactions, transaction infos, events, event
+/// accumulators and the indices on transactions by account and events by key.
+///
+/// The transaction accumulator is untouched since its frozen nodes are needed to prove later
+/// versions.
+pub fn prune_ledger(
+    db: Arc<DB>,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+) -> Result<()> {
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["ledger_pruner_commit"])
+        .start_timer();
+    let mut batch = SchemaBatch::new();
+
+    // Transactions, transaction infos, write sets and the account index.
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+    for res in iter {
+        let (version, txn) = res?;
+        if version >= target_least_readable_version {
+            break;
+        }
+        if let Transaction::UserTransaction(txn) = txn {
+            batch.delete::<TransactionByAccountSchema>(&(txn.sender(), txn.sequence_number()))?;
+        }
+        batch.delete::<TransactionSchema>(&version)?;
+        batch.delete::<TransactionInfoSchema>(&version)?;
+        batch.delete::<WriteSetSchema>(&version)?;
+    }
+
+    // Events and the indices on them.
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+    for res in iter {
+        let ((version, index), event) = res?;
+        if version >= target_least_readable_version {
+            break;
+        }
+        batch.delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
+        batch.delete::<EventByVersionSchema>(&(*event.key(), version, event.sequence_number()))?;
+        batch.delete::<EventSchema>(&(version, index))?;
+    }
+
+    // Event accumulators.
+    let mut iter = db.iter::<EventAccumulatorSchema>(ReadOptions::default())?;
+    iter.seek(&(least_readable_version, Position::from_inorder_index(0)))?;
+    for res in iter {
+        let ((version, position), _hash) = res?;
+        if version >= target_least_readable_version {
+            break;
+        }
+        batch.delete::<EventAccumulatorSchema>(&(version, position))?;
+    }
+
+    db.write_schemas(batch)
+}
+
 #[cfg(test)]
 mod test;
@@ -2,11 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::{change_set::ChangeSet, state_store::StateStore, DiemDB};
+use crate::{
+    change_set::ChangeSet, errors::DiemDbError, state_store::StateStore,
+    test_helper::arb_blocks_to_commit, DiemDB,
+};
+use diem_config::config::RocksdbConfig;
 use diem_crypto::HashValue;
 use diem_temppath::TempPath;
-use diem_types::{account_address::AccountAddress, account_state_blob::AccountStateBlob};
+use diem_types::{
+    account_address::AccountAddress,
+    account_state_blob::AccountStateBlob,
+    transaction::{Transaction, TransactionToCommit},
+};
+use proptest::prelude::*;
 use std::collections::HashMap;
+use storage_interface::{DbReader, DbWriter};
 
 fn put_account_state_set(
     db: &DB,
@@ -50,7 +60,12 @@ fn test_pruner() {
     let tmp_dir = TempPath::new();
     let db = DiemDB::new_for_test(&tmp_dir).db;
     let state_store = &StateStore::new(Arc::clone(&db));
-    let pruner = Pruner::new(Arc::clone(&db), 0 /* historical_versions_to_keep */);
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        Some(0), /* state_prune_window */
+        None,    /* ledger_prune_window */
+    )
+    .unwrap();
 
     let _root0 = put_account_state_set(
         &db,
@@ -136,16 +151,19 @@ fn test_worker_quit_eagerly() {
         let worker = Worker::new(
             Arc::clone(&db),
             command_receiver,
-            Arc::new(AtomicU64::new(0)), /* progress */
+            Arc::new(AtomicU64::new(0)), /* state progress */
+            Arc::new(AtomicU64::new(0)), /* ledger progress */
         );
         command_sender
             .send(Command::Prune {
-                least_readable_version: 1,
+                state_least_readable_version: Some(1),
+                ledger_least_readable_version: None,
             })
             .unwrap();
         command_sender
             .send(Command::Prune {
-                least_readable_version: 2,
+                state_least_readable_version: Some(2),
+                ledger_least_readable_version: None,
             })
             .unwrap();
         command_sender.send(Command::Quit).unwrap();
@@ -156,3 +174,148 @@ fn test_worker_quit_eagerly() {
         verify_state_in_store(state_store, address, Some(&value2), 2);
     }
 }
+
+fn assert_pruned<T: std::fmt::Debug>(result: Result<T>) {
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DiemDbError>(),
+        Some(DiemDbError::Pruned(_))
+    ));
+}
+
+fn verify_ledger_pruned(
+    db: &DiemDB,
+    txns_to_commit: &[TransactionToCommit],
+    latest_version: Version,
+) {
+    for version in 0..latest_version {
+        assert!(db.db.get::<TransactionSchema>(&version).unwrap().is_none());
+        assert!(db
+            .db
+            .get::<TransactionInfoSchema>(&version)
+            .unwrap()
+            .is_none());
+        assert!(db.db.get::<WriteSetSchema>(&version).unwrap().is_none());
+        assert!(db
+            .event_store
+            .get_events_by_version(version)
+            .unwrap()
+            .is_empty());
+
+        assert_pruned(db.get_transactions(
+            version,
+            1,
+            latest_version,
+            true, /* fetch_events */
+        ));
+        // Ranges reaching into the readable versions are pruned as a whole.
+        assert_pruned(db.get_transactions(
+            version,
+            latest_version - version + 1,
+            latest_version,
+            false, /* fetch_events */
+        ));
+        assert_pruned(db.get_transaction_outputs(version, 1, latest_version));
+    }
+    for (version, txn_to_commit) in txns_to_commit.iter().enumerate() {
+        if let Transaction::UserTransaction(txn) = txn_to_commit.transaction() {
+            let indexed = db
+                .transaction_store
+                .get_account_transaction_version(
+                    txn.sender(),
+                    txn.sequence_number(),
+                    latest_version,
+                )
+                .unwrap();
+            assert!(indexed.map_or(true, |v| v == latest_version));
+            // Pruned transactions are told apart from the ones never sent by the sequence number
+            // of the account.
+            let result = db.get_account_transaction(
+                txn.sender(),
+                txn.sequence_number(),
+                true, /* include_events */
+                latest_version,
+            );
+            let result_in_list = db.get_account_transactions(
+                txn.sender(),
+                txn.sequence_number(),
+                1,    /* limit */
+                true, /* include_events */
+                latest_version,
+            );
+            if (version as Version) < latest_version {
+                assert_pruned(result);
+                assert_pruned(result_in_list);
+            } else {
+                assert_eq!(result.unwrap().unwrap().version, latest_version);
+                assert_eq!(result_in_list.unwrap().len(), 1);
+            }
+            assert!(db
+                .get_account_transaction(
+                    txn.sender(),
+                    u64::max_value(),
+                    false, /* include_events */
+                    latest_version,
+                )
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    // The latest version is still readable.
+    let txn_list = db
+        .get_transactions(
+            latest_version,
+            1,
+            latest_version,
+            true, /* fetch_events */
+        )
+        .unwrap();
+    assert_eq!(txn_list.transactions.len(), 1);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_ledger_pruner(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::open(
+            &tmp_dir,
+            false,   /* readonly */
+            None,    /* pruner */
+            Some(0), /* ledger_pruner */
+            RocksdbConfig::default(),
+        )
+        .unwrap();
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in &input {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let latest_version = cur_ver - 1;
+        let pruner = db.pruner.as_ref().unwrap();
+        pruner.wake_and_wait(latest_version).unwrap();
+        prop_assert_eq!(pruner.least_readable_ledger_version(), latest_version);
+
+        let all_txns = input
+            .iter()
+            .flat_map(|(txns_to_commit, _)| txns_to_commit.clone())
+            .collect::<Vec<_>>();
+        verify_ledger_pruned(&db, &all_txns, latest_version);
+
+        // Reopened readonly, without a pruner, the pruned versions are still reported as such.
+        drop(db);
+        let db = DiemDB::open(
+            &tmp_dir,
+            true, /* readonly */
+            None, /* pruner */
+            None, /* ledger_pruner */
+            RocksdbConfig::default(),
+        )
+        .unwrap();
+        verify_ledger_pruned(&db, &all_txns, latest_version);
+    }
+}
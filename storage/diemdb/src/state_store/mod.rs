@@ -13,7 +13,7 @@ use crate::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
     },
 };
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use diem_crypto::HashValue;
 use diem_jellyfish_merkle::{node_type::NodeKey, JellyfishMerkleTree, TreeReader, TreeWriter};
 use diem_types::{
@@ -52,6 +52,18 @@ impl StateStore {
         JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)
     }
 
+    /// Gets the siblings of the path from the root to the account whose hashed address is `key`,
+    /// from the bottom level to the root level.
+    pub fn get_account_state_siblings(
+        &self,
+        key: HashValue,
+        version: Version,
+    ) -> Result<Vec<HashValue>> {
+        let (account, proof) = JellyfishMerkleTree::new(self).get_with_proof(key, version)?;
+        ensure!(account.is_some(), "Account {:x} doesn't exist.", key);
+        Ok(proof.siblings().to_vec())
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
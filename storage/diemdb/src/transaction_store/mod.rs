@@ -6,13 +6,17 @@
 use crate::{
     change_set::ChangeSet,
     errors::DiemDbError,
-    schema::{transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema},
+    schema::{
+        transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
+        write_set::WriteSetSchema,
+    },
 };
 use anyhow::{ensure, format_err, Result};
 use diem_types::{
     account_address::AccountAddress,
     block_metadata::BlockMetadata,
     transaction::{Transaction, Version},
+    write_set::WriteSet,
 };
 use schemadb::{ReadOptions, SchemaIterator, DB};
 use std::sync::Arc;
@@ -141,6 +145,23 @@ impl TransactionStore {
 
         Ok(())
     }
+
+    /// Get the write set of the transaction at `version`
+    pub fn get_write_set(&self, version: Version) -> Result<WriteSet> {
+        self.db
+            .get::<WriteSetSchema>(&version)?
+            .ok_or_else(|| DiemDbError::NotFound(format!("WriteSet at version {}", version)).into())
+    }
+
+    /// Save the write set of the transaction at `version`
+    pub fn put_write_set(
+        &self,
+        version: Version,
+        write_set: &WriteSet,
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        cs.batch.put::<WriteSetSchema>(&version, write_set)
+    }
 }
 
 pub struct TransactionIter<'a> {
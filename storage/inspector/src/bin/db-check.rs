@@ -0,0 +1,89 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::Result;
+use diem_config::config::RocksdbConfig;
+use diem_logger::info;
+use diem_types::transaction::Version;
+use diemdb::DiemDB;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    about = "Checks the consistency of the data in a DiemDB. Whatever the range of versions, the \
+    transaction_by_account and event_by_key indexes are scanned whole for dangling entries."
+)]
+struct Opt {
+    #[structopt(long, parse(from_os_str), help = "The DB root directory.")]
+    db: PathBuf,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Directory for the RocksDB secondary instance, defaulting to a temporary one."
+    )]
+    secondary_dir: Option<PathBuf>,
+
+    #[structopt(long, default_value = "0")]
+    start_version: Version,
+
+    #[structopt(long, help = "Defaulting to the latest version in the DB.")]
+    end_version: Option<Version>,
+
+    #[structopt(
+        long,
+        conflicts_with = "secondary-dir",
+        help = "Repair the inconsistencies in the indexes. This opens the DB for writing, so the \
+        node must be stopped."
+    )]
+    repair: bool,
+}
+
+fn main() -> Result<()> {
+    ::diem_logger::DiemLogger::builder().build();
+
+    let opt = Opt::from_args();
+
+    let tmp_dir = tempfile::tempdir()?;
+    let db = if opt.repair {
+        info!("Opening DB at {:?} for repair.", opt.db);
+        DiemDB::open(
+            &opt.db,
+            false, /* readonly */
+            None,  /* pruner */
+            None,  /* ledger_pruner */
+            RocksdbConfig::default(),
+        )?
+    } else {
+        let secondary_dir = opt
+            .secondary_dir
+            .clone()
+            .unwrap_or_else(|| tmp_dir.path().to_path_buf());
+        info!(
+            "Opening DB at {:?} as secondary at {:?}.",
+            opt.db, secondary_dir
+        );
+        DiemDB::open_as_secondary(opt.db.clone(), secondary_dir, RocksdbConfig::default())?
+    };
+
+    let checker = db.get_db_checker();
+    let inconsistencies = checker.check(opt.start_version, opt.end_version)?;
+    for inconsistency in &inconsistencies {
+        println!("{}", inconsistency);
+    }
+    println!("Found {} inconsistencies.", inconsistencies.len());
+
+    let mut num_left = inconsistencies.len();
+    if opt.repair {
+        let num_repaired = checker.repair(&inconsistencies)?;
+        println!("Repaired {} inconsistencies.", num_repaired);
+        num_left -= num_repaired;
+    }
+    if num_left > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
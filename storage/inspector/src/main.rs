@@ -3,16 +3,29 @@
 
 #![forbid(unsafe_code)]
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use diem_config::config::RocksdbConfig;
+use diem_crypto::HashValue;
 use diem_framework_releases::name_for_script;
-use diem_logger::info;
+use diem_logger::{info, warn};
 use diemdb::DiemDB;
-use std::path::PathBuf;
+use move_core_types::{
+    language_storage::{ModuleId, StructTag},
+    resolver::{ModuleResolver, ResourceResolver},
+};
+use resource_viewer::MoveValueAnnotator;
+use serde_json::json;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+};
 use storage_interface::DbReader;
 
 use diem_types::{
     account_address::AccountAddress, account_config::AccountResource, account_state::AccountState,
+    account_state_blob::AccountStateBlob, transaction::Version,
 };
 use std::convert::TryFrom;
 use structopt::StructOpt;
@@ -39,6 +52,41 @@ enum Command {
     },
     #[structopt(name = "list-accounts")]
     ListAccounts,
+    #[structopt(
+        name = "export",
+        about = "Export the decoded resources of all accounts at a version."
+    )]
+    Export {
+        #[structopt(long, help = "Defaulting to the latest version.")]
+        version: Option<Version>,
+        #[structopt(
+            long,
+            default_value = "jsonl",
+            possible_values = &["jsonl", "csv"],
+            help = "jsonl: one account per line; csv: one resource per row."
+        )]
+        format: ExportFormat,
+        #[structopt(long, parse(from_os_str), help = "Defaulting to stdout.")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug)]
+enum ExportFormat {
+    Jsonl,
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "jsonl" => ExportFormat::Jsonl,
+            "csv" => ExportFormat::Csv,
+            _ => bail!("Unknown export format: {}", s),
+        })
+    }
 }
 
 /// Print out latest information stored in the DB.
@@ -152,6 +200,126 @@ fn list_accounts(db: &DiemDB) {
     info!("Total Accounts: {}", num_account);
 }
 
+/// Resolves the modules and resources from the state at `version`, so that the accounts exported
+/// at a past version are annotated with the modules published at that version.
+struct StateAtVersion<'a> {
+    db: &'a DiemDB,
+    version: Version,
+}
+
+impl<'a> StateAtVersion<'a> {
+    fn get_account_state(&self, address: AccountAddress) -> Result<Option<AccountState>> {
+        let (blob, _) = self
+            .db
+            .get_account_state_with_proof_by_version(address, self.version)?;
+        blob.as_ref().map(AccountState::try_from).transpose()
+    }
+}
+
+impl<'a> ModuleResolver for StateAtVersion<'a> {
+    type Error = anyhow::Error;
+
+    fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .get_account_state(*module_id.address())?
+            .and_then(|account_state| account_state.get(&module_id.access_vector()).cloned()))
+    }
+}
+
+impl<'a> ResourceResolver for StateAtVersion<'a> {
+    type Error = anyhow::Error;
+
+    fn get_resource(&self, address: &AccountAddress, tag: &StructTag) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .get_account_state(*address)?
+            .and_then(|account_state| account_state.get(&tag.access_vector()).cloned()))
+    }
+}
+
+fn export(
+    db: &DiemDB,
+    version: Option<Version>,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let version = match version {
+        Some(v) => v,
+        None => db.get_latest_version()?,
+    };
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    if let ExportFormat::Csv = format {
+        writeln!(out, "key,address,resource_type,value")?;
+    }
+
+    let state = StateAtVersion { db, version };
+    let annotator = MoveValueAnnotator::new(&state);
+    let mut num_accounts = 0;
+    for chunk in db.get_account_iter(version, HashValue::zero())? {
+        for (key, blob) in chunk?.account_blobs {
+            export_account(&annotator, &key, &blob, &format, &mut out)?;
+            num_accounts += 1;
+        }
+    }
+    out.flush()?;
+    info!("Exported {} accounts at version {}.", num_accounts, version);
+
+    Ok(())
+}
+
+fn export_account(
+    annotator: &MoveValueAnnotator<StateAtVersion>,
+    key: &HashValue,
+    blob: &AccountStateBlob,
+    format: &ExportFormat,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let account_state = AccountState::try_from(blob)?;
+    let address = account_state.get_account_address()?;
+    let mut resources = Vec::new();
+    for (typ, bytes) in account_state.get_resources() {
+        match annotator.view_resource(&typ, bytes) {
+            Ok(resource) => resources.push((typ.to_string(), serde_json::to_value(&resource)?)),
+            Err(e) => warn!(
+                "Failed to decode resource {} of account {:x}: {}",
+                typ, key, e
+            ),
+        }
+    }
+
+    match format {
+        ExportFormat::Jsonl => {
+            let resources: serde_json::Map<_, _> = resources.into_iter().collect();
+            let line = json!({
+                "key": format!("{:x}", key),
+                "address": address.map(|a| a.to_string()),
+                "resources": resources,
+            });
+            writeln!(out, "{}", line)?;
+        }
+        ExportFormat::Csv => {
+            let address = address.map_or_else(String::new, |a| a.to_string());
+            for (typ, value) in resources {
+                writeln!(
+                    out,
+                    "{:x},{},{},{}",
+                    key,
+                    address,
+                    csv_field(&typ),
+                    csv_field(&value.to_string()),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn csv_field(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
 fn main() {
     ::diem_logger::DiemLogger::builder().build();
 
@@ -171,6 +339,7 @@ fn main() {
         p,
         true, /* readonly */
         None, /* pruner */
+        None, /* ledger_pruner */
         RocksdbConfig::default(),
     )
     .expect("Unable to open DiemDB");
@@ -190,6 +359,13 @@ fn main() {
             Command::ListAccounts => {
                 list_accounts(&db);
             }
+            Command::Export {
+                version,
+                format,
+                output,
+            } => {
+                export(&db, version, format, output).expect("Unable to export accounts");
+            }
         }
     } else {
         print_head(&db).expect("Unable to read information from DB");
@@ -105,6 +105,25 @@ where
     }
 }
 
+/// Consumes chunks of accounts produced by iterating a `JellyfishMerkleTree` in key order, e.g.
+/// from a backup or from a state snapshot served by a peer, and restores the tree from them.
+pub trait StateSnapshotReceiver<V> {
+    /// Verifies the chunk against the expected root hash using `proof` and persists it.
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, V)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Finishes the restoration once all the accounts have been added.
+    fn finish(self) -> Result<()>
+    where
+        Self: Sized;
+
+    /// Same as `finish`, for a boxed receiver.
+    fn finish_box(self: Box<Self>) -> Result<()>;
+}
+
 pub struct JellyfishMerkleRestore<V> {
     /// The underlying storage.
     store: Arc<dyn TreeWriter<V>>,
@@ -678,3 +697,24 @@ where
         self.store.write_node_batch(&self.frozen_nodes)
     }
 }
+
+impl<V> StateSnapshotReceiver<V> for JellyfishMerkleRestore<V>
+where
+    V: crate::Value,
+{
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, V)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        JellyfishMerkleRestore::add_chunk(self, chunk, proof)
+    }
+
+    fn finish(self) -> Result<()> {
+        JellyfishMerkleRestore::finish(self)
+    }
+
+    fn finish_box(self: Box<Self>) -> Result<()> {
+        JellyfishMerkleRestore::finish(*self)
+    }
+}
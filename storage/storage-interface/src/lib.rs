@@ -3,11 +3,12 @@
 
 use anyhow::{format_err, Result};
 use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
+use diem_jellyfish_merkle::restore::StateSnapshotReceiver;
 use diem_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesChunkWithProof},
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
@@ -21,7 +22,7 @@ use diem_types::{
     state_proof::StateProof,
     transaction::{
         AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof,
-        TransactionToCommit, TransactionWithProof, Version,
+        TransactionOutputListWithProof, TransactionToCommit, TransactionWithProof, Version,
     },
 };
 use itertools::Itertools;
@@ -190,6 +191,19 @@ pub trait DbReader: Send + Sync {
         fetch_events: bool,
     ) -> Result<TransactionListWithProof>;
 
+    /// See [`DiemDB::get_transaction_outputs`].
+    ///
+    /// [`DiemDB::get_transaction_outputs`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_transaction_outputs
+    fn get_transaction_outputs(
+        &self,
+        _start_version: Version,
+        _limit: u64,
+        _ledger_version: Version,
+    ) -> Result<TransactionOutputListWithProof> {
+        unimplemented!()
+    }
+
     /// Returns events by given event key
     fn get_events(
         &self,
@@ -387,6 +401,31 @@ pub trait DbReader: Send + Sync {
             ledger_version,
         )
     }
+
+    /// Gets a chunk of at most `chunk_size` accounts in the state tree at `version`, starting
+    /// right after the account whose hashed address is `last_key` (or the leftmost account if
+    /// `None`), with a proof that can be used to restore the tree from the chunks.
+    fn get_account_chunk_with_proof(
+        &self,
+        _version: Version,
+        _last_key: Option<HashValue>,
+        _chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        unimplemented!()
+    }
+
+    /// Gets an iterator over the accounts in the state tree at `version`, in increasing order of
+    /// hashed address, starting from the account whose hashed address is `start_key` or the
+    /// first one after it. Accounts are yielded in chunks, each with the proofs of its leftmost
+    /// and rightmost accounts, so that it can be verified on its own against the state root hash
+    /// (see `AccountStatesChunkWithProof::verify`).
+    fn get_account_iter(
+        &self,
+        _version: Version,
+        _start_key: HashValue,
+    ) -> Result<Box<dyn Iterator<Item = Result<AccountStatesChunkWithProof>> + Send + '_>> {
+        unimplemented!()
+    }
 }
 
 impl MoveStorage for &dyn DbReader {
@@ -464,11 +503,40 @@ pub trait DbWriter: Send + Sync {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()>;
+
+    /// Returns a receiver that restores the state tree at `version` from account chunks, verifying
+    /// them against `expected_root_hash`. Used to sync a state snapshot instead of replaying all
+    /// the transactions before `version`.
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
+        unimplemented!()
+    }
+
+    /// Finalizes a state snapshot restored at `version`, by atomically persisting the transaction
+    /// and its output at that version, the frozen subtrees of the transaction accumulator before
+    /// it and the epoch ending ledger infos, so that the DB can continue from `version` as if it
+    /// had all the history.
+    ///
+    /// The caller is responsible for verifying all the inputs.
+    fn finalize_state_snapshot(
+        &self,
+        _version: Version,
+        _output_list_with_proof: TransactionOutputListWithProof,
+        _frozen_subtrees: Vec<HashValue>,
+        _ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        unimplemented!()
+    }
 }
 
 pub trait MoveDbReader:
     DbReader + ResourceResolver<Error = anyhow::Error> + ModuleResolver<Error = anyhow::Error>
 {
+    /// Returns the same db as a plain `DbReader`, e.g. to build a `VerifiedStateView` on top of it.
+    fn into_db_reader(self: Arc<Self>) -> Arc<dyn DbReader>;
 }
 
 #[derive(Clone)]
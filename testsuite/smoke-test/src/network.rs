@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::smoke_test_environment::new_local_swarm;
+use crate::smoke_test_environment::{new_in_process_swarm, new_local_swarm, InProcessValidator};
 use diem_config::{
     config::{DiscoveryMethod, Identity, NetworkConfig, NodeConfig, PeerSet, PersistableConfig},
     network_id::NetworkId,
@@ -11,9 +11,12 @@ use diem_operational_tool::{
     keys::{EncodingType, KeyType},
     test_helper::OperationalTool,
 };
+use diem_sdk::client::BlockingClient;
 use diem_temppath::TempPath;
+use diem_time_service::TimeService;
 use diem_types::network_address::{NetworkAddress, Protocol};
 use forge::{FullNode, LocalNode, NodeExt, Swarm};
+use netcore::transport::simulated::{LinkConfig, NetworkSimulator};
 use std::{
     collections::HashMap,
     path::Path,
@@ -184,6 +187,68 @@ fn test_file_discovery() {
 }
 
 /// Creates a discovery file with the given `PeerSet`
+#[test]
+fn test_partition_and_heal() {
+    let network_simulator = NetworkSimulator::new(TimeService::real(), 0, LinkConfig::default());
+    let simulation_start = Instant::now();
+    let swarm = new_in_process_swarm(4, network_simulator.clone());
+    let clients: Vec<_> = swarm
+        .validators()
+        .map(InProcessValidator::json_rpc_client)
+        .collect();
+    wait_for_version(&clients, 5, Duration::from_secs(60));
+
+    // Partition the last validator from the others for 30 seconds
+    let ports: Vec<_> = swarm
+        .validators()
+        .map(InProcessValidator::simulator_port)
+        .collect();
+    let (quorum_ports, partitioned_ports) = ports.split_at(ports.len() - 1);
+    let partition_start = simulation_start.elapsed();
+    let partition_end = partition_start + Duration::from_secs(30);
+    network_simulator.partition(
+        quorum_ports,
+        partitioned_ports,
+        partition_start,
+        partition_end,
+    );
+
+    // Let the messages sent before the partition land, then the partitioned validator is stuck
+    // while the others keep committing
+    sleep(Duration::from_secs(2));
+    let (quorum_clients, partitioned_clients) = clients.split_at(clients.len() - 1);
+    let partitioned_version = ledger_version(&partitioned_clients[0]);
+    let target_version = partitioned_version + 5;
+    wait_for_version(quorum_clients, target_version, Duration::from_secs(20));
+    assert!(simulation_start.elapsed() < partition_end);
+    assert_eq!(ledger_version(&partitioned_clients[0]), partitioned_version);
+
+    // Once the partition heals, the validator catches up
+    wait_for_version(partitioned_clients, target_version, Duration::from_secs(60));
+}
+
+fn ledger_version(client: &BlockingClient) -> u64 {
+    client
+        .get_metadata()
+        .map(|response| response.into_inner().version)
+        .unwrap_or(0)
+}
+
+fn wait_for_version(clients: &[BlockingClient], version: u64, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while clients
+        .iter()
+        .any(|client| ledger_version(client) < version)
+    {
+        assert!(
+            Instant::now() < deadline,
+            "Validators didn't reach version {} in time",
+            version
+        );
+        sleep(Duration::from_millis(500));
+    }
+}
+
 fn create_discovery_file(peer_set: PeerSet) -> TempPath {
     let discovery_file = TempPath::new();
     discovery_file.create_as_file().unwrap();
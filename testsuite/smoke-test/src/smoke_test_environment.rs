@@ -1,7 +1,14 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use diem_config::config::NodeConfig;
+use diem_genesis_tool::validator_builder::ValidatorBuilder;
+use diem_node::DiemHandle;
+use diem_sdk::client::BlockingClient;
+use diem_temppath::TempPath;
+use diem_types::{network_address::parse_memory, PeerId};
 use forge::{LocalFactory, LocalSwarm};
+use netcore::transport::simulated::NetworkSimulator;
 use once_cell::sync::Lazy;
 use rand::rngs::OsRng;
 use std::num::NonZeroUsize;
@@ -15,3 +22,85 @@ pub fn new_local_swarm(num_validators: usize) -> LocalSwarm {
         .new_swarm(OsRng, NonZeroUsize::new(num_validators).unwrap())
         .unwrap()
 }
+
+/// Validators running within the test process instead of as separate diem-node processes. Their
+/// validator network goes through a `NetworkSimulator`, which can change its links and partition
+/// it while the validators run.
+pub struct InProcessSwarm {
+    validators: Vec<InProcessValidator>,
+    _dir: TempPath,
+}
+
+pub struct InProcessValidator {
+    config: NodeConfig,
+    _handle: DiemHandle,
+}
+
+impl InProcessSwarm {
+    pub fn validators(&self) -> impl Iterator<Item = &InProcessValidator> {
+        self.validators.iter()
+    }
+}
+
+impl InProcessValidator {
+    pub fn peer_id(&self) -> PeerId {
+        self.config.peer_id().unwrap()
+    }
+
+    /// The port identifying the validator in the `NetworkSimulator`.
+    pub fn simulator_port(&self) -> u16 {
+        let listen_address = &self
+            .config
+            .validator_network
+            .as_ref()
+            .unwrap()
+            .listen_address;
+        parse_memory(listen_address.as_slice()).unwrap().0
+    }
+
+    pub fn json_rpc_client(&self) -> BlockingClient {
+        let address = self.config.json_rpc.address;
+        BlockingClient::new(format!("http://{}:{}/v1", address.ip(), address.port()))
+    }
+}
+
+/// Starts `num_validators` validators in this process, connected through `network_simulator`.
+/// The simulator should run on real time, as the rest of the validators do.
+pub fn new_in_process_swarm(
+    num_validators: usize,
+    network_simulator: NetworkSimulator,
+) -> InProcessSwarm {
+    ::diem_logger::Logger::new().init();
+
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let (_root_keys, _genesis, _genesis_waypoint, validator_configs) = ValidatorBuilder::new(
+        dir.path(),
+        diem_framework_releases::current_module_blobs().to_vec(),
+    )
+    .num_validators(NonZeroUsize::new(num_validators).unwrap())
+    .simulated_validator_network(true)
+    .build(OsRng)
+    .unwrap();
+
+    let validators = validator_configs
+        .iter()
+        .map(|validator_config| {
+            let config = NodeConfig::load(validator_config.config_path()).unwrap();
+            let handle = diem_node::setup_environment_with_network_simulator(
+                &config,
+                None,
+                network_simulator.clone(),
+            );
+            InProcessValidator {
+                config,
+                _handle: handle,
+            }
+        })
+        .collect();
+
+    InProcessSwarm {
+        validators,
+        _dir: dir,
+    }
+}
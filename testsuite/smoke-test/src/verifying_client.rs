@@ -295,7 +295,7 @@ fn arb_request(
 
     prop_oneof![
         arb_metadata_version.prop_map(MethodRequest::get_metadata_by_version),
-        (arb_account, arb_version).prop_map(|(a, v)| MethodRequest::GetAccount(a, Some(v))),
+        (arb_account, arb_version).prop_map(|(a, v)| MethodRequest::GetAccount(a, Some(v), None)),
         (arb_version_and_limit, arb_include_events)
             .prop_map(|((v, l), i)| MethodRequest::GetTransactions(v, l, i)),
         arb_acct_txns.prop_map(|(a, s, l, i)| MethodRequest::GetAccountTransactions(a, s, l, i)),
@@ -6,12 +6,14 @@ use crate::{
     account_config::{AccountResource, BalanceResource},
     account_state::AccountState,
     ledger_info::LedgerInfo,
-    proof::AccountStateProof,
+    proof::{
+        AccountStateProof, SparseMerkleInternalNode, SparseMerkleLeafNode, SparseMerkleRangeProof,
+    },
     transaction::Version,
 };
 use anyhow::{anyhow, ensure, Error, Result};
 use diem_crypto::{
-    hash::{CryptoHash, CryptoHasher},
+    hash::{CryptoHash, CryptoHasher, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
 };
 use diem_crypto_derive::CryptoHasher;
@@ -184,6 +186,249 @@ impl AccountStateWithProof {
     }
 }
 
+/// A chunk of consecutive (in hashed address order) account states in the state tree at
+/// `version`. Together with all the accounts on its left, the chunk can be authenticated against
+/// the state root hash using the proof. Used to download state snapshots in pieces.
+/// If the chunk carries the siblings of the path to its first account as well, it can be
+/// authenticated on its own, see `verify`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct AccountStatesChunkWithProof {
+    /// The version of the state tree the chunk is taken from.
+    pub version: Version,
+    /// Hashed addresses and account state blobs, in increasing order of hashed address.
+    pub account_blobs: Vec<(HashValue, AccountStateBlob)>,
+    /// The proof of the rightmost account in the chunk, see `SparseMerkleRangeProof`.
+    pub proof: SparseMerkleRangeProof,
+    /// The siblings of the path from the root to the leftmost account in the chunk, ordered from
+    /// the bottom level to the root level like in `SparseMerkleProof`.
+    pub left_siblings: Option<Vec<HashValue>>,
+}
+
+impl AccountStatesChunkWithProof {
+    /// Constructor.
+    pub fn new(
+        version: Version,
+        account_blobs: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Self {
+        Self {
+            version,
+            account_blobs,
+            proof,
+            left_siblings: None,
+        }
+    }
+
+    /// Sets the siblings of the path to the leftmost account in the chunk.
+    pub fn with_left_siblings(mut self, left_siblings: Vec<HashValue>) -> Self {
+        self.left_siblings = Some(left_siblings);
+        self
+    }
+
+    /// Returns the hashed address of the last account in the chunk, if any.
+    pub fn last_key(&self) -> Option<HashValue> {
+        self.account_blobs.last().map(|(key, _blob)| *key)
+    }
+
+    /// Returns true if there are no more accounts on the right of this chunk, i.e., all the right
+    /// siblings in the proof are placeholders.
+    pub fn is_last_chunk(&self) -> bool {
+        self.proof
+            .right_siblings()
+            .iter()
+            .all(|sibling| *sibling == *SPARSE_MERKLE_PLACEHOLDER_HASH)
+    }
+
+    /// Verifies that the chunk holds all the accounts of the state tree with root hash
+    /// `state_root_hash` between its leftmost and rightmost accounts. The accounts on the left of
+    /// the chunk are authenticated by `left_siblings`, the ones on the right by `proof`.
+    pub fn verify(&self, state_root_hash: HashValue) -> Result<()> {
+        let left_siblings = self
+            .left_siblings
+            .as_ref()
+            .ok_or_else(|| anyhow!("Chunk has no proof of its leftmost account."))?;
+        ensure!(
+            !self.account_blobs.is_empty(),
+            "Chunk has no account to verify."
+        );
+        ensure!(
+            left_siblings.len() <= HashValue::LENGTH_IN_BITS,
+            "Chunk proof has more than {} ({}) left siblings.",
+            HashValue::LENGTH_IN_BITS,
+            left_siblings.len(),
+        );
+        ensure!(
+            self.account_blobs
+                .windows(2)
+                .all(|pair| pair[0].0 < pair[1].0),
+            "Chunk accounts are not in increasing order of hashed address."
+        );
+        let leaves: Vec<_> = self
+            .account_blobs
+            .iter()
+            .map(|(key, blob)| (*key, SparseMerkleLeafNode::new(*key, blob.hash()).hash()))
+            .collect();
+        let verifier = ChunkVerifier {
+            first_key: leaves[0].0,
+            last_key: leaves[leaves.len() - 1].0,
+            left_siblings,
+            right_siblings: self.proof.right_siblings(),
+        };
+        let root_hash = verifier.subtree_hash(0, &leaves, PathSide::Both)?;
+        ensure!(
+            root_hash == state_root_hash,
+            "Chunk root hash {:x} doesn't match the expected root hash {:x}.",
+            root_hash,
+            state_root_hash,
+        );
+        Ok(())
+    }
+}
+
+/// Which boundary paths of the chunk go through a subtree.
+#[derive(Clone, Copy)]
+enum PathSide {
+    /// The subtree holds both the first and the last account of the chunk.
+    Both,
+    /// The subtree holds the first account, all its accounts on the right are in the chunk.
+    Left,
+    /// The subtree holds the last account, all its accounts on the left are in the chunk.
+    Right,
+    /// All the accounts of the subtree are in the chunk.
+    Inner,
+}
+
+/// Recomputes the root hash of the state tree from the leaves of a chunk and the siblings of the
+/// paths to its first and last accounts.
+struct ChunkVerifier<'a> {
+    first_key: HashValue,
+    last_key: HashValue,
+    // Bottom to root, see `SparseMerkleProof`.
+    left_siblings: &'a [HashValue],
+    // Bottom to root, only the ones on the right, see `SparseMerkleRangeProof`.
+    right_siblings: &'a [HashValue],
+}
+
+impl<'a> ChunkVerifier<'a> {
+    /// The sibling at `depth` of the path to the first account.
+    fn left_sibling(&self, depth: usize) -> Result<HashValue> {
+        ensure!(
+            depth < self.left_siblings.len(),
+            "Missing left sibling at depth {}.",
+            depth
+        );
+        Ok(self.left_siblings[self.left_siblings.len() - 1 - depth])
+    }
+
+    /// The number of right siblings of the path to the last account above `depth`.
+    fn num_right_siblings_above(&self, depth: usize) -> usize {
+        self.last_key
+            .iter_bits()
+            .take(depth)
+            .filter(|bit| !*bit)
+            .count()
+    }
+
+    /// The right sibling at `depth` of the path to the last account.
+    fn right_sibling(&self, depth: usize) -> Result<HashValue> {
+        let index = self.num_right_siblings_above(depth);
+        ensure!(
+            index < self.right_siblings.len(),
+            "Missing right sibling at depth {}.",
+            depth
+        );
+        Ok(self.right_siblings[self.right_siblings.len() - 1 - index])
+    }
+
+    /// Computes the hash of the subtree at `depth` that holds the (non-empty unless `Inner`)
+    /// `leaves`, all sharing the path to the subtree.
+    fn subtree_hash(
+        &self,
+        depth: usize,
+        leaves: &[(HashValue, HashValue)],
+        side: PathSide,
+    ) -> Result<HashValue> {
+        let is_leaf = match side {
+            PathSide::Inner => leaves.len() <= 1,
+            PathSide::Both | PathSide::Left => depth == self.left_siblings.len(),
+            PathSide::Right => {
+                leaves.len() == 1
+                    && self.num_right_siblings_above(depth) == self.right_siblings.len()
+            }
+        };
+        if is_leaf {
+            ensure!(
+                leaves.len() <= 1,
+                "Chunk accounts are missing from the subtree at depth {}.",
+                depth
+            );
+            return Ok(leaves
+                .first()
+                .map_or(*SPARSE_MERKLE_PLACEHOLDER_HASH, |(_key, hash)| *hash));
+        }
+        ensure!(
+            depth < HashValue::LENGTH_IN_BITS,
+            "Chunk accounts share a path longer than {} bits.",
+            HashValue::LENGTH_IN_BITS
+        );
+
+        let split = leaves
+            .iter()
+            .position(|(key, _hash)| key.bit(depth))
+            .unwrap_or(leaves.len());
+        let (left_leaves, right_leaves) = leaves.split_at(split);
+        let (left_hash, right_hash) = match side {
+            PathSide::Inner => (
+                self.subtree_hash(depth + 1, left_leaves, PathSide::Inner)?,
+                self.subtree_hash(depth + 1, right_leaves, PathSide::Inner)?,
+            ),
+            PathSide::Both => match (self.first_key.bit(depth), self.last_key.bit(depth)) {
+                (true, true) => (
+                    self.left_sibling(depth)?,
+                    self.subtree_hash(depth + 1, leaves, PathSide::Both)?,
+                ),
+                (false, false) => (
+                    self.subtree_hash(depth + 1, leaves, PathSide::Both)?,
+                    self.right_sibling(depth)?,
+                ),
+                (false, true) => (
+                    self.subtree_hash(depth + 1, left_leaves, PathSide::Left)?,
+                    self.subtree_hash(depth + 1, right_leaves, PathSide::Right)?,
+                ),
+                (true, false) => unreachable!("The first account is before the last one."),
+            },
+            PathSide::Left => {
+                if self.first_key.bit(depth) {
+                    (
+                        self.left_sibling(depth)?,
+                        self.subtree_hash(depth + 1, leaves, PathSide::Left)?,
+                    )
+                } else {
+                    (
+                        self.subtree_hash(depth + 1, left_leaves, PathSide::Left)?,
+                        self.subtree_hash(depth + 1, right_leaves, PathSide::Inner)?,
+                    )
+                }
+            }
+            PathSide::Right => {
+                if self.last_key.bit(depth) {
+                    (
+                        self.subtree_hash(depth + 1, left_leaves, PathSide::Inner)?,
+                        self.subtree_hash(depth + 1, right_leaves, PathSide::Right)?,
+                    )
+                } else {
+                    (
+                        self.subtree_hash(depth + 1, leaves, PathSide::Right)?,
+                        self.right_sibling(depth)?,
+                    )
+                }
+            }
+        };
+        Ok(SparseMerkleInternalNode::new(left_hash, right_hash).hash())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +456,11 @@ mod tests {
         fn account_state_with_proof_bcs_roundtrip(account_state_with_proof in any::<AccountStateWithProof>()) {
             assert_canonical_encode_decode(account_state_with_proof);
         }
+
+        #[test]
+        fn account_states_chunk_with_proof_bcs_roundtrip(chunk in any::<AccountStatesChunkWithProof>()) {
+            assert_canonical_encode_decode(chunk);
+        }
     }
 
     #[test]
@@ -826,6 +826,7 @@ impl TransactionToCommitGen {
             Transaction::UserTransaction(transaction),
             account_states,
             None,
+            WriteSet::default(),
             events,
             self.gas_used,
             self.status,
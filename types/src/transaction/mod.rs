@@ -15,7 +15,7 @@ use crate::{
     vm_status::{DiscardedVMStatus, KeptVMStatus, StatusCode, StatusType, VMStatus},
     write_set::WriteSet,
 };
-use anyhow::{ensure, format_err, Error, Result};
+use anyhow::{bail, ensure, format_err, Error, Result};
 use diem_crypto::{
     ed25519::*,
     hash::{CryptoHash, EventAccumulatorHasher},
@@ -603,6 +603,13 @@ impl SignedTransaction {
         Ok(SignatureCheckedTransaction(self))
     }
 
+    /// Skips signature verification, so that the transaction can be simulated before it is
+    /// signed. The output of executing the returned transaction must never be committed.
+    #[cfg(feature = "simulation")]
+    pub fn into_unchecked_for_simulation(self) -> SignatureCheckedTransaction {
+        SignatureCheckedTransaction(self)
+    }
+
     pub fn contains_duplicate_signers(&self) -> bool {
         let mut all_signer_addresses = self.authenticator.secondary_signer_addreses();
         all_signer_addresses.push(self.sender());
@@ -993,6 +1000,7 @@ pub struct TransactionToCommit {
     transaction: Transaction,
     account_states: HashMap<AccountAddress, AccountStateBlob>,
     jf_node_hashes: Option<HashMap<NibblePath, HashValue>>,
+    write_set: WriteSet,
     events: Vec<ContractEvent>,
     gas_used: u64,
     status: KeptVMStatus,
@@ -1003,6 +1011,7 @@ impl TransactionToCommit {
         transaction: Transaction,
         account_states: HashMap<AccountAddress, AccountStateBlob>,
         jf_node_hashes: Option<HashMap<NibblePath, HashValue>>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         gas_used: u64,
         status: KeptVMStatus,
@@ -1011,6 +1020,7 @@ impl TransactionToCommit {
             transaction,
             account_states,
             jf_node_hashes,
+            write_set,
             events,
             gas_used,
             status,
@@ -1029,6 +1039,10 @@ impl TransactionToCommit {
         self.jf_node_hashes.as_ref()
     }
 
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     pub fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -1142,6 +1156,117 @@ impl TransactionListWithProof {
     }
 }
 
+/// A list of transactions and their outputs, with a proof that the transactions exist on the
+/// ledger. `verify` authenticates the events, gas used and status of the outputs against the
+/// transaction infos carried by the proof. The write sets can only be authenticated by applying
+/// them and comparing the resulting state root hashes with the transaction infos.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TransactionOutputListWithProof {
+    pub transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+    pub first_transaction_output_version: Option<Version>,
+    pub proof: TransactionListProof,
+}
+
+impl TransactionOutputListWithProof {
+    /// Constructor.
+    pub fn new(
+        transactions_and_outputs: Vec<(Transaction, TransactionOutput)>,
+        first_transaction_output_version: Option<Version>,
+        proof: TransactionListProof,
+    ) -> Self {
+        Self {
+            transactions_and_outputs,
+            first_transaction_output_version,
+            proof,
+        }
+    }
+
+    /// Creates an empty transaction output list.
+    pub fn new_empty() -> Self {
+        Self::new(vec![], None, TransactionListProof::new_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions_and_outputs.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions_and_outputs.len()
+    }
+
+    /// Verifies the transaction output list with the proofs, both carried on `self`.
+    ///
+    /// Three things are ensured if no error is raised:
+    ///   1. All the transactions exist on the ledger represented by `ledger_info`.
+    ///   2. The transactions in the list has consecutive versions starting from
+    /// `first_transaction_output_version`. When `first_transaction_output_version` is None,
+    /// ensures the list is empty.
+    ///   3. The events, gas used and status of every output match its transaction info.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        first_transaction_output_version: Option<Version>,
+    ) -> Result<()> {
+        ensure!(
+            self.first_transaction_output_version == first_transaction_output_version,
+            "First transaction output version ({}) not expected ({}).",
+            TransactionListWithProof::display_option_version(self.first_transaction_output_version),
+            TransactionListWithProof::display_option_version(first_transaction_output_version),
+        );
+
+        let txn_hashes: Vec<_> = self
+            .transactions_and_outputs
+            .iter()
+            .map(|(txn, _output)| txn.hash())
+            .collect();
+        self.proof.verify(
+            ledger_info,
+            self.first_transaction_output_version,
+            &txn_hashes,
+        )?;
+
+        itertools::zip_eq(
+            &self.transactions_and_outputs,
+            self.proof.transaction_infos(),
+        )
+        .map(|((_txn, output), txn_info)| {
+            let event_hashes: Vec<_> = output.events().iter().map(ContractEvent::hash).collect();
+            let event_root_hash =
+                InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                    .root_hash();
+            ensure!(
+                event_root_hash == txn_info.event_root_hash(),
+                "Some event root hash calculated doesn't match that carried on the \
+                     transaction info.",
+            );
+            ensure!(
+                output.gas_used() == txn_info.gas_used(),
+                "Gas used in the output ({}) doesn't match that carried on the transaction \
+                     info ({}).",
+                output.gas_used(),
+                txn_info.gas_used(),
+            );
+            match output.status() {
+                TransactionStatus::Keep(status) => ensure!(
+                    status == txn_info.status(),
+                    "Status in the output ({:?}) doesn't match that carried on the \
+                         transaction info ({:?}).",
+                    status,
+                    txn_info.status(),
+                ),
+                status => bail!(
+                    "Transaction output with a status not to keep: {:?}.",
+                    status
+                ),
+            }
+            Ok(())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(())
+    }
+}
+
 /// A list of transactions under an account that are contiguous by sequence number
 /// and include proofs.
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]